@@ -0,0 +1,227 @@
+use crate::i18n::{Msg, warn_msg};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+pub const APP_DIR_NAME: &str = "kitsune-livewallpaper";
+pub const SYSTEM_CONFIG_DIR: &str = "/etc/kitsune-livewallpaper";
+
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    std::env::var("HOME")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// `$var` when it is an absolute path (per the XDG spec relative values are
+/// ignored), else `$HOME/<home_relative>`.
+fn xdg_base_dir(var: &str, home_relative: &str) -> Option<PathBuf> {
+    resolve_xdg_base_dir(std::env::var(var).ok(), home_dir(), home_relative)
+}
+
+fn resolve_xdg_base_dir(
+    value: Option<String>,
+    home: Option<PathBuf>,
+    home_relative: &str,
+) -> Option<PathBuf> {
+    value
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home.map(|h| h.join(home_relative)))
+}
+
+pub fn config_home() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn cache_home() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CACHE_HOME", ".cache")
+}
+
+pub fn data_home() -> Option<PathBuf> {
+    xdg_base_dir("XDG_DATA_HOME", ".local/share")
+}
+
+pub fn state_home() -> Option<PathBuf> {
+    xdg_base_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// `$XDG_RUNTIME_DIR`; unlike the other base dirs it has no home fallback.
+pub fn runtime_home() -> Option<PathBuf> {
+    resolve_xdg_base_dir(std::env::var("XDG_RUNTIME_DIR").ok(), None, "")
+}
+
+thread_local! {
+    static APP_DIRS_ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with the app config, cache, state and data dirs of this thread
+/// under `root/<kind>`, so tests never read or write the user's real dirs.
+pub fn with_app_dirs_root<R>(root: &Path, f: impl FnOnce() -> R) -> R {
+    let previous = APP_DIRS_ROOT.with(|c| c.replace(Some(root.to_path_buf())));
+    let result = f();
+    APP_DIRS_ROOT.with(|c| *c.borrow_mut() = previous);
+    result
+}
+
+/// Users without a home (greeters, system services) fall back to /tmp.
+fn app_dir(base: Option<PathBuf>, fallback: &str) -> PathBuf {
+    if let Some(root) = APP_DIRS_ROOT.with(|c| c.borrow().clone()) {
+        return root.join(fallback);
+    }
+    base.map(|b| b.join(APP_DIR_NAME))
+        .unwrap_or_else(|| Path::new("/tmp").join(APP_DIR_NAME).join(fallback))
+}
+
+pub fn app_config_dir() -> PathBuf {
+    app_dir(config_home(), "config")
+}
+
+pub fn app_cache_dir() -> PathBuf {
+    app_dir(cache_home(), "cache")
+}
+
+pub fn app_state_dir() -> PathBuf {
+    app_dir(state_home(), "state")
+}
+
+pub fn app_data_dir() -> PathBuf {
+    app_dir(data_home(), "data")
+}
+
+/// Per-login sockets (mpv IPC), private to the user unlike bare /tmp.
+pub fn app_runtime_dir() -> PathBuf {
+    app_dir(runtime_home(), "runtime")
+}
+
+pub fn user_config_path() -> PathBuf {
+    app_config_dir().join("config.json")
+}
+
+/// Read-only defaults shipped by distro packages or set by an admin.
+pub fn system_config_path() -> PathBuf {
+    Path::new(SYSTEM_CONFIG_DIR).join("config.json")
+}
+
+pub fn system_quirks_path() -> PathBuf {
+    Path::new(SYSTEM_CONFIG_DIR).join("quirks.json")
+}
+
+/// Characters that break a path inside an mpv option list or ffmpeg filter
+/// argument, where each layer of parsing strips one level of escaping.
+const FILTER_UNSAFE_CHARS: &str = "[]:,;'\"\\";
+
+pub fn is_filter_safe_path(path: &Path) -> bool {
+    path.to_str().is_some_and(|text| {
+        !text.contains(|c: char| c.is_whitespace() || FILTER_UNSAFE_CHARS.contains(c))
+    })
+}
+
+/// Symlink in `alias_dir` pointing at `path`, named after a hash of the path
+/// so repeated calls reuse it.
+fn path_alias_in(path: &Path, alias_dir: &Path) -> std::io::Result<PathBuf> {
+    let target = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    let mut hasher = DefaultHasher::new();
+    target.hash(&mut hasher);
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default();
+    let alias = alias_dir.join(format!("{:016x}{}", hasher.finish(), ext));
+    if fs::read_link(&alias).is_ok_and(|current| current == target) {
+        return Ok(alias);
+    }
+    fs::create_dir_all(alias_dir)?;
+    let _ = fs::remove_file(&alias);
+    std::os::unix::fs::symlink(&target, &alias)?;
+    Ok(alias)
+}
+
+fn alias_or_original(path: &Path) -> PathBuf {
+    match path_alias_in(path, &app_cache_dir().join("path-aliases")) {
+        Ok(alias) => alias,
+        Err(err) => {
+            warn_msg!(Msg::PathAliasFailed, path.display(), err);
+            path.to_path_buf()
+        }
+    }
+}
+
+/// `path`, or a plain-named alias of it when it cannot go through an mpv
+/// filter or option list (spaces, quotes, `:`/`,`, non-UTF-8 bytes).
+pub fn filter_safe_path(path: &Path) -> PathBuf {
+    if is_filter_safe_path(path) {
+        path.to_path_buf()
+    } else {
+        alias_or_original(path)
+    }
+}
+
+/// `path`, or an alias of it when it is not valid UTF-8 and would be mangled
+/// by a text field (JSON reports, IPC commands).
+pub fn utf8_safe_path(path: &Path) -> PathBuf {
+    if path.to_str().is_some() {
+        path.to_path_buf()
+    } else {
+        alias_or_original(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_xdg_values_are_ignored() {
+        let home = Some(PathBuf::from("/home/u"));
+        assert_eq!(
+            resolve_xdg_base_dir(Some("relative/dir".to_string()), home.clone(), ".cache"),
+            Some(PathBuf::from("/home/u/.cache"))
+        );
+        assert_eq!(
+            resolve_xdg_base_dir(Some("/srv/cache".to_string()), home, ".cache"),
+            Some(PathBuf::from("/srv/cache"))
+        );
+        assert_eq!(resolve_xdg_base_dir(None, None, ".cache"), None);
+
+        let root = Path::new("/tmp/kwe-test-dirs");
+        with_app_dirs_root(root, || {
+            assert_eq!(app_cache_dir(), root.join("cache"));
+            assert_eq!(user_config_path(), root.join("config/config.json"));
+        });
+        assert_ne!(app_cache_dir(), root.join("cache"));
+    }
+
+    #[test]
+    fn exotic_paths_get_plain_aliases() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let alias_dir = dir.path().join("aliases");
+        let plain = dir.path().join("clip.mp4");
+        assert!(is_filter_safe_path(&plain));
+
+        for name in [
+            OsStr::new("it's [my] clip, v2.mp4"),
+            OsStr::from_bytes(b"caf\xe9.mp4"),
+        ] {
+            let path = dir.path().join(name);
+            fs::write(&path, b"x").unwrap();
+            assert!(!is_filter_safe_path(&path));
+            let alias = path_alias_in(&path, &alias_dir).unwrap();
+            assert!(is_filter_safe_path(&alias));
+            assert_eq!(alias.extension().unwrap(), "mp4");
+            assert_eq!(fs::read(&alias).unwrap(), b"x");
+            assert_eq!(path_alias_in(&path, &alias_dir).unwrap(), alias);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::app_paths::app_cache_dir;
+use crate::cli::Scaling;
+use crate::display_orientation::CropRegion;
+use crate::i18n::{Msg, warn_msg};
+use crate::scene_thumbnail::grab_video_frame;
+use crate::video_opt::probe_video_size;
+use crate::wallpaper::is_video_file;
+use anyhow::{Context, Result};
+use image::GrayImage;
+use image::imageops::FilterType;
+use serde::Serialize;
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// Aspect ratios further apart than this factor are reported at apply time.
+const MISMATCH_FACTOR: f32 = 1.3;
+/// Up to this factor `fill` loses little of the frame; past it `fit` is
+/// suggested instead.
+const FILL_FACTOR: f32 = 1.5;
+/// Long side of the copy the saliency is computed on.
+const ANALYSIS_SIZE: u32 = 160;
+const ENTROPY_BLOCK: u32 = 8;
+
+/// `apply` options that depend on the output the wallpaper lands on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AspectFit {
+    pub scaling: Option<Scaling>,
+    pub smart_crop: bool,
+}
+
+thread_local! {
+    static APPLY_ASPECT_FIT: Cell<Option<AspectFit>> = const { Cell::new(None) };
+}
+
+/// Runs `f` checking every mpvpaper launch on this thread against `fit`.
+pub fn with_aspect_fit<R>(fit: AspectFit, f: impl FnOnce() -> R) -> R {
+    let previous = APPLY_ASPECT_FIT.with(|c| c.replace(Some(fit)));
+    let result = f();
+    APPLY_ASPECT_FIT.with(|c| c.set(previous));
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AspectMismatch {
+    pub wallpaper: (u32, u32),
+    pub output: (u32, u32),
+    /// Larger aspect ratio over the smaller one.
+    pub factor: f32,
+    pub suggested: &'static str,
+}
+
+fn aspect((w, h): (u32, u32)) -> f32 {
+    w.max(1) as f32 / h.max(1) as f32
+}
+
+pub fn detect_aspect_mismatch(wallpaper: (u32, u32), output: (u32, u32)) -> Option<AspectMismatch> {
+    let (a, b) = (aspect(wallpaper), aspect(output));
+    let factor = a.max(b) / a.min(b);
+    (factor > MISMATCH_FACTOR).then_some(AspectMismatch {
+        wallpaper,
+        output,
+        factor,
+        suggested: if factor <= FILL_FACTOR { "fill" } else { "fit" },
+    })
+}
+
+pub fn scaling_mpv_options(scaling: Option<Scaling>) -> Vec<String> {
+    match scaling {
+        None | Some(Scaling::Fit) => Vec::new(),
+        Some(Scaling::Fill) => vec!["panscan=1.0".to_string()],
+        Some(Scaling::Stretch) => vec!["keepaspect=no".to_string()],
+    }
+}
+
+/// Per-pixel interest of `gray`: gradient magnitude plus the luminance
+/// entropy of the surrounding block, each scaled to 0..1 and summed.
+pub fn saliency_map(gray: &GrayImage) -> Vec<f32> {
+    let (w, h) = gray.dimensions();
+    let luma = |x: u32, y: u32| gray.get_pixel(x.min(w - 1), y.min(h - 1))[0] as f32;
+    let mut edges = Vec::with_capacity((w * h) as usize);
+    for y in 0..h {
+        for x in 0..w {
+            let dx = luma(x + 1, y) - luma(x.saturating_sub(1), y);
+            let dy = luma(x, y + 1) - luma(x, y.saturating_sub(1));
+            edges.push((dx * dx + dy * dy).sqrt());
+        }
+    }
+
+    let (bw, bh) = (w.div_ceil(ENTROPY_BLOCK), h.div_ceil(ENTROPY_BLOCK));
+    let mut entropy = vec![0.0f32; (bw * bh) as usize];
+    for by in 0..bh {
+        for bx in 0..bw {
+            let mut bins = [0u32; 16];
+            let mut count = 0;
+            for y in by * ENTROPY_BLOCK..((by + 1) * ENTROPY_BLOCK).min(h) {
+                for x in bx * ENTROPY_BLOCK..((bx + 1) * ENTROPY_BLOCK).min(w) {
+                    bins[(gray.get_pixel(x, y)[0] >> 4) as usize] += 1;
+                    count += 1;
+                }
+            }
+            entropy[(by * bw + bx) as usize] = bins
+                .iter()
+                .filter(|n| **n > 0)
+                .map(|n| {
+                    let p = *n as f32 / count as f32;
+                    -p * p.log2()
+                })
+                .sum();
+        }
+    }
+
+    let max_edge = edges.iter().copied().fold(0.0, f32::max).max(1e-6);
+    let max_entropy = entropy.iter().copied().fold(0.0, f32::max).max(1e-6);
+    (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let block = (y / ENTROPY_BLOCK * bw + x / ENTROPY_BLOCK) as usize;
+            edges[(y * w + x) as usize] / max_edge + entropy[block] / max_entropy
+        })
+        .collect()
+}
+
+/// Start of the `window` long run of `profile` with the most interest; ties
+/// go to the run nearest the centre.
+fn best_window(profile: &[f32], window: usize) -> usize {
+    let window = window.clamp(1, profile.len().max(1));
+    let slots = profile.len().saturating_sub(window);
+    let centre = slots as f32 / 2.0;
+    let mut sum = profile[..window].iter().sum::<f32>();
+    let (mut best, mut best_sum) = (0, sum);
+    let epsilon = profile.iter().sum::<f32>() * 1e-3;
+    for start in 1..=slots {
+        sum += profile[start + window - 1] - profile[start - 1];
+        let closer = (start as f32 - centre).abs() < (best as f32 - centre).abs();
+        if sum > best_sum + epsilon || (sum >= best_sum - epsilon && closer) {
+            (best, best_sum) = (start, sum);
+        }
+    }
+    best
+}
+
+/// Region of `gray` with the aspect of `output`, placed over its most
+/// salient part, as 0..1 fractions.
+pub fn focal_region(gray: &GrayImage, output: (u32, u32)) -> CropRegion {
+    let (w, h) = gray.dimensions();
+    let saliency = saliency_map(gray);
+    let target = aspect(output);
+    if aspect((w, h)) > target {
+        let window = ((h as f32 * target).round() as usize).clamp(1, w as usize);
+        let columns = (0..w as usize)
+            .map(|x| (0..h as usize).map(|y| saliency[y * w as usize + x]).sum())
+            .collect::<Vec<f32>>();
+        let start = best_window(&columns, window);
+        CropRegion {
+            x: start as f32 / w as f32,
+            y: 0.0,
+            w: window as f32 / w as f32,
+            h: 1.0,
+        }
+    } else {
+        let window = ((w as f32 / target).round() as usize).clamp(1, h as usize);
+        let rows = saliency
+            .chunks(w as usize)
+            .map(|row| row.iter().sum())
+            .collect::<Vec<f32>>();
+        let start = best_window(&rows, window);
+        CropRegion {
+            x: 0.0,
+            y: start as f32 / h as f32,
+            w: 1.0,
+            h: window as f32 / h as f32,
+        }
+    }
+}
+
+fn visual_size(entry: &Path) -> Option<(u32, u32)> {
+    if is_video_file(entry) {
+        probe_video_size(entry)
+    } else {
+        image::image_dimensions(entry).ok()
+    }
+}
+
+/// Smart crop of `entry` for `output`; videos are judged on one frame.
+fn smart_crop_region(entry: &Path, output: (u32, u32)) -> Result<CropRegion> {
+    let image = if is_video_file(entry) {
+        let dir = app_cache_dir().join("smart-crop");
+        fs::create_dir_all(&dir).with_context(|| format!("Failed creating {}", dir.display()))?;
+        let mut hasher = DefaultHasher::new();
+        entry.hash(&mut hasher);
+        let frame = dir.join(format!("{:016x}.png", hasher.finish()));
+        grab_video_frame(entry, &frame)?;
+        image::open(&frame).with_context(|| format!("Failed reading {}", frame.display()))?
+    } else {
+        image::open(entry).with_context(|| format!("Failed reading {}", entry.display()))?
+    };
+    let small = image.resize(ANALYSIS_SIZE, ANALYSIS_SIZE, FilterType::Triangle);
+    Ok(focal_region(&small.to_luma8(), output))
+}
+
+/// mpv options for the running `apply` on `monitor` (of size `output`, when
+/// the compositor reports it): the `--scaling` mode and the `--smart-crop`
+/// filter. Warns when the aspect is far off and neither was asked for.
+pub fn aspect_fit_options(monitor: &str, entry: &Path, output: Option<(u32, u32)>) -> Vec<String> {
+    let Some(fit) = APPLY_ASPECT_FIT.with(Cell::get) else {
+        return Vec::new();
+    };
+    let mut opts = scaling_mpv_options(fit.scaling);
+    let Some(output) = output else {
+        return opts;
+    };
+    let Some(wallpaper) = visual_size(entry) else {
+        return opts;
+    };
+    let Some(mismatch) = detect_aspect_mismatch(wallpaper, output) else {
+        return opts;
+    };
+    if !fit.smart_crop {
+        if fit.scaling.is_none() {
+            warn_msg!(
+                Msg::AspectMismatch,
+                monitor,
+                wallpaper.0,
+                wallpaper.1,
+                aspect(wallpaper),
+                output.0,
+                output.1,
+                aspect(output),
+                mismatch.suggested
+            );
+        }
+        return opts;
+    }
+    match smart_crop_region(entry, output) {
+        Ok(region) => {
+            eprintln!(
+                "[ok] smart crop on {}: x={:.3} y={:.3} w={:.3} h={:.3}",
+                monitor, region.x, region.y, region.w, region.h
+            );
+            opts.push(format!("vf-append={}", region.mpv_crop_filter()));
+        }
+        Err(err) => warn_msg!(Msg::SmartCropFailed, monitor, err),
+    }
+    opts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatch_is_reported_and_smart_crop_follows_detail() {
+        assert_eq!(detect_aspect_mismatch((1920, 1080), (2560, 1440)), None);
+        assert_eq!(
+            detect_aspect_mismatch((1920, 1080), (3440, 1440)).map(|m| m.suggested),
+            Some("fill")
+        );
+        assert_eq!(
+            detect_aspect_mismatch((1080, 2340), (3440, 1440)).map(|m| m.suggested),
+            Some("fit")
+        );
+        assert_eq!(
+            scaling_mpv_options(Some(Scaling::Fill)),
+            ["panscan=1.0".to_string()]
+        );
+
+        // Flat frame with a checkerboard near the right edge.
+        let gray = GrayImage::from_fn(160, 40, |x, y| {
+            let busy = (110..150).contains(&x) && (x / 4 + y / 4) % 2 == 0;
+            image::Luma([if busy { 230 } else { 40 }])
+        });
+        let region = focal_region(&gray, (1000, 1000));
+        assert!((region.w - 0.25).abs() < 1e-3 && region.h == 1.0);
+        assert!((0.6..=0.7).contains(&region.x), "{region:?}");
+
+        let flat = GrayImage::from_pixel(40, 160, image::Luma([90]));
+        let centred = focal_region(&flat, (1000, 1000));
+        assert!((centred.y - 0.375).abs() < 1e-3, "{centred:?}");
+    }
+}
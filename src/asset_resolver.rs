@@ -1,7 +1,16 @@
-use crate::scene_pkg::{ScenePkg, find_entry, parse_scene_pkg, read_entry_bytes};
-use anyhow::{Result, bail};
+use crate::i18n::{Msg, bail_msg};
+use crate::scene_pkg::{ScenePkg, parse_scene_pkg, read_entry_bytes};
+use anyhow::Result;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Past this many files a directory index stops growing and misses fall
+/// back to a filesystem check.
+const MAX_INDEXED_FILES: usize = 100_000;
+const MAX_INDEX_DEPTH: usize = 16;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AssetSourceKind {
@@ -23,10 +32,94 @@ pub struct ResolvedAsset {
 pub struct AssetResolver {
     root: PathBuf,
     pkg: Option<ScenePkg>,
+    /// Lowercase entry name -> position in `pkg.entries`.
+    pkg_index: HashMap<String, usize>,
+    /// Files of `root`, listed on the first lookup that reaches it.
+    root_index: OnceLock<Arc<DirIndex>>,
     workshop_root: Option<PathBuf>,
     global_assets_root: Option<PathBuf>,
 }
 
+/// Files under one directory by lowercase relative path, so candidate
+/// probing is a map lookup instead of a `stat` per candidate.
+#[derive(Debug, Default)]
+struct DirIndex {
+    files: HashMap<String, String>,
+    /// False when the listing hit [`MAX_INDEXED_FILES`].
+    complete: bool,
+}
+
+impl DirIndex {
+    fn scan(dir: &Path) -> Self {
+        let mut index = DirIndex {
+            files: HashMap::new(),
+            complete: true,
+        };
+        index.scan_into(dir, "", 0);
+        index
+    }
+
+    fn scan_into(&mut self, dir: &Path, prefix: &str, depth: usize) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut entries = entries.flatten().collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let rel = format!("{}{}", prefix, name);
+            let path = entry.path();
+            // `DirEntry::file_type` does not follow symlinks; links to
+            // directories are skipped so one pointing up the tree cannot
+            // make the scan loop.
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                if depth < MAX_INDEX_DEPTH {
+                    self.scan_into(&path, &format!("{}/", rel), depth + 1);
+                }
+            } else if path.is_dir() {
+                continue;
+            } else if self.files.len() >= MAX_INDEXED_FILES {
+                self.complete = false;
+                return;
+            } else {
+                self.files.entry(rel.to_ascii_lowercase()).or_insert(rel);
+            }
+        }
+    }
+
+    /// `rel` as stored on disk under `dir`, matched case-insensitively as
+    /// Wallpaper Engine does.
+    fn find(&self, dir: &Path, rel: &str) -> Option<PathBuf> {
+        if let Some(stored) = self.files.get(&rel.to_ascii_lowercase()) {
+            return Some(dir.join(stored));
+        }
+        let path = dir.join(rel);
+        (!self.complete && path.is_file()).then_some(path)
+    }
+}
+
+/// Indexes of the global assets and of other workshop items, shared by every
+/// resolver so a library scan lists them once instead of once per wallpaper.
+/// Each is kept with the directory's mtime and rebuilt when it changes, so a
+/// long-running daemon sees items installed or updated meanwhile.
+fn shared_dir_index(dir: &Path) -> Arc<DirIndex> {
+    type SharedIndexes = HashMap<PathBuf, (Option<SystemTime>, Arc<DirIndex>)>;
+    static SHARED: OnceLock<Mutex<SharedIndexes>> = OnceLock::new();
+    let mtime = fs::metadata(dir).and_then(|m| m.modified()).ok();
+    let mut shared = SHARED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match shared.get(dir) {
+        Some((seen, index)) if *seen == mtime => index.clone(),
+        _ => {
+            let index = Arc::new(DirIndex::scan(dir));
+            shared.insert(dir.to_path_buf(), (mtime, index.clone()));
+            index
+        }
+    }
+}
+
 fn normalize_rel_path(path: &str) -> Option<String> {
     let raw = path.trim().replace('\\', "/");
     if raw.is_empty() {
@@ -107,44 +200,44 @@ fn find_workshop_root() -> Option<PathBuf> {
     None
 }
 
-fn workshop_candidates(rel: &str, workshop_root: &Path) -> Vec<PathBuf> {
+/// Workshop item and paths inside it that `effects|materials|shaders/
+/// workshop/<id>/...` may point to.
+fn workshop_candidates(rel: &str) -> Option<(&str, [String; 2])> {
     let parts = rel.split('/').collect::<Vec<_>>();
     if parts.len() < 4 {
-        return Vec::new();
+        return None;
     }
     let kind = parts[0];
     if !matches!(kind, "effects" | "materials" | "shaders") {
-        return Vec::new();
+        return None;
     }
     if parts[1] != "workshop" {
-        return Vec::new();
+        return None;
     }
     let workshop_id = parts[2];
     if workshop_id.is_empty() {
-        return Vec::new();
+        return None;
     }
     let tail = parts[3..].join("/");
     if tail.is_empty() {
-        return Vec::new();
+        return None;
     }
-    vec![
-        workshop_root.join(workshop_id).join(kind).join(&tail),
-        workshop_root.join(workshop_id).join(&tail),
-    ]
-}
-
-fn user_home_dir() -> Option<PathBuf> {
-    std::env::var("HOME").ok().map(PathBuf::from)
+    Some((workshop_id, [format!("{}/{}", kind, tail), tail]))
 }
 
 fn steam_roots() -> Vec<PathBuf> {
     let mut roots = Vec::<PathBuf>::new();
-    let Some(home) = user_home_dir() else {
+    let Some(home) = crate::app_paths::home_dir() else {
         return roots;
     };
 
     // Native Steam installs
     roots.push(home.join(".local/share/Steam"));
+    if let Some(data) = crate::app_paths::data_home()
+        && !roots.contains(&data.join("Steam"))
+    {
+        roots.push(data.join("Steam"));
+    }
     roots.push(home.join(".steam/steam"));
     roots.push(home.join(".steam/root"));
 
@@ -219,7 +312,7 @@ fn find_steam_wallpaper_engine_assets() -> Option<PathBuf> {
 impl AssetResolver {
     pub fn new(root: &Path) -> Result<Self> {
         if !root.exists() {
-            bail!("Wallpaper root does not exist: {}", root.display());
+            bail_msg!(Msg::WallpaperRootMissing, root.display());
         }
 
         let pkg = if root.join("scene.pkg").is_file() {
@@ -230,9 +323,19 @@ impl AssetResolver {
             None
         };
 
+        let mut pkg_index = HashMap::new();
+        for (i, entry) in pkg.iter().flat_map(|p| p.entries.iter()).enumerate() {
+            // First entry wins, as in `find_entry`.
+            pkg_index
+                .entry(entry.filename.to_ascii_lowercase())
+                .or_insert(i);
+        }
+
         Ok(Self {
             root: root.to_path_buf(),
             pkg,
+            pkg_index,
+            root_index: OnceLock::new(),
             workshop_root: find_workshop_root(),
             global_assets_root: find_global_assets_root(root),
         })
@@ -263,36 +366,46 @@ impl AssetResolver {
         let rel = normalize_rel_path(request_path)?;
 
         if let Some(pkg) = &self.pkg
-            && let Some(entry) = find_entry(pkg, &rel)
-            && let Ok(bytes) = read_entry_bytes(pkg, &entry)
+            && let Some(&i) = self.pkg_index.get(&rel.to_ascii_lowercase())
+            && let Ok(bytes) = read_entry_bytes(pkg, &pkg.entries[i])
         {
             return Some(ResolvedAsset {
                 request_path: rel.clone(),
-                resolved_path: entry.filename,
+                resolved_path: pkg.entries[i].filename.clone(),
                 source: AssetSourceKind::Package,
                 bytes,
             });
         }
 
-        let fs_path = self.root.join(&rel);
-        if fs_path.is_file()
+        let root_index = self
+            .root_index
+            .get_or_init(|| Arc::new(DirIndex::scan(&self.root)));
+        if let Some(fs_path) = root_index.find(&self.root, &rel)
             && let Ok(bytes) = fs::read(&fs_path)
         {
+            let resolved = fs_path
+                .strip_prefix(&self.root)
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_else(|_| rel.clone());
             return Some(ResolvedAsset {
                 request_path: rel.clone(),
-                resolved_path: rel.clone(),
+                resolved_path: resolved,
                 source: AssetSourceKind::WallpaperDir,
                 bytes,
             });
         }
 
-        if let Some(workshop_root) = &self.workshop_root {
-            for candidate in workshop_candidates(&rel, workshop_root) {
-                if !candidate.is_file() {
+        if let Some(workshop_root) = &self.workshop_root
+            && let Some((workshop_id, candidates)) = workshop_candidates(&rel)
+        {
+            let item_dir = workshop_root.join(workshop_id);
+            let index = shared_dir_index(&item_dir);
+            for candidate in candidates {
+                let Some(path) = index.find(&item_dir, &candidate) else {
                     continue;
-                }
-                if let Ok(bytes) = fs::read(&candidate) {
-                    let resolved = candidate.to_string_lossy().replace('\\', "/");
+                };
+                if let Ok(bytes) = fs::read(&path) {
+                    let resolved = path.to_string_lossy().replace('\\', "/");
                     return Some(ResolvedAsset {
                         request_path: rel.clone(),
                         resolved_path: resolved,
@@ -304,22 +417,22 @@ impl AssetResolver {
         }
 
         if let Some(global_root) = &self.global_assets_root {
-            let mut global_candidates = Vec::<PathBuf>::new();
-            global_candidates.push(global_root.join(&rel));
-            if rel.starts_with("assets/") {
-                global_candidates.push(global_root.join(rel.trim_start_matches("assets/")));
+            let mut global_candidates = vec![rel.as_str()];
+            if let Some(stripped) = rel.strip_prefix("assets/") {
+                global_candidates.push(stripped);
             }
 
+            let index = shared_dir_index(global_root);
             for candidate in global_candidates {
-                if !candidate.is_file() {
+                let Some(path) = index.find(global_root, candidate) else {
                     continue;
-                }
-                if let Ok(bytes) = fs::read(&candidate) {
-                    let resolved = candidate
+                };
+                if let Ok(bytes) = fs::read(&path) {
+                    let resolved = path
                         .strip_prefix(global_root)
                         .ok()
                         .map(|p| p.to_string_lossy().replace('\\', "/"))
-                        .unwrap_or_else(|| candidate.to_string_lossy().replace('\\', "/"));
+                        .unwrap_or_else(|| path.to_string_lossy().replace('\\', "/"));
                     return Some(ResolvedAsset {
                         request_path: rel.clone(),
                         resolved_path: resolved,
@@ -351,6 +464,55 @@ mod tests {
         assert!(normalize_rel_path("  ").is_none());
     }
 
+    #[test]
+    fn candidates_resolve_through_the_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Materials/Sub")).unwrap();
+        fs::write(root.join("Materials/Sub/Leaf.png"), b"fs").unwrap();
+        crate::scene_pkg::write_scene_pkg(
+            &root.join("scene.pkg"),
+            &[("materials/bark.tex".to_string(), b"pkg".to_vec())],
+        )
+        .unwrap();
+        let resolver = AssetResolver::new(root).unwrap();
+
+        let candidates = ["materials/missing.tex", "MATERIALS/Bark.tex"].map(str::to_string);
+        let asset = resolver.resolve_first(&candidates).unwrap();
+        assert_eq!(asset.source, AssetSourceKind::Package);
+        assert_eq!(asset.resolved_path, "materials/bark.tex");
+
+        let asset = resolver.resolve("./materials/sub/leaf.png").unwrap();
+        assert_eq!(asset.source, AssetSourceKind::WallpaperDir);
+        assert_eq!(asset.resolved_path, "Materials/Sub/Leaf.png");
+        assert_eq!(asset.bytes, b"fs");
+        assert!(resolver.resolve("materials/sub/none.png").is_none());
+
+        assert_eq!(
+            workshop_candidates("materials/workshop/123/tree/bark.tex")
+                .map(|(id, c)| (id, c[0].clone())),
+            Some(("123", "materials/tree/bark.tex".to_string()))
+        );
+    }
+
+    #[test]
+    fn shared_indexes_skip_dir_links_and_follow_new_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("materials")).unwrap();
+        fs::write(root.join("materials/a.png"), b"a").unwrap();
+        std::os::unix::fs::symlink(root, root.join("materials/loop")).unwrap();
+
+        let index = shared_dir_index(root);
+        assert!(index.complete);
+        assert_eq!(index.files.len(), 1, "{:?}", index.files);
+        assert!(index.find(root, "b.png").is_none());
+
+        fs::write(root.join("b.png"), b"b").unwrap();
+        let index = shared_dir_index(root);
+        assert_eq!(index.find(root, "B.PNG"), Some(root.join("b.png")));
+    }
+
     #[test]
     fn parses_libraryfolders_paths() {
         let tmp = std::env::temp_dir().join("kwe-libraryfolders-test.vdf");
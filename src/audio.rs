@@ -102,6 +102,104 @@ pub fn probe_audio(source: Option<String>, seconds: u64) -> Result<AudioProbeRes
     })
 }
 
+/// Interleaved samples that make up one `frame_ms` level frame.
+pub fn samples_per_frame(sample_rate: u32, channels: u8, frame_ms: u64) -> usize {
+    ((sample_rate as u64)
+        .saturating_mul(channels as u64)
+        .saturating_mul(frame_ms)
+        / 1000)
+        .max(1) as usize
+}
+
+/// Folds normalized samples into per-frame peak/RMS, shared by live capture
+/// and the synthetic `audio-test` signals.
+pub struct LevelMeter {
+    samples_per_frame: usize,
+    frames: Vec<AudioLevelFrame>,
+    frame_peak: f32,
+    frame_sq_sum: f64,
+    frame_samples: usize,
+    pub samples: usize,
+}
+
+impl LevelMeter {
+    pub fn new(samples_per_frame: usize) -> Self {
+        Self {
+            samples_per_frame: samples_per_frame.max(1),
+            frames: Vec::new(),
+            frame_peak: 0.0,
+            frame_sq_sum: 0.0,
+            frame_samples: 0,
+            samples: 0,
+        }
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        self.frame_peak = self.frame_peak.max(sample.abs());
+        self.frame_sq_sum += (sample as f64) * (sample as f64);
+        self.frame_samples += 1;
+        self.samples += 1;
+        if self.frame_samples >= self.samples_per_frame {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        let rms = (self.frame_sq_sum / self.frame_samples as f64).sqrt() as f32;
+        self.frames.push(AudioLevelFrame {
+            frame_index: self.frames.len() as u64,
+            peak: self.frame_peak,
+            rms,
+        });
+        self.frame_peak = 0.0;
+        self.frame_sq_sum = 0.0;
+        self.frame_samples = 0;
+    }
+
+    /// Closes a trailing partial frame and returns every frame.
+    pub fn finish(mut self) -> Vec<AudioLevelFrame> {
+        if self.frame_samples > 0 {
+            self.flush();
+        }
+        self.frames
+    }
+}
+
+/// Lowest/highest centre frequency of the bar spectrum.
+const BAND_MIN_HZ: f32 = 40.0;
+const BAND_MAX_HZ: f32 = 16_000.0;
+
+/// Magnitude of `bands` log-spaced frequencies in a mono window (Goertzel),
+/// sqrt-scaled to 0..1 like the `showfreqs=ascale=sqrt` bars overlay.
+pub fn spectrum_bands(window: &[f32], sample_rate: u32, bands: usize) -> Vec<f32> {
+    if window.is_empty() || bands == 0 {
+        return vec![0.0; bands];
+    }
+    let n = window.len() as f32;
+    let ratio = BAND_MAX_HZ / BAND_MIN_HZ;
+    (0..bands)
+        .map(|band| {
+            let t = if bands == 1 {
+                0.0
+            } else {
+                band as f32 / (bands - 1) as f32
+            };
+            let freq = BAND_MIN_HZ * ratio.powf(t);
+            let coeff = 2.0 * (std::f32::consts::TAU * freq / sample_rate as f32).cos();
+            let (mut s1, mut s2) = (0.0f32, 0.0f32);
+            for &x in window {
+                let s0 = x + coeff * s1 - s2;
+                s2 = s1;
+                s1 = s0;
+            }
+            let power = (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.0);
+            // A full-scale sine reads as amplitude 1.0.
+            let amplitude = 2.0 * power.sqrt() / n;
+            amplitude.sqrt().clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
 pub fn stream_audio_levels(
     source: Option<String>,
     seconds: u64,
@@ -135,21 +233,9 @@ pub fn stream_audio_levels(
         .saturating_mul(bytes_per_sample)
         .saturating_mul(seconds as usize);
 
-    let samples_per_frame = ((sample_rate as u64)
-        .saturating_mul(channels as u64)
-        .saturating_mul(frame_ms)
-        / 1000)
-        .max(1) as usize;
-
     let start = Instant::now();
     let mut read_total = 0usize;
-    let mut all_samples = 0usize;
-    let mut frames = Vec::<AudioLevelFrame>::new();
-
-    let mut frame_peak = 0.0f32;
-    let mut frame_sq_sum = 0.0f64;
-    let mut frame_samples = 0usize;
-    let mut frame_idx = 0u64;
+    let mut meter = LevelMeter::new(samples_per_frame(sample_rate, channels, frame_ms));
 
     let mut buf = vec![0u8; 64 * 1024];
     while read_total < target_bytes && start.elapsed() < Duration::from_secs(seconds + 2) {
@@ -162,40 +248,14 @@ pub fn stream_audio_levels(
 
         let usable = n - (n % 2);
         for i in (0..usable).step_by(2) {
-            let sample = i16::from_le_bytes([buf[i], buf[i + 1]]) as f32 / 32768.0;
-            let abs = sample.abs();
-            if abs > frame_peak {
-                frame_peak = abs;
-            }
-            frame_sq_sum += (sample as f64) * (sample as f64);
-            frame_samples += 1;
-            all_samples += 1;
-
-            if frame_samples >= samples_per_frame {
-                let rms = (frame_sq_sum / frame_samples as f64).sqrt() as f32;
-                frames.push(AudioLevelFrame {
-                    frame_index: frame_idx,
-                    peak: frame_peak,
-                    rms,
-                });
-                frame_idx += 1;
-                frame_peak = 0.0;
-                frame_sq_sum = 0.0;
-                frame_samples = 0;
-            }
+            meter.push(i16::from_le_bytes([buf[i], buf[i + 1]]) as f32 / 32768.0);
         }
 
         read_total += n;
     }
 
-    if frame_samples > 0 {
-        let rms = (frame_sq_sum / frame_samples as f64).sqrt() as f32;
-        frames.push(AudioLevelFrame {
-            frame_index: frame_idx,
-            peak: frame_peak,
-            rms,
-        });
-    }
+    let all_samples = meter.samples;
+    let frames = meter.finish();
 
     let _ = child.kill();
     let _ = child.wait();
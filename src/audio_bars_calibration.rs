@@ -0,0 +1,360 @@
+use crate::app_paths::app_config_dir;
+use crate::i18n::{Msg, warn_msg};
+use crate::layer_overrides::override_key;
+use crate::scene_effect_proxy::{AudioBarsOverlay, detect_scene_audio_bars_overlay};
+use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_native_renderer::{draw_layer_pattern, render_native_static_layers};
+use crate::scene_native_runtime::build_native_runtime_plan;
+use crate::scene_pkg::default_scene_cache_root;
+use anyhow::{Context, Result, bail};
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Test pattern colour; pure magenta is rare enough in scene art.
+const PATTERN: Rgba<u8> = Rgba([255, 0, 255, 255]);
+/// Largest centre or size error, in scene pixels, still counted as aligned.
+const TOLERANCE_PX: f32 = 2.0;
+/// How far (normalized) the heuristics may move before a saved correction
+/// no longer describes the same rect.
+const STALE_EPSILON: f32 = 0.002;
+
+/// Axis-aligned rect in 0..1 of the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationRect {
+    pub center_x: f32,
+    pub center_y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CalibrationRect {
+    pub fn from_overlay(bars: &AudioBarsOverlay) -> Self {
+        Self {
+            center_x: bars.center_x_norm,
+            center_y: bars.center_y_norm,
+            width: bars.width_norm,
+            height: bars.height_norm,
+        }
+    }
+
+    /// `(x0, y0, x1, y1)` inclusive pixel bounds within a `w`x`h` frame.
+    fn from_bounds((x0, y0, x1, y1): (u32, u32, u32, u32), w: u32, h: u32) -> Self {
+        let (w, h) = (w.max(1) as f32, h.max(1) as f32);
+        let (bw, bh) = ((x1 - x0 + 1) as f32, (y1 - y0 + 1) as f32);
+        Self {
+            center_x: (x0 as f32 + bw / 2.0) / w,
+            center_y: (y0 as f32 + bh / 2.0) / h,
+            width: bw / w,
+            height: bh / h,
+        }
+    }
+
+    fn max_difference(&self, other: &Self) -> f32 {
+        [
+            self.center_x - other.center_x,
+            self.center_y - other.center_y,
+            self.width - other.width,
+            self.height - other.height,
+        ]
+        .iter()
+        .fold(0.0f32, |acc, d| acc.max(d.abs()))
+    }
+}
+
+/// Saved fix for one wallpaper: `computed` is what the heuristics gave when
+/// it was measured, `corrected` where the compositor drew the bars object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioBarsCorrection {
+    pub computed: CalibrationRect,
+    pub corrected: CalibrationRect,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioBarsCalibrations {
+    pub version: u32,
+    /// workshop id (or wallpaper path) -> correction
+    pub wallpapers: BTreeMap<String, AudioBarsCorrection>,
+}
+
+impl Default for AudioBarsCalibrations {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            wallpapers: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn audio_bars_calibrations_path() -> PathBuf {
+    app_config_dir().join("audio-bars-calibration.json")
+}
+
+pub fn load_audio_bars_calibrations() -> Result<AudioBarsCalibrations> {
+    let path = audio_bars_calibrations_path();
+    if !path.is_file() {
+        return Ok(AudioBarsCalibrations::default());
+    }
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+fn save_audio_bars_calibrations(calibrations: &AudioBarsCalibrations) -> Result<()> {
+    let path = audio_bars_calibrations_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(calibrations)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+fn calibration_key(root: &Path) -> String {
+    let project_json = fs::read(root.join("project.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<Value>(&raw).ok());
+    override_key(root, project_json.as_ref())
+}
+
+/// `bars` moved and resized to `correction`, or `None` when the correction
+/// was measured against a different rect (the scene or the heuristics
+/// changed since). Angle, opacity and blend mode are kept.
+pub fn corrected_overlay(
+    bars: &AudioBarsOverlay,
+    correction: &AudioBarsCorrection,
+) -> Option<AudioBarsOverlay> {
+    if CalibrationRect::from_overlay(bars).max_difference(&correction.computed) > STALE_EPSILON {
+        return None;
+    }
+    let rect = correction.corrected;
+    let (scene_w, scene_h) = (
+        bars.scene_width.max(1) as f32,
+        bars.scene_height.max(1) as f32,
+    );
+    Some(AudioBarsOverlay {
+        center_x: (rect.center_x * scene_w).round() as i32,
+        center_y: (rect.center_y * scene_h).round() as i32,
+        width: ((rect.width * scene_w).round() as u32).max(16),
+        height: ((rect.height * scene_h).round() as u32).max(16),
+        center_x_norm: rect.center_x,
+        center_y_norm: rect.center_y,
+        width_norm: rect.width,
+        height_norm: rect.height,
+        ..bars.clone()
+    })
+}
+
+/// Applies the saved correction of the wallpaper at `root`, if any.
+pub fn apply_audio_bars_calibration(root: &Path, bars: AudioBarsOverlay) -> AudioBarsOverlay {
+    let calibrations = match load_audio_bars_calibrations() {
+        Ok(calibrations) => calibrations,
+        Err(err) => {
+            warn_msg!(Msg::AudioBarsCalibrationIgnored, err);
+            return bars;
+        }
+    };
+    let key = calibration_key(root);
+    let Some(correction) = calibrations.wallpapers.get(&key) else {
+        return bars;
+    };
+    corrected_overlay(&bars, correction).unwrap_or_else(|| {
+        warn_msg!(Msg::AudioBarsCalibrationStale, key);
+        bars
+    })
+}
+
+/// Inclusive bounds of the pixels of `frame` that are exactly `color`.
+pub fn find_pattern_bounds(frame: &RgbaImage, color: Rgba<u8>) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, px) in frame.enumerate_pixels() {
+        if px.0[..3] != color.0[..3] {
+            continue;
+        }
+        bounds = Some(match bounds {
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+    bounds
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioBarsCalibrationReport {
+    pub wallpaper: String,
+    /// Key this wallpaper uses in `audio-bars-calibration.json`.
+    pub key: String,
+    pub object_id: u64,
+    /// Rect from the scene.json heuristics.
+    pub computed: CalibrationRect,
+    /// Rect of the test pattern in the rendered frame.
+    pub measured: CalibrationRect,
+    /// Measured minus computed centre, in scene pixels.
+    pub offset_px: [f32; 2],
+    /// Measured over computed size.
+    pub scale: [f32; 2],
+    pub aligned: bool,
+    pub saved: bool,
+    pub frame_image: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// Renders the scene through the native compositor with the audio bars
+/// object drawn as a solid test pattern, finds the pattern in the written
+/// frame and compares it with the heuristic rect. With `save`, stores the
+/// measured rect for the wallpaper (or drops the entry when both agree).
+pub fn calibrate_audio_bars(
+    root: &Path,
+    save: bool,
+    out: Option<&Path>,
+) -> Result<AudioBarsCalibrationReport> {
+    let Some(bars) = detect_scene_audio_bars_overlay(root)? else {
+        bail!("{} has no audio bars effect", root.display());
+    };
+    let graph = build_scene_gpu_graph(root)?;
+    let plan = build_native_runtime_plan(&graph);
+    let Some(layer) = plan
+        .draw_layers
+        .iter()
+        .find(|layer| layer.object_id == bars.object_id)
+    else {
+        bail!(
+            "audio bars object #{} has no draw layer to calibrate against",
+            bars.object_id
+        );
+    };
+
+    let key = calibration_key(root);
+    let work_dir = default_scene_cache_root(&key.replace('/', "_")).join("calibration");
+    fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed creating {}", work_dir.display()))?;
+    let (width, height) = (graph.scene_width.max(1), graph.scene_height.max(1));
+    let render = render_native_static_layers(root, &work_dir, width, height, &plan)?;
+    let mut notes = Vec::new();
+    let mut frame = match image::open(&render.output_image) {
+        Ok(img) if !render.output_image.is_empty() => img.to_rgba8(),
+        _ => {
+            notes.push("Scene frame unavailable; pattern drawn on black".to_string());
+            RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]))
+        }
+    };
+    if !plan.viewports.is_empty() {
+        notes.push("Viewports ignored: pattern measured in scene space".to_string());
+    }
+    draw_layer_pattern(&mut frame, layer, PATTERN);
+    let frame_path = out
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| work_dir.join("audio_bars_calibration.png"));
+    frame
+        .save(&frame_path)
+        .with_context(|| format!("Failed writing {}", frame_path.display()))?;
+
+    // Measure on the frame as written, not on the canvas in memory.
+    let captured = image::open(&frame_path)
+        .with_context(|| format!("Failed reading {}", frame_path.display()))?
+        .to_rgba8();
+    let Some(bounds) = find_pattern_bounds(&captured, PATTERN) else {
+        bail!("test pattern not found in {}", frame_path.display());
+    };
+    let measured = CalibrationRect::from_bounds(bounds, captured.width(), captured.height());
+    let computed = CalibrationRect::from_overlay(&bars);
+    let (scene_w, scene_h) = (bars.scene_width as f32, bars.scene_height as f32);
+    let offset_px = [
+        (measured.center_x - computed.center_x) * scene_w,
+        (measured.center_y - computed.center_y) * scene_h,
+    ];
+    let aligned = offset_px.iter().all(|d| d.abs() <= TOLERANCE_PX)
+        && ((measured.width - computed.width) * scene_w).abs() <= TOLERANCE_PX
+        && ((measured.height - computed.height) * scene_h).abs() <= TOLERANCE_PX;
+    if bars.angle_rad.abs() > 1e-3 {
+        notes.push("Rotation kept from the scene; only position and size are calibrated".into());
+    }
+
+    if save {
+        let mut calibrations = load_audio_bars_calibrations()?;
+        if aligned {
+            calibrations.wallpapers.remove(&key);
+        } else {
+            calibrations.wallpapers.insert(
+                key.clone(),
+                AudioBarsCorrection {
+                    computed,
+                    corrected: measured,
+                },
+            );
+        }
+        save_audio_bars_calibrations(&calibrations)?;
+    }
+
+    Ok(AudioBarsCalibrationReport {
+        wallpaper: root.display().to_string(),
+        key,
+        object_id: bars.object_id,
+        computed,
+        measured,
+        offset_px,
+        scale: [
+            measured.width / computed.width.max(f32::EPSILON),
+            measured.height / computed.height.max(f32::EPSILON),
+        ],
+        aligned,
+        saved: save && !aligned,
+        frame_image: frame_path.display().to_string(),
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_bounds_become_a_correction_for_the_same_rect_only() {
+        let mut frame = RgbaImage::from_pixel(200, 100, Rgba([10, 20, 30, 255]));
+        for y in 60..80 {
+            for x in 40..160 {
+                frame.put_pixel(x, y, PATTERN);
+            }
+        }
+        let bounds = find_pattern_bounds(&frame, PATTERN).unwrap();
+        assert_eq!(bounds, (40, 60, 159, 79));
+        let measured = CalibrationRect::from_bounds(bounds, 200, 100);
+        assert_eq!(measured.center_x, 0.5);
+        assert_eq!(measured.center_y, 0.7);
+        assert_eq!((measured.width, measured.height), (0.6, 0.2));
+
+        let bars = AudioBarsOverlay {
+            object_id: 3,
+            center_x: 100,
+            center_y: 90,
+            width: 200,
+            height: 48,
+            angle_rad: 0.0,
+            opacity: 1.0,
+            transparency_mode: 1,
+            scene_width: 200,
+            scene_height: 100,
+            center_x_norm: 0.5,
+            center_y_norm: 0.9,
+            width_norm: 1.0,
+            height_norm: 0.48,
+        };
+        let correction = AudioBarsCorrection {
+            computed: CalibrationRect::from_overlay(&bars),
+            corrected: measured,
+        };
+        let fixed = corrected_overlay(&bars, &correction).unwrap();
+        assert_eq!((fixed.center_x, fixed.center_y), (100, 70));
+        assert_eq!((fixed.width, fixed.height), (120, 20));
+        assert_eq!(fixed.object_id, 3);
+
+        let moved = AudioBarsOverlay {
+            center_y_norm: 0.8,
+            ..bars
+        };
+        assert!(corrected_overlay(&moved, &correction).is_none());
+    }
+}
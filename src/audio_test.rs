@@ -0,0 +1,212 @@
+use crate::audio::{LevelMeter, samples_per_frame, spectrum_bands};
+use crate::cli::AudioTestPattern;
+use crate::scene_runtime::build_uniform_timeline;
+use anyhow::{Context, Result, bail};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use std::f32::consts::TAU;
+use std::fs;
+use std::path::Path;
+
+const SAMPLE_RATE: u32 = 48_000;
+const SWEEP_START_HZ: f32 = 40.0;
+const SWEEP_END_HZ: f32 = 16_000.0;
+const BEAT_INTERVAL_S: f32 = 0.5;
+const KICK_HZ: f32 = 60.0;
+const MAX_SECONDS: u64 = 120;
+
+#[derive(Debug, Serialize)]
+pub struct AudioTestFrame {
+    pub frame_index: u64,
+    pub time_s: f32,
+    pub peak: f32,
+    pub rms: f32,
+    pub energy: f32,
+    pub beat: f32,
+    pub bands: Vec<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AudioTestReport {
+    pub pattern: AudioTestPattern,
+    pub sample_rate: u32,
+    pub seconds: u64,
+    pub frame_ms: u64,
+    pub bands: usize,
+    pub frames: Vec<AudioTestFrame>,
+    /// PNGs of the bars overlay, one per frame, when `--frames-out` is set.
+    pub overlay_frames: Vec<String>,
+}
+
+/// Mono samples of `pattern`; the same input always yields the same signal.
+pub fn synth_pattern(pattern: AudioTestPattern, seconds: u64, sample_rate: u32) -> Vec<f32> {
+    let total = sample_rate as usize * seconds as usize;
+    let rate = sample_rate as f32;
+    match pattern {
+        AudioTestPattern::Silence => vec![0.0; total],
+        AudioTestPattern::Sweep => {
+            let duration = seconds.max(1) as f32;
+            let k = (SWEEP_END_HZ / SWEEP_START_HZ).ln() / duration;
+            (0..total)
+                .map(|i| {
+                    let t = i as f32 / rate;
+                    // Phase of an exponential chirp, so frequency rises evenly per band.
+                    let phase = TAU * SWEEP_START_HZ * ((k * t).exp() - 1.0) / k;
+                    0.5 * phase.sin()
+                })
+                .collect()
+        }
+        AudioTestPattern::Beats => (0..total)
+            .map(|i| {
+                let t = i as f32 / rate;
+                let since = t % BEAT_INTERVAL_S;
+                0.9 * (-since * 18.0).exp() * (TAU * KICK_HZ * since).sin()
+            })
+            .collect(),
+    }
+}
+
+/// Draws one bars-overlay frame: white bars on transparent, bottom-aligned,
+/// matching the `showfreqs=mode=bar` overlay the proxies composite.
+pub fn render_bars_frame(bands: &[f32], width: u32, height: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(width.max(1), height.max(1));
+    if bands.is_empty() {
+        return img;
+    }
+    let slot = img.width() as f32 / bands.len() as f32;
+    for (i, level) in bands.iter().enumerate() {
+        let x0 = (i as f32 * slot).round() as u32;
+        let x1 = (((i + 1) as f32 * slot) - 1.0).round().max(x0 as f32) as u32;
+        let bar_h = (level.clamp(0.0, 1.0) * img.height() as f32).round() as u32;
+        for x in x0..=x1.min(img.width() - 1) {
+            for y in img.height() - bar_h..img.height() {
+                img.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+    }
+    img
+}
+
+/// Runs a synthetic signal through level metering, the bar spectrum and the
+/// uniform timeline, optionally writing the bars overlay frames as PNGs.
+pub fn run_audio_test(
+    pattern: AudioTestPattern,
+    seconds: u64,
+    frame_ms: u64,
+    bands: usize,
+    frames_out: Option<&Path>,
+    frame_size: (u32, u32),
+) -> Result<AudioTestReport> {
+    if !(1..=MAX_SECONDS).contains(&seconds) {
+        bail!("--seconds must be between 1 and {}", MAX_SECONDS);
+    }
+    if bands == 0 {
+        bail!("--bands must be at least 1");
+    }
+    let frame_ms = frame_ms.max(1);
+    let samples = synth_pattern(pattern, seconds, SAMPLE_RATE);
+    let window = samples_per_frame(SAMPLE_RATE, 1, frame_ms);
+
+    let mut meter = LevelMeter::new(window);
+    for &sample in &samples {
+        meter.push(sample);
+    }
+    let levels = meter.finish();
+    let uniforms = build_uniform_timeline(&levels, frame_ms);
+
+    if let Some(dir) = frames_out {
+        fs::create_dir_all(dir).with_context(|| format!("Failed creating {}", dir.display()))?;
+    }
+    let mut overlay_frames = Vec::new();
+    let mut frames = Vec::with_capacity(uniforms.len());
+    for (uniform, chunk) in uniforms.into_iter().zip(samples.chunks(window)) {
+        let band_values = spectrum_bands(chunk, SAMPLE_RATE, bands);
+        if let Some(dir) = frames_out {
+            let path = dir.join(format!("bars_{:05}.png", uniform.frame_index));
+            render_bars_frame(&band_values, frame_size.0, frame_size.1)
+                .save(&path)
+                .with_context(|| format!("Failed writing {}", path.display()))?;
+            overlay_frames.push(path.display().to_string());
+        }
+        frames.push(AudioTestFrame {
+            frame_index: uniform.frame_index,
+            time_s: uniform.time_s,
+            peak: uniform.peak,
+            rms: uniform.rms,
+            energy: uniform.energy,
+            beat: uniform.beat,
+            bands: band_values,
+        });
+    }
+    if let Some(dir) = frames_out {
+        eprintln!(
+            "[ok] {} overlay frames written to {}",
+            overlay_frames.len(),
+            dir.display()
+        );
+    }
+
+    Ok(AudioTestReport {
+        pattern,
+        sample_rate: SAMPLE_RATE,
+        seconds,
+        frame_ms,
+        bands,
+        frames,
+        overlay_frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loudest_band(frame: &AudioTestFrame) -> usize {
+        (0..frame.bands.len())
+            .max_by(|a, b| frame.bands[*a].total_cmp(&frame.bands[*b]))
+            .unwrap()
+    }
+
+    #[test]
+    fn synthetic_patterns_drive_bands_and_uniforms() {
+        let silence = run_audio_test(AudioTestPattern::Silence, 1, 50, 16, None, (64, 16)).unwrap();
+        assert_eq!(silence.frames.len(), 20);
+        assert!(silence.frames.iter().all(|f| f.rms == 0.0 && f.beat == 0.0));
+        assert!(
+            silence
+                .frames
+                .iter()
+                .all(|f| f.bands.iter().all(|b| *b == 0.0))
+        );
+
+        let sweep = run_audio_test(AudioTestPattern::Sweep, 2, 50, 16, None, (64, 16)).unwrap();
+        let first = loudest_band(&sweep.frames[1]);
+        let last = loudest_band(sweep.frames.last().unwrap());
+        assert!(first < 3 && last > 12, "sweep bands {} -> {}", first, last);
+
+        let beats = run_audio_test(AudioTestPattern::Beats, 2, 50, 16, None, (64, 16)).unwrap();
+        let onsets = beats
+            .frames
+            .windows(2)
+            .filter(|w| w[0].beat == 0.0 && w[1].beat > 0.0)
+            .count();
+        // Kicks at 0.5, 1.0 and 1.5 s (the one at 0 s opens the clip).
+        assert_eq!(onsets, 3);
+        assert!(beats.frames[0].beat > 0.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = run_audio_test(
+            AudioTestPattern::Beats,
+            1,
+            250,
+            8,
+            Some(dir.path()),
+            (64, 16),
+        )
+        .unwrap();
+        assert_eq!(out.overlay_frames.len(), 4);
+        let img = image::open(&out.overlay_frames[0]).unwrap().to_rgba8();
+        assert_eq!(img.dimensions(), (64, 16));
+        assert_eq!(img.get_pixel(0, 15)[3], 255);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::app_paths::{APP_DIR_NAME, app_cache_dir};
+use crate::i18n::{Msg, warn_msg};
+use crate::scene_renderer::previous_inputs_fingerprint;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever files under the cache change layout; each bump adds a
+/// step to `MIGRATIONS`.
+pub const CACHE_LAYOUT_VERSION: u32 = 1;
+const MARKER_FILE: &str = "cache-layout.json";
+
+type MigrationFn = fn(&Path, bool) -> Result<Vec<String>>;
+
+/// Steps in order: `(version reached, name, step)`. A step gets the cache
+/// dir and `dry_run`, and returns the paths it removed (or would remove).
+const MIGRATIONS: &[(u32, &str, MigrationFn)] = &[
+    (
+        1,
+        "render sessions without inputs fingerprint",
+        drop_unfingerprinted_sessions,
+    ),
+    (1, "stale text updater pid files", drop_stale_updater_pids),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheMarker {
+    pub version: u32,
+    /// Package version that wrote the marker.
+    pub written_by: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheMigrationStep {
+    pub version: u32,
+    pub name: String,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheMigrationReport {
+    pub cache_dir: String,
+    /// `None` for caches written before the marker existed.
+    pub from_version: Option<u32>,
+    pub to_version: u32,
+    pub steps: Vec<CacheMigrationStep>,
+    pub dry_run: bool,
+}
+
+impl CacheMigrationReport {
+    pub fn removed_count(&self) -> usize {
+        self.steps.iter().map(|s| s.removed.len()).sum()
+    }
+}
+
+fn cache_marker_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join(MARKER_FILE)
+}
+
+pub fn read_cache_marker(cache_dir: &Path) -> Option<CacheMarker> {
+    let raw = fs::read(cache_marker_path(cache_dir)).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+fn write_cache_marker(cache_dir: &Path) -> Result<()> {
+    let marker = CacheMarker {
+        version: CACHE_LAYOUT_VERSION,
+        written_by: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let path = cache_marker_path(cache_dir);
+    fs::write(&path, serde_json::to_vec_pretty(&marker)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// `<cache>/scene/*/<name>` that exist.
+fn scene_cache_children(cache_dir: &Path, name: &str) -> Vec<PathBuf> {
+    let Ok(dir) = fs::read_dir(cache_dir.join("scene")) else {
+        return Vec::new();
+    };
+    let mut paths = dir
+        .flatten()
+        .map(|e| e.path().join(name))
+        .filter(|p| p.exists())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn remove_path(path: &Path, dry_run: bool) -> Result<String> {
+    if !dry_run {
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        removed.with_context(|| format!("Failed removing {}", path.display()))?;
+    }
+    Ok(path.display().to_string())
+}
+
+/// Sessions from before the inputs fingerprint keep `gpu/` and `proxy/`
+/// output nothing can tell apart from current ones.
+fn drop_unfingerprinted_sessions(cache_dir: &Path, dry_run: bool) -> Result<Vec<String>> {
+    scene_cache_children(cache_dir, "render-session")
+        .into_iter()
+        .filter(|session| previous_inputs_fingerprint(&session.join("manifest.json")).is_none())
+        .map(|session| remove_path(&session, dry_run))
+        .collect()
+}
+
+/// The next text refresh kills the pid in `updater.pid`; once that process
+/// is gone the pid may belong to anything.
+fn drop_stale_updater_pids(cache_dir: &Path, dry_run: bool) -> Result<Vec<String>> {
+    scene_cache_children(cache_dir, "text-layers/updater.pid")
+        .into_iter()
+        .filter(|pid_file| {
+            let cmdline = fs::read_to_string(pid_file)
+                .ok()
+                .and_then(|raw| raw.trim().parse::<u32>().ok())
+                .and_then(|pid| fs::read(format!("/proc/{}/cmdline", pid)).ok());
+            !cmdline.is_some_and(|c| String::from_utf8_lossy(&c).contains(APP_DIR_NAME))
+        })
+        .map(|pid_file| remove_path(&pid_file, dry_run))
+        .collect()
+}
+
+/// Runs the steps newer than the marker of `cache_dir`, then records the
+/// current layout. Caches from a newer layout are left untouched.
+pub fn migrate_cache_dir(cache_dir: &Path, dry_run: bool) -> Result<CacheMigrationReport> {
+    let from_version = read_cache_marker(cache_dir).map(|m| m.version);
+    let mut report = CacheMigrationReport {
+        cache_dir: cache_dir.display().to_string(),
+        from_version,
+        to_version: CACHE_LAYOUT_VERSION,
+        steps: Vec::new(),
+        dry_run,
+    };
+    let from = from_version.unwrap_or(0);
+    if from > CACHE_LAYOUT_VERSION {
+        warn_msg!(Msg::CacheLayoutNewer, cache_dir.display(), from);
+        report.to_version = from;
+        return Ok(report);
+    }
+    for (version, name, step) in MIGRATIONS.iter().filter(|(v, _, _)| *v > from) {
+        report.steps.push(CacheMigrationStep {
+            version: *version,
+            name: name.to_string(),
+            removed: step(cache_dir, dry_run)?,
+        });
+    }
+    if !dry_run && cache_dir.is_dir() && from < CACHE_LAYOUT_VERSION {
+        write_cache_marker(cache_dir)?;
+    }
+    Ok(report)
+}
+
+/// First run of a new layout: migrates the cache in `cache_dir` once and
+/// reports what changed. Nothing is touched on a dry run. Failures only cost
+/// a warning.
+pub fn ensure_cache_layout_in(cache_dir: &Path, dry_run: bool) {
+    if dry_run
+        || !cache_dir.is_dir()
+        || read_cache_marker(cache_dir).is_some_and(|m| m.version >= CACHE_LAYOUT_VERSION)
+    {
+        return;
+    }
+    match migrate_cache_dir(cache_dir, false) {
+        Ok(report) => {
+            for step in report.steps.iter().filter(|s| !s.removed.is_empty()) {
+                eprintln!(
+                    "[ok] cache layout v{}: removed {} ({})",
+                    step.version,
+                    step.removed.len(),
+                    step.name
+                );
+            }
+        }
+        Err(err) => warn_msg!(Msg::CacheMigrationFailed, err),
+    }
+}
+
+/// Migrates the user cache. Only called from the binary's `main`.
+pub fn ensure_cache_layout(dry_run: bool) {
+    ensure_cache_layout_in(&app_cache_dir(), dry_run);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmarked_cache_drops_legacy_leftovers_once() {
+        let cache = tempfile::tempdir().unwrap();
+        let scene = cache.path().join("scene");
+        for (id, manifest) in [
+            ("100", r#"{"visual_asset_path": "a.tex"}"#),
+            ("200", r#"{"inputs_fingerprint": "abc"}"#),
+        ] {
+            fs::create_dir_all(scene.join(id).join("render-session/gpu")).unwrap();
+            fs::write(
+                scene.join(id).join("render-session/manifest.json"),
+                manifest,
+            )
+            .unwrap();
+        }
+        fs::create_dir_all(scene.join("200/text-layers")).unwrap();
+        fs::write(scene.join("200/text-layers/updater.pid"), "4294967295").unwrap();
+
+        let dry = migrate_cache_dir(cache.path(), true).unwrap();
+        assert_eq!((dry.from_version, dry.removed_count()), (None, 2));
+        assert!(scene.join("100/render-session").is_dir());
+        assert!(read_cache_marker(cache.path()).is_none());
+
+        let report = migrate_cache_dir(cache.path(), false).unwrap();
+        assert_eq!(report.steps[0].removed.len(), 1);
+        assert_eq!(report.steps[1].removed.len(), 1);
+        assert!(!scene.join("100/render-session").exists());
+        assert!(scene.join("200/render-session/gpu").is_dir());
+        assert!(!scene.join("200/text-layers/updater.pid").exists());
+        assert_eq!(
+            read_cache_marker(cache.path()).map(|m| m.version),
+            Some(CACHE_LAYOUT_VERSION)
+        );
+
+        let again = migrate_cache_dir(cache.path(), false).unwrap();
+        assert_eq!((again.from_version, again.steps.len()), (Some(1), 0));
+
+        // The startup check leaves the cache alone on a dry run.
+        let fresh = tempfile::tempdir().unwrap();
+        fs::create_dir_all(fresh.path().join("scene/300/render-session")).unwrap();
+        ensure_cache_layout_in(fresh.path(), true);
+        assert!(read_cache_marker(fresh.path()).is_none());
+        ensure_cache_layout_in(fresh.path(), false);
+        assert!(fresh.path().join("scene/300").is_dir());
+        assert!(read_cache_marker(fresh.path()).is_some());
+        assert!(!fresh.path().join("scene/300/render-session").exists());
+
+        // The entry point `main` calls works on the app cache dir.
+        let app_dirs = tempfile::tempdir().unwrap();
+        fs::create_dir(app_dirs.path().join("cache")).unwrap();
+        crate::app_paths::with_app_dirs_root(app_dirs.path(), || ensure_cache_layout(false));
+        assert!(read_cache_marker(&app_dirs.path().join("cache")).is_some());
+    }
+}
@@ -1,14 +1,142 @@
+use crate::app_paths;
+use crate::custom_profiles::parse_playback_profile;
+use crate::display_orientation::{CropRegion, parse_crop_region};
+use crate::journal::JournalKind;
+use crate::mpv_options::parse_mpv_option;
+use crate::overlay_wallpaper::parse_pip_rect;
+use crate::scene_effect_proxy::parse_uniform_assignment;
+use crate::scene_text::parse_timezone;
+use chrono_tz::Tz;
 use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Copy, ValueEnum, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlaybackProfile {
     Performance,
     Balanced,
     Quality,
+    /// Profile defined under `profiles` in config.json.
+    Custom(&'static str),
+}
+
+impl PlaybackProfile {
+    pub const PRESETS: [PlaybackProfile; 3] = [
+        PlaybackProfile::Performance,
+        PlaybackProfile::Balanced,
+        PlaybackProfile::Quality,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlaybackProfile::Performance => "performance",
+            PlaybackProfile::Balanced => "balanced",
+            PlaybackProfile::Quality => "quality",
+            PlaybackProfile::Custom(name) => name,
+        }
+    }
+
+    /// Preset called `name`, or a custom profile of that name (not checked
+    /// against the config; see `parse_playback_profile`).
+    pub fn from_name(name: &str) -> PlaybackProfile {
+        Self::PRESETS
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .unwrap_or_else(|| PlaybackProfile::Custom(intern_profile_name(name)))
+    }
+}
+
+/// Custom profile names live as long as the process; each distinct name is
+/// leaked once so the profile stays `Copy`.
+fn intern_profile_name(name: &str) -> &'static str {
+    static NAMES: std::sync::Mutex<Vec<&'static str>> = std::sync::Mutex::new(Vec::new());
+    let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(known) = names.iter().find(|known| **known == name) {
+        return known;
+    }
+    let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.push(leaked);
+    leaked
+}
+
+impl std::fmt::Display for PlaybackProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for PlaybackProfile {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for PlaybackProfile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(PlaybackProfile::from_name(&name))
+    }
+}
+
+/// What commands write to stdout. Status lines (`[ok]`, `[warn]`,
+/// `[dry-run]`) always go to stderr, so stdout stays machine-readable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RoadmapExportFormat {
+    Markdown,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum StatsFormat {
+    Json,
+    Prometheus,
+}
+
+/// Language of warnings and errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+}
+
+/// How scene text layers reach the screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextRenderMode {
+    /// ffmpeg drawtext filter that re-reads one text file per layer.
+    #[default]
+    Drawtext,
+    /// mpv `osd-overlay` (ASS) pushed over the IPC socket by the refresh daemon.
+    Osd,
+}
+
+/// Layer-shell level the wallpaper surface is placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayerShellLayer {
+    Background,
+    /// Above other background tools, still below windows and panels.
+    Bottom,
+}
+
+/// How a wallpaper whose aspect differs from the output fills it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Scaling {
+    /// Whole frame visible, with bars (mpv's default).
+    Fit,
+    /// Cover the output, cropping the centre.
+    Fill,
+    /// Cover the output, distorting the frame.
+    Stretch,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -18,10 +146,21 @@ pub enum ProxyPreset {
     Ultra,
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum GpuTransport {
     Mp4Proxy,
     NativeRealtime,
+    /// Frames from the native compositor piped into mpv, with no proxy file.
+    NativeStream,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Orientation {
+    /// Portrait when the compositor reports a 90/270 degree transform.
+    Auto,
+    Landscape,
+    Portrait,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -30,10 +169,76 @@ pub enum AudioBarsSource {
     Synth,
 }
 
+/// How the `native-realtime` producer handles frames that barely change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RealtimeMotion {
+    /// Encode every frame at the proxy fps.
+    #[default]
+    Full,
+    /// Drop near-duplicate frames; mpv keeps showing the last one.
+    Adaptive,
+    /// Like `adaptive`, producing at half the proxy fps.
+    Low,
+}
+
+/// Deterministic signal fed through the audio-reactive pipeline by `audio-test`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AudioTestPattern {
+    /// Logarithmic sine sweep across the bar spectrum.
+    Sweep,
+    /// Kick-like bursts at 120 BPM.
+    Beats,
+    Silence,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum MuteState {
+    On,
+    Off,
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GreeterTarget {
+    Greetd,
+    Sddm,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GreeterMode {
+    Static,
+    Animated,
+}
+
 #[derive(Parser)]
 #[command(name = "kitsune-livewallpaper")]
 #[command(about = "Kitsune custom wallpaper engine MVP")]
 pub struct Cli {
+    /// Formato de stdout: json (por defecto) o un resumen de texto
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Json)]
+    pub output: OutputFormat,
+    /// Modo seguro: solo rutas estables (mp4-proxy, sin audio-reactive ni overlay, previews)
+    #[arg(long, global = true)]
+    pub safe: bool,
+    /// Etiqueta de instancia (o KWE_INSTANCE); solo se detienen los wallpapers de la misma instancia
+    #[arg(long, global = true)]
+    pub instance: Option<String>,
+    /// Detiene tambien wallpapers y servicios de otras instancias
+    #[arg(long, global = true)]
+    pub force: bool,
+    /// Opcion extra de mpv (clave=valor, repetible), anadida a cada mpvpaper lanzado
+    #[arg(long = "mpv-option", global = true, value_parser = parse_mpv_option)]
+    pub mpv_options: Vec<String>,
+    /// Intenta leer pkg/tex ofuscados con variantes conocidas (cabecera desplazada, tabla XOR)
+    #[arg(long, global = true)]
+    pub allow_obfuscated: bool,
+    /// Falla antes de escribir nada dentro de la libreria de wallpapers (p. ej. montada en solo lectura)
+    #[arg(long, global = true)]
+    pub read_only_library: bool,
+    /// Idioma de avisos y errores (por defecto: LC_ALL, LC_MESSAGES o LANG)
+    #[arg(long, global = true, value_enum)]
+    pub lang: Option<Lang>,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -51,9 +256,37 @@ pub enum Commands {
     StartConfig {
         #[arg(long, default_value_os_t = default_config_path())]
         config: PathBuf,
+        /// Sigue en primer plano tras aplicar: avisa READY/WATCHDOG a systemd
+        /// mientras la reproduccion de cada monitor responda
+        #[arg(long)]
+        supervise: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(about = "Silencia/reactiva el audio de todos los wallpapers (modo oficina)")]
+    Mute {
+        #[arg(value_enum)]
+        state: MuteState,
         #[arg(long)]
         dry_run: bool,
     },
+    #[command(
+        about = "Ajusta propiedades por monitor en caliente (speed, volume, props de escena)"
+    )]
+    Property {
+        #[command(subcommand)]
+        command: PropertyCommands,
+    },
+    #[command(about = "Sincroniza la libreria con colecciones del Workshop de Steam")]
+    Workshop {
+        #[command(subcommand)]
+        command: WorkshopCommands,
+    },
+    #[command(about = "Fondo de pantalla para la pantalla de login (greetd/SDDM)")]
+    Greeter {
+        #[command(subcommand)]
+        command: GreeterCommands,
+    },
     #[command(about = "Instala/habilita/deshabilita el servicio de autostart de usuario")]
     ServiceAutostart {
         #[command(subcommand)]
@@ -73,6 +306,11 @@ pub enum Commands {
         #[arg(long)]
         full: bool,
     },
+    #[command(about = "Exporta o importa la tabla de entradas de scene.pkg como JSON")]
+    ScenePkg {
+        #[command(subcommand)]
+        command: ScenePkgCommands,
+    },
     #[command(about = "Genera un plan de render/reproduccion para una escena")]
     ScenePlan {
         wallpaper: String,
@@ -85,6 +323,20 @@ pub enum Commands {
         #[arg(long, default_value_os_t = default_downloads_root())]
         downloads_root: PathBuf,
     },
+    #[command(
+        about = "Compara el rect de las barras de audio con un fotograma renderizado y guarda la correccion"
+    )]
+    CalibrateAudioBars {
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        /// Guarda el rect medido para este wallpaper
+        #[arg(long)]
+        save: bool,
+        /// Guarda el fotograma con el patron de prueba en esta ruta
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
     #[command(about = "Escanea libreria local y resume contenido de wallpapers")]
     LibraryScan {
         #[arg(long, default_value_os_t = default_downloads_root())]
@@ -93,6 +345,10 @@ pub enum Commands {
         top_effects: usize,
         #[arg(long)]
         summary_only: bool,
+        /// Procesa cada wallpaper en streaming y acota el histograma de efectos
+        /// (para equipos con poca RAM)
+        #[arg(long)]
+        low_memory: bool,
     },
     #[command(about = "Genera roadmap/prioridades de procesamiento para la libreria")]
     LibraryRoadmap {
@@ -100,6 +356,33 @@ pub enum Commands {
         downloads_root: PathBuf,
         #[arg(long, default_value_t = 15)]
         top_n: usize,
+        /// Escribe un reporte markdown/csv en --out en lugar del JSON
+        #[arg(long, value_enum, requires = "out")]
+        export: Option<RoadmapExportFormat>,
+        #[arg(long, requires = "export")]
+        out: Option<PathBuf>,
+        /// Reutiliza el ultimo roadmap generado (sin reescanear la libreria)
+        #[arg(long)]
+        from_cache: bool,
+        /// Reescanea como mucho una vez cada N minutos; mientras tanto exporta
+        /// el roadmap en cache (para exportes automaticos con cron/timers)
+        #[arg(long, value_name = "MINUTOS", conflicts_with = "from_cache")]
+        min_interval: Option<u64>,
+    },
+    #[command(about = "Exporta estadisticas de compatibilidad de la libreria para un dashboard")]
+    LibraryStats {
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        #[arg(long, value_enum, default_value_t = StatsFormat::Json)]
+        format: StatsFormat,
+        /// Familias de shader no soportadas a listar
+        #[arg(long, default_value_t = 10)]
+        top_families: usize,
+        /// Envia las estadisticas (sin ids, titulos ni rutas) por POST a esta URL
+        #[arg(long)]
+        share: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
     },
     #[command(about = "Simula runtime de escena y extrae telemetria basica")]
     SceneRuntime {
@@ -133,11 +416,147 @@ pub enum Commands {
         #[arg(long, default_value_os_t = default_downloads_root())]
         downloads_root: PathBuf,
     },
+    #[command(about = "Lista propiedades de usuario y grupos de objetos de una escena")]
+    SceneProperties {
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+    #[command(about = "Resume los sistemas de particulas de una escena")]
+    SceneParticles {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+    #[command(about = "Extrae los shaders de una escena para analisis offline")]
+    SceneShaders {
+        #[command(subcommand)]
+        command: SceneShaderCommands,
+    },
+    #[command(about = "Prueba valores de uniforms sobre el plan de efectos en tiempo real")]
+    SceneUniform {
+        #[command(subcommand)]
+        command: SceneUniformCommands,
+    },
+    #[command(about = "Plan de overlays por monitor (JSON) para visualizadores externos")]
+    OverlayPlan {
+        #[command(subcommand)]
+        command: OverlayPlanCommands,
+    },
+    #[command(about = "Consulta los metadatos incrustados en los proxies generados")]
+    Proxy {
+        #[command(subcommand)]
+        command: ProxyCommands,
+    },
+    #[command(about = "Exporta o importa la configuracion completa (perfil .tar.zst)")]
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    #[command(about = "Perfiles de reproduccion: presets y los definidos en config.json")]
+    Profiles {
+        #[command(subcommand)]
+        command: ProfilesCommands,
+    },
+    #[command(about = "Lista u omite capas y efectos de una escena (overrides.json)")]
+    Layers {
+        #[command(subcommand)]
+        command: LayersCommands,
+    },
+    #[command(about = "Genera material para reportar un problema con un wallpaper")]
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
     #[command(about = "Planifica ruta de reproduccion nativa (sin proxy)")]
     SceneNativePlan {
         wallpaper: String,
         #[arg(long, default_value_os_t = default_downloads_root())]
         downloads_root: PathBuf,
+        /// Presupuesto de coste (por defecto KWE_NATIVE_BUDGET o segun los hilos de CPU)
+        #[arg(long)]
+        budget: Option<f32>,
+    },
+    #[command(about = "Compara el soporte de una escena con otros motores")]
+    Compat {
+        #[command(subcommand)]
+        command: CompatCommands,
+    },
+    #[command(about = "Compila una escena a un bundle normalizado .kwescene")]
+    SceneCompile {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        #[arg(long, default_value_t = 4)]
+        seconds: u64,
+        #[arg(long, default_value_t = 50)]
+        frame_ms: u64,
+        #[arg(long)]
+        no_bake: bool,
+    },
+    #[command(
+        about = "Verifica sin pantalla que una escena se renderiza en nativo (para CI de autores)"
+    )]
+    VerifyRender {
+        #[arg(long)]
+        wallpaper: String,
+        /// Numero exacto de capas que debe dibujar el compositor nativo
+        #[arg(long)]
+        assert_layers: Option<usize>,
+        /// Falla si la escena cae al proxy o al preview
+        #[arg(long)]
+        assert_no_fallback: bool,
+        /// Guarda el fotograma renderizado en esta ruta
+        #[arg(long)]
+        out: Option<PathBuf>,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+    /// Uso interno del transporte `native-stream`.
+    #[command(hide = true)]
+    NativeStream {
+        #[arg(long)]
+        root: PathBuf,
+        #[arg(long)]
+        monitor: String,
+        #[arg(long)]
+        session_dir: PathBuf,
+        #[arg(long)]
+        fifo: PathBuf,
+        #[arg(long, default_value_t = 30)]
+        fps: u32,
+    },
+    #[command(about = "Genera una miniatura nitida de un wallpaper desde el plan nativo")]
+    RenderThumbnail {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value = "1280x720")]
+        size: String,
+        #[arg(long)]
+        out: Option<PathBuf>,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+    #[command(
+        about = "Genera una tira de fotogramas (contact sheet) del movimiento de un wallpaper"
+    )]
+    PreviewStrip {
+        #[arg(long)]
+        wallpaper: String,
+        /// Numero de fotogramas muestreados a lo largo del proxy/render
+        #[arg(long, default_value_t = 6)]
+        frames: usize,
+        /// Tamano de cada fotograma
+        #[arg(long, default_value = "320x180")]
+        frame_size: String,
+        #[arg(long)]
+        out: Option<PathBuf>,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
     },
     #[command(about = "Reproduce escena con pipeline GPU experimental")]
     SceneGpuPlay {
@@ -158,12 +577,23 @@ pub enum Commands {
         frame_ms: u64,
         #[arg(long)]
         mute_audio: bool,
-        #[arg(long, value_enum, default_value_t = PlaybackProfile::Performance)]
+        #[arg(
+            long,
+            value_parser = parse_playback_profile,
+            default_value_t = PlaybackProfile::Performance
+        )]
         profile: PlaybackProfile,
         #[arg(long)]
         display_fps: Option<u32>,
-        #[arg(long, default_value_t = true)]
-        clock_overlay: bool,
+        /// Capas de texto de scene.json (por defecto: config, si no `true`)
+        #[arg(long)]
+        clock_overlay: Option<bool>,
+        /// Zona horaria IANA de los relojes y fechas (por defecto: config, si no la local)
+        #[arg(long, value_parser = parse_timezone)]
+        timezone: Option<Tz>,
+        /// Orientacion de la salida; `auto` usa el transform de hyprctl
+        #[arg(long, value_enum, default_value_t = Orientation::Auto)]
+        orientation: Orientation,
         #[arg(long, default_value_t = true)]
         apply_kitsune_overlay: bool,
         #[arg(long, value_enum, default_value_t = GpuTransport::Mp4Proxy)]
@@ -172,12 +602,28 @@ pub enum Commands {
         require_native: bool,
         #[arg(long, value_enum, default_value_t = AudioBarsSource::Pulse)]
         audio_bars_source: AudioBarsSource,
+        /// Con native-realtime: `adaptive` descarta frames casi iguales, `low` ademas produce a mitad de fps
+        #[arg(long, value_enum, default_value_t = RealtimeMotion::Full)]
+        realtime_motion: RealtimeMotion,
         #[arg(long, default_value_t = 2560)]
         proxy_width: u32,
-        #[arg(long, default_value_t = 60)]
-        proxy_fps: u32,
+        /// Defaults to the fps inferred from the scene motion (10/30/refresh)
+        #[arg(long)]
+        proxy_fps: Option<u32>,
         #[arg(long, default_value_t = 20)]
         proxy_crf: u8,
+        /// Override the loop-friendly proxy length derived from the scene motion
+        #[arg(long)]
+        proxy_seconds: Option<f32>,
+        /// Cadena ffmpeg extra para el proxy: `pre=...`, `post=...` o solo la cadena (post). Sustituye a `custom_filters` de overrides.json
+        #[arg(long = "custom-filter")]
+        custom_filters: Vec<String>,
+        #[arg(long)]
+        audio_reactive: bool,
+        #[arg(long, default_value_t = 1.0)]
+        audio_reactive_strength: f32,
+        #[arg(long)]
+        explain: bool,
         #[arg(long)]
         dry_run: bool,
     },
@@ -187,8 +633,9 @@ pub enum Commands {
         spec: PathBuf,
         #[arg(long = "loop", default_value_t = false)]
         loop_mode: bool,
-        #[arg(long, default_value_t = 1)]
-        interval_seconds: u64,
+        /// Intervalo fijo; por defecto se espera al siguiente cambio de segundo/minuto/dia
+        #[arg(long)]
+        interval_seconds: Option<u64>,
     },
     #[command(about = "Reproduce una escena de Wallpaper Engine como live wallpaper")]
     ScenePlay {
@@ -209,12 +656,29 @@ pub enum Commands {
         frame_ms: u64,
         #[arg(long)]
         mute_audio: bool,
-        #[arg(long, value_enum, default_value_t = PlaybackProfile::Performance)]
+        #[arg(
+            long,
+            value_parser = parse_playback_profile,
+            default_value_t = PlaybackProfile::Performance
+        )]
         profile: PlaybackProfile,
         #[arg(long)]
         display_fps: Option<u32>,
-        #[arg(long, default_value_t = true)]
-        clock_overlay: bool,
+        /// Capas de texto de scene.json (por defecto: config, si no `true`)
+        #[arg(long)]
+        clock_overlay: Option<bool>,
+        /// Zona horaria IANA de los relojes y fechas (por defecto: config, si no la local)
+        #[arg(long, value_parser = parse_timezone)]
+        timezone: Option<Tz>,
+        /// Orientacion de la salida; `auto` usa el transform de hyprctl
+        #[arg(long, value_enum, default_value_t = Orientation::Auto)]
+        orientation: Orientation,
+        /// Reloj generico cuando la escena no tiene texto (por defecto: config, si no `false`)
+        #[arg(long)]
+        fallback_clock: Option<bool>,
+        /// Recorta y reescala la salida a x,y,w,h (pixeles de la escena o fracciones 0..1)
+        #[arg(long, value_parser = parse_crop_region)]
+        crop_region: Option<CropRegion>,
         #[arg(long, value_enum, default_value_t = ProxyPreset::Balanced)]
         proxy_preset: ProxyPreset,
         #[arg(long)]
@@ -225,9 +689,19 @@ pub enum Commands {
         proxy_fps: Option<u32>,
         #[arg(long)]
         proxy_crf: Option<u8>,
+        /// Override the loop-friendly proxy length derived from the scene motion
+        #[arg(long)]
+        proxy_seconds: Option<f32>,
+        /// Cadena ffmpeg extra para el proxy: `pre=...`, `post=...` o solo la cadena (post). Sustituye a `custom_filters` de overrides.json
+        #[arg(long = "custom-filter")]
+        custom_filters: Vec<String>,
         #[arg(long)]
         no_proxy_optimize: bool,
         #[arg(long)]
+        warm_start: bool,
+        #[arg(long)]
+        explain: bool,
+        #[arg(long)]
         dry_run: bool,
     },
     #[command(about = "Reproduce un archivo de video como live wallpaper")]
@@ -243,7 +717,11 @@ pub enum Commands {
         services: Vec<String>,
         #[arg(long)]
         mute_audio: bool,
-        #[arg(long, value_enum, default_value_t = PlaybackProfile::Quality)]
+        #[arg(
+            long,
+            value_parser = parse_playback_profile,
+            default_value_t = PlaybackProfile::Quality
+        )]
         profile: PlaybackProfile,
         #[arg(long)]
         display_fps: Option<u32>,
@@ -261,6 +739,9 @@ pub enum Commands {
         proxy_fps: u32,
         #[arg(long, default_value_t = 16)]
         proxy_crf: u8,
+        /// Recorta y reescala la salida a x,y,w,h (pixeles del video o fracciones 0..1)
+        #[arg(long, value_parser = parse_crop_region)]
+        crop_region: Option<CropRegion>,
         #[arg(long)]
         dry_run: bool,
     },
@@ -271,6 +752,26 @@ pub enum Commands {
         #[arg(long, default_value_t = 2)]
         seconds: u64,
     },
+    #[command(
+        about = "Pasa una senal sintetica por el pipeline de audio (bandas, barras y uniforms)"
+    )]
+    AudioTest {
+        #[arg(long, value_enum, default_value_t = AudioTestPattern::Sweep)]
+        pattern: AudioTestPattern,
+        #[arg(long, default_value_t = 10)]
+        seconds: u64,
+        #[arg(long, default_value_t = 50)]
+        frame_ms: u64,
+        /// Numero de barras del espectro
+        #[arg(long, default_value_t = 32)]
+        bands: usize,
+        /// Carpeta donde guardar los frames PNG del overlay de barras
+        #[arg(long)]
+        frames_out: Option<PathBuf>,
+        /// Tamano de cada frame del overlay (ANCHOxALTO)
+        #[arg(long, default_value = "512x128")]
+        frame_size: String,
+    },
     #[command(about = "Lee stream de audio en tiempo real para analisis/debug")]
     AudioStream {
         #[arg(long)]
@@ -294,6 +795,105 @@ pub enum Commands {
         #[arg(long)]
         dry_run: bool,
     },
+    #[command(about = "Consulta los servicios en conflicto (sin detener nada)")]
+    Services {
+        #[command(subcommand)]
+        command: ServicesCommands,
+    },
+    #[command(about = "Wallpaper secundario picture-in-picture sobre un monitor")]
+    OverlayWallpaper {
+        #[command(subcommand)]
+        command: OverlayWallpaperCommands,
+    },
+    #[command(about = "Graba lo que muestra el wallpaper de un monitor (sin cursor ni ventanas)")]
+    Record {
+        #[arg(long)]
+        monitor: String,
+        #[arg(long, default_value_t = 10.0)]
+        seconds: f64,
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(about = "Sincroniza el wallpaper con otras maquinas (opt-in, TCP en la red local)")]
+    PeerSync {
+        /// Identificador de este nodo (por defecto el hostname)
+        #[arg(long)]
+        node_id: Option<String>,
+        /// El nodo alcanzable con mayor prioridad es el lider
+        #[arg(long, default_value_t = 0)]
+        priority: i32,
+        /// Direccion de escucha; fuera de loopback requiere --public
+        #[arg(long, default_value = "127.0.0.1:47415")]
+        listen: SocketAddr,
+        /// Permite escuchar en la red (requiere KWE_SYNC_SECRET en cada nodo)
+        #[arg(long)]
+        public: bool,
+        /// host:puerto de otro nodo (repetible)
+        #[arg(long = "peer")]
+        peers: Vec<String>,
+        /// Monitores locales que siguen al lider (por defecto los del lider)
+        #[arg(long = "monitor")]
+        monitors: Vec<String>,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        #[arg(long, default_value_t = 5)]
+        interval_seconds: u64,
+        /// Consulta a los peers una vez, sin escuchar, y sale
+        #[arg(long)]
+        once: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(
+        about = "Mantiene sincronizados los monitores que reproducen el mismo contenido (IPC de mpv)"
+    )]
+    PlaybackSync {
+        /// Monitor a sincronizar (repetible); el primero de cada grupo es el lider
+        #[arg(long = "monitor", required = true)]
+        monitors: Vec<String>,
+        /// Intervalo entre correcciones
+        #[arg(long, default_value_t = 250)]
+        interval_ms: u64,
+        /// Hace una sola correccion, imprime las muestras y sale
+        #[arg(long)]
+        once: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(about = "Vuelve a aplicar el wallpaper anterior de un monitor")]
+    Back {
+        #[arg(long)]
+        monitor: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(about = "Lista el historial de wallpapers aplicados por monitor")]
+    History {
+        /// Sin --monitor lista todos los monitores
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+    #[command(about = "Consulta el registro de eventos (aplicaciones, fallbacks, proxies)")]
+    Journal {
+        #[command(subcommand)]
+        command: JournalCommands,
+    },
+    #[command(
+        about = "Muestra la version; con --json, funciones, herramientas, transportes y backends"
+    )]
+    Version {
+        /// Informe completo en JSON para reportes de errores y frontends
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(about = "Migra la cache al formato actual (se hace solo en la primera ejecucion)")]
+    CacheMigrate {
+        /// Solo lista lo que se eliminaria
+        #[arg(long)]
+        dry_run: bool,
+    },
     #[command(about = "Aplica un wallpaper (auto: escena o video segun entrada)")]
     Apply {
         wallpaper: String,
@@ -307,12 +907,19 @@ pub enum Commands {
         services: Vec<String>,
         #[arg(long)]
         mute_audio: bool,
-        #[arg(long, value_enum, default_value_t = PlaybackProfile::Balanced)]
-        profile: PlaybackProfile,
+        /// Por defecto el de la regla de `profile_rules` que coincida, o balanced
+        #[arg(long, value_parser = parse_playback_profile)]
+        profile: Option<PlaybackProfile>,
         #[arg(long)]
         display_fps: Option<u32>,
         #[arg(long)]
         allow_scene_preview_fallback: bool,
+        /// Como llenar una salida con otra relacion de aspecto (por defecto fit)
+        #[arg(long, value_enum)]
+        scaling: Option<Scaling>,
+        /// Recorta la zona mas interesante del visual hasta la relacion de aspecto de la salida
+        #[arg(long)]
+        smart_crop: bool,
         #[arg(long)]
         dry_run: bool,
     },
@@ -333,7 +940,11 @@ pub enum ConfigCommands {
         keep_services: bool,
         #[arg(long)]
         mute_audio: bool,
-        #[arg(long, value_enum, default_value_t = PlaybackProfile::Performance)]
+        #[arg(
+            long,
+            value_parser = parse_playback_profile,
+            default_value_t = PlaybackProfile::Performance
+        )]
         profile: PlaybackProfile,
         #[arg(long)]
         display_fps: Option<u32>,
@@ -367,7 +978,11 @@ pub enum ConfigCommands {
         keep_services: bool,
         #[arg(long)]
         mute_audio: bool,
-        #[arg(long, value_enum, default_value_t = PlaybackProfile::Balanced)]
+        #[arg(
+            long,
+            value_parser = parse_playback_profile,
+            default_value_t = PlaybackProfile::Balanced
+        )]
         profile: PlaybackProfile,
         #[arg(long)]
         display_fps: Option<u32>,
@@ -376,6 +991,50 @@ pub enum ConfigCommands {
         #[arg(long, default_value_os_t = default_config_path())]
         config: PathBuf,
     },
+    /// Guarda preferencias de overlays globales o por wallpaper
+    #[command(about = "Guarda preferencias de overlays globales o por wallpaper")]
+    Overlay {
+        /// Sin `--wallpaper` se cambia el valor global
+        #[arg(long)]
+        wallpaper: Option<String>,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        #[arg(long)]
+        text_overlay: Option<bool>,
+        #[arg(long)]
+        fallback_clock: Option<bool>,
+        /// Zona horaria IANA de los relojes y fechas, p. ej. Europe/Madrid
+        #[arg(long, value_parser = parse_timezone)]
+        timezone: Option<Tz>,
+        /// Como se dibuja el texto: filtro drawtext o OSD de mpv actualizado por IPC
+        #[arg(long, value_enum)]
+        text_mode: Option<TextRenderMode>,
+        /// Fondo de los videos con transparencia: color (black, #203040) o wallpaper/archivo
+        #[arg(long)]
+        alpha_background: Option<String>,
+        /// Elimina las preferencias guardadas (del wallpaper o globales)
+        #[arg(long)]
+        clear: bool,
+        #[arg(long, default_value_os_t = default_config_path())]
+        config: PathBuf,
+    },
+    /// Guarda la capa y el namespace layer-shell de un monitor
+    #[command(about = "Guarda la capa y el namespace layer-shell de un monitor")]
+    Layer {
+        #[arg(long)]
+        monitor: String,
+        /// Capa de la superficie: background (por defecto de mpvpaper) o bottom
+        #[arg(long, value_enum)]
+        layer: Option<LayerShellLayer>,
+        /// Namespace layer-shell (solo si mpvpaper soporta --namespace)
+        #[arg(long)]
+        namespace: Option<String>,
+        /// Elimina la capa y el namespace guardados del monitor
+        #[arg(long)]
+        clear: bool,
+        #[arg(long, default_value_os_t = default_config_path())]
+        config: PathBuf,
+    },
     /// Elimina la configuracion de un monitor
     #[command(about = "Elimina la configuracion de un monitor")]
     Remove {
@@ -392,6 +1051,389 @@ pub enum ConfigCommands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum GreeterCommands {
+    /// Renderiza el wallpaper y genera la config minima del greeter
+    #[command(about = "Renderiza el wallpaper y genera la config minima del greeter")]
+    Install {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, value_enum, default_value_t = GreeterTarget::Greetd)]
+        target: GreeterTarget,
+        #[arg(long, value_enum, default_value_t = GreeterMode::Static)]
+        mode: GreeterMode,
+        #[arg(long, default_value = "1920x1080")]
+        size: String,
+        #[arg(long, default_value_os_t = crate::greeter::default_greeter_dir())]
+        dest: PathBuf,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        #[arg(long)]
+        overwrite: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PropertyCommands {
+    /// Guarda el override y lo aplica en vivo via mpv IPC cuando es posible
+    #[command(about = "Guarda el override y lo aplica en vivo via mpv IPC cuando es posible")]
+    Set {
+        #[arg(long)]
+        monitor: String,
+        key: String,
+        value: String,
+        /// Duracion de la transicion en vivo en ms (0 = salto; por defecto property_transition_ms o 400)
+        #[arg(long)]
+        transition_ms: Option<u32>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Muestra los overrides guardados
+    #[command(about = "Muestra los overrides guardados")]
+    List {
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+    /// Elimina un override (o todos los del monitor)
+    #[command(about = "Elimina un override (o todos los del monitor)")]
+    Clear {
+        #[arg(long)]
+        monitor: String,
+        key: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorkshopCommands {
+    /// Descarga los items faltantes de una coleccion y escanea los nuevos
+    #[command(about = "Descarga los items faltantes de una coleccion y escanea los nuevos")]
+    SyncCollection {
+        collection_id: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        /// Usuario de steamcmd (los items de Wallpaper Engine suelen requerir una cuenta con el juego)
+        #[arg(long, default_value = "anonymous")]
+        steam_user: String,
+        /// Elimina items que una sincronizacion anterior descargo y ya no estan en la coleccion
+        #[arg(long)]
+        prune: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SceneShaderCommands {
+    /// Copia shaders vert/frag e includes con sus defines y uniforms a un index.json
+    #[command(
+        about = "Copia shaders vert/frag e includes con sus defines y uniforms a un index.json"
+    )]
+    Dump {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SceneUniformCommands {
+    /// Reconstruye el plan de efectos en tiempo real con uniforms inyectados, sin tocar la reproduccion
+    #[command(
+        about = "Reconstruye el plan de efectos en tiempo real con uniforms inyectados, sin tocar la reproduccion"
+    )]
+    Eval {
+        #[arg(long)]
+        wallpaper: String,
+        /// Uniform a inyectar, p.ej. g_FlowSpeed=2 (repetible)
+        #[arg(long = "set", value_parser = parse_uniform_assignment)]
+        set: Vec<(String, serde_json::Value)>,
+        /// Imprime solo el filter_complex resultante
+        #[arg(long)]
+        print_filter: bool,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OverlayPlanCommands {
+    /// Muestra el plan de overlays actual de un monitor
+    #[command(about = "Muestra el plan de overlays actual de un monitor")]
+    Show {
+        #[arg(long)]
+        monitor: String,
+    },
+    /// Emite una linea JSON por cada cambio del plan (todos los monitores si no se indica)
+    #[command(
+        about = "Emite una linea JSON por cada cambio del plan (todos los monitores si no se indica)"
+    )]
+    Watch {
+        #[arg(long)]
+        monitor: Option<String>,
+        /// Intervalo de sondeo en milisegundos
+        #[arg(long, default_value_t = 500)]
+        interval_ms: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProxyCommands {
+    /// Muestra titulo, workshop id, version del generador y hash de ajustes de un proxy
+    #[command(
+        about = "Muestra titulo, workshop id, version del generador y hash de ajustes de un proxy"
+    )]
+    Info {
+        /// Archivo de proxy (mp4/mov/webm)
+        file: PathBuf,
+    },
+    /// Invalida los proxies en cache para que se regeneren con esta version del motor
+    #[command(
+        about = "Invalida los proxies en cache para que se regeneren con esta version del motor"
+    )]
+    Invalidate {
+        /// Solo los proxies de este wallpaper (workshop id o carpeta)
+        #[arg(long, conflicts_with = "all", required_unless_present = "all")]
+        wallpaper: Option<String>,
+        /// Todos los proxies de la cache
+        #[arg(long)]
+        all: bool,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        /// Solo los generados por una version anterior (o sin metadatos)
+        #[arg(long)]
+        only_outdated: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// Empaqueta config, overrides de propiedades, quirks y colecciones del Workshop
+    #[command(
+        about = "Empaqueta config, overrides de propiedades, quirks y colecciones del Workshop"
+    )]
+    Export {
+        /// Archivo de salida (p. ej. my-setup.tar.zst)
+        out: PathBuf,
+        /// Incluye tambien los videos/imagenes locales (no Workshop) usados por la config
+        #[arg(long)]
+        include_media: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Restaura un perfil exportado (los archivos reemplazados quedan como .bak)
+    #[command(about = "Restaura un perfil exportado (los archivos reemplazados quedan como .bak)")]
+    Import {
+        archive: PathBuf,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfilesCommands {
+    /// Lista presets y perfiles propios con sus ajustes, marcando los invalidos
+    #[command(about = "Lista presets y perfiles propios con sus ajustes, marcando los invalidos")]
+    List {
+        #[arg(long, default_value_os_t = default_config_path())]
+        config: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Empaqueta diagnosticos, versiones, eventos recientes y el manifest de sesion (rutas ocultas)
+    #[command(about = "Empaqueta los diagnosticos de un wallpaper en un .tar.gz para un issue")]
+    Bundle {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        /// Archivo de salida (p. ej. report.tar.gz)
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LayersCommands {
+    /// Muestra objetos y efectos de la escena con sus ids, marcando los omitidos
+    #[command(about = "Muestra objetos y efectos de la escena con sus ids")]
+    List {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+    /// Excluye objetos o efectos del grafo y la emulacion de este wallpaper
+    #[command(about = "Excluye objetos o efectos del grafo y la emulacion de este wallpaper")]
+    Skip {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        /// Id numerico o nombre del objeto (repetible)
+        #[arg(long = "object")]
+        objects: Vec<String>,
+        /// Archivo de efecto o su carpeta, p. ej. effects/shake (repetible)
+        #[arg(long = "effect")]
+        effects: Vec<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Vuelve a incluir objetos o efectos (sin argumentos, todos los del wallpaper)
+    #[command(about = "Vuelve a incluir objetos o efectos omitidos")]
+    Clear {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        #[arg(long = "object")]
+        objects: Vec<String>,
+        #[arg(long = "effect")]
+        effects: Vec<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScenePkgCommands {
+    /// Escribe nombres, offsets y tamanos de cada entrada, con los problemas detectados
+    #[command(about = "Exporta la tabla de entradas del paquete como JSON")]
+    Table {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        /// Archivo de salida (sin el, la tabla va a stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Reconstruye el paquete leyendo cada entrada en el offset de la tabla editada
+    #[command(about = "Reconstruye un paquete nuevo a partir de una tabla editada")]
+    Import {
+        #[arg(long)]
+        wallpaper: String,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        /// Tabla JSON exportada con `scene-pkg table`
+        #[arg(long)]
+        table: PathBuf,
+        /// Paquete a escribir; nunca se sobrescribe el original
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum JournalCommands {
+    /// Muestra los eventos registrados, del mas antiguo al mas reciente
+    #[command(about = "Muestra los eventos registrados, del mas antiguo al mas reciente")]
+    Show {
+        /// Antiguedad maxima: 30m, 12h, 1d, 2w
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        monitor: Option<String>,
+        #[arg(long, value_enum)]
+        kind: Option<JournalKind>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ServicesCommands {
+    /// Muestra que servicios/procesos en conflicto estan corriendo
+    #[command(about = "Muestra que servicios/procesos en conflicto estan corriendo")]
+    Status {
+        /// Unit de systemd o `process:<nombre>`; por defecto la lista configurada
+        #[arg(long = "service")]
+        services: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CompatCommands {
+    /// Cruza el plan nativo con el log de linux-wallpaperengine del mismo wallpaper
+    #[command(about = "Cruza el plan nativo con el log de linux-wallpaperengine")]
+    Compare {
+        #[arg(long)]
+        wallpaper: String,
+        /// Salida/log de linux-wallpaperengine reproduciendo ese wallpaper
+        #[arg(long)]
+        other_log: PathBuf,
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OverlayWallpaperCommands {
+    /// Compone un segundo wallpaper en un rectangulo del monitor
+    #[command(about = "Compone un segundo wallpaper en un rectangulo del monitor")]
+    Add {
+        #[arg(long)]
+        monitor: String,
+        #[arg(long)]
+        wallpaper: String,
+        /// x,y,w,h en porcentaje (70%,70%,30%,30%) o fraccion del monitor
+        #[arg(long, value_parser = parse_pip_rect, default_value = "70%,70%,30%,30%")]
+        rect: [f32; 4],
+        #[arg(long, default_value_os_t = default_downloads_root())]
+        downloads_root: PathBuf,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Quita un overlay (o todos los del monitor sin --id)
+    #[command(about = "Quita un overlay (o todos los del monitor sin --id)")]
+    Remove {
+        #[arg(long)]
+        monitor: String,
+        #[arg(long)]
+        id: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Congela el overlay en su primer frame sin tocar el wallpaper principal
+    #[command(about = "Congela el overlay sin tocar el wallpaper principal")]
+    Pause {
+        #[arg(long)]
+        monitor: String,
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Reanuda un overlay pausado
+    #[command(about = "Reanuda un overlay pausado")]
+    Resume {
+        #[arg(long)]
+        monitor: String,
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Lista los overlays guardados por monitor
+    #[command(about = "Lista los overlays guardados por monitor")]
+    List {
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum ServiceAutostartCommands {
     /// Instala unit file de systemd --user para autostart
@@ -425,16 +1467,13 @@ pub enum ServiceAutostartCommands {
     Status,
 }
 
-fn default_downloads_root() -> PathBuf {
-    if let Ok(home) = env::var("HOME") {
-        return PathBuf::from(home).join(".local/share/kitsune/we/downloads");
+pub(crate) fn default_downloads_root() -> PathBuf {
+    if let Some(data) = app_paths::data_home() {
+        return data.join("kitsune/we/downloads");
     }
     PathBuf::from(".")
 }
 
 fn default_config_path() -> PathBuf {
-    if let Ok(home) = env::var("HOME") {
-        return PathBuf::from(home).join(".config/kitsune-livewallpaper/config.json");
-    }
-    PathBuf::from("config.json")
+    app_paths::user_config_path()
 }
@@ -0,0 +1,109 @@
+use crate::cli::PlaybackProfile;
+use std::process::Command;
+
+/// RGB to limited-range BT.709 4:2:0. A plain `format=yuv420p` converts with
+/// the BT.601 matrix, which players read back as BT.709 for HD sizes and
+/// shift every hue.
+pub const YUV420P_BT709: &str = "scale=out_color_matrix=bt709:out_range=tv,format=yuv420p";
+
+/// Stream tags for video made from sRGB pixels (scene textures, PNG frames,
+/// GIF palettes): BT.709 primaries and matrix, sRGB transfer, limited range.
+pub const SRGB_BT709_TAGS: [&str; 8] = [
+    "-colorspace",
+    "bt709",
+    "-color_primaries",
+    "bt709",
+    "-color_trc",
+    "iec61966-2-1",
+    "-color_range",
+    "tv",
+];
+
+/// Adds [`SRGB_BT709_TAGS`] to an ffmpeg encode.
+pub fn tag_srgb_output(cmd: &mut Command) -> &mut Command {
+    cmd.args(SRGB_BT709_TAGS)
+}
+
+/// mpv output color options for `profile`. `performance` leaves mpv's
+/// defaults alone; the others pin an sRGB target so tagged proxies show the
+/// texture values Wallpaper Engine draws.
+pub fn mpv_color_options(profile: PlaybackProfile) -> &'static [&'static str] {
+    match profile {
+        PlaybackProfile::Performance => &[],
+        PlaybackProfile::Balanced | PlaybackProfile::Quality | PlaybackProfile::Custom(_) => {
+            &["target-prim=bt.709", "target-trc=srgb"]
+        }
+    }
+}
+
+fn png_crc(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Adds an `sRGB` chunk right after `IHDR` so decoders (ffmpeg included)
+/// treat extracted texture pixels as sRGB. PNGs that already carry color
+/// information (`sRGB`, `iCCP`, `gAMA`, `cHRM`) are returned unchanged.
+pub fn tag_png_srgb(png: Vec<u8>) -> Vec<u8> {
+    // Signature (8) + IHDR length, type, 13 data bytes and CRC.
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+    if png.len() < IHDR_END || &png[12..16] != b"IHDR" {
+        return png;
+    }
+    let mut at = 8;
+    while at + 8 <= png.len() {
+        let len = u32::from_be_bytes([png[at], png[at + 1], png[at + 2], png[at + 3]]) as usize;
+        let kind = &png[at + 4..at + 8];
+        if matches!(kind, b"sRGB" | b"iCCP" | b"gAMA" | b"cHRM") {
+            return png;
+        }
+        if kind == b"IDAT" {
+            break;
+        }
+        at += 12 + len;
+    }
+    // Rendering intent 0: perceptual.
+    let chunk = [b's', b'R', b'G', b'B', 0];
+    let mut out = Vec::with_capacity(png.len() + 13);
+    out.extend_from_slice(&png[..IHDR_END]);
+    out.extend_from_slice(&1u32.to_be_bytes());
+    out.extend_from_slice(&chunk);
+    out.extend_from_slice(&png_crc(&chunk).to_be_bytes());
+    out.extend_from_slice(&png[IHDR_END..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    #[test]
+    fn png_gets_an_srgb_chunk_and_stays_decodable() {
+        let mut png = Vec::new();
+        RgbImage::from_pixel(2, 2, image::Rgb([200, 100, 50]))
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let tagged = tag_png_srgb(png.clone());
+        assert_eq!(tagged.len(), png.len() + 13);
+        assert_eq!(&tagged[37..41], b"sRGB");
+        assert_eq!(png_crc(b"IEND"), 0xAE42_6082);
+        let decoded = image::load_from_memory(&tagged).unwrap().to_rgb8();
+        assert_eq!(decoded.get_pixel(1, 1).0, [200, 100, 50]);
+        // Already tagged: left alone.
+        assert_eq!(tag_png_srgb(tagged.clone()), tagged);
+
+        assert!(mpv_color_options(PlaybackProfile::Performance).is_empty());
+        assert!(mpv_color_options(PlaybackProfile::Quality).contains(&"target-trc=srgb"));
+    }
+}
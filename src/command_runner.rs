@@ -0,0 +1,281 @@
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus, Output};
+use std::rc::Rc;
+
+/// Indirection over `std::process::Command` so orchestration code can be
+/// exercised without ffmpeg/mpvpaper/Wayland. Production code runs commands
+/// for real; tests install a [`MockRunner`] for the current thread with
+/// [`with_runner`] and assert on the recorded command lines.
+pub trait CommandRunner {
+    fn output(&self, cmd: &mut Command) -> io::Result<Output>;
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus>;
+    /// Starts a detached process; callers never wait on it.
+    fn spawn_detached(&self, cmd: &mut Command) -> io::Result<()>;
+    /// Starts a background process and returns its pid, for callers that
+    /// check on it or stop it later.
+    fn spawn_pid(&self, cmd: &mut Command) -> io::Result<u32>;
+}
+
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn output(&self, cmd: &mut Command) -> io::Result<Output> {
+        cmd.output()
+    }
+
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus> {
+        cmd.status()
+    }
+
+    fn spawn_detached(&self, cmd: &mut Command) -> io::Result<()> {
+        cmd.spawn().map(|_| ())
+    }
+
+    fn spawn_pid(&self, cmd: &mut Command) -> io::Result<u32> {
+        cmd.spawn().map(|child| child.id())
+    }
+}
+
+thread_local! {
+    static RUNNER: RefCell<Option<Rc<dyn CommandRunner>>> = const { RefCell::new(None) };
+}
+
+fn current_runner() -> Rc<dyn CommandRunner> {
+    RUNNER
+        .with(|r| r.borrow().clone())
+        .unwrap_or_else(|| Rc::new(SystemRunner))
+}
+
+/// Runs `f` with `runner` handling every command started on this thread.
+pub fn with_runner<R>(runner: Rc<dyn CommandRunner>, f: impl FnOnce() -> R) -> R {
+    let previous = RUNNER.with(|r| r.borrow_mut().replace(runner));
+    let result = f();
+    RUNNER.with(|r| *r.borrow_mut() = previous);
+    result
+}
+
+/// Drop-in replacements for `output()`/`status()`/`spawn()` that go through
+/// the thread's [`CommandRunner`].
+pub trait CommandExt {
+    fn run_output(&mut self) -> io::Result<Output>;
+    fn run_status(&mut self) -> io::Result<ExitStatus>;
+    fn spawn_detached(&mut self) -> io::Result<()>;
+    fn spawn_pid(&mut self) -> io::Result<u32>;
+}
+
+impl CommandExt for Command {
+    fn run_output(&mut self) -> io::Result<Output> {
+        current_runner().output(self)
+    }
+
+    fn run_status(&mut self) -> io::Result<ExitStatus> {
+        current_runner().status(self)
+    }
+
+    fn spawn_detached(&mut self) -> io::Result<()> {
+        current_runner().spawn_detached(self)
+    }
+
+    fn spawn_pid(&mut self) -> io::Result<u32> {
+        current_runner().spawn_pid(self)
+    }
+}
+
+/// `program arg1 arg2 ...` as it would be typed in a shell (without quoting).
+pub fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|s| s.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `arg` as a POSIX shell word: bare when it only has safe characters,
+/// single-quoted otherwise. Bytes that are not UTF-8 are written as
+/// `$'\xNN'` so a printed line still names the exact file.
+pub fn shell_quote(arg: &OsStr) -> String {
+    let bytes = arg.as_bytes();
+    let safe = |b: &u8| b.is_ascii_alphanumeric() || b"_@%+=:,./-".contains(b);
+    if !bytes.is_empty() && bytes.iter().all(safe) {
+        return arg.to_string_lossy().to_string();
+    }
+    if let Some(text) = arg.to_str() {
+        return format!("'{}'", text.replace('\'', r"'\''"));
+    }
+    let mut out = String::from("$'");
+    for &b in bytes {
+        match b {
+            b'\'' | b'\\' => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Like [`command_line`], with every word quoted so the line can be pasted
+/// into a shell as is. Used by `--dry-run` output.
+pub fn shell_command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub success: bool,
+    pub stdout: String,
+}
+
+/// Records command lines instead of running them. Every command succeeds
+/// with empty output unless a canned response matches its program name.
+#[derive(Default)]
+pub struct MockRunner {
+    pub recorded: RefCell<Vec<String>>,
+    /// The same commands as [`shell_command_line`], which keeps non-UTF-8
+    /// arguments byte for byte.
+    pub recorded_quoted: RefCell<Vec<String>>,
+    responses: RefCell<Vec<(String, MockResponse)>>,
+    /// Create the last argument as an empty file (ffmpeg-style output path)
+    /// so callers that check for the output see it.
+    pub touch_outputs: bool,
+}
+
+impl MockRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn touching_outputs() -> Self {
+        Self {
+            touch_outputs: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn respond(&self, program: &str, success: bool, stdout: &str) {
+        self.responses.borrow_mut().push((
+            program.to_string(),
+            MockResponse {
+                success,
+                stdout: stdout.to_string(),
+            },
+        ));
+    }
+
+    pub fn commands(&self) -> Vec<String> {
+        self.recorded.borrow().clone()
+    }
+
+    pub fn quoted_commands(&self) -> Vec<String> {
+        self.recorded_quoted.borrow().clone()
+    }
+
+    fn record(&self, cmd: &Command) -> MockResponse {
+        self.recorded.borrow_mut().push(command_line(cmd));
+        self.recorded_quoted
+            .borrow_mut()
+            .push(shell_command_line(cmd));
+        if self.touch_outputs
+            && let Some(last) = cmd.get_args().last()
+            && last.to_string_lossy().starts_with('/')
+            && std::path::Path::new(last)
+                .parent()
+                .is_some_and(|p| p.is_dir())
+        {
+            let _ = std::fs::write(last, b"");
+        }
+        let program = cmd.get_program().to_string_lossy().to_string();
+        self.responses
+            .borrow()
+            .iter()
+            .find(|(p, _)| *p == program)
+            .map(|(_, r)| r.clone())
+            .unwrap_or(MockResponse {
+                success: true,
+                stdout: String::new(),
+            })
+    }
+}
+
+fn exit_status(success: bool) -> ExitStatus {
+    // Raw wait status: exit code lives in the high byte.
+    ExitStatus::from_raw(if success { 0 } else { 1 << 8 })
+}
+
+impl CommandRunner for MockRunner {
+    fn output(&self, cmd: &mut Command) -> io::Result<Output> {
+        let response = self.record(cmd);
+        Ok(Output {
+            status: exit_status(response.success),
+            stdout: response.stdout.into_bytes(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus> {
+        Ok(exit_status(self.record(cmd).success))
+    }
+
+    fn spawn_detached(&self, cmd: &mut Command) -> io::Result<()> {
+        self.record(cmd);
+        Ok(())
+    }
+
+    /// A pid no process can have, so liveness checks see it as exited.
+    fn spawn_pid(&self, cmd: &mut Command) -> io::Result<u32> {
+        self.record(cmd);
+        Ok(u32::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_records_and_restores_previous_runner() {
+        let mock = Rc::new(MockRunner::new());
+        mock.respond("pgrep", false, "");
+        let status = with_runner(mock.clone(), || {
+            Command::new("ffmpeg")
+                .arg("-y")
+                .arg("/tmp/x.mp4")
+                .run_output()
+                .unwrap();
+            Command::new("pgrep").arg("-fa").run_status().unwrap()
+        });
+        assert!(!status.success());
+        assert_eq!(mock.commands(), vec!["ffmpeg -y /tmp/x.mp4", "pgrep -fa"]);
+        assert!(RUNNER.with(|r| r.borrow().is_none()));
+    }
+
+    #[test]
+    fn dry_run_lines_quote_adversarial_file_names() {
+        let mut cmd = Command::new("mpvpaper");
+        cmd.arg("-o")
+            .arg("profile=fast no-audio")
+            .arg("DP-1")
+            .arg("/lib/12 3/it's \"$HOME\".mp4")
+            .arg(OsStr::from_bytes(b"/lib/caf\xe9's.webm"))
+            .arg("");
+        assert_eq!(
+            shell_command_line(&cmd),
+            r#"mpvpaper -o 'profile=fast no-audio' DP-1 '/lib/12 3/it'\''s "$HOME".mp4' $'/lib/caf\xe9\'s.webm' ''"#
+        );
+        assert_eq!(
+            shell_quote(OsStr::new("/tmp/ビデオ 1.mp4")),
+            "'/tmp/ビデオ 1.mp4'"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_native_runtime::{
+    NativeRuntimePlan, NativeSupportTier, build_native_runtime_plan,
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompatSupport {
+    Supported,
+    Partial,
+    Unsupported,
+}
+
+/// One scene object as seen by both engines.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatObject {
+    pub object_index: usize,
+    pub object_id: u64,
+    pub object_name: String,
+    pub kind: String,
+    pub effects: Vec<String>,
+    pub this_crate: CompatSupport,
+    /// Shader families this crate cannot draw natively for the object.
+    pub this_crate_gaps: Vec<String>,
+    pub other: CompatSupport,
+    /// Lines of the other engine's log blaming this object.
+    pub other_messages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompatSummary {
+    pub both_supported: usize,
+    pub only_this_crate: usize,
+    pub only_other: usize,
+    pub neither: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatComparison {
+    pub wallpaper: String,
+    pub other_log: String,
+    pub summary: CompatSummary,
+    /// Shader families of objects the other engine draws and this crate does
+    /// not, by object count: where porting work pays off first.
+    pub invest: BTreeMap<String, usize>,
+    pub objects: Vec<CompatObject>,
+    /// Problem lines of the other log that name no object of the scene.
+    pub unmatched_other_messages: Vec<String>,
+}
+
+/// Objects of the native plan with this crate's verdict; `other` starts as
+/// supported until the log says otherwise.
+fn compat_objects(
+    graph_kinds: &BTreeMap<usize, (String, Vec<String>)>,
+    plan: &NativeRuntimePlan,
+) -> Vec<CompatObject> {
+    let mut objects = BTreeMap::<usize, CompatObject>::new();
+    let mut ready = BTreeMap::<usize, (usize, usize)>::new();
+    for pass in &plan.passes {
+        let (kind, effects) = graph_kinds
+            .get(&pass.object_index)
+            .cloned()
+            .unwrap_or_default();
+        let object = objects
+            .entry(pass.object_index)
+            .or_insert_with(|| CompatObject {
+                object_index: pass.object_index,
+                object_id: pass.object_id,
+                object_name: pass.object_name.clone(),
+                kind,
+                effects,
+                this_crate: CompatSupport::Supported,
+                this_crate_gaps: Vec::new(),
+                other: CompatSupport::Supported,
+                other_messages: Vec::new(),
+            });
+        let counts = ready.entry(pass.object_index).or_default();
+        counts.1 += 1;
+        if matches!(pass.tier, NativeSupportTier::Ready) {
+            counts.0 += 1;
+        } else if !object.this_crate_gaps.contains(&pass.shader_family) {
+            object.this_crate_gaps.push(pass.shader_family.clone());
+        }
+    }
+    for (index, (ready, total)) in ready {
+        if let Some(object) = objects.get_mut(&index) {
+            object.this_crate = match ready {
+                r if r == total => CompatSupport::Supported,
+                0 => CompatSupport::Unsupported,
+                _ => CompatSupport::Partial,
+            };
+        }
+    }
+    objects.into_values().collect()
+}
+
+/// Severity of a log line, `None` when it does not report a problem.
+fn problem_severity(line: &str) -> Option<CompatSupport> {
+    let lower = line.to_ascii_lowercase();
+    const ERRORS: [&str; 8] = [
+        "error",
+        "unsupported",
+        "not supported",
+        "not implemented",
+        "unknown",
+        "cannot",
+        "failed",
+        "missing",
+    ];
+    if ERRORS.iter().any(|k| lower.contains(k)) {
+        Some(CompatSupport::Unsupported)
+    } else if lower.contains("warn") || lower.contains("skipping") {
+        Some(CompatSupport::Partial)
+    } else {
+        None
+    }
+}
+
+fn mentions_object(line: &str, object: &CompatObject) -> bool {
+    let lower = line.to_ascii_lowercase();
+    let id = object.object_id.to_string();
+    let mentions_id = lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .collect::<Vec<_>>()
+        .windows(2)
+        .any(|w| matches!(w[0], "id" | "object" | "obj") && w[1] == id);
+    // Short names ("1", "bg") would match unrelated words.
+    let name = object.object_name.to_ascii_lowercase();
+    mentions_id
+        || (name.len() >= 3 && lower.contains(&name))
+        || object
+            .effects
+            .iter()
+            .any(|effect| lower.contains(&effect.to_ascii_lowercase()))
+}
+
+/// Marks objects blamed by `log` and returns the problem lines that match none.
+pub fn apply_other_log(objects: &mut [CompatObject], log: &str) -> Vec<String> {
+    let mut unmatched = Vec::new();
+    for line in log.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let Some(severity) = problem_severity(line) else {
+            continue;
+        };
+        let mut matched = false;
+        for object in objects.iter_mut().filter(|o| mentions_object(line, o)) {
+            matched = true;
+            if severity == CompatSupport::Unsupported || object.other == CompatSupport::Supported {
+                object.other = severity;
+            }
+            object.other_messages.push(line.to_string());
+        }
+        if !matched {
+            unmatched.push(line.to_string());
+        }
+    }
+    unmatched
+}
+
+pub fn summarize(objects: &[CompatObject]) -> (CompatSummary, BTreeMap<String, usize>) {
+    let mut summary = CompatSummary::default();
+    let mut invest = BTreeMap::<String, usize>::new();
+    for object in objects {
+        let ours = object.this_crate == CompatSupport::Supported;
+        let theirs = object.other == CompatSupport::Supported;
+        match (ours, theirs) {
+            (true, true) => summary.both_supported += 1,
+            (true, false) => summary.only_this_crate += 1,
+            (false, true) => {
+                summary.only_other += 1;
+                for family in &object.this_crate_gaps {
+                    *invest.entry(family.clone()).or_default() += 1;
+                }
+            }
+            (false, false) => summary.neither += 1,
+        }
+    }
+    (summary, invest)
+}
+
+/// Compares this crate's native plan for the scene at `root` with a
+/// linux-wallpaperengine log of the same wallpaper.
+pub fn compare_with_other_log(root: &Path, other_log: &Path) -> Result<CompatComparison> {
+    let log =
+        fs::read(other_log).with_context(|| format!("Failed reading {}", other_log.display()))?;
+    let graph = build_scene_gpu_graph(root)?;
+    let plan = build_native_runtime_plan(&graph);
+    let mut kinds = BTreeMap::<usize, (String, Vec<String>)>::new();
+    for node in &graph.effect_nodes {
+        let (_, effects) = kinds
+            .entry(node.object_index)
+            .or_insert_with(|| (node.object_kind.clone(), Vec::new()));
+        if node.effect_index.is_some() && !effects.contains(&node.effect_file) {
+            effects.push(node.effect_file.clone());
+        }
+    }
+    let mut objects = compat_objects(&kinds, &plan);
+    let unmatched_other_messages = apply_other_log(&mut objects, &String::from_utf8_lossy(&log));
+    let (summary, invest) = summarize(&objects);
+    Ok(CompatComparison {
+        wallpaper: root.display().to_string(),
+        other_log: other_log.display().to_string(),
+        summary,
+        invest,
+        objects,
+        unmatched_other_messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(
+        id: u64,
+        name: &str,
+        effects: &[&str],
+        ours: CompatSupport,
+        gaps: &[&str],
+    ) -> CompatObject {
+        CompatObject {
+            object_index: id as usize,
+            object_id: id,
+            object_name: name.to_string(),
+            kind: "image".to_string(),
+            effects: effects.iter().map(|e| e.to_string()).collect(),
+            this_crate: ours,
+            this_crate_gaps: gaps.iter().map(|g| g.to_string()).collect(),
+            other: CompatSupport::Supported,
+            other_messages: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn log_lines_are_attributed_and_gaps_ranked() {
+        let mut objects = vec![
+            object(1, "Background", &[], CompatSupport::Supported, &[]),
+            object(
+                2,
+                "Rain",
+                &["effects/waterripple/effect.json"],
+                CompatSupport::Unsupported,
+                &["waterripple"],
+            ),
+            object(3, "Logo", &[], CompatSupport::Partial, &["genericimage4"]),
+        ];
+        let log = "\
+[INFO] loading scene.json
+[ERROR] Cannot compile shader for effects/waterripple/effect.json
+[WARN] skipping object id 3: blend mode not handled
+[ERROR] Unknown object type 'sound'
+";
+        let unmatched = apply_other_log(&mut objects, log);
+        assert_eq!(unmatched, vec!["[ERROR] Unknown object type 'sound'"]);
+        assert_eq!(objects[0].other, CompatSupport::Supported);
+        assert_eq!(objects[1].other, CompatSupport::Unsupported);
+        assert_eq!(objects[2].other, CompatSupport::Partial);
+
+        objects[2].other = CompatSupport::Supported;
+        let (summary, invest) = summarize(&objects);
+        assert_eq!(summary.both_supported, 1);
+        assert_eq!(summary.neither, 1);
+        assert_eq!(summary.only_other, 1);
+        assert_eq!(invest.get("genericimage4"), Some(&1));
+    }
+}
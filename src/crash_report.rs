@@ -0,0 +1,188 @@
+use crate::app_paths::app_cache_dir;
+use crate::journal::{JournalEvent, recent_journal_events};
+use crate::version_info::{VersionReport, build_version_report_with};
+use crate::workshop::is_workshop_id;
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::Serialize;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+
+/// Reports kept in the crash dir; older ones are removed.
+const MAX_CRASH_REPORTS: usize = 20;
+/// Journal events copied into a report, newest last.
+const RECENT_EVENTS: usize = 20;
+
+/// Local record of a panic. Only written to disk, never sent anywhere.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    pub at: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub thread: String,
+    pub command: Vec<String>,
+    /// Workshop id or wallpaper folder named on the command line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallpaper: Option<String>,
+    pub os: String,
+    pub versions: VersionReport,
+    pub backtrace: Vec<String>,
+    pub recent_events: Vec<JournalEvent>,
+}
+
+/// `KWE_CRASH_REPORTS=0` turns the reports off; the panic is still printed
+/// and journaled.
+pub fn crash_reports_enabled() -> bool {
+    std::env::var("KWE_CRASH_REPORTS").ok().as_deref() != Some("0")
+}
+
+pub fn crash_reports_dir() -> PathBuf {
+    app_cache_dir().join("crashes")
+}
+
+/// First argument naming a wallpaper: a workshop id, or a folder with a
+/// `project.json`.
+pub fn wallpaper_from_args(args: &[String]) -> Option<String> {
+    args.iter()
+        .skip(1)
+        .find(|arg| is_workshop_id(arg) || Path::new(arg).join("project.json").is_file())
+        .cloned()
+}
+
+pub fn build_crash_report(
+    message: String,
+    location: Option<String>,
+    backtrace: &Backtrace,
+    args: Vec<String>,
+    versions: VersionReport,
+    recent_events: Vec<JournalEvent>,
+) -> CrashReport {
+    CrashReport {
+        at: Local::now().to_rfc3339(),
+        message,
+        location,
+        thread: std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string(),
+        wallpaper: wallpaper_from_args(&args),
+        command: args,
+        os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        versions,
+        backtrace: backtrace.to_string().lines().map(str::to_string).collect(),
+        recent_events,
+    }
+}
+
+/// Writes `report` as `crash-<time>-<pid>.json` in `dir`, keeping only the
+/// newest reports.
+pub fn write_crash_report(dir: &Path, report: &CrashReport) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed creating {}", dir.display()))?;
+    let path = dir.join(format!(
+        "crash-{}-{}.json",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    ));
+    fs::write(&path, serde_json::to_vec_pretty(report)?)
+        .with_context(|| format!("Failed writing {}", path.display()))?;
+
+    let mut reports = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with("crash-"))
+        })
+        .collect::<Vec<_>>();
+    reports.sort();
+    let excess = reports.len().saturating_sub(MAX_CRASH_REPORTS);
+    for old in &reports[..excess] {
+        fs::remove_file(old).ok();
+    }
+    Ok(path)
+}
+
+/// Newest report in the crash dir.
+pub fn latest_crash_report() -> Option<PathBuf> {
+    fs::read_dir(crash_reports_dir())
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with("crash-"))
+        })
+        .max()
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic with a non-string payload".to_string())
+}
+
+/// Crash report for the panic in `info`, written to the cache dir. Runs in
+/// the panic hook, so it spawns nothing: external tools are not probed
+/// (only the version and features are recorded) and only the journal's
+/// tail is read.
+pub fn write_panic_report(info: &PanicHookInfo) -> Result<PathBuf> {
+    let backtrace = Backtrace::force_capture();
+    let report = build_crash_report(
+        panic_message(info),
+        info.location().map(|l| l.to_string()),
+        &backtrace,
+        std::env::args().collect(),
+        build_version_report_with(Vec::new()),
+        recent_journal_events(RECENT_EVENTS),
+    );
+    write_crash_report(&crash_reports_dir(), &report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crash_reports_name_the_wallpaper_and_are_pruned() {
+        let args = [
+            "kitsune-livewallpaper",
+            "apply",
+            "2233445566",
+            "--monitor",
+            "DP-1",
+        ]
+        .map(str::to_string)
+        .to_vec();
+        let report = build_crash_report(
+            "index out of bounds".to_string(),
+            Some("src/scene_pkg.rs:120:5".to_string()),
+            &Backtrace::disabled(),
+            args,
+            build_version_report_with(Vec::new()),
+            Vec::new(),
+        );
+        assert_eq!(report.wallpaper.as_deref(), Some("2233445566"));
+        assert_eq!(report.versions.version, env!("CARGO_PKG_VERSION"));
+
+        let dir = tempfile::tempdir().unwrap();
+        for n in 0..MAX_CRASH_REPORTS {
+            fs::write(
+                dir.path().join(format!("crash-00000000-{:02}.json", n)),
+                "{}",
+            )
+            .unwrap();
+        }
+        let path = write_crash_report(dir.path(), &report).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["message"], "index out of bounds");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), MAX_CRASH_REPORTS);
+        assert!(!dir.path().join("crash-00000000-00.json").exists());
+    }
+}
@@ -0,0 +1,427 @@
+use crate::colorspace::YUV420P_BT709;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+/// Filters a chain may use, with the option keys each one accepts: pure
+/// per-frame video filters. Anything that can load files, plugins, sockets
+/// or commands (`movie`, `drawtext`, `lut3d`, `subtitles`, `frei0r`,
+/// `sendcmd`...) is left out, and so are the file options of allowed filters
+/// (`curves=psfile=`, `curves=plot=`), so a snippet from a shared
+/// `overrides.json` cannot reach outside the graph.
+const ALLOWED_FILTERS: [(&str, &[&str]); 33] = [
+    (
+        "boxblur",
+        &[
+            "luma_radius",
+            "lr",
+            "luma_power",
+            "lp",
+            "chroma_radius",
+            "cr",
+            "chroma_power",
+            "cp",
+            "alpha_radius",
+            "ar",
+            "alpha_power",
+            "ap",
+        ],
+    ),
+    ("chromashift", &["cbh", "cbv", "crh", "crv", "edge"]),
+    (
+        "colorbalance",
+        &["rs", "gs", "bs", "rm", "gm", "bm", "rh", "gh", "bh", "pl"],
+    ),
+    (
+        "colorchannelmixer",
+        &[
+            "rr", "rg", "rb", "ra", "gr", "gg", "gb", "ga", "br", "bg", "bb", "ba", "ar", "ag",
+            "ab", "aa", "pc", "pa",
+        ],
+    ),
+    (
+        "colorcontrast",
+        &["rc", "gm", "by", "rcw", "gmw", "byw", "pl"],
+    ),
+    (
+        "colorcorrect",
+        &["rl", "bl", "rh", "bh", "saturation", "analyze"],
+    ),
+    (
+        "colorlevels",
+        &[
+            "rimin", "gimin", "bimin", "aimin", "rimax", "gimax", "bimax", "aimax", "romin",
+            "gomin", "bomin", "aomin", "romax", "gomax", "bomax", "aomax", "preserve",
+        ],
+    ),
+    ("colortemperature", &["temperature", "mix", "pl"]),
+    (
+        "crop",
+        &["w", "out_w", "h", "out_h", "x", "y", "keep_aspect", "exact"],
+    ),
+    (
+        "curves",
+        &[
+            "preset", "master", "m", "red", "r", "green", "g", "blue", "b", "all", "interp",
+        ],
+    ),
+    (
+        "deband",
+        &[
+            "1thr",
+            "2thr",
+            "3thr",
+            "4thr",
+            "range",
+            "r",
+            "direction",
+            "d",
+            "blur",
+            "b",
+            "coupling",
+            "c",
+        ],
+    ),
+    ("edgedetect", &["high", "low", "mode", "planes"]),
+    (
+        "eq",
+        &[
+            "contrast",
+            "brightness",
+            "saturation",
+            "gamma",
+            "gamma_r",
+            "gamma_g",
+            "gamma_b",
+            "gamma_weight",
+            "eval",
+        ],
+    ),
+    (
+        "fade",
+        &[
+            "type",
+            "t",
+            "start_frame",
+            "s",
+            "nb_frames",
+            "n",
+            "alpha",
+            "start_time",
+            "st",
+            "duration",
+            "d",
+            "color",
+            "c",
+        ],
+    ),
+    ("format", &["pix_fmts"]),
+    ("gblur", &["sigma", "steps", "planes", "sigmaV"]),
+    ("gradfun", &["strength", "radius"]),
+    ("hflip", &[]),
+    (
+        "hqdn3d",
+        &["luma_spatial", "chroma_spatial", "luma_tmp", "chroma_tmp"],
+    ),
+    ("hue", &["h", "s", "H", "b"]),
+    ("lutrgb", &["r", "g", "b", "a", "c0", "c1", "c2", "c3"]),
+    ("lutyuv", &["y", "u", "v", "a", "c0", "c1", "c2", "c3"]),
+    ("negate", &["components", "negate_alpha"]),
+    (
+        "noise",
+        &[
+            "all_seed",
+            "all_strength",
+            "alls",
+            "all_flags",
+            "allf",
+            "c0_strength",
+            "c0s",
+            "c0_flags",
+            "c0f",
+            "c1_strength",
+            "c1s",
+            "c1_flags",
+            "c1f",
+            "c2_strength",
+            "c2s",
+            "c2_flags",
+            "c2f",
+        ],
+    ),
+    ("null", &[]),
+    (
+        "pad",
+        &[
+            "width", "w", "height", "h", "x", "y", "color", "eval", "aspect",
+        ],
+    ),
+    (
+        "rotate",
+        &[
+            "angle",
+            "a",
+            "out_w",
+            "ow",
+            "out_h",
+            "oh",
+            "bilinear",
+            "fillcolor",
+            "c",
+        ],
+    ),
+    (
+        "scale",
+        &[
+            "w",
+            "width",
+            "h",
+            "height",
+            "flags",
+            "interl",
+            "in_color_matrix",
+            "out_color_matrix",
+            "in_range",
+            "out_range",
+            "force_original_aspect_ratio",
+            "force_divisible_by",
+            "eval",
+        ],
+    ),
+    ("setsar", &["sar", "r", "max"]),
+    ("transpose", &["dir", "passthrough"]),
+    (
+        "unsharp",
+        &[
+            "luma_msize_x",
+            "lx",
+            "luma_msize_y",
+            "ly",
+            "luma_amount",
+            "la",
+            "chroma_msize_x",
+            "cx",
+            "chroma_msize_y",
+            "cy",
+            "chroma_amount",
+            "ca",
+            "alpha_msize_x",
+            "ax",
+            "alpha_msize_y",
+            "ay",
+            "alpha_amount",
+            "aa",
+        ],
+    ),
+    ("vflip", &[]),
+    (
+        "vignette",
+        &["angle", "a", "x0", "y0", "mode", "eval", "dither", "aspect"],
+    ),
+];
+
+/// Timeline option every filter of the list supports.
+const COMMON_OPTIONS: [&str; 1] = ["enable"];
+
+/// User ffmpeg filter chains spliced into the effect-proxy graph of one
+/// wallpaper: `pre` runs on the base image before the effects, `post` on the
+/// composited frame. `{width}`, `{height}` and `{time}` are replaced with the
+/// scene size and ffmpeg's `t`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CustomFilters {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post: Option<String>,
+}
+
+impl CustomFilters {
+    pub fn is_empty(&self) -> bool {
+        self.pre.is_none() && self.post.is_none()
+    }
+
+    /// `--custom-filter` values: `pre=<chain>`, `post=<chain>`, or a bare
+    /// chain (post). Later values for the same hook win.
+    pub fn from_cli(values: &[String]) -> Result<Option<Self>> {
+        let mut filters = Self::default();
+        for value in values {
+            let (hook, chain) = match value.split_once('=') {
+                Some(("pre", chain)) => (&mut filters.pre, chain),
+                Some(("post", chain)) => (&mut filters.post, chain),
+                _ => (&mut filters.post, value.as_str()),
+            };
+            *hook = Some(chain.to_string());
+        }
+        if filters.is_empty() {
+            return Ok(None);
+        }
+        filters.expanded(1920, 1080)?;
+        Ok(Some(filters))
+    }
+
+    /// Placeholders replaced and each chain validated.
+    pub fn expanded(&self, scene_w: u32, scene_h: u32) -> Result<Self> {
+        let expand = |chain: &Option<String>, hook: &str| -> Result<Option<String>> {
+            chain
+                .as_deref()
+                .map(|chain| {
+                    expand_filter_chain(chain, scene_w, scene_h)
+                        .map_err(|err| anyhow::anyhow!("custom {} filter: {}", hook, err))
+                })
+                .transpose()
+        };
+        Ok(Self {
+            pre: expand(&self.pre, "pre")?,
+            post: expand(&self.post, "post")?,
+        })
+    }
+}
+
+/// Splits on top-level `sep`, keeping separators inside quotes and escapes.
+fn split_unquoted(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\'' => quoted = !quoted,
+            c if c == sep && !quoted => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+pub fn expand_filter_chain(chain: &str, scene_w: u32, scene_h: u32) -> Result<String> {
+    let chain = chain
+        .trim()
+        .replace("{width}", &scene_w.to_string())
+        .replace("{height}", &scene_h.to_string())
+        .replace("{time}", "t");
+    if chain.is_empty() {
+        bail!("empty filter chain");
+    }
+    if let Some(c) = chain.chars().find(|c| ";[]{}".contains(*c)) {
+        bail!(
+            "'{}' is not allowed (one linear chain; placeholders are {{width}}, {{height}}, {{time}})",
+            c
+        );
+    }
+    for part in split_unquoted(&chain, ',') {
+        let (name, args) = part.split_once('=').unwrap_or((part, ""));
+        let name = name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            bail!("'{}' does not start with a filter name", part.trim());
+        }
+        let Some((_, keys)) = ALLOWED_FILTERS.iter().find(|(allowed, _)| *allowed == name) else {
+            bail!("filter '{}' is not allowed", name);
+        };
+        if args.trim().is_empty() {
+            continue;
+        }
+        // Positional values would reach options without naming them.
+        for opt in split_unquoted(args, ':') {
+            let Some((key, _)) = opt.split_once('=') else {
+                bail!(
+                    "positional value '{}' of '{}' is not allowed; write it as key=value",
+                    opt.trim(),
+                    name
+                );
+            };
+            let key = key.trim();
+            if !keys.contains(&key) && !COMMON_OPTIONS.contains(&key) {
+                bail!("option '{}' of '{}' is not allowed", key, name);
+            }
+        }
+    }
+    Ok(chain)
+}
+
+/// Runs `pre` on input `[0:v]` of a graph that consumes it once at the start.
+pub fn with_pre_filter(filter: String, pre: Option<&str>) -> String {
+    match (pre, filter.strip_prefix("[0:v]")) {
+        (Some(pre), Some(rest)) => format!("[0:v]{}[cfpre];[cfpre]{}", pre, rest),
+        _ => filter,
+    }
+}
+
+/// Runs `post` on the `[v]` output of a graph.
+pub fn with_post_filter(filter: String, post: Option<&str>) -> String {
+    match (post, filter.strip_suffix("[v]")) {
+        (Some(post), Some(head)) => {
+            format!("{}[cfpost];[cfpost]{},{}[v]", head, post, YUV420P_BT709)
+        }
+        _ => filter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_chains_are_expanded_validated_and_spliced() {
+        let filters = CustomFilters::from_cli(&[
+            "pre=hue=s=0".to_string(),
+            "crop=w={width}/2:h={height}:x='if(gt({time},1),10,0)'".to_string(),
+        ])
+        .unwrap()
+        .unwrap();
+        let expanded = filters.expanded(1280, 720).unwrap();
+        assert_eq!(
+            expanded.post.as_deref(),
+            Some("crop=w=1280/2:h=720:x='if(gt(t,1),10,0)'")
+        );
+
+        let graph = "[0:v]format=rgba[c0];[c0]format=yuv420p[v]".to_string();
+        let graph = with_post_filter(
+            with_pre_filter(graph, expanded.pre.as_deref()),
+            expanded.post.as_deref(),
+        );
+        assert_eq!(
+            graph,
+            "[0:v]hue=s=0[cfpre];[cfpre]format=rgba[c0];[c0]format=yuv420p[cfpost];\
+             [cfpost]crop=w=1280/2:h=720:x='if(gt(t,1),10,0)',\
+             scale=out_color_matrix=bt709:out_range=tv,format=yuv420p[v]"
+        );
+
+        for bad in [
+            "",
+            "hue=s=0;[1:v]null",
+            "movie=/etc/passwd",
+            "drawtext=textfile=/etc/passwd",
+            "lut3d=file=/tmp/x.cube",
+            "subtitles=/tmp/x.srt",
+            "ass=/tmp/x.ass",
+            "curves=psfile=/tmp/x.acv",
+            "curves=preset=vintage:psfile=/tmp/x.acv",
+            "curves=plot=/tmp/x",
+            "curves=preset=vintage:plot=/tmp/x",
+            "curves=/tmp/x.acv",
+            "scale=1280:720",
+            "eq=gamma=1.1:/tmp/x",
+            "frei0r=filter_name=glow",
+            "ladspa=file=/tmp/x.so",
+            "scale={depth}",
+            "eq=1,'quoted'",
+        ] {
+            assert!(expand_filter_chain(bad, 10, 10).is_err(), "{}", bad);
+        }
+        for good in [
+            "curves=preset=vintage,eq=saturation=1.2",
+            "hflip,eq=gamma=1.1:enable='gte(t\\,2)'",
+            "scale=w=1280:h=-2",
+        ] {
+            assert!(expand_filter_chain(good, 10, 10).is_ok(), "{}", good);
+        }
+        assert_eq!(CustomFilters::from_cli(&[]).unwrap(), None);
+    }
+}
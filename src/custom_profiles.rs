@@ -0,0 +1,300 @@
+use crate::app_paths::user_config_path;
+use crate::cli::PlaybackProfile;
+use crate::i18n::{Msg, warn_msg};
+use crate::mpv_options::{parse_mpv_option, validate_mpv_options};
+use crate::playback::build_mpv_options;
+use crate::startup_config::{StartupConfig, load_config};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    static CONFIG_PROFILES: RefCell<Option<BTreeMap<String, CustomProfile>>> =
+        const { RefCell::new(None) };
+}
+
+/// Runs `f` resolving custom profiles from `profiles` (e.g. those of a
+/// `start-config --config` file) instead of the user's config.json.
+pub fn with_config_profiles<R>(
+    profiles: BTreeMap<String, CustomProfile>,
+    f: impl FnOnce() -> R,
+) -> R {
+    let previous = CONFIG_PROFILES.with(|c| c.replace(Some(profiles)));
+    let result = f();
+    CONFIG_PROFILES.with(|c| *c.borrow_mut() = previous);
+    result
+}
+
+/// Custom profiles in effect: the ones set by `with_config_profiles`, or
+/// the user's config.json.
+pub fn configured_profiles() -> Result<BTreeMap<String, CustomProfile>> {
+    if let Some(profiles) = CONFIG_PROFILES.with(|c| c.borrow().clone()) {
+        return Ok(profiles);
+    }
+    Ok(load_config(&user_config_path())?.profiles)
+}
+
+/// Proxy encode settings of a profile; unset ones keep the command's values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileProxySettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crf: Option<u8>,
+}
+
+impl ProfileProxySettings {
+    pub fn is_empty(&self) -> bool {
+        self == &ProfileProxySettings::default()
+    }
+}
+
+fn default_base() -> PlaybackProfile {
+    PlaybackProfile::Balanced
+}
+
+/// User profile under `profiles` in config.json: a preset plus overrides,
+/// selectable with `--profile <name>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomProfile {
+    /// Preset the profile starts from.
+    #[serde(default = "default_base")]
+    pub base: PlaybackProfile,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// mpv options as `key=value`, after the preset's and the config's
+    /// `mpv_options` sets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mpv_options: Vec<String>,
+    /// mpv `hwdec` value in place of `auto-safe`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hwdec: Option<String>,
+    /// Highest frame rate mpv shows and proxies are encoded at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps_cap: Option<u32>,
+    #[serde(default, skip_serializing_if = "ProfileProxySettings::is_empty")]
+    pub proxy: ProfileProxySettings,
+}
+
+pub fn validate_profile(name: &str, profile: &CustomProfile) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        bail!(
+            "invalid profile name '{}': use letters, digits, '-' and '_'",
+            name
+        );
+    }
+    if PlaybackProfile::PRESETS.iter().any(|p| p.name() == name) {
+        bail!("profile '{}' would shadow the built-in preset", name);
+    }
+    if let PlaybackProfile::Custom(base) = profile.base {
+        bail!(
+            "profile '{}': base must be performance, balanced or quality, got '{}'",
+            name,
+            base
+        );
+    }
+    for opt in &profile.mpv_options {
+        if let Err(err) = parse_mpv_option(opt) {
+            bail!("profile '{}': {}", name, err);
+        }
+    }
+    if let Some(hwdec) = &profile.hwdec
+        && (hwdec.is_empty() || hwdec.chars().any(char::is_whitespace))
+    {
+        bail!("profile '{}': invalid hwdec '{}'", name, hwdec);
+    }
+    if profile.fps_cap == Some(0) || profile.proxy.fps == Some(0) || profile.proxy.width == Some(0)
+    {
+        bail!("profile '{}': fps and width must be positive", name);
+    }
+    if profile.proxy.crf.is_some_and(|crf| crf > 51) {
+        bail!("profile '{}': proxy crf must be 0-51", name);
+    }
+    let base = build_mpv_options(profile.base, false, None);
+    validate_mpv_options(&base, &profile.mpv_options, &[])
+        .map_err(|err| err.context(format!("profile '{}'", name)))
+}
+
+pub fn validate_profiles(profiles: &BTreeMap<String, CustomProfile>) -> Result<()> {
+    for (name, profile) in profiles {
+        validate_profile(name, profile)?;
+    }
+    Ok(())
+}
+
+/// Value parser for `--profile`: a preset, or a valid profile from the
+/// configured profiles.
+pub fn parse_playback_profile(raw: &str) -> std::result::Result<PlaybackProfile, String> {
+    let raw = raw.trim();
+    let profile = PlaybackProfile::from_name(raw);
+    if !matches!(profile, PlaybackProfile::Custom(_)) {
+        return Ok(profile);
+    }
+    let profiles = configured_profiles().map_err(|err| format!("{:#}", err))?;
+    let Some(custom) = profiles.get(raw) else {
+        let available = PlaybackProfile::PRESETS
+            .iter()
+            .map(|p| p.name())
+            .chain(profiles.keys().map(String::as_str))
+            .collect::<Vec<_>>();
+        return Err(format!(
+            "unknown profile '{}' (available: {})",
+            raw,
+            available.join(", ")
+        ));
+    };
+    validate_profile(raw, custom).map_err(|err| format!("{:#}", err))?;
+    Ok(profile)
+}
+
+/// Definition of a custom `profile` from the configured profiles.
+pub fn custom_profile(profile: PlaybackProfile) -> Option<CustomProfile> {
+    let PlaybackProfile::Custom(name) = profile else {
+        return None;
+    };
+    configured_profiles().ok()?.remove(name)
+}
+
+/// Preset behind `profile` and its custom definition. A custom profile that
+/// is gone from the config (e.g. replayed from history) plays as balanced.
+pub fn resolve_profile(profile: PlaybackProfile) -> (PlaybackProfile, Option<CustomProfile>) {
+    let PlaybackProfile::Custom(name) = profile else {
+        return (profile, None);
+    };
+    match custom_profile(profile) {
+        Some(custom) if !matches!(custom.base, PlaybackProfile::Custom(_)) => {
+            (custom.base, Some(custom))
+        }
+        _ => {
+            warn_msg!(Msg::CustomProfileMissing, name);
+            (PlaybackProfile::Balanced, None)
+        }
+    }
+}
+
+/// Proxy `width`, `fps` and `crf` under `profile`: a custom profile's proxy
+/// settings replace them, and its `fps_cap` bounds the fps.
+pub fn profile_proxy_settings(
+    profile: PlaybackProfile,
+    width: u32,
+    fps: u32,
+    crf: u8,
+) -> (u32, u32, u8) {
+    let Some(custom) = custom_profile(profile) else {
+        return (width, fps, crf);
+    };
+    let fps = custom.proxy.fps.unwrap_or(fps);
+    (
+        custom.proxy.width.unwrap_or(width),
+        custom.fps_cap.map_or(fps, |cap| fps.min(cap)),
+        custom.proxy.crf.unwrap_or(crf),
+    )
+}
+
+/// One `profiles list` row.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileListing {
+    pub name: String,
+    pub builtin: bool,
+    #[serde(flatten)]
+    pub definition: Option<CustomProfile>,
+    /// Why `--profile <name>` would be refused.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn list_profiles(cfg: &StartupConfig) -> Vec<ProfileListing> {
+    let presets = PlaybackProfile::PRESETS.iter().map(|p| ProfileListing {
+        name: p.name().to_string(),
+        builtin: true,
+        definition: None,
+        error: None,
+    });
+    let custom = cfg.profiles.iter().map(|(name, profile)| ProfileListing {
+        name: name.clone(),
+        builtin: false,
+        definition: Some(profile.clone()),
+        error: validate_profile(name, profile)
+            .err()
+            .map(|err| format!("{:#}", err)),
+    });
+    presets.chain(custom).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_profiles_are_validated_listed_and_round_trip() {
+        let cfg: StartupConfig = serde_json::from_str(
+            r#"{"version":1,"entries":[],"profiles":{
+                "laptop":{"base":"performance","hwdec":"vaapi","fps_cap":30,
+                          "mpv_options":["deband=no"],"proxy":{"width":1920,"crf":26}},
+                "broken":{"mpv_options":["vo=gpu-next"]},
+                "quality":{"base":"quality"}}}"#,
+        )
+        .unwrap();
+        let laptop = &cfg.profiles["laptop"];
+        assert_eq!(laptop.base, PlaybackProfile::Performance);
+        assert!(validate_profile("laptop", laptop).is_ok());
+        assert_eq!(cfg.profiles["broken"].base, PlaybackProfile::Balanced);
+
+        let rows = list_profiles(&cfg);
+        let row = |name: &str| rows.iter().find(|r| !r.builtin && r.name == name).unwrap();
+        assert_eq!(rows.iter().filter(|r| r.builtin).count(), 3);
+        assert!(row("laptop").error.is_none());
+        assert!(
+            row("broken")
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("managed by kitsune")
+        );
+        assert!(row("quality").error.as_deref().unwrap().contains("shadow"));
+
+        let custom = PlaybackProfile::from_name("laptop");
+        assert_eq!(custom, PlaybackProfile::Custom("laptop"));
+        assert_eq!(
+            PlaybackProfile::from_name("quality"),
+            PlaybackProfile::Quality
+        );
+        assert_eq!(
+            serde_json::to_value([custom, PlaybackProfile::Balanced]).unwrap(),
+            serde_json::json!(["laptop", "balanced"])
+        );
+        assert_eq!(
+            parse_playback_profile("performance"),
+            Ok(PlaybackProfile::Performance)
+        );
+    }
+
+    #[test]
+    fn profiles_resolve_from_the_loaded_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg: StartupConfig = serde_json::from_str(
+            r#"{"version":1,"entries":[],"profiles":{
+                "desk":{"base":"quality","proxy":{"width":2560}}}}"#,
+        )
+        .unwrap();
+        crate::app_paths::with_app_dirs_root(dir.path(), || {
+            let desk = PlaybackProfile::from_name("desk");
+            assert!(parse_playback_profile("desk").is_err());
+            with_config_profiles(cfg.profiles.clone(), || {
+                assert_eq!(parse_playback_profile("desk"), Ok(desk));
+                let (preset, custom) = resolve_profile(desk);
+                assert_eq!(preset, PlaybackProfile::Quality);
+                assert_eq!(custom.as_ref(), cfg.profiles.get("desk"));
+                assert_eq!(profile_proxy_settings(desk, 1920, 30, 23), (2560, 30, 23));
+            });
+            assert!(custom_profile(desk).is_none());
+        });
+    }
+}
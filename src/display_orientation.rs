@@ -0,0 +1,328 @@
+use crate::cli::Orientation;
+use crate::command_runner::CommandExt;
+use std::process::Command;
+
+/// Part of the scene canvas shown on the output (portrait center crop or a
+/// `--crop-region`). Ratios are the fraction of the scene still visible on
+/// each axis; offsets where that window starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanvasCrop {
+    pub out_w: u32,
+    pub out_h: u32,
+    pub visible_x: f32,
+    pub visible_y: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+impl CanvasCrop {
+    /// Appended after a composite so the encoded proxy already matches the output.
+    pub fn ffmpeg_filter(&self) -> String {
+        format!(
+            "scale={w}:{h}:force_original_aspect_ratio=increase:flags=bicubic,crop={w}:{h}",
+            w = self.out_w,
+            h = self.out_h
+        )
+    }
+
+    /// Maps a 0..1 scene position to the cropped frame.
+    pub fn map_ratio(&self, x: f32, y: f32) -> (f32, f32) {
+        let map = |v: f32, offset: f32, visible: f32| (v - offset) / visible;
+        (
+            map(x, self.offset_x, self.visible_x),
+            map(y, self.offset_y, self.visible_y),
+        )
+    }
+
+    /// Maps a size given as a fraction of scene height.
+    pub fn map_height_ratio(&self, v: f32) -> f32 {
+        v / self.visible_y
+    }
+}
+
+fn even(v: f32) -> u32 {
+    ((v.round() as u32) / 2 * 2).max(2)
+}
+
+/// Swapped canvas for a portrait output; `None` when the scene is already
+/// portrait (or square) and plays fine as is.
+pub fn portrait_canvas(scene_w: u32, scene_h: u32) -> Option<CanvasCrop> {
+    let (w, h) = (scene_w.max(1) as f32, scene_h.max(1) as f32);
+    if w <= h {
+        return None;
+    }
+    let (out_w, out_h) = (even(h), even(w));
+    // force_original_aspect_ratio=increase scales by the larger factor.
+    let scale = (out_w as f32 / w).max(out_h as f32 / h);
+    let visible_x = (out_w as f32 / scale / w).min(1.0);
+    let visible_y = (out_h as f32 / scale / h).min(1.0);
+    Some(CanvasCrop {
+        out_w,
+        out_h,
+        visible_x,
+        visible_y,
+        offset_x: (1.0 - visible_x) / 2.0,
+        offset_y: (1.0 - visible_y) / 2.0,
+    })
+}
+
+/// `--crop-region x,y,w,h`: pixels of the scene (scene-play) or of the video
+/// (video-play), or 0..1 fractions when every value is at most 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRegion {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+pub fn parse_crop_region(raw: &str) -> Result<CropRegion, String> {
+    let values = raw
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("expected x,y,w,h numbers, got '{}'", raw))?;
+    let [x, y, w, h] = values[..] else {
+        return Err(format!("expected 4 values (x,y,w,h), got {}", values.len()));
+    };
+    if x < 0.0 || y < 0.0 || w <= 0.0 || h <= 0.0 {
+        return Err("crop region needs x,y >= 0 and w,h > 0".to_string());
+    }
+    Ok(CropRegion { x, y, w, h })
+}
+
+impl CropRegion {
+    pub fn is_relative(&self) -> bool {
+        [self.x, self.y, self.w, self.h].iter().all(|v| *v <= 1.0)
+    }
+
+    /// Region as 0..1 fractions of a `src_w`x`src_h` canvas, clamped to it.
+    pub fn to_fractions(&self, src_w: u32, src_h: u32) -> CropRegion {
+        let (sw, sh) = if self.is_relative() {
+            (1.0, 1.0)
+        } else {
+            (src_w.max(1) as f32, src_h.max(1) as f32)
+        };
+        let x = (self.x / sw).clamp(0.0, 0.99);
+        let y = (self.y / sh).clamp(0.0, 0.99);
+        CropRegion {
+            x,
+            y,
+            w: (self.w / sw).clamp(0.01, 1.0 - x),
+            h: (self.h / sh).clamp(0.01, 1.0 - y),
+        }
+    }
+
+    /// mpv `vf` crop; fractions become `iw`/`ih` expressions so the same
+    /// region holds for any proxy resolution.
+    pub fn mpv_crop_filter(&self) -> String {
+        if self.is_relative() {
+            format!(
+                "crop=w=iw*{:.5}:h=ih*{:.5}:x=iw*{:.5}:y=ih*{:.5}",
+                self.w, self.h, self.x, self.y
+            )
+        } else {
+            format!(
+                "crop=w={}:h={}:x={}:y={}",
+                self.w.round(),
+                self.h.round(),
+                self.x.round(),
+                self.y.round()
+            )
+        }
+    }
+
+    /// Canvas for overlays drawn after the crop; expects fractions.
+    pub fn canvas(&self, scene_w: u32, scene_h: u32) -> CanvasCrop {
+        CanvasCrop {
+            out_w: even(scene_w as f32 * self.w),
+            out_h: even(scene_h as f32 * self.h),
+            visible_x: self.w,
+            visible_y: self.h,
+            offset_x: self.x,
+            offset_y: self.y,
+        }
+    }
+}
+
+/// How overlays are placed relative to the full scene canvas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFraming {
+    Full,
+    Portrait,
+    /// Fractions, see [`CropRegion::to_fractions`].
+    Region(CropRegion),
+}
+
+impl OutputFraming {
+    pub fn canvas(&self, scene_w: u32, scene_h: u32) -> Option<CanvasCrop> {
+        match self {
+            OutputFraming::Full => None,
+            OutputFraming::Portrait => portrait_canvas(scene_w, scene_h),
+            OutputFraming::Region(region) => Some(region.canvas(scene_w, scene_h)),
+        }
+    }
+}
+
+/// wl_output transforms 1/3 (90/270) and their flipped variants 5/7.
+fn is_rotated_transform(transform: u64) -> bool {
+    transform % 2 == 1
+}
+
+fn transform_from_hyprctl_json(raw: &str, monitor: &str) -> Option<u64> {
+    let monitors = serde_json::from_str::<serde_json::Value>(raw).ok()?;
+    monitors
+        .as_array()?
+        .iter()
+        .find(|m| m.get("name").and_then(|v| v.as_str()) == Some(monitor))?
+        .get("transform")
+        .and_then(|v| v.as_u64())
+}
+
+/// Pixel size of the output as seen by the wallpaper (rotated outputs swap).
+pub fn size_from_hyprctl_json(raw: &str, monitor: &str) -> Option<(u32, u32)> {
+    let monitors = serde_json::from_str::<serde_json::Value>(raw).ok()?;
+    let m = monitors
+        .as_array()?
+        .iter()
+        .find(|m| m.get("name").and_then(|v| v.as_str()) == Some(monitor))?;
+    let w = m.get("width").and_then(|v| v.as_u64())? as u32;
+    let h = m.get("height").and_then(|v| v.as_u64())? as u32;
+    let rotated = m
+        .get("transform")
+        .and_then(|v| v.as_u64())
+        .is_some_and(is_rotated_transform);
+    (w > 0 && h > 0).then_some(if rotated { (h, w) } else { (w, h) })
+}
+
+/// `hyprctl -j monitors`; `None` on other compositors. Launches query it
+/// once and read both the refresh rate and the output size from it.
+pub fn hyprctl_monitors() -> Option<String> {
+    let output = Command::new("hyprctl")
+        .arg("-j")
+        .arg("monitors")
+        .run_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Output transform reported by Hyprland; `None` on other compositors.
+pub fn monitor_transform(monitor: &str) -> Option<u64> {
+    transform_from_hyprctl_json(&hyprctl_monitors()?, monitor)
+}
+
+pub fn is_portrait_output(orientation: Orientation, monitor: &str) -> bool {
+    match orientation {
+        Orientation::Landscape => false,
+        Orientation::Portrait => true,
+        Orientation::Auto => monitor_transform(monitor).is_some_and(is_rotated_transform),
+    }
+}
+
+/// Crop to apply to scene proxies for `monitor`, if any.
+pub fn canvas_crop_for_output(
+    orientation: Orientation,
+    monitor: &str,
+    scene_w: u32,
+    scene_h: u32,
+) -> Option<CanvasCrop> {
+    if !is_portrait_output(orientation, monitor) {
+        return None;
+    }
+    let crop = portrait_canvas(scene_w, scene_h)?;
+    eprintln!(
+        "[ok] portrait output {}: proxy canvas {}x{}",
+        monitor, crop.out_w, crop.out_h
+    );
+    Some(crop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portrait_canvas_swaps_and_maps_overlay_positions() {
+        let crop = portrait_canvas(1920, 1080).unwrap();
+        assert_eq!((crop.out_w, crop.out_h), (1080, 1920));
+        assert!((crop.visible_y - 1.0).abs() < 1e-4);
+        assert!((crop.visible_x - 0.3164).abs() < 1e-3);
+        let (x, y) = crop.map_ratio(0.5, 0.25);
+        assert!((x - 0.5).abs() < 1e-4 && (y - 0.25).abs() < 1e-4);
+        assert!(crop.map_ratio(0.1, 0.5).0 < 0.0);
+        assert!(portrait_canvas(1080, 1920).is_none());
+
+        let region = parse_crop_region("960,0,960,1080")
+            .unwrap()
+            .to_fractions(1920, 1080);
+        assert!((region.x - 0.5).abs() < 1e-4 && (region.w - 0.5).abs() < 1e-4);
+        assert!(
+            region
+                .mpv_crop_filter()
+                .starts_with("crop=w=iw*0.50000:h=ih*1.00000")
+        );
+        let canvas = OutputFraming::Region(region).canvas(1920, 1080).unwrap();
+        let (x, _) = canvas.map_ratio(0.75, 0.5);
+        assert!((x - 0.5).abs() < 1e-4);
+        assert!(parse_crop_region("1,2,3").is_err());
+
+        let raw = r#"[{"name":"DP-1","transform":1},{"name":"HDMI-A-1","transform":0}]"#;
+        assert_eq!(transform_from_hyprctl_json(raw, "DP-1"), Some(1));
+        let sized = r#"[{"name":"DP-1","width":2560,"height":1440,"transform":1}]"#;
+        assert_eq!(size_from_hyprctl_json(sized, "DP-1"), Some((1440, 2560)));
+        assert!(is_rotated_transform(3) && !is_rotated_transform(2));
+    }
+
+    #[test]
+    fn crop_regions_parse_and_become_crop_filters() {
+        assert_eq!(
+            parse_crop_region(" 10, 20 ,300,200"),
+            Ok(CropRegion {
+                x: 10.0,
+                y: 20.0,
+                w: 300.0,
+                h: 200.0
+            })
+        );
+        for bad in [
+            "",
+            "1,2,3",
+            "1,2,3,4,5",
+            "a,0,10,10",
+            "-1,0,10,10",
+            "0,0,0,10",
+            "0,0,10,-5",
+        ] {
+            assert!(parse_crop_region(bad).is_err(), "{bad:?}");
+        }
+
+        // Pixels of a 1000x500 source, clamped to it.
+        let pixels = parse_crop_region("250,100,500,250").unwrap();
+        assert!(!pixels.is_relative());
+        assert_eq!(pixels.mpv_crop_filter(), "crop=w=500:h=250:x=250:y=100");
+        let fractions = pixels.to_fractions(1000, 500);
+        assert_eq!(
+            fractions,
+            CropRegion {
+                x: 0.25,
+                y: 0.2,
+                w: 0.5,
+                h: 0.5
+            }
+        );
+        assert_eq!(
+            fractions.mpv_crop_filter(),
+            "crop=w=iw*0.50000:h=ih*0.50000:x=iw*0.25000:y=ih*0.20000"
+        );
+        let past_edge = parse_crop_region("900,0,400,500")
+            .unwrap()
+            .to_fractions(1000, 500);
+        assert!((past_edge.x + past_edge.w - 1.0).abs() < 1e-6);
+        // Fractions are kept as given.
+        let relative = parse_crop_region("0,0,0.5,1").unwrap();
+        assert_eq!(relative.to_fractions(1000, 500), relative);
+    }
+}
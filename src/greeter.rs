@@ -0,0 +1,341 @@
+use crate::app_paths::{SYSTEM_CONFIG_DIR, app_cache_dir};
+use crate::cli::{GreeterMode, GreeterTarget, PlaybackProfile};
+use crate::command_runner::shell_quote;
+use crate::i18n::{Msg, warn_msg};
+use crate::playback::build_mpv_options;
+use crate::scene_effect_proxy::maybe_build_scene_animated_proxy;
+use crate::scene_renderer::build_scene_render_session;
+use crate::scene_thumbnail::render_scene_thumbnail;
+use crate::tex_payload::extract_playable_proxy_from_tex;
+use crate::types::{ProjectJson, WallpaperType};
+use crate::video_opt::maybe_build_optimized_proxy;
+use crate::wallpaper::{detect_type, find_video_entry, is_video_file};
+use anyhow::{Context, Result, bail};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Low-power settings for the pre-login background: the greeter only needs
+/// something pleasant, not full quality.
+const GREETER_PROXY_FPS: u32 = 24;
+const GREETER_PROXY_CRF: u8 = 30;
+
+pub fn default_greeter_dir() -> PathBuf {
+    Path::new(SYSTEM_CONFIG_DIR).join("greeter")
+}
+
+pub struct GreeterInstallArgs {
+    pub root: PathBuf,
+    pub target: GreeterTarget,
+    pub mode: GreeterMode,
+    pub width: u32,
+    pub height: u32,
+    pub dest: PathBuf,
+    pub overwrite: bool,
+    pub dry_run: bool,
+}
+
+fn load_project(root: &Path) -> Option<ProjectJson> {
+    let raw = fs::read_to_string(root.join("project.json")).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn build_light_animated_asset(root: &Path, work_dir: &Path, width: u32) -> Result<Option<PathBuf>> {
+    let project = load_project(root);
+    let source = match detect_type(root, project.as_ref()) {
+        WallpaperType::Video => find_video_entry(root, project.as_ref()),
+        WallpaperType::Scene => {
+            let session = build_scene_render_session(root, None, 4, 50)?;
+            let visual = PathBuf::from(&session.visual_asset_path);
+            let session_dir = PathBuf::from(&session.session_dir);
+            let base = if visual
+                .extension()
+                .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("tex"))
+            {
+                extract_playable_proxy_from_tex(&visual, &session_dir.join("proxy"))?
+            } else {
+                Some(visual)
+            };
+            match base {
+                Some(base) if is_video_file(&base) => Some(base),
+                Some(base) => maybe_build_scene_animated_proxy(
+                    root,
+                    &session_dir,
+                    &base,
+                    None,
+                    false,
+                    None,
+                    false,
+                )?,
+                None => None,
+            }
+        }
+        _ => None,
+    };
+    let Some(source) = source else {
+        return Ok(None);
+    };
+    let proxy = maybe_build_optimized_proxy(
+        &source,
+        work_dir,
+        width,
+        GREETER_PROXY_FPS,
+        GREETER_PROXY_CRF,
+        false,
+    )?;
+    Ok(is_video_file(&proxy).then_some(proxy))
+}
+
+fn greeter_mpv_options() -> String {
+    build_mpv_options(PlaybackProfile::Performance, true, None)
+}
+
+/// Launcher run from the greeter compositor; `*` covers every output.
+pub fn greeter_launcher_script(asset: &Path, animated: bool) -> String {
+    let body = if animated {
+        format!(
+            "exec mpvpaper -o {} '*' {}",
+            shell_quote(OsStr::new(&greeter_mpv_options())),
+            shell_quote(asset.as_os_str())
+        )
+    } else {
+        format!("exec swaybg -m fill -i {}", shell_quote(asset.as_os_str()))
+    };
+    format!(
+        "#!/bin/sh\n# auto-generated by kitsune-livewallpaper greeter install\n{}\n",
+        body
+    )
+}
+
+/// Snippet for the sway config greetd runs its greeter under (regreet, gtkgreet...).
+pub fn greetd_sway_snippet(launcher: &Path) -> String {
+    format!(
+        "# auto-generated by kitsune-livewallpaper greeter install\nexec {}\n",
+        shell_quote(launcher.as_os_str())
+    )
+}
+
+pub fn sddm_theme_conf(asset: &Path) -> String {
+    format!(
+        "# auto-generated by kitsune-livewallpaper greeter install\n[General]\nbackground={}\n",
+        asset.display()
+    )
+}
+
+/// Files an install writes into `dest`: the background asset and the
+/// target's config files with their contents. The launcher is the only
+/// executable one.
+struct GreeterFiles {
+    asset: PathBuf,
+    configs: Vec<(PathBuf, String)>,
+    launcher: Option<PathBuf>,
+}
+
+fn greeter_files(dest: &Path, target: GreeterTarget, animated: bool) -> GreeterFiles {
+    let asset = dest.join(if animated {
+        "background.mp4"
+    } else {
+        "background.png"
+    });
+    match target {
+        GreeterTarget::Greetd => {
+            let launcher = dest.join("kitsune-greeter-background.sh");
+            let configs = vec![
+                (launcher.clone(), greeter_launcher_script(&asset, animated)),
+                (
+                    dest.join("greetd-sway.conf"),
+                    greetd_sway_snippet(&launcher),
+                ),
+            ];
+            GreeterFiles {
+                asset,
+                configs,
+                launcher: Some(launcher),
+            }
+        }
+        GreeterTarget::Sddm => GreeterFiles {
+            configs: vec![(dest.join("theme.conf.user"), sddm_theme_conf(&asset))],
+            asset,
+            launcher: None,
+        },
+    }
+}
+
+impl GreeterFiles {
+    /// Refuses before anything is written, so a refusal never leaves a
+    /// partial install behind.
+    fn refuse_existing(&self) -> Result<()> {
+        let paths = std::iter::once(&self.asset).chain(self.configs.iter().map(|(p, _)| p));
+        for path in paths {
+            if path.exists() {
+                bail!(
+                    "{} already exists (use --overwrite to replace)",
+                    path.display()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    fs::write(path, contents).with_context(|| {
+        format!(
+            "Failed writing {} (system dirs need root; try sudo or --dest)",
+            path.display()
+        )
+    })
+}
+
+/// Renders the wallpaper for the login screen and writes the greeter config
+/// next to it. Nothing outside `dest` is modified; a dry run renders nothing
+/// and only prints the planned actions.
+pub fn install_greeter(args: GreeterInstallArgs) -> Result<()> {
+    // SDDM's stock themes only take still images.
+    let mut animated = matches!(args.mode, GreeterMode::Animated);
+    if animated && matches!(args.target, GreeterTarget::Sddm) {
+        warn_msg!(Msg::GreeterSddmStatic);
+        animated = false;
+    }
+    let mut files = greeter_files(&args.dest, args.target, animated);
+    if !args.overwrite {
+        files.refuse_existing()?;
+    }
+
+    if args.dry_run {
+        eprintln!(
+            "[dry-run] render {} background from {}",
+            if animated { "animated" } else { "static" },
+            args.root.display()
+        );
+        eprintln!("[dry-run] mkdir -p {}", args.dest.display());
+        eprintln!("[dry-run] write {}", files.asset.display());
+        for (path, contents) in &files.configs {
+            eprintln!(
+                "[dry-run] write {} ({} bytes)",
+                path.display(),
+                contents.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let work_dir = app_cache_dir().join("greeter");
+    fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed creating {}", work_dir.display()))?;
+    let mut rendered = None;
+    if animated {
+        match build_light_animated_asset(&args.root, &work_dir, args.width) {
+            Ok(Some(video)) => rendered = Some(video),
+            Ok(None) => {
+                warn_msg!(Msg::GreeterNoAnimatedSource)
+            }
+            Err(err) => warn_msg!(Msg::GreeterAnimatedFailed, err),
+        }
+        if rendered.is_none() {
+            // The still fallback lands under another name: check it too.
+            animated = false;
+            files = greeter_files(&args.dest, args.target, animated);
+            if !args.overwrite {
+                files.refuse_existing()?;
+            }
+        }
+    }
+    let rendered = match rendered {
+        Some(video) => video,
+        None => {
+            let frame = work_dir.join("background.png");
+            let report = render_scene_thumbnail(&args.root, &frame, args.width, args.height)?;
+            eprintln!("[ok] greeter frame from {}", report.source);
+            frame
+        }
+    };
+
+    fs::create_dir_all(&args.dest).with_context(|| {
+        format!(
+            "Failed creating {} (system dirs need root; try sudo or --dest)",
+            args.dest.display()
+        )
+    })?;
+    let bytes =
+        fs::read(&rendered).with_context(|| format!("Failed reading {}", rendered.display()))?;
+    write_file(&files.asset, &bytes)?;
+    for (path, contents) in &files.configs {
+        write_file(path, contents.as_bytes())?;
+    }
+    if let Some(launcher) = &files.launcher {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(launcher, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed chmod {}", launcher.display()))?;
+    }
+
+    let (config, _) = files.configs.last().expect("every target writes a config");
+    match args.target {
+        GreeterTarget::Greetd => eprintln!(
+            "[ok] greetd: add `include {}` to the sway config your greeter runs under",
+            config.display()
+        ),
+        GreeterTarget::Sddm => eprintln!(
+            "[ok] sddm: copy {} into /usr/share/sddm/themes/<theme>/",
+            config.display()
+        ),
+    }
+    eprintln!(
+        "[ok] greeter background installed: {} ({})",
+        files.asset.display(),
+        if animated { "animated" } else { "static" }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greeter_launcher_is_silent_and_low_power() {
+        let script = greeter_launcher_script(Path::new("/etc/kwe/background.mp4"), true);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("mpvpaper -o '"));
+        assert!(script.contains("no-audio"));
+        assert!(script.contains("profile=fast"));
+        assert!(script.contains("'*' /etc/kwe/background.mp4\n"));
+
+        let still = greeter_launcher_script(Path::new("/etc/kwe/background.png"), false);
+        assert!(still.contains("swaybg -m fill -i /etc/kwe/background.png\n"));
+        let odd = greeter_launcher_script(Path::new("/srv/it's here.png"), false);
+        assert!(odd.contains(r"swaybg -m fill -i '/srv/it'\''s here.png'"));
+    }
+
+    #[test]
+    fn install_checks_every_destination_and_dry_runs_render_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("greeter");
+        let args = |overwrite, dry_run| GreeterInstallArgs {
+            root: dir.path().join("missing-wallpaper"),
+            target: GreeterTarget::Greetd,
+            mode: GreeterMode::Animated,
+            width: 640,
+            height: 360,
+            dest: dest.clone(),
+            overwrite,
+            dry_run,
+        };
+        crate::app_paths::with_app_dirs_root(&dir.path().join("app"), || {
+            // Nothing is rendered, so a wallpaper that cannot be read is fine.
+            install_greeter(args(false, true)).unwrap();
+            assert!(!dir.path().join("app/cache/greeter").exists());
+            assert!(!dest.exists());
+
+            fs::create_dir_all(&dest).unwrap();
+            fs::write(dest.join("greetd-sway.conf"), b"mine").unwrap();
+            let err = install_greeter(args(false, false)).unwrap_err();
+            assert!(err.to_string().contains("greetd-sway.conf"), "{err}");
+            // Refused before rendering or writing the asset and launcher.
+            let left = fs::read_dir(&dest).unwrap().count();
+            assert_eq!(left, 1);
+            assert!(!dir.path().join("app/cache/greeter").exists());
+            install_greeter(args(true, true)).unwrap();
+        });
+    }
+}
@@ -0,0 +1,153 @@
+use crate::app_paths::app_state_dir;
+use crate::startup_config::StartupCommand;
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+pub const MAX_HISTORY_PER_MONITOR: usize = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub applied_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub command: StartupCommand,
+}
+
+impl HistoryEntry {
+    /// Wallpaper id/path (apply) or video (video-play) the entry points at.
+    pub fn target(&self) -> &str {
+        match &self.command {
+            StartupCommand::Apply { wallpaper, .. } => wallpaper,
+            StartupCommand::Video { video, .. } => video,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match &self.command {
+            StartupCommand::Apply { .. } => "apply",
+            StartupCommand::Video { .. } => "video",
+        }
+    }
+}
+
+/// Per-monitor list of applied wallpapers, oldest first; the last entry is
+/// what the monitor currently shows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WallpaperHistory {
+    pub monitors: BTreeMap<String, Vec<HistoryEntry>>,
+}
+
+impl WallpaperHistory {
+    /// Re-applying the current wallpaper only refreshes its entry, so going
+    /// back does not bounce between the same two wallpapers.
+    pub fn record(&mut self, monitor: &str, entry: HistoryEntry) {
+        let list = self.monitors.entry(monitor.to_string()).or_default();
+        if list
+            .last()
+            .is_some_and(|last| last.target() == entry.target())
+        {
+            list.pop();
+        }
+        list.push(entry);
+        if list.len() > MAX_HISTORY_PER_MONITOR {
+            list.drain(..list.len() - MAX_HISTORY_PER_MONITOR);
+        }
+    }
+
+    /// Drops the current entry and returns the one to reapply.
+    pub fn step_back(&mut self, monitor: &str) -> Option<HistoryEntry> {
+        let list = self.monitors.get_mut(monitor)?;
+        if list.len() < 2 {
+            return None;
+        }
+        list.pop();
+        list.last().cloned()
+    }
+
+    pub fn entries(&self, monitor: &str) -> &[HistoryEntry] {
+        self.monitors.get(monitor).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+fn history_path() -> PathBuf {
+    app_state_dir().join("history.json")
+}
+
+pub fn load_history() -> Result<WallpaperHistory> {
+    let path = history_path();
+    if !path.is_file() {
+        return Ok(WallpaperHistory::default());
+    }
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+pub fn save_history(history: &WallpaperHistory) -> Result<()> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(history)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// Adds an applied wallpaper to the history. Dry runs never get here (see
+/// `applied_wallpaper`).
+pub fn record_applied(monitor: &str, command: StartupCommand, title: Option<String>) -> Result<()> {
+    let mut history = load_history()?;
+    history.record(
+        monitor,
+        HistoryEntry {
+            applied_at: Local::now().to_rfc3339(),
+            title,
+            command,
+        },
+    );
+    save_history(&history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::PlaybackProfile;
+
+    fn apply(wallpaper: &str) -> HistoryEntry {
+        HistoryEntry {
+            applied_at: "2026-01-01T00:00:00+00:00".to_string(),
+            title: None,
+            command: StartupCommand::Apply {
+                wallpaper: wallpaper.to_string(),
+                downloads_root: PathBuf::from("/tmp"),
+                keep_services: true,
+                mute_audio: false,
+                profile: PlaybackProfile::Balanced,
+                display_fps: None,
+                allow_scene_preview_fallback: false,
+            },
+        }
+    }
+
+    #[test]
+    fn back_walks_history_without_bouncing() {
+        let mut history = WallpaperHistory::default();
+        for id in ["111", "222", "222", "333"] {
+            history.record("DP-1", apply(id));
+        }
+        assert_eq!(history.entries("DP-1").len(), 3);
+
+        let prev = history.step_back("DP-1").unwrap();
+        assert_eq!(prev.target(), "222");
+        // Reapplying it (what `back` does) keeps a single entry on top.
+        history.record("DP-1", prev);
+        assert_eq!(history.entries("DP-1").len(), 2);
+        assert_eq!(history.step_back("DP-1").unwrap().target(), "111");
+        assert!(history.step_back("DP-1").is_none());
+        assert!(history.step_back("HDMI-A-1").is_none());
+    }
+}
@@ -0,0 +1,724 @@
+use crate::cli::Lang;
+use std::cell::Cell;
+use std::fmt::{Display, Write};
+
+thread_local! {
+    static LANG: Cell<Option<Lang>> = const { Cell::new(None) };
+}
+
+/// `es` for Spanish locale values (`es_ES.UTF-8`, `es`), `en` for any other
+/// set value. `C`/`POSIX` count as unset so the next variable decides.
+pub fn lang_from_locale(value: &str) -> Option<Lang> {
+    let value = value.trim();
+    if value.is_empty() || value == "C" || value == "POSIX" || value.starts_with("C.") {
+        return None;
+    }
+    let code = value.split(['_', '.', '@', '-']).next().unwrap_or_default();
+    Some(if code.eq_ignore_ascii_case("es") {
+        Lang::Es
+    } else {
+        Lang::En
+    })
+}
+
+/// Language from `LC_ALL`, `LC_MESSAGES` or `LANG`, in that order.
+pub fn env_lang() -> Lang {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|var| lang_from_locale(&std::env::var(var).ok()?))
+        .unwrap_or_default()
+}
+
+/// `--lang` when set on this thread, else the environment locale.
+pub fn current_lang() -> Lang {
+    LANG.with(Cell::get).unwrap_or_else(env_lang)
+}
+
+/// The `--lang` in effect on this thread, for commands that re-enter `run`.
+pub fn lang_override() -> Option<Lang> {
+    LANG.with(Cell::get)
+}
+
+/// Runs `f` with messages on this thread in `lang` (`None` follows the locale).
+pub fn with_lang<R>(lang: Option<Lang>, f: impl FnOnce() -> R) -> R {
+    let previous = LANG.with(|c| c.replace(lang));
+    let result = f();
+    LANG.with(|c| c.set(previous));
+    result
+}
+
+/// Translated messages: every `[warn]` line and the errors a user can fix
+/// (bad ids, paths, flags, library refusals). Failures of external tools and
+/// internal invariants stay in English. Templates use `{}`, `{:#}` and
+/// `{:.N}` placeholders, filled in order by [`message`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    // Warnings.
+    PathAliasFailed,
+    GreeterSddmStatic,
+    GreeterNoAnimatedSource,
+    GreeterAnimatedFailed,
+    JournalWriteFailed,
+    LayerOverridesIgnored,
+    AlphaBackgroundUnusable,
+    HistoryRecordFailed,
+    OverlayPlanWriteFailed,
+    CapabilitiesUnavailable,
+    ShaderNotFound,
+    ProxyMetadataMissing,
+    WarmStartNoProxy,
+    PortraitOnlyImageProxies,
+    SceneOsdTextFailed,
+    SceneTextOverlaysFailed,
+    WarmStartHotSwapFailed,
+    WarmStartSnapshotFailed,
+    NoHistory,
+    NoJournalEvents,
+    MpvOptionsIgnored,
+    OverlayPlanWithoutBars,
+    OverlayWallpapersIgnored,
+    PeerLeaderNotDownloaded,
+    PeerLeaderInvalidId,
+    WorkshopDownloadFailed,
+    PkgTableIssue,
+    PeerSyncFailed,
+    PeerUnreachable,
+    PeerFollowFailed,
+    MpvpaperOtherInstance,
+    KillFailed,
+    OverlayRestoreFailed,
+    SocketSkipped,
+    ProfileMediaMissing,
+    LiveUpdateFailed,
+    SafeModeConfigIgnored,
+    ProxyBakeFailed,
+    CustomFiltersIgnored,
+    ProceduralProxyFallback,
+    NativeOverBudget,
+    AudioSpectrumExperimental,
+    TexProxySuspicious,
+    NoSafeAlbedoTex,
+    TexPayloadExtracted,
+    NativeRealtimeExperimental,
+    PortraitKeepsLandscape,
+    KitsuneOverlayFailed,
+    GpuTextOverlaysUnavailable,
+    SplitSceneUnsplitProxy,
+    AudioReactiveMuted,
+    AudioReactiveNoMusic,
+    RealtimePlanUnavailable,
+    RealtimeFfmpegFailed,
+    NativeStreamFailed,
+    ShaderIncludeNotFound,
+    TextTimezoneIgnored,
+    FontFallback,
+    DefaultServicesList,
+    InstanceLeavesServices,
+    StopServiceFailed,
+    NoSystemdUnit,
+    TimezoneLocalFallback,
+    AlphaBackgroundPrefIgnored,
+    OverlayPrefsIgnored,
+    StaticBackendConfigIgnored,
+    CommandFailed,
+    StaticBackendRejected,
+    OptimizedProxyFailed,
+    GifConvertFailed,
+    CrossfadeTooShort,
+    CrossfadeFailed,
+    AudioReactiveNoDuration,
+    AudioReactiveProxyFailed,
+    AlphaCompositeFailed,
+    ProresOverlayFailed,
+    QuirksIgnored,
+    NestedCollection,
+    SystemdFeatureMissing,
+    PlaybackStalled,
+    SceneSoundMissing,
+    SceneSoundFailed,
+    PlaybackSyncSkipped,
+    ProfileRulesIgnored,
+    AudioBarsCalibrationIgnored,
+    AudioBarsCalibrationStale,
+    CacheLayoutNewer,
+    CacheMigrationFailed,
+    LayerNamespaceUnsupported,
+    AspectMismatch,
+    SmartCropFailed,
+    CustomProfileMissing,
+    CrashReportWritten,
+    CrashReportFailed,
+    // Playback fallbacks, also recorded in the journal.
+    SafeModePreview,
+    TexPayloadProxy,
+    TexPayloadMissing,
+    VisualNotPlayable,
+    CompatFilesystem,
+    CompatPkgExtract,
+    // Errors.
+    WallpaperNotDirectory,
+    WorkshopNotDownloaded,
+    WorkshopIdSpaces,
+    WallpaperMissing,
+    WallpaperRootMissing,
+    WallpaperTypeUnsupported,
+    InvalidCollectionId,
+    InvalidSince,
+    InvalidSinceUnit,
+    ReadOnlyLibraryWrite,
+    ReadOnlyLibrarySync,
+    ProfileArchiveMissing,
+    ProfileVersionTooNew,
+    NoRunningWallpaper,
+    NoOverlayToRemove,
+    PropertyNotNumber,
+    PropertyOutOfRange,
+    PkgTableOffsetOverflow,
+}
+
+impl Msg {
+    /// English and Spanish templates.
+    fn templates(self) -> [&'static str; 2] {
+        match self {
+            Msg::PathAliasFailed => [
+                "could not alias {}: {}; passing it as-is",
+                "no se pudo crear un alias para {}: {}; se usa tal cual",
+            ],
+            Msg::GreeterSddmStatic => [
+                "SDDM backgrounds are still images; using static mode",
+                "los fondos de SDDM son imagenes fijas; se usa el modo estatico",
+            ],
+            Msg::GreeterNoAnimatedSource => [
+                "no animated source for this wallpaper; using static mode",
+                "este wallpaper no tiene fuente animada; se usa el modo estatico",
+            ],
+            Msg::GreeterAnimatedFailed => [
+                "animated greeter asset failed, using static: {:#}",
+                "fallo el recurso animado del greeter, se usa el estatico: {:#}",
+            ],
+            Msg::JournalWriteFailed => [
+                "failed to write event journal: {:#}",
+                "no se pudo escribir el journal de eventos: {:#}",
+            ],
+            Msg::LayerOverridesIgnored => [
+                "layer overrides ignored: {:#}",
+                "se ignoran los overrides de capas: {:#}",
+            ],
+            Msg::AlphaBackgroundUnusable => [
+                "alpha background '{}' unusable, using black: {:#}",
+                "el fondo alpha '{}' no se puede usar, se usa negro: {:#}",
+            ],
+            Msg::HistoryRecordFailed => [
+                "failed to record wallpaper history: {}",
+                "no se pudo guardar el historial de wallpapers: {}",
+            ],
+            Msg::OverlayPlanWriteFailed => [
+                "failed to write overlay plan: {}",
+                "no se pudo escribir el plan de overlay: {}",
+            ],
+            Msg::CapabilitiesUnavailable => [
+                "capabilities unavailable: {}",
+                "capacidades no disponibles: {}",
+            ],
+            Msg::ShaderNotFound => ["shader not found: {} ({})", "shader no encontrado: {} ({})"],
+            Msg::ProxyMetadataMissing => [
+                "{} has no kitsune proxy metadata (not generated here, or built by an older version)",
+                "{} no tiene metadatos de proxy de kitsune (no se genero aqui o es de una version anterior)",
+            ],
+            Msg::WarmStartNoProxy => [
+                "warm-start: no previous proxy for this wallpaper yet",
+                "warm-start: todavia no hay un proxy previo para este wallpaper",
+            ],
+            Msg::PortraitOnlyImageProxies => [
+                "portrait output: only image-based scene proxies are re-cropped",
+                "salida vertical: solo se recortan los proxies de escenas basadas en imagen",
+            ],
+            Msg::SceneOsdTextFailed => [
+                "could not build scene OSD text: {}",
+                "no se pudo crear el texto OSD de la escena: {}",
+            ],
+            Msg::SceneTextOverlaysFailed => [
+                "could not build scene text overlays: {}",
+                "no se pudieron crear los textos de la escena: {}",
+            ],
+            Msg::WarmStartHotSwapFailed => [
+                "warm-start hot-swap failed, relaunching: {:#}",
+                "fallo el cambio en caliente del warm-start, se relanza: {:#}",
+            ],
+            Msg::WarmStartSnapshotFailed => [
+                "could not snapshot warm-start entry: {:#}",
+                "no se pudo guardar la entrada de warm-start: {:#}",
+            ],
+            Msg::NoHistory => [
+                "no wallpaper history recorded",
+                "no hay historial de wallpapers",
+            ],
+            Msg::NoJournalEvents => [
+                "no journal events match",
+                "ningun evento del journal coincide",
+            ],
+            Msg::MpvOptionsIgnored => [
+                "ignoring mpv_options from config: {}",
+                "se ignora mpv_options de la config: {}",
+            ],
+            Msg::OverlayPlanWithoutBars => [
+                "overlay plan without audio bars: {}",
+                "plan de overlay sin barras de audio: {}",
+            ],
+            Msg::OverlayWallpapersIgnored => [
+                "ignoring overlay wallpapers: {}",
+                "se ignoran los overlay wallpapers: {}",
+            ],
+            Msg::PeerLeaderNotDownloaded => [
+                "{}: leader {} shows {}, not downloaded locally",
+                "{}: el lider {} muestra {}, que no esta descargado aqui",
+            ],
+            Msg::PeerLeaderInvalidId => [
+                "{}: leader {} sent an invalid workshop id {}; ignored",
+                "{}: el lider {} envio un id de Workshop invalido {}; se ignora",
+            ],
+            Msg::WorkshopDownloadFailed => {
+                ["{}: download failed: {:#}", "{}: fallo la descarga: {:#}"]
+            }
+            Msg::PkgTableIssue => ["pkg table issue: {}", "problema en la tabla del pkg: {}"],
+            Msg::PeerSyncFailed => ["sync peer {}: {:#}", "peer de sync {}: {:#}"],
+            Msg::PeerUnreachable => [
+                "sync peer {} unreachable: {:#}",
+                "peer de sync {} inalcanzable: {:#}",
+            ],
+            Msg::PeerFollowFailed => [
+                "{}: failed to follow {}: {:#}",
+                "{}: no se pudo seguir {}: {:#}",
+            ],
+            Msg::MpvpaperOtherInstance => [
+                "keeping mpvpaper pid={} on {}: owned by another instance (use --force)",
+                "se mantiene mpvpaper pid={} en {}: pertenece a otra instancia (usa --force)",
+            ],
+            Msg::KillFailed => ["failed to kill {}: {}", "no se pudo terminar {}: {}"],
+            Msg::OverlayRestoreFailed => [
+                "could not restore overlay wallpapers: {}",
+                "no se pudieron restaurar los overlay wallpapers: {}",
+            ],
+            Msg::SocketSkipped => ["skipped {}: {:#}", "se omite {}: {:#}"],
+            Msg::ProfileMediaMissing => [
+                "profile media missing from archive: {}",
+                "falta media del perfil en el archivo: {}",
+            ],
+            Msg::LiveUpdateFailed => [
+                "live update failed, applies on next launch: {:#}",
+                "fallo la actualizacion en vivo, se aplica en el proximo arranque: {:#}",
+            ],
+            Msg::SafeModeConfigIgnored => [
+                "ignoring safe_mode from config: {}",
+                "se ignora safe_mode de la config: {}",
+            ],
+            Msg::ProxyBakeFailed => [
+                "scene-compile: animated proxy bake failed: {:#}",
+                "scene-compile: fallo el horneado del proxy animado: {:#}",
+            ],
+            Msg::CustomFiltersIgnored => [
+                "custom filters ignored: {:#}",
+                "se ignoran los filtros propios: {:#}",
+            ],
+            Msg::ProceduralProxyFallback => [
+                "scene effect proxy using procedural fallback (no effect masks)",
+                "el proxy de efectos de la escena usa el modo procedural (sin mascaras de efecto)",
+            ],
+            Msg::NativeOverBudget => [
+                "native cost {:.1} over budget {:.1}: {} layers merged/dropped (see native-runtime-plan.json)",
+                "coste nativo {:.1} por encima del presupuesto {:.1}: {} capas fusionadas/descartadas (ver native-runtime-plan.json)",
+            ],
+            Msg::AudioSpectrumExperimental => [
+                "Audio spectrum and audio-reactive (bars) support is experimental. Enabling it is not recommended yet. For stable audio spectrums, use Kitowall Spectrum.",
+                "Soporte de espectros de audio y audio reactivo (barras) en fase de pruebas. No se recomienda su activacion por ahora. Si quieres espectros de audio estables, usa Kitowall Spectrum.",
+            ],
+            Msg::TexProxySuspicious => [
+                "gpu-play: extracted primary tex proxy looks suspicious (likely monochrome noise): {}",
+                "gpu-play: el proxy extraido del tex principal parece sospechoso (probablemente ruido monocromo): {}",
+            ],
+            Msg::NoSafeAlbedoTex => [
+                "gpu-play: no safe albedo tex candidate found; keeping primary tex proxy (preview fallback disabled)",
+                "gpu-play: no hay un tex albedo fiable; se mantiene el proxy del tex principal (sin recurrir al preview)",
+            ],
+            Msg::TexPayloadExtracted => [
+                "gpu-play: primary visual is .tex; extracted playable payload: {}",
+                "gpu-play: el visual principal es .tex; se extrajo un payload reproducible: {}",
+            ],
+            Msg::NativeRealtimeExperimental => [
+                "native-realtime transport requested: experimental backend enabled",
+                "transporte native-realtime solicitado: backend experimental activado",
+            ],
+            Msg::PortraitKeepsLandscape => [
+                "portrait output: {} keeps the landscape canvas",
+                "salida vertical: {} mantiene el lienzo horizontal",
+            ],
+            Msg::KitsuneOverlayFailed => [
+                "Kitsune overlay apply failed: {}",
+                "fallo al aplicar el overlay de Kitsune: {}",
+            ],
+            Msg::GpuTextOverlaysUnavailable => [
+                "gpu-play text overlays unavailable: {}",
+                "textos de gpu-play no disponibles: {}",
+            ],
+            Msg::SplitSceneUnsplitProxy => [
+                "split scene with {} viewports: mp4-proxy shows the unsplit canvas (use --transport native-realtime)",
+                "escena dividida en {} viewports: mp4-proxy muestra el lienzo sin dividir (usa --transport native-realtime)",
+            ],
+            Msg::AudioReactiveMuted => [
+                "--audio-reactive ignored with --mute-audio",
+                "--audio-reactive se ignora con --mute-audio",
+            ],
+            Msg::AudioReactiveNoMusic => [
+                "--audio-reactive ignored: scene has no music asset",
+                "--audio-reactive se ignora: la escena no tiene musica",
+            ],
+            Msg::RealtimePlanUnavailable => [
+                "native-realtime plan unavailable, falling back to mp4-proxy",
+                "plan native-realtime no disponible, se usa mp4-proxy",
+            ],
+            Msg::RealtimeFfmpegFailed => [
+                "native-realtime ffmpeg failed, falling back to mp4-proxy (log: {})",
+                "fallo el ffmpeg de native-realtime, se usa mp4-proxy (log: {})",
+            ],
+            Msg::NativeStreamFailed => [
+                "native-stream writer failed, trying the animated proxy (log: {})",
+                "fallo el escritor de native-stream, se prueba el proxy animado (log: {})",
+            ],
+            Msg::ShaderIncludeNotFound => [
+                "shader include not found: {}",
+                "include de shader no encontrado: {}",
+            ],
+            Msg::TextTimezoneIgnored => [
+                "ignoring text timezone override {}={}",
+                "se ignora la zona horaria de texto {}={}",
+            ],
+            Msg::FontFallback => [
+                "no installed font covers all glyphs of '{}'; using {}",
+                "ninguna fuente instalada cubre todos los glifos de '{}'; se usa {}",
+            ],
+            Msg::DefaultServicesList => [
+                "using default services list: {}",
+                "se usa la lista de servicios por defecto: {}",
+            ],
+            Msg::InstanceLeavesServices => [
+                "instance '{}' leaves conflicting services alone (use --force)",
+                "la instancia '{}' no toca los servicios en conflicto (usa --force)",
+            ],
+            Msg::StopServiceFailed => ["could not stop {}: {}", "no se pudo detener {}: {}"],
+            Msg::NoSystemdUnit => [
+                "{} has no systemd user unit; start it yourself",
+                "{} no tiene unidad de usuario de systemd; arrancalo a mano",
+            ],
+            Msg::TimezoneLocalFallback => ["{}; using local time", "{}; se usa la hora local"],
+            Msg::AlphaBackgroundPrefIgnored => [
+                "ignoring alpha background preference: {}",
+                "se ignora la preferencia de fondo alpha: {}",
+            ],
+            Msg::OverlayPrefsIgnored => [
+                "ignoring overlay preferences: {}",
+                "se ignoran las preferencias de overlay: {}",
+            ],
+            Msg::StaticBackendConfigIgnored => [
+                "ignoring static_backend from config: {}",
+                "se ignora static_backend de la config: {}",
+            ],
+            Msg::CommandFailed => ["{} failed: {}", "{} fallo: {}"],
+            Msg::StaticBackendRejected => [
+                "{} rejected the image, using mpvpaper",
+                "{} rechazo la imagen, se usa mpvpaper",
+            ],
+            Msg::OptimizedProxyFailed => [
+                "could not build optimized scene proxy, using original media: {}",
+                "no se pudo crear el proxy optimizado, se usa el archivo original: {}",
+            ],
+            Msg::GifConvertFailed => [
+                "could not convert GIF to video, playing it directly: {}",
+                "no se pudo convertir el GIF a video, se reproduce directamente: {}",
+            ],
+            Msg::CrossfadeTooShort => [
+                "loop-crossfade skipped: video is too short for fade window (duration={:.3}s, fade={:.3}s)",
+                "se omite loop-crossfade: el video es demasiado corto para el fundido (duracion={:.3}s, fundido={:.3}s)",
+            ],
+            Msg::CrossfadeFailed => [
+                "loop-crossfade proxy failed, falling back to optimized proxy: {}",
+                "fallo el proxy con loop-crossfade, se usa el proxy optimizado: {}",
+            ],
+            Msg::AudioReactiveNoDuration => [
+                "audio-reactive proxy skipped: could not probe soundtrack duration ({})",
+                "se omite el proxy audio-reactive: no se pudo leer la duracion de la musica ({})",
+            ],
+            Msg::AudioReactiveProxyFailed => [
+                "could not build audio-reactive proxy, using previous proxy: {}",
+                "no se pudo crear el proxy audio-reactive, se usa el proxy anterior: {}",
+            ],
+            Msg::AlphaCompositeFailed => [
+                "could not composite alpha video, playing it without transparency: {}",
+                "no se pudo componer el video con alpha, se reproduce sin transparencia: {}",
+            ],
+            Msg::ProresOverlayFailed => [
+                "could not build ProRes 4444 overlay proxy, overlay loses transparency: {}",
+                "no se pudo crear el proxy ProRes 4444 del overlay, pierde la transparencia: {}",
+            ],
+            Msg::QuirksIgnored => ["quirks ignored: {:#}", "se ignoran los quirks: {:#}"],
+            Msg::NestedCollection => [
+                "nested collection {} is not expanded",
+                "la coleccion anidada {} no se expande",
+            ],
+            Msg::SystemdFeatureMissing => [
+                "NOTIFY_SOCKET is set but this build has no systemd feature; systemd gets no READY/WATCHDOG",
+                "NOTIFY_SOCKET esta definido pero este binario no tiene la feature systemd; systemd no recibe READY/WATCHDOG",
+            ],
+            Msg::PlaybackStalled => [
+                "playback not responding on {}; skipping the watchdog ping",
+                "la reproduccion no responde en {}; no se envia el ping del watchdog",
+            ],
+            Msg::SceneSoundMissing => [
+                "sound '{}' of object '{}' not found; it stays silent",
+                "no se encontro el sonido '{}' del objeto '{}'; queda en silencio",
+            ],
+            Msg::SceneSoundFailed => [
+                "ambient sound mix failed, playing without scene sounds: {:#}",
+                "fallo la mezcla del sonido ambiente, se reproduce sin sonidos de la escena: {:#}",
+            ],
+            Msg::ProfileRulesIgnored => [
+                "ignoring profile_rules from config: {:#}",
+                "se ignoran las profile_rules de la config: {:#}",
+            ],
+            Msg::AudioBarsCalibrationIgnored => [
+                "ignoring audio bars calibrations: {:#}",
+                "se ignoran las calibraciones de barras de audio: {:#}",
+            ],
+            Msg::AudioBarsCalibrationStale => [
+                "audio bars calibration of {} no longer matches the scene; run calibrate-audio-bars again",
+                "la calibracion de barras de audio de {} ya no coincide con la escena; repite calibrate-audio-bars",
+            ],
+            Msg::CacheLayoutNewer => [
+                "cache {} has layout v{} from a newer version; leaving it as is",
+                "la cache {} tiene el formato v{} de una version mas nueva; se deja como esta",
+            ],
+            Msg::CacheMigrationFailed => [
+                "cache migration failed: {:#}",
+                "fallo la migracion de la cache: {:#}",
+            ],
+            Msg::LayerNamespaceUnsupported => [
+                "{}: mpvpaper has no --namespace; layer-shell namespace '{}' not applied",
+                "{}: mpvpaper no tiene --namespace; no se aplica el namespace layer-shell '{}'",
+            ],
+            Msg::AspectMismatch => [
+                "{}: wallpaper is {}x{} ({:.2}:1) but the output is {}x{} ({:.2}:1); try --scaling {} or --smart-crop",
+                "{}: el wallpaper es {}x{} ({:.2}:1) y la salida {}x{} ({:.2}:1); prueba --scaling {} o --smart-crop",
+            ],
+            Msg::SmartCropFailed => [
+                "{}: smart crop skipped: {:#}",
+                "{}: se omite el recorte inteligente: {:#}",
+            ],
+            Msg::CustomProfileMissing => [
+                "profile '{}' is not in config.json profiles; playing with balanced",
+                "el perfil '{}' no esta en profiles de config.json; se reproduce con balanced",
+            ],
+            Msg::CrashReportWritten => [
+                "crash report saved to {} (local only, attach it to a bug report)",
+                "informe del fallo guardado en {} (solo local, adjuntalo al reportar el error)",
+            ],
+            Msg::CrashReportFailed => [
+                "failed to write crash report: {:#}",
+                "no se pudo escribir el informe del fallo: {:#}",
+            ],
+            Msg::PlaybackSyncSkipped => [
+                "sync skipped for {} this round: {:#}",
+                "se omite la sincronizacion de {} en esta ronda: {:#}",
+            ],
+            Msg::SafeModePreview => [
+                "safe mode: playing preview {}",
+                "modo seguro: se reproduce el preview {}",
+            ],
+            Msg::TexPayloadProxy => [
+                "primary visual asset is .tex; extracted playable proxy from texture payload: {}",
+                "el visual principal es .tex; se extrajo un proxy reproducible del payload de la textura: {}",
+            ],
+            Msg::TexPayloadMissing => [
+                ".tex proxy extraction did not find playable payload. Using preview proxy: {}",
+                "la extraccion del .tex no encontro un payload reproducible. Se usa el preview: {}",
+            ],
+            Msg::VisualNotPlayable => [
+                "primary visual asset is not directly playable yet ({}). Using preview proxy: {}",
+                "el visual principal aun no se puede reproducir directamente ({}). Se usa el preview: {}",
+            ],
+            Msg::CompatFilesystem => [
+                "scene compatibility mode (filesystem): using {}",
+                "modo de compatibilidad de escena (archivos): se usa {}",
+            ],
+            Msg::CompatPkgExtract => [
+                "scene compatibility mode (pkg extract): {} -> {}",
+                "modo de compatibilidad de escena (extraccion del pkg): {} -> {}",
+            ],
+            Msg::WallpaperNotDirectory => [
+                "Wallpaper path is not a directory: {}",
+                "La ruta del wallpaper no es un directorio: {}",
+            ],
+            Msg::WorkshopNotDownloaded => [
+                "Workshop id {} is not downloaded in {} (subscribe to it in Steam or pass --downloads-root)",
+                "El id de Workshop {} no esta descargado en {} (suscribete en Steam o pasa --downloads-root)",
+            ],
+            Msg::WorkshopIdSpaces => [
+                "Invalid workshop id {}: remove the surrounding spaces",
+                "Id de Workshop invalido {}: quita los espacios alrededor",
+            ],
+            Msg::WallpaperMissing => [
+                "Wallpaper path does not exist: {} (workshop ids are digits only)",
+                "La ruta del wallpaper no existe: {} (los ids de Workshop son solo digitos)",
+            ],
+            Msg::WallpaperRootMissing => [
+                "Wallpaper root does not exist: {}",
+                "La carpeta del wallpaper no existe: {}",
+            ],
+            Msg::WallpaperTypeUnsupported => [
+                "{} wallpapers are not implemented yet in kitsune-livewallpaper MVP",
+                "Los wallpapers de tipo {} aun no estan implementados en kitsune-livewallpaper MVP",
+            ],
+            Msg::InvalidCollectionId => {
+                ["Invalid collection id: {}", "Id de coleccion invalido: {}"]
+            }
+            Msg::InvalidSince => [
+                "Invalid --since '{}', expected e.g. 30m, 12h, 1d",
+                "--since invalido '{}', se espera p. ej. 30m, 12h, 1d",
+            ],
+            Msg::InvalidSinceUnit => [
+                "Invalid --since unit '{}', use s, m, h, d or w",
+                "Unidad de --since invalida '{}', usa s, m, h, d o w",
+            ],
+            Msg::ReadOnlyLibraryWrite => [
+                "--read-only-library: {} would write {} inside the library ({}); generated files belong in the cache dir, pass another output path",
+                "--read-only-library: {} escribiria {} dentro de la biblioteca ({}); los archivos generados van en la cache, pasa otra ruta de salida",
+            ],
+            Msg::ReadOnlyLibrarySync => [
+                "--read-only-library: workshop sync downloads into {}; run it where the library is writable",
+                "--read-only-library: workshop sync descarga en {}; ejecutalo donde la biblioteca se pueda escribir",
+            ],
+            Msg::ProfileArchiveMissing => [
+                "Profile archive does not exist: {}",
+                "El archivo de perfil no existe: {}",
+            ],
+            Msg::ProfileVersionTooNew => [
+                "Profile version {} is newer than this build supports ({})",
+                "La version del perfil {} es mas nueva de lo que soporta esta compilacion ({})",
+            ],
+            Msg::NoRunningWallpaper => [
+                "No running wallpaper on {} to record",
+                "No hay un wallpaper en marcha en {} para grabar",
+            ],
+            Msg::NoOverlayToRemove => [
+                "No overlay wallpaper to remove on {}",
+                "No hay un wallpaper superpuesto que quitar en {}",
+            ],
+            Msg::PropertyNotNumber => [
+                "Property '{}' expects a number, got {}",
+                "La propiedad '{}' espera un numero, se recibio {}",
+            ],
+            Msg::PkgTableOffsetOverflow => [
+                "Entry {} at base offset {} + offset {} + length {} is past any file; fix the table",
+                "La entrada {} en base {} + offset {} + longitud {} queda fuera de cualquier archivo; corrige la tabla",
+            ],
+            Msg::PropertyOutOfRange => [
+                "Property '{}' value {} out of range",
+                "La propiedad '{}' tiene el valor {} fuera de rango",
+            ],
+        }
+    }
+}
+
+/// Fills `{}`, `{:#}` and `{:.N}` placeholders of `template` with `args` in
+/// order. Missing arguments leave the placeholder as written.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let spec = &rest[start + 1..start + len];
+        let written = match (spec, args.next()) {
+            ("", Some(arg)) => write!(out, "{}", arg).is_ok(),
+            (":#", Some(arg)) => write!(out, "{:#}", arg).is_ok(),
+            (spec, Some(arg)) => match spec.strip_prefix(":.").and_then(|p| p.parse().ok()) {
+                Some(precision) => write!(out, "{:.*}", precision, arg).is_ok(),
+                None => false,
+            },
+            _ => false,
+        };
+        if !written {
+            out.push_str(&rest[start..=start + len]);
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn message_in(lang: Lang, msg: Msg, args: &[&dyn Display]) -> String {
+    let [en, es] = msg.templates();
+    fill(if lang == Lang::Es { es } else { en }, args)
+}
+
+/// `msg` in the current language.
+pub fn message(msg: Msg, args: &[&dyn Display]) -> String {
+    message_in(current_lang(), msg, args)
+}
+
+/// Prints a translated `[warn]` line to stderr.
+macro_rules! warn_msg {
+    ($msg:expr $(, $arg:expr)* $(,)?) => {
+        eprintln!(
+            "[warn] {}",
+            $crate::i18n::message($msg, &[$(&$arg as &dyn std::fmt::Display),*])
+        )
+    };
+}
+pub(crate) use warn_msg;
+
+/// Returns early with a translated error.
+macro_rules! bail_msg {
+    ($msg:expr $(, $arg:expr)* $(,)?) => {
+        anyhow::bail!(
+            "{}",
+            $crate::i18n::message($msg, &[$(&$arg as &dyn std::fmt::Display),*])
+        )
+    };
+}
+pub(crate) use bail_msg;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_follow_the_locale_and_fill_format_specs() {
+        assert_eq!(lang_from_locale("es_MX.UTF-8"), Some(Lang::Es));
+        assert_eq!(lang_from_locale("en_US.UTF-8"), Some(Lang::En));
+        assert_eq!(lang_from_locale("C.UTF-8"), None);
+        assert_eq!(lang_from_locale(""), None);
+
+        let err = anyhow::anyhow!("inner").context("outer");
+        assert_eq!(
+            message_in(Lang::En, Msg::LiveUpdateFailed, &[&err]),
+            "live update failed, applies on next launch: outer: inner"
+        );
+        assert_eq!(
+            message_in(Lang::Es, Msg::CrossfadeTooShort, &[&1.23456, &0.5]),
+            "se omite loop-crossfade: el video es demasiado corto para el fundido (duracion=1.235s, fundido=0.500s)"
+        );
+        assert_eq!(fill("{} and {}", &[&1]), "1 and {}");
+        assert_eq!(
+            with_lang(Some(Lang::Es), || message(Msg::NoHistory, &[])),
+            "no hay historial de wallpapers"
+        );
+        let err = with_lang(Some(Lang::Es), || crate::journal::parse_since("3y")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unidad de --since invalida 'y', usa s, m, h, d o w"
+        );
+    }
+}
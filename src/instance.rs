@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+
+/// Instance a plain `kitsune-livewallpaper` run belongs to. mpvpaper sessions
+/// launched before instance tags existed count as this one.
+pub const DEFAULT_INSTANCE: &str = "main";
+
+/// Key of the mpv `script-opts` entry that tags a launched mpvpaper.
+const INSTANCE_KEY: &str = "kwe_instance=";
+
+struct InstanceScope {
+    name: String,
+    force: bool,
+}
+
+thread_local! {
+    static INSTANCE: RefCell<InstanceScope> = RefCell::new(InstanceScope {
+        name: DEFAULT_INSTANCE.to_string(),
+        force: false,
+    });
+}
+
+/// Instance tag of the running command (`--instance` / `KWE_INSTANCE`).
+pub fn current_instance() -> String {
+    INSTANCE.with(|s| s.borrow().name.clone())
+}
+
+/// `--force`: teardown also hits sessions of other instances.
+pub fn force_teardown() -> bool {
+    INSTANCE.with(|s| s.borrow().force)
+}
+
+/// Runs `f` as instance `name` for every launch and teardown on this thread.
+pub fn with_instance<R>(name: String, force: bool, f: impl FnOnce() -> R) -> R {
+    let previous = INSTANCE.with(|s| s.replace(InstanceScope { name, force }));
+    let result = f();
+    INSTANCE.with(|s| s.replace(previous));
+    result
+}
+
+/// `--instance`, else `$KWE_INSTANCE`, else [`DEFAULT_INSTANCE`]; reduced to
+/// characters that survive mpv option strings and socket file names.
+pub fn resolve_instance(cli_value: Option<String>) -> String {
+    let raw = cli_value
+        .or_else(|| std::env::var("KWE_INSTANCE").ok())
+        .unwrap_or_default();
+    let name = raw
+        .trim()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect::<String>();
+    if name.is_empty() {
+        DEFAULT_INSTANCE.to_string()
+    } else {
+        name
+    }
+}
+
+/// mpv option carrying the instance tag; libmpv ignores unknown script-opts,
+/// and the tag shows up in the mpvpaper command line for `pgrep -fa`.
+pub fn mpv_instance_option(instance: &str) -> String {
+    format!("script-opts-append={}{}", INSTANCE_KEY, instance)
+}
+
+/// Instance tag found in an mpvpaper command line, if any.
+fn session_instance(cmdline: &str) -> Option<&str> {
+    let start = cmdline.find(INSTANCE_KEY)? + INSTANCE_KEY.len();
+    let rest = &cmdline[start..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_')))
+        .unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// Whether a teardown by `instance` may stop the session in `cmdline`.
+pub fn owns_session(cmdline: &str, instance: &str, force: bool) -> bool {
+    force || session_instance(cmdline).unwrap_or(DEFAULT_INSTANCE) == instance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn teardown_only_matches_own_instance_unless_forced() {
+        let tagged = |name: &str| {
+            format!(
+                "mpvpaper -o --loop-file=inf {} input-ipc-server=/tmp/kwe-mpv-DP-1.sock DP-1 /c/kitsune-livewallpaper/a.mp4",
+                mpv_instance_option(name)
+            )
+        };
+        let legacy = "mpvpaper -o --loop-file=inf DP-1 /c/kitsune-livewallpaper/a.mp4";
+
+        assert!(owns_session(&tagged("main"), "main", false));
+        assert!(owns_session(legacy, "main", false));
+        assert!(!owns_session(&tagged("main"), "test", false));
+        assert!(!owns_session(legacy, "test", false));
+        assert!(!owns_session(&tagged("test2"), "test", false));
+        assert!(owns_session(&tagged("test"), "test", false));
+        assert!(owns_session(&tagged("main"), "test", true));
+
+        assert_eq!(resolve_instance(Some(" Test 1!".to_string())), "Test1");
+        assert_eq!(current_instance(), DEFAULT_INSTANCE);
+        assert_eq!(
+            with_instance("test".to_string(), true, || (
+                current_instance(),
+                force_teardown()
+            )),
+            ("test".to_string(), true)
+        );
+        assert!(!force_teardown());
+    }
+}
@@ -0,0 +1,285 @@
+use crate::app_paths::app_cache_dir;
+use crate::crash_report::{crash_reports_enabled, write_panic_report};
+use crate::i18n::{Msg, bail_msg, warn_msg};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Past this size the oldest half of the journal is dropped.
+const MAX_JOURNAL_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum JournalKind {
+    Apply,
+    Fallback,
+    ProxyBuild,
+    Crash,
+}
+
+impl JournalKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            JournalKind::Apply => "apply",
+            JournalKind::Fallback => "fallback",
+            JournalKind::ProxyBuild => "proxy-build",
+            JournalKind::Crash => "crash",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEvent {
+    pub at: String,
+    pub kind: JournalKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    pub message: String,
+    /// Project title of the wallpaper the event is about, when it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl JournalEvent {
+    pub fn new(kind: JournalKind, monitor: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            at: Local::now().to_rfc3339(),
+            kind,
+            monitor: monitor.map(str::to_string),
+            ok: true,
+            duration_ms: None,
+            message: message.into(),
+            title: None,
+        }
+    }
+
+    pub fn titled(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    pub fn since(mut self, started: Instant) -> Self {
+        self.duration_ms = Some(started.elapsed().as_millis() as u64);
+        self
+    }
+
+    pub fn failed(mut self) -> Self {
+        self.ok = false;
+        self
+    }
+}
+
+fn journal_path() -> PathBuf {
+    app_cache_dir().join("journal.jsonl")
+}
+
+/// Keeps the newest lines of `content` that fit in half of `max_bytes`.
+fn trim_journal(content: &str, max_bytes: usize) -> &str {
+    if content.len() <= max_bytes {
+        return content;
+    }
+    let mut cut = content.len() - max_bytes / 2;
+    while !content.is_char_boundary(cut) {
+        cut += 1;
+    }
+    match content[cut..].find('\n') {
+        Some(newline) => &content[cut + newline + 1..],
+        None => "",
+    }
+}
+
+fn append_event(path: &Path, event: &JournalEvent) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    if fs::metadata(path).is_ok_and(|m| m.len() as usize > MAX_JOURNAL_BYTES) {
+        let content = fs::read_to_string(path).unwrap_or_default();
+        fs::write(path, trim_journal(&content, MAX_JOURNAL_BYTES))
+            .with_context(|| format!("Failed writing {}", path.display()))?;
+    }
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(&line))
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// Appends `event` to the journal. Never fails the caller: a journal that
+/// cannot be written only costs a warning.
+pub fn record_event(event: JournalEvent) {
+    if let Err(err) = append_event(&journal_path(), &event) {
+        warn_msg!(Msg::JournalWriteFailed, err);
+    }
+}
+
+/// Records a proxy build that ran `ffmpeg` since `started`.
+pub fn record_proxy_build(what: &str, out: &Path, started: Instant, ok: bool) {
+    let event = JournalEvent::new(
+        JournalKind::ProxyBuild,
+        None,
+        format!("{} proxy -> {}", what, out.display()),
+    )
+    .since(started);
+    record_event(if ok { event } else { event.failed() });
+}
+
+/// Journals panics as crashes before the default hook prints them, then
+/// saves a local crash report and prints where it is.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = crash_reports_enabled().then(|| write_panic_report(info));
+        let mut message = info.to_string();
+        if let Some(Ok(path)) = &report {
+            message = format!("{} (report: {})", message, path.display());
+        }
+        record_event(JournalEvent::new(JournalKind::Crash, None, message).failed());
+        default_hook(info);
+        match report {
+            Some(Ok(path)) => warn_msg!(Msg::CrashReportWritten, path.display()),
+            Some(Err(err)) => warn_msg!(Msg::CrashReportFailed, err),
+            None => {}
+        }
+    }));
+}
+
+/// `30m`, `12h`, `1d`, `2w` (a bare number is seconds).
+pub fn parse_since(value: &str) -> Result<ChronoDuration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let Ok(amount) = amount.parse::<i64>() else {
+        bail_msg!(Msg::InvalidSince, value);
+    };
+    Ok(match unit {
+        "" | "s" => ChronoDuration::seconds(amount),
+        "m" => ChronoDuration::minutes(amount),
+        "h" => ChronoDuration::hours(amount),
+        "d" => ChronoDuration::days(amount),
+        "w" => ChronoDuration::weeks(amount),
+        _ => bail_msg!(Msg::InvalidSinceUnit, unit),
+    })
+}
+
+pub fn filter_events(
+    events: Vec<JournalEvent>,
+    since: Option<DateTime<Local>>,
+    monitor: Option<&str>,
+    kind: Option<JournalKind>,
+) -> Vec<JournalEvent> {
+    events
+        .into_iter()
+        .filter(|e| kind.is_none_or(|k| e.kind == k))
+        .filter(|e| monitor.is_none_or(|m| e.monitor.as_deref() == Some(m)))
+        .filter(|e| {
+            since
+                .is_none_or(|since| DateTime::parse_from_rfc3339(&e.at).is_ok_and(|at| at >= since))
+        })
+        .collect()
+}
+
+/// Journal events, oldest first; unreadable lines are skipped.
+pub fn load_journal() -> Result<Vec<JournalEvent>> {
+    let path = journal_path();
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Newest `count` events of the journal at `path`, read from its last
+/// `max_bytes` only so a crash handler never loads the whole file.
+pub fn tail_events(path: &Path, count: usize, max_bytes: u64) -> Vec<JournalEvent> {
+    let Ok(mut file) = File::open(path) else {
+        return Vec::new();
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = len.saturating_sub(max_bytes);
+    let mut tail = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_to_end(&mut tail).is_err() {
+        return Vec::new();
+    }
+    let tail = String::from_utf8_lossy(&tail);
+    // A cut line at the start of the tail is dropped by the parse.
+    let mut events = tail
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect::<Vec<JournalEvent>>();
+    events.split_off(events.len().saturating_sub(count))
+}
+
+/// Newest `count` events of the user's journal; see [`tail_events`].
+pub fn recent_journal_events(count: usize) -> Vec<JournalEvent> {
+    tail_events(&journal_path(), count, 64 * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_filters_by_age_monitor_and_kind() {
+        assert_eq!(parse_since("90m").unwrap(), ChronoDuration::minutes(90));
+        assert_eq!(parse_since("1d").unwrap(), ChronoDuration::hours(24));
+        assert!(parse_since("1y").is_err());
+
+        let mut old = JournalEvent::new(JournalKind::Apply, Some("DP-1"), "apply 111");
+        old.at = (Local::now() - ChronoDuration::days(3)).to_rfc3339();
+        let events = vec![
+            old,
+            JournalEvent::new(JournalKind::Apply, Some("DP-1"), "apply 222").failed(),
+            JournalEvent::new(JournalKind::Fallback, Some("HDMI-A-1"), "preview"),
+            JournalEvent::new(JournalKind::ProxyBuild, None, "gif proxy"),
+        ];
+        let since = Some(Local::now() - parse_since("1d").unwrap());
+        let recent = filter_events(events.clone(), since, Some("DP-1"), None);
+        assert_eq!(recent.len(), 1);
+        assert!(!recent[0].ok);
+        let builds = filter_events(events, None, None, Some(JournalKind::ProxyBuild));
+        assert_eq!(builds[0].message, "gif proxy");
+
+        let content = "aaaa\nbbbb\ncccc\ndddd\n";
+        assert_eq!(trim_journal(content, 100), content);
+        assert_eq!(trim_journal(content, 12), "dddd\n");
+    }
+
+    #[test]
+    fn tail_events_reads_only_the_end_of_the_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        for n in 0..200 {
+            append_event(
+                &path,
+                &JournalEvent::new(JournalKind::Apply, None, format!("apply {}", n)),
+            )
+            .unwrap();
+        }
+        let events = tail_events(&path, 3, 1024);
+        let messages = events
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["apply 197", "apply 198", "apply 199"]);
+        assert!(tail_events(&path, 500, 1024).len() < 200);
+        assert!(tail_events(&dir.path().join("missing"), 3, 1024).is_empty());
+    }
+}
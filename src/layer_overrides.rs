@@ -0,0 +1,378 @@
+use crate::app_paths::app_config_dir;
+use crate::asset_resolver::AssetResolver;
+use crate::custom_filters::CustomFilters;
+use crate::i18n::{Msg, warn_msg};
+use crate::wallpaper_quirks::{object_matches, workshop_id_for_root};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parts of one wallpaper left out of the graph and emulation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayerOverride {
+    /// Object ids (numbers) or names (strings), as in quirk `force_visible`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_objects: Vec<Value>,
+    /// Effect files (`effects/shake/effect.json`) or their folder (`effects/shake`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_effects: Vec<String>,
+    /// ffmpeg chains added to the effect-proxy graph.
+    #[serde(default, skip_serializing_if = "CustomFilters::is_empty")]
+    pub custom_filters: CustomFilters,
+}
+
+impl LayerOverride {
+    pub fn is_empty(&self) -> bool {
+        self.skip_objects.is_empty()
+            && self.skip_effects.is_empty()
+            && self.custom_filters.is_empty()
+    }
+
+    fn skips_object(&self, object: &Value) -> bool {
+        self.skip_objects.iter().any(|s| object_matches(object, s))
+    }
+
+    fn skips_effect(&self, file: &str) -> bool {
+        let file = file.trim().replace('\\', "/").to_ascii_lowercase();
+        self.skip_effects.iter().any(|skip| {
+            let skip = skip.trim().trim_end_matches('/').to_ascii_lowercase();
+            file == skip || file.starts_with(&format!("{}/", skip))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerOverrides {
+    pub version: u32,
+    /// workshop id (or wallpaper path) -> override
+    pub wallpapers: BTreeMap<String, LayerOverride>,
+}
+
+impl Default for LayerOverrides {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            wallpapers: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn layer_overrides_path() -> PathBuf {
+    app_config_dir().join("overrides.json")
+}
+
+pub fn load_layer_overrides() -> Result<LayerOverrides> {
+    let path = layer_overrides_path();
+    if !path.is_file() {
+        return Ok(LayerOverrides::default());
+    }
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+fn save_layer_overrides(overrides: &LayerOverrides) -> Result<()> {
+    let path = layer_overrides_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(overrides)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// Key of a wallpaper in `overrides.json`: its workshop id, or its path for
+/// wallpapers outside the workshop folder.
+pub fn override_key(root: &Path, project_json: Option<&Value>) -> String {
+    workshop_id_for_root(root, project_json).unwrap_or_else(|| root.to_string_lossy().to_string())
+}
+
+fn read_project_json(root: &Path) -> Option<Value> {
+    fs::read(root.join("project.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_slice(&raw).ok())
+}
+
+pub fn layer_override_for_wallpaper(
+    root: &Path,
+    project_json: Option<&Value>,
+) -> Option<LayerOverride> {
+    let overrides = match load_layer_overrides() {
+        Ok(overrides) => overrides,
+        Err(err) => {
+            warn_msg!(Msg::LayerOverridesIgnored, err);
+            return None;
+        }
+    };
+    overrides
+        .wallpapers
+        .get(&override_key(root, project_json))
+        .cloned()
+}
+
+/// Same as `layer_override_for_wallpaper`, reading `project.json` from the
+/// wallpaper dir.
+pub fn load_layer_override(root: &Path) -> Option<LayerOverride> {
+    layer_override_for_wallpaper(root, read_project_json(root).as_ref())
+}
+
+/// Removes skipped objects and hides skipped effects in `scene_json`, and
+/// returns notes describing what changed. Effects are hidden instead of
+/// removed so per-effect indexes keep lining up.
+pub fn apply_layer_overrides(
+    scene_json: &mut Value,
+    layer_override: &LayerOverride,
+) -> Vec<String> {
+    let mut notes = Vec::new();
+    let Some(objects) = scene_json.get_mut("objects").and_then(|v| v.as_array_mut()) else {
+        return notes;
+    };
+    objects.retain(|object| {
+        if !layer_override.skips_object(object) {
+            return true;
+        }
+        notes.push(format!(
+            "Override: object '{}' skipped",
+            object_label(object)
+        ));
+        false
+    });
+    for object in objects.iter_mut() {
+        let label = object_label(object);
+        let Some(effects) = object.get_mut("effects").and_then(|v| v.as_array_mut()) else {
+            continue;
+        };
+        for effect in effects.iter_mut() {
+            let Some(file) = effect.get("file").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if !layer_override.skips_effect(file) {
+                continue;
+            }
+            notes.push(format!("Override: effect {} on '{}' skipped", file, label));
+            if let Some(effect) = effect.as_object_mut() {
+                effect.insert("visible".to_string(), Value::Bool(false));
+            }
+        }
+    }
+    notes
+}
+
+fn object_label(object: &Value) -> String {
+    let id = object.get("id").and_then(|v| v.as_u64()).unwrap_or(0);
+    match object.get("name").and_then(|v| v.as_str()) {
+        Some(name) if !name.is_empty() => format!("{} (#{})", name, id),
+        _ => format!("#{}", id),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneEffectInfo {
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneLayerInfo {
+    pub id: u64,
+    pub name: String,
+    /// `image`, `particle`, `text`, `sound` or `other`.
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u64>,
+    pub skipped: bool,
+    pub effects: Vec<SceneEffectInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneLayersReport {
+    pub wallpaper: String,
+    /// Key this wallpaper uses in `overrides.json`.
+    pub override_key: String,
+    pub overrides: LayerOverride,
+    pub layers: Vec<SceneLayerInfo>,
+}
+
+fn object_kind(object: &Value) -> &'static str {
+    ["image", "particle", "text", "sound"]
+        .into_iter()
+        .find(|key| object.get(*key).is_some_and(|v| !v.is_null()))
+        .unwrap_or("other")
+}
+
+/// Objects of `scene_json` with their effects, flagged with what
+/// `layer_override` skips.
+pub fn scene_layers(scene_json: &Value, layer_override: &LayerOverride) -> Vec<SceneLayerInfo> {
+    scene_json
+        .get("objects")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .map(|object| SceneLayerInfo {
+            id: object.get("id").and_then(|v| v.as_u64()).unwrap_or(0),
+            name: object
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            kind: object_kind(object).to_string(),
+            parent: object.get("parent").and_then(|v| match v {
+                Value::Number(n) => n.as_u64(),
+                Value::String(s) => s.trim().parse().ok(),
+                _ => None,
+            }),
+            skipped: layer_override.skips_object(object),
+            effects: object
+                .get("effects")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|effect| {
+                    let file = effect.get("file")?.as_str()?.to_string();
+                    Some(SceneEffectInfo {
+                        skipped: layer_override.skips_effect(&file),
+                        name: effect
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .filter(|n| !n.is_empty())
+                            .map(str::to_string),
+                        file,
+                    })
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// `layers list`: every object and effect of the scene in `root`, with the
+/// ids and files `layers skip` takes.
+pub fn list_scene_layers(wallpaper: &str, root: &Path) -> Result<SceneLayersReport> {
+    let resolver = AssetResolver::new(root)?;
+    let Some(scene_asset) = resolver
+        .resolve("scene.json")
+        .or_else(|| resolver.resolve("gifscene.json"))
+    else {
+        bail!("No scene.json/gifscene.json found in {}", root.display());
+    };
+    let scene_json: Value = serde_json::from_slice(&scene_asset.bytes)?;
+    let key = override_key(root, read_project_json(root).as_ref());
+    let overrides = load_layer_overrides()?
+        .wallpapers
+        .remove(&key)
+        .unwrap_or_default();
+    Ok(SceneLayersReport {
+        wallpaper: wallpaper.to_string(),
+        layers: scene_layers(&scene_json, &overrides),
+        override_key: key,
+        overrides,
+    })
+}
+
+/// `--object 7` is an object id; anything else is matched by name.
+pub fn parse_object_selector(raw: &str) -> Value {
+    raw.trim()
+        .parse::<u64>()
+        .map(Value::from)
+        .unwrap_or_else(|_| Value::from(raw.trim()))
+}
+
+/// Adds (`skip`) or removes the given selectors for the wallpaper in `root`.
+/// Removing with no selectors clears the wallpaper's entry. Returns what is
+/// stored afterwards.
+pub fn update_layer_override(
+    root: &Path,
+    objects: &[String],
+    effects: &[String],
+    skip: bool,
+    dry_run: bool,
+) -> Result<LayerOverride> {
+    let key = override_key(root, read_project_json(root).as_ref());
+    let mut overrides = load_layer_overrides()?;
+    let entry = overrides.wallpapers.entry(key.clone()).or_default();
+    let selectors = objects
+        .iter()
+        .map(|o| parse_object_selector(o))
+        .collect::<Vec<_>>();
+    if skip {
+        for selector in selectors {
+            if !entry.skip_objects.contains(&selector) {
+                entry.skip_objects.push(selector);
+            }
+        }
+        for effect in effects {
+            if !entry.skip_effects.contains(effect) {
+                entry.skip_effects.push(effect.clone());
+            }
+        }
+    } else if objects.is_empty() && effects.is_empty() {
+        *entry = LayerOverride::default();
+    } else {
+        entry.skip_objects.retain(|s| !selectors.contains(s));
+        entry.skip_effects.retain(|e| !effects.contains(e));
+    }
+    let result = entry.clone();
+    if result.is_empty() {
+        overrides.wallpapers.remove(&key);
+    }
+    if dry_run {
+        eprintln!(
+            "[dry-run] {} overrides for {}: {} object(s), {} effect(s)",
+            layer_overrides_path().display(),
+            key,
+            result.skip_objects.len(),
+            result.skip_effects.len()
+        );
+        return Ok(result);
+    }
+    save_layer_overrides(&overrides)?;
+    eprintln!(
+        "[ok] layer overrides for {}: {} object(s), {} effect(s) skipped",
+        key,
+        result.skip_objects.len(),
+        result.skip_effects.len()
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn overrides_drop_objects_and_hide_effects() {
+        let mut scene = json!({
+            "objects": [
+                {"id": 1, "name": "Background", "image": "models/bg.json",
+                 "effects": [
+                    {"file": "effects/shake/effect.json", "name": "Shake"},
+                    {"file": "effects/waterripple/effect.json"}
+                 ]},
+                {"id": 2, "name": "Glitch", "image": "models/glitch.json"},
+                {"id": 3, "name": "Clock", "text": {"value": "12:00"}}
+            ]
+        });
+        let layer_override = LayerOverride {
+            skip_objects: vec![parse_object_selector("glitch"), parse_object_selector("3")],
+            skip_effects: vec!["effects/shake".to_string()],
+            ..Default::default()
+        };
+
+        let layers = scene_layers(&scene, &layer_override);
+        assert_eq!(layers[1].kind, "image");
+        assert_eq!(layers[2].kind, "text");
+        assert!(!layers[0].skipped && layers[1].skipped && layers[2].skipped);
+        assert!(layers[0].effects[0].skipped && !layers[0].effects[1].skipped);
+
+        let notes = apply_layer_overrides(&mut scene, &layer_override);
+        assert_eq!(notes.len(), 3);
+        let objects = scene["objects"].as_array().unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0]["effects"][0]["visible"], false);
+        assert!(objects[0]["effects"][1].get("visible").is_none());
+    }
+}
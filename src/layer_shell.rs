@@ -0,0 +1,146 @@
+use crate::app_paths::user_config_path;
+use crate::cli::LayerShellLayer;
+use crate::command_runner::CommandExt;
+use crate::i18n::{Msg, warn_msg};
+use crate::startup_config::load_config;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Unset fields keep mpvpaper's defaults (`background`, namespace `mpvpaper`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerShellPrefs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<LayerShellLayer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+impl LayerShellPrefs {
+    pub fn is_empty(&self) -> bool {
+        self.layer.is_none() && self.namespace.is_none()
+    }
+
+    /// Applies the fields set in `other`; `clear` drops the stored values first.
+    pub fn update(&mut self, other: &LayerShellPrefs, clear: bool) {
+        if clear {
+            *self = LayerShellPrefs::default();
+        }
+        self.layer = other.layer.or(self.layer);
+        self.namespace = other.namespace.clone().or(self.namespace.take());
+    }
+}
+
+/// Compositors match layer rules on the namespace, so it must be one word.
+pub fn validate_namespace(namespace: &str) -> Result<()> {
+    if namespace.is_empty()
+        || namespace
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control())
+    {
+        bail!(
+            "Invalid layer-shell namespace '{}': expected a single word",
+            namespace
+        );
+    }
+    Ok(())
+}
+
+/// Only some mpvpaper builds take `--namespace`; older ones exit on it.
+fn mpvpaper_supports_namespace() -> bool {
+    Command::new("mpvpaper")
+        .arg("--help")
+        .run_output()
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout).contains("--namespace")
+                || String::from_utf8_lossy(&output.stderr).contains("--namespace")
+        })
+}
+
+/// mpvpaper arguments for `prefs`. Returns the namespace that could not be
+/// passed when mpvpaper lacks `--namespace`.
+pub fn mpvpaper_layer_args(
+    prefs: &LayerShellPrefs,
+    supports_namespace: bool,
+) -> (Vec<String>, Option<String>) {
+    let mut args = Vec::new();
+    if let Some(layer) = prefs.layer {
+        let name = match layer {
+            LayerShellLayer::Background => "background",
+            LayerShellLayer::Bottom => "bottom",
+        };
+        args.extend(["--layer".to_string(), name.to_string()]);
+    }
+    let mut skipped = None;
+    if let Some(namespace) = &prefs.namespace {
+        if supports_namespace {
+            args.extend(["--namespace".to_string(), namespace.clone()]);
+        } else {
+            skipped = Some(namespace.clone());
+        }
+    }
+    (args, skipped)
+}
+
+/// `monitor_layers` entry of config.json for `monitor`, as mpvpaper arguments.
+pub fn monitor_layer_args(monitor: &str) -> Vec<String> {
+    let prefs = load_config(&user_config_path())
+        .ok()
+        .and_then(|mut cfg| cfg.monitor_layers.remove(monitor))
+        .unwrap_or_default();
+    let supports_namespace = prefs.namespace.is_some() && mpvpaper_supports_namespace();
+    let (args, skipped) = mpvpaper_layer_args(&prefs, supports_namespace);
+    if let Some(namespace) = skipped {
+        warn_msg!(Msg::LayerNamespaceUnsupported, monitor, namespace);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_layer_and_namespace_become_mpvpaper_arguments() {
+        let mut prefs = LayerShellPrefs {
+            layer: Some(LayerShellLayer::Bottom),
+            ..LayerShellPrefs::default()
+        };
+        prefs.update(
+            &LayerShellPrefs {
+                namespace: Some("kitsune".to_string()),
+                ..LayerShellPrefs::default()
+            },
+            false,
+        );
+        assert_eq!(
+            mpvpaper_layer_args(&prefs, true),
+            (
+                vec![
+                    "--layer".to_string(),
+                    "bottom".to_string(),
+                    "--namespace".to_string(),
+                    "kitsune".to_string()
+                ],
+                None
+            )
+        );
+        assert_eq!(
+            mpvpaper_layer_args(&prefs, false),
+            (
+                vec!["--layer".to_string(), "bottom".to_string()],
+                Some("kitsune".to_string())
+            )
+        );
+        assert_eq!(
+            serde_json::to_value(&prefs).unwrap(),
+            serde_json::json!({"layer": "bottom", "namespace": "kitsune"})
+        );
+
+        prefs.update(&LayerShellPrefs::default(), true);
+        assert!(prefs.is_empty());
+        assert!(validate_namespace("kitsune-wall").is_ok());
+        assert!(validate_namespace("two words").is_err());
+        assert!(validate_namespace("").is_err());
+    }
+}
@@ -1,63 +1,245 @@
 use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::process::Command;
+use std::time::Instant;
 
+pub mod app_paths;
+pub mod aspect_fit;
 pub mod asset_resolver;
 pub mod audio;
+pub mod audio_bars_calibration;
+pub mod audio_test;
+pub mod cache_migration;
 pub mod cli;
+pub mod colorspace;
+pub mod command_runner;
+pub mod compat;
+pub mod crash_report;
+pub mod custom_filters;
+pub mod custom_profiles;
+pub mod display_orientation;
+pub mod greeter;
+pub mod history;
+pub mod i18n;
+pub mod instance;
+pub mod journal;
+pub mod layer_overrides;
+pub mod layer_shell;
+pub mod library_guard;
 pub mod library_scan;
+pub mod library_stats;
+pub mod mpv_options;
+pub mod output;
+pub mod overlay_sidecar;
+pub mod overlay_wallpaper;
+pub mod peer_sync;
+pub mod pkg_obfuscation;
 pub mod playback;
+pub mod playback_sync;
+pub mod profile_bundle;
+pub mod profile_rules;
+pub mod property_overrides;
+pub mod proxy_invalidate;
+pub mod proxy_meta;
+pub mod record;
+pub mod report_bundle;
+pub mod roadmap_export;
+pub mod safe_mode;
+pub mod scene_bundle;
+pub mod scene_capabilities;
 pub mod scene_effect_proxy;
+pub mod scene_emulation_trace;
+pub mod scene_fps;
 pub mod scene_gpu_backend;
 pub mod scene_gpu_graph;
+pub mod scene_gpu_transport;
 pub mod scene_native_renderer;
 pub mod scene_native_runtime;
+pub mod scene_native_stream;
+pub mod scene_particles;
 pub mod scene_pkg;
 pub mod scene_plan;
+pub mod scene_properties;
 pub mod scene_renderer;
 pub mod scene_runtime;
 pub mod scene_script;
+pub mod scene_shaders;
+pub mod scene_sound;
 pub mod scene_text;
+pub mod scene_thumbnail;
+pub mod scene_tile_grid;
+pub mod scene_verify;
+pub mod scene_visible_expr;
+pub mod sd_notify;
 pub mod services;
 pub mod startup_config;
+pub mod static_backend;
+#[cfg(feature = "testsupport")]
+pub mod testsupport;
 pub mod tex_payload;
 pub mod types;
+pub mod version_info;
 pub mod video_opt;
 pub mod video_tune;
 pub mod wallpaper;
+pub mod wallpaper_quirks;
+pub mod workshop;
 
+use aspect_fit::{AspectFit, with_aspect_fit};
 use audio::{probe_audio, stream_audio_levels};
-use cli::{Cli, Commands, ConfigCommands, ServiceAutostartCommands};
+use audio_bars_calibration::calibrate_audio_bars;
+use audio_test::run_audio_test;
+use cache_migration::migrate_cache_dir;
+use cli::{
+    Cli, Commands, CompatCommands, ConfigCommands, GreeterCommands, JournalCommands, Lang,
+    LayersCommands, MuteState, OutputFormat, OverlayPlanCommands, OverlayWallpaperCommands,
+    ProfileCommands, ProfilesCommands, PropertyCommands, ProxyCommands, ReportCommands,
+    ScenePkgCommands, SceneShaderCommands, SceneUniformCommands, ServiceAutostartCommands,
+    ServicesCommands, StatsFormat, TextRenderMode, WorkshopCommands,
+};
+use compat::compare_with_other_log;
+use custom_filters::CustomFilters;
+use custom_profiles::{
+    CustomProfile, configured_profiles, list_profiles, profile_proxy_settings, validate_profiles,
+    with_config_profiles,
+};
+use display_orientation::{OutputFraming, is_portrait_output};
+use greeter::{GreeterInstallArgs, install_greeter};
+use history::{load_history, record_applied, save_history};
+use i18n::{Msg, bail_msg, message, message_in, warn_msg, with_lang};
+use instance::{resolve_instance, with_instance};
+use journal::{JournalEvent, JournalKind, filter_events, load_journal, parse_since, record_event};
+use layer_overrides::{list_scene_layers, update_layer_override};
+use layer_shell::{LayerShellPrefs, validate_namespace};
+use library_guard::with_read_only_library;
 use library_scan::{build_library_roadmap, scan_library};
-use playback::{launch_mpvpaper, launch_mpvpaper_with_extra, stop_existing_mpvpaper_for_monitor};
-use scene_effect_proxy::{build_scene_audio_bars_overlay, maybe_build_scene_animated_proxy};
+use library_stats::{build_library_stats, render_prometheus, share_library_stats};
+use mpv_options::{cli_mpv_options, merge_mpv_options, with_mpv_options};
+use output::{output_format, print_report, with_output_format};
+use overlay_sidecar::{
+    build_overlay_sidecar, load_overlay_sidecar, watch_overlay_sidecars, write_overlay_sidecar,
+};
+use overlay_wallpaper::{
+    hide_overlay_live, load_overlay_state, resolve_overlay_source, save_overlay_state,
+    set_overlay_paused, show_overlay_live,
+};
+use peer_sync::{PeerSyncArgs, run_peer_sync, sync_secret_from_env};
+use pkg_obfuscation::with_allow_obfuscated;
+use playback::{
+    hot_swap_mpvpaper_entry, launch_mpvpaper, launch_mpvpaper_with_extra, load_global_mute,
+    mpv_ipc_socket_path, running_mpv_ipc_sockets, set_global_mute,
+    stop_existing_mpvpaper_for_monitor,
+};
+use playback_sync::run_playback_sync;
+use profile_rules::{MatchedRule, match_rule_for, validate_profile_rules};
+use property_overrides::{
+    clear_property_override, load_property_overrides, monitor_property_overrides,
+    resolve_transition_ms, set_property_override,
+};
+use proxy_invalidate::invalidate_proxies;
+use record::record_monitor;
+use roadmap_export::{export_roadmap, fresh_roadmap_cache, load_roadmap_cache, save_roadmap_cache};
+use safe_mode::{resolve_safe_mode, restrict_gpu_play, safe_mode, with_safe_mode};
+use scene_bundle::{
+    compile_kwescene, default_kwescene_cache_dir, extract_kwescene, is_kwescene_path,
+};
+use scene_capabilities::build_wallpaper_capabilities;
+use scene_effect_proxy::{
+    build_scene_audio_bars_overlay, eval_realtime_plan_uniforms, maybe_build_scene_animated_proxy,
+};
+use scene_emulation_trace::{print_emulation_trace_summary, reset_emulation_trace};
+use scene_fps::{DEFAULT_REFRESH_FPS, infer_scene_fps};
 use scene_gpu_backend::{SceneGpuPlayArgs, scene_gpu_play};
 use scene_gpu_graph::build_scene_gpu_graph;
-use scene_native_runtime::build_native_runtime_plan;
+use scene_native_runtime::{apply_cost_budget, build_native_runtime_plan, native_cost_budget};
+use scene_native_stream::run_native_stream;
+use scene_particles::build_scene_particle_report;
 use scene_pkg::{
-    best_video_entry, default_scene_cache_root, extract_entry_to_cache, parse_scene_pkg,
+    PkgTable, best_video_entry, default_scene_cache_root, export_pkg_table, extract_entry_to_cache,
+    find_scene_pkg_path, import_pkg_table, parse_scene_pkg, refresh_scene_cache,
 };
 use scene_plan::build_scene_plan;
-use scene_renderer::build_scene_render_session;
+use scene_properties::build_scene_property_schema;
+use scene_renderer::{build_scene_render_session, find_warm_start_entry, save_warm_start_entry};
 use scene_runtime::run_scene_runtime;
+use scene_shaders::dump_scene_shaders;
 use scene_text::{
-    build_scene_drawtext_filter, run_text_refresh, run_text_refresh_loop, start_text_refresh_daemon,
+    TextTimezones, build_scene_drawtext_filter, build_scene_osd_text, run_text_refresh,
+    run_text_refresh_loop, start_text_refresh_daemon,
+};
+use scene_thumbnail::{
+    default_preview_strip_path, default_thumbnail_path, parse_thumbnail_size, render_preview_strip,
+    render_scene_thumbnail,
 };
+use scene_verify::{VerifyExpectations, verify_scene_render};
+use sd_notify::{playback_responding, run_watchdog};
 use services::{
-    autostart_service_status, default_services, disable_autostart_service, enable_autostart_service,
-    install_autostart_service, remove_autostart_service, start_services, stop_services,
+    autostart_service_status, disable_autostart_service, effective_services,
+    enable_autostart_service, install_autostart_service, remove_autostart_service, services_status,
+    start_services, stop_services,
 };
 use startup_config::{
-    MonitorEntry, StartupCommand, entry_fingerprint, load_config as load_startup_config,
-    load_state as load_startup_state, remove_entry as remove_startup_entry,
-    save_config as save_startup_config, save_state as save_startup_state, upsert_entry,
+    MonitorEntry, OverlayPrefs, StartupCommand, alpha_background_for_wallpaper, entry_fingerprint,
+    load_config as load_startup_config, load_state as load_startup_state, overlays_for_wallpaper,
+    remove_entry as remove_startup_entry, save_config as save_startup_config,
+    save_state as save_startup_state, upsert_entry, wallpaper_overlay_key,
+};
+use tex_payload::{extract_playable_proxy_from_tex, probe_mp4_video_info};
+use types::{SceneDiagnostics, VideoPlaybackHints, WallpaperType};
+use version_info::build_version_report;
+use video_opt::{
+    AlphaBackground, maybe_build_alpha_composite_proxy, maybe_build_alpha_overlay_proxy,
+    maybe_build_gif_proxy, maybe_build_loop_crossfade_proxy, maybe_build_optimized_proxy,
+    probe_alpha_stream, probe_video_size,
 };
-use tex_payload::extract_playable_proxy_from_tex;
-use types::{SceneDiagnostics, WallpaperType};
-use video_opt::{maybe_build_loop_crossfade_proxy, maybe_build_optimized_proxy};
 use video_tune::{auto_tune_preset, preset_values};
-use wallpaper::{find_scene_compatible_video, inspect_wallpaper, resolve_wallpaper_path};
+use wallpaper::{
+    find_scene_compatible_video, inspect_wallpaper, is_video_file, resolve_existing_wallpaper,
+    resolve_wallpaper_path, wallpaper_name, wallpaper_name_at,
+};
+use workshop::{CollectionSyncArgs, sync_collection};
+
+/// Generic clock drawn when a scene has no text objects and the user opted in.
+const FALLBACK_CLOCK_VF: &str = "vf=drawtext=text=%{localtime\\:%a-%d-%b-%H\\\\:%M}:fontcolor=white:fontsize=44:x=(w-text_w)/2:y=28:box=1:boxcolor=0x00000088:boxborderw=14";
+
+/// Prepends `filter` to the `vf=` chain of an mpv extra option.
+pub(crate) fn with_vf_prefix(opt: Option<String>, filter: &str) -> String {
+    match opt {
+        Some(existing) => {
+            let chain = existing.strip_prefix("vf=").unwrap_or(&existing);
+            format!("vf={},{}", filter, chain)
+        }
+        None => format!("vf={}", filter),
+    }
+}
+
+/// mpv options for a video's `project.json` playback hints. `vf` carries
+/// filters already chosen; the fps filter is skipped when a proxy was
+/// already encoded at the hinted rate.
+fn video_hint_opts(
+    hints: Option<VideoPlaybackHints>,
+    mut vf: Option<String>,
+    fps_filter: bool,
+) -> Vec<String> {
+    let mut opts = Vec::new();
+    if let Some(hints) = hints {
+        eprintln!(
+            "[ok] project.json playback hints: speed={} fps={}",
+            hints.speed.unwrap_or(1.0),
+            hints.fps.map_or_else(|| "-".to_string(), |f| f.to_string())
+        );
+        if fps_filter && let Some(fps) = hints.fps {
+            vf = Some(with_vf_prefix(vf, &format!("fps={}", fps)));
+        }
+        if let Some(speed) = hints.speed {
+            opts.push(format!("speed={:.3}", speed));
+        }
+    }
+    opts.extend(vf);
+    opts
+}
 
 fn scene_diagnostics_json(diag: Option<&SceneDiagnostics>) -> String {
     diag.and_then(|s| serde_json::to_string_pretty(s).ok())
@@ -102,10 +284,69 @@ fn find_preview_fallback(root: &std::path::Path) -> Option<std::path::PathBuf> {
 }
 
 fn default_video_live_cache_root() -> std::path::PathBuf {
-    if let Ok(home) = std::env::var("HOME") {
-        return std::path::PathBuf::from(home).join(".cache/kitsune-livewallpaper/video-live");
+    app_paths::app_cache_dir().join("video-live")
+}
+
+/// GIF entries play from a cached H.264 conversion; anything else as-is.
+fn gif_proxy_entry(entry: &std::path::Path, dry_run: bool) -> Result<std::path::PathBuf> {
+    let proxy =
+        maybe_build_gif_proxy(entry, &default_video_live_cache_root().join("gif"), dry_run)?;
+    if proxy != entry {
+        eprintln!("[ok] GIF proxy: {}", proxy.display());
+    }
+    Ok(proxy)
+}
+
+/// Flattens an alpha video onto the configured background so its
+/// transparent parts do not come out black; opaque media is returned as-is.
+fn alpha_composite_entry(
+    entry: &std::path::Path,
+    root: Option<&std::path::Path>,
+    downloads_root: &std::path::Path,
+    dry_run: bool,
+) -> Result<std::path::PathBuf> {
+    let Some(stream) = probe_alpha_stream(entry) else {
+        return Ok(entry.to_path_buf());
+    };
+    let background = match alpha_background_for_wallpaper(root) {
+        Some(value) => parse_alpha_background(&value, downloads_root),
+        None => AlphaBackground::Color("black".to_string()),
+    };
+    let proxy = maybe_build_alpha_composite_proxy(
+        entry,
+        &stream,
+        &background,
+        &default_video_live_cache_root().join("alpha"),
+        dry_run,
+    )?;
+    if proxy != entry {
+        eprintln!(
+            "[ok] alpha video composited over {}: {}",
+            background,
+            proxy.display()
+        );
+    }
+    Ok(proxy)
+}
+
+/// `black`, `#203040` or `0x203040` are colors; anything else is a media
+/// file or wallpaper resolved like an overlay wallpaper source.
+fn parse_alpha_background(value: &str, downloads_root: &std::path::Path) -> AlphaBackground {
+    let value = value.trim();
+    let is_color = value.starts_with('#')
+        || value.starts_with("0x")
+        || (value.chars().all(|c| c.is_ascii_alphabetic())
+            && !std::path::Path::new(value).exists());
+    if is_color {
+        return AlphaBackground::Color(value.to_string());
+    }
+    match resolve_overlay_source(value, downloads_root) {
+        Ok(media) => AlphaBackground::Media(media),
+        Err(err) => {
+            warn_msg!(Msg::AlphaBackgroundUnusable, value, err);
+            AlphaBackground::Color("black".to_string())
+        }
     }
-    std::path::PathBuf::from("/tmp/kitsune-livewallpaper/video-live")
 }
 
 fn find_install_deps_script() -> Option<std::path::PathBuf> {
@@ -116,8 +357,8 @@ fn find_install_deps_script() -> Option<std::path::PathBuf> {
     candidates.into_iter().find(|p| p.is_file())
 }
 
-fn run_startup_entry(entry: &MonitorEntry, dry_run: bool) -> Result<()> {
-    match &entry.command {
+fn run_startup_entry(entry: &MonitorEntry, context: &RunContext, dry_run: bool) -> Result<()> {
+    let command = match &entry.command {
         StartupCommand::Video {
             video,
             downloads_root,
@@ -132,53 +373,330 @@ fn run_startup_entry(entry: &MonitorEntry, dry_run: bool) -> Result<()> {
             proxy_width,
             proxy_fps,
             proxy_crf,
-        } => run(Cli {
-            command: Commands::VideoPlay {
-                video: video.clone(),
-                monitor: entry.monitor.clone(),
+        } => Commands::VideoPlay {
+            video: video.clone(),
+            monitor: entry.monitor.clone(),
+            downloads_root: downloads_root.clone(),
+            keep_services: *keep_services,
+            services: Vec::new(),
+            mute_audio: *mute_audio,
+            profile: *profile,
+            display_fps: *display_fps,
+            seamless_loop: *seamless_loop,
+            loop_crossfade: *loop_crossfade,
+            loop_crossfade_seconds: *loop_crossfade_seconds,
+            optimize: *optimize,
+            proxy_width: *proxy_width,
+            proxy_fps: *proxy_fps,
+            proxy_crf: *proxy_crf,
+            crop_region: None,
+            dry_run,
+        },
+        StartupCommand::Apply {
+            wallpaper,
+            downloads_root,
+            keep_services,
+            mute_audio,
+            profile,
+            display_fps,
+            allow_scene_preview_fallback,
+        } => Commands::Apply {
+            wallpaper: wallpaper.clone(),
+            monitor: entry.monitor.clone(),
+            downloads_root: downloads_root.clone(),
+            keep_services: *keep_services,
+            services: Vec::new(),
+            mute_audio: *mute_audio,
+            profile: Some(*profile),
+            display_fps: *display_fps,
+            allow_scene_preview_fallback: *allow_scene_preview_fallback,
+            scaling: None,
+            smart_crop: false,
+            dry_run,
+        },
+    };
+    run_in(context, command)
+}
+
+fn play_kwescene(
+    bundle: &std::path::Path,
+    monitor: &str,
+    profile: cli::PlaybackProfile,
+    mute_audio: bool,
+    display_fps: Option<u32>,
+    dry_run: bool,
+) -> Result<()> {
+    let extracted = extract_kwescene(bundle, &default_kwescene_cache_dir(bundle))?;
+    eprintln!(
+        "[ok] kwescene {} ({}x{}, {} layer(s)) extracted to {}",
+        bundle.display(),
+        extracted.manifest.scene_width,
+        extracted.manifest.scene_height,
+        extracted.manifest.layers.len(),
+        extracted.dir.display()
+    );
+    launch_mpvpaper(
+        monitor,
+        &extracted.playable_entry,
+        profile,
+        mute_audio,
+        display_fps,
+        dry_run,
+    )
+}
+
+/// Inverse of `run_startup_entry`: the monitor and replayable command of a
+/// wallpaper application, for the history.
+fn applied_wallpaper(command: &Commands) -> Option<(String, StartupCommand)> {
+    match command {
+        Commands::Apply {
+            wallpaper,
+            monitor,
+            downloads_root,
+            keep_services,
+            mute_audio,
+            profile,
+            display_fps,
+            allow_scene_preview_fallback,
+            dry_run: false,
+            ..
+        } => Some((
+            monitor.clone(),
+            StartupCommand::Apply {
+                wallpaper: wallpaper.clone(),
                 downloads_root: downloads_root.clone(),
                 keep_services: *keep_services,
-                services: Vec::new(),
                 mute_audio: *mute_audio,
-                profile: *profile,
+                // Replays keep the profile a rule picked today.
+                profile: profile
+                    .or_else(|| {
+                        let info = inspect_wallpaper(wallpaper, downloads_root).ok()?;
+                        profile_rule_for(&info)?.profile
+                    })
+                    .unwrap_or(cli::PlaybackProfile::Balanced),
                 display_fps: *display_fps,
-                seamless_loop: *seamless_loop,
-                loop_crossfade: *loop_crossfade,
-                loop_crossfade_seconds: *loop_crossfade_seconds,
-                optimize: *optimize,
-                proxy_width: *proxy_width,
-                proxy_fps: *proxy_fps,
-                proxy_crf: *proxy_crf,
-                dry_run,
+                allow_scene_preview_fallback: *allow_scene_preview_fallback,
             },
-        }),
-        StartupCommand::Apply {
-            wallpaper,
+        )),
+        Commands::VideoPlay {
+            video,
+            monitor,
             downloads_root,
             keep_services,
             mute_audio,
             profile,
             display_fps,
-            allow_scene_preview_fallback,
-        } => run(Cli {
-            command: Commands::Apply {
-                wallpaper: wallpaper.clone(),
-                monitor: entry.monitor.clone(),
+            seamless_loop,
+            loop_crossfade,
+            loop_crossfade_seconds,
+            optimize,
+            proxy_width,
+            proxy_fps,
+            proxy_crf,
+            dry_run: false,
+            ..
+        } => Some((
+            monitor.clone(),
+            StartupCommand::Video {
+                video: video.clone(),
                 downloads_root: downloads_root.clone(),
                 keep_services: *keep_services,
-                services: Vec::new(),
                 mute_audio: *mute_audio,
                 profile: *profile,
                 display_fps: *display_fps,
-                allow_scene_preview_fallback: *allow_scene_preview_fallback,
-                dry_run,
+                seamless_loop: *seamless_loop,
+                loop_crossfade: *loop_crossfade,
+                loop_crossfade_seconds: *loop_crossfade_seconds,
+                optimize: *optimize,
+                proxy_width: *proxy_width,
+                proxy_fps: *proxy_fps,
+                proxy_crf: *proxy_crf,
             },
-        }),
+        )),
+        _ => None,
+    }
+}
+
+/// First config profile rule matching `info`. A broken config or rule set
+/// only costs the rules, never the apply.
+fn profile_rule_for(info: &types::InspectOutput) -> Option<MatchedRule> {
+    let rules = load_startup_config(&app_paths::user_config_path()).and_then(|cfg| {
+        let profiles = configured_profiles()?;
+        validate_profiles(&profiles)?;
+        validate_profile_rules(&cfg.profile_rules, &profiles)?;
+        Ok(cfg.profile_rules)
+    });
+    match rules {
+        Ok(rules) => match_rule_for(&rules, info),
+        Err(err) => {
+            warn_msg!(Msg::ProfileRulesIgnored, err);
+            None
+        }
+    }
+}
+
+/// What a matched rule changed, for the apply output.
+fn describe_rule_effect(
+    rule: &MatchedRule,
+    profile: cli::PlaybackProfile,
+    display_fps: Option<u32>,
+) -> String {
+    let mut parts = Vec::new();
+    if rule.profile.is_some() {
+        parts.push(format!("profile={}", profile));
+    }
+    if let Some(fps) = rule.display_fps.and(display_fps) {
+        parts.push(format!("display_fps={}", fps));
+    }
+    if !rule.mpv_options.is_empty() {
+        parts.push(format!("mpv {}", rule.mpv_options.join(" ")));
+    }
+    if parts.is_empty() {
+        "no options".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Warns about a degraded playback choice and keeps it in the journal, in
+/// English so journals read the same in bug reports whatever the locale.
+fn warn_fallback(monitor: &str, msg: Msg, args: &[&dyn std::fmt::Display]) {
+    eprintln!("[warn] {}", message(msg, args));
+    record_event(JournalEvent::new(
+        JournalKind::Fallback,
+        Some(monitor),
+        message_in(Lang::En, msg, args),
+    ));
+}
+
+/// Global flags a command runs under, resolved once from the CLI and handed
+/// on unchanged to the commands that re-enter [`run_in`] (start-config,
+/// back, peer-sync).
+#[derive(Debug, Clone)]
+struct RunContext {
+    output: OutputFormat,
+    safe: bool,
+    instance: String,
+    force: bool,
+    mpv_options: Vec<String>,
+    allow_obfuscated: bool,
+    read_only_library: bool,
+    lang: Option<Lang>,
+    /// Custom profiles of a `start-config --config` file; `None` reads the
+    /// user's config.json.
+    profiles: Option<BTreeMap<String, CustomProfile>>,
+}
+
+impl RunContext {
+    /// Installs the context as this thread's scope while `f` runs.
+    fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        let ctx = self.clone();
+        let scoped = || {
+            with_instance(ctx.instance, ctx.force, || {
+                with_safe_mode(ctx.safe, || {
+                    with_mpv_options(ctx.mpv_options, || {
+                        with_allow_obfuscated(ctx.allow_obfuscated, || {
+                            with_read_only_library(ctx.read_only_library, || {
+                                with_lang(ctx.lang, || with_output_format(ctx.output, f))
+                            })
+                        })
+                    })
+                })
+            })
+        };
+        match ctx.profiles {
+            Some(profiles) => with_config_profiles(profiles, scoped),
+            None => scoped(),
+        }
     }
 }
 
 pub fn run(cli: Cli) -> Result<()> {
-    match cli.command {
+    let safe = resolve_safe_mode(cli.safe);
+    if safe && !safe_mode() {
+        eprintln!("[ok] safe mode: experimental playback paths disabled");
+    }
+    let context = RunContext {
+        output: cli.output,
+        safe,
+        instance: resolve_instance(cli.instance),
+        force: cli.force,
+        mpv_options: cli.mpv_options,
+        allow_obfuscated: cli.allow_obfuscated,
+        read_only_library: cli.read_only_library,
+        lang: cli.lang,
+        profiles: None,
+    };
+    run_in(&context, cli.command)
+}
+
+/// Runs `command` under `context` and records wallpaper applications in the
+/// journal and history.
+fn run_in(context: &RunContext, command: Commands) -> Result<()> {
+    let applied = applied_wallpaper(&command);
+    let started = Instant::now();
+    let result = context.enter(|| run_command(command, context));
+    let Some((monitor, command)) = applied else {
+        return result;
+    };
+    let (target, title) = match &command {
+        StartupCommand::Apply {
+            wallpaper,
+            downloads_root,
+            ..
+        } => (
+            format!("apply {}", wallpaper),
+            wallpaper_name(wallpaper, downloads_root).title,
+        ),
+        StartupCommand::Video { video, .. } => (format!("video {}", video), None),
+    };
+    match &result {
+        Ok(()) => {
+            record_event(
+                JournalEvent::new(JournalKind::Apply, Some(&monitor), target)
+                    .since(started)
+                    .titled(title.clone()),
+            );
+            publish_overlay_sidecar(&monitor, &command);
+            if let Err(err) = record_applied(&monitor, command, title) {
+                warn_msg!(Msg::HistoryRecordFailed, err);
+            }
+        }
+        Err(err) => record_event(
+            JournalEvent::new(
+                JournalKind::Apply,
+                Some(&monitor),
+                format!("{}: {:#}", target, err),
+            )
+            .since(started)
+            .titled(title)
+            .failed(),
+        ),
+    }
+    result
+}
+
+/// Rewrites the monitor's overlay sidecar so external visualizers follow the
+/// new wallpaper.
+fn publish_overlay_sidecar(monitor: &str, command: &StartupCommand) {
+    let sidecar = match command {
+        StartupCommand::Apply {
+            wallpaper,
+            downloads_root,
+            ..
+        } => {
+            let root = resolve_wallpaper_path(wallpaper, downloads_root);
+            build_overlay_sidecar(monitor, wallpaper, Some(&root))
+        }
+        StartupCommand::Video { video, .. } => build_overlay_sidecar(monitor, video, None),
+    };
+    if let Err(err) = write_overlay_sidecar(&sidecar) {
+        warn_msg!(Msg::OverlayPlanWriteFailed, err);
+    }
+}
+
+fn run_command(command: Commands, context: &RunContext) -> Result<()> {
+    match command {
         Commands::InstallDependencies => {
             let script = find_install_deps_script().context(
                 "install-dependencies script not found. Expected scripts/install-deps.sh or /usr/share/kitsune-livewallpaper/install-deps.sh",
@@ -236,7 +754,11 @@ pub fn run(cli: Cli) -> Result<()> {
                     },
                 );
                 save_startup_config(&config, &cfg)?;
-                println!("[ok] updated config for monitor={} file={}", monitor, config.display());
+                eprintln!(
+                    "[ok] updated config for monitor={} file={}",
+                    monitor,
+                    config.display()
+                );
                 Ok(())
             }
             ConfigCommands::SetApply {
@@ -267,16 +789,88 @@ pub fn run(cli: Cli) -> Result<()> {
                     },
                 );
                 save_startup_config(&config, &cfg)?;
-                println!("[ok] updated config for monitor={} file={}", monitor, config.display());
+                eprintln!(
+                    "[ok] updated config for monitor={} file={}",
+                    monitor,
+                    config.display()
+                );
+                Ok(())
+            }
+            ConfigCommands::Overlay {
+                wallpaper,
+                downloads_root,
+                text_overlay,
+                fallback_clock,
+                timezone,
+                text_mode,
+                alpha_background,
+                clear,
+                config,
+            } => {
+                let mut cfg = load_startup_config(&config)?;
+                let update = OverlayPrefs {
+                    text_overlay,
+                    fallback_clock,
+                    timezone: timezone.map(|tz| tz.name().to_string()),
+                    text_mode,
+                    alpha_background,
+                };
+                let target = match &wallpaper {
+                    Some(wallpaper) => {
+                        let root = resolve_wallpaper_path(wallpaper, &downloads_root);
+                        let key = wallpaper_overlay_key(&root);
+                        cfg.wallpaper_overlays
+                            .entry(key.clone())
+                            .or_default()
+                            .update(&update, clear);
+                        cfg.wallpaper_overlays.retain(|_, p| !p.is_empty());
+                        format!("wallpaper={}", key)
+                    }
+                    None => {
+                        cfg.overlay_defaults.update(&update, clear);
+                        "global defaults".to_string()
+                    }
+                };
+                save_startup_config(&config, &cfg)?;
+                eprintln!(
+                    "[ok] updated overlay preferences for {} in {}",
+                    target,
+                    config.display()
+                );
+                Ok(())
+            }
+            ConfigCommands::Layer {
+                monitor,
+                layer,
+                namespace,
+                clear,
+                config,
+            } => {
+                if let Some(namespace) = &namespace {
+                    validate_namespace(namespace)?;
+                }
+                let mut cfg = load_startup_config(&config)?;
+                let update = LayerShellPrefs { layer, namespace };
+                cfg.monitor_layers
+                    .entry(monitor.clone())
+                    .or_default()
+                    .update(&update, clear);
+                cfg.monitor_layers.retain(|_, p| !p.is_empty());
+                save_startup_config(&config, &cfg)?;
+                eprintln!(
+                    "[ok] updated layer-shell settings for monitor={} in {}; applies on next launch",
+                    monitor,
+                    config.display()
+                );
                 Ok(())
             }
             ConfigCommands::Remove { monitor, config } => {
                 let mut cfg = load_startup_config(&config)?;
                 if remove_startup_entry(&mut cfg, &monitor) {
                     save_startup_config(&config, &cfg)?;
-                    println!("[ok] removed monitor={} from {}", monitor, config.display());
+                    eprintln!("[ok] removed monitor={} from {}", monitor, config.display());
                 } else {
-                    println!(
+                    eprintln!(
                         "[ok] monitor={} was not present in {}",
                         monitor,
                         config.display()
@@ -286,26 +880,38 @@ pub fn run(cli: Cli) -> Result<()> {
             }
             ConfigCommands::List { config } => {
                 let cfg = load_startup_config(&config)?;
-                println!("{}", serde_json::to_string_pretty(&cfg)?);
+                print_report(&cfg)?;
                 Ok(())
             }
         },
-        Commands::StartConfig { config, dry_run } => {
+        Commands::StartConfig {
+            config,
+            supervise,
+            dry_run,
+        } => {
             let cfg = load_startup_config(&config)?;
             let mut state = load_startup_state()?;
+            validate_profiles(&cfg.profiles)?;
+            let context = RunContext {
+                profiles: Some(cfg.profiles.clone()),
+                ..context.clone()
+            };
             let mut changed = 0usize;
             let mut skipped = 0usize;
             for entry in &cfg.entries {
                 let fp = entry_fingerprint(entry);
                 let prev = state.monitor_fingerprints.get(&entry.monitor).copied();
-                if prev == Some(fp) {
+                // After a watchdog restart the playback died with the old
+                // process, so an unchanged entry is not enough to skip it.
+                let playing = !supervise || dry_run || playback_responding(&entry.monitor);
+                if prev == Some(fp) && playing {
                     skipped += 1;
-                    println!("[ok] unchanged monitor={}, skipped", entry.monitor);
+                    eprintln!("[ok] unchanged monitor={}, skipped", entry.monitor);
                     continue;
                 }
 
-                println!("[ok] applying monitor={} from config", entry.monitor);
-                run_startup_entry(entry, dry_run)?;
+                eprintln!("[ok] applying monitor={} from config", entry.monitor);
+                run_startup_entry(entry, &context, dry_run)?;
                 if !dry_run {
                     state.monitor_fingerprints.insert(entry.monitor.clone(), fp);
                 }
@@ -314,14 +920,101 @@ pub fn run(cli: Cli) -> Result<()> {
             if !dry_run {
                 save_startup_state(&state)?;
             }
-            println!(
+            eprintln!(
                 "[ok] start-config done changed={} unchanged={} total={}",
                 changed,
                 skipped,
                 cfg.entries.len()
             );
+            if supervise && !dry_run {
+                let monitors = cfg
+                    .entries
+                    .iter()
+                    .map(|entry| entry.monitor.clone())
+                    .collect::<Vec<_>>();
+                run_watchdog(&monitors)?;
+            }
             Ok(())
         }
+        Commands::Mute { state, dry_run } => match state {
+            MuteState::On => set_global_mute(true, dry_run),
+            MuteState::Off => set_global_mute(false, dry_run),
+            MuteState::Status => print_report(&serde_json::json!({
+                "global_mute": load_global_mute(),
+                "running_instances": running_mpv_ipc_sockets(),
+            })),
+        },
+        Commands::Property { command } => match command {
+            PropertyCommands::Set {
+                monitor,
+                key,
+                value,
+                transition_ms,
+                dry_run,
+            } => set_property_override(
+                &monitor,
+                &key,
+                &value,
+                resolve_transition_ms(transition_ms),
+                dry_run,
+            ),
+            PropertyCommands::List { monitor } => {
+                let mut overrides = load_property_overrides()?;
+                if let Some(monitor) = monitor {
+                    overrides.monitors.retain(|m, _| *m == monitor);
+                }
+                print_report(&overrides)?;
+                Ok(())
+            }
+            PropertyCommands::Clear {
+                monitor,
+                key,
+                dry_run,
+            } => clear_property_override(&monitor, key.as_deref(), dry_run),
+        },
+        Commands::Workshop { command } => match command {
+            WorkshopCommands::SyncCollection {
+                collection_id,
+                downloads_root,
+                steam_user,
+                prune,
+                dry_run,
+            } => {
+                let report = sync_collection(CollectionSyncArgs {
+                    collection_id,
+                    downloads_root,
+                    steam_user,
+                    prune,
+                    dry_run,
+                })?;
+                print_report(&report)?;
+                Ok(())
+            }
+        },
+        Commands::Greeter { command } => match command {
+            GreeterCommands::Install {
+                wallpaper,
+                target,
+                mode,
+                size,
+                dest,
+                downloads_root,
+                overwrite,
+                dry_run,
+            } => {
+                let (width, height) = parse_thumbnail_size(&size)?;
+                install_greeter(GreeterInstallArgs {
+                    root: resolve_wallpaper_path(&wallpaper, &downloads_root),
+                    target,
+                    mode,
+                    width,
+                    height,
+                    dest,
+                    overwrite,
+                    dry_run,
+                })
+            }
+        },
         Commands::ServiceAutostart { command } => match command {
             ServiceAutostartCommands::Install { overwrite, dry_run } => {
                 install_autostart_service(overwrite, dry_run)
@@ -329,14 +1022,19 @@ pub fn run(cli: Cli) -> Result<()> {
             ServiceAutostartCommands::Enable { dry_run } => enable_autostart_service(dry_run),
             ServiceAutostartCommands::Disable { dry_run } => disable_autostart_service(dry_run),
             ServiceAutostartCommands::Remove { dry_run } => remove_autostart_service(dry_run),
-            ServiceAutostartCommands::Status => autostart_service_status(),
+            ServiceAutostartCommands::Status => print_report(&autostart_service_status()?),
         },
         Commands::Inspect {
             wallpaper,
             downloads_root,
         } => {
-            let info = inspect_wallpaper(&wallpaper, &downloads_root)?;
-            println!("{}", serde_json::to_string_pretty(&info)?);
+            let mut info = inspect_wallpaper(&wallpaper, &downloads_root)?;
+            let root = std::path::Path::new(&info.root);
+            match build_wallpaper_capabilities(root, &info.wallpaper_type) {
+                Ok(capabilities) => info.capabilities = capabilities,
+                Err(err) => warn_msg!(Msg::CapabilitiesUnavailable, err),
+            }
+            print_report(&info)?;
             Ok(())
         }
         Commands::SceneDump {
@@ -345,13 +1043,7 @@ pub fn run(cli: Cli) -> Result<()> {
             full,
         } => {
             let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
-            let scene_pkg_path = if root.join("scene.pkg").is_file() {
-                root.join("scene.pkg")
-            } else if root.join("gifscene.pkg").is_file() {
-                root.join("gifscene.pkg")
-            } else {
-                bail!("No scene.pkg/gifscene.pkg found in {}", root.display());
-            };
+            let scene_pkg_path = find_scene_pkg_path(&root)?;
 
             let pkg = parse_scene_pkg(&scene_pkg_path)?;
             let candidate = best_video_entry(&pkg, true).map(|e| e.filename);
@@ -364,7 +1056,7 @@ pub fn run(cli: Cli) -> Result<()> {
                     "best_video_candidate": candidate,
                     "entries": pkg.entries,
                 });
-                println!("{}", serde_json::to_string_pretty(&out)?);
+                print_report(&out)?;
             } else {
                 let out = serde_json::json!({
                     "pkg": scene_pkg_path,
@@ -372,7 +1064,7 @@ pub fn run(cli: Cli) -> Result<()> {
                     "entries_count": pkg.entries.len(),
                     "best_video_candidate": candidate,
                 });
-                println!("{}", serde_json::to_string_pretty(&out)?);
+                print_report(&out)?;
             }
             Ok(())
         }
@@ -382,7 +1074,7 @@ pub fn run(cli: Cli) -> Result<()> {
         } => {
             let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
             let plan = build_scene_plan(&root)?;
-            println!("{}", serde_json::to_string_pretty(&plan)?);
+            print_report(&plan)?;
             Ok(())
         }
         Commands::SceneAudioPlan {
@@ -391,24 +1083,102 @@ pub fn run(cli: Cli) -> Result<()> {
         } => {
             let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
             let plan = build_scene_audio_bars_overlay(&root)?;
-            println!("{}", serde_json::to_string_pretty(&plan)?);
+            print_report(&plan)?;
+            Ok(())
+        }
+        Commands::CalibrateAudioBars {
+            wallpaper,
+            downloads_root,
+            save,
+            out,
+        } => {
+            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let report = calibrate_audio_bars(&root, save, out.as_deref())?;
+            print_report(&report)?;
+            if report.aligned {
+                eprintln!("[ok] audio bars aligned with the rendered frame");
+            } else {
+                eprintln!(
+                    "[{}] audio bars off by {:.1},{:.1} px (scale {:.3}x{:.3})",
+                    if report.saved { "ok" } else { "warn" },
+                    report.offset_px[0],
+                    report.offset_px[1],
+                    report.scale[0],
+                    report.scale[1]
+                );
+            }
             Ok(())
         }
         Commands::LibraryScan {
             downloads_root,
             top_effects,
             summary_only,
+            low_memory,
         } => {
-            let report = scan_library(&downloads_root, top_effects.max(1), summary_only)?;
-            println!("{}", serde_json::to_string_pretty(&report)?);
+            let report = scan_library(
+                &downloads_root,
+                top_effects.max(1),
+                summary_only,
+                low_memory,
+            )?;
+            print_report(&report)?;
             Ok(())
         }
         Commands::LibraryRoadmap {
             downloads_root,
             top_n,
+            export,
+            out,
+            from_cache,
+            min_interval,
         } => {
-            let report = build_library_roadmap(&downloads_root, top_n.max(1))?;
-            println!("{}", serde_json::to_string_pretty(&report)?);
+            let top_n = top_n.max(1);
+            let cached = min_interval.and_then(|minutes| {
+                let max_age = std::time::Duration::from_secs(minutes.saturating_mul(60));
+                fresh_roadmap_cache(&downloads_root, top_n, max_age)
+            });
+            let report = if from_cache {
+                load_roadmap_cache()?
+            } else if let Some(report) = cached {
+                eprintln!(
+                    "[ok] roadmap from {} reused (--min-interval {} min)",
+                    report.generated_at,
+                    min_interval.unwrap_or_default()
+                );
+                report
+            } else {
+                let report = build_library_roadmap(&downloads_root, top_n)?;
+                save_roadmap_cache(&report)?;
+                report
+            };
+            match (export, out) {
+                (Some(format), Some(out)) => {
+                    export_roadmap(&report, format, &out)?;
+                    eprintln!("[ok] roadmap exported to {}", out.display());
+                }
+                _ => print_report(&report)?,
+            }
+            Ok(())
+        }
+        Commands::LibraryStats {
+            downloads_root,
+            format,
+            top_families,
+            share,
+            dry_run,
+        } => {
+            let stats = build_library_stats(&downloads_root, top_families)?;
+            let (body, content_type) = match format {
+                StatsFormat::Json => (serde_json::to_string_pretty(&stats)?, "application/json"),
+                StatsFormat::Prometheus => (render_prometheus(&stats), "text/plain; version=0.0.4"),
+            };
+            match format {
+                StatsFormat::Json => print_report(&stats)?,
+                StatsFormat::Prometheus => print!("{}", body),
+            }
+            if let Some(url) = share {
+                share_library_stats(&url, &body, content_type, dry_run)?;
+            }
             Ok(())
         }
         Commands::SceneRuntime {
@@ -420,40 +1190,341 @@ pub fn run(cli: Cli) -> Result<()> {
             extract_music,
         } => {
             let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
-            let runtime = run_scene_runtime(&root, source, seconds, frame_ms, extract_music)?;
-            println!("{}", serde_json::to_string_pretty(&runtime)?);
+            let runtime = run_scene_runtime(&root, source, seconds, frame_ms, extract_music)?;
+            print_report(&runtime)?;
+            Ok(())
+        }
+        Commands::SceneRender {
+            wallpaper,
+            downloads_root,
+            source,
+            seconds,
+            frame_ms,
+        } => {
+            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let session = build_scene_render_session(&root, source, seconds, frame_ms)?;
+            print_report(&session)?;
+            Ok(())
+        }
+        Commands::SceneGpuGraph {
+            wallpaper,
+            downloads_root,
+        } => {
+            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let graph = build_scene_gpu_graph(&root)?;
+            print_report(&graph)?;
+            Ok(())
+        }
+        Commands::SceneProperties {
+            wallpaper,
+            downloads_root,
+        } => {
+            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let schema = build_scene_property_schema(&root)?;
+            print_report(&schema)?;
+            Ok(())
+        }
+        Commands::SceneParticles {
+            wallpaper,
+            downloads_root,
+        } => {
+            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let report = build_scene_particle_report(&root)?;
+            print_report(&report)?;
+            Ok(())
+        }
+        Commands::SceneShaders { command } => match command {
+            SceneShaderCommands::Dump {
+                wallpaper,
+                out,
+                downloads_root,
+            } => {
+                let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+                let index = dump_scene_shaders(&root, &out)?;
+                eprintln!(
+                    "[ok] {} shader(s), {} include(s) -> {}",
+                    index.shaders.len(),
+                    index.includes.len(),
+                    out.join("index.json").display()
+                );
+                for m in &index.missing {
+                    warn_msg!(Msg::ShaderNotFound, m.shader, m.stage);
+                }
+                Ok(())
+            }
+        },
+        Commands::SceneUniform { command } => match command {
+            SceneUniformCommands::Eval {
+                wallpaper,
+                set,
+                print_filter,
+                downloads_root,
+            } => {
+                let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+                let uniforms = set.into_iter().collect();
+                let scratch = app_paths::app_cache_dir().join("uniform-eval");
+                let report = eval_realtime_plan_uniforms(&root, &scratch, &uniforms)?;
+                if print_filter {
+                    println!("{}", report.filter_complex);
+                    return Ok(());
+                }
+                print_report(&report)
+            }
+        },
+        Commands::OverlayPlan { command } => match command {
+            OverlayPlanCommands::Show { monitor } => print_report(&load_overlay_sidecar(&monitor)?),
+            OverlayPlanCommands::Watch {
+                monitor,
+                interval_ms,
+            } => watch_overlay_sidecars(
+                monitor.as_deref(),
+                std::time::Duration::from_millis(interval_ms.max(50)),
+            ),
+        },
+        Commands::Proxy { command } => match command {
+            ProxyCommands::Info { file } => {
+                let info = proxy_meta::read_proxy_info(&file)?;
+                if info.proxy.is_none() {
+                    warn_msg!(Msg::ProxyMetadataMissing, file.display());
+                }
+                print_report(&info)
+            }
+            ProxyCommands::Invalidate {
+                wallpaper,
+                all: _,
+                downloads_root,
+                only_outdated,
+                dry_run,
+            } => {
+                let key = wallpaper
+                    .as_deref()
+                    .map(|w| wallpaper_overlay_key(&resolve_wallpaper_path(w, &downloads_root)));
+                let report = invalidate_proxies(
+                    &app_paths::app_cache_dir(),
+                    key.as_deref(),
+                    only_outdated,
+                    dry_run,
+                )?;
+                let verb = if dry_run {
+                    "would invalidate"
+                } else {
+                    "invalidated"
+                };
+                eprintln!(
+                    "[ok] {} {} of {} proxies; they rebuild on next playback",
+                    verb,
+                    report.invalidated.len(),
+                    report.scanned
+                );
+                print_report(&report)
+            }
+        },
+        Commands::Profile { command } => match command {
+            ProfileCommands::Export {
+                out,
+                include_media,
+                dry_run,
+            } => print_report(&profile_bundle::export_profile(
+                &out,
+                include_media,
+                dry_run,
+            )?),
+            ProfileCommands::Import { archive, dry_run } => {
+                print_report(&profile_bundle::import_profile(&archive, dry_run)?)
+            }
+        },
+        Commands::Profiles { command } => match command {
+            ProfilesCommands::List { config } => {
+                let cfg = load_startup_config(&config)?;
+                print_report(&list_profiles(&cfg))
+            }
+        },
+        Commands::Layers { command } => match command {
+            LayersCommands::List {
+                wallpaper,
+                downloads_root,
+            } => {
+                let root = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+                print_report(&list_scene_layers(&wallpaper, &root)?)
+            }
+            LayersCommands::Skip {
+                wallpaper,
+                downloads_root,
+                objects,
+                effects,
+                dry_run,
+            } => {
+                if objects.is_empty() && effects.is_empty() {
+                    bail!("Pass at least one --object or --effect to skip");
+                }
+                let root = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+                print_report(&update_layer_override(
+                    &root, &objects, &effects, true, dry_run,
+                )?)
+            }
+            LayersCommands::Clear {
+                wallpaper,
+                downloads_root,
+                objects,
+                effects,
+                dry_run,
+            } => {
+                let root = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+                print_report(&update_layer_override(
+                    &root, &objects, &effects, false, dry_run,
+                )?)
+            }
+        },
+        Commands::ScenePkg { command } => match command {
+            ScenePkgCommands::Table {
+                wallpaper,
+                downloads_root,
+                out,
+            } => {
+                let root = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+                let table = export_pkg_table(&find_scene_pkg_path(&root)?)?;
+                let Some(out) = out else {
+                    return print_report(&table);
+                };
+                std::fs::write(&out, serde_json::to_string_pretty(&table)? + "\n")
+                    .with_context(|| format!("Failed writing {}", out.display()))?;
+                for issue in &table.issues {
+                    warn_msg!(Msg::PkgTableIssue, issue);
+                }
+                eprintln!(
+                    "[ok] wrote {} ({} entries)",
+                    out.display(),
+                    table.entries.len()
+                );
+                Ok(())
+            }
+            ScenePkgCommands::Import {
+                wallpaper,
+                downloads_root,
+                table,
+                out,
+                dry_run,
+            } => {
+                let root = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+                let text = std::fs::read_to_string(&table)
+                    .with_context(|| format!("Failed reading {}", table.display()))?;
+                let table: PkgTable = serde_json::from_str(&text)
+                    .with_context(|| format!("Invalid pkg table {}", table.display()))?;
+                print_report(&import_pkg_table(
+                    &find_scene_pkg_path(&root)?,
+                    &table,
+                    &out,
+                    dry_run,
+                )?)
+            }
+        },
+        Commands::Report { command } => match command {
+            ReportCommands::Bundle {
+                wallpaper,
+                downloads_root,
+                out,
+                dry_run,
+            } => print_report(&report_bundle::build_report_bundle(
+                &wallpaper,
+                &downloads_root,
+                &out,
+                dry_run,
+            )?),
+        },
+        Commands::SceneNativePlan {
+            wallpaper,
+            downloads_root,
+            budget,
+        } => {
+            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let graph = build_scene_gpu_graph(&root)?;
+            let mut plan = build_native_runtime_plan(&graph);
+            apply_cost_budget(
+                &mut plan,
+                graph.scene_width,
+                graph.scene_height,
+                budget.unwrap_or_else(native_cost_budget),
+            );
+            print_report(&plan)?;
+            Ok(())
+        }
+        Commands::Compat { command } => match command {
+            CompatCommands::Compare {
+                wallpaper,
+                other_log,
+                downloads_root,
+            } => {
+                let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+                print_report(&compare_with_other_log(&root, &other_log)?)
+            }
+        },
+        Commands::SceneCompile {
+            wallpaper,
+            out,
+            downloads_root,
+            seconds,
+            frame_ms,
+            no_bake,
+        } => {
+            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let report = compile_kwescene(&root, &out, seconds, frame_ms, !no_bake)?;
+            print_report(&report)?;
             Ok(())
         }
-        Commands::SceneRender {
+        Commands::VerifyRender {
             wallpaper,
+            assert_layers,
+            assert_no_fallback,
+            out,
             downloads_root,
-            source,
-            seconds,
-            frame_ms,
         } => {
             let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
-            let session = build_scene_render_session(&root, source, seconds, frame_ms)?;
-            println!("{}", serde_json::to_string_pretty(&session)?);
+            let expect = VerifyExpectations {
+                layers: assert_layers,
+                no_fallback: assert_no_fallback,
+            };
+            let report = verify_scene_render(&root, &expect, out.as_deref())?;
+            print_report(&report)?;
+            if !report.passed {
+                bail!("verify-render failed: {}", report.failures.join("; "));
+            }
+            eprintln!(
+                "[ok] verify-render passed ({} layers)",
+                report.rendered_layers
+            );
             Ok(())
         }
-        Commands::SceneGpuGraph {
+        Commands::NativeStream {
+            root,
+            monitor,
+            session_dir,
+            fifo,
+            fps,
+        } => run_native_stream(&root, &monitor, &session_dir, &fifo, fps),
+        Commands::RenderThumbnail {
             wallpaper,
+            size,
+            out,
             downloads_root,
         } => {
             let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
-            let graph = build_scene_gpu_graph(&root)?;
-            println!("{}", serde_json::to_string_pretty(&graph)?);
+            let (width, height) = parse_thumbnail_size(&size)?;
+            let out = out.unwrap_or_else(|| default_thumbnail_path(&root, width, height));
+            let report = render_scene_thumbnail(&root, &out, width, height)?;
+            print_report(&report)?;
             Ok(())
         }
-        Commands::SceneNativePlan {
+        Commands::PreviewStrip {
             wallpaper,
+            frames,
+            frame_size,
+            out,
             downloads_root,
         } => {
             let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
-            let graph = build_scene_gpu_graph(&root)?;
-            let plan = build_native_runtime_plan(&graph);
-            println!("{}", serde_json::to_string_pretty(&plan)?);
-            Ok(())
+            let (width, height) = parse_thumbnail_size(&frame_size)?;
+            let out = out.unwrap_or_else(|| default_preview_strip_path(&root, frames));
+            print_report(&render_preview_strip(&root, &out, frames, width, height)?)
         }
         Commands::SceneGpuPlay {
             wallpaper,
@@ -468,27 +1539,49 @@ pub fn run(cli: Cli) -> Result<()> {
             profile,
             display_fps,
             clock_overlay,
+            timezone,
+            orientation,
             apply_kitsune_overlay,
             transport,
             require_native,
             audio_bars_source,
+            realtime_motion,
             proxy_width,
             proxy_fps,
             proxy_crf,
+            proxy_seconds,
+            custom_filters,
+            audio_reactive,
+            audio_reactive_strength,
+            explain,
             dry_run,
         } => {
-            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
-            let effective_services = if services.is_empty() {
-                default_services()
-            } else {
-                services
-            };
+            let root = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+            let custom_filters = CustomFilters::from_cli(&custom_filters)?;
+            eprintln!(
+                "[ok] wallpaper: {}",
+                wallpaper_name_at(&wallpaper, &root).label()
+            );
+            let overlays = overlays_for_wallpaper(
+                &root,
+                &OverlayPrefs {
+                    text_overlay: clock_overlay,
+                    fallback_clock: None,
+                    timezone: timezone.map(|tz| tz.name().to_string()),
+                    text_mode: None,
+                    alpha_background: None,
+                },
+            );
+            let effective_services = effective_services(services);
             if !keep_services {
                 stop_services(&effective_services, dry_run)?;
             }
             stop_existing_mpvpaper_for_monitor(&monitor, dry_run)?;
+            if is_kwescene_path(&root) {
+                return play_kwescene(&root, &monitor, profile, mute_audio, display_fps, dry_run);
+            }
 
-            let out = scene_gpu_play(SceneGpuPlayArgs {
+            let mut args = SceneGpuPlayArgs {
                 root,
                 monitor,
                 source,
@@ -497,17 +1590,35 @@ pub fn run(cli: Cli) -> Result<()> {
                 profile,
                 mute_audio,
                 display_fps,
-                clock_overlay,
+                clock_overlay: overlays.text_overlay,
+                text_timezone: overlays.timezone,
+                text_mode: overlays.text_mode,
+                orientation,
                 apply_kitsune_overlay,
                 transport,
                 require_native,
                 audio_bars_source,
+                realtime_motion,
+                custom_filters,
                 proxy_width,
-                proxy_fps,
+                proxy_fps: proxy_fps.or(display_fps).unwrap_or(DEFAULT_REFRESH_FPS),
+                auto_fps: proxy_fps.is_none() && display_fps.is_none(),
                 proxy_crf,
+                proxy_seconds,
+                audio_reactive,
+                audio_reactive_strength,
                 dry_run,
-            })?;
-            println!("{}", serde_json::to_string_pretty(&out)?);
+            };
+            if safe_mode() {
+                for change in restrict_gpu_play(&mut args) {
+                    eprintln!("[ok] safe mode: {}", change);
+                }
+            }
+            let out = scene_gpu_play(args)?;
+            print_report(&out)?;
+            if explain {
+                print_emulation_trace_summary(std::path::Path::new(&out.scene_session_dir))?;
+            }
             Ok(())
         }
         Commands::TextRefresh {
@@ -519,7 +1630,7 @@ pub fn run(cli: Cli) -> Result<()> {
                 run_text_refresh_loop(&spec, interval_seconds)
             } else {
                 let updated = run_text_refresh(&spec)?;
-                println!("[ok] refreshed {} text layers", updated);
+                eprintln!("[ok] refreshed {} text layers", updated);
                 Ok(())
             }
         }
@@ -536,28 +1647,86 @@ pub fn run(cli: Cli) -> Result<()> {
             profile,
             display_fps,
             clock_overlay,
+            timezone,
+            fallback_clock,
+            crop_region,
+            orientation,
             proxy_preset,
             auto_tune,
             proxy_width,
             proxy_fps,
             proxy_crf,
+            proxy_seconds,
+            custom_filters,
             no_proxy_optimize,
+            warm_start,
+            explain,
             dry_run,
         } => {
-            let root = resolve_wallpaper_path(&wallpaper, &downloads_root);
+            let root = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+            let custom_filters = CustomFilters::from_cli(&custom_filters)?;
+            eprintln!(
+                "[ok] wallpaper: {}",
+                wallpaper_name_at(&wallpaper, &root).label()
+            );
+            let overlays = overlays_for_wallpaper(
+                &root,
+                &OverlayPrefs {
+                    text_overlay: clock_overlay,
+                    fallback_clock,
+                    timezone: timezone.map(|tz| tz.name().to_string()),
+                    text_mode: None,
+                    alpha_background: None,
+                },
+            );
 
-            let effective_services = if services.is_empty() {
-                default_services()
-            } else {
-                services
-            };
+            let effective_services = effective_services(services);
 
             if !keep_services {
                 stop_services(&effective_services, dry_run)?;
             }
 
             stop_existing_mpvpaper_for_monitor(&monitor, dry_run)?;
+            if is_kwescene_path(&root) {
+                return play_kwescene(&root, &monitor, profile, mute_audio, display_fps, dry_run);
+            }
+            if safe_mode()
+                && let Some(preview) = find_preview_fallback(&root).filter(|p| is_video_file(p))
+            {
+                warn_fallback(&monitor, Msg::SafeModePreview, &[&preview.display()]);
+                let entry = gif_proxy_entry(&preview, dry_run)?;
+                return launch_mpvpaper(
+                    &monitor,
+                    &entry,
+                    profile,
+                    mute_audio,
+                    display_fps,
+                    dry_run,
+                );
+            }
+            let warm_entry = if warm_start {
+                find_warm_start_entry(&root)
+            } else {
+                None
+            };
+            if let Some(previous) = &warm_entry {
+                eprintln!(
+                    "[ok] warm-start: showing previous proxy while the new one builds: {}",
+                    previous.display()
+                );
+                launch_mpvpaper(
+                    &monitor,
+                    previous,
+                    profile,
+                    mute_audio,
+                    display_fps,
+                    dry_run,
+                )?;
+            } else if warm_start {
+                warn_msg!(Msg::WarmStartNoProxy);
+            }
             let session = build_scene_render_session(&root, source, seconds, frame_ms)?;
+            reset_emulation_trace(std::path::Path::new(&session.session_dir))?;
 
             let visual_path = std::path::PathBuf::from(&session.visual_asset_path);
             let preview_fallback = find_preview_fallback(&root);
@@ -574,16 +1743,20 @@ pub fn run(cli: Cli) -> Result<()> {
                 if let Some(proxy_from_tex) =
                     extract_playable_proxy_from_tex(&visual_path, &proxy_dir)?
                 {
-                    eprintln!(
-                        "[warn] primary visual asset is .tex; extracted playable proxy from texture payload: {}",
-                        proxy_from_tex.display()
-                    );
+                    warn_fallback(&monitor, Msg::TexPayloadProxy, &[&proxy_from_tex.display()]);
+                    if let Some(info) = probe_mp4_video_info(&proxy_from_tex) {
+                        eprintln!(
+                            "[ok] embedded video: {} frames at {:.2} fps ({:.1}s, {}x{})",
+                            info.frame_count,
+                            info.fps,
+                            info.duration_seconds,
+                            info.width,
+                            info.height
+                        );
+                    }
                     proxy_from_tex.to_string_lossy().to_string()
                 } else if let Some(proxy) = preview_fallback.as_ref() {
-                    eprintln!(
-                        "[warn] .tex proxy extraction did not find playable payload. Using preview proxy: {}",
-                        proxy.display()
-                    );
+                    warn_fallback(&monitor, Msg::TexPayloadMissing, &[&proxy.display()]);
                     proxy.to_string_lossy().to_string()
                 } else {
                     bail!(
@@ -593,10 +1766,10 @@ pub fn run(cli: Cli) -> Result<()> {
                     );
                 }
             } else if let Some(proxy) = preview_fallback.as_ref() {
-                eprintln!(
-                    "[warn] primary visual asset is not directly playable yet ({}). Using preview proxy: {}",
-                    visual_path.display(),
-                    proxy.display()
+                warn_fallback(
+                    &monitor,
+                    Msg::VisualNotPlayable,
+                    &[&visual_path.display(), &proxy.display()],
                 );
                 proxy.to_string_lossy().to_string()
             } else {
@@ -607,18 +1780,48 @@ pub fn run(cli: Cli) -> Result<()> {
                 );
             };
 
+            // Without an explicit cap, pace both the proxy and mpv by scene motion.
+            let (display_fps, proxy_fps) = match (display_fps, proxy_fps) {
+                (None, None) => match build_scene_gpu_graph(&root) {
+                    Ok(graph) => {
+                        let fps = infer_scene_fps(
+                            &graph,
+                            is_video_file(std::path::Path::new(&entry_to_launch)),
+                            &monitor,
+                        );
+                        (Some(fps), Some(fps))
+                    }
+                    Err(_) => (None, None),
+                },
+                explicit => explicit,
+            };
+
+            // Scene pixels become fractions so the crop holds at any proxy size.
+            let crop_region = crop_region.map(|region| match build_scene_gpu_graph(&root) {
+                Ok(graph) => region.to_fractions(graph.scene_width, graph.scene_height),
+                Err(_) => region,
+            });
+            let portrait = crop_region.is_none() && is_portrait_output(orientation, &monitor);
+            let mut portrait_canvas = false;
             let animated_entry = match maybe_build_scene_animated_proxy(
                 &root,
                 std::path::Path::new(&session.session_dir),
                 std::path::Path::new(&entry_to_launch),
+                proxy_seconds,
+                portrait,
+                custom_filters.as_ref(),
                 dry_run,
             )? {
                 Some(p) => {
                     eprintln!("[ok] built animated scene proxy: {}", p.display());
+                    portrait_canvas = portrait;
                     p.to_string_lossy().to_string()
                 }
                 None => entry_to_launch,
             };
+            if portrait && !portrait_canvas {
+                warn_msg!(Msg::PortraitOnlyImageProxies);
+            }
 
             let final_entry = if no_proxy_optimize {
                 animated_entry
@@ -633,6 +1836,8 @@ pub fn run(cli: Cli) -> Result<()> {
                 let eff_width = proxy_width.unwrap_or(base.width);
                 let eff_fps = proxy_fps.unwrap_or(base.fps);
                 let eff_crf = proxy_crf.unwrap_or(base.crf);
+                let (eff_width, eff_fps, eff_crf) =
+                    profile_proxy_settings(profile, eff_width, eff_fps, eff_crf);
 
                 if auto_tune {
                     eprintln!(
@@ -656,25 +1861,43 @@ pub fn run(cli: Cli) -> Result<()> {
                 optimized.to_string_lossy().to_string()
             };
 
-            let drawtext_opt = if clock_overlay {
-                let mut built = match build_scene_drawtext_filter(&root, 3) {
-                    Ok(Some(vf)) => {
-                        eprintln!("[ok] scene text overlays generated from scene.json");
-                        Some(vf)
+            let drawtext_opt = if overlays.text_overlay {
+                let fallback = overlays
+                    .fallback_clock
+                    .then(|| FALLBACK_CLOCK_VF.to_string());
+                let framing = match crop_region {
+                    Some(region) if region.is_relative() => OutputFraming::Region(region),
+                    _ if portrait_canvas => OutputFraming::Portrait,
+                    _ => OutputFraming::Full,
+                };
+                let timezones = TextTimezones::with_overrides(
+                    overlays.timezone,
+                    &monitor_property_overrides(&monitor),
+                );
+                let mut built = if overlays.text_mode == TextRenderMode::Osd {
+                    let socket = mpv_ipc_socket_path(&monitor);
+                    match build_scene_osd_text(&root, 3, framing, &timezones, &socket) {
+                        Ok(0) => fallback,
+                        Ok(count) => {
+                            eprintln!("[ok] {} scene text layers drawn on the mpv OSD", count);
+                            None
+                        }
+                        Err(err) => {
+                            warn_msg!(Msg::SceneOsdTextFailed, err);
+                            fallback
+                        }
                     }
-                    Ok(None) => Some(
-                        "vf=drawtext=text=%{localtime\\:%a-%d-%b-%H\\\\:%M}:fontcolor=white:fontsize=44:x=(w-text_w)/2:y=28:box=1:boxcolor=0x00000088:boxborderw=14"
-                            .to_string(),
-                    ),
-                    Err(err) => {
-                        eprintln!(
-                            "[warn] could not build scene text overlays, using fallback clock: {}",
-                            err
-                        );
-                        Some(
-                            "vf=drawtext=text=%{localtime\\:%a-%d-%b-%H\\\\:%M}:fontcolor=white:fontsize=44:x=(w-text_w)/2:y=28:box=1:boxcolor=0x00000088:boxborderw=14"
-                                .to_string(),
-                        )
+                } else {
+                    match build_scene_drawtext_filter(&root, 3, framing, &timezones) {
+                        Ok(Some(vf)) => {
+                            eprintln!("[ok] scene text overlays generated from scene.json");
+                            Some(vf)
+                        }
+                        Ok(None) => fallback,
+                        Err(err) => {
+                            warn_msg!(Msg::SceneTextOverlaysFailed, err);
+                            fallback
+                        }
                     }
                 };
                 if std::env::var("KWE_DEBUG_TEXT").ok().as_deref() == Some("1") {
@@ -701,28 +1924,70 @@ pub fn run(cli: Cli) -> Result<()> {
                 None
             };
 
-            if clock_overlay {
+            let drawtext_opt = match crop_region {
+                Some(region) => {
+                    eprintln!("[ok] crop region: {}", region.mpv_crop_filter());
+                    Some(with_vf_prefix(drawtext_opt, &region.mpv_crop_filter()))
+                }
+                None => drawtext_opt,
+            };
+
+            if overlays.text_overlay {
                 start_text_refresh_daemon(&root, dry_run)?;
             }
 
-            let result = launch_mpvpaper_with_extra(
-                &monitor,
-                &final_entry,
-                profile,
-                mute_audio,
-                display_fps,
-                drawtext_opt.as_deref(),
-                dry_run,
-            );
+            let launch_fresh = || {
+                launch_mpvpaper_with_extra(
+                    &monitor,
+                    std::path::Path::new(&final_entry),
+                    profile,
+                    mute_audio,
+                    display_fps,
+                    drawtext_opt.as_deref(),
+                    dry_run,
+                )
+            };
+            let result = if warm_entry.is_some() {
+                match hot_swap_mpvpaper_entry(
+                    &monitor,
+                    std::path::Path::new(&final_entry),
+                    profile,
+                    display_fps,
+                    drawtext_opt.as_deref(),
+                    dry_run,
+                ) {
+                    Ok(()) => Ok(()),
+                    Err(err) => {
+                        warn_msg!(Msg::WarmStartHotSwapFailed, err);
+                        stop_existing_mpvpaper_for_monitor(&monitor, dry_run)?;
+                        launch_fresh()
+                    }
+                }
+            } else {
+                launch_fresh()
+            };
+            if result.is_ok()
+                && !dry_run
+                && let Err(err) = save_warm_start_entry(&root, std::path::Path::new(&final_entry))
+            {
+                warn_msg!(Msg::WarmStartSnapshotFailed, err);
+            }
 
-            println!("[ok] scene session dir: {}", session.session_dir);
-            println!("[ok] scene manifest: {}", session.manifest_path);
-            println!("[ok] scene uniforms: {}", session.uniforms_path);
+            eprintln!("[ok] scene session dir: {}", session.session_dir);
+            eprintln!("[ok] scene manifest: {}", session.manifest_path);
+            eprintln!("[ok] scene uniforms: {}", session.uniforms_path);
+            eprintln!(
+                "[ok] scene inputs fingerprint: {}",
+                session.inputs_fingerprint
+            );
+            if explain {
+                print_emulation_trace_summary(std::path::Path::new(&session.session_dir))?;
+            }
             result
         }
         Commands::AudioProbe { source, seconds } => {
             let out = probe_audio(source, seconds)?;
-            println!("{}", serde_json::to_string_pretty(&out)?);
+            print_report(&out)?;
             Ok(())
         }
         Commands::VideoPlay {
@@ -741,13 +2006,10 @@ pub fn run(cli: Cli) -> Result<()> {
             proxy_width,
             proxy_fps,
             proxy_crf,
+            crop_region,
             dry_run,
         } => {
-            let effective_services = if services.is_empty() {
-                default_services()
-            } else {
-                services
-            };
+            let effective_services = effective_services(services);
 
             if !keep_services {
                 stop_services(&effective_services, dry_run)?;
@@ -756,10 +2018,14 @@ pub fn run(cli: Cli) -> Result<()> {
             stop_existing_mpvpaper_for_monitor(&monitor, dry_run)?;
 
             let explicit_path = std::path::PathBuf::from(&video);
+            let mut video_hints = None;
+            let mut wallpaper_root = None;
             let resolved_entry = if explicit_path.is_file() {
                 explicit_path
             } else {
                 let info = inspect_wallpaper(&video, &downloads_root)?;
+                video_hints = info.video_hints;
+                wallpaper_root = Some(std::path::PathBuf::from(&info.root));
                 match info.wallpaper_type {
                     WallpaperType::Video => std::path::PathBuf::from(
                         info.entry
@@ -783,9 +2049,30 @@ pub fn run(cli: Cli) -> Result<()> {
             };
 
             if !resolved_entry.is_file() {
-                bail!("Resolved video entry does not exist: {}", resolved_entry.display());
+                bail!(
+                    "Resolved video entry does not exist: {}",
+                    resolved_entry.display()
+                );
             }
+            let resolved_entry = gif_proxy_entry(&resolved_entry, dry_run)?;
+            let resolved_entry = alpha_composite_entry(
+                &resolved_entry,
+                wallpaper_root.as_deref(),
+                &downloads_root,
+                dry_run,
+            )?;
+            // Pixels refer to the source video; the optimized proxy may be smaller.
+            let crop_region = crop_region.map(|region| match probe_video_size(&resolved_entry) {
+                Some((w, h)) => region.to_fractions(w, h),
+                None => region,
+            });
 
+            let (proxy_width, proxy_fps, proxy_crf) =
+                profile_proxy_settings(profile, proxy_width, proxy_fps, proxy_crf);
+            // Encode the proxy at the hinted rate instead of filtering it in mpv.
+            let proxy_fps = video_hints
+                .and_then(|h| h.fps)
+                .map_or(proxy_fps, |fps| proxy_fps.min(fps));
             let final_entry = if optimize {
                 let mut hasher = DefaultHasher::new();
                 resolved_entry.to_string_lossy().hash(&mut hasher);
@@ -827,22 +2114,32 @@ pub fn run(cli: Cli) -> Result<()> {
                 resolved_entry
             };
 
-            let extra_opt = seamless_loop.then_some(
-                "keep-open=no cache=yes demuxer-max-bytes=134217728 demuxer-max-back-bytes=67108864 hr-seek=no",
-            );
+            let mut extra_opts = Vec::<String>::new();
+            if seamless_loop {
+                extra_opts.push(
+                    "keep-open=no cache=yes demuxer-max-bytes=134217728 demuxer-max-back-bytes=67108864 hr-seek=no"
+                        .to_string(),
+                );
+            }
+            let crop_vf = crop_region.map(|region| {
+                eprintln!("[ok] crop region: {}", region.mpv_crop_filter());
+                with_vf_prefix(None, &region.mpv_crop_filter())
+            });
+            extra_opts.extend(video_hint_opts(video_hints, crop_vf, !optimize));
+            let extra_opt = (!extra_opts.is_empty()).then(|| extra_opts.join(" "));
 
             launch_mpvpaper_with_extra(
                 &monitor,
-                &final_entry.to_string_lossy(),
+                &final_entry,
                 profile,
                 mute_audio,
                 display_fps,
-                extra_opt,
+                extra_opt.as_deref(),
                 dry_run,
             )?;
 
-            println!("[ok] video livewallpaper: {}", final_entry.display());
-            println!(
+            eprintln!("[ok] video livewallpaper: {}", final_entry.display());
+            eprintln!(
                 "[ok] seamless_loop={} loop_crossfade={} fade_seconds={:.3} optimize={} width={} fps={} crf={}",
                 seamless_loop,
                 loop_crossfade,
@@ -854,31 +2151,280 @@ pub fn run(cli: Cli) -> Result<()> {
             );
             Ok(())
         }
+        Commands::AudioTest {
+            pattern,
+            seconds,
+            frame_ms,
+            bands,
+            frames_out,
+            frame_size,
+        } => {
+            let frame_size = parse_thumbnail_size(&frame_size)?;
+            let out = run_audio_test(
+                pattern,
+                seconds,
+                frame_ms,
+                bands,
+                frames_out.as_deref(),
+                frame_size,
+            )?;
+            print_report(&out)?;
+            Ok(())
+        }
         Commands::AudioStream {
             source,
             seconds,
             frame_ms,
         } => {
             let out = stream_audio_levels(source, seconds, frame_ms)?;
-            println!("{}", serde_json::to_string_pretty(&out)?);
+            print_report(&out)?;
             Ok(())
         }
         Commands::StopServices { services, dry_run } => {
-            let services = if services.is_empty() {
-                default_services()
-            } else {
-                services
-            };
+            let services = effective_services(services);
             stop_services(&services, dry_run)
         }
         Commands::StartServices { services, dry_run } => {
-            let services = if services.is_empty() {
-                default_services()
-            } else {
-                services
-            };
+            let services = effective_services(services);
             start_services(&services, dry_run)
         }
+        Commands::Services { command } => match command {
+            ServicesCommands::Status { services } => {
+                print_report(&services_status(&effective_services(services))?)
+            }
+        },
+        Commands::OverlayWallpaper { command } => match command {
+            OverlayWallpaperCommands::Add {
+                monitor,
+                wallpaper,
+                rect,
+                downloads_root,
+                dry_run,
+            } => {
+                let source = resolve_overlay_source(&wallpaper, &downloads_root)?;
+                let source = match probe_alpha_stream(&source) {
+                    Some(stream) => maybe_build_alpha_overlay_proxy(
+                        &source,
+                        &stream,
+                        &default_video_live_cache_root().join("alpha"),
+                        dry_run,
+                    )?,
+                    None => source,
+                };
+                let mut state = load_overlay_state()?;
+                let overlay = state.add(&monitor, &wallpaper, &source.to_string_lossy(), rect);
+                show_overlay_live(&monitor, &overlay, dry_run)?;
+                if !dry_run {
+                    save_overlay_state(&state)?;
+                    eprintln!("[ok] {}: overlay {} -> {}", monitor, overlay.id, wallpaper);
+                }
+                print_report(&overlay)
+            }
+            OverlayWallpaperCommands::Remove {
+                monitor,
+                id,
+                dry_run,
+            } => {
+                let mut state = load_overlay_state()?;
+                let removed = state.remove(&monitor, id.as_deref());
+                if removed.is_empty() {
+                    bail_msg!(Msg::NoOverlayToRemove, monitor);
+                }
+                for overlay in &removed {
+                    hide_overlay_live(&monitor, &overlay.id, dry_run)?;
+                }
+                if !dry_run {
+                    save_overlay_state(&state)?;
+                }
+                print_report(&removed)
+            }
+            OverlayWallpaperCommands::Pause {
+                monitor,
+                id,
+                dry_run,
+            } => print_report(&set_overlay_paused(&monitor, &id, true, dry_run)?),
+            OverlayWallpaperCommands::Resume {
+                monitor,
+                id,
+                dry_run,
+            } => print_report(&set_overlay_paused(&monitor, &id, false, dry_run)?),
+            OverlayWallpaperCommands::List { monitor } => {
+                let mut state = load_overlay_state()?;
+                if let Some(monitor) = monitor {
+                    state.monitors.retain(|name, _| *name == monitor);
+                }
+                print_report(&state)
+            }
+        },
+        Commands::Record {
+            monitor,
+            seconds,
+            out,
+            dry_run,
+        } => print_report(&record_monitor(&monitor, seconds, &out, dry_run)?),
+        Commands::PeerSync {
+            node_id,
+            priority,
+            listen,
+            public,
+            peers,
+            monitors,
+            downloads_root,
+            interval_seconds,
+            once,
+            dry_run,
+        } => {
+            let args = PeerSyncArgs {
+                node_id,
+                priority,
+                listen,
+                public,
+                secret: sync_secret_from_env(),
+                peers,
+                monitors,
+                downloads_root,
+                interval_seconds,
+                once,
+            };
+            run_peer_sync(args, |monitor, command| {
+                let entry = MonitorEntry {
+                    monitor: monitor.to_string(),
+                    command,
+                };
+                run_startup_entry(&entry, context, dry_run)
+            })
+        }
+        Commands::PlaybackSync {
+            monitors,
+            interval_ms,
+            once,
+            dry_run,
+        } => print_report(&run_playback_sync(
+            &monitors,
+            std::time::Duration::from_millis(interval_ms.max(50)),
+            once,
+            dry_run,
+        )?),
+        Commands::Back { monitor, dry_run } => {
+            let mut history = load_history()?;
+            let original = history.clone();
+            let previous = history
+                .step_back(&monitor)
+                .with_context(|| format!("No previous wallpaper recorded for {}", monitor))?;
+            eprintln!(
+                "[ok] {}: back to {} (applied {})",
+                monitor,
+                previous.target(),
+                previous.applied_at
+            );
+            let entry = MonitorEntry {
+                monitor: monitor.clone(),
+                command: previous.command,
+            };
+            if dry_run {
+                return run_startup_entry(&entry, context, true);
+            }
+            save_history(&history)?;
+            if let Err(err) = run_startup_entry(&entry, context, false) {
+                save_history(&original)?;
+                return Err(err);
+            }
+            Ok(())
+        }
+        Commands::History { monitor } => {
+            let mut history = load_history()?;
+            if let Some(m) = &monitor {
+                history.monitors.retain(|k, _| k == m);
+            }
+            if output_format() == OutputFormat::Json {
+                return print_report(&history);
+            }
+            if history.monitors.is_empty() {
+                warn_msg!(Msg::NoHistory);
+            }
+            for (name, entries) in &history.monitors {
+                println!("{}:", name);
+                for (i, entry) in entries.iter().enumerate().rev() {
+                    let marker = if i + 1 == entries.len() { "*" } else { " " };
+                    let title = entry
+                        .title
+                        .as_deref()
+                        .map(|t| format!("  \"{}\"", t))
+                        .unwrap_or_default();
+                    println!(
+                        "  {} {}  {:<5} {}{}",
+                        marker,
+                        entry.applied_at,
+                        entry.kind(),
+                        entry.target(),
+                        title
+                    );
+                }
+            }
+            Ok(())
+        }
+        Commands::Version { json } => {
+            if json {
+                print_report(&build_version_report())?;
+            } else {
+                println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            }
+            Ok(())
+        }
+        Commands::CacheMigrate { dry_run } => {
+            let report = migrate_cache_dir(&app_paths::app_cache_dir(), dry_run)?;
+            print_report(&report)?;
+            eprintln!(
+                "[{}] cache layout v{}: {} path(s) {}",
+                if dry_run { "dry-run" } else { "ok" },
+                report.to_version,
+                report.removed_count(),
+                if dry_run { "to remove" } else { "removed" }
+            );
+            Ok(())
+        }
+        Commands::Journal { command } => match command {
+            JournalCommands::Show {
+                since,
+                monitor,
+                kind,
+            } => {
+                let since = since
+                    .as_deref()
+                    .map(parse_since)
+                    .transpose()?
+                    .map(|age| chrono::Local::now() - age);
+                let events = filter_events(load_journal()?, since, monitor.as_deref(), kind);
+                if output_format() == OutputFormat::Json {
+                    return print_report(&events);
+                }
+                if events.is_empty() {
+                    warn_msg!(Msg::NoJournalEvents);
+                }
+                for event in &events {
+                    let duration = event
+                        .duration_ms
+                        .map(|ms| format!(" ({:.1}s)", ms as f64 / 1000.0))
+                        .unwrap_or_default();
+                    let title = event
+                        .title
+                        .as_deref()
+                        .map(|t| format!(" \"{}\"", t))
+                        .unwrap_or_default();
+                    println!(
+                        "{} {:<4} {:<11} {:<10} {}{}{}",
+                        event.at,
+                        if event.ok { "ok" } else { "FAIL" },
+                        event.kind.label(),
+                        event.monitor.as_deref().unwrap_or("-"),
+                        event.message,
+                        title,
+                        duration
+                    );
+                }
+                Ok(())
+            }
+        },
         Commands::Apply {
             wallpaper,
             monitor,
@@ -889,112 +2435,451 @@ pub fn run(cli: Cli) -> Result<()> {
             profile,
             display_fps,
             allow_scene_preview_fallback,
+            scaling,
+            smart_crop,
             dry_run,
         } => {
-            let effective_services = if services.is_empty() {
-                default_services()
-            } else {
-                services
-            };
+            let effective_services = effective_services(services);
 
             if !keep_services {
                 stop_services(&effective_services, dry_run)?;
             }
 
             stop_existing_mpvpaper_for_monitor(&monitor, dry_run)?;
+            let bundle = resolve_existing_wallpaper(&wallpaper, &downloads_root)?;
+            if is_kwescene_path(&bundle) && bundle.is_file() {
+                let profile = profile.unwrap_or(cli::PlaybackProfile::Balanced);
+                return play_kwescene(&bundle, &monitor, profile, mute_audio, display_fps, dry_run);
+            }
             let info = inspect_wallpaper(&wallpaper, &downloads_root)?;
+            eprintln!(
+                "[ok] wallpaper: {}",
+                wallpaper_name_at(&wallpaper, std::path::Path::new(&info.root)).label()
+            );
+            let allow_scene_preview_fallback = allow_scene_preview_fallback || safe_mode();
+            let rule = profile_rule_for(&info);
+            let profile = profile
+                .or(rule.as_ref().and_then(|r| r.profile))
+                .unwrap_or(cli::PlaybackProfile::Balanced);
+            let display_fps = display_fps.or(rule.as_ref().and_then(|r| r.display_fps));
+            if let Some(rule) = &rule {
+                eprintln!(
+                    "[ok] profile rule '{}' matched ({}): {}",
+                    rule.rule,
+                    rule.reason,
+                    describe_rule_effect(rule, profile, display_fps)
+                );
+            }
+            let mpv_options = merge_mpv_options(
+                rule.map(|r| r.mpv_options).unwrap_or_default(),
+                cli_mpv_options(),
+            );
 
-            match info.wallpaper_type {
-                WallpaperType::Video => {
-                    let entry = info
-                        .entry
-                        .as_deref()
-                        .context("Video wallpaper entry was not found")?;
-                    launch_mpvpaper(&monitor, entry, profile, mute_audio, display_fps, dry_run)
-                }
-                WallpaperType::Scene => {
-                    let scene_root = std::path::Path::new(&info.root);
-
-                    if let Some(fs_video) =
-                        find_scene_compatible_video(scene_root, allow_scene_preview_fallback)
-                    {
-                        eprintln!(
-                            "[warn] scene compatibility mode (filesystem): using {}",
-                            fs_video.display()
-                        );
-                        return launch_mpvpaper(
+            let fit = AspectFit {
+                scaling,
+                smart_crop,
+            };
+            with_aspect_fit(fit, || {
+                with_mpv_options(mpv_options, || match info.wallpaper_type {
+                    WallpaperType::Video => {
+                        let entry = info
+                            .entry
+                            .as_deref()
+                            .context("Video wallpaper entry was not found")?;
+                        let entry = gif_proxy_entry(std::path::Path::new(entry), dry_run)?;
+                        let entry = alpha_composite_entry(
+                            &entry,
+                            Some(std::path::Path::new(&info.root)),
+                            &downloads_root,
+                            dry_run,
+                        )?;
+                        let extra_opt = video_hint_opts(info.video_hints, None, true).join(" ");
+                        launch_mpvpaper_with_extra(
                             &monitor,
-                            &fs_video.to_string_lossy(),
+                            &entry,
                             profile,
                             mute_audio,
                             display_fps,
+                            (!extra_opt.is_empty()).then_some(extra_opt.as_str()),
                             dry_run,
-                        );
+                        )
                     }
+                    WallpaperType::Scene => {
+                        let scene_root = std::path::Path::new(&info.root);
 
-                    let pkg_path = if scene_root.join("scene.pkg").is_file() {
-                        Some(scene_root.join("scene.pkg"))
-                    } else if scene_root.join("gifscene.pkg").is_file() {
-                        Some(scene_root.join("gifscene.pkg"))
-                    } else {
-                        None
-                    };
-
-                    if let Some(pkg_path) = pkg_path {
-                        let pkg = parse_scene_pkg(&pkg_path).with_context(|| {
-                            format!("Failed to parse scene package {}", pkg_path.display())
-                        })?;
-
-                        if let Some(best) = best_video_entry(&pkg, allow_scene_preview_fallback) {
-                            let cache_key = info
-                                .workshopid
-                                .clone()
-                                .unwrap_or_else(|| scene_root.to_string_lossy().replace('/', "_"));
-                            let cache_root = default_scene_cache_root(&cache_key);
-
-                            let extracted = if dry_run {
-                                cache_root.join(&best.filename)
-                            } else {
-                                extract_entry_to_cache(&pkg, &best, &cache_root)?
-                            };
-
-                            eprintln!(
-                                "[warn] scene compatibility mode (pkg extract): {} -> {}",
-                                best.filename,
-                                extracted.display()
-                            );
-
+                        if let Some(fs_video) =
+                            find_scene_compatible_video(scene_root, allow_scene_preview_fallback)
+                        {
+                            warn_fallback(&monitor, Msg::CompatFilesystem, &[&fs_video.display()]);
                             return launch_mpvpaper(
                                 &monitor,
-                                &extracted.to_string_lossy(),
+                                &fs_video,
                                 profile,
                                 mute_audio,
                                 display_fps,
                                 dry_run,
                             );
                         }
-                    }
 
-                    let plan_hint = build_scene_plan(scene_root)
-                        .ok()
-                        .and_then(|p| serde_json::to_string_pretty(&p).ok())
-                        .unwrap_or_else(|| "{}".to_string());
+                        let pkg_path = if scene_root.join("scene.pkg").is_file() {
+                            Some(scene_root.join("scene.pkg"))
+                        } else if scene_root.join("gifscene.pkg").is_file() {
+                            Some(scene_root.join("gifscene.pkg"))
+                        } else {
+                            None
+                        };
 
-                    bail!(
-                        "Scene wallpaper detected, but no usable video fallback was found in filesystem or package. \
+                        if let Some(pkg_path) = pkg_path {
+                            let pkg = parse_scene_pkg(&pkg_path).with_context(|| {
+                                format!("Failed to parse scene package {}", pkg_path.display())
+                            })?;
+
+                            if let Some(best) = best_video_entry(&pkg, allow_scene_preview_fallback)
+                            {
+                                let cache_key = info.workshopid.clone().unwrap_or_else(|| {
+                                    scene_root.to_string_lossy().replace('/', "_")
+                                });
+                                let cache_root = default_scene_cache_root(&cache_key);
+
+                                let extracted = if dry_run {
+                                    cache_root.join(&best.filename)
+                                } else {
+                                    refresh_scene_cache(&pkg)?;
+                                    extract_entry_to_cache(&pkg, &best, &cache_root)?
+                                };
+
+                                warn_fallback(
+                                    &monitor,
+                                    Msg::CompatPkgExtract,
+                                    &[&best.filename, &extracted.display()],
+                                );
+
+                                return launch_mpvpaper(
+                                    &monitor,
+                                    &extracted,
+                                    profile,
+                                    mute_audio,
+                                    display_fps,
+                                    dry_run,
+                                );
+                            }
+                        }
+
+                        let plan_hint = build_scene_plan(scene_root)
+                            .ok()
+                            .and_then(|p| serde_json::to_string_pretty(&p).ok())
+                            .unwrap_or_else(|| "{}".to_string());
+
+                        bail!(
+                            "Scene wallpaper detected, but no usable video fallback was found in filesystem or package. \
 Use native scene renderer path and inspect assets with `scene-plan`/`scene-runtime`/`scene-render`, or force low-quality fallback with --allow-scene-preview-fallback.\n\nScene diagnostics:\n{}\n\nScene plan:\n{}",
-                        scene_diagnostics_json(info.scene.as_ref()),
-                        plan_hint
-                    );
-                }
-                WallpaperType::Web => {
-                    bail!("Web wallpapers are not implemented yet in kitsune-livewallpaper MVP")
-                }
-                WallpaperType::Application => bail!(
-                    "Application wallpapers are not implemented yet in kitsune-livewallpaper MVP"
+                            scene_diagnostics_json(info.scene.as_ref()),
+                            plan_hint
+                        );
+                    }
+                    WallpaperType::Web => {
+                        bail_msg!(Msg::WallpaperTypeUnsupported, "Web")
+                    }
+                    WallpaperType::Application => {
+                        bail_msg!(Msg::WallpaperTypeUnsupported, "Application")
+                    }
+                    WallpaperType::Unknown => bail!("Unsupported/unknown wallpaper type"),
+                })
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use app_paths::with_app_dirs_root;
+    use clap::Parser;
+    use command_runner::{MockRunner, with_runner};
+    use std::rc::Rc;
+
+    /// Parses `args` and runs them against a [`MockRunner`] (set up by
+    /// `respond`) with the app dirs under `app_dirs`, returning the recorded
+    /// commands. Building the clap command and `run_command`'s frame outgrow
+    /// the default test stack in debug builds, so this runs on its own thread.
+    fn run_cli_with_mock(
+        args: &[String],
+        app_dirs: &std::path::Path,
+        respond: impl FnOnce(&MockRunner) + Send + 'static,
+    ) -> Result<Vec<String>> {
+        let (args, app_dirs) = (args.to_vec(), app_dirs.to_path_buf());
+        std::thread::Builder::new()
+            .stack_size(32 * 1024 * 1024)
+            .spawn(move || {
+                let cli = Cli::try_parse_from(args)?;
+                let mock = Rc::new(MockRunner::touching_outputs());
+                respond(&mock);
+                with_app_dirs_root(&app_dirs, || with_runner(mock.clone(), || run(cli)))
+                    .map(|()| mock.commands())
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    /// Scene wallpaper `<downloads>/<id>` whose scene.pkg holds an empty
+    /// scene.json and one PNG texture.
+    fn write_scene_wallpaper(downloads: &std::path::Path, id: &str) {
+        let root = downloads.join(id);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("project.json"),
+            r#"{"type":"scene","file":"scene.json","title":"Fixture scene"}"#,
+        )
+        .unwrap();
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(64, 36, image::Rgba([40, 80, 120, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        scene_pkg::write_scene_pkg(
+            &root.join("scene.pkg"),
+            &[
+                ("scene.json".to_string(), br#"{"objects":[]}"#.to_vec()),
+                ("materials/bg.png".to_string(), png),
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn scene_play_and_scene_gpu_play_command_graphs() {
+        let dir = tempfile::tempdir().unwrap();
+        write_scene_wallpaper(dir.path(), "2002");
+        let downloads = dir.path().to_string_lossy().to_string();
+        for (command, proxy_settings) in [
+            ("scene-play", "1920w_10fps_crf28"),
+            // mp4 transport: the quality preset's proxy settings.
+            ("scene-gpu-play", "2560w_10fps_crf20"),
+        ] {
+            let app_dirs = tempfile::tempdir().unwrap();
+            let session = app_dirs.path().join("cache/scene/2002/render-session");
+            let proxy = session.join(format!(
+                "proxy-opt/scene_animated_proxy_opt_{}.mp4",
+                proxy_settings
+            ));
+            let args = [
+                "kitsune-livewallpaper",
+                command,
+                "2002",
+                "--monitor",
+                "KWE-TEST-1",
+                "--downloads-root",
+                &downloads,
+                "--keep-services",
+            ]
+            .map(str::to_string);
+            let running = format!("4242 mpvpaper -o opts KWE-TEST-1 {}\n", proxy.display());
+            let cmds = run_cli_with_mock(&args, app_dirs.path(), move |mock| {
+                mock.respond("pgrep", true, &running);
+            })
+            .unwrap_or_else(|err| panic!("{command}: {err:#}"));
+
+            assert_eq!(cmds.len(), 9, "{command}: {cmds:#?}");
+            assert_eq!(cmds[0], "pgrep -fa mpvpaper");
+            // The running session plays from our cache, so it is replaced.
+            assert_eq!(cmds[1], "kill 4242");
+            assert_eq!(cmds[2], "hyprctl -j monitors");
+            let effect_proxy = session.join("effect-proxy/scene_animated_proxy.mp4");
+            assert!(cmds[3].starts_with("ffmpeg ") && cmds[3].contains("assets/materials/bg.png"));
+            assert!(cmds[3].ends_with(&effect_proxy.display().to_string()));
+            assert!(cmds[4].contains(&format!("-i {} ", effect_proxy.display())));
+            assert!(cmds[4].ends_with(&proxy.display().to_string()));
+            // Refresh rate for mpv; neither tool knows the test monitor.
+            assert_eq!(cmds[5], "hyprctl -j monitors");
+            assert_eq!(cmds[6], "wlr-randr --json");
+            assert!(cmds[7].starts_with("nohup mpvpaper -o --loop-file=inf "));
+            // Static scene: mpv is capped at the inferred 10 fps.
+            assert!(cmds[7].contains(" vf-append=fps=10 "), "{}", cmds[7]);
+            assert!(!cmds[7].contains("display-fps-override"));
+            assert!(cmds[7].ends_with(&format!(" KWE-TEST-1 {}", proxy.display())));
+            assert_eq!(cmds[8], "pgrep -fa mpvpaper");
+            assert!(session.join("manifest.json").is_file());
+        }
+    }
+
+    #[test]
+    fn crop_region_runs_before_the_text_overlays() {
+        // The crop comes first so text is laid out on the cropped frame.
+        assert_eq!(with_vf_prefix(None, "crop=w=10"), "vf=crop=w=10");
+        assert_eq!(
+            with_vf_prefix(Some("vf=drawtext=text=a".to_string()), "crop=w=10"),
+            "vf=crop=w=10,drawtext=text=a"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        write_scene_wallpaper(dir.path(), "2003");
+        let downloads = dir.path().to_string_lossy().to_string();
+        let app_dirs = tempfile::tempdir().unwrap();
+        let args = [
+            "kitsune-livewallpaper",
+            "scene-play",
+            "2003",
+            "--monitor",
+            "KWE-TEST-1",
+            "--downloads-root",
+            &downloads,
+            "--keep-services",
+            "--crop-region",
+            "0,0,0.5,1",
+        ]
+        .map(str::to_string);
+        let proxy = app_dirs.path().join(
+            "cache/scene/2003/render-session/proxy-opt/scene_animated_proxy_opt_1920w_10fps_crf28.mp4",
+        );
+        let running = format!("4242 mpvpaper -o opts KWE-TEST-1 {}\n", proxy.display());
+        let cmds = run_cli_with_mock(&args, app_dirs.path(), move |mock| {
+            mock.respond("pgrep", true, &running);
+        })
+        .unwrap();
+        let launch = cmds
+            .iter()
+            .find(|c| c.starts_with("nohup mpvpaper "))
+            .unwrap_or_else(|| panic!("{cmds:#?}"));
+        assert!(
+            launch.contains(" vf=crop=w=iw*0.50000:h=ih*1.00000:x=iw*0.00000:y=ih*0.00000"),
+            "{launch}"
+        );
+    }
+
+    #[test]
+    fn apply_video_wallpaper_command_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("1001");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("loop.mp4"), b"x").unwrap();
+        std::fs::write(
+            root.join("project.json"),
+            r#"{"type":"Video","file":"loop.mp4","title":"Fixture"}"#,
+        )
+        .unwrap();
+        let entry = root.join("loop.mp4").to_string_lossy().to_string();
+
+        let mock = Rc::new(MockRunner::new());
+        mock.respond(
+            "pgrep",
+            true,
+            &format!("4242 mpvpaper -o opts KWE-TEST-1 {}\n", entry),
+        );
+        mock.respond(
+            "hyprctl",
+            true,
+            r#"[{"name":"KWE-TEST-1","refreshRate":143.86,"width":3440,"height":1440}]"#,
+        );
+        mock.respond("ffprobe", true, "1920x1080\n");
+        let app_dirs = tempfile::tempdir().unwrap();
+        with_app_dirs_root(app_dirs.path(), || {
+            with_runner(mock.clone(), || {
+                run(Cli {
+                    output: OutputFormat::Json,
+                    safe: false,
+                    instance: None,
+                    force: false,
+                    mpv_options: Vec::new(),
+                    allow_obfuscated: false,
+                    read_only_library: false,
+                    lang: None,
+                    command: Commands::Apply {
+                        wallpaper: "1001".to_string(),
+                        monitor: "KWE-TEST-1".to_string(),
+                        downloads_root: dir.path().to_path_buf(),
+                        keep_services: true,
+                        services: Vec::new(),
+                        mute_audio: true,
+                        profile: Some(cli::PlaybackProfile::Performance),
+                        display_fps: None,
+                        allow_scene_preview_fallback: false,
+                        scaling: None,
+                        smart_crop: false,
+                        dry_run: false,
+                    },
+                })
+            })
+        })
+        .unwrap();
+        // The apply is recorded in the test's own state dir, never the user's.
+        let history = std::fs::read_to_string(app_dirs.path().join("state/history.json")).unwrap();
+        assert!(history.contains(r#""wallpaper": "1001""#), "{history}");
+        assert!(
+            app_dirs
+                .path()
+                .join("state/overlay-plan/KWE-TEST-1.json")
+                .is_file()
+        );
+
+        let cmds = mock.commands();
+        assert_eq!(cmds.len(), 5, "{cmds:#?}");
+        // The fixture process is not a kwe session, so nothing is killed.
+        assert_eq!(cmds[0], "pgrep -fa mpvpaper");
+        // One query for the refresh rate and the output size, then the
+        // video size for the aspect check.
+        assert_eq!(cmds[1], "hyprctl -j monitors");
+        assert!(cmds[2].starts_with("ffprobe "));
+        assert!(cmds[3].starts_with(
+            "nohup mpvpaper -o --loop-file=inf hwdec=auto-safe keep-open=yes profile=fast"
+        ));
+        let socket = app_dirs.path().join("runtime/kwe-mpv-KWE-TEST-1.sock");
+        assert!(cmds[3].contains(&format!(
+            " no-audio display-fps-override=144 input-ipc-server={}",
+            socket.display()
+        )));
+        assert!(cmds[3].ends_with(&format!(" KWE-TEST-1 {}", entry)));
+        assert_eq!(cmds[4], "pgrep -fa mpvpaper");
+    }
+
+    #[test]
+    fn kwescene_bundles_play_through_scene_play_and_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("rain.kwescene");
+        let manifest = serde_json::json!({
+            "version": 1, "source_root": "/w/1", "title": null, "workshop_id": null,
+            "scene_width": 1920, "scene_height": 1080,
+            "visual_entry": "visual/base.png", "baked_proxy_entry": "proxy/scene.mp4",
+            "music_entry": null, "uniforms_entry": "curves/uniforms.json",
+            "frame_ms": 50, "frame_count": 0, "layers": [], "user_properties": null,
+            "script_assignments": [], "notes": []
+        });
+        scene_pkg::write_scene_pkg(
+            &bundle,
+            &[
+                (
+                    "kwescene.json".to_string(),
+                    manifest.to_string().into_bytes(),
                 ),
-                WallpaperType::Unknown => bail!("Unsupported/unknown wallpaper type"),
-            }
+                ("visual/base.png".to_string(), vec![0; 4]),
+                ("proxy/scene.mp4".to_string(), vec![1; 4]),
+            ],
+        )
+        .unwrap();
+
+        let app_dirs = tempfile::tempdir().unwrap();
+        let bundle_arg = bundle.to_string_lossy().to_string();
+        for command in ["scene-play", "apply"] {
+            let args = [
+                "kitsune-livewallpaper",
+                command,
+                &bundle_arg,
+                "--monitor",
+                "KWE-TEST-1",
+                "--keep-services",
+                "--dry-run",
+            ]
+            .map(str::to_string);
+            let cmds = run_cli_with_mock(&args, app_dirs.path(), |_| {})
+                .unwrap_or_else(|err| panic!("{command}: {err:#}"));
+            assert_eq!(cmds[0], "pgrep -fa mpvpaper");
+            // Dry run: the extracted proxy is never handed to mpvpaper.
+            assert!(!cmds.iter().any(|c| c.contains("mpvpaper -o")), "{cmds:#?}");
         }
+        assert!(
+            app_dirs
+                .path()
+                .join("cache/kwescene/rain/proxy/scene.mp4")
+                .is_file()
+        );
     }
 }
@@ -0,0 +1,107 @@
+use crate::cli::default_downloads_root;
+use crate::i18n::{Msg, bail_msg};
+use anyhow::Result;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static READ_ONLY_LIBRARY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether `--read-only-library` is set for the running command.
+pub fn read_only_library() -> bool {
+    READ_ONLY_LIBRARY.with(Cell::get)
+}
+
+/// Runs `f` refusing every write into the wallpaper library on this thread.
+pub fn with_read_only_library<R>(enabled: bool, f: impl FnOnce() -> R) -> R {
+    let previous = READ_ONLY_LIBRARY.with(|c| c.replace(enabled));
+    let result = f();
+    READ_ONLY_LIBRARY.with(|c| c.set(previous));
+    result
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    std::env::current_dir()
+        .map(|cwd| cwd.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Library folder `path` falls in: one of `roots`, or a wallpaper folder
+/// (any ancestor holding a `project.json`).
+pub fn library_dir_containing(path: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let path = absolute(path);
+    if let Some(dir) = path
+        .ancestors()
+        .find(|dir| dir.join("project.json").is_file())
+    {
+        return Some(dir.to_path_buf());
+    }
+    roots.iter().find_map(|root| {
+        let root = absolute(root);
+        let canonical = root.canonicalize().unwrap_or_else(|_| root.clone());
+        (path.starts_with(&root) || path.starts_with(&canonical)).then_some(root)
+    })
+}
+
+/// Fails when `--read-only-library` is set and `path` lies in the library.
+/// Called before anything is written, so the command stops with nothing
+/// half-done.
+pub fn ensure_outside_library(path: &Path, what: &str) -> Result<()> {
+    if !read_only_library() {
+        return Ok(());
+    }
+    // Without a data dir the default root is `.`, which says nothing.
+    let roots = Some(default_downloads_root())
+        .filter(|root| root != Path::new("."))
+        .into_iter()
+        .collect::<Vec<_>>();
+    if let Some(dir) = library_dir_containing(path, &roots) {
+        bail_msg!(
+            Msg::ReadOnlyLibraryWrite,
+            what,
+            path.display(),
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_into_wallpaper_folders_are_refused_only_when_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join("downloads");
+        let wallpaper = dir.path().join("elsewhere/1234");
+        std::fs::create_dir_all(&wallpaper).unwrap();
+        std::fs::write(wallpaper.join("project.json"), "{}").unwrap();
+        let roots = [library.clone()];
+
+        assert_eq!(
+            library_dir_containing(&library.join("5678/proxy.mp4"), &roots),
+            Some(library.clone())
+        );
+        assert_eq!(
+            library_dir_containing(&wallpaper.join("out/scene.pkg"), &roots),
+            Some(wallpaper.clone())
+        );
+        assert_eq!(
+            library_dir_containing(&dir.path().join("cache/x.mp4"), &roots),
+            None
+        );
+
+        let target = wallpaper.join("scene.pkg");
+        assert!(ensure_outside_library(&target, "scene-pkg import").is_ok());
+        let err =
+            with_read_only_library(true, || ensure_outside_library(&target, "scene-pkg import"))
+                .unwrap_err();
+        assert!(err.to_string().contains("--read-only-library"), "{err}");
+        assert!(!read_only_library());
+    }
+}
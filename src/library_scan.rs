@@ -1,14 +1,14 @@
 use crate::scene_effect_proxy::build_scene_audio_bars_overlay;
-use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_gpu_graph::{GraphDetail, build_scene_gpu_graph_with, graph_reads_audio_spectrum};
 use crate::scene_plan::build_scene_plan;
 use crate::types::WallpaperType;
 use crate::wallpaper::inspect_wallpaper;
 use anyhow::Result;
 use chrono::Local;
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct EffectFrequency {
@@ -21,6 +21,8 @@ pub struct WallpaperCompatStatus {
     pub id: String,
     pub root: String,
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
     pub wallpaper_type: WallpaperType,
     pub compatibility_percent: u8,
     pub quality_tier: String,
@@ -29,6 +31,8 @@ pub struct WallpaperCompatStatus {
     pub effect_nodes: usize,
     pub likely_audio_reactive: bool,
     pub audio_overlay_plan_available: bool,
+    /// Some shader reads the `g_AudioSpectrum*` arrays.
+    pub reads_audio_spectrum: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -39,22 +43,41 @@ pub struct LibraryScanReport {
     pub average_compatibility_percent: f32,
     pub counts_by_type: HashMap<String, usize>,
     pub top_effects: Vec<EffectFrequency>,
+    /// Set when `--low-memory` had to prune rare effects from the histogram;
+    /// `top_effects` counts are then lower bounds.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub effect_histogram_pruned: bool,
+    /// Scenes whose shaders read the `g_AudioSpectrum*` arrays.
+    pub audio_spectrum_wallpapers: usize,
     pub wallpapers: Vec<WallpaperCompatStatus>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoadmapWallpaperRef {
+    pub id: String,
+    pub title: Option<String>,
+    pub compatibility_percent: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoadmapEffectItem {
     pub rank: usize,
     pub effect_file: String,
     pub wallpapers_affected: usize,
     pub avg_current_score: f32,
     pub estimated_coverage_gain_points: f32,
+    /// Lowest score first, so exports list the worst-off wallpapers on top.
+    #[serde(default)]
+    pub affected_wallpapers: Vec<RoadmapWallpaperRef>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LibraryRoadmapReport {
     pub generated_at: String,
     pub downloads_root: String,
+    /// `--top-n` the roadmap was built with.
+    #[serde(default)]
+    pub top_n: usize,
     pub wallpapers_scanned: usize,
     pub baseline_average_compatibility_percent: f32,
     pub estimated_average_after_top_n: f32,
@@ -77,27 +100,110 @@ fn tier_for(score: u8) -> String {
     }
 }
 
+/// Distinct effects kept by the `--low-memory` histogram before pruning.
+pub const LOW_MEMORY_EFFECT_HIST_CAP: usize = 256;
+
+/// Effect-file histogram; with a cap, it drops the rarest entries once it
+/// holds twice the cap, so memory stays flat on huge libraries.
+#[derive(Debug, Default)]
+struct EffectHistogram {
+    counts: HashMap<String, usize>,
+    cap: Option<usize>,
+    pruned: bool,
+}
+
+impl EffectHistogram {
+    fn bounded(cap: usize) -> Self {
+        Self {
+            cap: Some(cap.max(1)),
+            ..Self::default()
+        }
+    }
+
+    fn add(&mut self, effect_file: String) {
+        *self.counts.entry(effect_file).or_insert(0) += 1;
+        if let Some(cap) = self.cap
+            && self.counts.len() > cap * 2
+        {
+            let mut ranked: Vec<(String, usize)> = self.counts.drain().collect();
+            ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            ranked.truncate(cap);
+            self.counts.extend(ranked);
+            self.pruned = true;
+        }
+    }
+
+    fn into_top(self, top_effects: usize) -> Vec<EffectFrequency> {
+        let mut top: Vec<EffectFrequency> = self
+            .counts
+            .into_iter()
+            .map(|(effect_file, count)| EffectFrequency { effect_file, count })
+            .collect();
+        top.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.effect_file.cmp(&b.effect_file))
+        });
+        top.truncate(top_effects);
+        top
+    }
+}
+
+/// `low_memory` streams the directory listing instead of collecting and
+/// sorting it first, and bounds the effect histogram.
 pub fn scan_library(
     downloads_root: &Path,
     top_effects: usize,
     summary_only: bool,
+    low_memory: bool,
 ) -> Result<LibraryScanReport> {
-    let mut dirs = Vec::new();
-    for entry in fs::read_dir(downloads_root)? {
-        let entry = match entry {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-        let path = entry.path();
-        if path.is_dir() {
-            dirs.push(path);
-        }
+    let dirs = fs::read_dir(downloads_root)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir());
+    if low_memory {
+        scan_library_dirs(downloads_root, dirs, top_effects, summary_only, true)
+    } else {
+        let mut dirs: Vec<PathBuf> = dirs.collect();
+        dirs.sort();
+        scan_library_dirs(downloads_root, dirs, top_effects, summary_only, false)
     }
+}
+
+/// Incremental variant: only scans the given item ids under `downloads_root`
+/// (missing ids are skipped).
+pub fn scan_library_items(
+    downloads_root: &Path,
+    ids: &[String],
+    top_effects: usize,
+    summary_only: bool,
+) -> Result<LibraryScanReport> {
+    let mut dirs: Vec<PathBuf> = ids
+        .iter()
+        .map(|id| downloads_root.join(id))
+        .filter(|p| p.is_dir())
+        .collect();
     dirs.sort();
+    scan_library_dirs(downloads_root, dirs, top_effects, summary_only, false)
+}
 
+fn scan_library_dirs(
+    downloads_root: &Path,
+    dirs: impl IntoIterator<Item = PathBuf>,
+    top_effects: usize,
+    summary_only: bool,
+    low_memory: bool,
+) -> Result<LibraryScanReport> {
     let mut scanned = Vec::<WallpaperCompatStatus>::new();
+    let mut scanned_count = 0usize;
+    let mut audio_spectrum_wallpapers = 0usize;
+    let mut score_sum = 0u64;
     let mut counts_by_type = HashMap::<String, usize>::new();
-    let mut effect_hist = HashMap::<String, usize>::new();
+    let mut effect_hist = if low_memory {
+        EffectHistogram::bounded(LOW_MEMORY_EFFECT_HIST_CAP.max(top_effects))
+    } else {
+        EffectHistogram::default()
+    };
 
     for dir in dirs {
         let id = dir
@@ -109,14 +215,17 @@ pub fn scan_library(
         let mut score: i32 = 35;
         let mut wtype = WallpaperType::Unknown;
         let mut title = None;
+        let mut author = None;
         let mut effect_nodes = 0usize;
         let mut likely_audio_reactive = false;
         let mut audio_overlay_plan_available = false;
+        let mut reads_audio_spectrum = false;
 
         match inspect_wallpaper(&dir.to_string_lossy(), downloads_root) {
             Ok(info) => {
                 wtype = info.wallpaper_type.clone();
                 title = info.title.clone();
+                author = info.author.clone();
                 *counts_by_type
                     .entry(format!("{:?}", info.wallpaper_type).to_ascii_lowercase())
                     .or_insert(0) += 1;
@@ -164,18 +273,37 @@ pub fn scan_library(
                             }
                         }
 
-                        match build_scene_gpu_graph(Path::new(&info.root)) {
+                        // Only counts and effect files are read here, so
+                        // shaders and uniforms are left unresolved.
+                        match build_scene_gpu_graph_with(
+                            Path::new(&info.root),
+                            &BTreeMap::new(),
+                            GraphDetail::Summary,
+                        ) {
                             Ok(graph) => {
                                 effect_nodes = graph.effect_nodes.len();
                                 if effect_nodes > 0 {
                                     capabilities.push("effect-graph-detected".to_string());
                                     score -= (effect_nodes as i32).min(28);
                                 }
+                                if !graph.viewports.is_empty() {
+                                    capabilities.push("split-scene-viewports".to_string());
+                                    score -= 6;
+                                    issues.push(format!(
+                                        "Scene defines {} viewports; only native transports composite them (mp4-proxy shows the unsplit canvas)",
+                                        graph.viewports.len()
+                                    ));
+                                }
+                                reads_audio_spectrum =
+                                    graph_reads_audio_spectrum(Path::new(&info.root), &graph);
+                                if reads_audio_spectrum {
+                                    capabilities.push("audio-spectrum-uniforms".to_string());
+                                }
                                 for node in graph.effect_nodes {
                                     if node.effect_file.is_empty() {
                                         continue;
                                     }
-                                    *effect_hist.entry(node.effect_file).or_insert(0) += 1;
+                                    effect_hist.add(node.effect_file);
                                 }
                             }
                             Err(err) => {
@@ -218,11 +346,18 @@ pub fn scan_library(
         }
 
         let compatibility_percent = clamp_score(score);
+        scanned_count += 1;
+        score_sum += compatibility_percent as u64;
+        audio_spectrum_wallpapers += usize::from(reads_audio_spectrum);
+        if summary_only {
+            continue;
+        }
         let tier = tier_for(compatibility_percent);
         scanned.push(WallpaperCompatStatus {
             id,
             root: dir.to_string_lossy().to_string(),
             title,
+            author,
             wallpaper_type: wtype,
             compatibility_percent,
             quality_tier: tier,
@@ -231,6 +366,7 @@ pub fn scan_library(
             effect_nodes,
             likely_audio_reactive,
             audio_overlay_plan_available,
+            reads_audio_spectrum,
         });
     }
 
@@ -240,43 +376,29 @@ pub fn scan_library(
             .then_with(|| a.id.cmp(&b.id))
     });
 
-    let avg = if scanned.is_empty() {
+    let avg = if scanned_count == 0 {
         0.0
     } else {
-        scanned
-            .iter()
-            .map(|s| s.compatibility_percent as f32)
-            .sum::<f32>()
-            / scanned.len() as f32
+        score_sum as f32 / scanned_count as f32
     };
-
-    let mut top: Vec<EffectFrequency> = effect_hist
-        .into_iter()
-        .map(|(effect_file, count)| EffectFrequency { effect_file, count })
-        .collect();
-    top.sort_by(|a, b| {
-        b.count
-            .cmp(&a.count)
-            .then_with(|| a.effect_file.cmp(&b.effect_file))
-    });
-    if top.len() > top_effects {
-        top.truncate(top_effects);
-    }
+    let effect_histogram_pruned = effect_hist.pruned;
 
     Ok(LibraryScanReport {
         generated_at: Local::now().format("%Y-%m-%d %H:%M:%S %z").to_string(),
         downloads_root: downloads_root.to_string_lossy().to_string(),
-        wallpapers_scanned: scanned.len(),
+        wallpapers_scanned: scanned_count,
         average_compatibility_percent: ((avg * 100.0).round() / 100.0),
         counts_by_type,
-        top_effects: top,
-        wallpapers: if summary_only { Vec::new() } else { scanned },
+        top_effects: effect_hist.into_top(top_effects),
+        effect_histogram_pruned,
+        audio_spectrum_wallpapers,
+        wallpapers: scanned,
     })
 }
 
 pub fn build_library_roadmap(downloads_root: &Path, top_n: usize) -> Result<LibraryRoadmapReport> {
-    let report = scan_library(downloads_root, 500, false)?;
-    let mut effect_to_scores = HashMap::<String, Vec<u8>>::new();
+    let report = scan_library(downloads_root, 500, false, false)?;
+    let mut effect_to_scores = HashMap::<String, Vec<RoadmapWallpaperRef>>::new();
     let mut dirs = Vec::new();
     for entry in fs::read_dir(downloads_root)? {
         let entry = match entry {
@@ -290,9 +412,9 @@ pub fn build_library_roadmap(downloads_root: &Path, top_n: usize) -> Result<Libr
     }
     dirs.sort();
 
-    let mut score_by_id = HashMap::<String, u8>::new();
+    let mut by_id = HashMap::<String, &WallpaperCompatStatus>::new();
     for w in &report.wallpapers {
-        score_by_id.insert(w.id.clone(), w.compatibility_percent);
+        by_id.insert(w.id.clone(), w);
     }
 
     for dir in dirs {
@@ -300,10 +422,11 @@ pub fn build_library_roadmap(downloads_root: &Path, top_n: usize) -> Result<Libr
             Some(v) => v.to_string_lossy().to_string(),
             None => continue,
         };
-        let Some(score) = score_by_id.get(&id).copied() else {
+        let Some(status) = by_id.get(&id) else {
             continue;
         };
-        let Ok(graph) = build_scene_gpu_graph(&dir) else {
+        let Ok(graph) = build_scene_gpu_graph_with(&dir, &BTreeMap::new(), GraphDetail::Summary)
+        else {
             continue;
         };
         if graph.effect_nodes.is_empty() {
@@ -317,18 +440,33 @@ pub fn build_library_roadmap(downloads_root: &Path, top_n: usize) -> Result<Libr
             uniq.insert(node.effect_file, true);
         }
         for effect in uniq.into_keys() {
-            effect_to_scores.entry(effect).or_default().push(score);
+            effect_to_scores
+                .entry(effect)
+                .or_default()
+                .push(RoadmapWallpaperRef {
+                    id: id.clone(),
+                    title: status.title.clone(),
+                    compatibility_percent: status.compatibility_percent,
+                });
         }
     }
 
     let mut items = Vec::<RoadmapEffectItem>::new();
-    for (effect_file, mut scores) in effect_to_scores {
-        if scores.is_empty() {
+    for (effect_file, mut wallpapers) in effect_to_scores {
+        if wallpapers.is_empty() {
             continue;
         }
-        scores.sort();
-        let affected = scores.len();
-        let avg_score = scores.iter().map(|v| *v as f32).sum::<f32>() / affected as f32;
+        wallpapers.sort_by(|a, b| {
+            a.compatibility_percent
+                .cmp(&b.compatibility_percent)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        let affected = wallpapers.len();
+        let avg_score = wallpapers
+            .iter()
+            .map(|w| w.compatibility_percent as f32)
+            .sum::<f32>()
+            / affected as f32;
         let lower_pressure = ((100.0 - avg_score) / 100.0).clamp(0.0, 1.0);
         let effect_weight = (affected as f32).sqrt().clamp(1.0, 12.0);
         let gain = (lower_pressure * effect_weight * 3.2).clamp(0.0, 15.0);
@@ -338,6 +476,7 @@ pub fn build_library_roadmap(downloads_root: &Path, top_n: usize) -> Result<Libr
             wallpapers_affected: affected,
             avg_current_score: ((avg_score * 100.0).round()) / 100.0,
             estimated_coverage_gain_points: ((gain * 100.0).round()) / 100.0,
+            affected_wallpapers: wallpapers,
         });
     }
 
@@ -367,9 +506,36 @@ pub fn build_library_roadmap(downloads_root: &Path, top_n: usize) -> Result<Libr
     Ok(LibraryRoadmapReport {
         generated_at: Local::now().format("%Y-%m-%d %H:%M:%S %z").to_string(),
         downloads_root: downloads_root.to_string_lossy().to_string(),
+        top_n,
         wallpapers_scanned: report.wallpapers_scanned,
         baseline_average_compatibility_percent: baseline,
         estimated_average_after_top_n: ((estimated_after * 100.0).round()) / 100.0,
         top_recommendations: items,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_histogram_keeps_frequent_effects() {
+        let mut hist = EffectHistogram::bounded(2);
+        for _ in 0..3 {
+            hist.add("effects/shake/effect.json".to_string());
+        }
+        hist.add("effects/waterripple/effect.json".to_string());
+        hist.add("effects/waterripple/effect.json".to_string());
+        for i in 0..4 {
+            hist.add(format!("effects/rare{i}/effect.json"));
+        }
+        assert!(hist.pruned);
+        assert!(hist.counts.len() <= 4);
+
+        let top = hist.into_top(2);
+        assert_eq!(top[0].effect_file, "effects/shake/effect.json");
+        assert_eq!(top[0].count, 3);
+        assert_eq!(top[1].effect_file, "effects/waterripple/effect.json");
+        assert_eq!(top[1].count, 2);
+    }
+}
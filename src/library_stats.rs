@@ -0,0 +1,470 @@
+use crate::app_paths::app_cache_dir;
+use crate::command_runner::CommandExt;
+use crate::library_scan::{LibraryScanReport, scan_library};
+use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_native_runtime::{NativeSupportTier, build_native_runtime_plan};
+use crate::types::WallpaperType;
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const QUALITY_TIERS: [&str; 4] = ["excellent", "good", "partial", "limited"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnsupportedFamilyStat {
+    pub family: String,
+    /// Scene wallpapers with at least one pass of this family.
+    pub wallpapers: usize,
+    pub passes: usize,
+}
+
+/// Library-wide compatibility numbers for a dashboard. Holds no ids, titles
+/// or paths, so it can be shared as is.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryStats {
+    pub generated_at: String,
+    pub engine_version: String,
+    pub wallpapers_scanned: usize,
+    pub average_compatibility_percent: f32,
+    pub counts_by_type: BTreeMap<String, usize>,
+    pub counts_by_tier: BTreeMap<String, usize>,
+    pub average_compatibility_by_type: BTreeMap<String, f32>,
+    /// Scenes whose shaders read the `g_AudioSpectrum*` arrays.
+    pub audio_spectrum_wallpapers: usize,
+    pub top_unsupported_families: Vec<UnsupportedFamilyStat>,
+    /// Bytes per top-level directory of the app cache (`scene`, `proxies`...).
+    pub cache_bytes: BTreeMap<String, u64>,
+}
+
+fn dir_bytes(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| match e.file_type() {
+            Ok(t) if t.is_dir() => dir_bytes(&e.path()),
+            Ok(t) if t.is_file() => e.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn cache_sizes(cache_dir: &Path) -> BTreeMap<String, u64> {
+    let mut sizes = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return sizes;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        // Loose files and id-named dirs are rolled up so no
+        // wallpaper-specific name leaks.
+        let name = entry.file_name().to_string_lossy().to_string();
+        let (key, bytes) = match entry.file_type() {
+            Ok(t) if t.is_dir() && name.chars().all(|c| c.is_ascii_digit()) => {
+                ("other".to_string(), dir_bytes(&entry.path()))
+            }
+            Ok(t) if t.is_dir() => (name, dir_bytes(&entry.path())),
+            Ok(t) if t.is_file() => (
+                "files".to_string(),
+                entry.metadata().map(|m| m.len()).unwrap_or(0),
+            ),
+            _ => continue,
+        };
+        *sizes.entry(key).or_insert(0) += bytes;
+    }
+    sizes
+}
+
+/// Base name of a shader family, for sharing: custom shaders are named by
+/// their path (`workshop/<id>/shaders/flag`), which would leak the library
+/// layout and workshop ids.
+fn family_base_name(family: &str) -> String {
+    let base = family.rsplit(['/', '\\']).next().unwrap_or_default();
+    let base = base.split('.').next().unwrap_or_default();
+    if base.is_empty() || base.chars().all(|c| c.is_ascii_digit()) {
+        "custom".to_string()
+    } else {
+        base.to_string()
+    }
+}
+
+/// Unsupported pass count per shader family, for one scene.
+fn unsupported_families(root: &Path) -> BTreeMap<String, usize> {
+    let Ok(graph) = build_scene_gpu_graph(root) else {
+        return BTreeMap::new();
+    };
+    let plan = build_native_runtime_plan(&graph);
+    let mut families = BTreeMap::new();
+    for pass in plan
+        .passes
+        .iter()
+        .filter(|p| matches!(p.tier, NativeSupportTier::Unsupported))
+    {
+        *families.entry(pass.shader_family.clone()).or_insert(0) += 1;
+    }
+    families
+}
+
+/// Folds a full scan and per-scene unsupported families into [`LibraryStats`].
+pub fn summarize_library(
+    report: &LibraryScanReport,
+    families: &[BTreeMap<String, usize>],
+    cache_bytes: BTreeMap<String, u64>,
+    top_families: usize,
+) -> LibraryStats {
+    let mut counts_by_tier = QUALITY_TIERS
+        .iter()
+        .map(|t| (t.to_string(), 0usize))
+        .collect::<BTreeMap<_, _>>();
+    let mut score_by_type = BTreeMap::<String, (u64, usize)>::new();
+    for wallpaper in &report.wallpapers {
+        *counts_by_tier
+            .entry(wallpaper.quality_tier.clone())
+            .or_insert(0) += 1;
+        let kind = format!("{:?}", wallpaper.wallpaper_type).to_ascii_lowercase();
+        let slot = score_by_type.entry(kind).or_insert((0, 0));
+        slot.0 += u64::from(wallpaper.compatibility_percent);
+        slot.1 += 1;
+    }
+
+    let mut by_family = BTreeMap::<String, (usize, usize)>::new();
+    for scene in families {
+        let mut scene_families = BTreeMap::<String, usize>::new();
+        for (family, passes) in scene {
+            *scene_families.entry(family_base_name(family)).or_insert(0) += passes;
+        }
+        for (family, passes) in scene_families {
+            let slot = by_family.entry(family).or_insert((0, 0));
+            slot.0 += 1;
+            slot.1 += passes;
+        }
+    }
+    let mut top_unsupported_families = by_family
+        .into_iter()
+        .map(|(family, (wallpapers, passes))| UnsupportedFamilyStat {
+            family,
+            wallpapers,
+            passes,
+        })
+        .collect::<Vec<_>>();
+    top_unsupported_families.sort_by(|a, b| {
+        b.wallpapers
+            .cmp(&a.wallpapers)
+            .then_with(|| b.passes.cmp(&a.passes))
+            .then_with(|| a.family.cmp(&b.family))
+    });
+    top_unsupported_families.truncate(top_families);
+
+    LibraryStats {
+        generated_at: Local::now().format("%Y-%m-%d %H:%M:%S %z").to_string(),
+        engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        wallpapers_scanned: report.wallpapers_scanned,
+        average_compatibility_percent: report.average_compatibility_percent,
+        counts_by_type: report.counts_by_type.clone().into_iter().collect(),
+        counts_by_tier,
+        average_compatibility_by_type: score_by_type
+            .into_iter()
+            .map(|(kind, (sum, n))| {
+                let avg = sum as f32 / n.max(1) as f32;
+                (kind, (avg * 100.0).round() / 100.0)
+            })
+            .collect(),
+        audio_spectrum_wallpapers: report.audio_spectrum_wallpapers,
+        top_unsupported_families,
+        cache_bytes,
+    }
+}
+
+pub fn build_library_stats(downloads_root: &Path, top_families: usize) -> Result<LibraryStats> {
+    let report = scan_library(downloads_root, 1, false, false)?;
+    let families = report
+        .wallpapers
+        .iter()
+        .filter(|w| matches!(w.wallpaper_type, WallpaperType::Scene))
+        .map(|w| unsupported_families(Path::new(&w.root)))
+        .collect::<Vec<_>>();
+    Ok(summarize_library(
+        &report,
+        &families,
+        cache_sizes(&app_cache_dir()),
+        top_families,
+    ))
+}
+
+fn label_value(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn gauge(out: &mut String, name: &str, help: &str) {
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+}
+
+/// Prometheus text exposition of `stats`, every metric a gauge.
+pub fn render_prometheus(stats: &LibraryStats) -> String {
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "kitsune_build_info",
+        "Engine version that produced the stats.",
+    );
+    out.push_str(&format!(
+        "kitsune_build_info{{version=\"{}\"}} 1\n",
+        label_value(&stats.engine_version)
+    ));
+    gauge(
+        &mut out,
+        "kitsune_library_wallpapers",
+        "Wallpapers scanned in the library.",
+    );
+    out.push_str(&format!(
+        "kitsune_library_wallpapers {}\n",
+        stats.wallpapers_scanned
+    ));
+    gauge(
+        &mut out,
+        "kitsune_library_wallpapers_by_type",
+        "Wallpapers per detected type.",
+    );
+    for (kind, count) in &stats.counts_by_type {
+        out.push_str(&format!(
+            "kitsune_library_wallpapers_by_type{{type=\"{}\"}} {}\n",
+            label_value(kind),
+            count
+        ));
+    }
+    gauge(
+        &mut out,
+        "kitsune_library_wallpapers_by_tier",
+        "Wallpapers per compatibility tier.",
+    );
+    for (tier, count) in &stats.counts_by_tier {
+        out.push_str(&format!(
+            "kitsune_library_wallpapers_by_tier{{tier=\"{}\"}} {}\n",
+            label_value(tier),
+            count
+        ));
+    }
+    gauge(
+        &mut out,
+        "kitsune_library_compat_score",
+        "Average compatibility score (0-100).",
+    );
+    out.push_str(&format!(
+        "kitsune_library_compat_score {}\n",
+        stats.average_compatibility_percent
+    ));
+    gauge(
+        &mut out,
+        "kitsune_library_compat_score_by_type",
+        "Average compatibility score (0-100) per type.",
+    );
+    for (kind, score) in &stats.average_compatibility_by_type {
+        out.push_str(&format!(
+            "kitsune_library_compat_score_by_type{{type=\"{}\"}} {}\n",
+            label_value(kind),
+            score
+        ));
+    }
+    gauge(
+        &mut out,
+        "kitsune_library_audio_spectrum_wallpapers",
+        "Scenes whose shaders read the audio spectrum arrays.",
+    );
+    out.push_str(&format!(
+        "kitsune_library_audio_spectrum_wallpapers {}\n",
+        stats.audio_spectrum_wallpapers
+    ));
+    gauge(
+        &mut out,
+        "kitsune_unsupported_family_wallpapers",
+        "Scenes with passes of an unsupported shader family.",
+    );
+    for family in &stats.top_unsupported_families {
+        out.push_str(&format!(
+            "kitsune_unsupported_family_wallpapers{{family=\"{}\"}} {}\n",
+            label_value(&family.family),
+            family.wallpapers
+        ));
+    }
+    gauge(
+        &mut out,
+        "kitsune_unsupported_family_passes",
+        "Passes of an unsupported shader family across the library.",
+    );
+    for family in &stats.top_unsupported_families {
+        out.push_str(&format!(
+            "kitsune_unsupported_family_passes{{family=\"{}\"}} {}\n",
+            label_value(&family.family),
+            family.passes
+        ));
+    }
+    gauge(
+        &mut out,
+        "kitsune_cache_bytes",
+        "Disk used by each app cache directory.",
+    );
+    for (cache, bytes) in &stats.cache_bytes {
+        out.push_str(&format!(
+            "kitsune_cache_bytes{{cache=\"{}\"}} {}\n",
+            label_value(cache),
+            bytes
+        ));
+    }
+    out
+}
+
+/// Posts `body` to a dashboard endpoint chosen by the user (`--share`).
+/// Nothing is sent unless the flag is given.
+pub fn share_library_stats(url: &str, body: &str, content_type: &str, dry_run: bool) -> Result<()> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        bail!("--share expects an http(s) URL, got '{}'", url);
+    }
+    if dry_run {
+        eprintln!(
+            "[dry-run] curl -fsS -X POST -H 'Content-Type: {}' --data-binary <{} bytes> '{}'",
+            content_type,
+            body.len(),
+            url
+        );
+        return Ok(());
+    }
+    let output = Command::new("curl")
+        .args(["-fsS", "-X", "POST", "-H"])
+        .arg(format!("Content-Type: {}", content_type))
+        .arg("--data-binary")
+        .arg(body)
+        .arg(url)
+        .run_output()
+        .context("Failed to run curl (is it installed?)")?;
+    if !output.status.success() {
+        bail!(
+            "sharing library stats failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    eprintln!("[ok] library stats shared with {}", url);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library_scan::WallpaperCompatStatus;
+    use std::collections::HashMap;
+
+    fn wallpaper(kind: WallpaperType, score: u8, tier: &str) -> WallpaperCompatStatus {
+        WallpaperCompatStatus {
+            id: "123".to_string(),
+            root: "/library/123".to_string(),
+            title: Some("Private title".to_string()),
+            author: Some("Private author".to_string()),
+            wallpaper_type: kind,
+            compatibility_percent: score,
+            quality_tier: tier.to_string(),
+            capabilities: Vec::new(),
+            issues: Vec::new(),
+            effect_nodes: 0,
+            likely_audio_reactive: false,
+            audio_overlay_plan_available: false,
+            reads_audio_spectrum: false,
+        }
+    }
+
+    #[test]
+    fn stats_aggregate_and_render_as_prometheus_without_ids() {
+        let report = LibraryScanReport {
+            generated_at: String::new(),
+            downloads_root: "/library".to_string(),
+            wallpapers_scanned: 3,
+            average_compatibility_percent: 70.0,
+            counts_by_type: HashMap::from([("scene".to_string(), 2), ("video".to_string(), 1)]),
+            top_effects: Vec::new(),
+            effect_histogram_pruned: false,
+            audio_spectrum_wallpapers: 1,
+            wallpapers: vec![
+                wallpaper(WallpaperType::Scene, 50, "limited"),
+                wallpaper(WallpaperType::Scene, 68, "partial"),
+                wallpaper(WallpaperType::Video, 92, "excellent"),
+            ],
+        };
+        let families = [
+            BTreeMap::from([("flag".to_string(), 2), ("unknown".to_string(), 1)]),
+            BTreeMap::from([("flag".to_string(), 1)]),
+        ];
+        let cache = BTreeMap::from([("scene".to_string(), 4096)]);
+
+        let stats = summarize_library(&report, &families, cache, 1);
+        assert_eq!(stats.counts_by_tier["good"], 0);
+        assert_eq!(stats.counts_by_tier["partial"], 1);
+        assert_eq!(stats.average_compatibility_by_type["scene"], 59.0);
+        assert_eq!(stats.top_unsupported_families.len(), 1);
+        assert_eq!(stats.top_unsupported_families[0].family, "flag");
+        assert_eq!(stats.top_unsupported_families[0].passes, 3);
+
+        let text = render_prometheus(&stats);
+        assert!(text.contains("# TYPE kitsune_library_wallpapers gauge\n"));
+        assert!(text.contains("kitsune_library_wallpapers_by_type{type=\"scene\"} 2\n"));
+        assert!(text.contains("kitsune_unsupported_family_wallpapers{family=\"flag\"} 2\n"));
+        assert!(text.contains("kitsune_cache_bytes{cache=\"scene\"} 4096\n"));
+        assert!(text.contains("kitsune_library_audio_spectrum_wallpapers 1\n"));
+        let json = serde_json::to_string(&stats).unwrap();
+        assert!(!json.contains("123") && !json.contains("Private") && !json.contains("/library"));
+    }
+
+    #[test]
+    fn shared_families_and_cache_keys_hold_no_paths_or_ids() {
+        let report = LibraryScanReport {
+            generated_at: String::new(),
+            downloads_root: "/library".to_string(),
+            wallpapers_scanned: 2,
+            average_compatibility_percent: 50.0,
+            counts_by_type: HashMap::from([("scene".to_string(), 2)]),
+            top_effects: Vec::new(),
+            effect_histogram_pruned: false,
+            audio_spectrum_wallpapers: 0,
+            wallpapers: vec![
+                wallpaper(WallpaperType::Scene, 50, "limited"),
+                wallpaper(WallpaperType::Scene, 50, "limited"),
+            ],
+        };
+        let families = [
+            BTreeMap::from([
+                ("workshop/2233445566/shaders/flag".to_string(), 2),
+                ("/home/me/shaders/flag.frag".to_string(), 1),
+            ]),
+            BTreeMap::from([
+                ("shaders\\glow".to_string(), 1),
+                ("workshop/2233445566".to_string(), 1),
+            ]),
+        ];
+        let cache_dir = tempfile::tempdir().unwrap();
+        for dir in ["scene", "2233445566"] {
+            fs::create_dir_all(cache_dir.path().join(dir)).unwrap();
+            fs::write(cache_dir.path().join(dir).join("a.bin"), b"1234").unwrap();
+        }
+
+        let stats = summarize_library(&report, &families, cache_sizes(cache_dir.path()), 10);
+        let flag = stats
+            .top_unsupported_families
+            .iter()
+            .find(|f| f.family == "flag")
+            .unwrap();
+        assert_eq!((flag.wallpapers, flag.passes), (1, 3));
+        for value in stats
+            .top_unsupported_families
+            .iter()
+            .map(|f| f.family.as_str())
+            .chain(stats.cache_bytes.keys().map(String::as_str))
+        {
+            assert!(!value.contains(['/', '\\']), "{}", value);
+            assert!(!value.chars().all(|c| c.is_ascii_digit()), "{}", value);
+        }
+        assert_eq!(stats.cache_bytes["other"], 4);
+    }
+}
@@ -3,5 +3,8 @@ use kitsune_livewallpaper::cli::Cli;
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    kitsune_livewallpaper::journal::install_panic_hook();
+    let dry_run = std::env::args().any(|arg| arg == "--dry-run");
+    kitsune_livewallpaper::cache_migration::ensure_cache_layout(dry_run);
     kitsune_livewallpaper::run(cli)
 }
@@ -0,0 +1,211 @@
+use crate::app_paths::user_config_path;
+use crate::cli::PlaybackProfile;
+use crate::i18n::{Msg, warn_msg};
+use crate::startup_config::{StartupConfig, load_config};
+use anyhow::{Result, bail};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// `mpv_options` key applied to every playback profile.
+pub const ALL_PROFILES_KEY: &str = "all";
+
+/// Options kitsune sets itself (or mpvpaper owns) and that a passthrough
+/// option would silently break.
+const RESERVED_OPTIONS: [(&str, &str); 5] = [
+    ("vo", "mpvpaper embeds libmpv and rejects vo overrides"),
+    ("wid", "mpvpaper owns the output surface"),
+    (
+        "input-ipc-server",
+        "needed for live property and mute control",
+    ),
+    (
+        "script-opts",
+        "carries the instance tag; use script-opts-append",
+    ),
+    ("loop-file", "wallpapers always loop"),
+];
+
+thread_local! {
+    static CLI_MPV_OPTIONS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// `--mpv-option` values of the running command.
+pub fn cli_mpv_options() -> Vec<String> {
+    CLI_MPV_OPTIONS.with(|o| o.borrow().clone())
+}
+
+/// Runs `f` with `options` appended to every mpvpaper launch on this thread.
+pub fn with_mpv_options<R>(options: Vec<String>, f: impl FnOnce() -> R) -> R {
+    let previous = CLI_MPV_OPTIONS.with(|o| o.replace(options));
+    let result = f();
+    CLI_MPV_OPTIONS.with(|o| o.replace(previous));
+    result
+}
+
+/// Parses `--mpv-option key=value` into the `key=value` form mpvpaper's
+/// option string takes (a leading `--` is dropped).
+pub fn parse_mpv_option(raw: &str) -> std::result::Result<String, String> {
+    let raw = raw.trim();
+    let Some((key, value)) = raw.trim_start_matches("--").split_once('=') else {
+        return Err(format!("expected KEY=VALUE, got '{}'", raw));
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("missing option name in '{}'", raw));
+    }
+    if raw.chars().any(char::is_whitespace) {
+        // mpvpaper splits its -o string on whitespace.
+        return Err(format!("mpv options cannot contain spaces: '{}'", raw));
+    }
+    Ok(format!("{}={}", key, value))
+}
+
+/// Option name and value, with `no-foo` read as `foo=no` and bare flags as
+/// `yes`, so `no-audio` and `audio=no` compare equal.
+fn split_option(opt: &str) -> (String, String) {
+    let opt = opt.trim_start_matches("--");
+    match opt.split_once('=') {
+        Some((key, value)) => (key.to_string(), value.to_string()),
+        None => match opt.strip_prefix("no-") {
+            Some(key) => (key.to_string(), "no".to_string()),
+            None => (opt.to_string(), "yes".to_string()),
+        },
+    }
+}
+
+/// Config option sets for `profile`: the `all` set, then the profile's own.
+/// A custom profile adds the set of its base preset before its own
+/// `mpv_options`.
+pub fn profile_mpv_options(cfg: &StartupConfig, profile: PlaybackProfile) -> Vec<String> {
+    let custom = match profile {
+        PlaybackProfile::Custom(name) => cfg.profiles.get(name),
+        _ => None,
+    };
+    let base = custom.map(|c| c.base.name());
+    [Some(ALL_PROFILES_KEY), base, Some(profile.name())]
+        .into_iter()
+        .flatten()
+        .filter_map(|key| cfg.mpv_options.get(key))
+        .flatten()
+        .chain(custom.into_iter().flat_map(|c| &c.mpv_options))
+        .cloned()
+        .collect()
+}
+
+/// `earlier` then `later`, without the `earlier` options whose name `later`
+/// sets again, so layered defaults never count as conflicting values.
+pub fn merge_mpv_options(earlier: Vec<String>, later: Vec<String>) -> Vec<String> {
+    let overridden = later
+        .iter()
+        .map(|opt| split_option(opt).0)
+        .collect::<Vec<_>>();
+    earlier
+        .into_iter()
+        .filter(|opt| !overridden.contains(&split_option(opt).0))
+        .chain(later)
+        .collect()
+}
+
+/// Rejects passthrough options that clash with kitsune's own or with each
+/// other. `base` is the option string built for the profile; later options
+/// win in mpv, so overriding `base` is fine unless it breaks playback.
+pub fn validate_mpv_options(base: &str, config: &[String], cli: &[String]) -> Result<()> {
+    for opt in config.iter().chain(cli) {
+        let (key, _) = split_option(opt);
+        if let Some((_, why)) = RESERVED_OPTIONS.iter().find(|(name, _)| *name == key) {
+            bail!("mpv option '{}' is managed by kitsune: {}", opt, why);
+        }
+    }
+    let mut from_cli = BTreeMap::<String, String>::new();
+    for opt in cli {
+        let (key, value) = split_option(opt);
+        if let Some(previous) = from_cli.insert(key.clone(), value.clone())
+            && previous != value
+        {
+            bail!(
+                "conflicting --mpv-option values for {}: '{}' and '{}'",
+                key,
+                previous,
+                value
+            );
+        }
+    }
+
+    let mut effective = BTreeMap::<String, String>::new();
+    for opt in base
+        .split_whitespace()
+        .map(str::to_string)
+        .chain(config.iter().cloned())
+    {
+        let (key, value) = split_option(&opt);
+        effective.insert(key, value);
+    }
+    effective.extend(from_cli);
+    let interpolation = effective.get("interpolation").map(String::as_str);
+    let video_sync = effective
+        .get("video-sync")
+        .map(String::as_str)
+        .unwrap_or("audio");
+    if interpolation == Some("yes") && !video_sync.starts_with("display-") {
+        bail!(
+            "mpv interpolation=yes needs video-sync=display-resample (got video-sync={})",
+            video_sync
+        );
+    }
+    Ok(())
+}
+
+/// Passthrough options for an mpvpaper launch: config sets for `profile`,
+/// then `--mpv-option`, validated against the `base` option string.
+pub fn resolve_mpv_options(base: &str, profile: PlaybackProfile) -> Result<Vec<String>> {
+    let config = match load_config(&user_config_path()) {
+        Ok(cfg) => profile_mpv_options(&cfg, profile),
+        Err(err) => {
+            warn_msg!(Msg::MpvOptionsIgnored, err);
+            Vec::new()
+        }
+    };
+    let cli = cli_mpv_options();
+    validate_mpv_options(base, &config, &cli)?;
+    Ok(config.into_iter().chain(cli).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passthrough_options_are_parsed_merged_and_validated() {
+        assert_eq!(parse_mpv_option("--hwdec=vaapi").unwrap(), "hwdec=vaapi");
+        assert!(parse_mpv_option("hwdec").is_err());
+        assert!(parse_mpv_option("glsl-shaders=a b").is_err());
+
+        let cfg: StartupConfig = serde_json::from_str(
+            r#"{"version":1,"entries":[],"mpv_options":{
+                "all":["hwdec=vaapi"],"quality":["scale=spline36"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            profile_mpv_options(&cfg, PlaybackProfile::Quality),
+            ["hwdec=vaapi", "scale=spline36"]
+        );
+        assert_eq!(
+            profile_mpv_options(&cfg, PlaybackProfile::Performance),
+            ["hwdec=vaapi"]
+        );
+
+        let base = "--loop-file=inf video-sync=audio no-audio";
+        let opt = |s: &str| vec![s.to_string()];
+        assert!(validate_mpv_options(base, &opt("hwdec=vaapi"), &opt("scale=bilinear")).is_ok());
+        assert!(validate_mpv_options(base, &[], &opt("vo=gpu-next")).is_err());
+        assert!(
+            validate_mpv_options(base, &[], &["deband=yes".into(), "deband=no".into()]).is_err()
+        );
+        assert!(validate_mpv_options(base, &[], &opt("interpolation=yes")).is_err());
+        let resample = [
+            "interpolation=yes".to_string(),
+            "video-sync=display-resample".to_string(),
+        ];
+        assert!(validate_mpv_options(base, &[], &resample).is_ok());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::cli::OutputFormat;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::Cell;
+
+thread_local! {
+    static FORMAT: Cell<OutputFormat> = const { Cell::new(OutputFormat::Json) };
+}
+
+pub fn output_format() -> OutputFormat {
+    FORMAT.with(Cell::get)
+}
+
+/// Runs `f` with `format` used by every [`print_report`] on this thread.
+pub fn with_output_format<R>(format: OutputFormat, f: impl FnOnce() -> R) -> R {
+    let previous = FORMAT.with(|c| c.replace(format));
+    let result = f();
+    FORMAT.with(|c| c.set(previous));
+    result
+}
+
+/// A command's result: pretty JSON, or a short summary with `--output text`.
+pub fn print_report<T: Serialize + ?Sized>(report: &T) -> Result<()> {
+    match output_format() {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        OutputFormat::Text => {
+            let summary = text_summary(&serde_json::to_value(report)?);
+            if !summary.is_empty() {
+                println!("{}", summary);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some("-".to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s.clone()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+/// Up to four scalar fields of a list item on one line.
+fn item_line(item: &Value) -> String {
+    match item.as_object() {
+        Some(map) => map
+            .iter()
+            .filter_map(|(k, v)| Some(format!("{}={}", k, scalar_text(v)?)))
+            .take(4)
+            .collect::<Vec<_>>()
+            .join("  "),
+        None => scalar_text(item).unwrap_or_else(|| "[...]".to_string()),
+    }
+}
+
+/// One line per top-level field: scalars verbatim, short scalar lists
+/// inline, everything else as a count. Top-level lists print one line per
+/// item.
+pub fn text_summary(value: &Value) -> String {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| match v {
+                Value::Array(items)
+                    if items.len() <= 8 && items.iter().all(|i| scalar_text(i).is_some()) =>
+                {
+                    let joined: Vec<String> = items.iter().filter_map(scalar_text).collect();
+                    format!("{}: {}", k, joined.join(", "))
+                }
+                Value::Array(items) => format!("{}: {} item(s)", k, items.len()),
+                Value::Object(fields) => format!("{}: {} field(s)", k, fields.len()),
+                scalar => format!("{}: {}", k, scalar_text(scalar).unwrap_or_default()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Array(items) => items.iter().map(item_line).collect::<Vec<_>>().join("\n"),
+        scalar => scalar_text(scalar).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn text_summary_condenses_reports() {
+        let report = json!({
+            "wallpapers_scanned": 3,
+            "downloads_root": "/lib",
+            "tiers": ["good", "limited"],
+            "wallpapers": [{"id": 1}, {"id": 2}, {"id": 3}, {"id": 4}, {"id": 5}, {"id": 6}, {"id": 7}, {"id": 8}, {"id": 9}],
+            "counts_by_type": {"scene": 2, "video": 1},
+            "title": null
+        });
+        assert_eq!(
+            text_summary(&report),
+            "counts_by_type: 2 field(s)\ndownloads_root: /lib\ntiers: good, limited\ntitle: -\nwallpapers: 9 item(s)\nwallpapers_scanned: 3"
+        );
+        assert_eq!(
+            text_summary(&json!([{"monitor": "DP-1", "pid": 42, "extra": {"a": 1}}])),
+            "monitor=DP-1  pid=42"
+        );
+        assert_eq!(
+            with_output_format(OutputFormat::Text, output_format),
+            OutputFormat::Text
+        );
+        assert_eq!(output_format(), OutputFormat::Json);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::app_paths::app_state_dir;
+use crate::i18n::{Msg, warn_msg};
+use crate::scene_effect_proxy::{AudioBarsOverlay, build_scene_audio_bars_overlay};
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Bumped when a field changes meaning; new fields keep the version.
+pub const SIDECAR_VERSION: u32 = 1;
+
+/// Rectangle with a top-left origin; `center_*` is the rotation pivot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub center_x: f32,
+    pub center_y: f32,
+}
+
+/// One element a visualizer can draw over the wallpaper.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarLayer {
+    /// `audio-bars` for now.
+    pub kind: String,
+    /// Scene pixels (`scene_width` x `scene_height`).
+    pub pixels: SidecarRect,
+    /// 0..1 of the scene; multiply by the output size.
+    pub normalized: SidecarRect,
+    /// Clockwise degrees around the center.
+    pub rotation_deg: f32,
+    pub opacity: f32,
+    /// `normal`, `add`, `subtract` or `multiply`.
+    pub blend_mode: String,
+}
+
+/// Overlay plan of the wallpaper a monitor currently shows. `layers` is empty
+/// for wallpapers without anything to draw over them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverlaySidecar {
+    pub version: u32,
+    pub monitor: String,
+    pub wallpaper: String,
+    pub updated_at: String,
+    pub scene_width: u32,
+    pub scene_height: u32,
+    pub layers: Vec<SidecarLayer>,
+}
+
+impl SidecarLayer {
+    pub fn audio_bars(bars: &AudioBarsOverlay) -> Self {
+        let scene_w = bars.scene_width.max(1) as f32;
+        let scene_h = bars.scene_height.max(1) as f32;
+        let (w, h) = (bars.width as f32, bars.height as f32);
+        let (cx, cy) = (bars.center_x as f32, bars.center_y as f32);
+        let pixels = SidecarRect {
+            x: cx - w / 2.0,
+            y: cy - h / 2.0,
+            width: w,
+            height: h,
+            center_x: cx,
+            center_y: cy,
+        };
+        let normalized = SidecarRect {
+            x: pixels.x / scene_w,
+            y: pixels.y / scene_h,
+            width: w / scene_w,
+            height: h / scene_h,
+            center_x: cx / scene_w,
+            center_y: cy / scene_h,
+        };
+        // Scene angles are counter-clockwise; the proxy rotates by -angle too.
+        Self {
+            kind: "audio-bars".to_string(),
+            pixels,
+            normalized,
+            rotation_deg: -bars.angle_rad.to_degrees(),
+            opacity: bars.opacity,
+            blend_mode: bars.blend_mode().to_string(),
+        }
+    }
+}
+
+fn sidecar_dir() -> PathBuf {
+    app_state_dir().join("overlay-plan")
+}
+
+pub fn sidecar_path(monitor: &str) -> PathBuf {
+    let safe = monitor
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    sidecar_dir().join(format!("{}.json", safe))
+}
+
+pub fn build_overlay_sidecar(
+    monitor: &str,
+    wallpaper: &str,
+    root: Option<&Path>,
+) -> OverlaySidecar {
+    let bars = root.and_then(|root| {
+        build_scene_audio_bars_overlay(root).unwrap_or_else(|err| {
+            warn_msg!(Msg::OverlayPlanWithoutBars, err);
+            None
+        })
+    });
+    OverlaySidecar {
+        version: SIDECAR_VERSION,
+        monitor: monitor.to_string(),
+        wallpaper: wallpaper.to_string(),
+        updated_at: Local::now().to_rfc3339(),
+        scene_width: bars.as_ref().map(|b| b.scene_width).unwrap_or(0),
+        scene_height: bars.as_ref().map(|b| b.scene_height).unwrap_or(0),
+        layers: bars.iter().map(SidecarLayer::audio_bars).collect(),
+    }
+}
+
+pub fn write_overlay_sidecar(sidecar: &OverlaySidecar) -> Result<PathBuf> {
+    let path = sidecar_path(&sidecar.monitor);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(sidecar)?)
+        .with_context(|| format!("Failed writing {}", path.display()))?;
+    Ok(path)
+}
+
+pub fn load_overlay_sidecar(monitor: &str) -> Result<OverlaySidecar> {
+    let path = sidecar_path(monitor);
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+/// Prints one compact JSON line per sidecar (of `monitor`, or every monitor)
+/// each time it is rewritten, starting with the current ones. Runs until
+/// interrupted.
+pub fn watch_overlay_sidecars(monitor: Option<&str>, interval: Duration) -> Result<()> {
+    let mut seen: BTreeMap<PathBuf, SystemTime> = BTreeMap::new();
+    loop {
+        let paths = match monitor {
+            Some(monitor) => vec![sidecar_path(monitor)],
+            None => fs::read_dir(sidecar_dir())
+                .map(|dir| dir.flatten().map(|e| e.path()).collect())
+                .unwrap_or_default(),
+        };
+        for path in paths {
+            let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if seen.get(&path) == Some(&modified) {
+                continue;
+            }
+            seen.insert(path.clone(), modified);
+            // A half-written file is picked up again on the next change.
+            let Ok(raw) = fs::read(&path) else { continue };
+            if let Ok(sidecar) = serde_json::from_slice::<OverlaySidecar>(&raw) {
+                println!("{}", serde_json::to_string(&sidecar)?);
+            }
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_bars_become_pixel_and_normalized_layer() {
+        let bars = AudioBarsOverlay {
+            object_id: 4,
+            center_x: 960,
+            center_y: 900,
+            width: 800,
+            height: 200,
+            angle_rad: std::f32::consts::FRAC_PI_2,
+            opacity: 0.8,
+            transparency_mode: 2,
+            scene_width: 1920,
+            scene_height: 1080,
+            center_x_norm: 0.5,
+            center_y_norm: 0.8333,
+            width_norm: 0.4167,
+            height_norm: 0.1852,
+        };
+        let layer = SidecarLayer::audio_bars(&bars);
+        assert_eq!(layer.pixels.x, 560.0);
+        assert_eq!(layer.pixels.y, 800.0);
+        assert_eq!(layer.normalized.center_x, 0.5);
+        assert!((layer.normalized.y - 800.0 / 1080.0).abs() < 1e-6);
+        assert!((layer.rotation_deg + 90.0).abs() < 1e-3);
+        assert_eq!(layer.blend_mode, "add");
+
+        let empty = build_overlay_sidecar("HDMI-A-1", "/v/clip.mp4", None);
+        assert!(empty.layers.is_empty());
+        assert!(sidecar_path("HDMI A/1").ends_with("overlay-plan/HDMI_A_1.json"));
+    }
+}
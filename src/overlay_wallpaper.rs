@@ -0,0 +1,306 @@
+use crate::app_paths::{app_state_dir, filter_safe_path};
+use crate::i18n::{Msg, warn_msg};
+use crate::playback::{mpv_ipc_command, mpv_ipc_socket_path};
+use crate::scene_renderer::find_warm_start_entry;
+use crate::types::WallpaperType;
+use crate::wallpaper::{inspect_wallpaper, resolve_wallpaper_path};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Picture-in-picture wallpaper drawn over a monitor's primary wallpaper.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverlayWallpaper {
+    /// mpv filter label (`pip1`, `pip2`, ...), also the id used by the CLI.
+    pub id: String,
+    pub wallpaper: String,
+    /// Media file actually composited.
+    pub source: String,
+    /// `[x, y, w, h]` as fractions of the output.
+    pub rect: [f32; 4],
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl OverlayWallpaper {
+    /// Labeled mpv `vf` entry: the source is scaled against the primary frame
+    /// and overlaid at the rect. Paused overlays hold their first frame.
+    pub fn mpv_filter(&self) -> String {
+        let [x, y, w, h] = self.rect;
+        let playback = if self.paused {
+            "trim=end_frame=1,loop=-1:1:0,setpts=N/25/TB"
+        } else {
+            "setpts=PTS-STARTPTS"
+        };
+        format!(
+            "@{}:lavfi=[movie={}:loop=0,{},format=rgba[pip];[pip][vin]scale2ref=w=main_w*{:.4}:h=main_h*{:.4}[pips][base];[base][pips]overlay=x=main_w*{:.4}:y=main_h*{:.4}:format=auto[vout]]",
+            self.id, self.source, playback, w, h, x, y
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OverlayWallpaperState {
+    pub monitors: BTreeMap<String, Vec<OverlayWallpaper>>,
+}
+
+impl OverlayWallpaperState {
+    pub fn overlays(&self, monitor: &str) -> &[OverlayWallpaper] {
+        self.monitors.get(monitor).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    fn next_id(&self, monitor: &str) -> String {
+        let taken: Vec<&str> = self
+            .overlays(monitor)
+            .iter()
+            .map(|o| o.id.as_str())
+            .collect();
+        (1..)
+            .map(|n| format!("pip{}", n))
+            .find(|id| !taken.contains(&id.as_str()))
+            .unwrap_or_default()
+    }
+
+    pub fn add(
+        &mut self,
+        monitor: &str,
+        wallpaper: &str,
+        source: &str,
+        rect: [f32; 4],
+    ) -> OverlayWallpaper {
+        let overlay = OverlayWallpaper {
+            id: self.next_id(monitor),
+            wallpaper: wallpaper.to_string(),
+            source: source.to_string(),
+            rect,
+            paused: false,
+        };
+        self.monitors
+            .entry(monitor.to_string())
+            .or_default()
+            .push(overlay.clone());
+        overlay
+    }
+
+    /// Removes `id` (or every overlay of the monitor) and returns what was removed.
+    pub fn remove(&mut self, monitor: &str, id: Option<&str>) -> Vec<OverlayWallpaper> {
+        let Some(list) = self.monitors.get_mut(monitor) else {
+            return Vec::new();
+        };
+        let removed = match id {
+            Some(id) => list
+                .iter()
+                .position(|o| o.id == id)
+                .map(|i| vec![list.remove(i)])
+                .unwrap_or_default(),
+            None => std::mem::take(list),
+        };
+        if list.is_empty() {
+            self.monitors.remove(monitor);
+        }
+        removed
+    }
+
+    pub fn get_mut(&mut self, monitor: &str, id: &str) -> Option<&mut OverlayWallpaper> {
+        self.monitors
+            .get_mut(monitor)?
+            .iter_mut()
+            .find(|o| o.id == id)
+    }
+}
+
+/// `x,y,w,h` as percentages (`70%`) or fractions (`0.7`) of the output.
+pub fn parse_pip_rect(raw: &str) -> Result<[f32; 4], String> {
+    let values: Vec<f32> = raw
+        .split(',')
+        .map(|part| {
+            let part = part.trim();
+            match part.strip_suffix('%') {
+                Some(pct) => pct.trim().parse::<f32>().map(|v| v / 100.0),
+                None => part.parse::<f32>(),
+            }
+            .map_err(|_| format!("invalid rect value '{}'", part))
+        })
+        .collect::<Result<_, _>>()?;
+    let [x, y, w, h] = values[..] else {
+        return Err("expected x,y,w,h (e.g. 70%,70%,30%,30%)".to_string());
+    };
+    if [x, y, w, h].iter().any(|v| !(0.0..=1.0).contains(v)) || w <= 0.0 || h <= 0.0 {
+        return Err("rect values must be within 0..100% and w/h > 0".to_string());
+    }
+    if x + w > 1.0 + f32::EPSILON || y + h > 1.0 + f32::EPSILON {
+        return Err("rect must fit inside the output".to_string());
+    }
+    Ok([x, y, w, h])
+}
+
+/// Playable media for `wallpaper`: a media file as-is, a video wallpaper's
+/// entry, or the cached proxy of a scene that was played before.
+pub fn resolve_overlay_source(wallpaper: &str, downloads_root: &Path) -> Result<PathBuf> {
+    let path = resolve_wallpaper_path(wallpaper, downloads_root);
+    let source = if path.is_file() {
+        path
+    } else {
+        let info = inspect_wallpaper(wallpaper, downloads_root)?;
+        match info.wallpaper_type {
+            WallpaperType::Video => PathBuf::from(
+                info.entry
+                    .context("Video wallpaper has no playable entry")?,
+            ),
+            WallpaperType::Scene => {
+                find_warm_start_entry(Path::new(&info.root)).with_context(|| {
+                    format!(
+                        "No cached proxy for scene {}; play it once with scene-play first",
+                        wallpaper
+                    )
+                })?
+            }
+            other => bail!("{:?} wallpapers cannot be used as overlays", other),
+        }
+    };
+    Ok(filter_safe_path(&source))
+}
+
+fn state_path() -> PathBuf {
+    app_state_dir().join("overlay-wallpapers.json")
+}
+
+pub fn load_overlay_state() -> Result<OverlayWallpaperState> {
+    let path = state_path();
+    if !path.is_file() {
+        return Ok(OverlayWallpaperState::default());
+    }
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+pub fn save_overlay_state(state: &OverlayWallpaperState) -> Result<()> {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(state)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// ` vf-append=...` options so a fresh mpvpaper launch starts with the
+/// monitor's overlays.
+pub fn overlay_launch_options(monitor: &str) -> String {
+    let state = load_overlay_state().unwrap_or_else(|err| {
+        warn_msg!(Msg::OverlayWallpapersIgnored, err);
+        OverlayWallpaperState::default()
+    });
+    state
+        .overlays(monitor)
+        .iter()
+        .map(|o| format!(" vf-append={}", o.mpv_filter()))
+        .collect()
+}
+
+fn live_vf(monitor: &str, args: &[&str], dry_run: bool) -> Result<()> {
+    if dry_run {
+        eprintln!(
+            "[dry-run] mpv IPC {}: vf {}",
+            mpv_ipc_socket_path(monitor).display(),
+            args.join(" ")
+        );
+        return Ok(());
+    }
+    if !mpv_ipc_socket_path(monitor).exists() {
+        eprintln!(
+            "[ok] no running wallpaper on {}; applies on next launch",
+            monitor
+        );
+        return Ok(());
+    }
+    let mut command = vec!["vf"];
+    command.extend_from_slice(args);
+    mpv_ipc_command(monitor, serde_json::json!(command))
+}
+
+/// Adds `overlay` to the running mpvpaper of `monitor`.
+pub fn show_overlay_live(monitor: &str, overlay: &OverlayWallpaper, dry_run: bool) -> Result<()> {
+    live_vf(monitor, &["add", &overlay.mpv_filter()], dry_run)
+}
+
+pub fn hide_overlay_live(monitor: &str, id: &str, dry_run: bool) -> Result<()> {
+    live_vf(monitor, &["remove", &format!("@{}", id)], dry_run)
+}
+
+/// Freezes or resumes one overlay; the primary wallpaper keeps playing.
+pub fn set_overlay_paused(
+    monitor: &str,
+    id: &str,
+    paused: bool,
+    dry_run: bool,
+) -> Result<OverlayWallpaper> {
+    let mut state = load_overlay_state()?;
+    let overlay = state
+        .get_mut(monitor, id)
+        .with_context(|| format!("No overlay {} on {}", id, monitor))?;
+    if overlay.paused == paused {
+        return Ok(overlay.clone());
+    }
+    overlay.paused = paused;
+    let overlay = overlay.clone();
+    // Same label: remove + add swaps only this layer of the vf chain.
+    hide_overlay_live(monitor, id, dry_run)?;
+    show_overlay_live(monitor, &overlay, dry_run)?;
+    if !dry_run {
+        save_overlay_state(&state)?;
+    }
+    Ok(overlay)
+}
+
+/// Re-adds the stored overlays after the `vf` chain was replaced (hot swap).
+pub fn reapply_overlays_live(monitor: &str) -> Result<()> {
+    for overlay in load_overlay_state()?.overlays(monitor) {
+        show_overlay_live(monitor, overlay, false)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rects_parse_and_overlays_keep_stable_ids() {
+        assert_eq!(
+            parse_pip_rect("70%,70%,30%,30%").unwrap(),
+            [0.7, 0.7, 0.3, 0.3]
+        );
+        assert_eq!(
+            parse_pip_rect("0,0.5,0.25,0.5").unwrap(),
+            [0.0, 0.5, 0.25, 0.5]
+        );
+        assert!(parse_pip_rect("80%,80%,30%,30%").is_err());
+        assert!(parse_pip_rect("10%,10%").is_err());
+
+        let mut state = OverlayWallpaperState::default();
+        let first = state.add("DP-1", "123", "/tmp/a.mp4", [0.7, 0.7, 0.3, 0.3]);
+        state.add("DP-1", "456", "/tmp/b.mp4", [0.0, 0.0, 0.2, 0.2]);
+        assert_eq!(state.remove("DP-1", Some("pip1")), vec![first]);
+        // Freed ids are reused; live ones are not.
+        assert_eq!(
+            state
+                .add("DP-1", "789", "/tmp/c.mp4", [0.1, 0.1, 0.2, 0.2])
+                .id,
+            "pip1"
+        );
+
+        let overlay = state.get_mut("DP-1", "pip2").unwrap();
+        assert!(
+            overlay
+                .mpv_filter()
+                .starts_with("@pip2:lavfi=[movie=/tmp/b.mp4:loop=0,setpts=")
+        );
+        overlay.paused = true;
+        assert!(overlay.mpv_filter().contains("trim=end_frame=1"));
+        assert_eq!(state.remove("DP-1", None).len(), 2);
+        assert!(state.monitors.is_empty());
+    }
+}
@@ -0,0 +1,500 @@
+use crate::cli::PlaybackProfile;
+use crate::history::{WallpaperHistory, load_history};
+use crate::i18n::{Msg, warn_msg};
+use crate::startup_config::StartupCommand;
+use crate::workshop::is_workshop_id;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const DEFAULT_SYNC_PORT: u16 = 47415;
+const SYNC_REQUEST: &str = "KWE-SYNC 1 STATE";
+/// Shared secret every node of a group sets; peers that do not send it get
+/// no answer.
+pub const SYNC_SECRET_ENV: &str = "KWE_SYNC_SECRET";
+const PEER_TIMEOUT: Duration = Duration::from_secs(2);
+/// Longest request or reply line read from a peer.
+const MAX_LINE: usize = 64 * 1024;
+
+/// Workshop wallpaper a node shows on one monitor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncSelection {
+    pub workshop_id: String,
+    pub applied_at: String,
+}
+
+/// What a node answers to a state request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncNode {
+    pub node_id: String,
+    pub priority: i32,
+    pub selections: BTreeMap<String, SyncSelection>,
+}
+
+pub struct PeerSyncArgs {
+    pub node_id: Option<String>,
+    pub priority: i32,
+    pub listen: SocketAddr,
+    /// Allows a non-loopback `listen` address.
+    pub public: bool,
+    pub secret: Option<String>,
+    pub peers: Vec<String>,
+    pub monitors: Vec<String>,
+    pub downloads_root: PathBuf,
+    pub interval_seconds: u64,
+    pub once: bool,
+}
+
+fn local_hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .map(|s| s.trim().to_string())
+        .find(|s| !s.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Current workshop wallpaper per monitor; paths and videos are not shared
+/// since they rarely exist at the same place on another machine.
+pub fn local_selections(history: &WallpaperHistory) -> BTreeMap<String, SyncSelection> {
+    history
+        .monitors
+        .iter()
+        .filter_map(|(monitor, entries)| {
+            let last = entries.last()?;
+            let StartupCommand::Apply { wallpaper, .. } = &last.command else {
+                return None;
+            };
+            is_workshop_id(wallpaper).then(|| {
+                (
+                    monitor.clone(),
+                    SyncSelection {
+                        workshop_id: wallpaper.clone(),
+                        applied_at: last.applied_at.clone(),
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+/// Highest priority wins, ties go to the smallest node id, so every node
+/// seeing the same peers elects the same leader.
+pub fn elect_leader<'a>(local: &'a SyncNode, peers: &'a [SyncNode]) -> &'a SyncNode {
+    peers.iter().fold(local, |best, node| {
+        if (node.priority, std::cmp::Reverse(&node.node_id))
+            > (best.priority, std::cmp::Reverse(&best.node_id))
+        {
+            node
+        } else {
+            best
+        }
+    })
+}
+
+/// Leader selection a local monitor follows: the one of the same name, else
+/// the leader's most recently applied wallpaper.
+fn leader_selection_for<'a>(leader: &'a SyncNode, monitor: &str) -> Option<&'a SyncSelection> {
+    leader.selections.get(monitor).or_else(|| {
+        leader
+            .selections
+            .values()
+            .max_by(|a, b| a.applied_at.cmp(&b.applied_at))
+    })
+}
+
+/// Monitors to switch and the workshop id to apply. A leader selection is
+/// followed once (`seen`), so a wallpaper picked by hand on a follower stays
+/// until the leader changes again.
+pub fn plan_follow(
+    leader: &SyncNode,
+    local: &SyncNode,
+    monitors: &[String],
+    downloads_root: &Path,
+    seen: &mut BTreeMap<String, SyncSelection>,
+) -> Vec<(String, String)> {
+    let mut switches = Vec::new();
+    for monitor in monitors {
+        let Some(selection) = leader_selection_for(leader, monitor) else {
+            continue;
+        };
+        if seen.get(monitor) == Some(selection) {
+            continue;
+        }
+        seen.insert(monitor.clone(), selection.clone());
+        let current = local
+            .selections
+            .get(monitor)
+            .map(|s| s.workshop_id.as_str());
+        if current == Some(selection.workshop_id.as_str()) {
+            continue;
+        }
+        // The id comes from the network and is joined onto the library path.
+        if !is_workshop_id(&selection.workshop_id) {
+            warn_msg!(
+                Msg::PeerLeaderInvalidId,
+                monitor,
+                leader.node_id,
+                format!("{:?}", selection.workshop_id)
+            );
+            continue;
+        }
+        if !downloads_root.join(&selection.workshop_id).is_dir() {
+            warn_msg!(
+                Msg::PeerLeaderNotDownloaded,
+                monitor,
+                leader.node_id,
+                selection.workshop_id
+            );
+            continue;
+        }
+        switches.push((monitor.clone(), selection.workshop_id.clone()));
+    }
+    switches
+}
+
+/// Apply command for following `workshop_id`, keeping the options of the
+/// monitor's last apply when there is one.
+pub fn follow_command(
+    history: &WallpaperHistory,
+    monitor: &str,
+    workshop_id: &str,
+    downloads_root: &Path,
+) -> StartupCommand {
+    let previous = history
+        .entries(monitor)
+        .iter()
+        .rev()
+        .find(|e| matches!(e.command, StartupCommand::Apply { .. }));
+    match previous.map(|e| e.command.clone()) {
+        Some(StartupCommand::Apply {
+            keep_services,
+            mute_audio,
+            profile,
+            display_fps,
+            allow_scene_preview_fallback,
+            ..
+        }) => StartupCommand::Apply {
+            wallpaper: workshop_id.to_string(),
+            downloads_root: downloads_root.to_path_buf(),
+            keep_services,
+            mute_audio,
+            profile,
+            display_fps,
+            allow_scene_preview_fallback,
+        },
+        _ => StartupCommand::Apply {
+            wallpaper: workshop_id.to_string(),
+            downloads_root: downloads_root.to_path_buf(),
+            keep_services: false,
+            mute_audio: false,
+            profile: PlaybackProfile::Balanced,
+            display_fps: None,
+            allow_scene_preview_fallback: false,
+        },
+    }
+}
+
+fn local_node(node_id: &str, priority: i32) -> Result<SyncNode> {
+    Ok(SyncNode {
+        node_id: node_id.to_string(),
+        priority,
+        selections: local_selections(&load_history()?),
+    })
+}
+
+/// Secret from `KWE_SYNC_SECRET`, if set.
+pub fn sync_secret_from_env() -> Option<String> {
+    std::env::var(SYNC_SECRET_ENV)
+        .ok()
+        .filter(|secret| !secret.is_empty())
+}
+
+fn sync_request(secret: Option<&str>) -> String {
+    match secret {
+        Some(secret) => format!("{} {}", SYNC_REQUEST, secret),
+        None => SYNC_REQUEST.to_string(),
+    }
+}
+
+/// One line from a peer, refusing lines past [`MAX_LINE`] so a hostile peer
+/// cannot grow the buffer without end.
+fn read_peer_line(stream: &TcpStream) -> Result<String> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_LINE as u64 + 1)).read_line(&mut line)?;
+    if line.len() > MAX_LINE {
+        bail!("peer line longer than {} bytes", MAX_LINE);
+    }
+    Ok(line)
+}
+
+/// Compares every byte so the time taken does not tell how much matched.
+fn same_request(got: &str, expected: &str) -> bool {
+    got.len() == expected.len()
+        && got
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn answer_peer(
+    stream: TcpStream,
+    node_id: &str,
+    priority: i32,
+    secret: Option<&str>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(PEER_TIMEOUT))?;
+    let line = read_peer_line(&stream)?;
+    if !same_request(line.trim(), &sync_request(secret)) {
+        // Never echo the line: it may hold a mistyped secret.
+        bail!("request without the expected sync secret");
+    }
+    let mut reply = serde_json::to_vec(&local_node(node_id, priority)?)?;
+    reply.push(b'\n');
+    (&stream).write_all(&reply)?;
+    Ok(())
+}
+
+/// Answers state requests from peers until the process exits.
+fn serve_state(listener: TcpListener, node_id: String, priority: i32, secret: Option<String>) {
+    for stream in listener.incoming().flatten() {
+        let peer = stream
+            .peer_addr()
+            .map(|a| a.to_string())
+            .unwrap_or_default();
+        if let Err(err) = answer_peer(stream, &node_id, priority, secret.as_deref()) {
+            warn_msg!(Msg::PeerSyncFailed, peer, err);
+        }
+    }
+}
+
+/// Asks `peer` (`host[:port]`) for its state.
+pub fn query_peer(peer: &str, secret: Option<&str>) -> Result<SyncNode> {
+    let peer = if peer.contains(':') {
+        peer.to_string()
+    } else {
+        format!("{}:{}", peer, DEFAULT_SYNC_PORT)
+    };
+    let addr = peer
+        .as_str()
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid peer address {}", peer))?
+        .next()
+        .with_context(|| format!("Peer {} resolved to no address", peer))?;
+    let stream = TcpStream::connect_timeout(&addr, PEER_TIMEOUT)?;
+    stream.set_read_timeout(Some(PEER_TIMEOUT))?;
+    (&stream).write_all(format!("{}\n", sync_request(secret)).as_bytes())?;
+    let line = read_peer_line(&stream)?;
+    serde_json::from_str(&line).with_context(|| format!("Invalid sync reply from {}", peer))
+}
+
+/// Only loopback addresses are served by default: anything else needs
+/// `--public` and a shared secret.
+fn check_listen_address(listen: SocketAddr, public: bool, secret: Option<&str>) -> Result<()> {
+    if listen.ip().is_loopback() {
+        return Ok(());
+    }
+    if !public {
+        bail!(
+            "--listen {} is reachable from the network; pass --public to allow it",
+            listen
+        );
+    }
+    if secret.is_none() {
+        bail!(
+            "--public needs a shared secret in {} on every node",
+            SYNC_SECRET_ENV
+        );
+    }
+    Ok(())
+}
+
+/// Publishes this machine's selection and follows the elected leader.
+/// `apply` switches one local monitor (the caller records it in history).
+pub fn run_peer_sync(
+    args: PeerSyncArgs,
+    mut apply: impl FnMut(&str, StartupCommand) -> Result<()>,
+) -> Result<()> {
+    let node_id = args.node_id.clone().unwrap_or_else(local_hostname);
+    if !args.once {
+        check_listen_address(args.listen, args.public, args.secret.as_deref())?;
+        let listener = TcpListener::bind(args.listen)
+            .with_context(|| format!("Failed listening on {}", args.listen))?;
+        eprintln!("[ok] sync node {} listening on {}", node_id, args.listen);
+        let (id, priority, secret) = (node_id.clone(), args.priority, args.secret.clone());
+        std::thread::spawn(move || serve_state(listener, id, priority, secret));
+    }
+
+    let mut seen = BTreeMap::<String, SyncSelection>::new();
+    let mut last_leader = String::new();
+    loop {
+        let history = load_history()?;
+        let local = SyncNode {
+            node_id: node_id.clone(),
+            priority: args.priority,
+            selections: local_selections(&history),
+        };
+        let peers: Vec<SyncNode> = args
+            .peers
+            .iter()
+            .filter_map(|peer| match query_peer(peer, args.secret.as_deref()) {
+                Ok(node) => Some(node),
+                Err(err) => {
+                    warn_msg!(Msg::PeerUnreachable, peer, err);
+                    None
+                }
+            })
+            .collect();
+        let leader = elect_leader(&local, &peers);
+        if leader.node_id != last_leader {
+            eprintln!("[ok] sync leader: {}", leader.node_id);
+            last_leader = leader.node_id.clone();
+        }
+        if leader.node_id != local.node_id {
+            let monitors = if args.monitors.is_empty() {
+                leader.selections.keys().cloned().collect()
+            } else {
+                args.monitors.clone()
+            };
+            for (monitor, id) in
+                plan_follow(leader, &local, &monitors, &args.downloads_root, &mut seen)
+            {
+                eprintln!("[ok] {}: following {} -> {}", monitor, leader.node_id, id);
+                let command = follow_command(&history, &monitor, &id, &args.downloads_root);
+                if let Err(err) = apply(&monitor, command) {
+                    warn_msg!(Msg::PeerFollowFailed, monitor, id, err);
+                }
+            }
+        } else {
+            // Whatever the leader shows later is new to us again.
+            seen.clear();
+        }
+        if args.once {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(args.interval_seconds.max(1)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, priority: i32, selections: &[(&str, &str, &str)]) -> SyncNode {
+        SyncNode {
+            node_id: id.to_string(),
+            priority,
+            selections: selections
+                .iter()
+                .map(|(monitor, workshop_id, at)| {
+                    (
+                        monitor.to_string(),
+                        SyncSelection {
+                            workshop_id: workshop_id.to_string(),
+                            applied_at: at.to_string(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn followers_apply_new_leader_selections_once() {
+        let local = node("desk-b", 0, &[("DP-1", "111", "1")]);
+        let peers = vec![
+            node("desk-c", 5, &[]),
+            node(
+                "desk-a",
+                5,
+                &[("DP-1", "222", "2"), ("HDMI-A-1", "333", "3")],
+            ),
+        ];
+        let leader = elect_leader(&local, &peers);
+        assert_eq!(leader.node_id, "desk-a");
+
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("222")).unwrap();
+        std::fs::create_dir(root.path().join("333")).unwrap();
+        let monitors = vec!["DP-1".to_string(), "eDP-1".to_string()];
+        let mut seen = BTreeMap::new();
+        assert_eq!(
+            plan_follow(leader, &local, &monitors, root.path(), &mut seen),
+            vec![
+                ("DP-1".to_string(), "222".to_string()),
+                // No DP-1 counterpart: follows the leader's latest pick.
+                ("eDP-1".to_string(), "333".to_string()),
+            ]
+        );
+        // Already followed; a manual change on this desk is left alone.
+        assert!(plan_follow(leader, &local, &monitors, root.path(), &mut seen).is_empty());
+
+        // Ids from the network are joined onto the library: only digits.
+        let hostile = node("desk-a", 5, &[("DP-1", "../../etc", "4")]);
+        let monitors = vec!["DP-1".to_string()];
+        assert!(plan_follow(&hostile, &local, &monitors, root.path(), &mut seen).is_empty());
+
+        let dirs = tempfile::tempdir().unwrap();
+        let dirs_root = dirs.path().to_path_buf();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            crate::app_paths::with_app_dirs_root(&dirs_root, || {
+                let (stream, _) = listener.accept().unwrap();
+                answer_peer(stream, "desk-a", 5, None).unwrap();
+            })
+        });
+        let reply = query_peer(&addr.to_string(), None).unwrap();
+        server.join().unwrap();
+        assert_eq!((reply.node_id.as_str(), reply.priority), ("desk-a", 5));
+        assert!(reply.selections.is_empty());
+    }
+
+    #[test]
+    fn peer_lines_past_the_limit_are_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            (&stream).write_all(&vec![b'x'; MAX_LINE + 10]).ok();
+        });
+        let stream = TcpStream::connect(addr).unwrap();
+        let err = read_peer_line(&stream).unwrap_err();
+        assert!(err.to_string().contains("longer than"), "{err}");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn public_listening_needs_the_flag_and_peers_need_the_secret() {
+        let any: SocketAddr = "0.0.0.0:47415".parse().unwrap();
+        let local: SocketAddr = "127.0.0.1:47415".parse().unwrap();
+        assert!(check_listen_address(local, false, None).is_ok());
+        assert!(check_listen_address(any, false, Some("s3cret")).is_err());
+        assert!(check_listen_address(any, true, None).is_err());
+        assert!(check_listen_address(any, true, Some("s3cret")).is_ok());
+
+        let dirs = tempfile::tempdir().unwrap();
+        let dirs_root = dirs.path().to_path_buf();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            let answers = crate::app_paths::with_app_dirs_root(&dirs_root, || {
+                listener
+                    .incoming()
+                    .take(3)
+                    .map(|stream| answer_peer(stream.unwrap(), "desk-a", 5, Some("s3cret")).is_ok())
+                    .collect::<Vec<_>>()
+            });
+            assert_eq!(answers, [false, false, true]);
+        });
+        assert!(query_peer(&addr, None).is_err());
+        assert!(query_peer(&addr, Some("wrong")).is_err());
+        assert_eq!(query_peer(&addr, Some("s3cret")).unwrap().node_id, "desk-a");
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Serialize;
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Bytes of a pkg/tex file searched for a displaced or XOR'd header.
+pub const OBFUSCATION_PROBE_BYTES: u64 = 4096;
+
+const TEX_MAGIC: &[u8] = b"TEXV0005\0TEXI0001\0";
+
+thread_local! {
+    static ALLOW_OBFUSCATED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether obfuscated pkg/tex files may be read best-effort.
+pub fn allow_obfuscated() -> bool {
+    ALLOW_OBFUSCATED.with(Cell::get)
+}
+
+/// Runs `f` with `--allow-obfuscated` set for every read on this thread.
+pub fn with_allow_obfuscated<R>(enabled: bool, f: impl FnOnce() -> R) -> R {
+    let previous = ALLOW_OBFUSCATED.with(|c| c.replace(enabled));
+    let result = f();
+    ALLOW_OBFUSCATED.with(|c| c.set(previous));
+    result
+}
+
+/// Known ways workshop items hide their pkg/tex headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "variant", rename_all = "kebab-case")]
+pub enum ObfuscationVariant {
+    /// Junk bytes before an intact header.
+    OffsetHeader { offset: u64 },
+    /// pkg header and entry table XOR'd with one byte; entry data is plain.
+    XorTable { key: u8 },
+    /// Every byte of a tex file XOR'd with one byte.
+    XorFile { key: u8 },
+}
+
+impl fmt::Display for ObfuscationVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OffsetHeader { offset } => write!(f, "header moved by {} bytes", offset),
+            Self::XorTable { key } => write!(f, "table XOR'd with 0x{:02x}", key),
+            Self::XorFile { key } => write!(f, "file XOR'd with 0x{:02x}", key),
+        }
+    }
+}
+
+/// What the first bytes of a file say about its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderCheck {
+    Plain,
+    Obfuscated(ObfuscationVariant),
+    Unknown,
+}
+
+/// A `PKGV*` sized string starts at `at`: a small length, then `PKGV`.
+fn pkg_header_at(bytes: &[u8], at: usize, key: u8) -> bool {
+    let Some(head) = bytes.get(at..at + 8) else {
+        return false;
+    };
+    let len = u32::from_le_bytes([head[0] ^ key, head[1] ^ key, head[2] ^ key, head[3] ^ key]);
+    (4..=16).contains(&len) && head[4..].iter().map(|b| b ^ key).eq(*b"PKGV")
+}
+
+pub fn check_pkg_header(prefix: &[u8]) -> HeaderCheck {
+    if pkg_header_at(prefix, 0, 0) {
+        return HeaderCheck::Plain;
+    }
+    // The high bytes of the header length are zero, so they carry the key.
+    if let Some(&key) = prefix.get(3)
+        && key != 0
+        && pkg_header_at(prefix, 0, key)
+    {
+        return HeaderCheck::Obfuscated(ObfuscationVariant::XorTable { key });
+    }
+    match (1..prefix.len()).find(|at| pkg_header_at(prefix, *at, 0)) {
+        Some(offset) => HeaderCheck::Obfuscated(ObfuscationVariant::OffsetHeader {
+            offset: offset as u64,
+        }),
+        None => HeaderCheck::Unknown,
+    }
+}
+
+pub fn check_tex_header(prefix: &[u8]) -> HeaderCheck {
+    if prefix.starts_with(TEX_MAGIC) {
+        return HeaderCheck::Plain;
+    }
+    if let Some(&first) = prefix.first() {
+        let key = first ^ TEX_MAGIC[0];
+        if key != 0
+            && prefix
+                .iter()
+                .map(|b| b ^ key)
+                .take(TEX_MAGIC.len())
+                .eq(TEX_MAGIC.iter().copied())
+        {
+            return HeaderCheck::Obfuscated(ObfuscationVariant::XorFile { key });
+        }
+    }
+    match prefix.windows(TEX_MAGIC.len()).position(|w| w == TEX_MAGIC) {
+        Some(offset) => HeaderCheck::Obfuscated(ObfuscationVariant::OffsetHeader {
+            offset: offset as u64,
+        }),
+        None => HeaderCheck::Unknown,
+    }
+}
+
+/// `bytes` of a file with `variant` undone.
+pub fn deobfuscate(bytes: &[u8], variant: ObfuscationVariant) -> Vec<u8> {
+    match variant {
+        ObfuscationVariant::OffsetHeader { offset } => {
+            bytes.get(offset as usize..).unwrap_or_default().to_vec()
+        }
+        ObfuscationVariant::XorTable { key } | ObfuscationVariant::XorFile { key } => {
+            bytes.iter().map(|b| b ^ key).collect()
+        }
+    }
+}
+
+/// Reader that undoes a one-byte XOR.
+pub struct XorReader<R> {
+    pub inner: R,
+    pub key: u8,
+}
+
+impl<R: Read> Read for XorReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for b in &mut buf[..n] {
+            *b ^= self.key;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_variants_are_told_apart_from_plain_and_unknown_headers() {
+        let pkg = b"\x08\x00\x00\x00PKGV0001\x01\x00\x00\x00".to_vec();
+        assert_eq!(check_pkg_header(&pkg), HeaderCheck::Plain);
+
+        let xored = pkg.iter().map(|b| b ^ 0x5a).collect::<Vec<_>>();
+        let variant = ObfuscationVariant::XorTable { key: 0x5a };
+        assert_eq!(check_pkg_header(&xored), HeaderCheck::Obfuscated(variant));
+        let mut plain = Vec::new();
+        XorReader {
+            inner: xored.as_slice(),
+            key: 0x5a,
+        }
+        .read_to_end(&mut plain)
+        .unwrap();
+        assert_eq!(plain, pkg);
+
+        let shifted = [b"junk!".as_slice(), &pkg].concat();
+        assert_eq!(
+            check_pkg_header(&shifted),
+            HeaderCheck::Obfuscated(ObfuscationVariant::OffsetHeader { offset: 5 })
+        );
+        assert_eq!(check_pkg_header(b"\x00\x01garbage"), HeaderCheck::Unknown);
+
+        let tex = [TEX_MAGIC, b"rest"].concat();
+        assert_eq!(check_tex_header(&tex), HeaderCheck::Plain);
+        let xored = tex.iter().map(|b| b ^ 0x21).collect::<Vec<_>>();
+        let variant = ObfuscationVariant::XorFile { key: 0x21 };
+        assert_eq!(check_tex_header(&xored), HeaderCheck::Obfuscated(variant));
+        assert_eq!(deobfuscate(&xored, variant), tex);
+        assert_eq!(variant.to_string(), "file XOR'd with 0x21");
+    }
+}
@@ -1,6 +1,27 @@
+use crate::app_paths::{app_cache_dir, app_runtime_dir, utf8_safe_path};
+use crate::aspect_fit::aspect_fit_options;
 use crate::cli::PlaybackProfile;
-use anyhow::{Context, Result};
+use crate::colorspace::mpv_color_options;
+use crate::command_runner::{CommandExt, shell_command_line, shell_quote};
+use crate::custom_profiles::resolve_profile;
+use crate::display_orientation::{hyprctl_monitors, size_from_hyprctl_json};
+use crate::i18n::{Msg, warn_msg};
+use crate::instance::{
+    DEFAULT_INSTANCE, current_instance, force_teardown, mpv_instance_option, owns_session,
+};
+use crate::layer_shell::monitor_layer_args;
+use crate::mpv_options::resolve_mpv_options;
+use crate::overlay_wallpaper::{overlay_launch_options, reapply_overlays_live};
+use crate::property_overrides::{monitor_property_overrides, mpv_override_options};
+use crate::scene_fps::monitor_refresh_fps;
+use crate::static_backend::{delegate_static_wallpaper, release_static_wallpaper};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
 use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::thread;
 use std::time::Duration;
@@ -8,21 +29,29 @@ use std::time::Duration;
 pub fn build_mpv_options(
     profile: PlaybackProfile,
     mute_audio: bool,
-    _display_fps: Option<u32>,
+    fps_cap: Option<u32>,
 ) -> String {
-    build_mpv_options_with_extra(profile, mute_audio, _display_fps, None)
+    build_mpv_options_with_extra(profile, mute_audio, fps_cap, None)
 }
 
+/// mpv options for `profile`. `fps_cap` (`--display-fps` or the scene's
+/// inferred rate) and a custom profile's `fps_cap` become one `fps` video
+/// filter at the lower of the two.
 pub fn build_mpv_options_with_extra(
     profile: PlaybackProfile,
     mute_audio: bool,
-    _display_fps: Option<u32>,
+    fps_cap: Option<u32>,
     extra_opt: Option<&str>,
 ) -> String {
+    let (preset, custom) = resolve_profile(profile);
+    let hwdec = custom
+        .as_ref()
+        .and_then(|c| c.hwdec.as_deref())
+        .unwrap_or("auto-safe");
     // Keep options compatible with mpvpaper (mpvpaper embeds libmpv and rejects vo overrides).
     let mut parts: Vec<String> = vec![
         "--loop-file=inf".to_string(),
-        "hwdec=auto-safe".to_string(),
+        format!("hwdec={}", hwdec),
         "keep-open=yes".to_string(),
     ];
 
@@ -32,14 +61,14 @@ pub fn build_mpv_options_with_extra(
         }
     }
 
-    match profile {
+    match preset {
         PlaybackProfile::Performance => {
             parts.push("profile=fast".to_string());
             parts.push("interpolation=no".to_string());
             parts.push("video-sync=audio".to_string());
             parts.push("deband=no".to_string());
         }
-        PlaybackProfile::Balanced => {
+        PlaybackProfile::Balanced | PlaybackProfile::Custom(_) => {
             parts.push("interpolation=no".to_string());
             parts.push("video-sync=display-resample".to_string());
             parts.push("scale=ewa_lanczossharp".to_string());
@@ -61,6 +90,11 @@ pub fn build_mpv_options_with_extra(
         }
     }
 
+    parts.extend(mpv_color_options(preset).iter().map(|opt| opt.to_string()));
+    if let Some(cap) = effective_fps_cap(custom.and_then(|c| c.fps_cap), fps_cap) {
+        parts.push(format!("vf-append=fps={}", cap));
+    }
+
     if mute_audio {
         parts.push("no-audio".to_string());
     }
@@ -68,11 +102,20 @@ pub fn build_mpv_options_with_extra(
     parts.join(" ")
 }
 
+/// The lower of the profile's and the caller's fps caps; 0 means no cap.
+fn effective_fps_cap(profile_cap: Option<u32>, fps_cap: Option<u32>) -> Option<u32> {
+    [fps_cap, profile_cap]
+        .into_iter()
+        .flatten()
+        .filter(|fps| *fps > 0)
+        .min()
+}
+
 pub fn stop_existing_mpvpaper_for_monitor(monitor: &str, dry_run: bool) -> Result<()> {
     let out = Command::new("pgrep")
         .arg("-fa")
         .arg("mpvpaper")
-        .output()
+        .run_output()
         .context("Failed to run pgrep for mpvpaper")?;
 
     if !out.status.success() {
@@ -93,29 +136,35 @@ pub fn stop_existing_mpvpaper_for_monitor(monitor: &str, dry_run: bool) -> Resul
         // Only kill mpvpaper sessions started by kitsune-livewallpaper.
         // This avoids killing Kitsune Spectrum (which may run on its own mpvpaper/layer stack).
         let is_kwe_session = cmd.contains("kitsune-livewallpaper")
-            || cmd.contains(".cache/kitsune-livewallpaper")
+            || cmd.contains(app_cache_dir().to_string_lossy().as_ref())
             || cmd.contains("render-session")
             || cmd.contains("udp://127.0.0.1:");
         if !is_kwe_session {
             continue;
         }
+        // A second instance (e.g. a test run) must not tear down the main one.
+        let instance = current_instance();
+        if !owns_session(cmd, &instance, force_teardown()) {
+            warn_msg!(Msg::MpvpaperOtherInstance, pid, monitor);
+            continue;
+        }
 
         if dry_run {
-            println!("[dry-run] kill {}  # {}", pid, cmd);
+            eprintln!("[dry-run] kill {}  # {}", pid, cmd);
             continue;
         }
 
-        let kill_out = Command::new("kill").arg(pid.to_string()).output();
+        let kill_out = Command::new("kill").arg(pid.to_string()).run_output();
         match kill_out {
             Ok(res) if res.status.success() => {
-                println!("[ok] killed old mpvpaper pid={} monitor={}", pid, monitor);
+                eprintln!("[ok] killed old mpvpaper pid={} monitor={}", pid, monitor);
             }
             Ok(res) => {
                 let err = String::from_utf8_lossy(&res.stderr);
-                eprintln!("[warn] failed to kill {}: {}", pid, err.trim());
+                warn_msg!(Msg::KillFailed, pid, err.trim());
             }
             Err(err) => {
-                eprintln!("[warn] failed to kill {}: {}", pid, err);
+                warn_msg!(Msg::KillFailed, pid, err);
             }
         }
     }
@@ -123,17 +172,19 @@ pub fn stop_existing_mpvpaper_for_monitor(monitor: &str, dry_run: bool) -> Resul
     Ok(())
 }
 
-fn find_running_mpvpaper_for_monitor(monitor: &str, entry: &str) -> Result<Option<u32>> {
+fn find_running_mpvpaper_for_monitor(monitor: &str, entry: &Path) -> Result<Option<u32>> {
     let out = Command::new("pgrep")
         .arg("-fa")
         .arg("mpvpaper")
-        .output()
+        .run_output()
         .context("Failed to query mpvpaper process list")?;
 
     if !out.status.success() {
         return Ok(None);
     }
 
+    // Both sides decoded the same lossy way, so non-UTF-8 entries still match.
+    let entry = entry.to_string_lossy();
     let stdout = String::from_utf8_lossy(&out.stdout);
     for line in stdout.lines() {
         let mut parts = line.splitn(2, ' ');
@@ -142,7 +193,7 @@ fn find_running_mpvpaper_for_monitor(monitor: &str, entry: &str) -> Result<Optio
             None => continue,
         };
         let cmd = parts.next().unwrap_or_default();
-        if cmd.contains(monitor) && cmd.contains(entry) {
+        if cmd.contains(monitor) && cmd.contains(entry.as_ref()) {
             return Ok(Some(pid));
         }
     }
@@ -150,59 +201,355 @@ fn find_running_mpvpaper_for_monitor(monitor: &str, entry: &str) -> Result<Optio
     Ok(None)
 }
 
-pub fn launch_mpvpaper(
+/// IPC socket passed to every mpvpaper we launch, one per monitor (and per
+/// instance besides the default one).
+pub fn mpv_ipc_socket_path(monitor: &str) -> PathBuf {
+    let safe = monitor
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let instance = current_instance();
+    let name = if instance == DEFAULT_INSTANCE {
+        format!("kwe-mpv-{}.sock", safe)
+    } else {
+        format!("kwe-mpv-{}@{}.sock", safe, instance)
+    };
+    app_runtime_dir().join(name)
+}
+
+pub(crate) fn mpv_ipc_command(monitor: &str, command: serde_json::Value) -> Result<()> {
+    mpv_ipc_command_at(&mpv_ipc_socket_path(monitor), command)
+}
+
+pub(crate) fn mpv_ipc_command_at(socket: &Path, command: serde_json::Value) -> Result<()> {
+    mpv_ipc_request_at(socket, command).map(|_| ())
+}
+
+/// Reads one property of a running kwe mpvpaper (`path`, `vf`, `time-pos`...).
+pub fn get_mpv_property(monitor: &str, name: &str) -> Result<serde_json::Value> {
+    mpv_ipc_request_at(
+        &mpv_ipc_socket_path(monitor),
+        serde_json::json!(["get_property", name]),
+    )
+}
+
+/// Sends `command` and returns the reply's `data` (null for plain commands).
+fn mpv_ipc_request_at(socket: &Path, command: serde_json::Value) -> Result<serde_json::Value> {
+    let mut stream = UnixStream::connect(socket)
+        .with_context(|| format!("Failed to connect to mpv IPC {}", socket.display()))?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .context("Failed to set mpv IPC timeout")?;
+    let payload = serde_json::json!({ "command": command });
+    writeln!(stream, "{}", payload).context("Failed to write mpv IPC command")?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader
+            .read_line(&mut line)
+            .context("Failed to read mpv IPC reply")?
+            == 0
+        {
+            bail!("mpv IPC closed before replying");
+        }
+        let Ok(reply) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        // Skip async events; the command reply is the one carrying "error".
+        let Some(status) = reply.get("error").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if status != "success" {
+            bail!("mpv IPC command {} failed: {}", payload, status);
+        }
+        return Ok(reply
+            .get("data")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null));
+    }
+}
+
+/// The `vf` chain a fresh launch of `entry` would get, in launch order:
+/// the caller's `vf=` filters, the fps cap, then the aspect-fit crop.
+/// Overlays are left out; they are re-added live after the swap.
+fn hot_swap_vf_chain(
     monitor: &str,
-    entry: &str,
+    entry: &Path,
     profile: PlaybackProfile,
-    mute_audio: bool,
-    _display_fps: Option<u32>,
+    fps_cap: Option<u32>,
+    extra_opt: Option<&str>,
+) -> String {
+    let (_, custom) = resolve_profile(profile);
+    let mut filters = extra_opt
+        .and_then(|opt| opt.strip_prefix("vf="))
+        .filter(|vf| !vf.is_empty())
+        .map(str::to_string)
+        .into_iter()
+        .collect::<Vec<_>>();
+    if let Some(cap) = effective_fps_cap(custom.and_then(|c| c.fps_cap), fps_cap) {
+        filters.push(format!("fps={}", cap));
+    }
+    let output_size = hyprctl_monitors()
+        .as_deref()
+        .and_then(|raw| size_from_hyprctl_json(raw, monitor));
+    filters.extend(
+        aspect_fit_options(monitor, entry, output_size)
+            .into_iter()
+            .filter_map(|opt| opt.strip_prefix("vf-append=").map(str::to_string)),
+    );
+    filters.join(",")
+}
+
+/// Replaces the file played by a running kwe mpvpaper without restarting it.
+/// The filter chain is rebuilt as a fresh launch would build it, so the fps
+/// cap and aspect-fit crop survive the swap.
+pub fn hot_swap_mpvpaper_entry(
+    monitor: &str,
+    entry: &Path,
+    profile: PlaybackProfile,
+    fps_cap: Option<u32>,
+    extra_opt: Option<&str>,
     dry_run: bool,
 ) -> Result<()> {
-    launch_mpvpaper_with_extra(
+    let vf = hot_swap_vf_chain(monitor, entry, profile, fps_cap, extra_opt);
+    let vf = vf.as_str();
+    // IPC commands are JSON text; a non-UTF-8 entry goes through an alias.
+    let ipc_entry = utf8_safe_path(entry);
+    let ipc_entry = ipc_entry.to_string_lossy();
+    if dry_run {
+        eprintln!(
+            "[dry-run] mpv IPC {}: set_property vf {}; loadfile {} replace",
+            mpv_ipc_socket_path(monitor).display(),
+            shell_quote(OsStr::new(vf)),
+            shell_quote(OsStr::new(ipc_entry.as_ref()))
+        );
+        return Ok(());
+    }
+    mpv_ipc_command(monitor, serde_json::json!(["set_property", "vf", vf]))?;
+    mpv_ipc_command(
         monitor,
-        entry,
-        profile,
-        mute_audio,
-        _display_fps,
-        None,
-        dry_run,
+        serde_json::json!(["loadfile", ipc_entry, "replace"]),
+    )?;
+    // `set_property vf` dropped the picture-in-picture layers.
+    if let Err(err) = reapply_overlays_live(monitor) {
+        warn_msg!(Msg::OverlayRestoreFailed, err);
+    }
+    eprintln!(
+        "[ok] hot-swapped mpvpaper monitor={} entry={}",
+        monitor,
+        entry.display()
+    );
+    Ok(())
+}
+
+/// Changes one property of a running kwe mpvpaper (speed, volume...).
+pub fn set_mpv_property(
+    monitor: &str,
+    name: &str,
+    value: serde_json::Value,
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run {
+        eprintln!(
+            "[dry-run] mpv IPC {}: set_property {} {}",
+            mpv_ipc_socket_path(monitor).display(),
+            name,
+            value
+        );
+        return Ok(());
+    }
+    mpv_ipc_command(
+        monitor,
+        serde_json::json!(["set_property", name, value.clone()]),
+    )?;
+    eprintln!("[ok] live {}={} on {}", name, value, monitor);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GlobalAudioState {
+    pub global_mute: bool,
+}
+
+fn global_audio_state_path() -> PathBuf {
+    crate::app_paths::app_state_dir().join("audio.json")
+}
+
+pub fn load_global_mute() -> bool {
+    std::fs::read(global_audio_state_path())
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<GlobalAudioState>(&raw).ok())
+        .map(|s| s.global_mute)
+        .unwrap_or(false)
+}
+
+fn save_global_mute(global_mute: bool) -> Result<()> {
+    let path = global_audio_state_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    std::fs::write(
+        &path,
+        serde_json::to_vec_pretty(&GlobalAudioState { global_mute })?,
     )
+    .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// IPC sockets of every mpvpaper we launched that may still be running.
+pub fn running_mpv_ipc_sockets() -> Vec<PathBuf> {
+    let mut out = std::fs::read_dir(app_runtime_dir())
+        .map(|dir| {
+            dir.flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .map(|n| n.to_string_lossy())
+                        .is_some_and(|n| n.starts_with("kwe-mpv-") && n.ends_with(".sock"))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    out.sort();
+    out
+}
+
+/// Persists the office-mode preference and applies it to every running
+/// wallpaper through mpv IPC, without restarting playback.
+pub fn set_global_mute(global_mute: bool, dry_run: bool) -> Result<()> {
+    let sockets = running_mpv_ipc_sockets();
+    if dry_run {
+        eprintln!(
+            "[dry-run] persist global_mute={} in {}",
+            global_mute,
+            global_audio_state_path().display()
+        );
+        for socket in &sockets {
+            eprintln!(
+                "[dry-run] mpv IPC {}: set_property mute {}",
+                socket.display(),
+                global_mute
+            );
+        }
+        return Ok(());
+    }
+
+    save_global_mute(global_mute)?;
+    for socket in &sockets {
+        match mpv_ipc_command_at(
+            socket,
+            serde_json::json!(["set_property", "mute", global_mute]),
+        ) {
+            Ok(()) => eprintln!(
+                "[ok] {} {}",
+                if global_mute { "muted" } else { "unmuted" },
+                socket.display()
+            ),
+            // Stale sockets are left behind by mpvpaper instances killed elsewhere.
+            Err(err) => warn_msg!(Msg::SocketSkipped, socket.display(), err),
+        }
+    }
+    eprintln!(
+        "[ok] global mute {} ({} running instance(s))",
+        if global_mute { "on" } else { "off" },
+        sockets.len()
+    );
+    Ok(())
+}
+
+pub fn launch_mpvpaper(
+    monitor: &str,
+    entry: &Path,
+    profile: PlaybackProfile,
+    mute_audio: bool,
+    fps_cap: Option<u32>,
+    dry_run: bool,
+) -> Result<()> {
+    launch_mpvpaper_with_extra(monitor, entry, profile, mute_audio, fps_cap, None, dry_run)
 }
 
 pub fn launch_mpvpaper_with_extra(
     monitor: &str,
-    entry: &str,
+    entry: &Path,
     profile: PlaybackProfile,
     mute_audio: bool,
-    _display_fps: Option<u32>,
+    fps_cap: Option<u32>,
     extra_opt: Option<&str>,
     dry_run: bool,
 ) -> Result<()> {
-    let mut opts = build_mpv_options_with_extra(profile, mute_audio, _display_fps, extra_opt);
+    let live_opts = format!(
+        "{}{}",
+        mpv_override_options(&monitor_property_overrides(monitor)),
+        overlay_launch_options(monitor)
+    );
+    // A plain still image needs no mpv filters; an image daemon shows it
+    // for a fraction of mpvpaper's memory.
+    if extra_opt.is_none_or(|e| e.trim().is_empty())
+        && live_opts.is_empty()
+        && delegate_static_wallpaper(monitor, entry, dry_run)?
+    {
+        return Ok(());
+    }
+    release_static_wallpaper(monitor, dry_run)?;
+
+    let mut opts = build_mpv_options_with_extra(profile, mute_audio, fps_cap, extra_opt);
+    // One compositor query serves both the refresh rate and the aspect check.
+    let hyprctl = hyprctl_monitors();
+    // Each output runs its own mpv, so mixed 60/144 Hz setups each get their
+    // rate. A plain libmpv option (not a vo override), so mpvpaper accepts it.
+    if let Some(refresh) = monitor_refresh_fps(monitor, hyprctl.as_deref()) {
+        opts.push_str(&format!(" display-fps-override={}", refresh));
+    }
     let mpv_log_enabled = std::env::var("KWE_MPV_LOG").ok().as_deref() == Some("1");
     if mpv_log_enabled && !opts.contains("msg-level=") {
         opts.push_str(" msg-level=all=v");
     }
-
-    if _display_fps.is_some() {
-        eprintln!("[warn] --display-fps is currently ignored for mpvpaper compatibility");
+    opts.push_str(&format!(
+        " input-ipc-server={} {}",
+        mpv_ipc_socket_path(monitor).display(),
+        mpv_instance_option(&current_instance())
+    ));
+    // `mute` instead of `no-audio` so `mute off` can bring the sound back live.
+    if !mute_audio && load_global_mute() {
+        opts.push_str(" mute=yes");
     }
-
-    if dry_run {
-        println!(
-            "[dry-run] nohup mpvpaper -o '{}' {} {}",
-            opts, monitor, entry
-        );
-        return Ok(());
+    let output_size = hyprctl
+        .as_deref()
+        .and_then(|raw| size_from_hyprctl_json(raw, monitor));
+    for opt in aspect_fit_options(monitor, entry, output_size) {
+        opts.push(' ');
+        opts.push_str(&opt);
+    }
+    // Before the live overrides, so `property set` values still win.
+    for opt in resolve_mpv_options(&opts, profile)? {
+        opts.push(' ');
+        opts.push_str(&opt);
     }
+    opts.push_str(&live_opts);
 
     let mut cmd = Command::new("nohup");
     cmd.arg("mpvpaper")
+        .args(monitor_layer_args(monitor))
         .arg("-o")
         .arg(&opts)
         .arg(monitor)
-        .arg(entry)
-        .stdin(Stdio::null());
+        .arg(entry);
+    if dry_run {
+        eprintln!("[dry-run] {}", shell_command_line(&cmd));
+        return Ok(());
+    }
+    cmd.stdin(Stdio::null());
+    let runtime_dir = app_runtime_dir();
+    std::fs::create_dir_all(&runtime_dir)
+        .with_context(|| format!("Failed creating {}", runtime_dir.display()))?;
 
     if mpv_log_enabled {
         let log_path = "/tmp/kwe-mpvpaper.log";
@@ -220,16 +567,19 @@ pub fn launch_mpvpaper_with_extra(
         cmd.stdout(Stdio::null()).stderr(Stdio::null());
     }
 
-    cmd.spawn()
+    cmd.spawn_detached()
         .context("Failed to spawn detached mpvpaper via nohup")?;
 
     thread::sleep(Duration::from_millis(500));
     let pid = find_running_mpvpaper_for_monitor(monitor, entry)?
         .context("mpvpaper process not found after launch")?;
 
-    println!(
-        "[ok] launched mpvpaper pid={} monitor={} profile={:?} entry={}",
-        pid, monitor, profile, entry
+    eprintln!(
+        "[ok] launched mpvpaper pid={} monitor={} profile={} entry={}",
+        pid,
+        monitor,
+        profile,
+        entry.display()
     );
     Ok(())
 }
@@ -237,6 +587,134 @@ pub fn launch_mpvpaper_with_extra(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command_runner::{MockRunner, with_runner};
+    use std::rc::Rc;
+
+    #[test]
+    fn ipc_socket_path_is_sanitized() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::app_paths::with_app_dirs_root(dir.path(), || {
+            assert_eq!(
+                mpv_ipc_socket_path("DP-1"),
+                dir.path().join("runtime/kwe-mpv-DP-1.sock")
+            );
+            assert_eq!(
+                mpv_ipc_socket_path("HDMI A/1"),
+                dir.path().join("runtime/kwe-mpv-HDMI_A_1.sock")
+            );
+        });
+    }
+
+    #[test]
+    fn global_mute_round_trips_and_reaches_new_launches() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = Rc::new(MockRunner::new());
+        let entry = "/videos/a.mp4";
+        mock.respond(
+            "pgrep",
+            true,
+            &format!("4242 mpvpaper -o opts KWE-TEST-1 {}", entry),
+        );
+        crate::app_paths::with_app_dirs_root(dir.path(), || {
+            assert!(!load_global_mute());
+            save_global_mute(true).unwrap();
+            assert!(load_global_mute());
+            assert!(dir.path().join("state/audio.json").is_file());
+
+            let socket = mpv_ipc_socket_path("KWE-TEST-1");
+            assert!(!socket.parent().unwrap().exists());
+            with_runner(mock.clone(), || {
+                for mute_audio in [false, true] {
+                    let profile = PlaybackProfile::Balanced;
+                    let entry = Path::new(entry);
+                    launch_mpvpaper("KWE-TEST-1", entry, profile, mute_audio, None, false).unwrap();
+                }
+            });
+            // mpv only creates the socket itself, not its directory.
+            std::fs::write(&socket, b"").unwrap();
+            assert_eq!(running_mpv_ipc_sockets(), vec![socket]);
+
+            save_global_mute(false).unwrap();
+            assert!(!load_global_mute());
+        });
+        let launches = mock
+            .commands()
+            .into_iter()
+            .filter(|c| c.starts_with("nohup mpvpaper "))
+            .collect::<Vec<_>>();
+        assert_eq!(launches.len(), 2, "{launches:#?}");
+        assert!(launches[0].contains(" mute=yes"), "{}", launches[0]);
+        // An explicit --mute-audio launch has no audio to unmute.
+        assert!(!launches[1].contains(" mute=yes"), "{}", launches[1]);
+        assert!(launches[1].contains(" no-audio"));
+    }
+
+    #[test]
+    fn hot_swap_keeps_the_fps_cap_and_aspect_crop() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = dir.path().join("wide.png");
+        image::RgbImage::from_fn(400, 100, |x, _| image::Rgb([(x % 256) as u8, 0, 0]))
+            .save(&entry)
+            .unwrap();
+        let mock = Rc::new(MockRunner::new());
+        mock.respond(
+            "hyprctl",
+            true,
+            r#"[{"name":"KWE-TEST-1","width":1920,"height":1080,"transform":0}]"#,
+        );
+        let fit = crate::aspect_fit::AspectFit {
+            scaling: None,
+            smart_crop: true,
+        };
+        with_runner(mock, || {
+            let vf = crate::aspect_fit::with_aspect_fit(fit, || {
+                hot_swap_vf_chain(
+                    "KWE-TEST-1",
+                    &entry,
+                    PlaybackProfile::Balanced,
+                    Some(10),
+                    Some("vf=drawtext=text=hi"),
+                )
+            });
+            assert!(vf.starts_with("drawtext=text=hi,fps=10,crop="), "{vf}");
+            let profile = PlaybackProfile::Balanced;
+            assert_eq!(
+                hot_swap_vf_chain("KWE-TEST-1", &entry, profile, None, None),
+                ""
+            );
+        });
+    }
+
+    #[test]
+    fn non_utf8_entries_reach_mpvpaper_byte_for_byte() {
+        use std::os::unix::ffi::OsStrExt;
+        let dir = tempfile::tempdir().unwrap();
+        let entry = Path::new("/videos").join(OsStr::from_bytes(b"clip-\xff.mp4"));
+        let mock = Rc::new(MockRunner::new());
+        mock.respond(
+            "pgrep",
+            true,
+            &format!(
+                "4242 mpvpaper -o opts KWE-TEST-1 {}",
+                entry.to_string_lossy()
+            ),
+        );
+        crate::app_paths::with_app_dirs_root(dir.path(), || {
+            with_runner(mock.clone(), || {
+                let profile = PlaybackProfile::Balanced;
+                launch_mpvpaper("KWE-TEST-1", &entry, profile, true, None, false).unwrap();
+            })
+        });
+        let launch = mock
+            .quoted_commands()
+            .into_iter()
+            .find(|c| c.starts_with("nohup mpvpaper "))
+            .unwrap();
+        assert!(
+            launch.ends_with(r" KWE-TEST-1 $'/videos/clip-\xff.mp4'"),
+            "{launch}"
+        );
+    }
 
     #[test]
     fn builds_quality_options() {
@@ -246,6 +724,16 @@ mod tests {
         assert!(!opts.contains("vo=gpu-next"));
     }
 
+    #[test]
+    fn fps_caps_become_an_fps_filter() {
+        let opts = build_mpv_options(PlaybackProfile::Balanced, false, Some(10));
+        assert!(opts.ends_with(" vf-append=fps=10"), "{opts}");
+        assert!(!opts.contains("display-fps-override"));
+        let uncapped = build_mpv_options(PlaybackProfile::Balanced, false, None);
+        assert!(!uncapped.contains("fps="));
+        assert!(!build_mpv_options(PlaybackProfile::Balanced, false, Some(0)).contains("fps="));
+    }
+
     #[test]
     fn builds_performance_options() {
         let opts = build_mpv_options(PlaybackProfile::Performance, true, None);
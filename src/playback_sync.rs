@@ -0,0 +1,234 @@
+use crate::i18n::{Msg, warn_msg};
+use crate::playback::{get_mpv_property, mpv_ipc_command, mpv_ipc_socket_path};
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Drift past which a follower seeks instead of catching up by speed.
+const SEEK_THRESHOLD_SECONDS: f64 = 1.0;
+/// Largest speed change per correction; ±3% is not visible in motion.
+const MAX_SPEED_ADJUST: f64 = 0.03;
+/// Seconds over which a speed correction should absorb the drift.
+const CATCH_UP_SECONDS: f64 = 2.0;
+const DEFAULT_FPS: f64 = 30.0;
+
+/// What one follower needs to stay on the leader's frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "action", content = "value", rename_all = "kebab-case")]
+pub enum SyncAction {
+    /// Within one frame: play at the leader's speed.
+    Hold,
+    Speed(f64),
+    Seek(f64),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncSample {
+    pub leader: String,
+    pub follower: String,
+    pub drift_ms: f64,
+    pub frame_ms: f64,
+    pub action: SyncAction,
+}
+
+/// Position of one instance, taken halfway through its IPC round trip.
+struct Probe {
+    time_pos: f64,
+    taken_at: Instant,
+}
+
+fn probe(monitor: &str) -> Result<Probe> {
+    let started = Instant::now();
+    let value = get_mpv_property(monitor, "time-pos")?;
+    let taken_at = started + started.elapsed() / 2;
+    let Some(time_pos) = value.as_f64() else {
+        bail!("{} has no playback position", monitor);
+    };
+    Ok(Probe { time_pos, taken_at })
+}
+
+fn f64_property(monitor: &str, name: &str) -> Option<f64> {
+    get_mpv_property(monitor, name).ok()?.as_f64()
+}
+
+/// Follower minus leader, in seconds, wrapped into half a loop either side so
+/// one instance that already looped is not seen as a whole loop behind.
+pub fn loop_drift(leader: f64, follower: f64, duration: Option<f64>) -> f64 {
+    let drift = follower - leader;
+    match duration.filter(|d| *d > 0.0) {
+        Some(d) => (drift + d / 2.0).rem_euclid(d) - d / 2.0,
+        None => drift,
+    }
+}
+
+/// Correction for `drift` (follower ahead when positive) at a frame
+/// duration of `frame` seconds, relative to the leader's `speed`.
+pub fn sync_action(drift: f64, frame: f64, leader_pos: f64, speed: f64) -> SyncAction {
+    if drift.abs() <= frame {
+        SyncAction::Hold
+    } else if drift.abs() > SEEK_THRESHOLD_SECONDS {
+        SyncAction::Seek(leader_pos)
+    } else {
+        let adjust = (drift / CATCH_UP_SECONDS).clamp(-MAX_SPEED_ADJUST, MAX_SPEED_ADJUST);
+        SyncAction::Speed(speed * (1.0 - adjust))
+    }
+}
+
+/// Splits `monitors` into groups playing the same file; the first monitor of
+/// each group leads. Monitors without a running mpv are left out.
+pub fn sync_groups(paths: &[(String, Option<String>)]) -> Vec<Vec<String>> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for (monitor, path) in paths {
+        let Some(path) = path else {
+            continue;
+        };
+        match groups.iter_mut().find(|(p, _)| p == path) {
+            Some((_, members)) => members.push(monitor.clone()),
+            None => groups.push((path.clone(), vec![monitor.clone()])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, members)| members)
+        .filter(|members| members.len() > 1)
+        .collect()
+}
+
+fn apply_action(follower: &str, action: SyncAction, dry_run: bool) -> Result<()> {
+    let command = match action {
+        SyncAction::Hold => serde_json::json!(["set_property", "speed", 1.0]),
+        SyncAction::Speed(speed) => serde_json::json!(["set_property", "speed", speed]),
+        SyncAction::Seek(pos) => serde_json::json!(["seek", pos, "absolute+exact"]),
+    };
+    if dry_run {
+        eprintln!(
+            "[dry-run] mpv IPC {}: {}",
+            mpv_ipc_socket_path(follower).display(),
+            command
+        );
+        return Ok(());
+    }
+    mpv_ipc_command(follower, command)
+}
+
+fn sync_group(
+    group: &[String],
+    last: &mut Vec<Option<SyncAction>>,
+    dry_run: bool,
+) -> Vec<SyncSample> {
+    let leader = &group[0];
+    let frame = 1.0 / f64_property(leader, "container-fps").unwrap_or(DEFAULT_FPS);
+    let duration = f64_property(leader, "duration");
+    // `property set speed` on the leader carries over to the group.
+    let speed = f64_property(leader, "speed").unwrap_or(1.0);
+    let mut samples = Vec::new();
+    last.resize(group.len() - 1, None);
+    for (follower, last_action) in group[1..].iter().zip(last.iter_mut()) {
+        let (lead, follow) = match (probe(leader), probe(follower)) {
+            (Ok(lead), Ok(follow)) => (lead, follow),
+            (Err(err), _) | (_, Err(err)) => {
+                warn_msg!(Msg::PlaybackSyncSkipped, follower, err);
+                continue;
+            }
+        };
+        // Bring the leader's position forward to the follower's sample time.
+        let elapsed = follow.taken_at.duration_since(lead.taken_at).as_secs_f64();
+        let leader_pos = lead.time_pos + elapsed * speed;
+        let drift = loop_drift(leader_pos, follow.time_pos, duration);
+        let action = sync_action(drift, frame, leader_pos, speed);
+        let applied = match action {
+            SyncAction::Hold => SyncAction::Speed(speed),
+            other => other,
+        };
+        // Re-sending an unchanged speed only adds IPC traffic.
+        if Some(applied) != *last_action || matches!(applied, SyncAction::Seek(_)) {
+            if let Err(err) = apply_action(follower, applied, dry_run) {
+                warn_msg!(Msg::PlaybackSyncSkipped, follower, err);
+                continue;
+            }
+            *last_action = Some(applied);
+        }
+        samples.push(SyncSample {
+            leader: leader.clone(),
+            follower: follower.clone(),
+            drift_ms: drift * 1000.0,
+            frame_ms: frame * 1000.0,
+            action,
+        });
+    }
+    samples
+}
+
+/// Keeps the mpv instances of `monitors` that play the same file within one
+/// frame of each other. With `once`, runs a single correction and returns
+/// its samples.
+pub fn run_playback_sync(
+    monitors: &[String],
+    interval: Duration,
+    once: bool,
+    dry_run: bool,
+) -> Result<Vec<SyncSample>> {
+    let paths = monitors
+        .iter()
+        .map(|monitor| {
+            let path = get_mpv_property(monitor, "path")
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string));
+            (monitor.clone(), path)
+        })
+        .collect::<Vec<_>>();
+    let groups = sync_groups(&paths);
+    if groups.is_empty() {
+        bail!("No two of {} play the same file", monitors.join(", "));
+    }
+    for group in &groups {
+        eprintln!("[ok] syncing {} to {}", group[1..].join(", "), group[0]);
+    }
+    let mut last = vec![Vec::new(); groups.len()];
+    loop {
+        let samples = groups
+            .iter()
+            .zip(last.iter_mut())
+            .flat_map(|(group, last)| sync_group(group, last, dry_run))
+            .collect::<Vec<_>>();
+        if once {
+            return Ok(samples);
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_wraps_at_the_loop_and_picks_a_correction() {
+        assert!((loop_drift(9.9, 0.1, Some(10.0)) - 0.2).abs() < 1e-9);
+        assert!((loop_drift(0.1, 9.9, Some(10.0)) + 0.2).abs() < 1e-9);
+        assert!((loop_drift(2.0, 2.5, None) - 0.5).abs() < 1e-9);
+
+        let frame = 1.0 / 60.0;
+        let speed_of = |action| match action {
+            SyncAction::Speed(speed) => (speed * 1000.0_f64).round() / 1000.0,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(sync_action(0.010, frame, 3.0, 1.0), SyncAction::Hold);
+        // Ahead: slow down, capped.
+        assert_eq!(speed_of(sync_action(0.5, frame, 3.0, 1.0)), 0.97);
+        assert_eq!(speed_of(sync_action(-0.04, frame, 3.0, 1.0)), 1.02);
+        assert_eq!(speed_of(sync_action(-0.04, frame, 3.0, 0.5)), 0.51);
+        assert_eq!(sync_action(1.5, frame, 3.0, 1.0), SyncAction::Seek(3.0));
+
+        let groups = sync_groups(&[
+            ("DP-1".to_string(), Some("/a.mp4".to_string())),
+            ("DP-2".to_string(), Some("/b.mp4".to_string())),
+            ("HDMI-A-1".to_string(), Some("/a.mp4".to_string())),
+            ("DP-3".to_string(), None),
+        ]);
+        assert_eq!(
+            groups,
+            vec![vec!["DP-1".to_string(), "HDMI-A-1".to_string()]]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use crate::app_paths::{app_cache_dir, app_data_dir, user_config_path};
+use crate::command_runner::CommandExt;
+use crate::i18n::{Msg, bail_msg, warn_msg};
+use crate::property_overrides::property_overrides_path;
+use crate::scene_pkg::entry_path_under;
+use crate::startup_config::{StartupCommand, StartupConfig, load_config, save_config};
+use crate::wallpaper_quirks::default_user_quirks_path;
+use crate::workshop::{is_workshop_id, sync_state_path};
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const PROFILE_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "kwe-profile.json";
+const CONFIG_ENTRY: &str = "config/config.json";
+
+/// Non-workshop file referenced by the config, stored under `media/`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileMedia {
+    pub original: String,
+    pub entry: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileManifest {
+    pub version: u32,
+    pub created_at: String,
+    pub generator: String,
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub media: Vec<ProfileMedia>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileExportReport {
+    pub archive: String,
+    pub files: Vec<String>,
+    pub media: Vec<ProfileMedia>,
+    /// Referenced media that was not packed (missing, or `--include-media` off).
+    pub skipped_media: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileImportReport {
+    pub archive: String,
+    pub restored: Vec<String>,
+    /// Previous files kept next to the restored ones as `<name>.bak`.
+    pub backups: Vec<String>,
+    pub media: Vec<String>,
+    pub rewritten_entries: usize,
+}
+
+/// Settings carried by a profile: archive entry and location on this machine.
+/// Per-wallpaper overlay prefs and startup entries live in `config.json`;
+/// synced workshop collections stand in for playlists.
+fn profile_files() -> Vec<(&'static str, PathBuf)> {
+    vec![
+        (CONFIG_ENTRY, user_config_path()),
+        ("config/property-overrides.json", property_overrides_path()),
+        ("config/quirks.json", default_user_quirks_path()),
+        ("state/workshop-collections.json", sync_state_path()),
+    ]
+}
+
+/// Local files the startup entries play (workshop ids are fetched again on the
+/// other machine, so they are not media).
+pub fn profile_media(cfg: &StartupConfig) -> Vec<PathBuf> {
+    let mut seen = BTreeSet::new();
+    cfg.entries
+        .iter()
+        .map(|entry| match &entry.command {
+            StartupCommand::Apply { wallpaper, .. } => wallpaper,
+            StartupCommand::Video { video, .. } => video,
+        })
+        .filter(|target| !is_workshop_id(target.trim()))
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute() && seen.insert(path.clone()))
+        .collect()
+}
+
+/// Points the entries that played `original` at the imported copy. Returns
+/// how many entries changed.
+pub fn rewrite_media_paths(cfg: &mut StartupConfig, moved: &BTreeMap<String, String>) -> usize {
+    let mut changed = 0usize;
+    for entry in &mut cfg.entries {
+        let target = match &mut entry.command {
+            StartupCommand::Apply { wallpaper, .. } => wallpaper,
+            StartupCommand::Video { video, .. } => video,
+        };
+        if let Some(new_path) = moved.get(target.as_str()) {
+            *target = new_path.clone();
+            changed += 1;
+        }
+    }
+    changed
+}
+
+fn fresh_dir(name: &str) -> Result<PathBuf> {
+    let dir = app_cache_dir().join(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).with_context(|| format!("Failed clearing {}", dir.display()))?;
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("Failed creating {}", dir.display()))?;
+    Ok(dir)
+}
+
+fn copy_into(src: &Path, dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::copy(src, dest)
+        .with_context(|| format!("Failed copying {} to {}", src.display(), dest.display()))?;
+    Ok(())
+}
+
+fn run_tar(args: &[&std::ffi::OsStr]) -> Result<()> {
+    let output = Command::new("tar")
+        .args(args)
+        .run_output()
+        .context("Failed running tar (needs zstd support)")?;
+    if !output.status.success() {
+        bail!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Packs config, property overrides, quirks and workshop collections (plus,
+/// with `include_media`, the local files the config plays) into `out`.
+pub fn export_profile(
+    out: &Path,
+    include_media: bool,
+    dry_run: bool,
+) -> Result<ProfileExportReport> {
+    let files = profile_files()
+        .into_iter()
+        .filter(|(_, path)| path.is_file())
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        bail!("Nothing to export: no config or overrides found");
+    }
+    let cfg = match files.iter().find(|(entry, _)| *entry == CONFIG_ENTRY) {
+        Some((_, path)) => load_config(path)?,
+        None => StartupConfig::default(),
+    };
+    let mut media = Vec::new();
+    let mut skipped_media = Vec::new();
+    for (i, path) in profile_media(&cfg).into_iter().enumerate() {
+        let original = path.to_string_lossy().to_string();
+        let Some(name) = path.file_name().filter(|_| include_media && path.is_file()) else {
+            skipped_media.push(original);
+            continue;
+        };
+        media.push(ProfileMedia {
+            original,
+            entry: format!("media/{}-{}", i, name.to_string_lossy()),
+        });
+    }
+
+    let report = ProfileExportReport {
+        archive: out.display().to_string(),
+        files: files.iter().map(|(entry, _)| entry.to_string()).collect(),
+        media,
+        skipped_media,
+    };
+    if dry_run {
+        eprintln!(
+            "[dry-run] tar --zstd -cf {} ({} files, {} media)",
+            out.display(),
+            report.files.len(),
+            report.media.len()
+        );
+        return Ok(report);
+    }
+
+    let staging = fresh_dir("profile-export")?;
+    for (entry, path) in &files {
+        copy_into(path, &staging.join(entry))?;
+    }
+    for item in &report.media {
+        copy_into(Path::new(&item.original), &staging.join(&item.entry))?;
+    }
+    let manifest = ProfileManifest {
+        version: PROFILE_VERSION,
+        created_at: Local::now().to_rfc3339(),
+        generator: format!("kitsune-livewallpaper {}", env!("CARGO_PKG_VERSION")),
+        files: report.files.clone(),
+        media: report.media.clone(),
+    };
+    fs::write(
+        staging.join(MANIFEST_ENTRY),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    run_tar(&[
+        "--zstd".as_ref(),
+        "-cf".as_ref(),
+        out.as_os_str(),
+        "-C".as_ref(),
+        staging.as_os_str(),
+        ".".as_ref(),
+    ])?;
+    eprintln!("[ok] profile exported to {}", out.display());
+    Ok(report)
+}
+
+fn backup_and_copy(src: &Path, dest: &Path, backups: &mut Vec<String>) -> Result<()> {
+    if dest.is_file() {
+        let mut backup = dest.as_os_str().to_owned();
+        backup.push(".bak");
+        fs::rename(dest, &backup)
+            .with_context(|| format!("Failed backing up {}", dest.display()))?;
+        backups.push(PathBuf::from(backup).display().to_string());
+    }
+    copy_into(src, dest)
+}
+
+/// Extracted file of an archive `entry`; entries that would leave the
+/// staging dir (absolute, `..`) are refused, and links, files under linked
+/// dirs or missing files give `None`.
+fn staged_file(staging: &Path, entry: &str) -> Result<Option<PathBuf>> {
+    let src = entry_path_under(staging, entry)
+        .with_context(|| format!("Refusing profile entry '{}'", entry))?;
+    let is_file = fs::symlink_metadata(&src).is_ok_and(|m| m.is_file());
+    let inside = || {
+        let parent = src.parent().and_then(|p| p.canonicalize().ok());
+        let staging = staging.canonicalize().ok();
+        parent.zip(staging).is_some_and(|(p, s)| p.starts_with(s))
+    };
+    Ok((is_file && inside()).then_some(src))
+}
+
+/// Restores a profile made by [`export_profile`]. Media goes to the app data
+/// dir and the restored config is pointed at it; files being replaced are
+/// kept as `.bak`.
+pub fn import_profile(archive: &Path, dry_run: bool) -> Result<ProfileImportReport> {
+    if !archive.is_file() {
+        bail_msg!(Msg::ProfileArchiveMissing, archive.display());
+    }
+    let staging = fresh_dir("profile-import")?;
+    run_tar(&[
+        "--zstd".as_ref(),
+        "-xf".as_ref(),
+        archive.as_os_str(),
+        "-C".as_ref(),
+        staging.as_os_str(),
+    ])?;
+    let manifest_path = staging.join(MANIFEST_ENTRY);
+    let raw = fs::read(&manifest_path)
+        .with_context(|| format!("{} is not a kitsune profile", archive.display()))?;
+    let manifest: ProfileManifest = serde_json::from_slice(&raw)
+        .with_context(|| format!("Invalid JSON in {}", manifest_path.display()))?;
+    if manifest.version > PROFILE_VERSION {
+        bail_msg!(Msg::ProfileVersionTooNew, manifest.version, PROFILE_VERSION);
+    }
+
+    let media_dir = app_data_dir().join("media");
+    let mut moved = BTreeMap::<String, String>::new();
+    let mut report = ProfileImportReport {
+        archive: archive.display().to_string(),
+        restored: Vec::new(),
+        backups: Vec::new(),
+        media: Vec::new(),
+        rewritten_entries: 0,
+    };
+    for item in &manifest.media {
+        let Some(src) = staged_file(&staging, &item.entry)? else {
+            warn_msg!(Msg::ProfileMediaMissing, item.entry);
+            continue;
+        };
+        let dest = media_dir.join(src.file_name().unwrap_or_default());
+        if !dry_run {
+            copy_into(&src, &dest)?;
+        }
+        moved.insert(item.original.clone(), dest.display().to_string());
+        report.media.push(dest.display().to_string());
+    }
+
+    for (entry, dest) in profile_files() {
+        let Some(src) = staged_file(&staging, entry)? else {
+            continue;
+        };
+        if entry == CONFIG_ENTRY && !moved.is_empty() {
+            let mut cfg = load_config(&src)?;
+            report.rewritten_entries = rewrite_media_paths(&mut cfg, &moved);
+            save_config(&src, &cfg)?;
+        }
+        if dry_run {
+            eprintln!("[dry-run] restore {} -> {}", entry, dest.display());
+        } else {
+            backup_and_copy(&src, &dest, &mut report.backups)?;
+        }
+        report.restored.push(dest.display().to_string());
+    }
+    if !dry_run {
+        eprintln!(
+            "[ok] profile imported from {} ({} files, {} media)",
+            archive.display(),
+            report.restored.len(),
+            report.media.len()
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::PlaybackProfile;
+    use crate::startup_config::MonitorEntry;
+
+    #[test]
+    fn profile_media_skips_workshop_ids_and_rewrites_paths() {
+        let apply = |monitor: &str, wallpaper: &str| MonitorEntry {
+            monitor: monitor.to_string(),
+            command: StartupCommand::Apply {
+                wallpaper: wallpaper.to_string(),
+                downloads_root: PathBuf::from("/d"),
+                keep_services: false,
+                mute_audio: true,
+                profile: PlaybackProfile::Performance,
+                display_fps: None,
+                allow_scene_preview_fallback: false,
+            },
+        };
+        let mut cfg = StartupConfig {
+            entries: vec![
+                apply("DP-1", "123456"),
+                apply("DP-2", "/home/u/Videos/rain.mp4"),
+                apply("HDMI-A-1", "/home/u/Videos/rain.mp4"),
+            ],
+            ..StartupConfig::default()
+        };
+        assert_eq!(
+            profile_media(&cfg),
+            [PathBuf::from("/home/u/Videos/rain.mp4")]
+        );
+
+        let moved = BTreeMap::from([(
+            "/home/u/Videos/rain.mp4".to_string(),
+            "/data/media/0-rain.mp4".to_string(),
+        )]);
+        assert_eq!(rewrite_media_paths(&mut cfg, &moved), 2);
+        assert!(profile_media(&cfg)[0].ends_with("0-rain.mp4"));
+    }
+
+    #[test]
+    fn staged_entries_stay_inside_the_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        fs::create_dir_all(staging.join("media")).unwrap();
+        fs::write(staging.join("media/0-rain.mp4"), b"mp4").unwrap();
+        fs::write(dir.path().join("secret"), b"x").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret"), staging.join("media/1-link.mp4"))
+            .unwrap();
+
+        assert_eq!(
+            staged_file(&staging, "media/0-rain.mp4").unwrap(),
+            Some(staging.join("media/0-rain.mp4"))
+        );
+        assert_eq!(staged_file(&staging, "media/1-link.mp4").unwrap(), None);
+        assert_eq!(staged_file(&staging, "media/none.mp4").unwrap(), None);
+        let secret = dir.path().join("secret").display().to_string();
+        assert!(staged_file(&staging, &secret).is_err());
+        assert!(staged_file(&staging, "media/../../secret").is_err());
+
+        // Config entries get the same checks, links to dirs included.
+        fs::create_dir_all(dir.path().join("elsewhere")).unwrap();
+        fs::write(dir.path().join("elsewhere/config.json"), b"{}").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("elsewhere"), staging.join("config")).unwrap();
+        assert_eq!(staged_file(&staging, CONFIG_ENTRY).unwrap(), None);
+        fs::remove_file(staging.join("config")).unwrap();
+        fs::create_dir(staging.join("config")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret"), staging.join(CONFIG_ENTRY)).unwrap();
+        assert_eq!(staged_file(&staging, CONFIG_ENTRY).unwrap(), None);
+    }
+}
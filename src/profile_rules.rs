@@ -0,0 +1,167 @@
+use crate::cli::PlaybackProfile;
+use crate::custom_profiles::CustomProfile;
+use crate::mpv_options::parse_mpv_option;
+use crate::types::{InspectOutput, WallpaperType};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Default options for wallpapers whose project.json matches. A rule matches
+/// when any of its `tags` and any of its `types` match; an empty list
+/// matches everything. The first matching rule wins.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileRule {
+    pub name: String,
+    /// project.json tags, compared case-insensitively.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<WallpaperType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<PlaybackProfile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_fps: Option<u32>,
+    /// Extra mpv options as `key=value`, before `--mpv-option`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mpv_options: Vec<String>,
+}
+
+impl ProfileRule {
+    /// Why the rule matches `wallpaper_type`/`tags`, or `None`.
+    fn match_reason(&self, wallpaper_type: &WallpaperType, tags: &[String]) -> Option<String> {
+        let tag = if self.tags.is_empty() {
+            None
+        } else {
+            Some(self.tags.iter().find(|rule_tag| {
+                tags.iter()
+                    .any(|tag| tag.trim().eq_ignore_ascii_case(rule_tag.trim()))
+            })?)
+        };
+        if !self.types.is_empty() && !self.types.contains(wallpaper_type) {
+            return None;
+        }
+        let mut reason = Vec::new();
+        if let Some(tag) = tag {
+            reason.push(format!("tag \"{}\"", tag));
+        }
+        if !self.types.is_empty() {
+            reason.push(format!("type {:?}", wallpaper_type).to_ascii_lowercase());
+        }
+        if reason.is_empty() {
+            reason.push("catch-all".to_string());
+        }
+        Some(reason.join(", "))
+    }
+}
+
+/// Rule picked for a wallpaper, as shown in the apply output.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchedRule {
+    pub rule: String,
+    pub reason: String,
+    pub profile: Option<PlaybackProfile>,
+    pub display_fps: Option<u32>,
+    pub mpv_options: Vec<String>,
+}
+
+pub fn match_profile_rule(
+    rules: &[ProfileRule],
+    wallpaper_type: &WallpaperType,
+    tags: &[String],
+) -> Option<MatchedRule> {
+    rules.iter().find_map(|rule| {
+        let reason = rule.match_reason(wallpaper_type, tags)?;
+        Some(MatchedRule {
+            rule: rule.name.clone(),
+            reason,
+            profile: rule.profile,
+            display_fps: rule.display_fps,
+            mpv_options: rule.mpv_options.clone(),
+        })
+    })
+}
+
+pub fn match_rule_for(rules: &[ProfileRule], info: &InspectOutput) -> Option<MatchedRule> {
+    match_profile_rule(rules, &info.wallpaper_type, &info.tags)
+}
+
+/// Rejects unnamed rules and malformed mpv options before any rule is used.
+pub fn validate_profile_rules(
+    rules: &[ProfileRule],
+    profiles: &BTreeMap<String, CustomProfile>,
+) -> Result<()> {
+    for rule in rules {
+        if rule.name.trim().is_empty() {
+            bail!("profile rule without a name");
+        }
+        if let Some(PlaybackProfile::Custom(name)) = rule.profile
+            && !profiles.contains_key(name)
+        {
+            bail!("profile rule '{}': unknown profile '{}'", rule.name, name);
+        }
+        for opt in &rule.mpv_options {
+            if let Err(err) = parse_mpv_option(opt) {
+                bail!("profile rule '{}': {}", rule.name, err);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_rule_wins_and_explains_why() {
+        let rules: Vec<ProfileRule> = serde_json::from_str(
+            r#"[
+                {"name": "pixel-art", "tags": ["Pixel Art"], "profile": "performance",
+                 "display_fps": 24, "mpv_options": ["scale=nearest", "dscale=nearest"]},
+                {"name": "videos", "types": ["video"], "profile": "quality"},
+                {"name": "rest", "display_fps": 30}
+            ]"#,
+        )
+        .unwrap();
+        validate_profile_rules(&rules, &BTreeMap::new()).unwrap();
+
+        let pixel = match_profile_rule(
+            &rules,
+            &WallpaperType::Video,
+            &["Anime".to_string(), "pixel art".to_string()],
+        )
+        .unwrap();
+        assert_eq!(pixel.rule, "pixel-art");
+        assert_eq!(pixel.reason, "tag \"Pixel Art\"");
+        assert_eq!(pixel.display_fps, Some(24));
+        assert_eq!(pixel.mpv_options, ["scale=nearest", "dscale=nearest"]);
+
+        let video = match_profile_rule(&rules, &WallpaperType::Video, &[]).unwrap();
+        assert_eq!(
+            (video.rule.as_str(), video.reason.as_str()),
+            ("videos", "type video")
+        );
+        let scene = match_profile_rule(&rules, &WallpaperType::Scene, &[]).unwrap();
+        assert_eq!(
+            (scene.rule.as_str(), scene.reason.as_str()),
+            ("rest", "catch-all")
+        );
+        assert_eq!(
+            match_profile_rule(&rules[..2], &WallpaperType::Scene, &[]),
+            None
+        );
+
+        let bad = vec![ProfileRule {
+            name: "x".to_string(),
+            mpv_options: vec!["scale".to_string()],
+            ..ProfileRule::default()
+        }];
+        assert!(validate_profile_rules(&bad, &BTreeMap::new()).is_err());
+        let unknown = vec![ProfileRule {
+            name: "x".to_string(),
+            profile: Some(PlaybackProfile::from_name("laptop")),
+            ..ProfileRule::default()
+        }];
+        assert!(validate_profile_rules(&unknown, &BTreeMap::new()).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use crate::app_paths::{app_config_dir, user_config_path};
+use crate::i18n::{Msg, bail_msg, warn_msg};
+use crate::playback::{get_mpv_property, mpv_ipc_command, mpv_ipc_socket_path, set_mpv_property};
+use crate::scene_text::{LAYER_TIMEZONE_PREFIX, parse_timezone};
+use crate::startup_config::load_config;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+/// Keys mpv can change on a running instance without reloading the file.
+/// Everything else is treated as a scene user property.
+const LIVE_MPV_PROPERTIES: [&str; 7] = [
+    "speed",
+    "volume",
+    "brightness",
+    "contrast",
+    "saturation",
+    "gamma",
+    "hue",
+];
+
+/// mpv's video equalizer only takes whole numbers.
+const INTEGER_MPV_PROPERTIES: [&str; 5] = ["brightness", "contrast", "saturation", "gamma", "hue"];
+
+/// Used when neither `--transition-ms` nor `property_transition_ms` is set.
+pub const DEFAULT_TRANSITION_MS: u32 = 400;
+/// Time between two updates of a transition (about 30 per second).
+const TRANSITION_STEP_MS: u32 = 33;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyOverrides {
+    pub version: u32,
+    /// monitor -> property -> value
+    pub monitors: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl Default for PropertyOverrides {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            monitors: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn property_overrides_path() -> PathBuf {
+    app_config_dir().join("property-overrides.json")
+}
+
+pub fn load_property_overrides() -> Result<PropertyOverrides> {
+    let path = property_overrides_path();
+    if !path.is_file() {
+        return Ok(PropertyOverrides::default());
+    }
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+fn save_property_overrides(overrides: &PropertyOverrides) -> Result<()> {
+    let path = property_overrides_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(overrides)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// Overrides stored for `monitor`; empty when none (or the store is unreadable).
+pub fn monitor_property_overrides(monitor: &str) -> BTreeMap<String, Value> {
+    load_property_overrides()
+        .ok()
+        .and_then(|mut o| o.monitors.remove(monitor))
+        .unwrap_or_default()
+}
+
+/// `true`/`false`, numbers and JSON literals keep their type; anything else
+/// is stored as a string (scene combo/text properties).
+pub fn parse_property_value(raw: &str) -> Value {
+    serde_json::from_str::<Value>(raw.trim()).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+pub fn is_live_mpv_property(key: &str) -> bool {
+    LIVE_MPV_PROPERTIES.contains(&key)
+}
+
+fn validate_live_value(key: &str, value: &Value) -> Result<()> {
+    let Some(n) = value.as_f64() else {
+        bail_msg!(Msg::PropertyNotNumber, key, value);
+    };
+    let ok = match key {
+        "speed" => n > 0.0 && n <= 100.0,
+        "volume" => (0.0..=1000.0).contains(&n),
+        _ => (-100.0..=100.0).contains(&n),
+    };
+    if !ok {
+        bail_msg!(Msg::PropertyOutOfRange, key, n);
+    }
+    Ok(())
+}
+
+/// mpv options for the live-capable overrides, appended at launch so a
+/// restart keeps what was tweaked over IPC.
+pub fn mpv_override_options(overrides: &BTreeMap<String, Value>) -> String {
+    overrides
+        .iter()
+        .filter(|(k, v)| is_live_mpv_property(k) && v.is_number())
+        .map(|(k, v)| format!(" {}={}", k, v))
+        .collect()
+}
+
+/// Color property in the scene format: "r g b" (or "r g b a") floats.
+fn parse_color(value: &Value) -> Option<Vec<f64>> {
+    let parts = value
+        .as_str()?
+        .split_whitespace()
+        .map(|p| p.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    matches!(parts.len(), 3 | 4).then_some(parts)
+}
+
+/// Value `t` (0..1) of the way from `from` to `to`, for numbers and colors;
+/// `None` when the two cannot be blended (text, booleans, mixed kinds).
+pub fn interpolate_property(from: &Value, to: &Value, t: f64) -> Option<Value> {
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    if let (Some(a), Some(b)) = (from.as_f64(), to.as_f64()) {
+        return Some(Value::from(lerp(a, b)));
+    }
+    let (a, b) = (parse_color(from)?, parse_color(to)?);
+    if a.len() != b.len() {
+        return None;
+    }
+    let blended = a
+        .iter()
+        .zip(&b)
+        .map(|(a, b)| format!("{:.4}", lerp(*a, *b)))
+        .collect::<Vec<_>>();
+    Some(Value::String(blended.join(" ")))
+}
+
+/// Eased values a transition walks through over `duration_ms`, ending on
+/// `to`. A single step (a jump) when the values cannot be blended.
+pub fn transition_steps(from: &Value, to: &Value, duration_ms: u32) -> Vec<Value> {
+    let steps = (duration_ms / TRANSITION_STEP_MS).max(1);
+    if steps == 1 || interpolate_property(from, to, 0.0).is_none() {
+        return vec![to.clone()];
+    }
+    (1..=steps)
+        .map(|i| {
+            let x = i as f64 / steps as f64;
+            // smoothstep: starts and stops gently.
+            let t = x * x * (3.0 - 2.0 * x);
+            interpolate_property(from, to, t).unwrap_or_else(|| to.clone())
+        })
+        .collect()
+}
+
+/// `--transition-ms`, else `property_transition_ms` from config.json.
+pub fn resolve_transition_ms(cli_value: Option<u32>) -> u32 {
+    cli_value
+        .or_else(|| {
+            load_config(&user_config_path())
+                .ok()
+                .and_then(|cfg| cfg.property_transition_ms)
+        })
+        .unwrap_or(DEFAULT_TRANSITION_MS)
+}
+
+/// Walks a running mpv from its current value to `value`.
+fn tween_mpv_property(monitor: &str, key: &str, value: Value, transition_ms: u32) -> Result<()> {
+    let Ok(current) = get_mpv_property(monitor, key) else {
+        return set_mpv_property(monitor, key, value, false);
+    };
+    let steps = transition_steps(&current, &value, transition_ms);
+    let last = steps.len() - 1;
+    for (i, step) in steps.into_iter().enumerate() {
+        let step = match step.as_f64() {
+            Some(n) if INTEGER_MPV_PROPERTIES.contains(&key) => Value::from(n.round() as i64),
+            _ => step,
+        };
+        if i == last {
+            return set_mpv_property(monitor, key, step, false);
+        }
+        mpv_ipc_command(monitor, serde_json::json!(["set_property", key, step]))?;
+        thread::sleep(Duration::from_millis(TRANSITION_STEP_MS as u64));
+    }
+    Ok(())
+}
+
+/// Stores the override and pushes it to the running wallpaper when mpv can
+/// take it live, easing from the current value over `transition_ms`; scene
+/// properties apply on the next launch.
+pub fn set_property_override(
+    monitor: &str,
+    key: &str,
+    raw: &str,
+    transition_ms: u32,
+    dry_run: bool,
+) -> Result<()> {
+    let key = key.trim();
+    if key.is_empty() {
+        bail!("Property name is empty");
+    }
+    if key.starts_with(LAYER_TIMEZONE_PREFIX) {
+        parse_timezone(raw).map_err(anyhow::Error::msg)?;
+    }
+    let value = parse_property_value(raw);
+    let live = is_live_mpv_property(key);
+    if live {
+        validate_live_value(key, &value)?;
+    }
+
+    if dry_run {
+        eprintln!(
+            "[dry-run] persist {}.{}={} in {}",
+            monitor,
+            key,
+            value,
+            property_overrides_path().display()
+        );
+    } else {
+        let mut overrides = load_property_overrides()?;
+        overrides
+            .monitors
+            .entry(monitor.to_string())
+            .or_default()
+            .insert(key.to_string(), value.clone());
+        save_property_overrides(&overrides)?;
+        eprintln!("[ok] stored {}.{}={}", monitor, key, value);
+    }
+
+    if !live {
+        eprintln!(
+            "[ok] '{}' is a scene property; it applies on the next apply/scene-gpu-play for {}",
+            key, monitor
+        );
+        return Ok(());
+    }
+    if !dry_run && !mpv_ipc_socket_path(monitor).exists() {
+        eprintln!(
+            "[ok] no running wallpaper on {}; applies on next launch",
+            monitor
+        );
+        return Ok(());
+    }
+    let result = if dry_run || transition_ms == 0 {
+        set_mpv_property(monitor, key, value, dry_run)
+    } else {
+        tween_mpv_property(monitor, key, value, transition_ms)
+    };
+    if let Err(err) = result {
+        warn_msg!(Msg::LiveUpdateFailed, err);
+    }
+    Ok(())
+}
+
+pub fn clear_property_override(monitor: &str, key: Option<&str>, dry_run: bool) -> Result<()> {
+    let mut overrides = load_property_overrides()?;
+    let Some(values) = overrides.monitors.get_mut(monitor) else {
+        eprintln!("[ok] no overrides stored for {}", monitor);
+        return Ok(());
+    };
+    let removed = match key {
+        Some(key) => values.remove(key).map(|_| 1).unwrap_or(0),
+        None => std::mem::take(values).len(),
+    };
+    if values.is_empty() {
+        overrides.monitors.remove(monitor);
+    }
+    if dry_run {
+        eprintln!("[dry-run] remove {} override(s) for {}", removed, monitor);
+        return Ok(());
+    }
+    save_property_overrides(&overrides)?;
+    eprintln!(
+        "[ok] removed {} override(s) for {} (takes effect on next launch)",
+        removed, monitor
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_keep_their_type_and_only_mpv_keys_become_options() {
+        assert_eq!(parse_property_value("2.0"), serde_json::json!(2.0));
+        assert_eq!(parse_property_value("true"), Value::Bool(true));
+        assert_eq!(parse_property_value("night"), Value::String("night".into()));
+
+        let mut values = BTreeMap::new();
+        values.insert("speed".to_string(), serde_json::json!(1.5));
+        values.insert("schemecolor".to_string(), serde_json::json!("0 0 1"));
+        assert_eq!(mpv_override_options(&values), " speed=1.5");
+
+        assert!(validate_live_value("speed", &serde_json::json!(0)).is_err());
+        assert!(validate_live_value("hue", &serde_json::json!(-20)).is_ok());
+    }
+
+    #[test]
+    fn transitions_ease_numbers_and_colors_and_jump_otherwise() {
+        let steps = transition_steps(&serde_json::json!(0), &serde_json::json!(10), 330);
+        assert_eq!(steps.len(), 10);
+        assert_eq!(steps.last(), Some(&serde_json::json!(10.0)));
+        let first = steps[0].as_f64().unwrap();
+        assert!(first > 0.0 && first < 1.0, "eased start, got {first}");
+
+        assert_eq!(
+            interpolate_property(&"0 0 0".into(), &"1 0.5 1".into(), 0.5),
+            Some(Value::String("0.5000 0.2500 0.5000".to_string()))
+        );
+        assert_eq!(
+            transition_steps(&"night".into(), &"day".into(), 400),
+            [Value::from("day")]
+        );
+        assert_eq!(
+            transition_steps(&serde_json::json!(1), &serde_json::json!(2), 0),
+            [serde_json::json!(2)]
+        );
+    }
+}
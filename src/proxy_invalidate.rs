@@ -0,0 +1,189 @@
+use crate::proxy_meta::{ProxyMeta, read_proxy_info};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Containers our proxy builders write.
+const PROXY_EXTS: [&str; 4] = ["mp4", "mov", "webm", "mkv"];
+/// Cache dirs our proxy builders write under (scene sessions, video and GIF
+/// proxies). Everything else in the cache (steamcmd, extracted bundles...)
+/// holds media we did not generate.
+const PROXY_ROOTS: [&str; 2] = ["scene", "video-live"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidatedProxy {
+    pub file: String,
+    pub kind: Option<String>,
+    pub workshop_id: Option<String>,
+    /// `None` for proxies written before the metadata tags existed.
+    pub generator: Option<String>,
+    pub outdated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyInvalidateReport {
+    pub cache_dir: String,
+    pub engine_version: String,
+    pub scanned: usize,
+    pub invalidated: Vec<InvalidatedProxy>,
+    pub dry_run: bool,
+}
+
+/// Numeric parts of `kitsune-livewallpaper 0.4.2`.
+fn generator_version(generator: &str) -> Option<Vec<u32>> {
+    let version = generator.split_whitespace().last()?;
+    version
+        .split(['.', '-', '+'])
+        .take(3)
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Proxies without tags, or tagged by an older (or unreadable) generator.
+pub fn is_outdated(meta: Option<&ProxyMeta>, engine_version: &str) -> bool {
+    let current = generator_version(engine_version);
+    match meta.and_then(|m| generator_version(&m.generator)) {
+        Some(version) => current.is_some_and(|current| version < current),
+        None => true,
+    }
+}
+
+/// Proxy files under `dir`. Warm-start copies stay: they are only shown
+/// until the proxy they stand in for is rebuilt.
+fn proxy_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(t) if t.is_dir() && name != "warm-start" => proxy_files(&path, out),
+            Ok(t) if t.is_file() => {
+                let ext = path
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                    .unwrap_or_default();
+                if PROXY_EXTS.contains(&ext.as_str()) {
+                    out.push(path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A proxy belongs to `key` when its tags name it, or when it sits in the
+/// `<key>` cache folder.
+fn belongs_to(path: &Path, meta: Option<&ProxyMeta>, key: &str) -> bool {
+    meta.and_then(|m| m.workshop_id.as_deref()) == Some(key)
+        || path.components().any(|c| c.as_os_str() == key)
+}
+
+/// Removes the proxies of `wallpaper` (cache folder key, or every wallpaper
+/// when `None`) so the next playback rebuilds them with this engine version.
+/// Only the [`PROXY_ROOTS`] of `cache_dir` are searched.
+pub fn invalidate_proxies(
+    cache_dir: &Path,
+    wallpaper: Option<&str>,
+    only_outdated: bool,
+    dry_run: bool,
+) -> Result<ProxyInvalidateReport> {
+    let engine_version = env!("CARGO_PKG_VERSION").to_string();
+    let mut files = Vec::new();
+    for root in PROXY_ROOTS {
+        proxy_files(&cache_dir.join(root), &mut files);
+    }
+    files.sort();
+    let mut report = ProxyInvalidateReport {
+        cache_dir: cache_dir.display().to_string(),
+        engine_version: engine_version.clone(),
+        scanned: 0,
+        invalidated: Vec::new(),
+        dry_run,
+    };
+    for file in files {
+        let meta = read_proxy_info(&file).ok().and_then(|info| info.proxy);
+        if wallpaper.is_some_and(|key| !belongs_to(&file, meta.as_ref(), key)) {
+            continue;
+        }
+        report.scanned += 1;
+        let outdated = is_outdated(meta.as_ref(), &engine_version);
+        if only_outdated && !outdated {
+            continue;
+        }
+        if !dry_run {
+            fs::remove_file(&file)
+                .with_context(|| format!("Failed removing {}", file.display()))?;
+        }
+        report.invalidated.push(InvalidatedProxy {
+            file: file.display().to_string(),
+            kind: meta.as_ref().map(|m| m.kind.clone()),
+            workshop_id: meta.as_ref().and_then(|m| m.workshop_id.clone()),
+            generator: meta.map(|m| m.generator),
+            outdated,
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_runner::{MockRunner, with_runner};
+    use std::rc::Rc;
+
+    #[test]
+    fn only_outdated_proxies_of_the_wallpaper_are_invalidated() {
+        let meta = |generator: &str| ProxyMeta {
+            kind: "scene-simple".to_string(),
+            workshop_id: Some("2233445566".to_string()),
+            title: None,
+            generator: generator.to_string(),
+            settings_hash: "0".to_string(),
+            source: "/w/2233445566/scene.pkg".to_string(),
+        };
+        assert!(is_outdated(
+            Some(&meta("kitsune-livewallpaper 0.1.0")),
+            "0.4.2"
+        ));
+        assert!(!is_outdated(
+            Some(&meta("kitsune-livewallpaper 0.4.2")),
+            "0.4.2"
+        ));
+        assert!(!is_outdated(
+            Some(&meta("kitsune-livewallpaper 0.10.0")),
+            "0.4.2"
+        ));
+        assert!(is_outdated(None, "0.4.2"));
+
+        let cache = tempfile::tempdir().unwrap();
+        let scene = cache.path().join("scene/2233445566/render-session/proxy");
+        let other = cache.path().join("scene/998877/render-session/proxy");
+        let warm = cache.path().join("scene/2233445566/warm-start");
+        let steamcmd = cache.path().join("steamcmd/2233445566");
+        for dir in [&scene, &other, &warm, &steamcmd] {
+            fs::create_dir_all(dir).unwrap();
+            fs::write(dir.join("clip.mp4"), b"x").unwrap();
+        }
+        fs::write(scene.join("frames.txt"), b"x").unwrap();
+
+        // No ffprobe tags: every file counts as written before the tags.
+        let mock = Rc::new(MockRunner::new());
+        mock.respond("ffprobe", true, r#"{"format":{}}"#);
+        let report = with_runner(mock, || {
+            invalidate_proxies(cache.path(), Some("2233445566"), true, false)
+        })
+        .unwrap();
+        assert_eq!((report.scanned, report.invalidated.len()), (1, 1));
+        assert!(report.invalidated[0].outdated);
+        assert!(!scene.join("clip.mp4").exists());
+        assert!(other.join("clip.mp4").is_file());
+        assert!(warm.join("clip.mp4").is_file());
+        assert!(steamcmd.join("clip.mp4").is_file());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::command_runner::CommandExt;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::process::Command;
+
+/// Prefix of the `comment` tag that marks a file as one of our proxies. mp4
+/// only keeps a few well-known tags, so everything rides in `comment`.
+const COMMENT_PREFIX: &str = "kwe-proxy ";
+
+/// Where a generated proxy came from, written into the file's own metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProxyMeta {
+    /// Builder that produced the file (`optimized`, `gif`, `scene-animated`, ...).
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workshop_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub generator: String,
+    /// Hash of the encode settings (filters, codec, size, fps).
+    pub settings_hash: String,
+    pub source: String,
+}
+
+fn project_title(project: &Path) -> Option<String> {
+    let raw = std::fs::read_to_string(project).ok()?;
+    let json: Value = serde_json::from_str(&raw).ok()?;
+    json.get("title")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+/// Workshop id and title for a proxy source: the nearest `project.json`
+/// above `source` (video wallpapers), otherwise the workshop-id folder of a
+/// scene cache path looked up in the default downloads root.
+fn identify_source(source: &Path) -> (Option<String>, Option<String>) {
+    for dir in source.ancestors().skip(1) {
+        let project = dir.join("project.json");
+        if project.is_file() {
+            let id = dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .filter(|n| crate::workshop::is_workshop_id(n));
+            return (id, project_title(&project));
+        }
+    }
+    let id = source
+        .ancestors()
+        .filter_map(|dir| dir.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .find(|n| crate::workshop::is_workshop_id(n));
+    let title = id.as_ref().and_then(|id| {
+        project_title(
+            &crate::cli::default_downloads_root()
+                .join(id)
+                .join("project.json"),
+        )
+    });
+    (id, title)
+}
+
+impl ProxyMeta {
+    pub fn new(kind: &str, source: &Path, settings: &str) -> Self {
+        let (workshop_id, title) = identify_source(source);
+        let mut hasher = DefaultHasher::new();
+        settings.hash(&mut hasher);
+        Self {
+            kind: kind.to_string(),
+            workshop_id,
+            title,
+            generator: format!("kitsune-livewallpaper {}", env!("CARGO_PKG_VERSION")),
+            settings_hash: format!("{:016x}", hasher.finish()),
+            source: source.to_string_lossy().to_string(),
+        }
+    }
+
+    /// `-metadata` options to add before the ffmpeg output path.
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(title) = &self.title {
+            args.extend(["-metadata".to_string(), format!("title={}", title)]);
+        }
+        let payload = serde_json::to_string(self).unwrap_or_default();
+        args.extend([
+            "-metadata".to_string(),
+            format!("comment={}{}", COMMENT_PREFIX, payload),
+        ]);
+        args
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProxyInfo {
+    pub file: String,
+    pub title: Option<String>,
+    /// `None` for files we did not generate (or older proxies without tags).
+    pub proxy: Option<ProxyMeta>,
+}
+
+/// Reads our tags back from `ffprobe -show_entries format_tags -of json`.
+fn parse_probe_tags(file: &Path, raw: &str) -> Result<ProxyInfo> {
+    let json: Value = serde_json::from_str(raw).context("Invalid ffprobe JSON")?;
+    let tag = |name: &str| {
+        json.pointer("/format/tags")
+            .and_then(Value::as_object)?
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .and_then(|(_, v)| v.as_str())
+            .map(str::to_string)
+    };
+    let proxy = tag("comment")
+        .and_then(|c| c.strip_prefix(COMMENT_PREFIX).map(str::to_string))
+        .and_then(|payload| serde_json::from_str(&payload).ok());
+    Ok(ProxyInfo {
+        file: file.display().to_string(),
+        title: tag("title"),
+        proxy,
+    })
+}
+
+pub fn read_proxy_info(file: &Path) -> Result<ProxyInfo> {
+    if !file.is_file() {
+        bail!("Proxy file does not exist: {}", file.display());
+    }
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format_tags")
+        .arg("-of")
+        .arg("json")
+        .arg(file)
+        .run_output()
+        .context("Failed running ffprobe for proxy metadata")?;
+    if !output.status.success() {
+        bail!(
+            "ffprobe could not read {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_probe_tags(file, &String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proxy_tags_round_trip_through_ffprobe_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("2233445566");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("project.json"), r#"{"title":"Rainy Window"}"#).unwrap();
+        let meta = ProxyMeta::new(
+            "optimized",
+            &root.join("clip.webm"),
+            "scale=1920,fps=30 crf20",
+        );
+        assert_eq!(meta.workshop_id.as_deref(), Some("2233445566"));
+        assert_eq!(meta.title.as_deref(), Some("Rainy Window"));
+        assert_ne!(
+            meta.settings_hash,
+            ProxyMeta::new("optimized", &root.join("clip.webm"), "crf18").settings_hash
+        );
+
+        let args = meta.ffmpeg_args();
+        assert_eq!(args[..2], ["-metadata", "title=Rainy Window"]);
+        let comment = args[3].strip_prefix("comment=").unwrap();
+        // mp4 reports lower-case keys, Matroska upper-case ones.
+        let probe =
+            serde_json::json!({"format": {"tags": {"TITLE": "Rainy Window", "COMMENT": comment}}});
+        let info = parse_probe_tags(Path::new("x.mp4"), &probe.to_string()).unwrap();
+        assert_eq!(info.proxy, Some(meta));
+
+        let foreign = r#"{"format":{"tags":{"comment":"made elsewhere"}}}"#;
+        assert_eq!(
+            parse_probe_tags(Path::new("y.mp4"), foreign).unwrap().proxy,
+            None
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::command_runner::{CommandExt, shell_command_line};
+use crate::i18n::{Msg, bail_msg};
+use crate::library_guard::ensure_outside_library;
+use crate::playback::{get_mpv_property, mpv_ipc_socket_path};
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+/// What `record` re-renders: the media and filter chain the monitor's mpv is
+/// playing, from its current position. Screen capture would also grab the
+/// windows and cursor above the background layer.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordPlan {
+    pub monitor: String,
+    pub source: String,
+    pub start_seconds: f64,
+    pub seconds: f64,
+    pub fps: Option<f64>,
+    pub filter_complex: Option<String>,
+    pub out: String,
+}
+
+fn is_still_image(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "webp" | "bmp")
+}
+
+/// Binds `[vin]`/`[vout]` to the stage pads and makes the graph's own labels
+/// unique, since every stage lands in one `-filter_complex`.
+fn relabel_lavfi_graph(graph: &str, stage: usize, input: &str, output: &str) -> String {
+    let mut out = String::with_capacity(graph.len());
+    let mut rest = graph;
+    while let Some(start) = rest.find('[') {
+        let Some(len) = rest[start..].find(']') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        match &rest[start + 1..start + len] {
+            "vin" => out.push_str(input),
+            "vout" => out.push_str(output),
+            label => out.push_str(&format!("[{}_{}]", label, stage)),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// ffmpeg `-filter_complex` equivalent of mpv's `vf` property (list of
+/// `{name, label, enabled, params}`), reading `[0:v]` and ending in `[vout]`.
+/// `lavfi` graphs keep their `[vin]`/`[vout]` pads, renamed per stage.
+pub fn vf_to_filter_complex(vf: &Value) -> Option<String> {
+    let filters: Vec<&Value> = vf
+        .as_array()?
+        .iter()
+        .filter(|f| f.get("enabled").and_then(|v| v.as_bool()) != Some(false))
+        .collect();
+    if filters.is_empty() {
+        return None;
+    }
+    let pad = |i: usize| {
+        if i == 0 {
+            "[0:v]".to_string()
+        } else if i == filters.len() {
+            "[vout]".to_string()
+        } else {
+            format!("[s{}]", i)
+        }
+    };
+    let stages = filters
+        .iter()
+        .enumerate()
+        .map(|(i, filter)| {
+            let name = filter
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            let params = filter.get("params").and_then(|v| v.as_object());
+            let (input, output) = (pad(i), pad(i + 1));
+            if name == "lavfi" {
+                let graph = params
+                    .and_then(|p| p.get("graph"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("null");
+                if graph.contains("[vin]") {
+                    relabel_lavfi_graph(graph, i, &input, &output)
+                } else {
+                    format!("{}{}{}", input, graph, output)
+                }
+            } else {
+                let args = params
+                    .map(|p| {
+                        p.iter()
+                            .map(|(k, v)| match v.as_str() {
+                                Some(s) => format!("{}={}", k, s),
+                                None => format!("{}={}", k, v),
+                            })
+                            .collect::<Vec<_>>()
+                            .join(":")
+                    })
+                    .unwrap_or_default();
+                if args.is_empty() {
+                    format!("{}{}{}", input, name, output)
+                } else {
+                    format!("{}{}={}{}", input, name, args, output)
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    Some(stages.join(";"))
+}
+
+pub fn plan_record(monitor: &str, seconds: f64, out: &Path) -> Result<RecordPlan> {
+    if !mpv_ipc_socket_path(monitor).exists() {
+        bail_msg!(Msg::NoRunningWallpaper, monitor);
+    }
+    let source = get_mpv_property(monitor, "path")?
+        .as_str()
+        .map(str::to_string)
+        .context("mpv reported no playing file")?;
+    let start_seconds = get_mpv_property(monitor, "time-pos")
+        .ok()
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0);
+    let fps = get_mpv_property(monitor, "container-fps")
+        .ok()
+        .and_then(|v| v.as_f64())
+        .filter(|fps| *fps > 0.0);
+    let vf = get_mpv_property(monitor, "vf").unwrap_or(Value::Null);
+    Ok(RecordPlan {
+        monitor: monitor.to_string(),
+        source,
+        start_seconds,
+        seconds,
+        fps,
+        filter_complex: vf_to_filter_complex(&vf),
+        out: out.display().to_string(),
+    })
+}
+
+fn record_command(plan: &RecordPlan) -> Command {
+    let source = Path::new(&plan.source);
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y"]);
+    if is_still_image(source) {
+        cmd.args(["-loop", "1"]);
+    } else {
+        // Loop like the wallpaper does when the clip outlasts the media.
+        cmd.args(["-ss", &format!("{:.3}", plan.start_seconds)]);
+        cmd.args(["-stream_loop", "-1"]);
+    }
+    cmd.arg("-i").arg(source);
+    cmd.args(["-t", &format!("{:.3}", plan.seconds)]);
+    match &plan.filter_complex {
+        Some(graph) => cmd.args(["-filter_complex", graph, "-map", "[vout]"]),
+        None => cmd.args(["-map", "0:v:0"]),
+    };
+    cmd.arg("-an");
+    if let Some(fps) = plan.fps {
+        cmd.args(["-r", &format!("{:.3}", fps)]);
+    }
+    cmd.args([
+        "-c:v",
+        "libx264",
+        "-preset",
+        "veryfast",
+        "-crf",
+        "20",
+        "-pix_fmt",
+        "yuv420p",
+        "-movflags",
+        "+faststart",
+    ]);
+    cmd.arg(&plan.out);
+    cmd
+}
+
+/// Records `seconds` of what `monitor` is showing into `out`.
+pub fn record_monitor(
+    monitor: &str,
+    seconds: f64,
+    out: &Path,
+    dry_run: bool,
+) -> Result<RecordPlan> {
+    if seconds <= 0.0 {
+        bail!("--seconds must be positive");
+    }
+    ensure_outside_library(out, "record")?;
+    let plan = plan_record(monitor, seconds, out)?;
+    let mut cmd = record_command(&plan);
+    if dry_run {
+        eprintln!("[dry-run] {}", shell_command_line(&cmd));
+        return Ok(plan);
+    }
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    let output = cmd
+        .run_output()
+        .context("Failed running ffmpeg for record")?;
+    if !output.status.success() {
+        bail!(
+            "ffmpeg could not record {}: {}",
+            monitor,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    eprintln!(
+        "[ok] recorded {:.1}s of {} -> {}",
+        seconds,
+        monitor,
+        out.display()
+    );
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn mpv_vf_chain_becomes_filter_complex() {
+        assert_eq!(vf_to_filter_complex(&json!([])), None);
+        let vf = json!([
+            {"name": "crop", "enabled": true, "params": {"w": "iw/2", "h": "ih"}},
+            {"name": "eq", "enabled": false, "params": {"brightness": "0.1"}},
+            {"name": "lavfi", "label": "pip1", "enabled": true,
+             "params": {"graph": "movie=a.mp4[p];[vin][p]overlay[vout]"}},
+            {"name": "lavfi", "enabled": true, "params": {"graph": "hflip"}}
+        ]);
+        assert_eq!(
+            vf_to_filter_complex(&vf).unwrap(),
+            "[0:v]crop=h=ih:w=iw/2[s1];movie=a.mp4[p_1];[s1][p_1]overlay[s2];[s2]hflip[vout]"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use crate::app_paths::{app_cache_dir, home_dir};
+use crate::command_runner::CommandExt;
+use crate::crash_report::latest_crash_report;
+use crate::journal::{filter_events, load_journal};
+use crate::scene_capabilities::build_wallpaper_capabilities;
+use crate::scene_emulation_trace::emulation_trace_path;
+use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_native_runtime::{
+    apply_cost_budget, build_native_runtime_plan, native_cost_budget,
+};
+use crate::scene_plan::build_scene_plan;
+use crate::scene_renderer::scene_session_dir_for_root;
+use crate::types::WallpaperType;
+use crate::version_info::detect_tools;
+use crate::wallpaper::inspect_wallpaper;
+use anyhow::{Context, Result, bail};
+use chrono::{Duration as ChronoDuration, Local};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Journal window and size kept in a bundle.
+const JOURNAL_DAYS: i64 = 7;
+const JOURNAL_MAX_EVENTS: usize = 200;
+
+#[derive(Debug, Serialize)]
+pub struct ReportBundleReport {
+    pub archive: String,
+    pub wallpaper: String,
+    pub wallpaper_type: String,
+    pub files: Vec<String>,
+    /// Sections that could not be produced; the bundle is written anyway.
+    pub errors: Vec<String>,
+}
+
+/// Replaces each `(path, placeholder)` in `text`, longest path first so a
+/// wallpaper dir inside the home dir keeps its own placeholder.
+pub fn redact_paths(text: &str, replacements: &[(String, &str)]) -> String {
+    let mut sorted = replacements
+        .iter()
+        .filter(|(path, _)| path.len() > 1)
+        .collect::<Vec<_>>();
+    sorted.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+    sorted
+        .into_iter()
+        .fold(text.to_string(), |acc, (path, placeholder)| {
+            acc.replace(path.as_str(), placeholder)
+        })
+}
+
+/// First non-empty line a tool printed for its version flag.
+pub fn first_output_line(stdout: &[u8], stderr: &[u8]) -> Option<String> {
+    [stdout, stderr]
+        .iter()
+        .flat_map(|out| {
+            String::from_utf8_lossy(out)
+                .lines()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .map(|line| line.trim().to_string())
+        .find(|line| !line.is_empty())
+}
+
+fn tool_versions() -> String {
+    let mut out = format!("kitsune-livewallpaper {}\n", env!("CARGO_PKG_VERSION"));
+    for tool in detect_tools() {
+        let line = match (tool.found, tool.banner) {
+            (true, Some(banner)) => banner,
+            (true, None) => "(no output)".to_string(),
+            (false, _) => "not found".to_string(),
+        };
+        out.push_str(&format!("{}: {}\n", tool.name, line));
+    }
+    out
+}
+
+fn recent_journal() -> Result<String> {
+    let since = Local::now() - ChronoDuration::days(JOURNAL_DAYS);
+    let events = filter_events(load_journal()?, Some(since), None, None);
+    let skip = events.len().saturating_sub(JOURNAL_MAX_EVENTS);
+    let mut out = String::new();
+    for event in &events[skip..] {
+        out.push_str(&serde_json::to_string(event)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn pretty<T: Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(value)?)
+}
+
+/// Archive entry name and its contents, or why it is missing.
+type Section = (String, Result<String>);
+
+fn collect_sections(
+    wallpaper: &str,
+    downloads_root: &Path,
+) -> Result<(PathBuf, String, Vec<Section>)> {
+    let mut info = inspect_wallpaper(wallpaper, downloads_root)?;
+    let root = PathBuf::from(&info.root);
+    if let Ok(capabilities) = build_wallpaper_capabilities(&root, &info.wallpaper_type) {
+        info.capabilities = capabilities;
+    }
+    let kind = format!("{:?}", info.wallpaper_type).to_ascii_lowercase();
+    let mut sections = vec![("inspect.json".to_string(), pretty(&info))];
+
+    if matches!(info.wallpaper_type, WallpaperType::Scene) {
+        sections.push((
+            "scene-plan.json".to_string(),
+            build_scene_plan(&root).and_then(|plan| pretty(&plan)),
+        ));
+        let graph = build_scene_gpu_graph(&root);
+        let native_plan = graph
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+            .and_then(|graph| {
+                let mut plan = build_native_runtime_plan(graph);
+                apply_cost_budget(
+                    &mut plan,
+                    graph.scene_width,
+                    graph.scene_height,
+                    native_cost_budget(),
+                );
+                pretty(&plan)
+            });
+        sections.push((
+            "scene-gpu-graph.json".to_string(),
+            graph.and_then(|graph| pretty(&graph)),
+        ));
+        sections.push(("scene-native-plan.json".to_string(), native_plan));
+
+        let session_dir = scene_session_dir_for_root(&root);
+        for (entry, path) in [
+            ("emulation-trace.json", emulation_trace_path(&session_dir)),
+            ("session-manifest.json", session_dir.join("manifest.json")),
+        ] {
+            let body = if path.is_file() {
+                fs::read_to_string(&path)
+                    .with_context(|| format!("Failed reading {}", path.display()))
+            } else {
+                Err(anyhow::anyhow!(
+                    "{} not found (apply the wallpaper once to create it)",
+                    path.display()
+                ))
+            };
+            sections.push((entry.to_string(), body));
+        }
+    }
+
+    sections.push(("tool-versions.txt".to_string(), Ok(tool_versions())));
+    sections.push(("journal.jsonl".to_string(), recent_journal()));
+    if let Some(path) = latest_crash_report() {
+        let body =
+            fs::read_to_string(&path).with_context(|| format!("Failed reading {}", path.display()));
+        sections.push(("crash-report.json".to_string(), body));
+    }
+    Ok((root, kind, sections))
+}
+
+fn run_tar_gz(out: &Path, staging: &Path) -> Result<()> {
+    let output = Command::new("tar")
+        .arg("-czf")
+        .arg(out)
+        .arg("-C")
+        .arg(staging)
+        .arg(".")
+        .run_output()
+        .context("Failed running tar")?;
+    if !output.status.success() {
+        bail!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Collects everything an issue report needs for `wallpaper` (inspect,
+/// plans, trace, tool versions, recent journal, session manifest) into a
+/// `.tar.gz`, with local paths replaced by placeholders.
+pub fn build_report_bundle(
+    wallpaper: &str,
+    downloads_root: &Path,
+    out: &Path,
+    dry_run: bool,
+) -> Result<ReportBundleReport> {
+    let (root, kind, sections) = collect_sections(wallpaper, downloads_root)?;
+    let mut redactions = vec![
+        (root.to_string_lossy().to_string(), "<wallpaper>"),
+        (downloads_root.to_string_lossy().to_string(), "<downloads>"),
+        (app_cache_dir().to_string_lossy().to_string(), "<cache>"),
+    ];
+    if let Some(home) = home_dir() {
+        redactions.push((home.to_string_lossy().to_string(), "~"));
+    }
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    let mut entries = Vec::new();
+    for (entry, body) in sections {
+        match body {
+            Ok(body) => {
+                files.push(entry.clone());
+                entries.push((entry, redact_paths(&body, &redactions)));
+            }
+            Err(err) => errors.push(redact_paths(&format!("{}: {:#}", entry, err), &redactions)),
+        }
+    }
+    let report = ReportBundleReport {
+        archive: out.display().to_string(),
+        wallpaper: wallpaper.to_string(),
+        wallpaper_type: kind,
+        files,
+        errors,
+    };
+    if dry_run {
+        eprintln!(
+            "[dry-run] tar -czf {} ({} files)",
+            out.display(),
+            report.files.len()
+        );
+        return Ok(report);
+    }
+
+    let staging = app_cache_dir().join("report-bundle");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)
+            .with_context(|| format!("Failed clearing {}", staging.display()))?;
+    }
+    fs::create_dir_all(&staging)
+        .with_context(|| format!("Failed creating {}", staging.display()))?;
+    for (entry, body) in &entries {
+        fs::write(staging.join(entry), body)
+            .with_context(|| format!("Failed writing {}", entry))?;
+    }
+    if !report.errors.is_empty() {
+        fs::write(staging.join("errors.txt"), report.errors.join("\n") + "\n")?;
+    }
+    run_tar_gz(out, &staging)?;
+    eprintln!("[ok] report bundle written to {}", out.display());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_text_is_path_redacted() {
+        let redactions = vec![
+            ("/home/ana".to_string(), "~"),
+            (
+                "/home/ana/.steam/steamapps/workshop/content/431960/123".to_string(),
+                "<wallpaper>",
+            ),
+            ("/".to_string(), "<root>"),
+        ];
+        let text = r#"{"root":"/home/ana/.steam/steamapps/workshop/content/431960/123","log":"/home/ana/x.log","tmp":"/tmp/a"}"#;
+        assert_eq!(
+            redact_paths(text, &redactions),
+            r#"{"root":"<wallpaper>","log":"~/x.log","tmp":"/tmp/a"}"#
+        );
+
+        assert_eq!(
+            first_output_line(b"\n  mpv 0.38.0 Copyright\nbuilt on", b""),
+            Some("mpv 0.38.0 Copyright".to_string())
+        );
+        assert_eq!(
+            first_output_line(b"", b"Usage: mpvpaper [options]\n"),
+            Some("Usage: mpvpaper [options]".to_string())
+        );
+        assert_eq!(first_output_line(b"", b""), None);
+    }
+}
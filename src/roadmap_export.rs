@@ -0,0 +1,222 @@
+use crate::app_paths::app_cache_dir;
+use crate::cli::RoadmapExportFormat;
+use crate::library_guard::ensure_outside_library;
+use crate::library_scan::LibraryRoadmapReport;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const WORKSHOP_ITEM_URL: &str = "https://steamcommunity.com/sharedfiles/filedetails/?id=";
+
+/// Last roadmap built by `library-roadmap`; `--from-cache` renders exports
+/// from it so the same data can be re-exported without a rescan.
+pub fn roadmap_cache_path() -> PathBuf {
+    app_cache_dir().join("library-roadmap.json")
+}
+
+pub fn save_roadmap_cache(report: &LibraryRoadmapReport) -> Result<()> {
+    let path = roadmap_cache_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(report)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+pub fn load_roadmap_cache() -> Result<LibraryRoadmapReport> {
+    let path = roadmap_cache_path();
+    let raw = fs::read(&path).with_context(|| {
+        format!(
+            "No cached roadmap at {} (run library-roadmap without --from-cache first)",
+            path.display()
+        )
+    })?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+/// The cached roadmap when it is younger than `max_age` and was built for
+/// the same library and `--top-n`, so scheduled exports rescan at most once
+/// per interval.
+pub fn fresh_roadmap_cache(
+    downloads_root: &Path,
+    top_n: usize,
+    max_age: Duration,
+) -> Option<LibraryRoadmapReport> {
+    let age = fs::metadata(roadmap_cache_path())
+        .and_then(|m| m.modified())
+        .ok()?
+        .elapsed()
+        .ok()?;
+    if age > max_age {
+        return None;
+    }
+    let report = load_roadmap_cache().ok()?;
+    (report.downloads_root == downloads_root.to_string_lossy() && report.top_n == top_n)
+        .then_some(report)
+}
+
+fn workshop_url(id: &str) -> Option<String> {
+    (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| format!("{WORKSHOP_ITEM_URL}{id}"))
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn render_roadmap_markdown(report: &LibraryRoadmapReport) -> String {
+    let mut out = String::new();
+    out.push_str("# Library roadmap\n\n");
+    out.push_str(&format!(
+        "- Generated: {}\n- Library: `{}`\n- Wallpapers scanned: {}\n- Average compatibility: {:.2}% (estimated after top {}: {:.2}%)\n\n",
+        report.generated_at,
+        report.downloads_root,
+        report.wallpapers_scanned,
+        report.baseline_average_compatibility_percent,
+        report.top_recommendations.len(),
+        report.estimated_average_after_top_n
+    ));
+    out.push_str("| Rank | Effect | Wallpapers | Avg score | Est. gain |\n");
+    out.push_str("|---:|---|---:|---:|---:|\n");
+    for item in &report.top_recommendations {
+        out.push_str(&format!(
+            "| {} | `{}` | {} | {:.2} | {:.2} |\n",
+            item.rank,
+            markdown_cell(&item.effect_file),
+            item.wallpapers_affected,
+            item.avg_current_score,
+            item.estimated_coverage_gain_points
+        ));
+    }
+    for item in &report.top_recommendations {
+        out.push_str(&format!(
+            "\n## {}. `{}`\n\n",
+            item.rank,
+            markdown_cell(&item.effect_file)
+        ));
+        for w in &item.affected_wallpapers {
+            let title = markdown_cell(w.title.as_deref().unwrap_or(&w.id));
+            let name = match workshop_url(&w.id) {
+                Some(url) => format!("[{}]({})", title, url),
+                None => title,
+            };
+            out.push_str(&format!(
+                "- {} (`{}`, {}%)\n",
+                name, w.id, w.compatibility_percent
+            ));
+        }
+    }
+    out
+}
+
+/// One row per (effect, affected wallpaper).
+pub fn render_roadmap_csv(report: &LibraryRoadmapReport) -> String {
+    let mut out = String::from(
+        "rank,effect_file,wallpapers_affected,avg_current_score,estimated_coverage_gain_points,wallpaper_id,title,compatibility_percent,workshop_url\n",
+    );
+    for item in &report.top_recommendations {
+        for w in &item.affected_wallpapers {
+            let row = [
+                item.rank.to_string(),
+                csv_field(&item.effect_file),
+                item.wallpapers_affected.to_string(),
+                format!("{:.2}", item.avg_current_score),
+                format!("{:.2}", item.estimated_coverage_gain_points),
+                csv_field(&w.id),
+                csv_field(w.title.as_deref().unwrap_or_default()),
+                w.compatibility_percent.to_string(),
+                workshop_url(&w.id).unwrap_or_default(),
+            ];
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+pub fn export_roadmap(
+    report: &LibraryRoadmapReport,
+    format: RoadmapExportFormat,
+    out: &Path,
+) -> Result<()> {
+    ensure_outside_library(out, "roadmap export")?;
+    let body = match format {
+        RoadmapExportFormat::Markdown => render_roadmap_markdown(report),
+        RoadmapExportFormat::Csv => render_roadmap_csv(report),
+    };
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(out, body).with_context(|| format!("Failed writing {}", out.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library_scan::{RoadmapEffectItem, RoadmapWallpaperRef};
+
+    #[test]
+    fn exports_link_workshop_items_and_escape_titles() {
+        let report = LibraryRoadmapReport {
+            generated_at: "2026-01-01 00:00:00 +0000".to_string(),
+            downloads_root: "/lib".to_string(),
+            top_n: 1,
+            wallpapers_scanned: 2,
+            baseline_average_compatibility_percent: 70.0,
+            estimated_average_after_top_n: 74.5,
+            top_recommendations: vec![RoadmapEffectItem {
+                rank: 1,
+                effect_file: "effects/waterripple/effect.json".to_string(),
+                wallpapers_affected: 2,
+                avg_current_score: 65.0,
+                estimated_coverage_gain_points: 2.1,
+                affected_wallpapers: vec![
+                    RoadmapWallpaperRef {
+                        id: "123".to_string(),
+                        title: Some("Rain, \"night\" | city".to_string()),
+                        compatibility_percent: 60,
+                    },
+                    RoadmapWallpaperRef {
+                        id: "local-scene".to_string(),
+                        title: None,
+                        compatibility_percent: 70,
+                    },
+                ],
+            }],
+        };
+
+        let md = render_roadmap_markdown(&report);
+        assert!(md.contains(
+            "[Rain, \"night\" \\| city](https://steamcommunity.com/sharedfiles/filedetails/?id=123)"
+        ));
+        assert!(md.contains("- local-scene (`local-scene`, 70%)"));
+
+        let csv = render_roadmap_csv(&report);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[1].contains(",\"Rain, \"\"night\"\" | city\",60,https://"));
+        assert!(rows[2].ends_with(",local-scene,,70,"));
+
+        let dir = tempfile::tempdir().unwrap();
+        crate::app_paths::with_app_dirs_root(dir.path(), || {
+            let hour = Duration::from_secs(3600);
+            assert!(fresh_roadmap_cache(Path::new("/lib"), 1, hour).is_none());
+            save_roadmap_cache(&report).unwrap();
+            assert!(fresh_roadmap_cache(Path::new("/lib"), 1, hour).is_some());
+            assert!(fresh_roadmap_cache(Path::new("/lib"), 5, hour).is_none());
+            assert!(fresh_roadmap_cache(Path::new("/other"), 1, hour).is_none());
+            assert!(fresh_roadmap_cache(Path::new("/lib"), 1, Duration::ZERO).is_none());
+        });
+    }
+}
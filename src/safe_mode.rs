@@ -0,0 +1,115 @@
+use crate::app_paths::user_config_path;
+use crate::cli::GpuTransport;
+use crate::i18n::{Msg, warn_msg};
+use crate::scene_gpu_backend::SceneGpuPlayArgs;
+use crate::startup_config::load_config;
+use std::cell::Cell;
+
+/// Effect layers the ffmpeg effect proxies composite in safe mode.
+pub const SAFE_EFFECT_LAYER_LIMIT: usize = 1;
+
+thread_local! {
+    static SAFE_MODE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the running command must stay on the stable playback paths.
+pub fn safe_mode() -> bool {
+    SAFE_MODE.with(Cell::get)
+}
+
+/// Runs `f` with safe mode set for every check on this thread.
+pub fn with_safe_mode<R>(enabled: bool, f: impl FnOnce() -> R) -> R {
+    let previous = SAFE_MODE.with(|c| c.replace(enabled));
+    let result = f();
+    SAFE_MODE.with(|c| c.set(previous));
+    result
+}
+
+/// `--safe`, or `safe_mode: true` in the user (or system) config.
+pub fn resolve_safe_mode(cli_flag: bool) -> bool {
+    if cli_flag {
+        return true;
+    }
+    match load_config(&user_config_path()) {
+        Ok(cfg) => cfg.safe_mode,
+        Err(err) => {
+            warn_msg!(Msg::SafeModeConfigIgnored, err);
+            false
+        }
+    }
+}
+
+/// Turns off the experimental parts of a `scene-gpu-play` launch and returns
+/// what was changed.
+pub fn restrict_gpu_play(args: &mut SceneGpuPlayArgs) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if matches!(
+        args.transport,
+        GpuTransport::NativeRealtime | GpuTransport::NativeStream
+    ) {
+        args.transport = GpuTransport::Mp4Proxy;
+        changed.push("transport mp4-proxy");
+    }
+    if args.require_native {
+        args.require_native = false;
+        changed.push("require-native off");
+    }
+    if args.audio_reactive {
+        args.audio_reactive = false;
+        changed.push("audio-reactive off");
+    }
+    if args.apply_kitsune_overlay {
+        args.apply_kitsune_overlay = false;
+        changed.push("kitsune overlay off");
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{
+        AudioBarsSource, Orientation, PlaybackProfile, RealtimeMotion, TextRenderMode,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn safe_mode_keeps_gpu_play_on_stable_paths() {
+        let mut args = SceneGpuPlayArgs {
+            root: PathBuf::from("/tmp/123"),
+            monitor: "DP-1".to_string(),
+            source: None,
+            seconds: 4,
+            frame_ms: 50,
+            profile: PlaybackProfile::Performance,
+            mute_audio: true,
+            display_fps: None,
+            clock_overlay: true,
+            text_timezone: None,
+            text_mode: TextRenderMode::Drawtext,
+            orientation: Orientation::Auto,
+            apply_kitsune_overlay: true,
+            transport: GpuTransport::NativeRealtime,
+            require_native: true,
+            audio_bars_source: AudioBarsSource::Pulse,
+            realtime_motion: RealtimeMotion::Full,
+            custom_filters: None,
+            proxy_width: 2560,
+            proxy_fps: 30,
+            auto_fps: true,
+            proxy_crf: 20,
+            proxy_seconds: None,
+            audio_reactive: true,
+            audio_reactive_strength: 1.0,
+            dry_run: true,
+        };
+        assert_eq!(restrict_gpu_play(&mut args).len(), 4);
+        assert!(matches!(args.transport, GpuTransport::Mp4Proxy));
+        assert!(!args.audio_reactive && !args.apply_kitsune_overlay && !args.require_native);
+        assert!(restrict_gpu_play(&mut args).is_empty());
+
+        assert!(!safe_mode());
+        assert!(with_safe_mode(true, safe_mode));
+        assert!(!safe_mode());
+    }
+}
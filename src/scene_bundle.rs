@@ -0,0 +1,350 @@
+use crate::app_paths::app_cache_dir;
+use crate::asset_resolver::AssetResolver;
+use crate::i18n::{Msg, warn_msg};
+use crate::scene_effect_proxy::maybe_build_scene_animated_proxy;
+use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_native_renderer::{decode_layer_image, resolve_layer_image};
+use crate::scene_native_runtime::{NativeDrawLayer, NativeSupportTier, build_native_runtime_plan};
+use crate::scene_pkg::{
+    ScenePkg, entry_path_under, extract_entry_to_cache, find_entry, parse_scene_pkg,
+    read_entry_bytes, write_scene_pkg,
+};
+use crate::scene_renderer::build_scene_render_session;
+use crate::scene_script::ScriptAssignment;
+use crate::tex_payload::extract_playable_proxy_from_tex;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub const KWESCENE_EXTENSION: &str = "kwescene";
+const MANIFEST_ENTRY: &str = "kwescene.json";
+const UNIFORMS_ENTRY: &str = "curves/uniforms.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KweSceneLayer {
+    #[serde(flatten)]
+    pub layer: NativeDrawLayer,
+    pub texture_entry: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KweSceneManifest {
+    pub version: u32,
+    pub source_root: String,
+    pub title: Option<String>,
+    pub workshop_id: Option<String>,
+    pub scene_width: u32,
+    pub scene_height: u32,
+    pub visual_entry: String,
+    pub baked_proxy_entry: Option<String>,
+    pub music_entry: Option<String>,
+    pub uniforms_entry: String,
+    pub frame_ms: u64,
+    pub frame_count: usize,
+    pub layers: Vec<KweSceneLayer>,
+    pub user_properties: Value,
+    pub script_assignments: Vec<ScriptAssignment>,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KweSceneCompileReport {
+    pub out_path: String,
+    pub entries: usize,
+    pub total_bytes: u64,
+    pub layers: usize,
+    pub layers_with_texture: usize,
+    pub baked_proxy: bool,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KweSceneExtracted {
+    pub manifest: KweSceneManifest,
+    pub dir: PathBuf,
+    /// Best entry for mpvpaper: the baked proxy when present, else the visual.
+    pub playable_entry: PathBuf,
+    pub music_path: Option<PathBuf>,
+}
+
+pub fn is_kwescene_path(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().eq_ignore_ascii_case(KWESCENE_EXTENSION))
+        .unwrap_or(false)
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|v| v.to_string_lossy().to_string())
+        .unwrap_or_else(|| "asset".to_string())
+}
+
+fn is_mpv_playable_visual(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    matches!(
+        ext.as_str(),
+        "mp4" | "webm" | "mkv" | "avi" | "mov" | "gif" | "png" | "jpg" | "jpeg" | "webp" | "bmp"
+    )
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("Failed reading {}", path.display()))
+}
+
+/// Resolves the scene once (graph, layers, textures, uniform curves) and
+/// packs everything playback needs into a single `.kwescene` container.
+pub fn compile_kwescene(
+    root: &Path,
+    out: &Path,
+    seconds: u64,
+    frame_ms: u64,
+    bake_proxy: bool,
+) -> Result<KweSceneCompileReport> {
+    let graph = build_scene_gpu_graph(root)?;
+    let plan = build_native_runtime_plan(&graph);
+    let session = build_scene_render_session(root, None, seconds, frame_ms)?;
+    let session_dir = PathBuf::from(&session.session_dir);
+    let mut notes = graph.notes.clone();
+    let mut entries = Vec::<(String, Vec<u8>)>::new();
+
+    let visual_path = PathBuf::from(&session.visual_asset_path);
+    let visual_playable = if is_mpv_playable_visual(&visual_path) {
+        visual_path.clone()
+    } else {
+        extract_playable_proxy_from_tex(&visual_path, &session_dir.join("proxy"))?.with_context(
+            || {
+                format!(
+                    "Primary visual {} could not be converted to a playable image",
+                    visual_path.display()
+                )
+            },
+        )?
+    };
+    let visual_entry = format!("visual/{}", file_name_of(&visual_playable));
+    entries.push((visual_entry.clone(), read_file(&visual_playable)?));
+
+    let mut baked_proxy_entry = None;
+    if bake_proxy {
+        match maybe_build_scene_animated_proxy(
+            root,
+            &session_dir,
+            &visual_playable,
+            None,
+            false,
+            None,
+            false,
+        ) {
+            Ok(Some(proxy)) if proxy.is_file() => {
+                let name = format!("proxy/{}", file_name_of(&proxy));
+                entries.push((name.clone(), read_file(&proxy)?));
+                baked_proxy_entry = Some(name);
+            }
+            Ok(_) => notes.push("No animated proxy baked (visual is not image-like)".to_string()),
+            Err(err) => {
+                warn_msg!(Msg::ProxyBakeFailed, err);
+                notes.push(format!("Animated proxy bake failed: {err}"));
+            }
+        }
+    }
+
+    let music_entry = match &session.music_asset_path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            let name = format!("audio/{}", file_name_of(&path));
+            entries.push((name.clone(), read_file(&path)?));
+            Some(name)
+        }
+        None => None,
+    };
+
+    entries.push((
+        UNIFORMS_ENTRY.to_string(),
+        read_file(Path::new(&session.uniforms_path))?,
+    ));
+
+    let resolver = AssetResolver::new(root)?;
+    let scratch = session_dir.join("kwescene-scratch");
+    let mut layers = Vec::<KweSceneLayer>::new();
+    for (idx, layer) in plan.draw_layers.iter().enumerate() {
+        let mut texture_entry = None;
+        if matches!(layer.tier, NativeSupportTier::Ready)
+            && let Some(texture_ref) = &layer.primary_texture
+        {
+            let decoded = resolve_layer_image(&resolver, texture_ref, &scratch)?
+                .and_then(|bytes| decode_layer_image(&bytes));
+            match decoded {
+                Some(img) => {
+                    let mut png = std::io::Cursor::new(Vec::new());
+                    img.write_to(&mut png, image::ImageFormat::Png)
+                        .with_context(|| format!("Failed encoding texture {}", texture_ref))?;
+                    let name = format!("textures/layer_{idx:03}.png");
+                    entries.push((name.clone(), png.into_inner()));
+                    texture_entry = Some(name);
+                }
+                None => notes.push(format!(
+                    "Layer '{}' texture '{}' could not be resolved",
+                    layer.object_name, texture_ref
+                )),
+            }
+        }
+        layers.push(KweSceneLayer {
+            layer: layer.clone(),
+            texture_entry,
+        });
+    }
+
+    let project_json = resolver
+        .resolve("project.json")
+        .and_then(|v| serde_json::from_slice::<Value>(&v.bytes).ok());
+    let manifest = KweSceneManifest {
+        version: 1,
+        source_root: root.to_string_lossy().to_string(),
+        title: project_json
+            .as_ref()
+            .and_then(|p| p.get("title"))
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+        workshop_id: project_json
+            .as_ref()
+            .and_then(|p| p.get("workshopid"))
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            }),
+        scene_width: graph.scene_width,
+        scene_height: graph.scene_height,
+        visual_entry,
+        baked_proxy_entry: baked_proxy_entry.clone(),
+        music_entry,
+        uniforms_entry: UNIFORMS_ENTRY.to_string(),
+        frame_ms,
+        frame_count: session.frame_count,
+        layers,
+        user_properties: graph.user_properties.clone(),
+        script_assignments: graph.script_assignments.clone(),
+        notes: notes.clone(),
+    };
+    entries.insert(
+        0,
+        (
+            MANIFEST_ENTRY.to_string(),
+            serde_json::to_vec_pretty(&manifest)?,
+        ),
+    );
+
+    write_scene_pkg(out, &entries)?;
+
+    Ok(KweSceneCompileReport {
+        out_path: out.to_string_lossy().to_string(),
+        entries: entries.len(),
+        total_bytes: entries.iter().map(|(_, b)| b.len() as u64).sum(),
+        layers: manifest.layers.len(),
+        layers_with_texture: manifest
+            .layers
+            .iter()
+            .filter(|l| l.texture_entry.is_some())
+            .count(),
+        baked_proxy: baked_proxy_entry.is_some(),
+        notes,
+    })
+}
+
+fn read_manifest(pkg: &ScenePkg) -> Result<KweSceneManifest> {
+    let entry = find_entry(pkg, MANIFEST_ENTRY)
+        .with_context(|| format!("{} has no {}", pkg.path.display(), MANIFEST_ENTRY))?;
+    serde_json::from_slice(&read_entry_bytes(pkg, &entry)?)
+        .with_context(|| format!("Invalid {} in {}", MANIFEST_ENTRY, pkg.path.display()))
+}
+
+pub fn load_kwescene_manifest(path: &Path) -> Result<KweSceneManifest> {
+    read_manifest(&parse_scene_pkg(path)?)
+}
+
+pub fn default_kwescene_cache_dir(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|v| v.to_string_lossy().to_string())
+        .unwrap_or_else(|| "scene".to_string());
+    app_cache_dir().join("kwescene").join(stem)
+}
+
+/// Unpacks a `.kwescene` so its entries can be handed to mpvpaper/ffmpeg.
+pub fn extract_kwescene(path: &Path, out_dir: &Path) -> Result<KweSceneExtracted> {
+    let pkg = parse_scene_pkg(path)?;
+    let manifest = read_manifest(&pkg)?;
+    if manifest.version != 1 {
+        bail!(
+            "Unsupported kwescene version {} in {}",
+            manifest.version,
+            path.display()
+        );
+    }
+    for entry in &pkg.entries {
+        extract_entry_to_cache(&pkg, entry, out_dir)?;
+    }
+    let playable = manifest
+        .baked_proxy_entry
+        .as_deref()
+        .unwrap_or(&manifest.visual_entry);
+    let music_path = manifest
+        .music_entry
+        .as_deref()
+        .map(|m| entry_path_under(out_dir, m))
+        .transpose()?;
+    Ok(KweSceneExtracted {
+        playable_entry: entry_path_under(out_dir, playable)?,
+        music_path,
+        dir: out_dir.to_path_buf(),
+        manifest,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_prefers_baked_proxy() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = KweSceneManifest {
+            version: 1,
+            source_root: "/tmp/1".to_string(),
+            title: None,
+            workshop_id: None,
+            scene_width: 1920,
+            scene_height: 1080,
+            visual_entry: "visual/base.png".to_string(),
+            baked_proxy_entry: Some("proxy/scene.mp4".to_string()),
+            music_entry: None,
+            uniforms_entry: UNIFORMS_ENTRY.to_string(),
+            frame_ms: 50,
+            frame_count: 0,
+            layers: Vec::new(),
+            user_properties: Value::Null,
+            script_assignments: Vec::new(),
+            notes: Vec::new(),
+        };
+        let bundle = dir.path().join("test.kwescene");
+        write_scene_pkg(
+            &bundle,
+            &[
+                (
+                    MANIFEST_ENTRY.to_string(),
+                    serde_json::to_vec(&manifest).unwrap(),
+                ),
+                ("visual/base.png".to_string(), vec![0u8; 4]),
+                ("proxy/scene.mp4".to_string(), vec![1u8; 4]),
+            ],
+        )
+        .unwrap();
+
+        assert!(is_kwescene_path(&bundle));
+        let out = extract_kwescene(&bundle, &dir.path().join("x")).unwrap();
+        assert_eq!(out.playable_entry, dir.path().join("x/proxy/scene.mp4"));
+        assert!(out.playable_entry.is_file());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::asset_resolver::AssetResolver;
+use crate::scene_effect_proxy::build_scene_audio_bars_overlay;
+use crate::scene_fps::{SceneMotionClass, classify_scene_motion};
+use crate::scene_gpu_graph::{SceneGpuGraph, build_scene_gpu_graph};
+use crate::scene_native_runtime::{NativeRuntimePlan, build_native_runtime_plan};
+use crate::scene_text::is_text_object;
+use crate::types::WallpaperType;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostClass {
+    Low,
+    Medium,
+    High,
+}
+
+/// Badges for frontends, computed from the gpu graph and native plan so a
+/// GUI does not need to run `scene-gpu-graph` and `scene-native-plan` too.
+#[derive(Debug, Clone, Serialize)]
+pub struct WallpaperCapabilities {
+    pub has_text_layers: bool,
+    pub has_particles: bool,
+    pub has_audio_bars: bool,
+    pub has_split_viewports: bool,
+    pub effect_families: Vec<String>,
+    pub motion: SceneMotionClass,
+    /// video | mp4-proxy | native-realtime
+    pub estimated_best_transport: String,
+    pub approximate_vram_mb: u32,
+    pub vram_cost: CostClass,
+    pub cpu_cost: CostClass,
+}
+
+pub fn video_capabilities() -> WallpaperCapabilities {
+    WallpaperCapabilities {
+        has_text_layers: false,
+        has_particles: false,
+        has_audio_bars: false,
+        has_split_viewports: false,
+        effect_families: Vec::new(),
+        motion: SceneMotionClass::Heavy,
+        estimated_best_transport: "video".to_string(),
+        // hwdec keeps a handful of decoded surfaces around.
+        approximate_vram_mb: 96,
+        vram_cost: CostClass::Low,
+        cpu_cost: CostClass::Low,
+    }
+}
+
+fn count_text_objects(root: &Path) -> usize {
+    let Ok(resolver) = AssetResolver::new(root) else {
+        return 0;
+    };
+    let Some(scene) = resolver
+        .resolve("scene.json")
+        .or_else(|| resolver.resolve("gifscene.json"))
+        .and_then(|a| serde_json::from_slice::<Value>(&a.bytes).ok())
+    else {
+        return 0;
+    };
+    scene
+        .get("objects")
+        .and_then(|v| v.as_array())
+        .map(|objects| objects.iter().filter(|o| is_text_object(o)).count())
+        .unwrap_or(0)
+}
+
+/// Native transports are only a safe bet when every pass is drawable (or the
+/// scene needs viewport compositing, which mp4-proxy cannot do).
+fn best_transport(plan: &NativeRuntimePlan) -> &'static str {
+    let fully_ready =
+        plan.ready_draw_layers > 0 && plan.experimental_nodes == 0 && plan.unsupported_nodes == 0;
+    if fully_ready || (!plan.viewports.is_empty() && plan.ready_draw_layers > 0) {
+        "native-realtime"
+    } else {
+        "mp4-proxy"
+    }
+}
+
+/// One RGBA canvas per drawn layer plus the composite, at scene size.
+fn approximate_vram_mb(graph: &SceneGpuGraph, plan: &NativeRuntimePlan) -> u32 {
+    let canvas = graph.scene_width.max(1) as u64 * graph.scene_height.max(1) as u64 * 4;
+    let surfaces = plan.ready_draw_layers as u64 + 1;
+    (canvas * surfaces).div_ceil(1024 * 1024) as u32
+}
+
+fn vram_cost(mb: u32) -> CostClass {
+    match mb {
+        0..=256 => CostClass::Low,
+        257..=1024 => CostClass::Medium,
+        _ => CostClass::High,
+    }
+}
+
+fn cpu_cost(motion: SceneMotionClass) -> CostClass {
+    match motion {
+        SceneMotionClass::Static => CostClass::Low,
+        SceneMotionClass::DriftOnly => CostClass::Medium,
+        SceneMotionClass::Heavy => CostClass::High,
+    }
+}
+
+pub fn scene_capabilities_from_graph(
+    graph: &SceneGpuGraph,
+    plan: &NativeRuntimePlan,
+    text_objects: usize,
+    has_audio_bars: bool,
+    primary_is_video: bool,
+) -> WallpaperCapabilities {
+    let mut families = BTreeSet::new();
+    families.extend(plan.passes.iter().map(|p| p.shader_family.clone()));
+    let motion = classify_scene_motion(graph, primary_is_video);
+    let vram = approximate_vram_mb(graph, plan);
+    WallpaperCapabilities {
+        has_text_layers: text_objects > 0,
+        has_particles: graph
+            .effect_nodes
+            .iter()
+            .any(|n| n.object_kind.eq_ignore_ascii_case("particle")),
+        has_audio_bars,
+        has_split_viewports: !graph.viewports.is_empty(),
+        effect_families: families.into_iter().filter(|f| !f.is_empty()).collect(),
+        motion,
+        estimated_best_transport: best_transport(plan).to_string(),
+        approximate_vram_mb: vram,
+        vram_cost: vram_cost(vram),
+        cpu_cost: cpu_cost(motion),
+    }
+}
+
+pub fn build_wallpaper_capabilities(
+    root: &Path,
+    wallpaper_type: &WallpaperType,
+) -> Result<Option<WallpaperCapabilities>> {
+    match wallpaper_type {
+        WallpaperType::Video => Ok(Some(video_capabilities())),
+        WallpaperType::Scene => {
+            let graph = build_scene_gpu_graph(root)?;
+            let plan = build_native_runtime_plan(&graph);
+            let has_audio_bars = build_scene_audio_bars_overlay(root)
+                .ok()
+                .flatten()
+                .is_some();
+            Ok(Some(scene_capabilities_from_graph(
+                &graph,
+                &plan,
+                count_text_objects(root),
+                has_audio_bars,
+                false,
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_classes_and_transport_pick() {
+        assert_eq!(vram_cost(64), CostClass::Low);
+        assert_eq!(vram_cost(600), CostClass::Medium);
+        assert_eq!(vram_cost(4096), CostClass::High);
+
+        let mut plan = NativeRuntimePlan {
+            ready_draw_layers: 3,
+            ..Default::default()
+        };
+        assert_eq!(best_transport(&plan), "native-realtime");
+        plan.unsupported_nodes = 1;
+        assert_eq!(best_transport(&plan), "mp4-proxy");
+        plan.ready_draw_layers = 0;
+        assert_eq!(best_transport(&plan), "mp4-proxy");
+    }
+}
@@ -1,11 +1,24 @@
-use crate::scene_gpu_graph::{SceneGpuGraph, build_scene_gpu_graph};
-use crate::scene_native_runtime::{NativeSupportTier, build_native_runtime_plan};
+use crate::audio_bars_calibration::apply_audio_bars_calibration;
+use crate::colorspace::{SRGB_BT709_TAGS, YUV420P_BT709};
+use crate::command_runner::{CommandExt, shell_command_line};
+use crate::custom_filters::{CustomFilters, with_post_filter, with_pre_filter};
+use crate::display_orientation::{CanvasCrop, portrait_canvas};
+use crate::i18n::{Msg, warn_msg};
+use crate::layer_overrides::load_layer_override;
+use crate::library_guard::ensure_outside_library;
+use crate::proxy_meta::ProxyMeta;
+use crate::safe_mode::{SAFE_EFFECT_LAYER_LIMIT, safe_mode};
+use crate::scene_emulation_trace::{EmulationKind, EmulationTraceEntry, record_emulation_stage};
+use crate::scene_gpu_graph::{GpuPassSpec, PrunedObject, SceneGpuGraph, build_scene_gpu_graph};
+use crate::scene_native_runtime::{
+    NativeSupportTier, RegionBlur, build_native_runtime_plan, region_blurs,
+};
 use crate::scene_pkg::{extract_entry_to_cache, find_entry, parse_scene_pkg, read_entry_bytes};
 use crate::tex_payload::extract_playable_proxy_from_tex;
 use anyhow::{Context, Result};
 use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -40,9 +53,21 @@ enum MotionProfile {
     Pulse,
 }
 
+impl MotionProfile {
+    fn as_str(self) -> &'static str {
+        match self {
+            MotionProfile::Iris => "iris",
+            MotionProfile::Shake => "shake",
+            MotionProfile::Drift => "drift",
+            MotionProfile::Pulse => "pulse",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct EffectLayerRef {
     texture_ref: String,
+    origin: EmulationTraceEntry,
     profile: MotionProfile,
     alpha: f32,
     family: String,
@@ -103,6 +128,8 @@ pub struct RealtimeEffectPlan {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct AudioBarsOverlay {
+    /// Scene object carrying the audio bars effect.
+    pub object_id: u64,
     pub center_x: i32,
     pub center_y: i32,
     pub width: u32,
@@ -118,6 +145,18 @@ pub struct AudioBarsOverlay {
     pub height_norm: f32,
 }
 
+impl AudioBarsOverlay {
+    /// Name of the `TRANSPARENCY` combo, as composited by the proxies below.
+    pub fn blend_mode(&self) -> &'static str {
+        match self.transparency_mode {
+            2 => "add",
+            3 => "subtract",
+            4 => "multiply",
+            _ => "normal",
+        }
+    }
+}
+
 fn infer_motion_profile(effect_file: &str) -> MotionProfile {
     let f = effect_file.to_ascii_lowercase();
     if f.contains("iris") || f.contains("eye") || f.contains("blink") {
@@ -202,17 +241,28 @@ fn visual_tuning_from_graph(graph: &SceneGpuGraph) -> VisualTuning {
     tuning
 }
 
-fn collect_effect_layer_refs(scene: &Value, max_candidates: usize) -> Vec<EffectLayerRef> {
+fn collect_effect_layer_refs(
+    scene: &Value,
+    pruned: &[PrunedObject],
+    max_candidates: usize,
+    trace: &mut Vec<EmulationTraceEntry>,
+) -> Vec<EffectLayerRef> {
     let mut out = Vec::new();
     let mut seen = HashSet::<String>::new();
     let Some(objects) = scene.get("objects").and_then(|v| v.as_array()) else {
         return out;
     };
-    for object in objects {
+    for (object_index, object) in objects.iter().enumerate() {
+        if pruned.iter().any(|p| p.object_index == object_index) {
+            continue;
+        }
         let Some(effects) = object.get("effects").and_then(|v| v.as_array()) else {
             continue;
         };
         for effect in effects {
+            if effect.get("visible") == Some(&Value::Bool(false)) {
+                continue;
+            }
             let file = effect
                 .get("file")
                 .and_then(|v| v.as_str())
@@ -227,7 +277,7 @@ fn collect_effect_layer_refs(scene: &Value, max_candidates: usize) -> Vec<Effect
             let Some(passes) = effect.get("passes").and_then(|v| v.as_array()) else {
                 continue;
             };
-            for pass in passes {
+            for (pass_index, pass) in passes.iter().enumerate() {
                 let Some(textures) = pass.get("textures").and_then(|v| v.as_array()) else {
                     continue;
                 };
@@ -237,23 +287,40 @@ fn collect_effect_layer_refs(scene: &Value, max_candidates: usize) -> Vec<Effect
                         if trimmed.is_empty() {
                             continue;
                         }
+                        let mut origin = EmulationTraceEntry::new(EmulationKind::Dropped, "");
+                        origin.object_index = Some(object_index);
+                        origin.object_id = object.get("id").and_then(|v| v.as_u64());
+                        origin.object_name = object
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .map(|v| v.to_string());
+                        origin.pass_index = Some(pass_index);
+                        origin.shader = Some(file.to_string());
+                        origin.shader_family = Some("legacy-effects".to_string());
+                        origin.texture_ref = Some(trimmed.to_string());
                         let key = trimmed.to_ascii_lowercase();
-                        if seen.insert(key) {
-                            out.push(EffectLayerRef {
-                                texture_ref: trimmed.to_string(),
-                                profile,
-                                alpha: profile_alpha,
-                                family: "legacy-effects".to_string(),
-                                center_x: 0.0,
-                                center_y: 0.0,
-                                width: 0.0,
-                                height: 0.0,
-                                angle_rad: 0.0,
-                            });
-                            if out.len() >= max_candidates.max(1) {
-                                return out;
-                            }
+                        if !seen.insert(key) {
+                            origin.reason = "texture already used by another layer".to_string();
+                            trace.push(origin);
+                            continue;
+                        }
+                        if out.len() >= max_candidates.max(1) {
+                            origin.reason = "effect candidate limit reached".to_string();
+                            trace.push(origin);
+                            continue;
                         }
+                        out.push(EffectLayerRef {
+                            texture_ref: trimmed.to_string(),
+                            origin,
+                            profile,
+                            alpha: profile_alpha,
+                            family: "legacy-effects".to_string(),
+                            center_x: 0.0,
+                            center_y: 0.0,
+                            width: 0.0,
+                            height: 0.0,
+                            angle_rad: 0.0,
+                        });
                     }
                 }
             }
@@ -265,26 +332,40 @@ fn collect_effect_layer_refs(scene: &Value, max_candidates: usize) -> Vec<Effect
 fn collect_effect_layer_refs_from_native_plan(
     graph: &SceneGpuGraph,
     max_candidates: usize,
+    trace: &mut Vec<EmulationTraceEntry>,
 ) -> Vec<EffectLayerRef> {
     let plan = build_native_runtime_plan(graph);
     let mut out = Vec::<EffectLayerRef>::new();
     let mut seen = HashSet::<String>::new();
 
-    for layer in plan.draw_layers {
+    for (pass, layer) in plan.passes.iter().zip(plan.draw_layers) {
+        let mut origin = EmulationTraceEntry::from_pass(pass, EmulationKind::Dropped, "");
         if !matches!(layer.tier, NativeSupportTier::Ready) {
+            origin.reason = format!("tier {:?}: {}", pass.tier, pass.reason);
+            trace.push(origin);
             continue;
         }
         let Some(texture_ref) = layer.primary_texture else {
+            origin.reason = "pass has no primary texture".to_string();
+            trace.push(origin);
             continue;
         };
         let key = texture_ref.to_ascii_lowercase();
         if !seen.insert(key) {
+            origin.reason = "texture already used by another layer".to_string();
+            trace.push(origin);
+            continue;
+        }
+        if out.len() >= max_candidates.max(1) {
+            origin.reason = "effect candidate limit reached".to_string();
+            trace.push(origin);
             continue;
         }
 
         let profile = infer_motion_profile(&format!("{} {}", layer.shader_family, layer.shader));
         out.push(EffectLayerRef {
             texture_ref,
+            origin,
             profile,
             alpha: layer.alpha.clamp(0.05, 1.0),
             family: layer.shader_family,
@@ -294,14 +375,103 @@ fn collect_effect_layer_refs_from_native_plan(
             height: layer.height,
             angle_rad: layer.angle_rad,
         });
-        if out.len() >= max_candidates.max(1) {
-            break;
-        }
     }
 
     out
 }
 
+/// Resolves mask images for `layer_refs` (up to `EFFECT_LAYER_LIMIT`, or
+/// `SAFE_EFFECT_LAYER_LIMIT` in safe mode) and records the emulation
+/// decision for every candidate in `trace`.
+fn resolve_effect_layers(
+    pkg: &crate::scene_pkg::ScenePkg,
+    layer_refs: Vec<EffectLayerRef>,
+    session_dir: &Path,
+    tuning: &VisualTuning,
+    trace: &mut Vec<EmulationTraceEntry>,
+) -> Vec<EffectLayer> {
+    let masks_src = session_dir.join("effect-proxy/masks-src");
+    let masks_proxy = session_dir.join("effect-proxy/masks-proxy");
+
+    let limit = if safe_mode() {
+        SAFE_EFFECT_LAYER_LIMIT
+    } else {
+        EFFECT_LAYER_LIMIT
+    };
+    let mut layers = Vec::<EffectLayer>::new();
+    for layer_ref in layer_refs {
+        let mut origin = layer_ref.origin.clone();
+        if layers.len() >= limit {
+            origin.reason = format!("effect layer limit ({limit}) reached");
+            trace.push(origin);
+            continue;
+        }
+        let Some(tex_entry) = find_tex_entry_for_texture_ref(pkg, &layer_ref.texture_ref) else {
+            origin.reason = "texture not found in scene pkg".to_string();
+            trace.push(origin);
+            continue;
+        };
+        let Some(mask_image) = extract_entry_to_cache(pkg, &tex_entry, &masks_src)
+            .ok()
+            .and_then(|tex_path| extract_playable_proxy_from_tex(&tex_path, &masks_proxy).ok())
+            .flatten()
+        else {
+            origin.reason = format!("could not decode {} into a mask image", tex_entry.filename);
+            trace.push(origin);
+            continue;
+        };
+        origin.emulation = EmulationKind::MaskDrift;
+        origin.reason = format!(
+            "{} motion applied through texture mask",
+            layer_ref.profile.as_str()
+        );
+        trace.push(
+            origin
+                .param("profile", layer_ref.profile.as_str())
+                .param("mask_image", mask_image.to_string_lossy().to_string())
+                .param(
+                    "alpha",
+                    (tuning.layer_alpha * layer_ref.alpha).clamp(0.03, 0.95),
+                )
+                .param("drift_amp_x", tuning.drift_amp_x)
+                .param("drift_amp_y", tuning.drift_amp_y)
+                .param("drift_freq_x", tuning.drift_freq_x)
+                .param("drift_freq_y", tuning.drift_freq_y)
+                .param("center_x", layer_ref.center_x)
+                .param("center_y", layer_ref.center_y)
+                .param("width", layer_ref.width)
+                .param("height", layer_ref.height)
+                .param("angle_rad", layer_ref.angle_rad),
+        );
+        layers.push(EffectLayer {
+            mask_image,
+            profile: layer_ref.profile,
+            alpha: layer_ref.alpha,
+            family: layer_ref.family.clone(),
+            center_x: layer_ref.center_x,
+            center_y: layer_ref.center_y,
+            width: layer_ref.width,
+            height: layer_ref.height,
+            angle_rad: layer_ref.angle_rad,
+        });
+    }
+    layers
+}
+
+fn effect_proxy_trace_notes(layers: &[EffectLayer], tuning: &VisualTuning) -> Vec<String> {
+    let mut notes = vec![format!(
+        "global grade contrast={:.3} saturation={:.3}",
+        tuning.contrast, tuning.saturation
+    )];
+    if layers.is_empty() {
+        notes.push(format!(
+            "no effect masks resolved; procedural drift applied to the whole frame (amp {:.2}x{:.2}, freq {:.2}x{:.2})",
+            tuning.drift_amp_x, tuning.drift_amp_y, tuning.drift_freq_x, tuning.drift_freq_y
+        ));
+    }
+    notes
+}
+
 fn find_tex_entry_for_texture_ref(
     pkg: &crate::scene_pkg::ScenePkg,
     texture_ref: &str,
@@ -333,39 +503,118 @@ fn find_tex_entry_for_texture_ref(
     candidates.into_iter().next()
 }
 
+/// Angular frequencies (rad/s) of the x/y drift `filter_for_profile` applies.
+fn profile_frequencies(profile: MotionProfile, tuning: &VisualTuning) -> (f32, f32) {
+    match profile {
+        MotionProfile::Iris => (2.9, 2.5),
+        MotionProfile::Shake => (
+            (tuning.drift_freq_x * 2.7).clamp(3.0, 14.0),
+            (tuning.drift_freq_y * 2.8).clamp(3.0, 14.0),
+        ),
+        MotionProfile::Pulse => (
+            (tuning.drift_freq_x * 2.0).clamp(1.8, 10.0),
+            (tuning.drift_freq_y * 2.1).clamp(1.8, 10.0),
+        ),
+        MotionProfile::Drift => (tuning.drift_freq_x, tuning.drift_freq_y),
+    }
+}
+
 fn filter_for_profile(
     profile: MotionProfile,
     src: &str,
     out: &str,
     tuning: &VisualTuning,
 ) -> String {
+    let (fx, fy) = profile_frequencies(profile, tuning);
     match profile {
         MotionProfile::Iris => format!(
-            "[{src}]crop=iw-10:ih-10:x='5+sin(t*2.9)*{:.3}':y='5+cos(t*2.5)*{:.3}',pad=iw+10:ih+10:5:5:color=black@0[{out}]",
+            "[{src}]crop=iw-10:ih-10:x='5+sin(t*{:.3})*{:.3}':y='5+cos(t*{:.3})*{:.3}',pad=iw+10:ih+10:5:5:color=black@0[{out}]",
+            fx,
             (tuning.drift_amp_x * 1.75).clamp(4.0, 18.0),
+            fy,
             (tuning.drift_amp_y * 1.60).clamp(3.0, 16.0)
         ),
         MotionProfile::Shake => format!(
             "[{src}]crop=iw-6:ih-6:x='3+sin(t*{:.3})*{:.3}':y='3+cos(t*{:.3})*{:.3}',pad=iw+6:ih+6:3:3:color=black@0[{out}]",
-            (tuning.drift_freq_x * 2.7).clamp(3.0, 14.0),
+            fx,
             (tuning.drift_amp_x * 1.20).clamp(2.0, 10.0),
-            (tuning.drift_freq_y * 2.8).clamp(3.0, 14.0),
+            fy,
             (tuning.drift_amp_y * 1.25).clamp(2.0, 10.0)
         ),
         MotionProfile::Pulse => format!(
             "[{src}]crop=iw-8:ih-8:x='4+sin(t*{:.3})*{:.3}':y='4+cos(t*{:.3})*{:.3}',pad=iw+8:ih+8:4:4:color=black@0[{out}]",
-            (tuning.drift_freq_x * 2.0).clamp(1.8, 10.0),
+            fx,
             (tuning.drift_amp_x * 1.0).clamp(1.0, 8.0),
-            (tuning.drift_freq_y * 2.1).clamp(1.8, 10.0),
+            fy,
             (tuning.drift_amp_y * 1.0).clamp(1.0, 7.0)
         ),
         MotionProfile::Drift => format!(
             "[{src}]crop=iw-8:ih-8:x='4+sin(t*{:.3})*{:.3}':y='4+cos(t*{:.3})*{:.3}',pad=iw+8:ih+8:4:4:color=black@0[{out}]",
-            tuning.drift_freq_x, tuning.drift_amp_x, tuning.drift_freq_y, tuning.drift_amp_y
+            fx, tuning.drift_amp_x, fy, tuning.drift_amp_y
         ),
     }
 }
 
+/// Used when a scene has no oscillating layers to derive a period from.
+const DEFAULT_PROXY_SECONDS: f32 = 20.0;
+const MIN_PROXY_SECONDS: f32 = 6.0;
+const MAX_PROXY_SECONDS: f32 = 60.0;
+
+/// Fraction of a cycle `sin(t*freq)` is away from a whole cycle at `seconds`.
+fn phase_error(freq: f32, seconds: f32) -> f32 {
+    let cycles = freq * seconds / std::f32::consts::TAU;
+    (cycles - cycles.round()).abs()
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Proxy length at which every `sin(t*freq)` drift completes whole cycles,
+/// so the mp4 loops without a visible jump: the LCM of the periods (0.1 s
+/// resolution) when it fits under the cap, else the capped length with the
+/// smallest worst-case phase error.
+pub fn loop_friendly_seconds(angular_freqs: &[f32]) -> f32 {
+    let freqs = angular_freqs
+        .iter()
+        .copied()
+        .filter(|f| f.is_finite() && *f > 0.001)
+        .collect::<Vec<_>>();
+    if freqs.is_empty() {
+        return DEFAULT_PROXY_SECONDS;
+    }
+    let min_ticks = (MIN_PROXY_SECONDS * 10.0) as u64;
+    let max_ticks = (MAX_PROXY_SECONDS * 10.0) as u64;
+
+    let mut lcm = 1u64;
+    for f in &freqs {
+        let ticks = ((std::f32::consts::TAU / f) * 10.0).round().max(1.0) as u64;
+        lcm = lcm / gcd(lcm, ticks) * ticks;
+        if lcm > max_ticks {
+            break;
+        }
+    }
+    if lcm <= max_ticks {
+        let repeats = min_ticks.div_ceil(lcm).max(1);
+        return (lcm * repeats).min(max_ticks) as f32 / 10.0;
+    }
+
+    let worst = |ticks: u64| {
+        freqs
+            .iter()
+            .map(|f| phase_error(*f, ticks as f32 / 10.0))
+            .fold(0.0f32, f32::max)
+    };
+    let best = (min_ticks..=max_ticks)
+        .min_by(|a, b| {
+            worst(*a)
+                .partial_cmp(&worst(*b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or(max_ticks);
+    best as f32 / 10.0
+}
+
 fn parse_vec3(value: &str) -> Option<(f32, f32, f32)> {
     let mut it = value.split_whitespace();
     let x = it.next()?.parse::<f32>().ok()?;
@@ -485,6 +734,7 @@ fn detect_audio_bars_overlay(
         let scene_wf = scene_w.max(1) as f32;
         let scene_hf = scene_h.max(1) as f32;
         return Some(AudioBarsOverlay {
+            object_id: object.get("id").and_then(|v| v.as_u64()).unwrap_or(0),
             center_x,
             center_y,
             width: w.max(16),
@@ -506,39 +756,20 @@ fn detect_audio_bars_overlay(
 fn build_masked_animated_proxy(
     base_image: &Path,
     layers: &[EffectLayer],
-    scene_w: u32,
-    scene_h: u32,
+    (scene_w, scene_h): (u32, u32),
     out: &Path,
+    seconds: f32,
+    output: &ProxyOutput,
     dry_run: bool,
 ) -> Result<PathBuf> {
     if layers.is_empty() {
-        return build_simple_animated_proxy(base_image, out, dry_run);
+        return build_simple_animated_proxy(base_image, out, seconds, output, dry_run);
     }
 
     let tuning = VisualTuning::default();
-    let filter = build_masked_filter(layers, scene_w, scene_h, None, &tuning);
-
-    if dry_run {
-        let mut args = format!(
-            "[dry-run] ffmpeg -hide_banner -loglevel error -y -loop 1 -i '{}'",
-            base_image.display()
-        );
-        for layer in layers {
-            args.push_str(&format!(" -loop 1 -i '{}'", layer.mask_image.display()));
-        }
-        args.push_str(&format!(
-            " -filter_complex \"{}\" -map '[v]' -t 20 -r 60 -c:v libx264 -preset veryfast -crf 20 '{}'",
-            filter,
-            out.display()
-        ));
-        println!("{}", args);
-        return Ok(out.to_path_buf());
-    }
-
-    if let Some(parent) = out.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed creating animated proxy dir {}", parent.display()))?;
-    }
+    let filter = output.apply(build_masked_filter(layers, scene_w, scene_h, None, &tuning));
+    let settings = format!("{} t{}", filter, seconds);
+    let meta = ProxyMeta::new("scene-masked", base_image, &settings);
 
     let mut cmd = Command::new("ffmpeg");
     cmd.arg("-hide_banner")
@@ -552,13 +783,12 @@ fn build_masked_animated_proxy(
     for layer in layers {
         cmd.arg("-loop").arg("1").arg("-i").arg(&layer.mask_image);
     }
-    let output = cmd
-        .arg("-filter_complex")
+    cmd.arg("-filter_complex")
         .arg(filter)
         .arg("-map")
         .arg("[v]")
         .arg("-t")
-        .arg("20")
+        .arg(seconds.to_string())
         .arg("-r")
         .arg("60")
         .arg("-c:v")
@@ -567,8 +797,23 @@ fn build_masked_animated_proxy(
         .arg("veryfast")
         .arg("-crf")
         .arg("20")
-        .arg(out)
-        .output()
+        .args(SRGB_BT709_TAGS)
+        .args(meta.ffmpeg_args())
+        .arg(out);
+
+    ensure_outside_library(out, "animated proxy")?;
+    if dry_run {
+        eprintln!("[dry-run] {}", shell_command_line(&cmd));
+        return Ok(out.to_path_buf());
+    }
+
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating animated proxy dir {}", parent.display()))?;
+    }
+
+    let output = cmd
+        .run_output()
         .context("Failed running ffmpeg for masked animated proxy")?;
 
     if !output.status.success() {
@@ -717,12 +962,12 @@ fn build_masked_filter(
             )),
         }
         filter.push_str(&format!(
-            "[cv]eq=contrast={:.3}:saturation={:.3},format=yuv420p[v]",
+            "[cv]eq=contrast={:.3}:saturation={:.3},{YUV420P_BT709}[v]",
             tuning.contrast, tuning.saturation
         ));
     } else {
         filter.push_str(&format!(
-            "[{}]eq=contrast={:.3}:saturation={:.3},format=yuv420p[v]",
+            "[{}]eq=contrast={:.3}:saturation={:.3},{YUV420P_BT709}[v]",
             final_comp, tuning.contrast, tuning.saturation
         ));
     }
@@ -772,13 +1017,13 @@ fn build_simple_filter(
             _ => f.push_str("[base][abp]overlay=0:0:format=auto[mix];"),
         }
         f.push_str(&format!(
-            " [mix]eq=contrast={:.3}:saturation={:.3},format=yuv420p[v]",
+            " [mix]eq=contrast={:.3}:saturation={:.3},{YUV420P_BT709}[v]",
             tuning.contrast, tuning.saturation
         ));
         return f;
     }
     format!(
-        "[0:v]crop=iw-8:ih-8:x='4+sin(t*{:.3})*{:.3}':y='4+cos(t*{:.3})*{:.3}',pad=iw+8:ih+8:4:4:color=black,eq=contrast={:.3}:saturation={:.3},format=yuv420p[v]",
+        "[0:v]crop=iw-8:ih-8:x='4+sin(t*{:.3})*{:.3}':y='4+cos(t*{:.3})*{:.3}',pad=iw+8:ih+8:4:4:color=black,eq=contrast={:.3}:saturation={:.3},{YUV420P_BT709}[v]",
         tuning.drift_freq_x,
         tuning.drift_amp_x,
         tuning.drift_freq_y,
@@ -792,11 +1037,119 @@ pub fn build_scene_realtime_effect_plan(
     root: &Path,
     session_dir: &Path,
     entry: &Path,
+    custom_filters: Option<&CustomFilters>,
 ) -> Result<Option<RealtimeEffectPlan>> {
     if !is_image_like(entry) {
         return Ok(None);
     }
+    let Some(mut plan) = build_realtime_plan_inner(root, session_dir, entry, |_| {})? else {
+        return Ok(None);
+    };
+    if let Some(custom) =
+        resolve_custom_filters(root, custom_filters, plan.scene_width, plan.scene_height)
+    {
+        plan.filter_complex = with_post_filter(
+            with_pre_filter(plan.filter_complex, custom.pre.as_deref()),
+            custom.post.as_deref(),
+        );
+    }
+    Ok(Some(plan))
+}
+
+/// Parses a `--set` value like `g_FlowSpeed=2` or `g_Color1=1 0 0`. Numbers
+/// and booleans stay typed; anything else is kept as the string WE uses.
+pub fn parse_uniform_assignment(raw: &str) -> std::result::Result<(String, Value), String> {
+    let Some((name, value)) = raw.split_once('=') else {
+        return Err(format!("expected UNIFORM=VALUE, got '{}'", raw));
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing uniform name in '{}'", raw));
+    }
+    let value = value.trim();
+    let value = if let Ok(b) = value.parse::<bool>() {
+        Value::Bool(b)
+    } else if let Some(n) = value
+        .parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+    {
+        Value::Number(n)
+    } else {
+        Value::String(value.to_string())
+    };
+    Ok((name.to_string(), value))
+}
+
+/// Overrides `uniforms` on every pass that declares them, or on every pass
+/// when none does. Returns how many passes each uniform reached.
+fn inject_uniforms(
+    graph: &mut SceneGpuGraph,
+    uniforms: &BTreeMap<String, Value>,
+) -> BTreeMap<String, usize> {
+    let mut touched = BTreeMap::new();
+    for (name, value) in uniforms {
+        let declares = |pass: &GpuPassSpec| pass.effective_uniforms.contains_key(name);
+        let declared = graph
+            .effect_nodes
+            .iter()
+            .any(|n| n.passes.iter().any(declares));
+        let mut count = 0usize;
+        for pass in graph.effect_nodes.iter_mut().flat_map(|n| &mut n.passes) {
+            if !declared || declares(pass) {
+                pass.effective_uniforms.insert(name.clone(), value.clone());
+                count += 1;
+            }
+        }
+        touched.insert(name.clone(), count);
+    }
+    touched
+}
 
+#[derive(Debug, Clone, Serialize)]
+pub struct UniformEvalReport {
+    pub wallpaper_root: String,
+    /// Passes each injected uniform was written to.
+    pub injected: BTreeMap<String, usize>,
+    /// Extra ffmpeg inputs (effect masks) after the base frame `[0:v]`.
+    pub mask_inputs: Vec<String>,
+    pub scene_width: u32,
+    pub scene_height: u32,
+    pub filter_complex: String,
+}
+
+/// Rebuilds only the realtime effect plan of a scene with `uniforms`
+/// injected into the GPU graph. Scratch files go to `session_dir`, never
+/// to a playback session.
+pub fn eval_realtime_plan_uniforms(
+    root: &Path,
+    session_dir: &Path,
+    uniforms: &BTreeMap<String, Value>,
+) -> Result<UniformEvalReport> {
+    let mut injected = BTreeMap::new();
+    let plan = build_realtime_plan_inner(root, session_dir, Path::new("base"), |graph| {
+        injected = inject_uniforms(graph, uniforms);
+    })?
+    .with_context(|| format!("{} has no scene.pkg/scene.json", root.display()))?;
+    Ok(UniformEvalReport {
+        wallpaper_root: root.display().to_string(),
+        injected,
+        mask_inputs: plan.inputs[1..]
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect(),
+        scene_width: plan.scene_width,
+        scene_height: plan.scene_height,
+        filter_complex: plan.filter_complex,
+    })
+}
+
+fn build_realtime_plan_inner(
+    root: &Path,
+    session_dir: &Path,
+    entry: &Path,
+    mut adjust_graph: impl FnMut(&mut SceneGpuGraph),
+) -> Result<Option<RealtimeEffectPlan>> {
     let Some(pkg_path) = pick_pkg_path(root) else {
         return Ok(None);
     };
@@ -807,49 +1160,38 @@ pub fn build_scene_realtime_effect_plan(
     };
     let scene_json: Value = serde_json::from_slice(&read_entry_bytes(&pkg, &scene_entry)?)?;
     let (scene_w, scene_h) = parse_scene_size(&scene_json);
-    let audio_bars = detect_audio_bars_overlay(&scene_json, scene_w, scene_h);
-    let graph = build_scene_gpu_graph(root).ok();
+    let audio_bars = detect_audio_bars_overlay(&scene_json, scene_w, scene_h)
+        .map(|bars| apply_audio_bars_calibration(root, bars));
+    let mut graph = build_scene_gpu_graph(root).ok();
+    if let Some(graph) = graph.as_mut() {
+        adjust_graph(graph);
+    }
     let tuning = graph
         .as_ref()
         .map(visual_tuning_from_graph)
         .unwrap_or_default();
 
-    let masks_src = session_dir.join("effect-proxy/masks-src");
-    let masks_proxy = session_dir.join("effect-proxy/masks-proxy");
-
-    let mut layers = Vec::<EffectLayer>::new();
+    let pruned = graph
+        .as_ref()
+        .map(|g| g.pruned.as_slice())
+        .unwrap_or_default();
+    let mut trace = Vec::<EmulationTraceEntry>::new();
     let layer_refs = graph
         .as_ref()
-        .map(|g| collect_effect_layer_refs_from_native_plan(g, 24))
+        .map(|g| collect_effect_layer_refs_from_native_plan(g, 24, &mut trace))
         .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| collect_effect_layer_refs(&scene_json, 24));
-
-    for layer_ref in layer_refs {
-        let Some(tex_entry) = find_tex_entry_for_texture_ref(&pkg, &layer_ref.texture_ref) else {
-            continue;
-        };
-        let Some(mask_image) = extract_entry_to_cache(&pkg, &tex_entry, &masks_src)
-            .ok()
-            .and_then(|tex_path| extract_playable_proxy_from_tex(&tex_path, &masks_proxy).ok())
-            .flatten()
-        else {
-            continue;
-        };
-        layers.push(EffectLayer {
-            mask_image,
-            profile: layer_ref.profile,
-            alpha: layer_ref.alpha,
-            family: layer_ref.family.clone(),
-            center_x: layer_ref.center_x,
-            center_y: layer_ref.center_y,
-            width: layer_ref.width,
-            height: layer_ref.height,
-            angle_rad: layer_ref.angle_rad,
+        .unwrap_or_else(|| {
+            trace.clear();
+            collect_effect_layer_refs(&scene_json, pruned, 24, &mut trace)
         });
-        if layers.len() >= EFFECT_LAYER_LIMIT {
-            break;
-        }
-    }
+    trace.extend(pruned.iter().map(EmulationTraceEntry::from_pruned));
+    let layers = resolve_effect_layers(&pkg, layer_refs, session_dir, &tuning, &mut trace);
+    record_emulation_stage(
+        session_dir,
+        "effect-proxy-realtime",
+        trace,
+        effect_proxy_trace_notes(&layers, &tuning),
+    )?;
 
     let mut inputs = vec![entry.to_path_buf()];
     let filter_complex = if layers.is_empty() {
@@ -878,7 +1220,13 @@ pub fn build_scene_realtime_effect_plan(
     }))
 }
 
+/// Audio bars rect of the scene, with the saved calibration applied.
 pub fn build_scene_audio_bars_overlay(root: &Path) -> Result<Option<AudioBarsOverlay>> {
+    Ok(detect_scene_audio_bars_overlay(root)?.map(|bars| apply_audio_bars_calibration(root, bars)))
+}
+
+/// Audio bars rect as the scene.json heuristics place it, uncalibrated.
+pub fn detect_scene_audio_bars_overlay(root: &Path) -> Result<Option<AudioBarsOverlay>> {
     let Some(pkg_path) = pick_pkg_path(root) else {
         return Ok(None);
     };
@@ -892,30 +1240,107 @@ pub fn build_scene_audio_bars_overlay(root: &Path) -> Result<Option<AudioBarsOve
     Ok(detect_audio_bars_overlay(&scene_json, scene_w, scene_h))
 }
 
-fn build_simple_animated_proxy(base_image: &Path, out: &Path, dry_run: bool) -> Result<PathBuf> {
-    let filter = concat!(
-        "[0:v]crop=iw-8:ih-8:x='4+sin(t*1.7)*3':y='4+cos(t*1.4)*2',",
-        "pad=iw+8:ih+8:4:4:color=black,",
-        "eq=contrast=1.01:saturation=1.02,format=yuv420p[v]"
-    );
+/// Drift frequencies of the procedural fallback filter below.
+const SIMPLE_PROXY_FREQS: [f32; 2] = [1.7, 1.4];
 
-    if dry_run {
-        println!(
-            "[dry-run] ffmpeg -hide_banner -loglevel error -y -loop 1 -i '{}' -filter_complex \"{}\" -map '[v]' -t 20 -r 60 -c:v libx264 -preset veryfast -crf 21 '{}'",
-            base_image.display(),
-            filter,
-            out.display()
-        );
-        return Ok(out.to_path_buf());
+/// Post-processing of the proxy `[v]` output: region blurs for blur effects,
+/// then the crop to the output canvas.
+struct ProxyOutput<'a> {
+    blurs: &'a [RegionBlur],
+    canvas: Option<&'a CanvasCrop>,
+    /// Already expanded for the scene size.
+    custom: Option<&'a CustomFilters>,
+}
+
+impl ProxyOutput<'_> {
+    fn apply(&self, filter: String) -> String {
+        let pre = self.custom.and_then(|c| c.pre.as_deref());
+        let post = self.custom.and_then(|c| c.post.as_deref());
+        let filter = with_region_blurs(with_pre_filter(filter, pre), self.blurs);
+        with_output_canvas(with_post_filter(filter, post), self.canvas)
     }
+}
 
-    if let Some(parent) = out.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed creating animated proxy dir {}", parent.display()))?;
+/// `cli` when given, else the wallpaper's `custom_filters` override,
+/// expanded for the scene size. An invalid chain is dropped with a warning
+/// so a typo never blocks playback.
+fn resolve_custom_filters(
+    root: &Path,
+    cli: Option<&CustomFilters>,
+    scene_w: u32,
+    scene_h: u32,
+) -> Option<CustomFilters> {
+    let filters = match cli {
+        Some(filters) => filters.clone(),
+        None => load_layer_override(root)?.custom_filters,
+    };
+    if filters.is_empty() {
+        return None;
+    }
+    match filters.expanded(scene_w, scene_h) {
+        Ok(expanded) => {
+            eprintln!(
+                "[ok] custom filters: pre={} post={}",
+                expanded.pre.as_deref().unwrap_or("-"),
+                expanded.post.as_deref().unwrap_or("-")
+            );
+            Some(expanded)
+        }
+        Err(err) => {
+            warn_msg!(Msg::CustomFiltersIgnored, err);
+            None
+        }
+    }
+}
+
+/// Re-targets the `[v]` output of a proxy filter graph through region blurs.
+fn with_region_blurs(filter: String, blurs: &[RegionBlur]) -> String {
+    let Some(head) = filter.strip_suffix("[v]").filter(|_| !blurs.is_empty()) else {
+        return filter;
+    };
+    let mut out = format!("{}[rbin0];", head);
+    for (i, blur) in blurs.iter().enumerate() {
+        let next = if i + 1 == blurs.len() {
+            "rbout".to_string()
+        } else {
+            format!("rbin{}", i + 1)
+        };
+        out.push_str(&blur.ffmpeg_filter(&format!("rbin{}", i), &next, &format!("rb{}", i)));
+    }
+    out.push_str(&format!("[rbout]{}[v]", YUV420P_BT709));
+    out
+}
+
+/// Re-targets the `[v]` output of a proxy filter graph to the output canvas.
+fn with_output_canvas(filter: String, canvas: Option<&CanvasCrop>) -> String {
+    match (canvas, filter.strip_suffix("[v]")) {
+        (Some(canvas), Some(head)) => {
+            format!("{}[vc];[vc]{},setsar=1[v]", head, canvas.ffmpeg_filter())
+        }
+        _ => filter,
     }
+}
 
-    let output = Command::new("ffmpeg")
-        .arg("-hide_banner")
+fn build_simple_animated_proxy(
+    base_image: &Path,
+    out: &Path,
+    seconds: f32,
+    output: &ProxyOutput,
+    dry_run: bool,
+) -> Result<PathBuf> {
+    let filter = output.apply(format!(
+        concat!(
+            "[0:v]crop=iw-8:ih-8:x='4+sin(t*1.7)*3':y='4+cos(t*1.4)*2',",
+            "pad=iw+8:ih+8:4:4:color=black,",
+            "eq=contrast=1.01:saturation=1.02,{}[v]"
+        ),
+        YUV420P_BT709
+    ));
+
+    let settings = format!("{} t{}", filter, seconds);
+    let meta = ProxyMeta::new("scene-simple", base_image, &settings);
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-hide_banner")
         .arg("-loglevel")
         .arg("error")
         .arg("-y")
@@ -924,11 +1349,11 @@ fn build_simple_animated_proxy(base_image: &Path, out: &Path, dry_run: bool) ->
         .arg("-i")
         .arg(base_image)
         .arg("-filter_complex")
-        .arg(filter)
+        .arg(&filter)
         .arg("-map")
         .arg("[v]")
         .arg("-t")
-        .arg("20")
+        .arg(seconds.to_string())
         .arg("-r")
         .arg("60")
         .arg("-c:v")
@@ -937,8 +1362,23 @@ fn build_simple_animated_proxy(base_image: &Path, out: &Path, dry_run: bool) ->
         .arg("veryfast")
         .arg("-crf")
         .arg("21")
-        .arg(out)
-        .output()
+        .args(SRGB_BT709_TAGS)
+        .args(meta.ffmpeg_args())
+        .arg(out);
+
+    ensure_outside_library(out, "animated proxy")?;
+    if dry_run {
+        eprintln!("[dry-run] {}", shell_command_line(&cmd));
+        return Ok(out.to_path_buf());
+    }
+
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating animated proxy dir {}", parent.display()))?;
+    }
+
+    let output = cmd
+        .run_output()
         .context("Failed running ffmpeg for simple animated proxy")?;
 
     if !output.status.success() {
@@ -953,6 +1393,9 @@ pub fn maybe_build_scene_animated_proxy(
     root: &Path,
     session_dir: &Path,
     entry: &Path,
+    proxy_seconds: Option<f32>,
+    portrait: bool,
+    custom_filters: Option<&CustomFilters>,
     dry_run: bool,
 ) -> Result<Option<PathBuf>> {
     if !is_image_like(entry) {
@@ -981,58 +1424,91 @@ pub fn maybe_build_scene_animated_proxy(
         .and_then(|v| v.as_u64())
         .unwrap_or(1080) as u32;
 
-    let masks_src = session_dir.join("effect-proxy/masks-src");
-    let masks_proxy = session_dir.join("effect-proxy/masks-proxy");
     let out_proxy = session_dir.join("effect-proxy/scene_animated_proxy.mp4");
 
-    let mut layers = Vec::<EffectLayer>::new();
-    for layer_ref in collect_effect_layer_refs(&scene_json, 24) {
-        let Some(tex_entry) = find_tex_entry_for_texture_ref(&pkg, &layer_ref.texture_ref) else {
-            continue;
-        };
-        let Some(mask_image) = extract_entry_to_cache(&pkg, &tex_entry, &masks_src)
-            .ok()
-            .and_then(|tex_path| extract_playable_proxy_from_tex(&tex_path, &masks_proxy).ok())
-            .flatten()
-        else {
-            continue;
-        };
-        layers.push(EffectLayer {
-            mask_image,
-            profile: layer_ref.profile,
-            alpha: layer_ref.alpha,
-            family: layer_ref.family.clone(),
-            center_x: layer_ref.center_x,
-            center_y: layer_ref.center_y,
-            width: layer_ref.width,
-            height: layer_ref.height,
-            angle_rad: layer_ref.angle_rad,
-        });
-        if layers.len() >= EFFECT_LAYER_LIMIT {
-            break;
-        }
-    }
+    // The animated proxy always renders with the default tuning.
+    let tuning = VisualTuning::default();
+    let graph = build_scene_gpu_graph(root).ok();
+    let pruned = graph
+        .as_ref()
+        .map(|g| g.pruned.as_slice())
+        .unwrap_or_default();
+    let mut trace = pruned
+        .iter()
+        .map(EmulationTraceEntry::from_pruned)
+        .collect::<Vec<_>>();
+    let layer_refs = collect_effect_layer_refs(&scene_json, pruned, 24, &mut trace);
+    let layers = resolve_effect_layers(&pkg, layer_refs, session_dir, &tuning, &mut trace);
+    record_emulation_stage(
+        session_dir,
+        "effect-proxy-animated",
+        trace,
+        effect_proxy_trace_notes(&layers, &tuning),
+    )?;
 
     if layers.is_empty() {
-        eprintln!("[warn] scene effect proxy using procedural fallback (no effect masks)");
+        warn_msg!(Msg::ProceduralProxyFallback);
     } else {
         eprintln!(
             "[ok] scene effect proxy using {} effect mask layer(s)",
             layers.len()
         );
     }
-    let built = build_masked_animated_proxy(entry, &layers, scene_w, scene_h, &out_proxy, dry_run)?;
+    let seconds = match proxy_seconds {
+        Some(seconds) => seconds.clamp(1.0, 600.0),
+        None if layers.is_empty() => loop_friendly_seconds(&SIMPLE_PROXY_FREQS),
+        None => loop_friendly_seconds(
+            &layers
+                .iter()
+                .flat_map(|l| {
+                    let (fx, fy) = profile_frequencies(l.profile, &tuning);
+                    [fx, fy]
+                })
+                .collect::<Vec<_>>(),
+        ),
+    };
+    eprintln!("[ok] scene effect proxy duration: {:.1}s", seconds);
+    let canvas = if portrait {
+        portrait_canvas(scene_w, scene_h)
+    } else {
+        None
+    };
+    let blurs = graph
+        .map(|graph| region_blurs(&build_native_runtime_plan(&graph), scene_w, scene_h))
+        .unwrap_or_default();
+    if !blurs.is_empty() {
+        eprintln!(
+            "[ok] scene effect proxy approximating {} blur region(s)",
+            blurs.len()
+        );
+    }
+    let custom = resolve_custom_filters(root, custom_filters, scene_w, scene_h);
+    let output = ProxyOutput {
+        blurs: &blurs,
+        canvas: canvas.as_ref(),
+        custom: custom.as_ref(),
+    };
+    let built = build_masked_animated_proxy(
+        entry,
+        &layers,
+        (scene_w, scene_h),
+        &out_proxy,
+        seconds,
+        &output,
+        dry_run,
+    )?;
     Ok(Some(built))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::scene_gpu_graph::{GpuEffectNode, GpuPassSpec, SceneGpuGraph};
+    use crate::scene_gpu_graph::{GpuEffectNode, GraphDetail};
     use serde_json::json;
 
     fn base_graph_with_uniforms() -> SceneGpuGraph {
         SceneGpuGraph {
+            detail: GraphDetail::Full,
             pkg_path: String::new(),
             scene_json_entry: "scene.json".to_string(),
             scene_width: 1920,
@@ -1041,6 +1517,12 @@ mod tests {
             user_properties: Value::Null,
             script_properties: Value::Null,
             script_assignments: Vec::new(),
+            viewports: Vec::new(),
+            groups: Vec::new(),
+            clock_hands: Vec::new(),
+            script_animations: Vec::new(),
+            pruned: Vec::new(),
+            sounds: Vec::new(),
             effect_nodes: vec![GpuEffectNode {
                 object_index: 0,
                 object_id: 1,
@@ -1054,6 +1536,7 @@ mod tests {
                 object_asset_size: Some([1920.0, 1080.0]),
                 object_parallax_depth: Some([1.0, 1.0]),
                 object_visible: true,
+                object_alpha: None,
                 effect_index: None,
                 instance_override: Value::Null,
                 effect_file: "materials/a.json".to_string(),
@@ -1074,6 +1557,7 @@ mod tests {
                     user_shader_values: Value::Null,
                     textures: vec!["materials/mask_a.tex".to_string()],
                     texture_refs: vec!["mask_a".to_string()],
+                    texture_wrap: None,
                     effective_uniforms: [
                         ("g_ScrollX".to_string(), json!(0.5)),
                         ("g_ScrollY".to_string(), json!(0.25)),
@@ -1083,11 +1567,15 @@ mod tests {
                     ]
                     .into_iter()
                     .collect(),
+                    target: None,
+                    binds: Vec::new(),
+                    audio_uniforms: Vec::new(),
                 }],
                 shader_vert: None,
                 shader_frag: None,
                 material_json: Some("materials/a.json".to_string()),
                 uniform_bindings: Vec::new(),
+                render_targets: Vec::new(),
             }],
             notes: Vec::new(),
         }
@@ -1104,13 +1592,85 @@ mod tests {
         assert!(tuning.layer_alpha < EFFECT_LAYER_ALPHA);
     }
 
+    #[test]
+    fn injected_uniforms_change_tuning() {
+        assert_eq!(
+            parse_uniform_assignment("g_ScrollX=3").unwrap(),
+            ("g_ScrollX".to_string(), json!(3.0))
+        );
+        assert_eq!(
+            parse_uniform_assignment("g_Color1=1 0 0").unwrap().1,
+            json!("1 0 0")
+        );
+        assert!(parse_uniform_assignment("g_ScrollX").is_err());
+
+        let mut graph = base_graph_with_uniforms();
+        let before = visual_tuning_from_graph(&graph).drift_amp_x;
+        let uniforms = BTreeMap::from([
+            ("g_ScrollX".to_string(), json!(3.0)),
+            ("g_FlowSpeed".to_string(), json!(2.0)),
+        ]);
+        let touched = inject_uniforms(&mut graph, &uniforms);
+        assert_eq!(touched["g_ScrollX"], 1);
+        // Undeclared uniforms still reach every pass for experiments.
+        assert_eq!(touched["g_FlowSpeed"], 1);
+        assert!(visual_tuning_from_graph(&graph).drift_amp_x > before);
+    }
+
     #[test]
     fn layer_refs_can_come_from_graph() {
         let graph = base_graph_with_uniforms();
-        let refs = collect_effect_layer_refs_from_native_plan(&graph, 8);
+        let mut trace = Vec::new();
+        let refs = collect_effect_layer_refs_from_native_plan(&graph, 8, &mut trace);
         assert_eq!(refs.len(), 1);
+        assert!(trace.is_empty());
         assert_eq!(refs[0].texture_ref, "materials/mask_a.tex");
         assert_eq!(refs[0].family, "genericimage");
         assert!((refs[0].alpha - 0.4).abs() < 0.0001);
     }
+
+    #[test]
+    fn blur_effects_become_region_blurs() {
+        let mut graph = base_graph_with_uniforms();
+        let node = &mut graph.effect_nodes[0];
+        node.effect_index = Some(0);
+        node.effect_file = "effects/blur/effect.json".to_string();
+        node.passes[0]
+            .effective_uniforms
+            .insert("g_BlurScale".to_string(), json!("2 1"));
+        let plan = build_native_runtime_plan(&graph);
+        assert!(matches!(
+            plan.passes[0].tier,
+            NativeSupportTier::ExperimentalReady
+        ));
+
+        let blurs = region_blurs(&plan, 1920, 1080);
+        assert_eq!(blurs.len(), 1);
+        assert!((blurs[0].sigma - 8.0).abs() < 0.001);
+        assert!((blurs[0].w - 1.0).abs() < 0.001);
+
+        let filter = with_region_blurs("[0:v]format=yuv420p[v]".to_string(), &blurs);
+        assert!(filter.contains("gblur=sigma=8.00"));
+        assert!(filter.ends_with(&format!("[rbout]{}[v]", YUV420P_BT709)));
+    }
+
+    #[test]
+    fn proxy_length_covers_whole_drift_cycles() {
+        use std::f32::consts::TAU;
+        // Periods of 2 s and 3 s: LCM 6 s.
+        assert_eq!(loop_friendly_seconds(&[TAU / 2.0, TAU / 3.0]), 6.0);
+        // A single 2.5 s period repeats up to the minimum length.
+        assert_eq!(loop_friendly_seconds(&[TAU / 2.5]), 7.5);
+        assert_eq!(loop_friendly_seconds(&[]), DEFAULT_PROXY_SECONDS);
+
+        // 1.7/1.4 rad/s have no short common period; the pick stays capped
+        // and ends both drifts close to a whole cycle.
+        let seconds = loop_friendly_seconds(&SIMPLE_PROXY_FREQS);
+        assert!((MIN_PROXY_SECONDS..=MAX_PROXY_SECONDS).contains(&seconds));
+        assert!(
+            SIMPLE_PROXY_FREQS
+                .iter()
+                .all(|f| phase_error(*f, seconds) < 0.05)
+        );
+    }
 }
@@ -0,0 +1,270 @@
+use crate::scene_gpu_graph::PrunedObject;
+use crate::scene_native_runtime::{NativePassSupport, NativeSupportTier};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const TRACE_FILE: &str = "emulation-trace.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmulationKind {
+    MaskDrift,
+    StaticQuad,
+    AnimatedQuad,
+    RegionBlur,
+    ClockHand,
+    Dropped,
+}
+
+impl EmulationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EmulationKind::MaskDrift => "mask-drift",
+            EmulationKind::StaticQuad => "static-quad",
+            EmulationKind::AnimatedQuad => "animated-quad",
+            EmulationKind::RegionBlur => "region-blur",
+            EmulationKind::ClockHand => "clock-hand",
+            EmulationKind::Dropped => "dropped",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmulationTraceEntry {
+    pub stage: String,
+    pub object_index: Option<usize>,
+    pub object_id: Option<u64>,
+    pub object_name: Option<String>,
+    pub pass_index: Option<usize>,
+    pub shader: Option<String>,
+    pub shader_family: Option<String>,
+    pub texture_ref: Option<String>,
+    pub tier: Option<NativeSupportTier>,
+    pub emulation: EmulationKind,
+    pub reason: String,
+    pub parameters: BTreeMap<String, Value>,
+}
+
+impl EmulationTraceEntry {
+    pub fn new(emulation: EmulationKind, reason: impl Into<String>) -> Self {
+        Self {
+            stage: String::new(),
+            object_index: None,
+            object_id: None,
+            object_name: None,
+            pass_index: None,
+            shader: None,
+            shader_family: None,
+            texture_ref: None,
+            tier: None,
+            emulation,
+            reason: reason.into(),
+            parameters: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_pass(
+        pass: &NativePassSupport,
+        emulation: EmulationKind,
+        reason: impl Into<String>,
+    ) -> Self {
+        let mut entry = Self::new(emulation, reason);
+        entry.object_index = Some(pass.object_index);
+        entry.object_id = Some(pass.object_id);
+        entry.object_name = Some(pass.object_name.clone());
+        entry.pass_index = Some(pass.pass_index);
+        entry.shader = Some(pass.pass_shader.clone());
+        entry.shader_family = Some(pass.shader_family.clone());
+        entry.texture_ref = pass.primary_texture.clone();
+        entry.tier = Some(pass.tier.clone());
+        entry
+    }
+
+    /// Entry for an object the graph pruned before any pass was planned.
+    pub fn from_pruned(pruned: &PrunedObject) -> Self {
+        let mut entry = Self::new(EmulationKind::Dropped, pruned.reason.clone());
+        entry.object_index = Some(pruned.object_index);
+        entry.object_id = Some(pruned.object_id);
+        entry.object_name = Some(pruned.object_name.clone());
+        entry.param("pass_nodes", pruned.pass_nodes)
+    }
+
+    pub fn param(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.parameters.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmulationTrace {
+    pub version: u32,
+    pub entries: Vec<EmulationTraceEntry>,
+    pub notes: Vec<String>,
+}
+
+pub fn emulation_trace_path(session_dir: &Path) -> PathBuf {
+    session_dir.join(TRACE_FILE)
+}
+
+pub fn load_emulation_trace(session_dir: &Path) -> Result<Option<EmulationTrace>> {
+    let path = emulation_trace_path(session_dir);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let raw = std::fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    let trace = serde_json::from_slice(&raw)
+        .with_context(|| format!("Failed parsing {}", path.display()))?;
+    Ok(Some(trace))
+}
+
+fn write_emulation_trace(session_dir: &Path, trace: &EmulationTrace) -> Result<()> {
+    std::fs::create_dir_all(session_dir)
+        .with_context(|| format!("Failed creating {}", session_dir.display()))?;
+    let path = emulation_trace_path(session_dir);
+    std::fs::write(&path, serde_json::to_vec_pretty(trace)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// Drops any trace left over from a previous run of the same session dir.
+pub fn reset_emulation_trace(session_dir: &Path) -> Result<()> {
+    let path = emulation_trace_path(session_dir);
+    if path.is_file() {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed removing {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Replaces every entry previously recorded for `stage` with `entries`
+/// (their `stage` field is overwritten).
+pub fn record_emulation_stage(
+    session_dir: &Path,
+    stage: &str,
+    entries: Vec<EmulationTraceEntry>,
+    notes: Vec<String>,
+) -> Result<()> {
+    let mut trace = load_emulation_trace(session_dir)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    trace.version = 1;
+    trace.entries.retain(|e| e.stage != stage);
+    trace.entries.extend(entries.into_iter().map(|mut e| {
+        e.stage = stage.to_string();
+        e
+    }));
+    let prefix = format!("{stage}: ");
+    trace.notes.retain(|n| !n.starts_with(&prefix));
+    trace
+        .notes
+        .extend(notes.into_iter().map(|n| format!("{prefix}{n}")));
+    write_emulation_trace(session_dir, &trace)
+}
+
+pub fn summarize_emulation_trace(trace: &EmulationTrace) -> Vec<String> {
+    let mut counts = BTreeMap::<(String, &'static str), usize>::new();
+    for entry in &trace.entries {
+        *counts
+            .entry((entry.stage.clone(), entry.emulation.as_str()))
+            .or_default() += 1;
+    }
+    let mut lines = counts
+        .into_iter()
+        .map(|((stage, kind), n)| format!("{stage}: {n} pass(es) -> {kind}"))
+        .collect::<Vec<_>>();
+    for entry in &trace.entries {
+        let tier = entry
+            .tier
+            .as_ref()
+            .map(|t| format!("{t:?}"))
+            .unwrap_or_else(|| "-".to_string());
+        lines.push(format!(
+            "  [{}] obj={} '{}' pass={} shader={} tier={} -> {} ({})",
+            entry.stage,
+            entry
+                .object_id
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            entry.object_name.as_deref().unwrap_or("-"),
+            entry
+                .pass_index
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            entry.shader.as_deref().unwrap_or("-"),
+            tier,
+            entry.emulation.as_str(),
+            entry.reason
+        ));
+    }
+    lines.extend(trace.notes.iter().map(|n| format!("  note: {n}")));
+    lines
+}
+
+pub fn print_emulation_trace_summary(session_dir: &Path) -> Result<()> {
+    let path = emulation_trace_path(session_dir);
+    let Some(trace) = load_emulation_trace(session_dir)? else {
+        eprintln!("[explain] no emulation trace recorded ({})", path.display());
+        return Ok(());
+    };
+    eprintln!("[explain] emulation trace: {}", path.display());
+    for line in summarize_emulation_trace(&trace) {
+        eprintln!("[explain] {line}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_a_stage_replaces_only_that_stage() {
+        let dir = tempfile::tempdir().unwrap();
+        record_emulation_stage(
+            dir.path(),
+            "effect-proxy",
+            vec![EmulationTraceEntry::new(
+                EmulationKind::Dropped,
+                "texture not found in pkg",
+            )],
+            vec!["first".to_string()],
+        )
+        .unwrap();
+        record_emulation_stage(
+            dir.path(),
+            "native-static",
+            vec![EmulationTraceEntry::new(EmulationKind::StaticQuad, "loaded").param("alpha", 0.5)],
+            Vec::new(),
+        )
+        .unwrap();
+        record_emulation_stage(
+            dir.path(),
+            "effect-proxy",
+            vec![EmulationTraceEntry::new(
+                EmulationKind::MaskDrift,
+                "mask composited",
+            )],
+            vec!["second".to_string()],
+        )
+        .unwrap();
+
+        let trace = load_emulation_trace(dir.path()).unwrap().unwrap();
+        assert_eq!(trace.entries.len(), 2);
+        assert!(
+            trace
+                .entries
+                .iter()
+                .any(|e| e.stage == "effect-proxy" && e.emulation == EmulationKind::MaskDrift)
+        );
+        assert_eq!(trace.notes, vec!["effect-proxy: second".to_string()]);
+        let summary = summarize_emulation_trace(&trace);
+        assert!(
+            summary
+                .iter()
+                .any(|l| l.contains("native-static: 1 pass(es) -> static-quad"))
+        );
+    }
+}
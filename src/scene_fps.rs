@@ -0,0 +1,201 @@
+use crate::app_paths::user_config_path;
+use crate::command_runner::CommandExt;
+use crate::scene_gpu_graph::SceneGpuGraph;
+use crate::startup_config::load_config;
+use serde::Serialize;
+use serde_json::Value;
+use std::process::Command;
+
+pub const STATIC_SCENE_FPS: u32 = 10;
+pub const DRIFT_SCENE_FPS: u32 = 30;
+/// Used for heavy scenes when the monitor refresh rate cannot be queried.
+pub const DEFAULT_REFRESH_FPS: u32 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SceneMotionClass {
+    /// Plain image layers: nothing changes between frames.
+    Static,
+    /// Effects that only translate/fade layers slowly.
+    DriftOnly,
+    /// Particles, video textures or audio-reactive layers.
+    Heavy,
+}
+
+fn is_video_ref(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    [".mp4", ".webm", ".mkv", ".mov", ".gif"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
+}
+
+pub fn classify_scene_motion(graph: &SceneGpuGraph, primary_is_video: bool) -> SceneMotionClass {
+    let nodes = graph.effect_nodes.iter().filter(|n| n.object_visible);
+    let mut drift = !graph.script_assignments.is_empty();
+    for node in nodes {
+        let effect = node.effect_file.to_ascii_lowercase();
+        let heavy = node.object_kind.eq_ignore_ascii_case("particle")
+            || effect.contains("audio")
+            || node
+                .passes
+                .iter()
+                .flat_map(|p| p.textures.iter())
+                .any(|t| is_video_ref(t));
+        if heavy {
+            return SceneMotionClass::Heavy;
+        }
+        drift |= node.effect_index.is_some();
+    }
+    if primary_is_video {
+        SceneMotionClass::Heavy
+    } else if drift {
+        SceneMotionClass::DriftOnly
+    } else {
+        SceneMotionClass::Static
+    }
+}
+
+pub fn fps_for_motion(class: SceneMotionClass, refresh: Option<u32>) -> u32 {
+    match class {
+        SceneMotionClass::Static => STATIC_SCENE_FPS,
+        SceneMotionClass::DriftOnly => DRIFT_SCENE_FPS,
+        SceneMotionClass::Heavy => refresh
+            .filter(|r| *r >= DRIFT_SCENE_FPS)
+            .unwrap_or(DEFAULT_REFRESH_FPS),
+    }
+}
+
+fn refresh_from_hyprctl_json(raw: &str, monitor: &str) -> Option<u32> {
+    let monitors = serde_json::from_str::<serde_json::Value>(raw).ok()?;
+    monitors
+        .as_array()?
+        .iter()
+        .find(|m| m.get("name").and_then(|v| v.as_str()) == Some(monitor))?
+        .get("refreshRate")
+        .and_then(|v| v.as_f64())
+        .map(|hz| hz.round() as u32)
+}
+
+/// Current mode of `monitor` in `wlr-randr --json` (wlroots compositors).
+fn refresh_from_wlr_randr_json(raw: &str, monitor: &str) -> Option<u32> {
+    let outputs = serde_json::from_str::<Value>(raw).ok()?;
+    outputs
+        .as_array()?
+        .iter()
+        .find(|o| o.get("name").and_then(|v| v.as_str()) == Some(monitor))?
+        .get("modes")?
+        .as_array()?
+        .iter()
+        .find(|m| m.get("current").and_then(|v| v.as_bool()) == Some(true))?
+        .get("refresh")
+        .and_then(|v| v.as_f64())
+        .map(|hz| hz.round() as u32)
+}
+
+fn query_stdout(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).run_output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Refresh rate reported by Hyprland, else by `wlr-randr`; `None` when
+/// neither knows the output.
+pub fn monitor_refresh_rate(monitor: &str) -> Option<u32> {
+    refresh_rate_in(
+        monitor,
+        query_stdout("hyprctl", &["-j", "monitors"]).as_deref(),
+    )
+}
+
+/// [`monitor_refresh_rate`] over an already fetched `hyprctl -j monitors`.
+fn refresh_rate_in(monitor: &str, hyprctl_monitors: Option<&str>) -> Option<u32> {
+    hyprctl_monitors
+        .and_then(|raw| refresh_from_hyprctl_json(raw, monitor))
+        .or_else(|| {
+            query_stdout("wlr-randr", &["--json"])
+                .and_then(|raw| refresh_from_wlr_randr_json(&raw, monitor))
+        })
+        .filter(|hz| *hz > 0)
+}
+
+/// Refresh rate mpv is told for `monitor`: `monitor_refresh` in config.json,
+/// else the refresh the compositor reports in `hyprctl_monitors` (or
+/// `wlr-randr`).
+pub fn monitor_refresh_fps(monitor: &str, hyprctl_monitors: Option<&str>) -> Option<u32> {
+    let configured = load_config(&user_config_path())
+        .ok()
+        .and_then(|cfg| cfg.monitor_refresh.get(monitor).copied())
+        .filter(|hz| *hz > 0);
+    configured.or_else(|| refresh_rate_in(monitor, hyprctl_monitors))
+}
+
+/// FPS cap for a scene when the user did not pass `--display-fps`/`--proxy-fps`.
+pub fn infer_scene_fps(graph: &SceneGpuGraph, primary_is_video: bool, monitor: &str) -> u32 {
+    let class = classify_scene_motion(graph, primary_is_video);
+    let refresh = match class {
+        SceneMotionClass::Heavy => monitor_refresh_rate(monitor),
+        _ => None,
+    };
+    let fps = fps_for_motion(class, refresh);
+    eprintln!("[ok] scene motion {:?}: capping fps at {}", class, fps);
+    fps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fps_follows_motion_class_and_refresh() {
+        assert_eq!(fps_for_motion(SceneMotionClass::Static, Some(144)), 10);
+        assert_eq!(fps_for_motion(SceneMotionClass::DriftOnly, Some(144)), 30);
+        assert_eq!(fps_for_motion(SceneMotionClass::Heavy, Some(144)), 144);
+        assert_eq!(fps_for_motion(SceneMotionClass::Heavy, None), 60);
+
+        let raw =
+            r#"[{"name":"DP-1","refreshRate":143.99},{"name":"HDMI-A-1","refreshRate":60.0}]"#;
+        assert_eq!(refresh_from_hyprctl_json(raw, "DP-1"), Some(144));
+        assert_eq!(refresh_from_hyprctl_json(raw, "eDP-1"), None);
+
+        let wlr = r#"[{"name":"HDMI-A-1","modes":[
+            {"width":1920,"height":1080,"refresh":144.001,"current":false},
+            {"width":1920,"height":1080,"refresh":59.94,"current":true}]}]"#;
+        assert_eq!(refresh_from_wlr_randr_json(wlr, "HDMI-A-1"), Some(60));
+        assert_eq!(refresh_from_wlr_randr_json(wlr, "DP-1"), None);
+    }
+
+    #[test]
+    fn each_monitor_gets_its_own_refresh_rate() {
+        use crate::app_paths::with_app_dirs_root;
+        use crate::command_runner::{MockRunner, with_runner};
+        use crate::startup_config::{StartupConfig, save_config};
+        use std::rc::Rc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let hyprctl = r#"[{"name":"DP-1","refreshRate":143.99},
+            {"name":"HDMI-A-1","refreshRate":60.0},
+            {"name":"DP-2","refreshRate":0.0}]"#;
+        let mock = Rc::new(MockRunner::new());
+        mock.respond("wlr-randr", false, "");
+        let rates = with_app_dirs_root(dir.path(), || {
+            with_runner(mock.clone(), || {
+                let mut cfg = StartupConfig::default();
+                cfg.monitor_refresh.insert("HDMI-A-1".to_string(), 75);
+                cfg.monitor_refresh.insert("DP-3".to_string(), 0);
+                save_config(&user_config_path(), &cfg).unwrap();
+                ["DP-1", "HDMI-A-1", "DP-2", "DP-3", "eDP-1"]
+                    .map(|monitor| monitor_refresh_fps(monitor, Some(hyprctl)))
+            })
+        });
+        // config.json wins over the compositor; a zero anywhere, or an output
+        // neither knows, leaves mpv to pick its own display fps.
+        assert_eq!(rates, [Some(144), Some(75), None, None, None]);
+        assert!(
+            mock.commands().iter().all(|c| c == "wlr-randr --json"),
+            "{:?}",
+            mock.commands()
+        );
+    }
+}
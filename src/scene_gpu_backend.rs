@@ -1,28 +1,41 @@
-use crate::audio::infer_default_monitor_source;
-use crate::cli::{AudioBarsSource, GpuTransport, PlaybackProfile};
-use crate::playback::launch_mpvpaper_with_extra;
-use crate::scene_effect_proxy::{
-    build_scene_audio_bars_overlay, build_scene_realtime_effect_plan,
-    maybe_build_scene_animated_proxy,
+use crate::cli::{
+    AudioBarsSource, GpuTransport, Orientation, PlaybackProfile, RealtimeMotion, TextRenderMode,
+};
+use crate::command_runner::CommandExt;
+use crate::custom_filters::CustomFilters;
+use crate::display_orientation::{OutputFraming, is_portrait_output};
+use crate::i18n::{Msg, warn_msg};
+use crate::playback::{launch_mpvpaper_with_extra, mpv_ipc_socket_path};
+use crate::property_overrides::monitor_property_overrides;
+use crate::scene_effect_proxy::build_scene_audio_bars_overlay;
+use crate::scene_emulation_trace::reset_emulation_trace;
+use crate::scene_fps::infer_scene_fps;
+use crate::scene_gpu_graph::{SceneGpuGraph, build_scene_gpu_graph_with_overrides};
+use crate::scene_gpu_transport::{TransportContext, run_transport_chain, transport_chain};
+use crate::scene_native_runtime::{
+    NativeRuntimePlan, apply_cost_budget, build_native_runtime_plan, native_cost_budget,
 };
-use crate::scene_gpu_graph::build_scene_gpu_graph;
-use crate::scene_native_renderer::{render_native_animated_proxy, render_native_static_frame};
-use crate::scene_native_runtime::build_native_runtime_plan;
-use crate::scene_plan::build_scene_plan;
 use crate::scene_pkg::{extract_entry_to_cache, parse_scene_pkg};
+use crate::scene_plan::build_scene_plan;
 use crate::scene_renderer::build_scene_render_session;
-use crate::scene_text::{build_scene_drawtext_filter, start_text_refresh_daemon};
+use crate::scene_sound::{ambient_sound_option, build_ambient_sound_track};
+use crate::scene_text::{
+    TextTimezones, build_scene_drawtext_filter, build_scene_osd_text, start_text_refresh_daemon,
+};
 use crate::tex_payload::extract_playable_proxy_from_tex;
-use crate::video_opt::maybe_build_optimized_proxy;
+use crate::video_opt::probe_duration_seconds;
+use crate::wallpaper::{WallpaperName, is_video_file, wallpaper_name_at};
+use crate::wallpaper_quirks::load_wallpaper_quirk;
+use crate::with_vf_prefix;
 use anyhow::{Context, Result, bail};
+use chrono_tz::Tz;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::thread;
-use std::time::Duration;
 
 #[derive(Debug, Serialize)]
 pub struct SceneGpuPlayResult {
+    pub wallpaper: WallpaperName,
     pub final_entry: String,
     pub gpu_manifest_path: String,
     pub scene_session_dir: String,
@@ -33,6 +46,7 @@ pub struct SceneGpuPlayResult {
     pub native_runtime_plan_path: Option<String>,
     pub native_static_report_path: Option<String>,
     pub audio_overlay_plan_path: Option<String>,
+    pub ambient_sound_path: Option<String>,
     pub kitsune_overlay_applied: bool,
     pub kitsune_overlay_message: Option<String>,
 }
@@ -169,17 +183,6 @@ fn collect_related_assets(graph: &crate::scene_gpu_graph::SceneGpuGraph) -> Vec<
     out
 }
 
-fn cache_key_for_root(root: &Path) -> String {
-    root.file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_else(|| root.to_string_lossy().replace('/', "_"))
-}
-
-fn realtime_pid_path(root: &Path) -> PathBuf {
-    crate::scene_pkg::default_scene_cache_root(&cache_key_for_root(root))
-        .join("gpu/realtime-ffmpeg.pid")
-}
-
 pub struct SceneGpuPlayArgs {
     pub root: PathBuf,
     pub monitor: String,
@@ -190,13 +193,25 @@ pub struct SceneGpuPlayArgs {
     pub mute_audio: bool,
     pub display_fps: Option<u32>,
     pub clock_overlay: bool,
+    /// Timezone of clock/date text (local time when unset).
+    pub text_timezone: Option<Tz>,
+    pub text_mode: TextRenderMode,
+    pub orientation: Orientation,
     pub apply_kitsune_overlay: bool,
     pub transport: GpuTransport,
     pub require_native: bool,
     pub audio_bars_source: AudioBarsSource,
+    pub realtime_motion: RealtimeMotion,
+    /// `--custom-filter` chains; `None` uses the wallpaper's overrides.
+    pub custom_filters: Option<CustomFilters>,
     pub proxy_width: u32,
     pub proxy_fps: u32,
+    /// Neither `--proxy-fps` nor `--display-fps` was given: cap by scene motion.
+    pub auto_fps: bool,
     pub proxy_crf: u8,
+    pub proxy_seconds: Option<f32>,
+    pub audio_reactive: bool,
+    pub audio_reactive_strength: f32,
     pub dry_run: bool,
 }
 
@@ -209,7 +224,7 @@ fn resolve_kitsune_command() -> Option<(String, Vec<String>)> {
     if Command::new("sh")
         .arg("-c")
         .arg("command -v kitsune >/dev/null 2>&1")
-        .status()
+        .run_status()
         .ok()
         .is_some_and(|s| s.success())
     {
@@ -272,7 +287,7 @@ fn apply_kitsune_overlay_plan(
 
     let run_cmd = |args: &[&str]| -> Result<()> {
         if dry_run {
-            println!("[dry-run] {} {}", prog, args.join(" "));
+            eprintln!("[dry-run] {} {}", prog, args.join(" "));
             return Ok(());
         }
         let mut cmd = Command::new(&prog);
@@ -283,7 +298,7 @@ fn apply_kitsune_overlay_plan(
             cmd.arg(a);
         }
         let out = cmd
-            .output()
+            .run_output()
             .with_context(|| format!("Failed to run {} {}", prog, args.join(" ")))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
@@ -309,17 +324,47 @@ fn apply_kitsune_overlay_plan(
     ))
 }
 
-pub fn scene_gpu_play(args: SceneGpuPlayArgs) -> Result<SceneGpuPlayResult> {
-    let graph = build_scene_gpu_graph(&args.root)?;
-    let native_plan = build_native_runtime_plan(&graph);
+/// Scene graph of `root` as shown on `monitor` and its native plan, trimmed
+/// to the native cost budget.
+pub fn budgeted_native_plan(
+    root: &Path,
+    monitor: &str,
+) -> Result<(SceneGpuGraph, NativeRuntimePlan)> {
+    let graph = build_scene_gpu_graph_with_overrides(root, &monitor_property_overrides(monitor))?;
+    let mut native_plan = build_native_runtime_plan(&graph);
+    apply_cost_budget(
+        &mut native_plan,
+        graph.scene_width,
+        graph.scene_height,
+        native_cost_budget(),
+    );
+    Ok((graph, native_plan))
+}
+
+pub fn scene_gpu_play(mut args: SceneGpuPlayArgs) -> Result<SceneGpuPlayResult> {
+    if let Some(pinned) = load_wallpaper_quirk(&args.root).and_then(|q| q.transport) {
+        eprintln!("[ok] quirk pins transport for this wallpaper: {:?}", pinned);
+        args.transport = pinned;
+    }
+    let (graph, native_plan) = budgeted_native_plan(&args.root, &args.monitor)?;
+    if let Some(budget) = native_plan
+        .budget
+        .as_ref()
+        .filter(|b| !b.trimmed.is_empty())
+    {
+        warn_msg!(
+            Msg::NativeOverBudget,
+            budget.estimated_cost,
+            budget.budget,
+            budget.trimmed.len(),
+        );
+    }
     let session =
         build_scene_render_session(&args.root, args.source.clone(), args.seconds, args.frame_ms)?;
+    reset_emulation_trace(Path::new(&session.session_dir))?;
     let audio_overlay_plan = build_scene_audio_bars_overlay(&args.root)?;
     if audio_overlay_plan.is_some() {
-        eprintln!(
-            "[warn] Soporte de espectros de audio y audio reactivo (barras) en fase de pruebas. \
-No se recomienda su activacion por ahora. Si quieres espectros de audio estables, usa Kitowall Spectrum."
-        );
+        warn_msg!(Msg::AudioSpectrumExperimental);
     }
     let mut kitsune_overlay_applied = false;
     let mut kitsune_overlay_message = None;
@@ -329,7 +374,6 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
     let native_plan_path = gpu_dir.join("native-runtime-plan.json");
     std::fs::write(&native_plan_path, serde_json::to_vec_pretty(&native_plan)?)
         .with_context(|| format!("Failed writing {}", native_plan_path.display()))?;
-    let mut native_static_report_path: Option<String> = None;
 
     let visual_path = PathBuf::from(&session.visual_asset_path);
     let entry_to_launch = if is_mpv_playable_visual(&visual_path) {
@@ -341,12 +385,10 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
         == Some("tex")
     {
         let proxy_dir = Path::new(&session.session_dir).join("proxy");
-        if let Some(mut proxy_from_tex) = extract_playable_proxy_from_tex(&visual_path, &proxy_dir)? {
+        if let Some(mut proxy_from_tex) = extract_playable_proxy_from_tex(&visual_path, &proxy_dir)?
+        {
             if proxy_looks_suspicious(&proxy_from_tex) {
-                eprintln!(
-                    "[warn] gpu-play: extracted primary tex proxy looks suspicious (likely monochrome noise): {}",
-                    proxy_from_tex.display()
-                );
+                warn_msg!(Msg::TexProxySuspicious, proxy_from_tex.display());
                 if let Ok(plan) = build_scene_plan(&args.root)
                     && let Some(pkg_path) = pick_pkg_path(&args.root)
                     && let Ok(pkg) = parse_scene_pkg(&pkg_path)
@@ -394,16 +436,11 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
                         break;
                     }
                     if !found_alternative {
-                        eprintln!(
-                            "[warn] gpu-play: no safe albedo tex candidate found; keeping primary tex proxy (preview fallback disabled)"
-                        );
+                        warn_msg!(Msg::NoSafeAlbedoTex);
                     }
                 }
             }
-            eprintln!(
-                "[warn] gpu-play: primary visual is .tex; extracted playable payload: {}",
-                proxy_from_tex.display()
-            );
+            warn_msg!(Msg::TexPayloadExtracted, proxy_from_tex.display());
             proxy_from_tex.to_string_lossy().to_string()
         } else {
             bail!(
@@ -418,483 +455,49 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
         );
     };
 
-    let (requested_transport, mut effective_transport) = match args.transport {
-        GpuTransport::Mp4Proxy => ("mp4-proxy".to_string(), "mp4-proxy".to_string()),
+    let requested_transport = match args.transport {
+        GpuTransport::Mp4Proxy => "mp4-proxy".to_string(),
         GpuTransport::NativeRealtime => {
-            if args.require_native {
-                ("native-realtime".to_string(), "native-realtime".to_string())
-            } else {
-                eprintln!(
-                    "[warn] native-realtime transport requested: experimental backend enabled"
-                );
-                ("native-realtime".to_string(), "native-realtime".to_string())
+            if !args.require_native {
+                warn_msg!(Msg::NativeRealtimeExperimental);
             }
+            "native-realtime".to_string()
         }
+        GpuTransport::NativeStream => "native-stream".to_string(),
     };
-    let use_native_realtime = matches!(args.transport, GpuTransport::NativeRealtime);
-
-    let final_entry = if use_native_realtime {
-        if native_plan.ready_draw_layers > 0 {
-            if let Some(report) = render_native_animated_proxy(
-                &args.root,
-                Path::new(&session.session_dir),
-                graph.scene_width,
-                graph.scene_height,
-                args.seconds,
-                args.proxy_fps,
-                args.dry_run,
-                &native_plan,
-            )? {
-                native_static_report_path = Some(report.report_path.clone());
-                effective_transport = "native-animated-layered".to_string();
-                report.output_video
-            } else {
-                if let Some(report) = render_native_static_frame(
-                    &args.root,
-                    Path::new(&session.session_dir),
-                    graph.scene_width,
-                    graph.scene_height,
-                    &native_plan,
-                )? {
-                    native_static_report_path = Some(
-                        Path::new(&report.output_image)
-                            .parent()
-                            .map(|p| {
-                                p.join("native_static_report.json")
-                                    .to_string_lossy()
-                                    .to_string()
-                            })
-                            .unwrap_or_default(),
-                    );
-                    effective_transport = "native-static-layered".to_string();
-                    report.output_image
-                } else {
-                    // continue to previous realtime filter path
-                    let plan_opt = build_scene_realtime_effect_plan(
-                        &args.root,
-                        Path::new(&session.session_dir),
-                        Path::new(&entry_to_launch),
-                    )?;
-                    if plan_opt.is_none() {
-                        if args.require_native {
-                            bail!("native-realtime requested but no realtime plan could be built");
-                        }
-                        eprintln!(
-                            "[warn] native-realtime plan unavailable, falling back to mp4-proxy"
-                        );
-                        effective_transport = "mp4-proxy (fallback)".to_string();
-                        let animated_entry = match maybe_build_scene_animated_proxy(
-                            &args.root,
-                            Path::new(&session.session_dir),
-                            Path::new(&entry_to_launch),
-                            args.dry_run,
-                        )? {
-                            Some(p) => p.to_string_lossy().to_string(),
-                            None => entry_to_launch.clone(),
-                        };
-                        maybe_build_optimized_proxy(
-                            Path::new(&animated_entry),
-                            Path::new(&session.session_dir),
-                            args.proxy_width,
-                            args.proxy_fps,
-                            args.proxy_crf,
-                            args.dry_run,
-                        )?
-                        .to_string_lossy()
-                        .to_string()
-                    } else {
-                        let plan = plan_opt.expect("checked is_some");
-                        if args.require_native
-                            && native_plan.ready_nodes == 0
-                            && native_plan.total_pass_nodes > 0
-                        {
-                            bail!(
-                                "native-realtime requested but no ready shader families in native plan. See {}",
-                                native_plan_path.display()
-                            );
-                        }
-                        let port_base = 19000u16;
-                        let mut hash: u32 = 0;
-                        for b in cache_key_for_root(&args.root).as_bytes() {
-                            hash = hash.wrapping_mul(31).wrapping_add(*b as u32);
-                        }
-                        let port = port_base + (hash % 5000) as u16;
-                        let stream_url = format!("udp://127.0.0.1:{}", port);
-                        let pid_file = realtime_pid_path(&args.root);
-                        if let Ok(pid_raw) = std::fs::read_to_string(&pid_file) {
-                            if let Ok(pid) = pid_raw.trim().parse::<u32>() {
-                                let _ = Command::new("kill").arg(pid.to_string()).status();
-                            }
-                        }
-                        if args.dry_run {
-                            let mut cmdline =
-                                "[dry-run] ffmpeg -hide_banner -loglevel warning -re -stream_loop -1"
-                                    .to_string();
-                            for input in &plan.inputs {
-                                cmdline.push_str(&format!(" -loop 1 -i '{}'", input.display()));
-                            }
-                            if plan.needs_audio_input {
-                                match args.audio_bars_source {
-                                    AudioBarsSource::Pulse => {
-                                        let pulse_src = infer_default_monitor_source()
-                                            .unwrap_or_else(|_| "default".to_string());
-                                        cmdline.push_str(&format!(" -f pulse -i '{}'", pulse_src));
-                                    }
-                                    AudioBarsSource::Synth => cmdline.push_str(
-                                        " -f lavfi -i anoisesrc=color=pink:amplitude=0.4",
-                                    ),
-                                }
-                            }
-                            cmdline.push_str(&format!(
-                                " -filter_complex \"{}\" -map '[v]' -r {} -an -f mpegts '{}'",
-                                plan.filter_complex, args.proxy_fps, stream_url
-                            ));
-                            println!("{}", cmdline);
-                        } else {
-                            if let Some(parent) = pid_file.parent() {
-                                std::fs::create_dir_all(parent).ok();
-                            }
-                            let native_log = "/tmp/kwe-native-ffmpeg.log";
-                            let log_file = std::fs::OpenOptions::new()
-                                .create(true)
-                                .write(true)
-                                .truncate(true)
-                                .open(native_log)
-                                .with_context(|| format!("Failed to open {}", native_log))?;
-                            let log_file_err = log_file
-                                .try_clone()
-                                .with_context(|| format!("Failed to clone {}", native_log))?;
-                            let mut cmd = Command::new("ffmpeg");
-                            cmd.arg("-hide_banner")
-                                .arg("-loglevel")
-                                .arg("warning")
-                                .arg("-re")
-                                .arg("-stream_loop")
-                                .arg("-1");
-                            for input in &plan.inputs {
-                                cmd.arg("-loop").arg("1").arg("-i").arg(input);
-                            }
-                            if plan.needs_audio_input {
-                                match args.audio_bars_source {
-                                    AudioBarsSource::Pulse => {
-                                        let pulse_src = infer_default_monitor_source()
-                                            .unwrap_or_else(|_| "default".to_string());
-                                        cmd.arg("-f").arg("pulse").arg("-i").arg(pulse_src);
-                                    }
-                                    AudioBarsSource::Synth => {
-                                        cmd.arg("-f")
-                                            .arg("lavfi")
-                                            .arg("-i")
-                                            .arg("anoisesrc=color=pink:amplitude=0.4");
-                                    }
-                                }
-                            }
-                            let child = cmd
-                                .arg("-filter_complex")
-                                .arg(&plan.filter_complex)
-                                .arg("-map")
-                                .arg("[v]")
-                                .arg("-r")
-                                .arg(args.proxy_fps.to_string())
-                                .arg("-an")
-                                .arg("-f")
-                                .arg("mpegts")
-                                .arg(&stream_url)
-                                .stdin(std::process::Stdio::null())
-                                .stdout(log_file)
-                                .stderr(log_file_err)
-                                .spawn()
-                                .context("Failed to spawn native-realtime ffmpeg")?;
-                            let child_id = child.id();
-                            std::fs::write(&pid_file, child_id.to_string()).with_context(|| {
-                                format!("Failed writing {}", pid_file.display())
-                            })?;
-                            thread::sleep(Duration::from_millis(1800));
-                            let alive = Command::new("kill")
-                                .arg("-0")
-                                .arg(child_id.to_string())
-                                .status()
-                                .map(|s| s.success())
-                                .unwrap_or(false);
-                            if !alive {
-                                if args.require_native {
-                                    bail!(
-                                        "native-realtime ffmpeg exited on startup. See {}",
-                                        native_log
-                                    );
-                                }
-                                eprintln!(
-                                    "[warn] native-realtime ffmpeg failed, falling back to mp4-proxy (log: {})",
-                                    native_log
-                                );
-                                effective_transport = "mp4-proxy (fallback)".to_string();
-                                let animated_entry = match maybe_build_scene_animated_proxy(
-                                    &args.root,
-                                    Path::new(&session.session_dir),
-                                    Path::new(&entry_to_launch),
-                                    args.dry_run,
-                                )? {
-                                    Some(p) => p.to_string_lossy().to_string(),
-                                    None => entry_to_launch.clone(),
-                                };
-                                return Ok(SceneGpuPlayResult {
-                                    final_entry: maybe_build_optimized_proxy(
-                                        Path::new(&animated_entry),
-                                        Path::new(&session.session_dir),
-                                        args.proxy_width,
-                                        args.proxy_fps,
-                                        args.proxy_crf,
-                                        args.dry_run,
-                                    )?
-                                    .to_string_lossy()
-                                    .to_string(),
-                                    gpu_manifest_path: Path::new(&session.session_dir)
-                                        .join("gpu/manifest.json")
-                                        .to_string_lossy()
-                                        .to_string(),
-                                    scene_session_dir: session.session_dir,
-                                    scene_manifest_path: session.manifest_path,
-                                    gpu_effect_nodes: graph.effect_nodes.len(),
-                                    requested_transport,
-                                    effective_transport,
-                                    native_runtime_plan_path: Some(
-                                        native_plan_path.to_string_lossy().to_string(),
-                                    ),
-                                    native_static_report_path: native_static_report_path.clone(),
-                                    audio_overlay_plan_path: None,
-                                    kitsune_overlay_applied: false,
-                                    kitsune_overlay_message: None,
-                                });
-                            }
-                        }
-                        stream_url
-                    }
-                }
-            }
-        } else {
-            let plan_opt = build_scene_realtime_effect_plan(
-                &args.root,
-                Path::new(&session.session_dir),
-                Path::new(&entry_to_launch),
-            )?;
-            if plan_opt.is_none() {
-                if args.require_native {
-                    bail!("native-realtime requested but no realtime plan could be built");
-                }
-                eprintln!("[warn] native-realtime plan unavailable, falling back to mp4-proxy");
-                effective_transport = "mp4-proxy (fallback)".to_string();
-                let animated_entry = match maybe_build_scene_animated_proxy(
-                    &args.root,
-                    Path::new(&session.session_dir),
-                    Path::new(&entry_to_launch),
-                    args.dry_run,
-                )? {
-                    Some(p) => p.to_string_lossy().to_string(),
-                    None => entry_to_launch.clone(),
-                };
-                maybe_build_optimized_proxy(
-                    Path::new(&animated_entry),
-                    Path::new(&session.session_dir),
-                    args.proxy_width,
-                    args.proxy_fps,
-                    args.proxy_crf,
-                    args.dry_run,
-                )?
-                .to_string_lossy()
-                .to_string()
-            } else {
-                let plan = plan_opt.expect("checked is_some");
-                if args.require_native
-                    && native_plan.ready_nodes == 0
-                    && native_plan.total_pass_nodes > 0
-                {
-                    bail!(
-                        "native-realtime requested but no ready shader families in native plan. See {}",
-                        native_plan_path.display()
-                    );
-                }
-
-                let port_base = 19000u16;
-                let mut hash: u32 = 0;
-                for b in cache_key_for_root(&args.root).as_bytes() {
-                    hash = hash.wrapping_mul(31).wrapping_add(*b as u32);
-                }
-                let port = port_base + (hash % 5000) as u16;
-                let stream_url = format!("udp://127.0.0.1:{}", port);
 
-                let pid_file = realtime_pid_path(&args.root);
-                if let Ok(pid_raw) = std::fs::read_to_string(&pid_file) {
-                    if let Ok(pid) = pid_raw.trim().parse::<u32>() {
-                        let _ = Command::new("kill").arg(pid.to_string()).status();
-                    }
-                }
-
-                if args.dry_run {
-                    let mut cmdline =
-                        "[dry-run] ffmpeg -hide_banner -loglevel warning -re -stream_loop -1"
-                            .to_string();
-                    for input in &plan.inputs {
-                        cmdline.push_str(&format!(" -loop 1 -i '{}'", input.display()));
-                    }
-                    if plan.needs_audio_input {
-                        match args.audio_bars_source {
-                            AudioBarsSource::Pulse => {
-                                let pulse_src = infer_default_monitor_source()
-                                    .unwrap_or_else(|_| "default".to_string());
-                                cmdline.push_str(&format!(" -f pulse -i '{}'", pulse_src));
-                            }
-                            AudioBarsSource::Synth => {
-                                cmdline.push_str(" -f lavfi -i anoisesrc=color=pink:amplitude=0.4")
-                            }
-                        }
-                    }
-                    cmdline.push_str(&format!(
-                        " -filter_complex \"{}\" -map '[v]' -r {} -an -f mpegts '{}'",
-                        plan.filter_complex, args.proxy_fps, stream_url
-                    ));
-                    println!("{}", cmdline);
-                } else {
-                    if let Some(parent) = pid_file.parent() {
-                        std::fs::create_dir_all(parent).ok();
-                    }
-                    let native_log = "/tmp/kwe-native-ffmpeg.log";
-                    let log_file = std::fs::OpenOptions::new()
-                        .create(true)
-                        .write(true)
-                        .truncate(true)
-                        .open(native_log)
-                        .with_context(|| format!("Failed to open {}", native_log))?;
-                    let log_file_err = log_file
-                        .try_clone()
-                        .with_context(|| format!("Failed to clone {}", native_log))?;
-                    let mut cmd = Command::new("ffmpeg");
-                    cmd.arg("-hide_banner")
-                        .arg("-loglevel")
-                        .arg("warning")
-                        .arg("-re")
-                        .arg("-stream_loop")
-                        .arg("-1");
-                    for input in &plan.inputs {
-                        cmd.arg("-loop").arg("1").arg("-i").arg(input);
-                    }
-                    if plan.needs_audio_input {
-                        match args.audio_bars_source {
-                            AudioBarsSource::Pulse => {
-                                let pulse_src = infer_default_monitor_source()
-                                    .unwrap_or_else(|_| "default".to_string());
-                                cmd.arg("-f").arg("pulse").arg("-i").arg(pulse_src);
-                            }
-                            AudioBarsSource::Synth => {
-                                cmd.arg("-f")
-                                    .arg("lavfi")
-                                    .arg("-i")
-                                    .arg("anoisesrc=color=pink:amplitude=0.4");
-                            }
-                        }
-                    }
-                    let child = cmd
-                        .arg("-filter_complex")
-                        .arg(&plan.filter_complex)
-                        .arg("-map")
-                        .arg("[v]")
-                        .arg("-r")
-                        .arg(args.proxy_fps.to_string())
-                        .arg("-an")
-                        .arg("-f")
-                        .arg("mpegts")
-                        .arg(&stream_url)
-                        .stdin(std::process::Stdio::null())
-                        .stdout(log_file)
-                        .stderr(log_file_err)
-                        .spawn()
-                        .context("Failed to spawn native-realtime ffmpeg")?;
-                    let child_id = child.id();
-                    std::fs::write(&pid_file, child_id.to_string())
-                        .with_context(|| format!("Failed writing {}", pid_file.display()))?;
-                    // Give native ffmpeg enough time to fail fast on invalid filters/input.
-                    thread::sleep(Duration::from_millis(1800));
-                    let alive = Command::new("kill")
-                        .arg("-0")
-                        .arg(child_id.to_string())
-                        .status()
-                        .map(|s| s.success())
-                        .unwrap_or(false);
-                    if !alive {
-                        if args.require_native {
-                            bail!(
-                                "native-realtime ffmpeg exited on startup. See {}",
-                                native_log
-                            );
-                        }
-                        eprintln!(
-                            "[warn] native-realtime ffmpeg failed, falling back to mp4-proxy (log: {})",
-                            native_log
-                        );
-                        effective_transport = "mp4-proxy (fallback)".to_string();
-                        let animated_entry = match maybe_build_scene_animated_proxy(
-                            &args.root,
-                            Path::new(&session.session_dir),
-                            Path::new(&entry_to_launch),
-                            args.dry_run,
-                        )? {
-                            Some(p) => p.to_string_lossy().to_string(),
-                            None => entry_to_launch.clone(),
-                        };
-                        return Ok(SceneGpuPlayResult {
-                            final_entry: maybe_build_optimized_proxy(
-                                Path::new(&animated_entry),
-                                Path::new(&session.session_dir),
-                                args.proxy_width,
-                                args.proxy_fps,
-                                args.proxy_crf,
-                                args.dry_run,
-                            )?
-                            .to_string_lossy()
-                            .to_string(),
-                            gpu_manifest_path: Path::new(&session.session_dir)
-                                .join("gpu/manifest.json")
-                                .to_string_lossy()
-                                .to_string(),
-                            scene_session_dir: session.session_dir,
-                            scene_manifest_path: session.manifest_path,
-                            gpu_effect_nodes: graph.effect_nodes.len(),
-                            requested_transport,
-                            effective_transport,
-                            native_runtime_plan_path: Some(
-                                native_plan_path.to_string_lossy().to_string(),
-                            ),
-                            native_static_report_path: native_static_report_path.clone(),
-                            audio_overlay_plan_path: None,
-                            kitsune_overlay_applied: false,
-                            kitsune_overlay_message: None,
-                        });
-                    }
-                }
+    if args.auto_fps {
+        let fps = infer_scene_fps(
+            &graph,
+            is_video_file(Path::new(&entry_to_launch)),
+            &args.monitor,
+        );
+        args.proxy_fps = fps;
+        args.display_fps = Some(fps);
+    }
 
-                stream_url
-            }
-        }
-    } else {
-        let animated_entry = match maybe_build_scene_animated_proxy(
-            &args.root,
-            Path::new(&session.session_dir),
-            Path::new(&entry_to_launch),
-            args.dry_run,
-        )? {
-            Some(p) => p.to_string_lossy().to_string(),
-            None => entry_to_launch,
-        };
-        maybe_build_optimized_proxy(
-            Path::new(&animated_entry),
-            Path::new(&session.session_dir),
-            args.proxy_width,
-            args.proxy_fps,
-            args.proxy_crf,
-            args.dry_run,
-        )?
-        .to_string_lossy()
-        .to_string()
+    let ctx = TransportContext {
+        args: &args,
+        session_dir: Path::new(&session.session_dir),
+        entry_to_launch: &entry_to_launch,
+        music_path: session.music_asset_path.as_deref().map(Path::new),
+        scene_width: graph.scene_width,
+        scene_height: graph.scene_height,
+        native_plan: &native_plan,
+        native_plan_path: &native_plan_path,
+        portrait: is_portrait_output(args.orientation, &args.monitor),
     };
+    let mut chain = transport_chain(args.transport, args.require_native);
+    let prepared = run_transport_chain(&mut chain, &ctx)?;
+    let final_entry = prepared.entry;
+    let prepared_portrait = prepared.portrait_canvas;
+    let live_filter = prepared.live_filter;
+    let transport_notes = prepared.manifest_notes;
+    if ctx.portrait && !prepared_portrait {
+        warn_msg!(Msg::PortraitKeepsLandscape, prepared.effective_transport);
+    }
+    let effective_transport = prepared.effective_transport;
+    let native_static_report_path = prepared.native_report_path;
 
     let mut extracted_assets = Vec::<String>::new();
     if let Some(pkg_path) = pick_pkg_path(&args.root) {
@@ -935,7 +538,10 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
             format!("Requested transport: {}", requested_transport),
             format!("Effective transport: {}", effective_transport),
             format!("Native families ready: {:?}", native_plan.ready_families),
-        ],
+        ]
+        .into_iter()
+        .chain(transport_notes)
+        .collect(),
     };
     let gpu_manifest_path = gpu_dir.join("manifest.json");
     std::fs::write(
@@ -964,7 +570,7 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
                 }
                 Err(err) => {
                     kitsune_overlay_message = Some(format!("apply failed: {}", err));
-                    eprintln!("[warn] Kitsune overlay apply failed: {}", err);
+                    warn_msg!(Msg::KitsuneOverlayFailed, err);
                 }
             }
         } else {
@@ -973,11 +579,29 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
     }
 
     let drawtext_opt = if args.clock_overlay {
-        match build_scene_drawtext_filter(&args.root, 3) {
+        let framing = if prepared_portrait {
+            OutputFraming::Portrait
+        } else {
+            OutputFraming::Full
+        };
+        let timezones = TextTimezones::with_overrides(
+            args.text_timezone,
+            &monitor_property_overrides(&args.monitor),
+        );
+        let socket = mpv_ipc_socket_path(&args.monitor);
+        let built = match args.text_mode {
+            TextRenderMode::Osd => {
+                build_scene_osd_text(&args.root, 3, framing, &timezones, &socket).map(|_| None)
+            }
+            TextRenderMode::Drawtext => {
+                build_scene_drawtext_filter(&args.root, 3, framing, &timezones)
+            }
+        };
+        match built {
             Ok(Some(v)) => Some(v),
             Ok(None) => None,
             Err(err) => {
-                eprintln!("[warn] gpu-play text overlays unavailable: {}", err);
+                warn_msg!(Msg::GpuTextOverlaysUnavailable, err);
                 None
             }
         }
@@ -988,18 +612,60 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
     if args.clock_overlay {
         start_text_refresh_daemon(&args.root, args.dry_run)?;
     }
+    let extra_vf = match live_filter {
+        Some(filter) => {
+            eprintln!("[ok] clock hands composited live over the proxy");
+            Some(with_vf_prefix(drawtext_opt, &filter))
+        }
+        None => drawtext_opt,
+    };
+
+    // `--mute-audio` drops audio entirely; the global mute still lets the
+    // track load so `mute off` brings the ambience back.
+    let ambient_sound = if args.mute_audio || graph.sounds.is_empty() {
+        None
+    } else {
+        let final_path = Path::new(&final_entry);
+        let seconds = is_video_file(final_path)
+            .then(|| probe_duration_seconds(final_path).ok())
+            .flatten();
+        match build_ambient_sound_track(
+            &args.root,
+            &graph.sounds,
+            Path::new(&session.session_dir),
+            seconds,
+            args.dry_run,
+        ) {
+            Ok(track) => track,
+            Err(err) => {
+                warn_msg!(Msg::SceneSoundFailed, err);
+                None
+            }
+        }
+    };
+    let extra_opt = match (extra_vf, &ambient_sound) {
+        (Some(vf), Some(track)) => Some(format!("{} {}", vf, ambient_sound_option(track))),
+        (None, Some(track)) => Some(ambient_sound_option(track)),
+        (vf, None) => vf,
+    };
 
     launch_mpvpaper_with_extra(
         &args.monitor,
-        &final_entry,
+        Path::new(&final_entry),
         args.profile,
         args.mute_audio,
         args.display_fps,
-        drawtext_opt.as_deref(),
+        extra_opt.as_deref(),
         args.dry_run,
     )?;
 
+    let id = args
+        .root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| args.root.to_string_lossy().to_string());
     Ok(SceneGpuPlayResult {
+        wallpaper: wallpaper_name_at(&id, &args.root),
         final_entry,
         gpu_manifest_path: gpu_manifest_path.to_string_lossy().to_string(),
         scene_session_dir: session.session_dir,
@@ -1010,6 +676,7 @@ No se recomienda su activacion por ahora. Si quieres espectros de audio estables
         native_runtime_plan_path: Some(native_plan_path.to_string_lossy().to_string()),
         native_static_report_path,
         audio_overlay_plan_path,
+        ambient_sound_path: ambient_sound.map(|p| p.to_string_lossy().to_string()),
         kitsune_overlay_applied,
         kitsune_overlay_message,
     })
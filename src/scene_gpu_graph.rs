@@ -1,11 +1,17 @@
 use crate::asset_resolver::AssetResolver;
+use crate::layer_overrides::{apply_layer_overrides, layer_override_for_wallpaper};
 use crate::scene_script::{
-    ScriptAssignment, apply_scene_scripts, collect_scene_user_properties, to_json_object,
+    ClockHand, PropertyAnimation, ScriptAssignment, apply_scene_scripts,
+    collect_scene_user_properties, detect_clock_hand, detect_object_animations, to_json_object,
 };
+use crate::scene_sound::{SceneSound, parse_scene_sound};
+use crate::scene_visible_expr::eval_visible_expr;
+use crate::tex_payload::{TEX_FLAG_CLAMP_UVS, tex_header_flags};
+use crate::wallpaper_quirks::{apply_scene_quirks, quirk_for_wallpaper};
 use anyhow::{Result, bail};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
 
 #[derive(Debug, Serialize, Clone)]
@@ -22,7 +28,75 @@ pub struct GpuPassSpec {
     pub user_shader_values: Value,
     pub textures: Vec<String>,
     pub texture_refs: Vec<String>,
+    /// "repeat" or "clamp" for the primary texture, when the material says so.
+    pub texture_wrap: Option<String>,
     pub effective_uniforms: BTreeMap<String, Value>,
+    /// Render target the pass writes to (`_rt_*`); `None` draws onto the layer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Render targets sampled by the pass, by texture slot.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub binds: Vec<RenderTargetBind>,
+    /// Audio spectrum arrays the pass shaders read (`g_AudioSpectrum16Left`...);
+    /// each also has a slot in `effective_uniforms`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audio_uniforms: Vec<String>,
+}
+
+/// Audio spectrum arrays Wallpaper Engine feeds to shaders, with their length.
+pub const AUDIO_SPECTRUM_UNIFORMS: [(&str, usize); 6] = [
+    ("g_AudioSpectrum16Left", 16),
+    ("g_AudioSpectrum16Right", 16),
+    ("g_AudioSpectrum32Left", 32),
+    ("g_AudioSpectrum32Right", 32),
+    ("g_AudioSpectrum64Left", 64),
+    ("g_AudioSpectrum64Right", 64),
+];
+
+/// `bind` entry of an effect pass: texture slot `index` reads render target
+/// `name` (`previous` is the output of the pass before).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RenderTargetBind {
+    pub name: String,
+    pub index: usize,
+}
+
+/// Intermediate buffer declared in the `fbos` list of an effect.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RenderTargetSpec {
+    pub name: String,
+    /// Divisor of the layer size (2 = half resolution).
+    pub scale: u32,
+    pub format: Option<String>,
+}
+
+/// Inputs and output of one pass in a render-target chain.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RenderTargetStep {
+    /// Index into `SceneGpuGraph::effect_nodes`.
+    pub node: usize,
+    /// Position in the node's `passes`.
+    pub pass: usize,
+    pub inputs: Vec<String>,
+    /// Render target name, or `layer` for the pass that draws the result.
+    pub output: String,
+}
+
+/// Passes of one effect instance that hand their output to each other
+/// through render targets instead of drawing independently.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RenderTargetChain {
+    pub object_index: usize,
+    pub effect_index: usize,
+    pub steps: Vec<RenderTargetStep>,
+}
+
+/// Render-target routing shared by the material passes of one effect pass.
+#[derive(Debug, Default)]
+struct PassRouting {
+    target: Option<String>,
+    binds: Vec<RenderTargetBind>,
+    render_targets: Vec<RenderTargetSpec>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -48,6 +122,9 @@ pub struct GpuEffectNode {
     pub object_asset_size: Option<[f32; 2]>,
     pub object_parallax_depth: Option<[f32; 2]>,
     pub object_visible: bool,
+    /// Legacy top-level `alpha`/`opacity` of the object, user bindings resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_alpha: Option<f32>,
     pub effect_index: Option<usize>,
     pub instance_override: Value,
     pub effect_file: String,
@@ -60,10 +137,26 @@ pub struct GpuEffectNode {
     pub shader_frag: Option<String>,
     pub material_json: Option<String>,
     pub uniform_bindings: Vec<ShaderUniformBinding>,
+    /// `fbos` of the effect the node belongs to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub render_targets: Vec<RenderTargetSpec>,
+}
+
+/// How much of each pass the graph builder resolves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphDetail {
+    /// Objects, effects and passes only, for callers that count them: no
+    /// shader or texture reads, so passes have no shader paths, uniform
+    /// bindings, audio uniforms or resolved uniform values.
+    Summary,
+    #[default]
+    Full,
 }
 
 #[derive(Debug, Serialize, Clone)]
 pub struct SceneGpuGraph {
+    pub detail: GraphDetail,
     pub pkg_path: String,
     pub scene_json_entry: String,
     pub scene_width: u32,
@@ -73,9 +166,150 @@ pub struct SceneGpuGraph {
     pub script_properties: Value,
     pub script_assignments: Vec<ScriptAssignment>,
     pub effect_nodes: Vec<GpuEffectNode>,
+    /// Only filled for split scenes (two or more viewports); a single
+    /// fullscreen projection stays implicit.
+    pub viewports: Vec<SceneViewport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<SceneObjectGroup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clock_hands: Vec<SceneClockHand>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub script_animations: Vec<SceneScriptAnimation>,
+    /// Objects dropped before any proxy or native plan sees them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pruned: Vec<PrunedObject>,
+    /// Ambient `sound` objects, mixed under the wallpaper at playback.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sounds: Vec<SceneSound>,
     pub notes: Vec<String>,
 }
 
+impl GpuPassSpec {
+    pub fn uses_render_targets(&self) -> bool {
+        self.target.is_some() || !self.binds.is_empty()
+    }
+
+    /// Writes `left`/`right` spectra (any band count, 0..1) into the audio
+    /// slots of the pass; empty spectra leave the slots at zero.
+    pub fn fill_audio_uniforms(&mut self, left: &[f32], right: &[f32]) {
+        fill_audio_uniforms(
+            &mut self.effective_uniforms,
+            &self.audio_uniforms,
+            left,
+            right,
+        );
+    }
+}
+
+impl SceneGpuGraph {
+    /// Effects whose passes feed each other through render targets, in pass
+    /// order. Passes without `bind` read the output of the pass before.
+    pub fn render_target_chains(&self) -> Vec<RenderTargetChain> {
+        let mut chains = Vec::<RenderTargetChain>::new();
+        for (i, node) in self.effect_nodes.iter().enumerate() {
+            let Some(effect_index) = node.effect_index else {
+                continue;
+            };
+            let continues = chains.last().is_some_and(|c| {
+                c.object_index == node.object_index && c.effect_index == effect_index
+            });
+            if !continues {
+                chains.push(RenderTargetChain {
+                    object_index: node.object_index,
+                    effect_index,
+                    steps: Vec::new(),
+                });
+            }
+            let chain = chains.last_mut().expect("chain pushed above");
+            for (pass_pos, pass) in node.passes.iter().enumerate() {
+                let inputs = if pass.binds.is_empty() {
+                    vec!["previous".to_string()]
+                } else {
+                    pass.binds.iter().map(|b| b.name.clone()).collect()
+                };
+                chain.steps.push(RenderTargetStep {
+                    node: i,
+                    pass: pass_pos,
+                    inputs,
+                    output: pass.target.clone().unwrap_or_else(|| "layer".to_string()),
+                });
+            }
+        }
+        chains.retain(|c| {
+            c.steps.len() > 1
+                && c.steps.iter().any(|s| {
+                    self.effect_nodes[s.node]
+                        .passes
+                        .iter()
+                        .any(GpuPassSpec::uses_render_targets)
+                })
+        });
+        chains
+    }
+}
+
+/// Scene object other objects are parented to (an editor group/folder).
+/// Hiding it hides every descendant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneObjectGroup {
+    pub id: u64,
+    pub name: String,
+    pub parent: Option<u64>,
+    /// Own visibility, before ancestor groups are applied.
+    pub visible: bool,
+    /// User property the group's visibility is bound to.
+    pub visibility_property: Option<String>,
+    /// Direct children ids.
+    pub members: Vec<u64>,
+}
+
+/// Object whose rotation a clock script drives from the local time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneClockHand {
+    pub object_index: usize,
+    pub object_id: u64,
+    pub object_name: String,
+    #[serde(flatten)]
+    pub hand: ClockHand,
+}
+
+/// Object whose colour, alpha or brightness a script keeps changing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneScriptAnimation {
+    pub object_index: usize,
+    pub object_id: u64,
+    pub object_name: String,
+    #[serde(flatten)]
+    pub animation: PropertyAnimation,
+}
+
+/// Object whose pass nodes were removed from the graph because it can never
+/// show up: hidden (itself or through a group) or with a fixed alpha of 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrunedObject {
+    pub object_index: usize,
+    pub object_id: u64,
+    pub object_name: String,
+    pub pass_nodes: usize,
+    pub reason: String,
+}
+
+/// One camera region of a split scene: the scene-space window it looks at
+/// (`source_*`) and where that window lands on the output canvas (`dest_*`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneViewport {
+    pub index: usize,
+    pub name: Option<String>,
+    pub dest_x: u32,
+    pub dest_y: u32,
+    pub dest_width: u32,
+    pub dest_height: u32,
+    pub source_x: f32,
+    pub source_y: f32,
+    pub source_width: f32,
+    pub source_height: f32,
+}
+
 fn parse_scene_size(scene_json: &Value) -> (u32, u32) {
     let width = scene_json
         .get("general")
@@ -92,7 +326,7 @@ fn parse_scene_size(scene_json: &Value) -> (u32, u32) {
     (width.max(1), height.max(1))
 }
 
-fn parse_vec3(value: &Value) -> Option<[f32; 3]> {
+pub(crate) fn parse_vec3(value: &Value) -> Option<[f32; 3]> {
     let s = value.as_str()?;
     let mut it = s.split_whitespace();
     let x = it.next()?.parse::<f32>().ok()?;
@@ -109,231 +343,99 @@ fn parse_vec2(value: &Value) -> Option<[f32; 2]> {
     Some([x, y])
 }
 
-fn parse_visible_operand(token: &str, user_values: &BTreeMap<String, Value>) -> Option<Value> {
-    let t = token.trim().trim_matches('(').trim_matches(')');
-    if t.is_empty() {
-        return None;
-    }
-    if t.eq_ignore_ascii_case("true") {
-        return Some(Value::Bool(true));
-    }
-    if t.eq_ignore_ascii_case("false") {
-        return Some(Value::Bool(false));
-    }
-    if (t.starts_with('\'') && t.ends_with('\'')) || (t.starts_with('"') && t.ends_with('"')) {
-        return Some(Value::String(t[1..t.len() - 1].to_string()));
-    }
-    if let Ok(v) = t.parse::<f64>()
-        && let Some(n) = serde_json::Number::from_f64(v)
-    {
-        return Some(Value::Number(n));
+fn viewport_rect(entry: &Value) -> Option<[f32; 4]> {
+    let from_list = |v: &Value| -> Option<[f32; 4]> {
+        let nums = match v {
+            Value::String(s) => s
+                .split_whitespace()
+                .map(|t| t.parse::<f32>().ok())
+                .collect::<Option<Vec<_>>>()?,
+            Value::Array(items) => items
+                .iter()
+                .map(|t| t.as_f64().map(|n| n as f32))
+                .collect::<Option<Vec<_>>>()?,
+            _ => return None,
+        };
+        (nums.len() == 4).then(|| [nums[0], nums[1], nums[2], nums[3]])
+    };
+    if let Some(rect) = entry.get("viewport").and_then(from_list) {
+        return Some(rect);
     }
-    let key = t.trim_end_matches(".value");
-    user_values.get(key).cloned()
+    let num = |key: &str| entry.get(key).and_then(|v| v.as_f64()).map(|n| n as f32);
+    Some([num("x")?, num("y")?, num("width")?, num("height")?])
 }
 
-fn value_as_f64(v: &Value) -> Option<f64> {
-    match v {
-        Value::Number(n) => n.as_f64(),
-        Value::String(s) => s.parse::<f64>().ok(),
-        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
-        _ => None,
-    }
-}
+/// Detects split scenes (`general.viewports`, `viewports` or `cameras`).
+/// Rect values in 0..=1 are read as fractions of the canvas, larger ones as pixels.
+fn parse_scene_viewports(scene_json: &Value, scene_w: u32, scene_h: u32) -> Vec<SceneViewport> {
+    let entries = scene_json
+        .get("general")
+        .and_then(|g| g.get("viewports"))
+        .or_else(|| scene_json.get("viewports"))
+        .or_else(|| scene_json.get("cameras"))
+        .and_then(|v| v.as_array());
+    let Some(entries) = entries.filter(|e| e.len() > 1) else {
+        return Vec::new();
+    };
 
-fn value_as_str(v: &Value) -> Option<String> {
-    match v {
-        Value::String(s) => Some(s.trim().to_string()),
-        Value::Number(n) => Some(n.to_string()),
-        Value::Bool(b) => Some(if *b {
-            "true".to_string()
+    let (cw, ch) = (scene_w.max(1) as f32, scene_h.max(1) as f32);
+    let mut out = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let Some([x, y, w, h]) = viewport_rect(entry) else {
+            continue;
+        };
+        let normalized = [x, y, w, h].iter().all(|v| (0.0..=1.0).contains(v));
+        let (dx, dy, dw, dh) = if normalized {
+            (x * cw, y * ch, w * cw, h * ch)
         } else {
-            "false".to_string()
-        }),
-        _ => None,
-    }
-}
-
-fn loosely_equal(left: &Value, right: &Value) -> bool {
-    if let (Some(a), Some(b)) = (value_as_f64(left), value_as_f64(right)) {
-        return (a - b).abs() < 1e-6;
-    }
-    if let (Some(a), Some(b)) = (value_as_str(left), value_as_str(right)) {
-        return a == b;
-    }
-    left == right
-}
-
-fn eval_visible_predicate(expr: &str, user_values: &BTreeMap<String, Value>) -> Option<bool> {
-    let e = expr.trim();
-    if e.is_empty() {
-        return None;
-    }
-    if e == "1" {
-        return Some(true);
-    }
-    if e == "0" {
-        return Some(false);
-    }
-
-    // String helpers seen in workshop conditions:
-    // - style.value.contains('1')
-    // - scheme.value.startsWith('dark')
-    // - foo.endsWith('.png')
-    for (marker, kind) in [
-        (".contains(", "contains"),
-        (".startsWith(", "startswith"),
-        (".startswith(", "startswith"),
-        (".endsWith(", "endswith"),
-        (".endswith(", "endswith"),
-    ] {
-        if let Some(idx) = e.find(marker)
-            && e.ends_with(')')
-        {
-            let lhs = &e[..idx];
-            let arg = &e[(idx + marker.len())..e.len() - 1];
-            let left = parse_visible_operand(lhs, user_values)?;
-            let right = parse_visible_operand(arg, user_values)?;
-            let l = value_as_str(&left)?;
-            let r = value_as_str(&right)?;
-            let result = match kind {
-                "contains" => l.contains(&r),
-                "startswith" => l.starts_with(&r),
-                "endswith" => l.ends_with(&r),
-                _ => false,
-            };
-            return Some(result);
-        }
-    }
-
-    let ops = ["==", "!=", ">=", "<=", ">", "<"];
-    for op in ops {
-        if let Some(idx) = e.find(op) {
-            let left = parse_visible_operand(&e[..idx], user_values)?;
-            let right = parse_visible_operand(&e[(idx + op.len())..], user_values)?;
-            return Some(match op {
-                "==" => loosely_equal(&left, &right),
-                "!=" => !loosely_equal(&left, &right),
-                ">=" => value_as_f64(&left)? >= value_as_f64(&right)?,
-                "<=" => value_as_f64(&left)? <= value_as_f64(&right)?,
-                ">" => value_as_f64(&left)? > value_as_f64(&right)?,
-                "<" => value_as_f64(&left)? < value_as_f64(&right)?,
-                _ => false,
-            });
-        }
-    }
-
-    let key = e.trim_end_matches(".value");
-    if let Some(v) = user_values.get(key) {
-        return Some(match v {
-            Value::Bool(b) => *b,
-            Value::Number(n) => n.as_f64().unwrap_or(0.0) != 0.0,
-            Value::String(s) => {
-                !s.trim().is_empty() && s != "0" && !s.eq_ignore_ascii_case("false")
-            }
-            _ => false,
+            (x, y, w, h)
+        };
+        let dest_x = dx.clamp(0.0, cw - 1.0).round() as u32;
+        let dest_y = dy.clamp(0.0, ch - 1.0).round() as u32;
+        let dest_width = (dw.round() as u32).clamp(1, scene_w.max(1) - dest_x);
+        let dest_height = (dh.round() as u32).clamp(1, scene_h.max(1) - dest_y);
+
+        let projection = entry.get("orthogonalprojection");
+        let proj = |key: &str, default: f32| {
+            projection
+                .and_then(|p| p.get(key))
+                .and_then(|v| v.as_f64())
+                .map(|n| n as f32)
+                .filter(|n| *n > 0.0)
+                .unwrap_or(default)
+        };
+        // Without an explicit projection the camera keeps scene units, so the
+        // region shows a same-size window of the scene.
+        let source_width = proj("width", dest_width as f32);
+        let source_height = proj("height", dest_height as f32);
+        let center = entry
+            .get("camera")
+            .and_then(|c| c.get("center"))
+            .or_else(|| entry.get("center"))
+            .and_then(|v| {
+                parse_vec3(v)
+                    .map(|c| [c[0], c[1]])
+                    .or_else(|| parse_vec2(v))
+            })
+            .unwrap_or([cw / 2.0, ch / 2.0]);
+
+        out.push(SceneViewport {
+            index,
+            name: entry
+                .get("name")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string()),
+            dest_x,
+            dest_y,
+            dest_width,
+            dest_height,
+            source_x: center[0] - source_width / 2.0,
+            source_y: center[1] - source_height / 2.0,
+            source_width,
+            source_height,
         });
     }
-    None
-}
-
-fn trim_outer_parens(mut expr: &str) -> &str {
-    loop {
-        let t = expr.trim();
-        if !(t.starts_with('(') && t.ends_with(')')) {
-            return t;
-        }
-        let mut depth = 0i32;
-        let mut in_sq = false;
-        let mut in_dq = false;
-        let mut balanced_outer = false;
-        for (i, ch) in t.char_indices() {
-            match ch {
-                '\'' if !in_dq => in_sq = !in_sq,
-                '"' if !in_sq => in_dq = !in_dq,
-                '(' if !in_sq && !in_dq => depth += 1,
-                ')' if !in_sq && !in_dq => {
-                    depth -= 1;
-                    if depth == 0 {
-                        balanced_outer = i == t.len() - 1;
-                        break;
-                    }
-                }
-                _ => {}
-            }
-        }
-        if balanced_outer {
-            expr = &t[1..t.len() - 1];
-            continue;
-        }
-        return t;
-    }
-}
-
-fn find_top_level_op(expr: &str, op: &str) -> Option<usize> {
-    let bytes = expr.as_bytes();
-    if op.len() != 2 || bytes.len() < 2 {
-        return None;
-    }
-    let mut depth = 0i32;
-    let mut in_sq = false;
-    let mut in_dq = false;
-    let mut i = 0usize;
-    while i + 1 < bytes.len() {
-        let ch = bytes[i] as char;
-        match ch {
-            '\'' if !in_dq => in_sq = !in_sq,
-            '"' if !in_sq => in_dq = !in_dq,
-            '(' if !in_sq && !in_dq => depth += 1,
-            ')' if !in_sq && !in_dq => depth -= 1,
-            _ => {}
-        }
-        if !in_sq
-            && !in_dq
-            && depth == 0
-            && bytes[i] == op.as_bytes()[0]
-            && bytes[i + 1] == op.as_bytes()[1]
-        {
-            return Some(i);
-        }
-        i += 1;
-    }
-    None
-}
-
-fn eval_visible_expr(expr: &str, user_values: &BTreeMap<String, Value>) -> Option<bool> {
-    let mut e = trim_outer_parens(expr).trim();
-    if e.is_empty() {
-        return None;
-    }
-    let mut negate = false;
-    while e.starts_with('!') {
-        negate = !negate;
-        e = trim_outer_parens(e[1..].trim());
-    }
-    if e.is_empty() {
-        return None;
-    }
-
-    let base = if let Some(idx) = find_top_level_op(e, "||") {
-        let left = &e[..idx];
-        let right = &e[idx + 2..];
-        Some(
-            eval_visible_expr(left, user_values).unwrap_or(false)
-                || eval_visible_expr(right, user_values).unwrap_or(false),
-        )
-    } else if let Some(idx) = find_top_level_op(e, "&&") {
-        let left = &e[..idx];
-        let right = &e[idx + 2..];
-        Some(
-            eval_visible_expr(left, user_values).unwrap_or(false)
-                && eval_visible_expr(right, user_values).unwrap_or(false),
-        )
-    } else {
-        eval_visible_predicate(e, user_values)
-    };
-
-    base.map(|v| if negate { !v } else { v })
+    if out.len() > 1 { out } else { Vec::new() }
 }
 
 fn eval_visible_condition(
@@ -353,6 +455,100 @@ fn eval_visible_condition(
     eval_visible_expr(&normalized, user_values)
 }
 
+/// Name of the user property a `visible` binding reads, e.g. `showclock`
+/// for `{"user": "showclock"}` or `style` for `style.value == 1`.
+fn visibility_property(value: Option<&Value>) -> Option<String> {
+    let user = value?.as_object()?.get("user")?;
+    let raw = match user {
+        Value::String(s) => s.as_str(),
+        Value::Object(obj) => obj
+            .get("name")
+            .and_then(|v| v.as_str())
+            .or_else(|| obj.get("condition").and_then(|v| v.as_str()))?,
+        _ => return None,
+    };
+    let name: String = raw
+        .trim_start_matches(|c: char| c == '!' || c == '(' || c.is_whitespace())
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+fn object_parent(object: &Value) -> Option<u64> {
+    match object.get("parent")? {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Objects with children are groups; visibility of each object is its own
+/// `visible` AND every ancestor's, indexed like `objects`.
+fn resolve_object_groups(
+    objects: &[Value],
+    user_values: &BTreeMap<String, Value>,
+) -> (Vec<SceneObjectGroup>, Vec<bool>) {
+    let own: Vec<bool> = objects
+        .iter()
+        .map(|o| parse_object_visible(o.get("visible"), user_values))
+        .collect();
+    let index_by_id: HashMap<u64, usize> = objects
+        .iter()
+        .enumerate()
+        .filter_map(|(i, o)| Some((o.get("id")?.as_u64()?, i)))
+        .collect();
+
+    let effective = (0..objects.len())
+        .map(|i| {
+            let mut visible = own[i];
+            let mut current = i;
+            // Bounded walk so a parent cycle cannot hang the graph build.
+            for _ in 0..objects.len() {
+                let Some(parent) = object_parent(&objects[current])
+                    .and_then(|id| index_by_id.get(&id).copied())
+                    .filter(|&p| p != current)
+                else {
+                    break;
+                };
+                visible &= own[parent];
+                current = parent;
+            }
+            visible
+        })
+        .collect();
+
+    let mut members = BTreeMap::<u64, Vec<u64>>::new();
+    for object in objects {
+        if let (Some(parent), Some(id)) = (
+            object_parent(object),
+            object.get("id").and_then(|v| v.as_u64()),
+        ) && index_by_id.contains_key(&parent)
+        {
+            members.entry(parent).or_default().push(id);
+        }
+    }
+    let groups = members
+        .into_iter()
+        .map(|(id, members)| {
+            let object = &objects[index_by_id[&id]];
+            SceneObjectGroup {
+                id,
+                name: object
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                parent: object_parent(object),
+                visible: own[index_by_id[&id]],
+                visibility_property: visibility_property(object.get("visible")),
+                members,
+            }
+        })
+        .collect();
+    (groups, effective)
+}
+
 fn parse_object_visible(value: Option<&Value>, user_values: &BTreeMap<String, Value>) -> bool {
     let Some(v) = value else {
         return true;
@@ -407,7 +603,7 @@ fn dedup_preserve(mut values: Vec<String>) -> Vec<String> {
     out
 }
 
-fn parse_json_asset(resolver: &AssetResolver, path: &str) -> Option<(Value, String)> {
+pub(crate) fn parse_json_asset(resolver: &AssetResolver, path: &str) -> Option<(Value, String)> {
     let asset = resolver.resolve(path)?;
     let json: Value = serde_json::from_slice(&asset.bytes).ok()?;
     Some((json, asset.resolved_path))
@@ -439,6 +635,83 @@ fn resolve_user_bound_value(v: &Value, user_values: &BTreeMap<String, Value>) ->
     v.clone()
 }
 
+/// Alpha driven by a script or timeline animation may rise above 0 later.
+fn object_alpha_is_animated(object: &Value) -> bool {
+    object
+        .get("alpha")
+        .or_else(|| object.get("opacity"))
+        .and_then(|v| v.as_object())
+        .is_some_and(|map| map.contains_key("script") || map.contains_key("animation"))
+}
+
+/// Removes the pass nodes of objects that cannot be seen, so their textures
+/// are never extracted and they do not reach ffmpeg graphs or native plans.
+/// Visibility is resolved against the user properties the graph is built
+/// with, so a toggled property shows the object again on the next build.
+fn prune_hidden_objects(
+    effect_nodes: &mut Vec<GpuEffectNode>,
+    objects: &[Value],
+) -> Vec<PrunedObject> {
+    let mut pruned = Vec::<PrunedObject>::new();
+    effect_nodes.retain(|node| {
+        let reason = if !node.object_visible {
+            "object hidden (visible=false or hidden group)"
+        } else if node.object_alpha == Some(0.0)
+            && !objects
+                .get(node.object_index)
+                .is_some_and(object_alpha_is_animated)
+        {
+            "object alpha is 0"
+        } else {
+            return true;
+        };
+        match pruned.last_mut() {
+            Some(last) if last.object_index == node.object_index => last.pass_nodes += 1,
+            _ => pruned.push(PrunedObject {
+                object_index: node.object_index,
+                object_id: node.object_id,
+                object_name: node.object_name.clone(),
+                pass_nodes: 1,
+                reason: reason.to_string(),
+            }),
+        }
+        false
+    });
+    pruned
+}
+
+/// Top-level `alpha` (or `opacity` in older scenes), either a plain number
+/// or bound to a user property like `{"user": "fade", "value": 0.5}`.
+fn parse_object_alpha(object: &Value, user_values: &BTreeMap<String, Value>) -> Option<f32> {
+    let raw = object.get("alpha").or_else(|| object.get("opacity"))?;
+    let alpha = match resolve_user_bound_value(raw, user_values) {
+        Value::Number(n) => n.as_f64()?,
+        Value::String(s) => s.trim().parse::<f64>().ok()?,
+        _ => return None,
+    };
+    Some(alpha.clamp(0.0, 1.0) as f32)
+}
+
+/// Scales the material's own `g_UserAlpha` by the object alpha; an
+/// `instanceoverride` alpha applied afterwards still wins.
+fn apply_object_alpha_uniform(uniforms: &mut BTreeMap<String, Value>, object_alpha: Option<f32>) {
+    let Some(alpha) = object_alpha else {
+        return;
+    };
+    let material_alpha = uniforms
+        .get("g_UserAlpha")
+        .and_then(|v| match v {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        })
+        .unwrap_or(1.0);
+    uniforms.insert(
+        "g_UserAlpha".to_string(),
+        Value::from(material_alpha * f64::from(alpha)),
+    );
+}
+
 fn apply_instance_override_uniforms(
     uniforms: &mut BTreeMap<String, Value>,
     instance_override: &Value,
@@ -461,9 +734,56 @@ fn apply_instance_override_uniforms(
     if let Some(size) = map.get("size") {
         uniforms.insert("instance_size".to_string(), size.clone());
     }
+    if let Some(rate) = map.get("rate") {
+        uniforms.insert("instance_rate".to_string(), rate.clone());
+    }
 }
 
-fn shader_candidates(shader: &str, ext: &str) -> Vec<String> {
+/// Audio spectrum arrays referenced in a shader source, in
+/// [`AUDIO_SPECTRUM_UNIFORMS`] order.
+pub(crate) fn shader_audio_uniforms(src: &str) -> Vec<String> {
+    AUDIO_SPECTRUM_UNIFORMS
+        .iter()
+        .filter(|(name, _)| src.contains(name))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Averages (or repeats, when upsampling) `spectrum` into `len` bands.
+fn resample_spectrum(spectrum: &[f32], len: usize) -> Vec<f32> {
+    if spectrum.is_empty() {
+        return vec![0.0; len];
+    }
+    (0..len)
+        .map(|i| {
+            let start = i * spectrum.len() / len;
+            let end = ((i + 1) * spectrum.len() / len).max(start + 1);
+            let bins = &spectrum[start..end];
+            bins.iter().sum::<f32>() / bins.len() as f32
+        })
+        .collect()
+}
+
+fn fill_audio_uniforms(
+    uniforms: &mut BTreeMap<String, Value>,
+    names: &[String],
+    left: &[f32],
+    right: &[f32],
+) {
+    for (name, len) in AUDIO_SPECTRUM_UNIFORMS {
+        if !names.iter().any(|n| n == name) {
+            continue;
+        }
+        let side = if name.ends_with("Left") { left } else { right };
+        let bands = resample_spectrum(side, len)
+            .into_iter()
+            .map(|v| Value::from(f64::from(v.clamp(0.0, 1.0))))
+            .collect();
+        uniforms.insert(name.to_string(), Value::Array(bands));
+    }
+}
+
+pub(crate) fn shader_candidates(shader: &str, ext: &str) -> Vec<String> {
     let s = shader.trim();
     if s.is_empty() {
         return Vec::new();
@@ -500,7 +820,7 @@ fn shader_candidates(shader: &str, ext: &str) -> Vec<String> {
     dedup_preserve(cands)
 }
 
-fn texture_candidates(token: &str) -> Vec<String> {
+pub(crate) fn texture_candidates(token: &str) -> Vec<String> {
     let t = token.trim();
     if t.is_empty() {
         return Vec::new();
@@ -543,7 +863,130 @@ fn texture_candidates(token: &str) -> Vec<String> {
     dedup_preserve(cands)
 }
 
-fn parse_uniform_meta_from_shader(src: &str, stage: &str) -> Vec<ShaderUniformBinding> {
+fn normalize_wrap_mode(raw: &str) -> Option<String> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "repeat" | "wrap" | "tile" => Some("repeat".to_string()),
+        "clamp" | "clamptoedge" | "clamp_to_edge" => Some("clamp".to_string()),
+        _ => None,
+    }
+}
+
+fn wrap_mode_key(object: &Value) -> Option<String> {
+    ["wrap", "texturewrap", "wrapmode"]
+        .iter()
+        .find_map(|key| match object.get(key) {
+            Some(Value::String(s)) => normalize_wrap_mode(s),
+            Some(Value::Array(arr)) => arr
+                .first()
+                .and_then(|v| v.as_str())
+                .and_then(normalize_wrap_mode),
+            _ => None,
+        })
+}
+
+/// Wrap mode from the pass (`wrap`/`texturewrap`/`wrapmode`, string or
+/// per-texture array), else from the primary texture: its `.tex-json` wrap
+/// key or `clampuvs`. `clampuvs: false` only lifts the material's clamp, so
+/// a texture whose own header still carries the clamp flag stays clamped.
+fn parse_texture_wrap(
+    pass: &Value,
+    primary_ref: Option<&str>,
+    resolver: &AssetResolver,
+) -> Option<String> {
+    if let Some(mode) = wrap_mode_key(pass) {
+        return Some(mode);
+    }
+    let primary = primary_ref?.trim();
+    let stem = primary.strip_suffix(".tex").unwrap_or(primary);
+    let candidates = |ext: &str| {
+        [
+            format!("{stem}.{ext}"),
+            format!("materials/{stem}.{ext}"),
+            format!("assets/materials/{stem}.{ext}"),
+        ]
+    };
+    let sidecar = resolver.resolve_first(&candidates("tex-json"))?;
+    let json = serde_json::from_slice::<Value>(&sidecar.bytes).ok()?;
+    if let Some(mode) = wrap_mode_key(&json) {
+        return Some(mode);
+    }
+    let clamp = json
+        .get("clampuvs")
+        .or_else(|| json.get("clampUVs"))
+        .and_then(|v| v.as_bool())?;
+    let texture_clamps = || {
+        resolver
+            .resolve_first(&candidates("tex"))
+            .and_then(|tex| tex_header_flags(&tex.bytes))
+            .is_some_and(|flags| flags & TEX_FLAG_CLAMP_UVS != 0)
+    };
+    Some(
+        if clamp || texture_clamps() {
+            "clamp"
+        } else {
+            "repeat"
+        }
+        .to_string(),
+    )
+}
+
+/// `fbos` of an effect.json: intermediate buffers its passes render into.
+fn parse_render_targets(effect: &Value) -> Vec<RenderTargetSpec> {
+    effect
+        .get("fbos")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|fbo| {
+                    Some(RenderTargetSpec {
+                        name: fbo.get("name")?.as_str()?.to_string(),
+                        scale: fbo
+                            .get("scale")
+                            .and_then(|v| v.as_u64())
+                            .unwrap_or(1)
+                            .max(1) as u32,
+                        format: fbo
+                            .get("format")
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `target` and `bind` of an effect pass. A single bind object is accepted
+/// as well as the usual array.
+fn parse_pass_routing(effect_pass: &Value, render_targets: &[RenderTargetSpec]) -> PassRouting {
+    let binds = match effect_pass.get("bind") {
+        Some(Value::Array(arr)) => arr.iter().collect::<Vec<_>>(),
+        Some(bind @ Value::Object(_)) => vec![bind],
+        _ => Vec::new(),
+    };
+    let mut binds = binds
+        .into_iter()
+        .filter_map(|b| {
+            Some(RenderTargetBind {
+                name: b.get("name")?.as_str()?.to_string(),
+                index: b.get("index").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+            })
+        })
+        .collect::<Vec<_>>();
+    binds.sort_by_key(|b| b.index);
+    PassRouting {
+        target: effect_pass
+            .get("target")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string()),
+        binds,
+        render_targets: render_targets.to_vec(),
+    }
+}
+
+pub(crate) fn parse_uniform_meta_from_shader(src: &str, stage: &str) -> Vec<ShaderUniformBinding> {
     let mut out = Vec::<ShaderUniformBinding>::new();
 
     for raw_line in src.lines() {
@@ -806,6 +1249,23 @@ fn effect_override_for_material_pass(
 }
 
 pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
+    build_scene_gpu_graph_with(root, &BTreeMap::new(), GraphDetail::Full)
+}
+
+/// Same as [`build_scene_gpu_graph`], with `overrides` (from `property set`)
+/// taking precedence over the project defaults and scene bindings.
+pub fn build_scene_gpu_graph_with_overrides(
+    root: &Path,
+    overrides: &BTreeMap<String, Value>,
+) -> Result<SceneGpuGraph> {
+    build_scene_gpu_graph_with(root, overrides, GraphDetail::Full)
+}
+
+pub fn build_scene_gpu_graph_with(
+    root: &Path,
+    overrides: &BTreeMap<String, Value>,
+    detail: GraphDetail,
+) -> Result<SceneGpuGraph> {
     let resolver = AssetResolver::new(root)?;
 
     let Some(scene_asset) = resolver
@@ -815,15 +1275,28 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
         bail!("No scene.json/gifscene.json found in {}", root.display());
     };
 
-    let scene_json: Value = serde_json::from_slice(&scene_asset.bytes)?;
+    let mut scene_json: Value = serde_json::from_slice(&scene_asset.bytes)?;
     let project_json = resolver
         .resolve("project.json")
         .and_then(|v| serde_json::from_slice::<Value>(&v.bytes).ok());
 
-    let (scene_width, scene_height) = parse_scene_size(&scene_json);
     let mut notes = Vec::<String>::new();
+    if let Some(quirk) = quirk_for_wallpaper(root, project_json.as_ref()) {
+        notes.extend(apply_scene_quirks(&mut scene_json, &quirk));
+    }
+    if let Some(layer_override) = layer_override_for_wallpaper(root, project_json.as_ref()) {
+        notes.extend(apply_layer_overrides(&mut scene_json, &layer_override));
+    }
+    let (scene_width, scene_height) = parse_scene_size(&scene_json);
 
-    let user_values = collect_scene_user_properties(&scene_json, project_json.as_ref());
+    let mut user_values = collect_scene_user_properties(&scene_json, project_json.as_ref());
+    if !overrides.is_empty() {
+        notes.push(format!(
+            "Applied {} user property override(s)",
+            overrides.len()
+        ));
+        user_values.extend(overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
     let script_eval = apply_scene_scripts(&scene_json, &user_values);
     notes.extend(script_eval.notes.clone());
     let mut script_values = BTreeMap::<String, Value>::new();
@@ -834,7 +1307,58 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
     }
 
     let mut effect_nodes = Vec::<GpuEffectNode>::new();
+    let mut groups = Vec::new();
+    let mut clock_hands = Vec::new();
+    let mut script_animations = Vec::new();
+    let mut pruned = Vec::new();
+    let mut sounds = Vec::new();
     if let Some(objects) = scene_json.get("objects").and_then(|v| v.as_array()) {
+        let (object_groups, visibility) = resolve_object_groups(objects, &user_values);
+        groups = object_groups;
+        clock_hands = objects
+            .iter()
+            .enumerate()
+            .filter_map(|(object_index, object)| {
+                let hand = detect_clock_hand(object.get("angles")?)?;
+                Some(SceneClockHand {
+                    object_index,
+                    object_id: object.get("id").and_then(|v| v.as_u64()).unwrap_or(0),
+                    object_name: object
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    hand,
+                })
+            })
+            .collect();
+        script_animations = objects
+            .iter()
+            .enumerate()
+            .flat_map(|(object_index, object)| {
+                let object_id = object.get("id").and_then(|v| v.as_u64()).unwrap_or(0);
+                let object_name = object
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                detect_object_animations(object)
+                    .into_iter()
+                    .map(move |animation| SceneScriptAnimation {
+                        object_index,
+                        object_id,
+                        object_name: object_name.clone(),
+                        animation,
+                    })
+            })
+            .collect();
+        sounds = objects
+            .iter()
+            .enumerate()
+            .filter_map(|(object_index, object)| {
+                parse_scene_sound(object_index, object, visibility[object_index], &user_values)
+            })
+            .collect();
         for (object_index, object) in objects.iter().enumerate() {
             let object_id = object.get("id").and_then(|v| v.as_u64()).unwrap_or(0);
             let object_name = object
@@ -856,7 +1380,8 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
             let object_angles = object.get("angles").and_then(parse_vec3);
             let object_size = object.get("size").and_then(parse_vec2);
             let object_parallax_depth = object.get("parallaxDepth").and_then(parse_vec2);
-            let object_visible = parse_object_visible(object.get("visible"), &user_values);
+            let object_visible = visibility[object_index];
+            let object_alpha = parse_object_alpha(object, &user_values);
             let instance_override = object
                 .get("instanceoverride")
                 .map(|v| resolve_user_bound_value(v, &user_values))
@@ -909,7 +1434,8 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                  material_asset_resolved: &str,
                  effect_file: &str,
                  effect_name: &str,
-                 effect_index: Option<usize>| {
+                 effect_index: Option<usize>,
+                 routing: &PassRouting| {
                     for pass in passes_data {
                         let shader_name = pass
                             .get("shader")
@@ -917,11 +1443,18 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                             .unwrap_or_default()
                             .to_string();
 
-                        let shader_vert = resolver
-                            .resolve_first(&shader_candidates(&shader_name, "vert"))
+                        let full = detail == GraphDetail::Full;
+                        let shader_vert = full
+                            .then(|| {
+                                resolver.resolve_first(&shader_candidates(&shader_name, "vert"))
+                            })
+                            .flatten()
                             .map(|a| a.resolved_path);
-                        let shader_frag = resolver
-                            .resolve_first(&shader_candidates(&shader_name, "frag"))
+                        let shader_frag = full
+                            .then(|| {
+                                resolver.resolve_first(&shader_candidates(&shader_name, "frag"))
+                            })
+                            .flatten()
                             .map(|a| a.resolved_path);
 
                         let texture_refs = pass
@@ -936,37 +1469,49 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
 
                         let mut textures = Vec::<String>::new();
                         for tex in &texture_refs {
-                            let resolved = resolver
-                                .resolve_first(&texture_candidates(tex))
+                            let resolved = full
+                                .then(|| resolver.resolve_first(&texture_candidates(tex)))
+                                .flatten()
                                 .map(|a| a.resolved_path)
                                 .unwrap_or_else(|| tex.to_string());
                             textures.push(resolved);
                         }
 
                         let mut uniform_bindings = Vec::<ShaderUniformBinding>::new();
+                        let mut audio_uniforms = Vec::<String>::new();
                         if let Some(v) = &shader_vert
                             && let Some(asset) = resolver.resolve(v)
                             && let Ok(src) = String::from_utf8(asset.bytes)
                         {
                             uniform_bindings.extend(parse_uniform_meta_from_shader(&src, "vert"));
+                            audio_uniforms.extend(shader_audio_uniforms(&src));
                         }
                         if let Some(v) = &shader_frag
                             && let Some(asset) = resolver.resolve(v)
                             && let Ok(src) = String::from_utf8(asset.bytes)
                         {
                             uniform_bindings.extend(parse_uniform_meta_from_shader(&src, "frag"));
+                            audio_uniforms.extend(shader_audio_uniforms(&src));
+                        }
+                        audio_uniforms.sort();
+                        audio_uniforms.dedup();
+
+                        let mut effective_uniforms = BTreeMap::new();
+                        if full {
+                            effective_uniforms = resolve_uniform_values(
+                                pass,
+                                &uniform_bindings,
+                                &user_values,
+                                &script_values,
+                            );
+                            apply_object_alpha_uniform(&mut effective_uniforms, object_alpha);
+                            apply_instance_override_uniforms(
+                                &mut effective_uniforms,
+                                &instance_override,
+                            );
+                            // No live audio while building the graph: silent spectrum.
+                            fill_audio_uniforms(&mut effective_uniforms, &audio_uniforms, &[], &[]);
                         }
-
-                        let mut effective_uniforms = resolve_uniform_values(
-                            pass,
-                            &uniform_bindings,
-                            &user_values,
-                            &script_values,
-                        );
-                        apply_instance_override_uniforms(
-                            &mut effective_uniforms,
-                            &instance_override,
-                        );
 
                         let pass_spec = GpuPassSpec {
                             pass_index: pipeline_pass_index,
@@ -999,9 +1544,17 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                                 .get("usershadervalues")
                                 .cloned()
                                 .unwrap_or(Value::Null),
+                            texture_wrap: parse_texture_wrap(
+                                pass,
+                                texture_refs.first().map(String::as_str),
+                                &resolver,
+                            ),
                             textures,
                             texture_refs,
                             effective_uniforms,
+                            target: routing.target.clone(),
+                            binds: routing.binds.clone(),
+                            audio_uniforms,
                         };
 
                         effect_nodes.push(GpuEffectNode {
@@ -1017,6 +1570,7 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                             object_asset_size,
                             object_parallax_depth,
                             object_visible,
+                            object_alpha,
                             effect_index,
                             instance_override: instance_override.clone(),
                             effect_file: effect_file.to_string(),
@@ -1029,6 +1583,7 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                             shader_frag,
                             material_json: Some(material_asset_resolved.to_string()),
                             uniform_bindings,
+                            render_targets: routing.render_targets.clone(),
                         });
                         pipeline_pass_index += 1;
                     }
@@ -1041,6 +1596,7 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                 &material_asset_resolved,
                 "base-material",
                 None,
+                &PassRouting::default(),
             );
 
             if let Some(object_effects) = object.get("effects").and_then(|v| v.as_array()) {
@@ -1071,6 +1627,7 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                         .and_then(|v| v.as_array())
                         .cloned()
                         .unwrap_or_default();
+                    let render_targets = parse_render_targets(&effect_data);
 
                     for (effect_pass_idx, effect_pass) in effect_passes.iter().enumerate() {
                         let Some(effect_material_ref) =
@@ -1117,13 +1674,25 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
                             &effect_file_resolved,
                             effect_name,
                             Some(effect_idx),
+                            &parse_pass_routing(effect_pass, &render_targets),
                         );
                     }
                 }
             }
         }
+        pruned = prune_hidden_objects(&mut effect_nodes, objects);
     }
 
+    if !pruned.is_empty() {
+        notes.push(format!(
+            "Pruned {} hidden object(s) ({} pass nodes)",
+            pruned.len(),
+            pruned.iter().map(|p| p.pass_nodes).sum::<usize>()
+        ));
+    }
+    if !sounds.is_empty() {
+        notes.push(format!("{} ambient sound object(s)", sounds.len()));
+    }
     if effect_nodes.is_empty() {
         notes.push("No material/pass nodes were generated from scene objects".to_string());
     } else {
@@ -1133,8 +1702,19 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
         ));
     }
     notes.push("Graph source: scene -> model/particle -> material -> passes -> shader".to_string());
+    if detail == GraphDetail::Summary {
+        notes.push("Summary detail: shaders, textures and uniforms not resolved".to_string());
+    }
+    let viewports = parse_scene_viewports(&scene_json, scene_width, scene_height);
+    if !viewports.is_empty() {
+        notes.push(format!(
+            "Split scene: {} viewports composited into the canvas",
+            viewports.len()
+        ));
+    }
 
     Ok(SceneGpuGraph {
+        detail,
         pkg_path: resolver.pkg_path().unwrap_or_default(),
         scene_json_entry: scene_asset.resolved_path,
         scene_width,
@@ -1144,15 +1724,126 @@ pub fn build_scene_gpu_graph(root: &Path) -> Result<SceneGpuGraph> {
         script_properties: to_json_object(&script_values),
         script_assignments: script_eval.assignments,
         effect_nodes,
+        viewports,
+        groups,
+        clock_hands,
+        script_animations,
+        pruned,
+        sounds,
         notes,
     })
 }
 
+/// Whether any pass shader reads the audio spectrum arrays. On a summary
+/// graph each distinct shader is read once, without uniform parsing.
+pub fn graph_reads_audio_spectrum(root: &Path, graph: &SceneGpuGraph) -> bool {
+    if graph.detail == GraphDetail::Full {
+        return graph
+            .effect_nodes
+            .iter()
+            .flat_map(|node| &node.passes)
+            .any(|pass| !pass.audio_uniforms.is_empty());
+    }
+    let Ok(resolver) = AssetResolver::new(root) else {
+        return false;
+    };
+    let shaders = graph
+        .effect_nodes
+        .iter()
+        .map(|node| node.pass_shader.as_str())
+        .collect::<BTreeSet<_>>();
+    shaders.into_iter().any(|shader| {
+        ["vert", "frag"].iter().any(|ext| {
+            resolver
+                .resolve_first(&shader_candidates(shader, ext))
+                .is_some_and(|asset| {
+                    !shader_audio_uniforms(&String::from_utf8_lossy(&asset.bytes)).is_empty()
+                })
+        })
+    })
+}
+
+/// Writes `(path, body)` pairs under `root`, the unpacked scene layout
+/// `build_scene_gpu_graph` reads.
+#[cfg(test)]
+pub(crate) fn write_scene_files(root: &Path, files: &[(&str, &str)]) {
+    for (name, body) in files {
+        let path = root.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::BTreeMap;
 
+    #[test]
+    fn texture_wrap_from_pass_or_tex_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("materials")).unwrap();
+        std::fs::write(
+            dir.path().join("materials/tiles.tex-json"),
+            r#"{"clampuvs": false}"#,
+        )
+        .unwrap();
+        let resolver = AssetResolver::new(dir.path()).unwrap();
+
+        let plain = serde_json::json!({"textures": ["tiles"]});
+        assert_eq!(
+            parse_texture_wrap(&plain, Some("tiles"), &resolver).as_deref(),
+            Some("repeat")
+        );
+        let clamped = serde_json::json!({"wrap": "ClampToEdge"});
+        assert_eq!(
+            parse_texture_wrap(&clamped, Some("tiles"), &resolver).as_deref(),
+            Some("clamp")
+        );
+        assert_eq!(parse_texture_wrap(&plain, Some("other"), &resolver), None);
+
+        // The texture's own header flag wins over `clampuvs: false`.
+        let mut tex = b"TEXV0005\0TEXI0001\0".to_vec();
+        tex.extend([0, 0, 0, 0]);
+        tex.extend(TEX_FLAG_CLAMP_UVS.to_le_bytes());
+        std::fs::write(dir.path().join("materials/tiles.tex"), &tex).unwrap();
+        let resolver = AssetResolver::new(dir.path()).unwrap();
+        assert_eq!(
+            parse_texture_wrap(&plain, Some("tiles"), &resolver).as_deref(),
+            Some("clamp")
+        );
+        std::fs::write(
+            dir.path().join("materials/tiles.tex-json"),
+            r#"{"clampuvs": false, "wrap": "repeat"}"#,
+        )
+        .unwrap();
+        let resolver = AssetResolver::new(dir.path()).unwrap();
+        assert_eq!(
+            parse_texture_wrap(&plain, Some("tiles"), &resolver).as_deref(),
+            Some("repeat")
+        );
+    }
+
+    #[test]
+    fn split_scene_viewports_are_detected() {
+        let scene = serde_json::json!({
+            "general": {"viewports": [
+                {"name": "left", "viewport": "0 0 0.5 1", "camera": {"center": "480 540 0"}},
+                {"x": 960, "y": 0, "width": 960, "height": 1080,
+                 "orthogonalprojection": {"width": 1920, "height": 2160}}
+            ]}
+        });
+        let vps = parse_scene_viewports(&scene, 1920, 1080);
+        assert_eq!(vps.len(), 2);
+        assert_eq!((vps[0].dest_x, vps[0].dest_width), (0, 960));
+        assert_eq!((vps[0].source_x, vps[0].source_width), (0.0, 960.0));
+        assert_eq!((vps[1].dest_x, vps[1].dest_height), (960, 1080));
+        assert_eq!((vps[1].source_x, vps[1].source_y), (0.0, -540.0));
+
+        let single = serde_json::json!({"cameras": [{"viewport": [0, 0, 1, 1]}]});
+        assert!(parse_scene_viewports(&single, 1920, 1080).is_empty());
+    }
+
     #[test]
     fn visible_condition_supports_or_and_parens() {
         let mut users = BTreeMap::<String, Value>::new();
@@ -1239,6 +1930,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn legacy_object_alpha_folds_into_user_alpha() {
+        let mut users = BTreeMap::<String, Value>::new();
+        users.insert("fade".to_string(), Value::from(0.5));
+        let bound = serde_json::json!({"alpha": {"user": "fade", "value": 1.0}});
+        let legacy = serde_json::json!({"opacity": "0.25"});
+        assert_eq!(parse_object_alpha(&bound, &users), Some(0.5));
+        assert_eq!(parse_object_alpha(&legacy, &users), Some(0.25));
+        assert_eq!(parse_object_alpha(&serde_json::json!({}), &users), None);
+
+        let pass = serde_json::json!({"constantshadervalues": {"Alpha": 0.8}});
+        let mut uniforms = resolve_uniform_values(&pass, &[], &users, &BTreeMap::new());
+        apply_object_alpha_uniform(&mut uniforms, Some(0.5));
+        assert_eq!(uniforms.get("g_UserAlpha"), Some(&Value::from(0.4)));
+        apply_instance_override_uniforms(&mut uniforms, &serde_json::json!({"alpha": 0.9}));
+        assert_eq!(uniforms.get("g_UserAlpha"), Some(&Value::from(0.9)));
+    }
+
+    #[test]
+    fn audio_spectrum_arrays_get_uniform_slots() {
+        let src =
+            "uniform float g_AudioSpectrum16Left[16];\nuniform float g_AudioSpectrum64Right[64];\n";
+        let names = shader_audio_uniforms(src);
+        assert_eq!(names, ["g_AudioSpectrum16Left", "g_AudioSpectrum64Right"]);
+
+        let mut uniforms = BTreeMap::new();
+        fill_audio_uniforms(&mut uniforms, &names, &[], &[]);
+        assert_eq!(uniforms.len(), 2);
+        assert_eq!(
+            uniforms["g_AudioSpectrum16Left"],
+            Value::Array(vec![Value::from(0.0); 16])
+        );
+
+        let left = (0..32)
+            .map(|i| if i < 2 { 1.0 } else { 0.0 })
+            .collect::<Vec<f32>>();
+        fill_audio_uniforms(&mut uniforms, &names, &left, &[0.5, 2.0]);
+        let low = uniforms["g_AudioSpectrum16Left"].as_array().unwrap();
+        assert_eq!((low[0].as_f64(), low[1].as_f64()), (Some(1.0), Some(0.0)));
+        let high = uniforms["g_AudioSpectrum64Right"].as_array().unwrap();
+        assert_eq!(high.len(), 64);
+        assert_eq!(
+            (high[0].as_f64(), high[63].as_f64()),
+            (Some(0.5), Some(1.0))
+        );
+    }
+
     #[test]
     fn shader_candidates_include_workshop_convention() {
         let cands = shader_candidates("effects/workshop/123456/scroll", "vert");
@@ -1307,6 +2045,24 @@ mod tests {
         assert_eq!(b0["constantshadervalues"]["p"], Value::from(3));
     }
 
+    #[test]
+    fn hidden_groups_hide_their_descendants() {
+        let users = BTreeMap::from([("showclock".to_string(), serde_json::json!(false))]);
+        let objects = vec![
+            serde_json::json!({"id": 1, "name": "Clock", "visible": {"user": "showclock", "value": true}}),
+            serde_json::json!({"id": 2, "name": "Digits", "parent": 1, "image": "models/d.json"}),
+            serde_json::json!({"id": 3, "name": "Hands", "parent": "2", "image": "models/h.json"}),
+            serde_json::json!({"id": 4, "name": "Background", "image": "models/bg.json"}),
+        ];
+        let (groups, visible) = resolve_object_groups(&objects, &users);
+        assert_eq!(visible, vec![false, false, false, true]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].id, 1);
+        assert_eq!(groups[0].members, vec![2]);
+        assert_eq!(groups[0].visibility_property.as_deref(), Some("showclock"));
+        assert_eq!(groups[1].parent, Some(1));
+    }
+
     #[test]
     fn effect_visible_user_condition_is_evaluated() {
         let mut users = BTreeMap::<String, Value>::new();
@@ -1317,4 +2073,87 @@ mod tests {
         });
         assert!(parse_object_visible(Some(&visible), &users));
     }
+
+    #[test]
+    fn hidden_and_transparent_objects_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "scene.json",
+                r#"{"objects": [
+                    {"id": 1, "name": "bg", "image": "models/a.json"},
+                    {"id": 2, "name": "off", "image": "models/a.json", "visible": false},
+                    {"id": 3, "name": "clear", "image": "models/a.json", "alpha": 0.0},
+                    {"id": 4, "name": "fade-in", "image": "models/a.json",
+                     "alpha": {"value": 0.0, "script": "export function update() { return 1; }"}},
+                    {"id": 5, "name": "user", "image": "models/a.json",
+                     "visible": {"user": "showlogo", "value": true}}
+                ]}"#,
+            ),
+            (
+                "project.json",
+                r#"{"general": {"properties": {"showlogo": {"type": "bool", "value": false}}}}"#,
+            ),
+            ("models/a.json", r#"{"material": "materials/a.json"}"#),
+            (
+                "materials/a.json",
+                r#"{"passes": [{"shader": "genericimage2", "textures": ["a"]}]}"#,
+            ),
+        ];
+        write_scene_files(dir.path(), &files);
+
+        let graph = build_scene_gpu_graph(dir.path()).unwrap();
+        let kept = graph
+            .effect_nodes
+            .iter()
+            .map(|n| n.object_id)
+            .collect::<Vec<_>>();
+        assert_eq!(kept, [1, 4]);
+        let pruned = graph
+            .pruned
+            .iter()
+            .map(|p| (p.object_id, p.pass_nodes, p.reason.contains("alpha")))
+            .collect::<Vec<_>>();
+        assert_eq!(pruned, [(2, 1, false), (3, 1, true), (5, 1, false)]);
+
+        let mut overrides = BTreeMap::new();
+        overrides.insert("showlogo".to_string(), Value::Bool(true));
+        let shown = build_scene_gpu_graph_with_overrides(dir.path(), &overrides).unwrap();
+        assert!(shown.effect_nodes.iter().any(|n| n.object_id == 5));
+    }
+
+    #[test]
+    fn summary_graphs_skip_shaders_but_keep_counts() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "scene.json",
+                r#"{"objects": [{"id": 1, "name": "bg", "image": "models/a.json"}]}"#,
+            ),
+            ("models/a.json", r#"{"material": "materials/a.json"}"#),
+            (
+                "materials/a.json",
+                r#"{"passes": [{"shader": "bars", "textures": ["a"]}]}"#,
+            ),
+            (
+                "shaders/bars.frag",
+                "uniform float g_AudioSpectrum16Left[16];\nuniform float u_Speed; // {\"material\":\"speed\",\"default\":2}\n",
+            ),
+        ];
+        write_scene_files(dir.path(), &files);
+
+        let full = build_scene_gpu_graph(dir.path()).unwrap();
+        let summary =
+            build_scene_gpu_graph_with(dir.path(), &BTreeMap::new(), GraphDetail::Summary).unwrap();
+        assert_eq!(summary.effect_nodes.len(), full.effect_nodes.len());
+        assert_eq!(summary.effect_nodes[0].pass_shader, "bars");
+        assert!(full.effect_nodes[0].shader_frag.is_some());
+        assert!(!full.effect_nodes[0].passes[0].effective_uniforms.is_empty());
+        let node = &summary.effect_nodes[0];
+        assert!(node.shader_frag.is_none());
+        assert!(node.uniform_bindings.is_empty());
+        assert!(node.passes[0].effective_uniforms.is_empty());
+        assert!(graph_reads_audio_spectrum(dir.path(), &full));
+        assert!(graph_reads_audio_spectrum(dir.path(), &summary));
+    }
 }
@@ -0,0 +1,827 @@
+use crate::audio::infer_default_monitor_source;
+use crate::cli::{AudioBarsSource, GpuTransport, RealtimeMotion};
+use crate::colorspace::tag_srgb_output;
+use crate::command_runner::{CommandExt, shell_command_line, shell_quote};
+use crate::custom_profiles::profile_proxy_settings;
+use crate::i18n::{Msg, warn_msg};
+use crate::scene_effect_proxy::{
+    build_scene_realtime_effect_plan, maybe_build_scene_animated_proxy,
+};
+use crate::scene_gpu_backend::SceneGpuPlayArgs;
+use crate::scene_native_renderer::{
+    NativeFrameStream, clock_hands_filter, render_native_animated_proxy, render_native_static_frame,
+};
+use crate::scene_native_runtime::NativeRuntimePlan;
+use crate::video_opt::{maybe_build_audio_reactive_proxy, maybe_build_optimized_proxy};
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// Everything a transport may need from the already-resolved scene session.
+pub struct TransportContext<'a> {
+    pub args: &'a SceneGpuPlayArgs,
+    pub session_dir: &'a Path,
+    /// Playable base visual (video or extracted image) for proxy-style transports.
+    pub entry_to_launch: &'a str,
+    pub music_path: Option<&'a Path>,
+    pub scene_width: u32,
+    pub scene_height: u32,
+    pub native_plan: &'a NativeRuntimePlan,
+    pub native_plan_path: &'a Path,
+    /// Output is rotated: proxy transports should build a portrait canvas.
+    pub portrait: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransportEntry {
+    /// What mpvpaper should open (file path or stream URL).
+    pub entry: String,
+    pub effective_transport: String,
+    pub native_report_path: Option<String>,
+    /// The entry was rendered on the portrait canvas (text must follow it).
+    pub portrait_canvas: bool,
+    /// mpv `vf` entry composited live over the entry (clock hands).
+    pub live_filter: Option<String>,
+    /// Extra lines for the gpu session manifest.
+    pub manifest_notes: Vec<String>,
+}
+
+pub trait Transport {
+    fn name(&self) -> &'static str;
+
+    /// Builds the media for mpvpaper. `Ok(None)` means this transport cannot
+    /// serve the scene and the next one in the chain should be tried.
+    fn prepare(&mut self, ctx: &TransportContext) -> Result<Option<TransportEntry>>;
+
+    /// Checks the prepared entry is still alive; `false` moves on to the next transport.
+    fn supervise(&mut self, _ctx: &TransportContext) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Releases whatever `prepare` started (background encoders, pid files).
+    fn teardown(&mut self, _ctx: &TransportContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Registered transports for `kind`, in the order they are attempted.
+pub fn transport_chain(kind: GpuTransport, require_native: bool) -> Vec<Box<dyn Transport>> {
+    match kind {
+        GpuTransport::Mp4Proxy => vec![Box::new(Mp4ProxyTransport { fallback: false })],
+        GpuTransport::NativeRealtime => {
+            let mut chain: Vec<Box<dyn Transport>> = vec![
+                Box::new(NativeAnimatedTransport),
+                Box::new(NativeStaticTransport),
+                Box::new(FfmpegRealtimeTransport::default()),
+            ];
+            if !require_native {
+                chain.push(Box::new(Mp4ProxyTransport { fallback: true }));
+            }
+            chain
+        }
+        GpuTransport::NativeStream => {
+            let mut chain: Vec<Box<dyn Transport>> = vec![
+                Box::new(NativeStreamTransport::default()),
+                Box::new(NativeAnimatedTransport),
+                Box::new(NativeStaticTransport),
+            ];
+            if !require_native {
+                chain.push(Box::new(Mp4ProxyTransport { fallback: true }));
+            }
+            chain
+        }
+    }
+}
+
+pub fn run_transport_chain(
+    chain: &mut [Box<dyn Transport>],
+    ctx: &TransportContext,
+) -> Result<TransportEntry> {
+    for transport in chain.iter_mut() {
+        let Some(entry) = transport.prepare(ctx)? else {
+            continue;
+        };
+        if transport.supervise(ctx)? {
+            return Ok(entry);
+        }
+        transport.teardown(ctx)?;
+    }
+    bail!(
+        "no transport could serve this scene (tried: {})",
+        chain
+            .iter()
+            .map(|t| t.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+pub struct Mp4ProxyTransport {
+    pub fallback: bool,
+}
+
+impl Transport for Mp4ProxyTransport {
+    fn name(&self) -> &'static str {
+        "mp4-proxy"
+    }
+
+    fn prepare(&mut self, ctx: &TransportContext) -> Result<Option<TransportEntry>> {
+        let args = ctx.args;
+        if !ctx.native_plan.viewports.is_empty() {
+            warn_msg!(Msg::SplitSceneUnsplitProxy, ctx.native_plan.viewports.len());
+        }
+        let mut portrait_canvas = false;
+        let animated_entry = match maybe_build_scene_animated_proxy(
+            &args.root,
+            ctx.session_dir,
+            Path::new(ctx.entry_to_launch),
+            args.proxy_seconds,
+            ctx.portrait,
+            args.custom_filters.as_ref(),
+            args.dry_run,
+        )? {
+            Some(p) => {
+                portrait_canvas = ctx.portrait;
+                p
+            }
+            None => PathBuf::from(ctx.entry_to_launch),
+        };
+        let (proxy_width, proxy_fps, proxy_crf) = profile_proxy_settings(
+            args.profile,
+            args.proxy_width,
+            args.proxy_fps,
+            args.proxy_crf,
+        );
+        let optimized = maybe_build_optimized_proxy(
+            &animated_entry,
+            ctx.session_dir,
+            proxy_width,
+            proxy_fps,
+            proxy_crf,
+            args.dry_run,
+        )?;
+        let entry = if !args.audio_reactive {
+            optimized
+        } else if args.mute_audio {
+            warn_msg!(Msg::AudioReactiveMuted);
+            optimized
+        } else if let Some(music) = ctx.music_path {
+            maybe_build_audio_reactive_proxy(
+                &optimized,
+                music,
+                ctx.session_dir,
+                args.audio_reactive_strength,
+                args.dry_run,
+            )?
+        } else {
+            warn_msg!(Msg::AudioReactiveNoMusic);
+            optimized
+        };
+        Ok(Some(TransportEntry {
+            entry: entry.to_string_lossy().to_string(),
+            effective_transport: if self.fallback {
+                "mp4-proxy (fallback)".to_string()
+            } else {
+                "mp4-proxy".to_string()
+            },
+            native_report_path: None,
+            portrait_canvas,
+            live_filter: None,
+            manifest_notes: Vec::new(),
+        }))
+    }
+}
+
+pub struct NativeAnimatedTransport;
+
+impl Transport for NativeAnimatedTransport {
+    fn name(&self) -> &'static str {
+        "native-animated-layered"
+    }
+
+    fn prepare(&mut self, ctx: &TransportContext) -> Result<Option<TransportEntry>> {
+        if ctx.native_plan.ready_draw_layers == 0 {
+            return Ok(None);
+        }
+        let report = render_native_animated_proxy(
+            &ctx.args.root,
+            ctx.session_dir,
+            ctx.scene_width,
+            ctx.scene_height,
+            ctx.args.proxy_seconds,
+            ctx.args.proxy_fps,
+            ctx.args.dry_run,
+            ctx.native_plan,
+        )?;
+        Ok(report.map(|report| TransportEntry {
+            live_filter: clock_hands_filter(
+                &report.clock_hands,
+                Local::now().offset().local_minus_utc(),
+            ),
+            manifest_notes: (report.ffmpeg_passes > 1)
+                .then(|| {
+                    format!(
+                        "Native filter graph split into {} ffmpeg passes",
+                        report.ffmpeg_passes
+                    )
+                })
+                .into_iter()
+                .collect(),
+            entry: report.output_video,
+            effective_transport: self.name().to_string(),
+            native_report_path: Some(report.report_path),
+            portrait_canvas: false,
+        }))
+    }
+}
+
+pub struct NativeStaticTransport;
+
+impl Transport for NativeStaticTransport {
+    fn name(&self) -> &'static str {
+        "native-static-layered"
+    }
+
+    fn prepare(&mut self, ctx: &TransportContext) -> Result<Option<TransportEntry>> {
+        if ctx.native_plan.ready_draw_layers == 0 {
+            return Ok(None);
+        }
+        let report = render_native_static_frame(
+            &ctx.args.root,
+            ctx.session_dir,
+            ctx.scene_width,
+            ctx.scene_height,
+            ctx.native_plan,
+        )?;
+        Ok(report.map(|report| TransportEntry {
+            native_report_path: Some(
+                Path::new(&report.output_image)
+                    .parent()
+                    .map(|p| {
+                        p.join("native_static_report.json")
+                            .to_string_lossy()
+                            .to_string()
+                    })
+                    .unwrap_or_default(),
+            ),
+            entry: report.output_image,
+            effective_transport: self.name().to_string(),
+            portrait_canvas: false,
+            live_filter: None,
+            manifest_notes: Vec::new(),
+        }))
+    }
+}
+
+const NATIVE_FFMPEG_LOG: &str = "/tmp/kwe-native-ffmpeg.log";
+
+/// Live ffmpeg filter graph streamed to mpvpaper over UDP.
+#[derive(Default)]
+pub struct FfmpegRealtimeTransport {
+    /// ffmpeg pid and the stream URL its command line names.
+    child: Option<(u32, String)>,
+}
+
+fn cache_key_for_root(root: &Path) -> String {
+    root.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().replace('/', "_"))
+}
+
+fn realtime_pid_path(root: &Path) -> PathBuf {
+    crate::scene_pkg::default_scene_cache_root(&cache_key_for_root(root))
+        .join("gpu/realtime-ffmpeg.pid")
+}
+
+fn realtime_stream_url(root: &Path) -> String {
+    let port_base = 19000u16;
+    let mut hash: u32 = 0;
+    for b in cache_key_for_root(root).as_bytes() {
+        hash = hash.wrapping_mul(31).wrapping_add(*b as u32);
+    }
+    let port = port_base + (hash % 5000) as u16;
+    format!("udp://127.0.0.1:{}", port)
+}
+
+/// Whether `pid` is still a process of ours: its command line must contain
+/// `marker` (a stream URL or FIFO path unique to the wallpaper), so a pid
+/// reused by another program after ours exited is never taken for it.
+fn pid_runs(pid: u32, marker: &str) -> bool {
+    std::fs::read(format!("/proc/{}/cmdline", pid)).is_ok_and(|cmdline| {
+        cmdline
+            .split(|b| *b == 0)
+            .any(|arg| arg == marker.as_bytes())
+    })
+}
+
+fn kill_pid(pid: u32, marker: &str) {
+    if pid_runs(pid, marker) {
+        let _ = Command::new("kill").arg(pid.to_string()).run_status();
+    }
+}
+
+/// Appends the motion stage to a realtime graph ending in `[v]`, returning
+/// the graph (now ending in `[vout]`) and the output rate. Dropped frames
+/// leave gaps in the timestamps, so mpv holds the previous frame instead of
+/// the encoder spending time on a duplicate; `max` still lets one frame
+/// through per second so the stream never stalls.
+pub fn realtime_motion_filter(
+    filter_complex: &str,
+    motion: RealtimeMotion,
+    fps: u32,
+) -> (String, u32) {
+    let fps = fps.max(1);
+    match motion {
+        RealtimeMotion::Full => (format!("{};[v]null[vout]", filter_complex), fps),
+        RealtimeMotion::Adaptive => (
+            format!(
+                "{};[v]mpdecimate=max={}[vout]",
+                filter_complex,
+                fps.saturating_sub(1).max(1)
+            ),
+            fps,
+        ),
+        RealtimeMotion::Low => {
+            let rate = (fps / 2).max(1);
+            (
+                format!(
+                    "{};[v]fps={},mpdecimate=max={}[vout]",
+                    filter_complex,
+                    rate,
+                    rate.saturating_sub(1).max(1)
+                ),
+                rate,
+            )
+        }
+    }
+}
+
+impl Transport for FfmpegRealtimeTransport {
+    fn name(&self) -> &'static str {
+        "native-realtime"
+    }
+
+    fn prepare(&mut self, ctx: &TransportContext) -> Result<Option<TransportEntry>> {
+        let args = ctx.args;
+        let Some(plan) = build_scene_realtime_effect_plan(
+            &args.root,
+            ctx.session_dir,
+            Path::new(ctx.entry_to_launch),
+            args.custom_filters.as_ref(),
+        )?
+        else {
+            if args.require_native {
+                bail!("native-realtime requested but no realtime plan could be built");
+            }
+            warn_msg!(Msg::RealtimePlanUnavailable);
+            return Ok(None);
+        };
+        if args.require_native
+            && ctx.native_plan.ready_nodes == 0
+            && ctx.native_plan.total_pass_nodes > 0
+        {
+            bail!(
+                "native-realtime requested but no ready shader families in native plan. See {}",
+                ctx.native_plan_path.display()
+            );
+        }
+
+        let stream_url = realtime_stream_url(&args.root);
+        let pid_file = realtime_pid_path(&args.root);
+        if let Ok(pid_raw) = std::fs::read_to_string(&pid_file)
+            && let Ok(pid) = pid_raw.trim().parse::<u32>()
+        {
+            kill_pid(pid, &stream_url);
+        }
+
+        let pulse_src = || infer_default_monitor_source().unwrap_or_else(|_| "default".to_string());
+        let mut cmd = Command::new("ffmpeg");
+        cmd.arg("-hide_banner")
+            .arg("-loglevel")
+            .arg("warning")
+            .arg("-re")
+            .arg("-stream_loop")
+            .arg("-1");
+        for input in &plan.inputs {
+            cmd.arg("-loop").arg("1").arg("-i").arg(input);
+        }
+        if plan.needs_audio_input {
+            match args.audio_bars_source {
+                AudioBarsSource::Pulse => {
+                    cmd.arg("-f").arg("pulse").arg("-i").arg(pulse_src());
+                }
+                AudioBarsSource::Synth => {
+                    cmd.arg("-f")
+                        .arg("lavfi")
+                        .arg("-i")
+                        .arg("anoisesrc=color=pink:amplitude=0.4");
+                }
+            }
+        }
+        let (filter_complex, output_fps) =
+            realtime_motion_filter(&plan.filter_complex, args.realtime_motion, args.proxy_fps);
+        cmd.arg("-filter_complex")
+            .arg(&filter_complex)
+            .arg("-map")
+            .arg("[vout]")
+            .arg("-r")
+            .arg(output_fps.to_string());
+        if args.realtime_motion != RealtimeMotion::Full {
+            cmd.arg("-fps_mode").arg("vfr");
+        }
+        tag_srgb_output(&mut cmd);
+        cmd.arg("-an").arg("-f").arg("mpegts").arg(&stream_url);
+        if args.dry_run {
+            eprintln!("[dry-run] {}", shell_command_line(&cmd));
+        } else {
+            if let Some(parent) = pid_file.parent() {
+                std::fs::create_dir_all(parent).ok();
+            }
+            let log_file = std::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(NATIVE_FFMPEG_LOG)
+                .with_context(|| format!("Failed to open {}", NATIVE_FFMPEG_LOG))?;
+            let log_file_err = log_file
+                .try_clone()
+                .with_context(|| format!("Failed to clone {}", NATIVE_FFMPEG_LOG))?;
+            let pid = cmd
+                .stdin(std::process::Stdio::null())
+                .stdout(log_file)
+                .stderr(log_file_err)
+                .spawn_pid()
+                .context("Failed to spawn native-realtime ffmpeg")?;
+            std::fs::write(&pid_file, pid.to_string())
+                .with_context(|| format!("Failed writing {}", pid_file.display()))?;
+            self.child = Some((pid, stream_url.clone()));
+        }
+
+        Ok(Some(TransportEntry {
+            entry: stream_url,
+            effective_transport: self.name().to_string(),
+            native_report_path: None,
+            portrait_canvas: false,
+            live_filter: None,
+            manifest_notes: Vec::new(),
+        }))
+    }
+
+    fn supervise(&mut self, ctx: &TransportContext) -> Result<bool> {
+        let Some((pid, marker)) = &self.child else {
+            return Ok(true);
+        };
+        // Give native ffmpeg enough time to fail fast on invalid filters/input.
+        thread::sleep(Duration::from_millis(1800));
+        if pid_runs(*pid, marker) {
+            return Ok(true);
+        }
+        if ctx.args.require_native {
+            bail!(
+                "native-realtime ffmpeg exited on startup. See {}",
+                NATIVE_FFMPEG_LOG
+            );
+        }
+        warn_msg!(Msg::RealtimeFfmpegFailed, NATIVE_FFMPEG_LOG);
+        Ok(false)
+    }
+
+    fn teardown(&mut self, ctx: &TransportContext) -> Result<()> {
+        if let Some((pid, marker)) = self.child.take() {
+            kill_pid(pid, &marker);
+            let _ = std::fs::remove_file(realtime_pid_path(&ctx.args.root));
+        }
+        Ok(())
+    }
+}
+
+const NATIVE_STREAM_LOG: &str = "/tmp/kwe-native-stream.log";
+
+/// Native compositor frames piped into mpvpaper through a FIFO by a
+/// background `native-stream` process, for as long as mpv keeps reading.
+#[derive(Default)]
+pub struct NativeStreamTransport {
+    /// Writer pid and the FIFO path its command line names.
+    child: Option<(u32, String)>,
+}
+
+fn native_stream_pid_path(root: &Path) -> PathBuf {
+    crate::scene_pkg::default_scene_cache_root(&cache_key_for_root(root))
+        .join("gpu/native-stream.pid")
+}
+
+impl Transport for NativeStreamTransport {
+    fn name(&self) -> &'static str {
+        "native-stream"
+    }
+
+    fn prepare(&mut self, ctx: &TransportContext) -> Result<Option<TransportEntry>> {
+        if ctx.native_plan.ready_draw_layers == 0 {
+            return Ok(None);
+        }
+        let args = ctx.args;
+        // Built here too so a scene with nothing drawable falls through now
+        // instead of leaving mpv waiting on an empty pipe.
+        let Some(stream) = NativeFrameStream::build(
+            &args.root,
+            ctx.session_dir,
+            ctx.scene_width,
+            ctx.scene_height,
+            ctx.native_plan,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        let pid_file = native_stream_pid_path(&args.root);
+        let fifo = ctx.session_dir.join("native-stream.y4m");
+        if let Ok(pid_raw) = std::fs::read_to_string(&pid_file)
+            && let Ok(pid) = pid_raw.trim().parse::<u32>()
+        {
+            kill_pid(pid, &fifo.to_string_lossy());
+        }
+        let exe = std::env::current_exe().context("Failed to locate own executable")?;
+        let mut cmd = Command::new(exe);
+        cmd.arg("native-stream")
+            .arg("--root")
+            .arg(&args.root)
+            .arg("--monitor")
+            .arg(&args.monitor)
+            .arg("--session-dir")
+            .arg(ctx.session_dir)
+            .arg("--fifo")
+            .arg(&fifo)
+            .arg("--fps")
+            .arg(args.proxy_fps.to_string());
+        if args.dry_run {
+            eprintln!("[dry-run] mkfifo {}", shell_quote(fifo.as_os_str()));
+            eprintln!("[dry-run] {}", shell_command_line(&cmd));
+        } else {
+            let _ = std::fs::remove_file(&fifo);
+            let made = Command::new("mkfifo")
+                .arg(&fifo)
+                .run_output()
+                .context("Failed to run mkfifo")?;
+            if !made.status.success() {
+                bail!(
+                    "mkfifo {} failed: {}",
+                    fifo.display(),
+                    String::from_utf8_lossy(&made.stderr).trim()
+                );
+            }
+            if let Some(parent) = pid_file.parent() {
+                std::fs::create_dir_all(parent).ok();
+            }
+            let log_file = std::fs::File::create(NATIVE_STREAM_LOG)
+                .with_context(|| format!("Failed to open {}", NATIVE_STREAM_LOG))?;
+            let log_file_err = log_file
+                .try_clone()
+                .with_context(|| format!("Failed to clone {}", NATIVE_STREAM_LOG))?;
+            let pid = cmd
+                .stdin(std::process::Stdio::null())
+                .stdout(log_file)
+                .stderr(log_file_err)
+                .spawn_pid()
+                .context("Failed to spawn native-stream")?;
+            std::fs::write(&pid_file, pid.to_string())
+                .with_context(|| format!("Failed writing {}", pid_file.display()))?;
+            self.child = Some((pid, fifo.to_string_lossy().to_string()));
+        }
+
+        Ok(Some(TransportEntry {
+            entry: fifo.to_string_lossy().to_string(),
+            effective_transport: self.name().to_string(),
+            native_report_path: None,
+            portrait_canvas: false,
+            live_filter: clock_hands_filter(
+                &stream.clock_hands,
+                Local::now().offset().local_minus_utc(),
+            ),
+            manifest_notes: vec![format!(
+                "Native stream: {} layers at {} fps over {}",
+                stream.rendered_layers,
+                args.proxy_fps,
+                fifo.display()
+            )],
+        }))
+    }
+
+    fn supervise(&mut self, ctx: &TransportContext) -> Result<bool> {
+        let Some((pid, marker)) = &self.child else {
+            return Ok(true);
+        };
+        // The writer rebuilds the plan before blocking on the FIFO; a bad
+        // plan makes it exit within this window.
+        thread::sleep(Duration::from_millis(1000));
+        if pid_runs(*pid, marker) {
+            return Ok(true);
+        }
+        if ctx.args.require_native {
+            bail!(
+                "native-stream writer exited on startup. See {}",
+                NATIVE_STREAM_LOG
+            );
+        }
+        warn_msg!(Msg::NativeStreamFailed, NATIVE_STREAM_LOG);
+        Ok(false)
+    }
+
+    fn teardown(&mut self, ctx: &TransportContext) -> Result<()> {
+        if let Some((pid, marker)) = self.child.take() {
+            kill_pid(pid, &marker);
+            let _ = std::fs::remove_file(native_stream_pid_path(&ctx.args.root));
+            let _ = std::fs::remove_file(ctx.session_dir.join("native-stream.y4m"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Orientation, PlaybackProfile, TextRenderMode};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct MockTransport {
+        name: &'static str,
+        serves: bool,
+        healthy: bool,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Transport for MockTransport {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn prepare(&mut self, _ctx: &TransportContext) -> Result<Option<TransportEntry>> {
+            self.log.borrow_mut().push(format!("prepare {}", self.name));
+            Ok(self.serves.then(|| TransportEntry {
+                entry: format!("{}.mp4", self.name),
+                effective_transport: self.name.to_string(),
+                native_report_path: None,
+                portrait_canvas: false,
+                live_filter: None,
+                manifest_notes: Vec::new(),
+            }))
+        }
+
+        fn supervise(&mut self, _ctx: &TransportContext) -> Result<bool> {
+            Ok(self.healthy)
+        }
+
+        fn teardown(&mut self, _ctx: &TransportContext) -> Result<()> {
+            self.log
+                .borrow_mut()
+                .push(format!("teardown {}", self.name));
+            Ok(())
+        }
+    }
+
+    fn test_args() -> SceneGpuPlayArgs {
+        SceneGpuPlayArgs {
+            root: PathBuf::from("/tmp/kwe-transport-test"),
+            monitor: "DP-1".to_string(),
+            source: None,
+            seconds: 4,
+            frame_ms: 50,
+            profile: PlaybackProfile::Performance,
+            mute_audio: false,
+            display_fps: None,
+            clock_overlay: false,
+            text_timezone: None,
+            text_mode: TextRenderMode::Drawtext,
+            orientation: Orientation::Landscape,
+            apply_kitsune_overlay: false,
+            transport: GpuTransport::NativeRealtime,
+            require_native: false,
+            audio_bars_source: AudioBarsSource::Synth,
+            realtime_motion: RealtimeMotion::Full,
+            custom_filters: None,
+            proxy_width: 1920,
+            proxy_fps: 30,
+            proxy_crf: 20,
+            proxy_seconds: None,
+            auto_fps: false,
+            audio_reactive: false,
+            audio_reactive_strength: 1.0,
+            dry_run: true,
+        }
+    }
+
+    #[test]
+    fn chain_skips_unserved_and_tears_down_unhealthy() {
+        let args = test_args();
+        let plan = NativeRuntimePlan::default();
+        let ctx = TransportContext {
+            args: &args,
+            session_dir: Path::new("/tmp/kwe-transport-test/session"),
+            entry_to_launch: "base.png",
+            music_path: None,
+            scene_width: 1920,
+            scene_height: 1080,
+            native_plan: &plan,
+            native_plan_path: Path::new("/tmp/kwe-transport-test/plan.json"),
+            portrait: false,
+        };
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mock = |name, serves, healthy| -> Box<dyn Transport> {
+            Box::new(MockTransport {
+                name,
+                serves,
+                healthy,
+                log: log.clone(),
+            })
+        };
+        let mut chain = vec![
+            mock("a", false, true),
+            mock("b", true, false),
+            mock("c", true, true),
+            mock("d", true, true),
+        ];
+
+        let entry = run_transport_chain(&mut chain, &ctx).unwrap();
+        assert_eq!(entry.entry, "c.mp4");
+        assert_eq!(
+            *log.borrow(),
+            vec!["prepare a", "prepare b", "teardown b", "prepare c"]
+        );
+
+        let mut empty = vec![mock("x", false, true)];
+        assert!(run_transport_chain(&mut empty, &ctx).is_err());
+    }
+
+    #[test]
+    fn require_native_drops_mp4_fallback() {
+        let names =
+            |chain: Vec<Box<dyn Transport>>| chain.iter().map(|t| t.name()).collect::<Vec<_>>();
+        assert_eq!(
+            names(transport_chain(GpuTransport::NativeRealtime, false)).last(),
+            Some(&"mp4-proxy")
+        );
+        assert_eq!(
+            names(transport_chain(GpuTransport::NativeRealtime, true)).last(),
+            Some(&"native-realtime")
+        );
+        assert_eq!(
+            names(transport_chain(GpuTransport::Mp4Proxy, true)),
+            vec!["mp4-proxy"]
+        );
+        assert_eq!(
+            names(transport_chain(GpuTransport::NativeStream, true)),
+            vec![
+                "native-stream",
+                "native-animated-layered",
+                "native-static-layered"
+            ]
+        );
+    }
+
+    #[test]
+    fn low_motion_decimates_and_halves_the_producer_rate() {
+        let graph = "[0:v]scale=64:64[v]";
+        assert_eq!(
+            realtime_motion_filter(graph, RealtimeMotion::Full, 30),
+            ("[0:v]scale=64:64[v];[v]null[vout]".to_string(), 30)
+        );
+        assert_eq!(
+            realtime_motion_filter(graph, RealtimeMotion::Adaptive, 30),
+            (
+                "[0:v]scale=64:64[v];[v]mpdecimate=max=29[vout]".to_string(),
+                30
+            )
+        );
+        assert_eq!(
+            realtime_motion_filter(graph, RealtimeMotion::Low, 30),
+            (
+                "[0:v]scale=64:64[v];[v]fps=15,mpdecimate=max=14[vout]".to_string(),
+                15
+            )
+        );
+        assert_eq!(realtime_motion_filter(graph, RealtimeMotion::Low, 1).1, 1);
+    }
+
+    #[test]
+    fn only_processes_naming_the_marker_are_killed() {
+        let mut sleeper = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = sleeper.id();
+        // The command line is the test binary's until `sleep` is exec'd.
+        for _ in 0..100 {
+            if pid_runs(pid, "30") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(pid_runs(pid, "30"));
+        assert!(!pid_runs(pid, "udp://127.0.0.1:19001"));
+        kill_pid(pid, "udp://127.0.0.1:19001");
+        assert!(sleeper.try_wait().unwrap().is_none());
+        kill_pid(pid, "30");
+        assert!(!sleeper.wait().unwrap().success());
+        assert!(!pid_runs(u32::MAX, "30"));
+    }
+}
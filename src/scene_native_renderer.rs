@@ -1,13 +1,27 @@
+use crate::app_paths::filter_safe_path;
 use crate::asset_resolver::AssetResolver;
-use crate::scene_native_runtime::{NativeRuntimePlan, NativeSupportTier};
+use crate::colorspace::{SRGB_BT709_TAGS, YUV420P_BT709};
+use crate::command_runner::CommandExt;
+use crate::proxy_meta::ProxyMeta;
+use crate::scene_effect_proxy::loop_friendly_seconds;
+use crate::scene_emulation_trace::{EmulationKind, EmulationTraceEntry, record_emulation_stage};
+use crate::scene_gpu_graph::SceneViewport;
+use crate::scene_native_runtime::{
+    NativeBudgetAction, NativeDrawLayer, NativePassSupport, NativeRuntimePlan, NativeSupportTier,
+    RegionBlur,
+};
+use crate::scene_script::ClockHand;
+use crate::scene_tile_grid::{parse_tile_name, probe_tile_grid, stitch_tiles};
 use crate::tex_payload::extract_playable_proxy_from_tex;
 use anyhow::{Context, Result};
 use image::imageops::FilterType;
 use image::{Rgba, RgbaImage};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::rc::Rc;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct NativeLayerResult {
@@ -37,14 +51,69 @@ pub struct NativeAnimatedRenderReport {
     pub report_path: String,
     pub canvas_width: u32,
     pub canvas_height: u32,
-    pub seconds: u64,
+    pub seconds: f32,
     pub fps: u32,
     pub total_ready_layers: usize,
     pub rendered_layers: usize,
     pub layers: Vec<NativeLayerResult>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub clock_hands: Vec<ClockHandOverlay>,
+    /// More than one when the graph was too large for a single ffmpeg run.
+    pub ffmpeg_passes: usize,
     pub notes: Vec<String>,
 }
 
+/// Clock hand layer kept out of the baked loop: mpv draws it over the proxy
+/// with a rotation taken from the wall clock, so it keeps ticking.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockHandOverlay {
+    pub object_id: u64,
+    pub image: String,
+    pub center_x: f32,
+    pub center_y: f32,
+    pub width: u32,
+    pub height: u32,
+    pub alpha: f32,
+    /// Tint with brightness already applied.
+    pub tint: [f32; 3],
+    pub hand: ClockHand,
+}
+
+/// mpv `vf` entry compositing `hands` over the playing video; `utc_offset`
+/// is the local time zone offset in seconds.
+pub fn clock_hands_filter(hands: &[ClockHandOverlay], utc_offset: i32) -> Option<String> {
+    let mut graph = Vec::<String>::new();
+    for (i, hand) in hands.iter().enumerate() {
+        let input = if i == 0 {
+            "vin".to_string()
+        } else {
+            format!("c{}", i)
+        };
+        let output = if i + 1 == hands.len() {
+            "vout".to_string()
+        } else {
+            format!("c{}", i + 1)
+        };
+        graph.push(format!(
+            "movie={},loop=-1:1:0,setpts=N/25/TB,format=rgba,scale={}:{},colorchannelmixer=rr={:.3}:gg={:.3}:bb={:.3}:aa={:.3},rotate={}:c=none:ow=hypot(iw\\,ih):oh=ow[h{}]",
+            hand.image,
+            hand.width,
+            hand.height,
+            hand.tint[0].clamp(0.0, 2.0),
+            hand.tint[1].clamp(0.0, 2.0),
+            hand.tint[2].clamp(0.0, 2.0),
+            hand.alpha.clamp(0.02, 1.0),
+            hand.hand.rotate_expr(utc_offset),
+            i
+        ));
+        graph.push(format!(
+            "[{}][h{}]overlay=x={:.3}-overlay_w/2:y={:.3}-overlay_h/2:format=auto[{}]",
+            input, i, hand.center_x, hand.center_y, output
+        ));
+    }
+    (!graph.is_empty()).then(|| format!("lavfi=[{}]", graph.join(";")))
+}
+
 fn lower_ext(path: &str) -> String {
     Path::new(path)
         .extension()
@@ -99,12 +168,56 @@ fn blend(
     }
 }
 
-fn decode_layer_image(bytes: &[u8]) -> Option<RgbaImage> {
+pub fn decode_layer_image(bytes: &[u8]) -> Option<RgbaImage> {
     let dyn_img = image::load_from_memory(bytes).ok()?;
     Some(dyn_img.to_rgba8())
 }
 
-fn resolve_layer_image(
+/// Encoded bytes of the image behind `texture_ref`. A `_0_0` tile of a
+/// grid-named set (`bg_0_0.tex`, `bg_0_1.tex`...) comes back as the whole
+/// set stitched into one PNG.
+pub fn resolve_layer_image(
+    resolver: &AssetResolver,
+    texture_ref: &str,
+    scratch_dir: &Path,
+) -> Result<Option<Vec<u8>>> {
+    if let Some(tile) = parse_tile_name(texture_ref)
+        && (tile.row, tile.col) == (0, 0)
+    {
+        let cells = probe_tile_grid(|row, col| {
+            (row, col) == (0, 0) || resolver.resolve(&tile.sibling(row, col)).is_some()
+        });
+        if cells.len() > 1 {
+            let mut tiles = Vec::with_capacity(cells.len());
+            for (row, col) in cells {
+                let bytes =
+                    resolve_single_layer_image(resolver, &tile.sibling(row, col), scratch_dir)?;
+                let Some(img) = bytes.and_then(|b| decode_layer_image(&b)) else {
+                    // One undecodable piece: show the first tile alone as before.
+                    return resolve_single_layer_image(resolver, texture_ref, scratch_dir);
+                };
+                tiles.push((row, col, img));
+            }
+            if let Some(stitched) = stitch_tiles(&tiles) {
+                let mut png = std::io::Cursor::new(Vec::new());
+                stitched
+                    .write_to(&mut png, image::ImageFormat::Png)
+                    .with_context(|| format!("Failed encoding tile set {}", tile.prefix))?;
+                eprintln!(
+                    "[ok] stitched {} tiles of {} into {}x{}",
+                    tiles.len(),
+                    tile.prefix,
+                    stitched.width(),
+                    stitched.height()
+                );
+                return Ok(Some(png.into_inner()));
+            }
+        }
+    }
+    resolve_single_layer_image(resolver, texture_ref, scratch_dir)
+}
+
+fn resolve_single_layer_image(
     resolver: &AssetResolver,
     texture_ref: &str,
     scratch_dir: &Path,
@@ -151,6 +264,134 @@ fn resolve_layer_image(
     Ok(None)
 }
 
+fn layer_pixel_size(layer: &NativeDrawLayer, width: u32, height: u32) -> (u32, u32) {
+    (
+        layer.width.max(8.0).min(width as f32 * 2.0).round() as u32,
+        layer.height.max(8.0).min(height as f32 * 2.0).round() as u32,
+    )
+}
+
+/// Repeats `img` at its native size over a `w`x`h` layer so tiled
+/// backgrounds keep their pattern density instead of being stretched.
+fn tile_to_size(img: &RgbaImage, w: u32, h: u32) -> RgbaImage {
+    let (tw, th) = (img.width().max(1), img.height().max(1));
+    RgbaImage::from_fn(w, h, |x, y| *img.get_pixel(x % tw, y % th))
+}
+
+/// Why a layer texture could not be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextureMiss {
+    Unresolved,
+    Undecodable,
+}
+
+impl TextureMiss {
+    fn record_reason(self) -> &'static str {
+        match self {
+            TextureMiss::Unresolved => {
+                "texture unresolved or unsupported format (expects png/jpg/webp or tex proxy)"
+            }
+            TextureMiss::Undecodable => "failed to decode image bytes",
+        }
+    }
+
+    fn trace_reason(self) -> &'static str {
+        match self {
+            TextureMiss::Unresolved => "texture unresolved or unsupported format",
+            TextureMiss::Undecodable => "failed to decode image bytes",
+        }
+    }
+}
+
+/// Texture as drawn on screen: tiled for repeating layers, shrunk to the
+/// layer size when larger (enlarging is left to the compositor).
+fn fit_to_screen(img: &RgbaImage, w: u32, h: u32, repeat: bool) -> RgbaImage {
+    if repeat {
+        tile_to_size(img, w, h)
+    } else if img.width() > w || img.height() > h {
+        image::imageops::resize(img, w, h, FilterType::Triangle)
+    } else {
+        img.clone()
+    }
+}
+
+/// Decodes each texture once for all the layers referencing it, and frees it
+/// after its last layer. Screen-sized PNGs are shared by layers drawing the
+/// same texture at the same size.
+struct LayerTextures<'a> {
+    resolver: &'a AssetResolver,
+    scratch: &'a Path,
+    remaining_uses: HashMap<String, usize>,
+    decoded: HashMap<String, Result<Rc<RgbaImage>, TextureMiss>>,
+    written: HashMap<(String, u32, u32, bool), PathBuf>,
+}
+
+impl<'a> LayerTextures<'a> {
+    fn new<'l>(
+        resolver: &'a AssetResolver,
+        scratch: &'a Path,
+        layers: impl IntoIterator<Item = &'l NativeDrawLayer>,
+    ) -> Self {
+        let mut remaining_uses = HashMap::<String, usize>::new();
+        for texture in layers.into_iter().filter_map(|l| l.primary_texture.clone()) {
+            *remaining_uses.entry(texture).or_default() += 1;
+        }
+        Self {
+            resolver,
+            scratch,
+            remaining_uses,
+            decoded: HashMap::new(),
+            written: HashMap::new(),
+        }
+    }
+
+    /// Texture for one more layer.
+    fn take(&mut self, texture_ref: &str) -> Result<Result<Rc<RgbaImage>, TextureMiss>> {
+        let image = match self.decoded.get(texture_ref) {
+            Some(image) => image.clone(),
+            None => {
+                let image = match resolve_layer_image(self.resolver, texture_ref, self.scratch)? {
+                    None => Err(TextureMiss::Unresolved),
+                    Some(bytes) => decode_layer_image(&bytes)
+                        .map(Rc::new)
+                        .ok_or(TextureMiss::Undecodable),
+                };
+                self.decoded.insert(texture_ref.to_string(), image.clone());
+                image
+            }
+        };
+        let uses = self
+            .remaining_uses
+            .entry(texture_ref.to_string())
+            .or_default();
+        *uses = uses.saturating_sub(1);
+        if *uses == 0 {
+            self.decoded.remove(texture_ref);
+        }
+        Ok(image)
+    }
+
+    fn screen_png(
+        &mut self,
+        texture_ref: &str,
+        img: &RgbaImage,
+        (w, h): (u32, u32),
+        repeat: bool,
+        out_dir: &Path,
+    ) -> Result<PathBuf> {
+        let key = (texture_ref.to_string(), w, h, repeat);
+        if let Some(path) = self.written.get(&key) {
+            return Ok(path.clone());
+        }
+        let path = out_dir.join(format!("layer_{:03}.png", self.written.len()));
+        fit_to_screen(img, w, h, repeat)
+            .save(&path)
+            .with_context(|| format!("Failed writing {}", path.display()))?;
+        self.written.insert(key, path.clone());
+        Ok(path)
+    }
+}
+
 fn layer_motion(
     uniforms: &std::collections::BTreeMap<String, serde_json::Value>,
     idx: usize,
@@ -163,13 +404,253 @@ fn layer_motion(
         .get("g_ScrollY")
         .and_then(|v| v.as_f64())
         .unwrap_or(0.0) as f32;
-    let fx = (1.1 + sx.abs() * 2.2 + idx as f32 * 0.09).clamp(0.7, 6.0);
-    let fy = (0.9 + sy.abs() * 2.0 + idx as f32 * 0.07).clamp(0.7, 6.0);
+    // Particle `instanceoverride` rate: faster emission reads as faster drift.
+    let rate = uniforms
+        .get("instance_rate")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(1.0)
+        .clamp(0.25, 4.0) as f32;
+    let fx = (1.1 + sx.abs() * 2.2 + idx as f32 * 0.09).clamp(0.7, 6.0) * rate;
+    let fy = (0.9 + sy.abs() * 2.0 + idx as f32 * 0.07).clamp(0.7, 6.0) * rate;
     let ax = (2.0 + sx.abs() * 7.0 + idx as f32 * 0.2).clamp(1.0, 14.0);
     let ay = (1.5 + sy.abs() * 6.0 + idx as f32 * 0.16).clamp(1.0, 12.0);
     (fx, fy, ax, ay)
 }
 
+/// Splits the plan into drawable layers and trace entries for passes the
+/// native compositor cannot draw.
+fn partition_ready_layers(
+    plan: &NativeRuntimePlan,
+) -> (
+    Vec<(NativePassSupport, NativeDrawLayer)>,
+    Vec<EmulationTraceEntry>,
+) {
+    let mut ready = Vec::new();
+    let mut trace = plan
+        .budget
+        .iter()
+        .flat_map(|b| &b.trimmed)
+        .filter(|t| t.action == NativeBudgetAction::Dropped)
+        .map(|t| {
+            EmulationTraceEntry::from_pass(&t.pass, EmulationKind::Dropped, &t.budget_reason)
+                .param("cost", t.cost)
+                .param("impact", t.impact)
+        })
+        .chain(plan.pruned.iter().map(EmulationTraceEntry::from_pruned))
+        .collect::<Vec<_>>();
+    for (pass, layer) in plan.passes.iter().zip(&plan.draw_layers) {
+        let region_blur = matches!(layer.tier, NativeSupportTier::ExperimentalReady)
+            && layer.blur_sigma.is_some();
+        if region_blur {
+            ready.push((pass.clone(), layer.clone()));
+        } else if !matches!(layer.tier, NativeSupportTier::Ready) {
+            trace.push(EmulationTraceEntry::from_pass(
+                pass,
+                EmulationKind::Dropped,
+                format!("tier {:?}: {}", pass.tier, pass.reason),
+            ));
+        } else if layer.primary_texture.is_none() {
+            trace.push(EmulationTraceEntry::from_pass(
+                pass,
+                EmulationKind::Dropped,
+                "pass has no primary texture",
+            ));
+        } else {
+            ready.push((pass.clone(), layer.clone()));
+        }
+    }
+    (ready, trace)
+}
+
+fn quad_trace_entry(
+    pass: &NativePassSupport,
+    layer: &NativeDrawLayer,
+    emulation: EmulationKind,
+    reason: &str,
+) -> EmulationTraceEntry {
+    EmulationTraceEntry::from_pass(pass, emulation, reason)
+        .param("blend_mode", layer.blend_mode.clone())
+        .param("alpha", layer.alpha)
+        .param("brightness", layer.brightness)
+        .param("tint", layer.tint.to_vec())
+        .param("center_x", layer.center_x)
+        .param("center_y", layer.center_y)
+        .param("width", layer.width)
+        .param("height", layer.height)
+        .param("angle_rad", layer.angle_rad)
+}
+
+/// `img` at the layer's on-screen size.
+fn scale_to_layer(img: &RgbaImage, layer: &NativeDrawLayer, width: u32, height: u32) -> RgbaImage {
+    let (layer_w, layer_h) = layer_pixel_size(layer, width, height);
+    if layer.texture_repeat {
+        tile_to_size(img, layer_w, layer_h)
+    } else {
+        image::imageops::resize(img, layer_w, layer_h, FilterType::Triangle)
+    }
+}
+
+/// Draws `img` as the layer's axis-aligned quad onto `canvas`.
+fn composite_quad(canvas: &mut RgbaImage, img: &RgbaImage, layer: &NativeDrawLayer) {
+    let (width, height) = canvas.dimensions();
+    let scaled = scale_to_layer(img, layer, width, height);
+    blit_layer(canvas, &scaled, layer, (0.0, 0.0));
+}
+
+/// Draws `layer`'s quad as opaque `color`, placed and sized exactly like its
+/// texture would be, so the layer can be found again in a rendered frame.
+pub fn draw_layer_pattern(canvas: &mut RgbaImage, layer: &NativeDrawLayer, color: Rgba<u8>) {
+    let (width, height) = canvas.dimensions();
+    let (layer_w, layer_h) = layer_pixel_size(layer, width, height);
+    let solid = NativeDrawLayer {
+        alpha: 1.0,
+        blend_mode: "normal".to_string(),
+        brightness: 1.0,
+        tint: [1.0; 3],
+        ..layer.clone()
+    };
+    blit_layer(
+        canvas,
+        &RgbaImage::from_pixel(layer_w, layer_h, color),
+        &solid,
+        (0.0, 0.0),
+    );
+}
+
+/// Blends an already scaled layer image centred on the layer, moved by `offset`.
+fn blit_layer(
+    canvas: &mut RgbaImage,
+    scaled: &RgbaImage,
+    layer: &NativeDrawLayer,
+    offset: (f32, f32),
+) {
+    let appearance = (layer.alpha, layer.brightness, layer.tint);
+    blit_layer_as(canvas, scaled, layer, offset, appearance);
+}
+
+/// [`blit_layer`] with `(alpha, brightness, tint)` in place of the layer's.
+fn blit_layer_as(
+    canvas: &mut RgbaImage,
+    scaled: &RgbaImage,
+    layer: &NativeDrawLayer,
+    offset: (f32, f32),
+    (alpha, brightness, tint): (f32, f32, [f32; 3]),
+) {
+    let (width, height) = canvas.dimensions();
+    let (layer_w, layer_h) = scaled.dimensions();
+    let x0 = (layer.center_x + offset.0 - layer_w as f32 / 2.0).round() as i32;
+    let y0 = (layer.center_y + offset.1 - layer_h as f32 / 2.0).round() as i32;
+    for y in 0..layer_h {
+        for x in 0..layer_w {
+            let dst_x = x0 + x as i32;
+            let dst_y = y0 + y as i32;
+            if dst_x < 0 || dst_y < 0 || dst_x >= width as i32 || dst_y >= height as i32 {
+                continue;
+            }
+            let src = *scaled.get_pixel(x, y);
+            let dst = canvas.get_pixel_mut(dst_x as u32, dst_y as u32);
+            blend(dst, src, alpha, &layer.blend_mode, brightness, tint);
+        }
+    }
+}
+
+/// Gaussian-blurs the canvas under the layer rect (blur effects).
+fn blur_canvas_region(canvas: &mut RgbaImage, region: &RegionBlur) {
+    let (w, h) = canvas.dimensions();
+    let x = (region.x * w as f32).round() as u32;
+    let y = (region.y * h as f32).round() as u32;
+    let rw = ((region.w * w as f32).round() as u32).min(w.saturating_sub(x));
+    let rh = ((region.h * h as f32).round() as u32).min(h.saturating_sub(y));
+    if rw < 2 || rh < 2 {
+        return;
+    }
+    let crop = image::imageops::crop_imm(canvas, x, y, rw, rh).to_image();
+    let blurred = image::imageops::blur(&crop, region.sigma);
+    image::imageops::replace(canvas, &blurred, x as i64, y as i64);
+}
+
+fn region_blur_trace(pass: &NativePassSupport, layer: &NativeDrawLayer) -> EmulationTraceEntry {
+    quad_trace_entry(
+        pass,
+        layer,
+        EmulationKind::RegionBlur,
+        "blur effect approximated as a region-limited gaussian blur",
+    )
+    .param("sigma", layer.blur_sigma.unwrap_or_default())
+}
+
+/// Visible part of a viewport: `(src, dst)` rects as `(x, y, w, h)`, with the
+/// source window clipped to the canvas and the destination shrunk to match.
+type ViewportRects = ((u32, u32, u32, u32), (u32, u32, u32, u32));
+
+fn viewport_rects(vp: &SceneViewport, width: u32, height: u32) -> Option<ViewportRects> {
+    let (sw, sh) = (vp.source_width.max(1.0), vp.source_height.max(1.0));
+    let sx0 = vp.source_x.max(0.0);
+    let sy0 = vp.source_y.max(0.0);
+    let sx1 = (vp.source_x + sw).min(width as f32);
+    let sy1 = (vp.source_y + sh).min(height as f32);
+    if sx1 - sx0 < 1.0 || sy1 - sy0 < 1.0 {
+        return None;
+    }
+    let map_x = |x: f32| vp.dest_x as f32 + (x - vp.source_x) / sw * vp.dest_width as f32;
+    let map_y = |y: f32| vp.dest_y as f32 + (y - vp.source_y) / sh * vp.dest_height as f32;
+    let (dx0, dy0) = (map_x(sx0).round() as u32, map_y(sy0).round() as u32);
+    let (dx1, dy1) = (map_x(sx1).round() as u32, map_y(sy1).round() as u32);
+    if dx1 <= dx0 || dy1 <= dy0 {
+        return None;
+    }
+    Some((
+        (
+            sx0.round() as u32,
+            sy0.round() as u32,
+            ((sx1 - sx0).round() as u32).max(1),
+            ((sy1 - sy0).round() as u32).max(1),
+        ),
+        (dx0, dy0, dx1 - dx0, dy1 - dy0),
+    ))
+}
+
+/// Re-projects the composed scene through each camera of a split scene.
+fn composite_viewports(scene: &RgbaImage, viewports: &[SceneViewport]) -> RgbaImage {
+    let (width, height) = scene.dimensions();
+    let mut out = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    for vp in viewports {
+        let Some(((sx, sy, sw, sh), (dx, dy, dw, dh))) = viewport_rects(vp, width, height) else {
+            continue;
+        };
+        let region = image::imageops::crop_imm(scene, sx, sy, sw, sh).to_image();
+        let scaled = image::imageops::resize(&region, dw, dh, FilterType::Triangle);
+        image::imageops::replace(&mut out, &scaled, dx as i64, dy as i64);
+    }
+    out
+}
+
+fn viewport_filter(input: &str, viewports: &[SceneViewport], width: u32, height: u32) -> String {
+    let rects = viewports
+        .iter()
+        .filter_map(|vp| viewport_rects(vp, width, height))
+        .collect::<Vec<_>>();
+    if rects.is_empty() {
+        return format!("[{}]copy[vpout];", input);
+    }
+    let mut filter = format!("[{}]split={}", input, rects.len());
+    for i in 0..rects.len() {
+        filter.push_str(&format!("[vps{}]", i));
+    }
+    filter.push_str(&format!(
+        ";color=c=black@1.0:s={}x{}:d=1,format=rgba[vpb0];",
+        width, height
+    ));
+    for (i, ((sx, sy, sw, sh), (dx, dy, dw, dh))) in rects.iter().enumerate() {
+        filter.push_str(&format!(
+            "[vps{i}]crop={sw}:{sh}:{sx}:{sy},scale={dw}:{dh}[vpr{i}];[vpb{i}][vpr{i}]overlay=x={dx}:y={dy}[vpb{}];",
+            i + 1
+        ));
+    }
+    filter.push_str(&format!("[vpb{}]copy[vpout];", rects.len()));
+    filter
+}
+
 pub fn render_native_static_frame(
     root: &Path,
     session_dir: &Path,
@@ -177,15 +658,35 @@ pub fn render_native_static_frame(
     canvas_height: u32,
     plan: &NativeRuntimePlan,
 ) -> Result<Option<NativeStaticRenderReport>> {
-    let ready_layers: Vec<_> = plan
-        .draw_layers
-        .iter()
-        .filter(|l| matches!(l.tier, NativeSupportTier::Ready) && l.primary_texture.is_some())
-        .cloned()
-        .collect();
+    let report = render_native_static_layers(root, session_dir, canvas_width, canvas_height, plan)?;
+    Ok(Some(report).filter(|r| r.rendered_layers > 0))
+}
+
+/// Composites the ready layers and reports every one of them, including when
+/// none could be drawn (then `output_image` is empty and nothing is written).
+pub fn render_native_static_layers(
+    root: &Path,
+    session_dir: &Path,
+    canvas_width: u32,
+    canvas_height: u32,
+    plan: &NativeRuntimePlan,
+) -> Result<NativeStaticRenderReport> {
+    let (ready_layers, mut trace) = partition_ready_layers(plan);
+    let width = canvas_width.max(1);
+    let height = canvas_height.max(1);
+    let empty_report = |layers: Vec<NativeLayerResult>| NativeStaticRenderReport {
+        output_image: String::new(),
+        canvas_width: width,
+        canvas_height: height,
+        total_ready_layers: layers.len(),
+        rendered_layers: 0,
+        layers,
+        notes: vec!["No ready layer could be composited".to_string()],
+    };
 
     if ready_layers.is_empty() {
-        return Ok(None);
+        record_emulation_stage(session_dir, "native-static", trace, Vec::new())?;
+        return Ok(empty_report(Vec::new()));
     }
 
     let resolver = AssetResolver::new(root)?;
@@ -193,15 +694,14 @@ pub fn render_native_static_frame(
     fs::create_dir_all(&out_dir)
         .with_context(|| format!("Failed creating {}", out_dir.display()))?;
     let scratch = out_dir.join("scratch");
+    let mut textures = LayerTextures::new(&resolver, &scratch, ready_layers.iter().map(|(_, l)| l));
 
-    let width = canvas_width.max(1);
-    let height = canvas_height.max(1);
     let mut canvas = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
 
     let mut results = Vec::<NativeLayerResult>::new();
     let mut rendered = 0usize;
 
-    for layer in ready_layers {
+    for (pass, layer) in ready_layers {
         let texture_ref = layer.primary_texture.clone().unwrap_or_default();
         let mut record = NativeLayerResult {
             object_id: layer.object_id,
@@ -213,54 +713,50 @@ pub fn render_native_static_frame(
             reason: None,
         };
 
-        let bytes = resolve_layer_image(&resolver, &texture_ref, &scratch)?;
-        let Some(bytes) = bytes else {
-            record.reason = Some(
-                "texture unresolved or unsupported format (expects png/jpg/webp or tex proxy)"
-                    .to_string(),
-            );
+        if layer.blur_sigma.is_some() {
+            if let Some(region) = RegionBlur::from_layer(&layer, width, height) {
+                blur_canvas_region(&mut canvas, &region);
+                trace.push(region_blur_trace(&pass, &layer));
+                record.loaded = true;
+            }
             results.push(record);
             continue;
-        };
+        }
 
-        let Some(img) = decode_layer_image(&bytes) else {
-            record.reason = Some("failed to decode image bytes".to_string());
-            results.push(record);
-            continue;
+        let img = match textures.take(&texture_ref)? {
+            Ok(img) => img,
+            Err(miss) => {
+                record.reason = Some(miss.record_reason().to_string());
+                trace.push(quad_trace_entry(
+                    &pass,
+                    &layer,
+                    EmulationKind::Dropped,
+                    miss.trace_reason(),
+                ));
+                results.push(record);
+                continue;
+            }
         };
 
-        let layer_w = layer.width.max(8.0).min(width as f32 * 2.0).round() as u32;
-        let layer_h = layer.height.max(8.0).min(height as f32 * 2.0).round() as u32;
-        let scaled = image::imageops::resize(&img, layer_w, layer_h, FilterType::Triangle);
-        let x0 = (layer.center_x - layer_w as f32 / 2.0).round() as i32;
-        let y0 = (layer.center_y - layer_h as f32 / 2.0).round() as i32;
-        for y in 0..layer_h {
-            for x in 0..layer_w {
-                let dst_x = x0 + x as i32;
-                let dst_y = y0 + y as i32;
-                if dst_x < 0 || dst_y < 0 || dst_x >= width as i32 || dst_y >= height as i32 {
-                    continue;
-                }
-                let src = *scaled.get_pixel(x, y);
-                let dst = canvas.get_pixel_mut(dst_x as u32, dst_y as u32);
-                blend(
-                    dst,
-                    src,
-                    layer.alpha,
-                    &layer.blend_mode,
-                    layer.brightness,
-                    layer.tint,
-                );
-            }
-        }
+        composite_quad(&mut canvas, &img, &layer);
 
+        trace.push(quad_trace_entry(
+            &pass,
+            &layer,
+            EmulationKind::StaticQuad,
+            "composited as a static textured quad",
+        ));
         record.loaded = true;
         rendered += 1;
         results.push(record);
     }
 
+    record_emulation_stage(session_dir, "native-static", trace, Vec::new())?;
     if rendered == 0 {
-        return Ok(None);
+        return Ok(empty_report(results));
+    }
+    if !plan.viewports.is_empty() {
+        canvas = composite_viewports(&canvas, &plan.viewports);
     }
 
     let output = out_dir.join("native_static_frame.png");
@@ -285,7 +781,253 @@ pub fn render_native_static_frame(
     fs::write(&report_path, serde_json::to_vec_pretty(&report)?)
         .with_context(|| format!("Failed writing {}", report_path.display()))?;
 
-    Ok(Some(report))
+    Ok(report)
+}
+
+/// Size limits of one ffmpeg run of the animated compositor. A single argv
+/// string is capped at 128 KiB on Linux, and every looped PNG input costs a
+/// decoder.
+#[derive(Debug, Clone, Copy)]
+struct CompositeLimits {
+    max_filter_bytes: usize,
+    max_inputs: usize,
+}
+
+impl Default for CompositeLimits {
+    fn default() -> Self {
+        Self {
+            max_filter_bytes: 96 * 1024,
+            max_inputs: 48,
+        }
+    }
+}
+
+/// One ffmpeg invocation. Every pass but the last writes a lossless
+/// intermediate that the next pass reads as input 0.
+#[derive(Debug, Clone)]
+struct CompositePass {
+    /// `(path, still image looped with -loop 1)`.
+    inputs: Vec<(PathBuf, bool)>,
+    filter: String,
+    output: PathBuf,
+    last: bool,
+}
+
+/// Filter for one layer composited from `[comp_in]` onto `[comp_out]`;
+/// `input` is its ffmpeg input index within the pass.
+fn layer_composite_filter(
+    layer: &NativeDrawLayer,
+    input: Option<usize>,
+    motion_index: Option<usize>,
+    i: usize,
+    comp_in: &str,
+    comp_out: &str,
+    (width, height): (u32, u32),
+) -> String {
+    let (Some(input_idx), Some(motion_index)) = (input, motion_index) else {
+        return RegionBlur::from_layer(layer, width, height)
+            .map(|region| region.ffmpeg_filter(comp_in, comp_out, &format!("rb{}", i)))
+            .unwrap_or_default();
+    };
+    let moved = format!("l{}_m", i);
+    let colored = format!("l{}_c", i);
+    let rotated = format!("l{}_r", i);
+    let (fx, fy, ax, ay) = layer_motion(&layer.uniforms, motion_index);
+    let (layer_w, layer_h) = layer_pixel_size(layer, width, height);
+
+    // Render-target passes run on the texture itself, before it is placed.
+    let chained = layer
+        .chain
+        .iter()
+        .filter_map(|step| step.blur_sigma)
+        .map(|sigma| format!("gblur=sigma={:.2},", sigma))
+        .collect::<String>();
+    let mut filter = format!(
+        "[{}:v]format=rgba,{}scale={}:{}:flags=bicubic,setsar=1,colorchannelmixer=rr={:.3}:gg={:.3}:bb={:.3}:aa={:.3}[{}];",
+        input_idx,
+        chained,
+        layer_w,
+        layer_h,
+        (layer.tint[0] * layer.brightness).clamp(0.0, 2.0),
+        (layer.tint[1] * layer.brightness).clamp(0.0, 2.0),
+        (layer.tint[2] * layer.brightness).clamp(0.0, 2.0),
+        layer.alpha.clamp(0.02, 1.0),
+        colored
+    );
+    if layer.angle_rad.abs() > 0.001 {
+        filter.push_str(&format!(
+            "[{}]rotate={:.6}:c=none:ow=rotw(iw):oh=roth(ih)[{}];",
+            colored, layer.angle_rad, rotated
+        ));
+    } else {
+        filter.push_str(&format!("[{}]copy[{}];", colored, rotated));
+    }
+    filter.push_str(&format!(
+        "[{}][{}]overlay=x='{:.3}-(overlay_w/2)+sin(t*{:.3})*{:.3}':y='{:.3}-(overlay_h/2)+cos(t*{:.3})*{:.3}':format=auto[{}];",
+        comp_in,
+        rotated,
+        layer.center_x,
+        fx,
+        ax,
+        layer.center_y,
+        fy,
+        ay,
+        moved
+    ));
+    filter.push_str(&format!("[{}]copy[{}];", moved, comp_out));
+    filter
+}
+
+/// Splits the layer chain into as few ffmpeg passes as `limits` allow. Each
+/// pass continues the composite of the previous one, so region blurs still
+/// see every layer below them.
+fn build_composite_passes(
+    layers: &[(Option<usize>, NativeDrawLayer)],
+    input_pngs: &[PathBuf],
+    underlay: Option<usize>,
+    canvas: (u32, u32),
+    viewports: &[SceneViewport],
+    out_video: &Path,
+    limits: CompositeLimits,
+) -> Vec<CompositePass> {
+    let (width, height) = canvas;
+    let intermediate = |n: usize| out_video.with_file_name(format!("native_pass_{:02}.mkv", n));
+    let mut passes = Vec::<CompositePass>::new();
+    let mut inputs = Vec::<(PathBuf, bool)>::new();
+    let mut filter = match underlay {
+        Some(index) => {
+            inputs.push((input_pngs[index].clone(), true));
+            "[0:v]format=rgba[comp0];".to_string()
+        }
+        None => format!(
+            "color=c=black@1.0:s={}x{}:d=1,format=rgba[comp0];",
+            width, height
+        ),
+    };
+    let mut comp_idx = 0usize;
+    let mut layers_in_pass = 0usize;
+
+    for (i, (input, layer)) in layers.iter().enumerate() {
+        let stage = |inputs: &Vec<(PathBuf, bool)>, comp_idx: usize| {
+            layer_composite_filter(
+                layer,
+                input.map(|_| inputs.len()),
+                *input,
+                i,
+                &format!("comp{}", comp_idx),
+                &format!("comp{}", comp_idx + 1),
+                canvas,
+            )
+        };
+        let mut snippet = stage(&inputs, comp_idx);
+        if snippet.is_empty() {
+            continue;
+        }
+        let too_many_inputs = input.is_some() && inputs.len() + 1 > limits.max_inputs;
+        if layers_in_pass > 0
+            && (too_many_inputs || filter.len() + snippet.len() > limits.max_filter_bytes)
+        {
+            let output = intermediate(passes.len() + 1);
+            filter.push_str(&format!("[comp{}]format=gbrp[v]", comp_idx));
+            passes.push(CompositePass {
+                inputs: std::mem::take(&mut inputs),
+                filter: std::mem::take(&mut filter),
+                output: output.clone(),
+                last: false,
+            });
+            inputs.push((output, false));
+            filter = "[0:v]format=rgba[comp0];".to_string();
+            comp_idx = 0;
+            layers_in_pass = 0;
+            snippet = stage(&inputs, comp_idx);
+        }
+        if let Some(index) = input {
+            inputs.push((input_pngs[*index].clone(), true));
+        }
+        filter.push_str(&snippet);
+        comp_idx += 1;
+        layers_in_pass += 1;
+    }
+
+    if viewports.is_empty() {
+        filter.push_str(&format!("[comp{}]{}[v]", comp_idx, YUV420P_BT709));
+    } else {
+        filter.push_str(&viewport_filter(
+            &format!("comp{}", comp_idx),
+            viewports,
+            width,
+            height,
+        ));
+        filter.push_str(&format!("[vpout]{}[v]", YUV420P_BT709));
+    }
+    passes.push(CompositePass {
+        inputs,
+        filter,
+        output: out_video.to_path_buf(),
+        last: true,
+    });
+    passes
+}
+
+fn run_composite_pass(pass: &CompositePass, duration: f32, fps: u32, dry_run: bool) -> Result<()> {
+    // Intermediates are lossless RGB so later passes do not stack artifacts.
+    let codec = if pass.last {
+        let mut codec = vec!["-c:v", "libx264", "-preset", "veryfast", "-crf", "20"];
+        codec.extend(SRGB_BT709_TAGS);
+        codec
+    } else {
+        vec!["-c:v", "ffv1"]
+    };
+    if dry_run {
+        let mut cmdline = "[dry-run] ffmpeg -hide_banner -loglevel error -y".to_string();
+        for (path, still) in &pass.inputs {
+            let looped = if *still { "-loop 1 " } else { "" };
+            cmdline.push_str(&format!(" {}-i '{}'", looped, path.display()));
+        }
+        cmdline.push_str(&format!(
+            " -filter_complex \"{}\" -map '[v]' -t {} -r {} -an {} '{}'",
+            pass.filter,
+            duration,
+            fps,
+            codec.join(" "),
+            pass.output.display()
+        ));
+        eprintln!("{}", cmdline);
+        return Ok(());
+    }
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-y");
+    for (path, still) in &pass.inputs {
+        if *still {
+            cmd.arg("-loop").arg("1");
+        }
+        cmd.arg("-i").arg(path);
+    }
+    let source = pass.inputs.first().map(|(path, _)| path.as_path());
+    let meta = source.map(|src| ProxyMeta::new("scene-native", src, &pass.filter));
+    let out = cmd
+        .arg("-filter_complex")
+        .arg(&pass.filter)
+        .arg("-map")
+        .arg("[v]")
+        .arg("-t")
+        .arg(duration.to_string())
+        .arg("-r")
+        .arg(fps.to_string())
+        .arg("-an")
+        .args(codec)
+        .args(meta.map(|m| m.ffmpeg_args()).unwrap_or_default())
+        .arg(&pass.output)
+        .run_output()
+        .context("Failed running ffmpeg for native animated proxy")?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        anyhow::bail!("ffmpeg native animated proxy failed: {}", err.trim());
+    }
+    Ok(())
 }
 
 pub fn render_native_animated_proxy(
@@ -293,18 +1035,14 @@ pub fn render_native_animated_proxy(
     session_dir: &Path,
     canvas_width: u32,
     canvas_height: u32,
-    seconds: u64,
+    seconds: Option<f32>,
     fps: u32,
     dry_run: bool,
     plan: &NativeRuntimePlan,
 ) -> Result<Option<NativeAnimatedRenderReport>> {
-    let ready_layers: Vec<_> = plan
-        .draw_layers
-        .iter()
-        .filter(|l| matches!(l.tier, NativeSupportTier::Ready) && l.primary_texture.is_some())
-        .cloned()
-        .collect();
+    let (ready_layers, mut trace) = partition_ready_layers(plan);
     if ready_layers.is_empty() {
+        record_emulation_stage(session_dir, "native-animated", trace, Vec::new())?;
         return Ok(None);
     }
 
@@ -315,16 +1053,21 @@ pub fn render_native_animated_proxy(
     let scratch = out_dir.join("scratch-animated");
     fs::create_dir_all(&scratch)
         .with_context(|| format!("Failed creating {}", scratch.display()))?;
+    let mut textures = LayerTextures::new(&resolver, &scratch, ready_layers.iter().map(|(_, l)| l));
 
     let width = canvas_width.max(1);
     let height = canvas_height.max(1);
-    let duration = seconds.max(4);
     let out_video = out_dir.join("native_animated_proxy.mp4");
 
     let mut rendered = Vec::<NativeLayerResult>::new();
     let mut input_pngs = Vec::<PathBuf>::new();
+    // Input index for textured quads, `None` for region blurs.
+    let mut loaded_layers = Vec::<(Option<usize>, NativeDrawLayer)>::new();
+    let mut clock_hands = Vec::<ClockHandOverlay>::new();
+    let mut underlay = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    let mut merged = 0usize;
 
-    for (idx, layer) in ready_layers.iter().enumerate() {
+    for (pass, layer) in &ready_layers {
         let texture_ref = layer.primary_texture.clone().unwrap_or_default();
         let mut record = NativeLayerResult {
             object_id: layer.object_id,
@@ -336,134 +1079,144 @@ pub fn render_native_animated_proxy(
             reason: None,
         };
 
-        let bytes = resolve_layer_image(&resolver, &texture_ref, &scratch)?;
-        let Some(bytes) = bytes else {
-            record.reason = Some(
-                "texture unresolved or unsupported format (expects png/jpg/webp or tex proxy)"
-                    .to_string(),
-            );
+        if layer.blur_sigma.is_some() {
+            if RegionBlur::from_layer(layer, width, height).is_some() {
+                trace.push(region_blur_trace(pass, layer));
+                loaded_layers.push((None, layer.clone()));
+                record.loaded = true;
+            }
             rendered.push(record);
             continue;
+        }
+
+        let img = match textures.take(&texture_ref)? {
+            Ok(img) => img,
+            Err(miss) => {
+                record.reason = Some(miss.record_reason().to_string());
+                trace.push(quad_trace_entry(
+                    pass,
+                    layer,
+                    EmulationKind::Dropped,
+                    miss.trace_reason(),
+                ));
+                rendered.push(record);
+                continue;
+            }
         };
 
-        let Some(img) = decode_layer_image(&bytes) else {
-            record.reason = Some("failed to decode image bytes".to_string());
+        if layer.merged_static {
+            composite_quad(&mut underlay, &img, layer);
+            trace.push(quad_trace_entry(
+                pass,
+                layer,
+                EmulationKind::StaticQuad,
+                "merged into the static underlay to fit the native cost budget",
+            ));
+            merged += 1;
+            record.loaded = true;
             rendered.push(record);
             continue;
-        };
-
-        let png_path = out_dir.join(format!("layer_{idx:03}.png"));
-        img.save(&png_path)
-            .with_context(|| format!("Failed writing {}", png_path.display()))?;
+        }
+        // Written at on-screen size so the ffmpeg scale is (nearly) a no-op.
+        let png_path = textures.screen_png(
+            &texture_ref,
+            &img,
+            layer_pixel_size(layer, width, height),
+            layer.texture_repeat,
+            &out_dir,
+        )?;
+        if let Some(hand) = layer.clock_hand {
+            let (layer_w, layer_h) = layer_pixel_size(layer, width, height);
+            trace.push(
+                quad_trace_entry(
+                    pass,
+                    layer,
+                    EmulationKind::ClockHand,
+                    "drawn live by mpv, rotated from the wall clock",
+                )
+                .param("period_seconds", hand.period_seconds())
+                .param("smooth", hand.smooth),
+            );
+            clock_hands.push(ClockHandOverlay {
+                object_id: layer.object_id,
+                image: filter_safe_path(&png_path).to_string_lossy().to_string(),
+                center_x: layer.center_x,
+                center_y: layer.center_y,
+                width: layer_w,
+                height: layer_h,
+                alpha: layer.alpha,
+                tint: layer.tint.map(|c| c * layer.brightness),
+                hand,
+            });
+            record.loaded = true;
+            rendered.push(record);
+            continue;
+        }
+        let (fx, fy, ax, ay) = layer_motion(&layer.uniforms, input_pngs.len());
+        trace.push(
+            quad_trace_entry(
+                pass,
+                layer,
+                EmulationKind::AnimatedQuad,
+                "composited as a textured quad with sinusoidal drift",
+            )
+            .param("motion_freq_x", fx)
+            .param("motion_freq_y", fy)
+            .param("motion_amp_x", ax)
+            .param("motion_amp_y", ay),
+        );
+        loaded_layers.push((Some(input_pngs.len()), layer.clone()));
         input_pngs.push(png_path);
         record.loaded = true;
         rendered.push(record);
     }
 
-    if input_pngs.is_empty() {
+    record_emulation_stage(session_dir, "native-animated", trace, Vec::new())?;
+    if input_pngs.is_empty() && merged == 0 {
         return Ok(None);
     }
+    let underlay_input = (merged > 0).then(|| {
+        let path = out_dir.join("underlay.png");
+        input_pngs.push(path.clone());
+        (input_pngs.len() - 1, path)
+    });
+    if let Some((_, path)) = &underlay_input {
+        underlay
+            .save(path)
+            .with_context(|| format!("Failed writing {}", path.display()))?;
+    }
 
-    let mut filter = format!(
-        "color=c=black@1.0:s={}x{}:d=1,format=rgba[comp0];",
-        width, height
+    // Whole drift cycles for every layer so the proxy loops without a jump.
+    let duration = match seconds {
+        Some(seconds) => seconds.clamp(1.0, 600.0),
+        None => loop_friendly_seconds(
+            &loaded_layers
+                .iter()
+                .filter_map(|(input, layer)| Some((input.as_ref()?, layer)))
+                .flat_map(|(&i, layer)| {
+                    let (fx, fy, _, _) = layer_motion(&layer.uniforms, i);
+                    [fx, fy]
+                })
+                .collect::<Vec<_>>(),
+        ),
+    };
+    let passes = build_composite_passes(
+        &loaded_layers,
+        &input_pngs,
+        underlay_input.as_ref().map(|(i, _)| *i),
+        (width, height),
+        &plan.viewports,
+        &out_video,
+        CompositeLimits::default(),
     );
-    let mut comp_idx = 0usize;
-    for (i, layer) in ready_layers.iter().enumerate() {
-        if i >= input_pngs.len() {
-            break;
-        }
-        let input_idx = i;
-        let moved = format!("l{}_m", i);
-        let colored = format!("l{}_c", i);
-        let rotated = format!("l{}_r", i);
-        let next_comp = format!("comp{}", comp_idx + 1);
-        let (fx, fy, ax, ay) = layer_motion(&layer.uniforms, i);
-        let layer_w = layer.width.max(8.0).min(width as f32 * 2.0).round() as u32;
-        let layer_h = layer.height.max(8.0).min(height as f32 * 2.0).round() as u32;
-
-        filter.push_str(&format!(
-            "[{}:v]format=rgba,scale={}:{}:flags=bicubic,setsar=1,colorchannelmixer=rr={:.3}:gg={:.3}:bb={:.3}:aa={:.3}[{}];",
-            input_idx,
-            layer_w,
-            layer_h,
-            (layer.tint[0] * layer.brightness).clamp(0.0, 2.0),
-            (layer.tint[1] * layer.brightness).clamp(0.0, 2.0),
-            (layer.tint[2] * layer.brightness).clamp(0.0, 2.0),
-            layer.alpha.clamp(0.02, 1.0),
-            colored
-        ));
-        if layer.angle_rad.abs() > 0.001 {
-            filter.push_str(&format!(
-                "[{}]rotate={:.6}:c=none:ow=rotw(iw):oh=roth(ih)[{}];",
-                colored, layer.angle_rad, rotated
-            ));
-        } else {
-            filter.push_str(&format!("[{}]copy[{}];", colored, rotated));
-        }
-        filter.push_str(&format!(
-            "[comp{}][{}]overlay=x='{:.3}-(overlay_w/2)+sin(t*{:.3})*{:.3}':y='{:.3}-(overlay_h/2)+cos(t*{:.3})*{:.3}':format=auto[{}];",
-            comp_idx,
-            rotated,
-            layer.center_x,
-            fx,
-            ax,
-            layer.center_y,
-            fy,
-            ay,
-            moved
-        ));
-
-        filter.push_str(&format!("[{}]copy[{}];", moved, next_comp));
-        comp_idx += 1;
+    for pass in &passes {
+        run_composite_pass(pass, duration, fps.max(24), dry_run)?;
     }
-    filter.push_str(&format!("[comp{}]format=yuv420p[v]", comp_idx));
-
-    if dry_run {
-        let mut cmdline = "[dry-run] ffmpeg -hide_banner -loglevel error -y".to_string();
-        for p in &input_pngs {
-            cmdline.push_str(&format!(" -loop 1 -i '{}'", p.display()));
-        }
-        cmdline.push_str(&format!(
-            " -filter_complex \"{}\" -map '[v]' -t {} -r {} -an -c:v libx264 -preset veryfast -crf 20 '{}'",
-            filter,
-            duration,
-            fps.max(24),
-            out_video.display()
-        ));
-        println!("{}", cmdline);
-    } else {
-        let mut cmd = Command::new("ffmpeg");
-        cmd.arg("-hide_banner")
-            .arg("-loglevel")
-            .arg("error")
-            .arg("-y");
-        for p in &input_pngs {
-            cmd.arg("-loop").arg("1").arg("-i").arg(p);
-        }
-        let out = cmd
-            .arg("-filter_complex")
-            .arg(&filter)
-            .arg("-map")
-            .arg("[v]")
-            .arg("-t")
-            .arg(duration.to_string())
-            .arg("-r")
-            .arg(fps.max(24).to_string())
-            .arg("-an")
-            .arg("-c:v")
-            .arg("libx264")
-            .arg("-preset")
-            .arg("veryfast")
-            .arg("-crf")
-            .arg("20")
-            .arg(&out_video)
-            .output()
-            .context("Failed running ffmpeg for native animated proxy")?;
-        if !out.status.success() {
-            let err = String::from_utf8_lossy(&out.stderr);
-            anyhow::bail!("ffmpeg native animated proxy failed: {}", err.trim());
-        }
+    if passes.len() > 1 {
+        eprintln!(
+            "[ok] native filter graph split into {} ffmpeg passes",
+            passes.len()
+        );
     }
 
     let report = NativeAnimatedRenderReport {
@@ -477,11 +1230,14 @@ pub fn render_native_animated_proxy(
         seconds: duration,
         fps: fps.max(24),
         total_ready_layers: ready_layers.len(),
-        rendered_layers: input_pngs.len(),
+        rendered_layers: input_pngs.len() + clock_hands.len() + merged - usize::from(merged > 0),
         layers: rendered,
+        clock_hands,
+        ffmpeg_passes: passes.len(),
         notes: vec![
             "Native animated compositor built from ready draw layers".to_string(),
             "Current animation path is ffmpeg-based with per-layer motion + blend".to_string(),
+            format!("ffmpeg passes: {}", passes.len()),
         ],
     };
     fs::write(&report.report_path, serde_json::to_vec_pretty(&report)?)
@@ -489,3 +1245,360 @@ pub fn render_native_animated_proxy(
 
     Ok(Some(report))
 }
+
+/// One drawing step of a [`NativeFrameStream`].
+enum StreamStep {
+    /// Layer image at on-screen size with its drift `(fx, fy, ax, ay)`.
+    Quad {
+        image: RgbaImage,
+        layer: Box<NativeDrawLayer>,
+        motion: (f32, f32, f32, f32),
+    },
+    Blur(RegionBlur),
+}
+
+/// The animated compositor evaluated in-process, one frame at a time, so
+/// frames can be streamed for as long as the wallpaper runs instead of being
+/// baked into a finite proxy.
+pub struct NativeFrameStream {
+    underlay: RgbaImage,
+    steps: Vec<StreamStep>,
+    viewports: Vec<SceneViewport>,
+    /// Drawn live by mpv over the stream, as with the animated proxy.
+    pub clock_hands: Vec<ClockHandOverlay>,
+    pub rendered_layers: usize,
+}
+
+impl NativeFrameStream {
+    /// Decodes and scales every ready layer once. `None` when nothing can be
+    /// drawn.
+    pub fn build(
+        root: &Path,
+        session_dir: &Path,
+        canvas_width: u32,
+        canvas_height: u32,
+        plan: &NativeRuntimePlan,
+    ) -> Result<Option<Self>> {
+        let (ready_layers, mut trace) = partition_ready_layers(plan);
+        if ready_layers.is_empty() {
+            record_emulation_stage(session_dir, "native-stream", trace, Vec::new())?;
+            return Ok(None);
+        }
+
+        let resolver = AssetResolver::new(root)?;
+        let out_dir = session_dir.join("native-stream");
+        let scratch = out_dir.join("scratch");
+        fs::create_dir_all(&scratch)
+            .with_context(|| format!("Failed creating {}", scratch.display()))?;
+        let mut textures =
+            LayerTextures::new(&resolver, &scratch, ready_layers.iter().map(|(_, l)| l));
+
+        let width = canvas_width.max(1);
+        let height = canvas_height.max(1);
+        let mut underlay = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+        let mut steps = Vec::new();
+        let mut clock_hands = Vec::new();
+        let mut rendered_layers = 0usize;
+        let mut moving = 0usize;
+
+        for (pass, layer) in &ready_layers {
+            if layer.blur_sigma.is_some() {
+                if let Some(region) = RegionBlur::from_layer(layer, width, height) {
+                    trace.push(region_blur_trace(pass, layer));
+                    steps.push(StreamStep::Blur(region));
+                }
+                continue;
+            }
+            let texture_ref = layer.primary_texture.clone().unwrap_or_default();
+            let img = match textures.take(&texture_ref)? {
+                Ok(img) => img,
+                Err(miss) => {
+                    trace.push(quad_trace_entry(
+                        pass,
+                        layer,
+                        EmulationKind::Dropped,
+                        miss.trace_reason(),
+                    ));
+                    continue;
+                }
+            };
+            rendered_layers += 1;
+            if layer.merged_static {
+                composite_quad(&mut underlay, &img, layer);
+                trace.push(quad_trace_entry(
+                    pass,
+                    layer,
+                    EmulationKind::StaticQuad,
+                    "merged into the static underlay to fit the native cost budget",
+                ));
+                continue;
+            }
+            if let Some(hand) = layer.clock_hand {
+                let (layer_w, layer_h) = layer_pixel_size(layer, width, height);
+                let png_path = textures.screen_png(
+                    &texture_ref,
+                    &img,
+                    (layer_w, layer_h),
+                    layer.texture_repeat,
+                    &out_dir,
+                )?;
+                trace.push(quad_trace_entry(
+                    pass,
+                    layer,
+                    EmulationKind::ClockHand,
+                    "drawn live by mpv, rotated from the wall clock",
+                ));
+                clock_hands.push(ClockHandOverlay {
+                    object_id: layer.object_id,
+                    image: filter_safe_path(&png_path).to_string_lossy().to_string(),
+                    center_x: layer.center_x,
+                    center_y: layer.center_y,
+                    width: layer_w,
+                    height: layer_h,
+                    alpha: layer.alpha,
+                    tint: layer.tint.map(|c| c * layer.brightness),
+                    hand,
+                });
+                continue;
+            }
+
+            let mut image = scale_to_layer(&img, layer, width, height);
+            // Render-target blurs run on the texture itself, as in the proxy graph.
+            for sigma in layer.chain.iter().filter_map(|step| step.blur_sigma) {
+                image = image::imageops::blur(&image, sigma);
+            }
+            let motion = layer_motion(&layer.uniforms, moving);
+            moving += 1;
+            let mut entry = quad_trace_entry(
+                pass,
+                layer,
+                EmulationKind::AnimatedQuad,
+                "streamed as a textured quad with sinusoidal drift",
+            )
+            .param("motion_freq_x", motion.0)
+            .param("motion_freq_y", motion.1);
+            if !layer.script_animations.is_empty() {
+                let animations = serde_json::to_value(&layer.script_animations)?;
+                entry = entry.param("script_animations", animations);
+            }
+            trace.push(entry);
+            steps.push(StreamStep::Quad {
+                image,
+                layer: Box::new(layer.clone()),
+                motion,
+            });
+        }
+
+        record_emulation_stage(session_dir, "native-stream", trace, Vec::new())?;
+        if rendered_layers == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            underlay,
+            steps,
+            viewports: plan.viewports.clone(),
+            clock_hands,
+            rendered_layers,
+        }))
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.underlay.dimensions()
+    }
+
+    /// The scene `t` seconds after the stream started.
+    pub fn render(&self, t: f32) -> RgbaImage {
+        let mut canvas = self.underlay.clone();
+        for step in &self.steps {
+            match step {
+                StreamStep::Quad {
+                    image,
+                    layer,
+                    motion: (fx, fy, ax, ay),
+                } => {
+                    let offset = ((t * fx).sin() * ax, (t * fy).cos() * ay);
+                    blit_layer_as(&mut canvas, image, layer, offset, layer.appearance_at(t));
+                }
+                StreamStep::Blur(region) => blur_canvas_region(&mut canvas, region),
+            }
+        }
+        if self.viewports.is_empty() {
+            canvas
+        } else {
+            composite_viewports(&canvas, &self.viewports)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_rects_clip_source_to_canvas() {
+        let vp = SceneViewport {
+            index: 0,
+            name: None,
+            dest_x: 960,
+            dest_y: 0,
+            dest_width: 960,
+            dest_height: 1080,
+            source_x: -480.0,
+            source_y: 0.0,
+            source_width: 960.0,
+            source_height: 1080.0,
+        };
+        // Left half of the window is off-canvas, so only the right half of the
+        // destination region is filled.
+        assert_eq!(
+            viewport_rects(&vp, 1920, 1080),
+            Some(((0, 0, 480, 1080), (1440, 0, 480, 1080)))
+        );
+    }
+
+    #[test]
+    fn clock_hands_chain_into_one_lavfi_graph() {
+        let hand = |unit| ClockHandOverlay {
+            object_id: 1,
+            image: "/tmp/hand.png".to_string(),
+            center_x: 960.0,
+            center_y: 540.0,
+            width: 40,
+            height: 400,
+            alpha: 1.0,
+            tint: [1.0, 1.0, 1.0],
+            hand: ClockHand { unit, smooth: true },
+        };
+        assert!(clock_hands_filter(&[], 0).is_none());
+        let filter = clock_hands_filter(
+            &[
+                hand(crate::scene_script::ClockHandUnit::Hours),
+                hand(crate::scene_script::ClockHandUnit::Seconds),
+            ],
+            7200,
+        )
+        .unwrap();
+        assert!(filter.starts_with("lavfi=[movie=/tmp/hand.png,"));
+        assert!(filter.contains("mod(time(0)+7200\\,43200)"));
+        assert!(filter.contains("[vin][h0]overlay="));
+        assert!(filter.contains("[c1][h1]overlay="));
+        assert!(filter.ends_with("[vout]]"));
+    }
+
+    #[test]
+    fn repeat_layers_tile_at_native_size() {
+        let tile = RgbaImage::from_fn(2, 2, |x, y| {
+            Rgba([(x * 100) as u8, (y * 100) as u8, 0, 255])
+        });
+        let tiled = tile_to_size(&tile, 5, 3);
+        assert_eq!(tiled.dimensions(), (5, 3));
+        assert_eq!(tiled.get_pixel(4, 2), tile.get_pixel(0, 0));
+        assert_eq!(tiled.get_pixel(3, 1), tile.get_pixel(1, 1));
+    }
+
+    #[test]
+    fn oversized_graphs_chain_through_intermediate_passes() {
+        let layers: Vec<(Option<usize>, NativeDrawLayer)> = (0..5)
+            .map(|i| {
+                (
+                    Some(i),
+                    crate::scene_native_runtime::tests::layer(i as u64, 100.0, 100.0, 1.0).1,
+                )
+            })
+            .collect();
+        let pngs: Vec<PathBuf> = (0..5)
+            .map(|i| PathBuf::from(format!("/tmp/l{}.png", i)))
+            .collect();
+        let out = Path::new("/tmp/render/native_animated_proxy.mp4");
+
+        let single = build_composite_passes(
+            &layers,
+            &pngs,
+            None,
+            (1920, 1080),
+            &[],
+            out,
+            CompositeLimits::default(),
+        );
+        assert_eq!(single.len(), 1);
+        assert!(single[0].filter.starts_with("color=c=black"));
+        assert!(
+            single[0]
+                .filter
+                .ends_with(&format!("[comp5]{}[v]", YUV420P_BT709))
+        );
+
+        let limits = CompositeLimits {
+            max_filter_bytes: usize::MAX,
+            max_inputs: 3,
+        };
+        let passes = build_composite_passes(&layers, &pngs, None, (1920, 1080), &[], out, limits);
+        // Three layers, then the intermediate plus the last two.
+        assert_eq!(passes.len(), 2);
+        assert!(passes.iter().all(|p| p.inputs.len() <= 3));
+        assert_eq!(
+            passes[0].output,
+            Path::new("/tmp/render/native_pass_01.mkv")
+        );
+        assert!(passes[0].filter.ends_with("format=gbrp[v]"));
+        // The next pass starts from the previous composite.
+        assert_eq!(passes[1].inputs[0], (passes[0].output.clone(), false));
+        assert!(
+            passes[1]
+                .filter
+                .starts_with("[0:v]format=rgba[comp0];[1:v]")
+        );
+        assert!(passes[1].last && passes[1].output == out);
+        assert!(
+            passes[1]
+                .filter
+                .ends_with(&format!("[comp2]{}[v]", YUV420P_BT709))
+        );
+
+        let mock = std::rc::Rc::new(crate::command_runner::MockRunner::new());
+        crate::command_runner::with_runner(mock.clone(), || {
+            for pass in &passes {
+                run_composite_pass(pass, 4.0, 30, false).unwrap();
+            }
+        });
+        let cmds = mock.commands();
+        assert_eq!(cmds.len(), 2);
+        assert!(cmds[0].starts_with("ffmpeg ") && cmds[0].contains("-c:v ffv1"));
+        assert!(cmds[1].ends_with("/tmp/render/native_animated_proxy.mp4"));
+    }
+
+    #[test]
+    fn shared_textures_decode_once_and_write_at_screen_size() {
+        let root = tempfile::tempdir().unwrap();
+        RgbaImage::from_pixel(64, 32, Rgba([255, 0, 0, 255]))
+            .save(root.path().join("big.png"))
+            .unwrap();
+        let resolver = AssetResolver::new(root.path()).unwrap();
+        let scratch = root.path().join("scratch");
+        let (_, mut a) = crate::scene_native_runtime::tests::layer(1, 16.0, 8.0, 1.0);
+        a.primary_texture = Some("big.png".to_string());
+        let b = a.clone();
+        let mut textures = LayerTextures::new(&resolver, &scratch, [&a, &b]);
+
+        let first = textures.take("big.png").unwrap().unwrap();
+        assert!(textures.decoded.contains_key("big.png"));
+        let second = textures.take("big.png").unwrap().unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert!(textures.decoded.is_empty());
+        assert_eq!(
+            textures.take("missing.png").unwrap().err(),
+            Some(TextureMiss::Unresolved)
+        );
+
+        let png_a = textures
+            .screen_png("big.png", &first, (16, 8), false, root.path())
+            .unwrap();
+        let png_b = textures
+            .screen_png("big.png", &second, (16, 8), false, root.path())
+            .unwrap();
+        assert_eq!(png_a, png_b);
+        assert_eq!(image::image_dimensions(&png_a).unwrap(), (16, 8));
+        // Smaller textures are left for the compositor to enlarge.
+        assert_eq!(fit_to_screen(&first, 128, 64, false).dimensions(), (64, 32));
+    }
+}
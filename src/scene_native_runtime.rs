@@ -1,11 +1,14 @@
-use crate::scene_gpu_graph::{GpuPassSpec, SceneGpuGraph};
-use serde::Serialize;
+use crate::scene_gpu_graph::{GpuPassSpec, PrunedObject, SceneGpuGraph, SceneViewport};
+use crate::scene_script::{AnimatedProperty, ClockHand, PropertyAnimation};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NativeSupportTier {
     Ready,
+    /// Drawn through an approximation (e.g. blur effects as region blurs).
+    ExperimentalReady,
     Experimental,
     Unsupported,
 }
@@ -23,7 +26,7 @@ pub struct NativePassSupport {
     pub reason: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NativeDrawLayer {
     pub object_index: usize,
     pub object_id: u64,
@@ -32,6 +35,9 @@ pub struct NativeDrawLayer {
     pub shader: String,
     pub shader_family: String,
     pub primary_texture: Option<String>,
+    /// Tile the texture at its native size instead of stretching it.
+    #[serde(default)]
+    pub texture_repeat: bool,
     pub blend_mode: String,
     pub depth_test: String,
     pub depth_write: String,
@@ -49,9 +55,108 @@ pub struct NativeDrawLayer {
     pub shader_defines: Vec<String>,
     pub uniforms: BTreeMap<String, Value>,
     pub tier: NativeSupportTier,
+    /// Set on the first pass of a blur effect: blur the composite under the
+    /// layer rect instead of drawing a texture.
+    #[serde(default)]
+    pub blur_sigma: Option<f32>,
+    /// Rotation follows the local time; composited live at playback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_hand: Option<ClockHand>,
+    /// Colour, alpha or brightness scripts re-evaluated every streamed frame;
+    /// baked proxies keep the values at load time.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub script_animations: Vec<PropertyAnimation>,
+    /// Folded into the static underlay of the animated compositor to fit the
+    /// cost budget; drawn without drift.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub merged_static: bool,
+    /// Render-target passes of the object's effects, applied in order to the
+    /// layer texture before it is composited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<ChainedPass>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl NativeDrawLayer {
+    /// `(alpha, brightness, tint)` `t` seconds into playback.
+    pub fn appearance_at(&self, t: f32) -> (f32, f32, [f32; 3]) {
+        let (mut alpha, mut brightness, mut tint) = (self.alpha, self.brightness, self.tint);
+        for anim in &self.script_animations {
+            match anim.property {
+                AnimatedProperty::Color => tint = anim.animation.color_at(self.tint, t),
+                AnimatedProperty::Alpha => {
+                    alpha = anim.animation.scalar_at(self.alpha, t).clamp(0.0, 1.0)
+                }
+                AnimatedProperty::Brightness => {
+                    brightness = anim.animation.scalar_at(self.brightness, t).max(0.0)
+                }
+            }
+        }
+        (alpha, brightness, tint)
+    }
+}
+
+/// One pass of a render-target chain folded into a draw layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainedPass {
+    pub shader: String,
+    /// Render target written, or `layer` for the final pass.
+    pub target: String,
+    /// Blur passes are approximated as a gaussian blur of the texture; other
+    /// passes are carried for inspection only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blur_sigma: Option<f32>,
+}
+
+/// Region blur in canvas fractions (0..1) so the same filter works for any
+/// proxy resolution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegionBlur {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub sigma: f32,
+}
+
+impl RegionBlur {
+    pub fn from_layer(layer: &NativeDrawLayer, scene_w: u32, scene_h: u32) -> Option<Self> {
+        let sigma = layer.blur_sigma?;
+        let (sw, sh) = (scene_w.max(1) as f32, scene_h.max(1) as f32);
+        let x0 = ((layer.center_x - layer.width / 2.0) / sw).clamp(0.0, 1.0);
+        let y0 = ((layer.center_y - layer.height / 2.0) / sh).clamp(0.0, 1.0);
+        let x1 = ((layer.center_x + layer.width / 2.0) / sw).clamp(0.0, 1.0);
+        let y1 = ((layer.center_y + layer.height / 2.0) / sh).clamp(0.0, 1.0);
+        (x1 - x0 > 0.001 && y1 - y0 > 0.001).then_some(Self {
+            x: x0,
+            y: y0,
+            w: x1 - x0,
+            h: y1 - y0,
+            sigma,
+        })
+    }
+
+    /// `[input]` -> blurred copy of the region overlaid back -> `[output]`.
+    pub fn ffmpeg_filter(&self, input: &str, output: &str, tag: &str) -> String {
+        format!(
+            "[{input}]split[{tag}a][{tag}b];[{tag}b]crop=w='max(2,iw*{w:.5})':h='max(2,ih*{h:.5})':x='iw*{x:.5}':y='ih*{y:.5}',gblur=sigma={s:.2}[{tag}c];[{tag}a][{tag}c]overlay=x='main_w*{x:.5}':y='main_h*{y:.5}'[{output}];",
+            w = self.w,
+            h = self.h,
+            x = self.x,
+            y = self.y,
+            s = self.sigma
+        )
+    }
+}
+
+pub fn region_blurs(plan: &NativeRuntimePlan, scene_w: u32, scene_h: u32) -> Vec<RegionBlur> {
+    plan.draw_layers
+        .iter()
+        .filter(|l| l.visible)
+        .filter_map(|l| RegionBlur::from_layer(l, scene_w, scene_h))
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct NativeRuntimePlan {
     pub total_pass_nodes: usize,
     pub ready_nodes: usize,
@@ -63,7 +168,211 @@ pub struct NativeRuntimePlan {
     pub ready_draw_layers: usize,
     pub passes: Vec<NativePassSupport>,
     pub draw_layers: Vec<NativeDrawLayer>,
+    pub viewports: Vec<SceneViewport>,
+    /// Hidden objects the graph dropped; kept for the emulation trace.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pruned: Vec<PrunedObject>,
     pub notes: Vec<String>,
+    /// Filled by `apply_cost_budget`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<NativeBudgetReport>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NativeBudgetAction {
+    Merged,
+    Dropped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NativeBudgetTrim {
+    #[serde(flatten)]
+    pub pass: NativePassSupport,
+    pub action: NativeBudgetAction,
+    pub cost: f32,
+    pub impact: f32,
+    pub budget_reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NativeBudgetReport {
+    pub budget: f32,
+    pub estimated_cost: f32,
+    pub final_cost: f32,
+    pub trimmed: Vec<NativeBudgetTrim>,
+}
+
+/// One fullscreen 1080p quad costs about 1.0; fixed per-layer filter setup.
+const LAYER_BASE_COST: f32 = 0.25;
+const FULL_HD_PIXELS: f32 = 1920.0 * 1080.0;
+
+/// Cost units the native compositor may spend on this machine:
+/// `KWE_NATIVE_BUDGET`, or two fullscreen quads per CPU thread.
+pub fn native_cost_budget() -> f32 {
+    if let Some(budget) = std::env::var("KWE_NATIVE_BUDGET")
+        .ok()
+        .and_then(|v| v.trim().parse::<f32>().ok())
+        .filter(|v| *v > 0.0)
+    {
+        return budget;
+    }
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4) as f32;
+    (threads * 2.0).clamp(4.0, 48.0)
+}
+
+fn layer_pixels(layer: &NativeDrawLayer, scene_w: u32, scene_h: u32) -> f32 {
+    let scene = (scene_w.max(1) * scene_h.max(1)) as f32;
+    (layer.width.abs() * layer.height.abs()).min(scene)
+}
+
+/// Estimated per-frame cost of a layer in the animated ffmpeg graph. Clock
+/// hands are drawn by mpv and cost nothing here.
+pub fn layer_cost(layer: &NativeDrawLayer, scene_w: u32, scene_h: u32) -> f32 {
+    if layer.clock_hand.is_some() {
+        return 0.0;
+    }
+    let area = layer_pixels(layer, scene_w, scene_h) / FULL_HD_PIXELS;
+    if let Some(sigma) = layer.blur_sigma {
+        return LAYER_BASE_COST + area * (1.0 + sigma / 8.0);
+    }
+    let rotate = if layer.angle_rad.abs() > 0.001 {
+        0.75
+    } else {
+        0.0
+    };
+    LAYER_BASE_COST + area * (1.0 + rotate)
+}
+
+/// How much the layer changes the picture: covered fraction of the scene
+/// weighted by opacity (blur strength for blurs).
+pub fn layer_impact(layer: &NativeDrawLayer, scene_w: u32, scene_h: u32) -> f32 {
+    let coverage = layer_pixels(layer, scene_w, scene_h) / (scene_w.max(1) * scene_h.max(1)) as f32;
+    match layer.blur_sigma {
+        Some(sigma) => coverage * (sigma / 10.0).min(1.0) * 0.5,
+        None => coverage * layer.alpha.clamp(0.0, 1.0),
+    }
+}
+
+fn is_budget_candidate(layer: &NativeDrawLayer) -> bool {
+    match layer.tier {
+        NativeSupportTier::Ready => layer.primary_texture.is_some(),
+        NativeSupportTier::ExperimentalReady => layer.blur_sigma.is_some(),
+        _ => false,
+    }
+}
+
+/// Keeps the animated compositor under `budget`: first the bottom run of
+/// plain (unrotated) quads is merged into one static underlay, then the lowest-impact
+/// layers are dropped until the estimate fits.
+pub fn apply_cost_budget(plan: &mut NativeRuntimePlan, scene_w: u32, scene_h: u32, budget: f32) {
+    let costs: Vec<f32> = plan
+        .draw_layers
+        .iter()
+        .map(|l| {
+            if is_budget_candidate(l) {
+                layer_cost(l, scene_w, scene_h)
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let estimated_cost: f32 = costs.iter().sum();
+    let mut cost = estimated_cost;
+    let mut trimmed = Vec::new();
+
+    if cost > budget {
+        let mergeable = plan
+            .draw_layers
+            .iter()
+            .take_while(|l| {
+                !is_budget_candidate(l)
+                    || (l.clock_hand.is_none()
+                        && l.script_animations.is_empty()
+                        && l.blur_sigma.is_none()
+                        && l.angle_rad.abs() <= 0.001)
+            })
+            .enumerate()
+            .filter(|(_, l)| is_budget_candidate(l))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        // The underlay is one fullscreen input.
+        let underlay = LAYER_BASE_COST + (scene_w * scene_h) as f32 / FULL_HD_PIXELS;
+        let merged_cost: f32 = mergeable.iter().map(|&i| costs[i]).sum();
+        if mergeable.len() > 1 && merged_cost > underlay {
+            cost += underlay;
+            for i in mergeable {
+                let layer = &mut plan.draw_layers[i];
+                layer.merged_static = true;
+                cost -= costs[i];
+                trimmed.push(NativeBudgetTrim {
+                    pass: plan.passes[i].clone(),
+                    action: NativeBudgetAction::Merged,
+                    cost: costs[i],
+                    impact: layer_impact(layer, scene_w, scene_h),
+                    budget_reason: "bottom layer folded into the static underlay".to_string(),
+                });
+            }
+        }
+    }
+
+    let mut dropped = Vec::<usize>::new();
+    while cost > budget {
+        let Some((i, impact)) = plan
+            .draw_layers
+            .iter()
+            .enumerate()
+            .filter(|(i, l)| {
+                is_budget_candidate(l)
+                    && !l.merged_static
+                    && costs[*i] > 0.0
+                    && !dropped.contains(i)
+            })
+            .map(|(i, l)| (i, layer_impact(l, scene_w, scene_h)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            break;
+        };
+        cost -= costs[i];
+        dropped.push(i);
+        trimmed.push(NativeBudgetTrim {
+            pass: plan.passes[i].clone(),
+            action: NativeBudgetAction::Dropped,
+            cost: costs[i],
+            impact,
+            budget_reason: format!(
+                "lowest-impact layer dropped: estimated cost {:.2} over budget {:.2}",
+                cost + costs[i],
+                budget
+            ),
+        });
+    }
+    dropped.sort_unstable();
+    for i in dropped.into_iter().rev() {
+        plan.passes.remove(i);
+        let layer = plan.draw_layers.remove(i);
+        if matches!(layer.tier, NativeSupportTier::Ready) {
+            plan.ready_draw_layers = plan.ready_draw_layers.saturating_sub(1);
+        }
+    }
+
+    if !trimmed.is_empty() {
+        plan.notes.push(format!(
+            "native cost budget {:.2}: estimated {:.2} -> {:.2} ({} layers trimmed)",
+            budget,
+            estimated_cost,
+            cost,
+            trimmed.len()
+        ));
+    }
+    plan.budget = Some(NativeBudgetReport {
+        budget,
+        estimated_cost,
+        final_cost: cost.max(0.0),
+        trimmed,
+    });
 }
 
 fn shader_family(shader: &str) -> String {
@@ -120,6 +429,42 @@ fn classify_family(shader: &str) -> (NativeSupportTier, String) {
     }
 }
 
+/// Built-in and common workshop blur effects (blur, blurprecise, depth of
+/// field, frosted glass...). Directional blurs are approximated as gaussian.
+pub fn is_blur_effect(effect_file: &str) -> bool {
+    let lower = effect_file.to_ascii_lowercase();
+    ["blur", "depthoffield", "dof/", "bokeh", "frost"]
+        .iter()
+        .any(|k| lower.contains(k))
+}
+
+const DEFAULT_BLUR_SIGMA: f32 = 6.0;
+
+fn blur_sigma(uniforms: &BTreeMap<String, Value>) -> f32 {
+    let strength = [
+        "g_BlurScale",
+        "g_BlurAmount",
+        "g_BlurStrength",
+        "g_Scale",
+        "g_Amount",
+        "g_Strength",
+    ]
+    .iter()
+    .find_map(|k| {
+        let v = uniforms.get(*k)?;
+        parse_f32_from_value(v).or_else(|| {
+            v.as_str()?
+                .split_whitespace()
+                .filter_map(|c| c.parse::<f32>().ok())
+                .reduce(f32::max)
+        })
+    });
+    match strength {
+        Some(v) => (v.abs() * 4.0).clamp(0.5, 40.0),
+        None => DEFAULT_BLUR_SIGMA,
+    }
+}
+
 fn parse_alpha(uniforms: &BTreeMap<String, Value>) -> f32 {
     uniforms
         .get("g_UserAlpha")
@@ -213,15 +558,21 @@ fn layer_rect_from_node(
     (center_x, center_y, width, height, angle_rad)
 }
 
+/// Render targets (`_rt_*`, `previous`) are not textures we can decode.
+fn is_texture_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && !name.starts_with("_rt_") && name != "previous"
+}
+
 fn first_texture(pass: &GpuPassSpec) -> Option<String> {
     pass.textures
         .iter()
-        .find(|t| !t.trim().is_empty())
+        .find(|t| is_texture_name(t))
         .cloned()
         .or_else(|| {
             pass.texture_refs
                 .iter()
-                .find(|t| !t.trim().is_empty())
+                .find(|t| is_texture_name(t))
                 .cloned()
         })
 }
@@ -247,6 +598,80 @@ fn with_texture_gate(
     }
 }
 
+/// Upper bound on the copies of one particle layer, so a large `count`
+/// override cannot blow up the composite.
+const MAX_PARTICLE_INSTANCES: usize = 16;
+
+/// Deterministic 0..1 jitter for instance `k` of `object_id`.
+fn instance_jitter(object_id: u64, k: usize, axis: u64) -> f32 {
+    let mut h = object_id
+        .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        .wrapping_add((k as u64) << 8 | axis);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    (h % 10_000) as f32 / 10_000.0
+}
+
+/// Applies `instanceoverride` count/size to particle layers: `size` scales
+/// the sprite, a `count` above 1 duplicates it with jittered positions over
+/// the emitter rect, and below 1 thins it out through alpha. Copies are
+/// inserted right after the original in both `passes` and `layers`.
+fn expand_particle_instances(
+    passes: &mut Vec<NativePassSupport>,
+    layers: &mut Vec<NativeDrawLayer>,
+    scene_w: f32,
+    scene_h: f32,
+) -> Vec<String> {
+    let mut notes = Vec::new();
+    for i in (0..layers.len()).rev() {
+        let layer = &mut layers[i];
+        if layer.shader_family != "particle" || !matches!(layer.tier, NativeSupportTier::Ready) {
+            continue;
+        }
+        let count = layer
+            .uniforms
+            .get("instance_count")
+            .and_then(parse_f32_from_value)
+            .unwrap_or(1.0)
+            .max(0.0);
+        let size = layer
+            .uniforms
+            .get("instance_size")
+            .and_then(parse_f32_from_value)
+            .unwrap_or(1.0)
+            .clamp(0.05, 8.0);
+        let (spread_w, spread_h) = (layer.width, layer.height);
+        layer.width = (layer.width * size).max(8.0);
+        layer.height = (layer.height * size).max(8.0);
+        if count < 1.0 {
+            layer.alpha *= count;
+        }
+        let copies = (count.round() as usize).clamp(1, MAX_PARTICLE_INSTANCES);
+        if copies == 1 {
+            continue;
+        }
+        notes.push(format!(
+            "particle object {} drawn as {} instances (count {:.2}, size {:.2})",
+            layer.object_id, copies, count, size
+        ));
+        let base = layer.clone();
+        let pass = passes[i].clone();
+        for k in (1..copies).rev() {
+            let mut copy = base.clone();
+            copy.center_x = (base.center_x
+                + (instance_jitter(base.object_id, k, 0) - 0.5) * spread_w)
+                .clamp(0.0, scene_w);
+            copy.center_y = (base.center_y
+                + (instance_jitter(base.object_id, k, 1) - 0.5) * spread_h)
+                .clamp(0.0, scene_h);
+            layers.insert(i + 1, copy);
+            passes.insert(i + 1, pass.clone());
+        }
+    }
+    notes
+}
+
 pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
     let mut ready = 0usize;
     let mut experimental = 0usize;
@@ -259,15 +684,45 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
     let mut passes = Vec::<NativePassSupport>::new();
     let mut draw_layers = Vec::<NativeDrawLayer>::new();
 
-    for node in &graph.effect_nodes {
-        for pass in &node.passes {
+    // Blur effects keep their region-blur approximation.
+    let chains = graph
+        .render_target_chains()
+        .into_iter()
+        .filter(|c| !is_blur_effect(&graph.effect_nodes[c.steps[0].node].effect_file))
+        .collect::<Vec<_>>();
+    let chained_nodes = chains
+        .iter()
+        .flat_map(|c| c.steps.iter().map(|s| s.node))
+        .collect::<BTreeSet<_>>();
+
+    for (node_pos, node) in graph.effect_nodes.iter().enumerate() {
+        let blur_node = node.effect_index.is_some() && is_blur_effect(&node.effect_file);
+        let chained = chained_nodes.contains(&node_pos);
+        for (pass_pos, pass) in node.passes.iter().enumerate() {
             let shader = if pass.shader.trim().is_empty() {
                 node.pass_shader.clone()
             } else {
                 pass.shader.clone()
             };
             let family = shader_family(&shader);
-            let (base_tier, base_reason) = classify_family(&shader);
+            let (base_tier, base_reason) = if blur_node && pass_pos == 0 {
+                (
+                    NativeSupportTier::ExperimentalReady,
+                    "blur effect approximated as a region-limited gaussian blur".to_string(),
+                )
+            } else if blur_node {
+                (
+                    NativeSupportTier::Experimental,
+                    "blur pass folded into the first pass of the effect".to_string(),
+                )
+            } else if chained {
+                (
+                    NativeSupportTier::Experimental,
+                    "render-target pass chained into the object layer".to_string(),
+                )
+            } else {
+                classify_family(&shader)
+            };
             let primary_texture = first_texture(pass);
             let (tier, reason) = with_texture_gate(
                 base_tier,
@@ -275,6 +730,8 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
                 primary_texture.as_ref(),
                 node.object_visible,
             );
+            let blur = matches!(tier, NativeSupportTier::ExperimentalReady)
+                .then(|| blur_sigma(&pass.effective_uniforms));
 
             match tier {
                 NativeSupportTier::Ready => {
@@ -282,7 +739,7 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
                     ready_layers += 1;
                     ready_families.insert(family.clone());
                 }
-                NativeSupportTier::Experimental => {
+                NativeSupportTier::ExperimentalReady | NativeSupportTier::Experimental => {
                     experimental += 1;
                     experimental_families.insert(family.clone());
                 }
@@ -324,6 +781,7 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
                 shader,
                 shader_family: family,
                 primary_texture,
+                texture_repeat: pass.texture_wrap.as_deref() == Some("repeat"),
                 blend_mode: pass
                     .blending
                     .clone()
@@ -346,9 +804,61 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
                 shader_defines: pass.shader_defines.clone(),
                 uniforms: pass.effective_uniforms.clone(),
                 tier,
+                blur_sigma: blur,
+                clock_hand: graph
+                    .clock_hands
+                    .iter()
+                    .find(|c| c.object_index == node.object_index)
+                    .map(|c| c.hand),
+                script_animations: graph
+                    .script_animations
+                    .iter()
+                    .filter(|a| a.object_index == node.object_index)
+                    .map(|a| a.animation)
+                    .collect(),
+                merged_static: false,
+                chain: Vec::new(),
+            });
+        }
+    }
+
+    let mut notes = Vec::<String>::new();
+    for chain in &chains {
+        let Some(layer) = draw_layers.iter_mut().find(|l| {
+            l.object_index == chain.object_index && matches!(l.tier, NativeSupportTier::Ready)
+        }) else {
+            notes.push(format!(
+                "render-target chain of object {} effect {} has no drawable layer",
+                chain.object_index, chain.effect_index
+            ));
+            continue;
+        };
+        for step in &chain.steps {
+            let node = &graph.effect_nodes[step.node];
+            let pass = &node.passes[step.pass];
+            let shader = if pass.shader.trim().is_empty() {
+                node.pass_shader.clone()
+            } else {
+                pass.shader.clone()
+            };
+            let blur_sigma = shader
+                .to_ascii_lowercase()
+                .contains("blur")
+                .then(|| blur_sigma(&pass.effective_uniforms));
+            layer.chain.push(ChainedPass {
+                shader,
+                target: step.output.clone(),
+                blur_sigma,
             });
         }
     }
+    let total_pass_nodes = passes.len();
+    notes.extend(expand_particle_instances(
+        &mut passes,
+        &mut draw_layers,
+        graph.scene_width.max(1) as f32,
+        graph.scene_height.max(1) as f32,
+    ));
     draw_layers.sort_by(|a, b| {
         a.object_index
             .cmp(&b.object_index)
@@ -361,7 +871,6 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
             .then_with(|| a.object_id.cmp(&b.object_id))
     });
 
-    let mut notes = Vec::<String>::new();
     notes.push(format!(
         "native support summary: ready={}, experimental={}, unsupported={}",
         ready, experimental, unsupported
@@ -372,12 +881,12 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
             ready_layers
         ));
     }
-    if ready == 0 && !passes.is_empty() {
+    if ready == 0 && total_pass_nodes > 0 {
         notes.push("no ready shader families detected; fallback transport recommended".to_string());
     }
 
     NativeRuntimePlan {
-        total_pass_nodes: passes.len(),
+        total_pass_nodes,
         ready_nodes: ready,
         experimental_nodes: experimental,
         unsupported_nodes: unsupported,
@@ -387,6 +896,218 @@ pub fn build_native_runtime_plan(graph: &SceneGpuGraph) -> NativeRuntimePlan {
         ready_draw_layers: ready_layers,
         passes,
         draw_layers,
+        viewports: graph.viewports.clone(),
+        pruned: graph.pruned.clone(),
         notes,
+        budget: None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::scene_gpu_graph::write_scene_files;
+
+    /// Ready fullscreen-centered quad for compositor tests.
+    pub(crate) fn layer(
+        object_id: u64,
+        width: f32,
+        height: f32,
+        alpha: f32,
+    ) -> (NativePassSupport, NativeDrawLayer) {
+        let pass = NativePassSupport {
+            object_index: object_id as usize,
+            object_id,
+            object_name: format!("layer{}", object_id),
+            pass_index: 0,
+            pass_shader: "genericimage2".to_string(),
+            shader_family: "genericimage".to_string(),
+            primary_texture: Some("tex".to_string()),
+            tier: NativeSupportTier::Ready,
+            reason: String::new(),
+        };
+        let layer = NativeDrawLayer {
+            object_index: pass.object_index,
+            object_id,
+            object_name: pass.object_name.clone(),
+            pass_index: 0,
+            shader: pass.pass_shader.clone(),
+            shader_family: pass.shader_family.clone(),
+            primary_texture: pass.primary_texture.clone(),
+            texture_repeat: false,
+            blend_mode: "normal".to_string(),
+            depth_test: "disabled".to_string(),
+            depth_write: "disabled".to_string(),
+            cull_mode: "nocull".to_string(),
+            alpha,
+            brightness: 1.0,
+            tint: [1.0; 3],
+            center_x: 960.0,
+            center_y: 540.0,
+            width,
+            height,
+            angle_rad: 0.0,
+            parallax_depth: 1.0,
+            visible: true,
+            shader_defines: Vec::new(),
+            uniforms: BTreeMap::new(),
+            tier: NativeSupportTier::Ready,
+            blur_sigma: None,
+            clock_hand: None,
+            script_animations: Vec::new(),
+            merged_static: false,
+            chain: Vec::new(),
+        };
+        (pass, layer)
+    }
+
+    #[test]
+    fn particle_instance_overrides_duplicate_and_scale_sprites() {
+        let particle = |id, count: f64| {
+            let (mut pass, mut layer) = layer(id, 400.0, 200.0, 1.0);
+            pass.shader_family = "particle".to_string();
+            layer.shader_family = "particle".to_string();
+            layer.uniforms.insert("instance_count".into(), count.into());
+            layer.uniforms.insert("instance_size".into(), "0.5".into());
+            (pass, layer)
+        };
+        let (mut passes, mut layers): (Vec<_>, Vec<_>) = [
+            particle(1, 3.0),
+            layer(2, 100.0, 100.0, 1.0),
+            particle(3, 0.5),
+        ]
+        .into_iter()
+        .unzip();
+
+        let notes = expand_particle_instances(&mut passes, &mut layers, 1920.0, 1080.0);
+        assert_eq!(notes.len(), 1);
+        let ids = layers.iter().map(|l| l.object_id).collect::<Vec<_>>();
+        assert_eq!(ids, [1, 1, 1, 2, 3]);
+        assert!(
+            passes
+                .iter()
+                .zip(&layers)
+                .all(|(p, l)| p.object_id == l.object_id)
+        );
+        assert!(
+            layers[..3]
+                .iter()
+                .all(|l| l.width == 200.0 && l.height == 100.0)
+        );
+        assert_ne!(
+            (layers[1].center_x, layers[1].center_y),
+            (layers[2].center_x, layers[2].center_y)
+        );
+        assert!(
+            layers[1..3]
+                .iter()
+                .all(|l| (l.center_x - 960.0).abs() <= 200.0)
+        );
+        assert_eq!(layers[3].width, 100.0);
+        assert_eq!(layers[4].alpha, 0.5);
+    }
+
+    #[test]
+    fn cost_budget_merges_bottom_layers_then_drops_low_impact() {
+        let mut plan = NativeRuntimePlan::default();
+        let mut rotated = layer(4, 1920.0, 1080.0, 1.0);
+        rotated.1.angle_rad = 0.5;
+        let layers = [
+            layer(1, 1920.0, 1080.0, 1.0),
+            layer(2, 1920.0, 1080.0, 1.0),
+            layer(3, 1920.0, 1080.0, 1.0),
+            rotated,
+            layer(5, 100.0, 100.0, 0.2),
+            layer(6, 1920.0, 540.0, 1.0),
+        ];
+        for (pass, layer) in layers {
+            plan.passes.push(pass);
+            plan.draw_layers.push(layer);
+        }
+        plan.ready_draw_layers = plan.draw_layers.len();
+
+        let mut roomy = plan.clone();
+        apply_cost_budget(&mut roomy, 1920, 1080, 100.0);
+        assert!(roomy.budget.unwrap().trimmed.is_empty());
+
+        apply_cost_budget(&mut plan, 1920, 1080, 3.0);
+        let report = plan.budget.as_ref().unwrap();
+        assert!(report.estimated_cost > 6.0);
+        assert!(report.final_cost <= 3.0);
+        // Layers 1-3 sit below the first rotated quad and fold into one input.
+        let merged: Vec<u64> = report
+            .trimmed
+            .iter()
+            .filter(|t| t.action == NativeBudgetAction::Merged)
+            .map(|t| t.pass.object_id)
+            .collect();
+        assert_eq!(merged, vec![1, 2, 3]);
+        // The tiny translucent layer goes first.
+        let dropped: Vec<u64> = report
+            .trimmed
+            .iter()
+            .filter(|t| t.action == NativeBudgetAction::Dropped)
+            .map(|t| t.pass.object_id)
+            .collect();
+        assert_eq!(dropped[0], 5);
+        assert_eq!(plan.passes.len(), plan.draw_layers.len());
+        assert!(plan.draw_layers.iter().all(|l| l.object_id != 5));
+        assert_eq!(plan.ready_draw_layers, plan.draw_layers.len());
+    }
+
+    #[test]
+    fn render_target_passes_chain_onto_object_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "scene.json",
+                r#"{"objects": [{"id": 7, "name": "bg", "image": "models/bg.json",
+                    "effects": [{"file": "effects/glow/effect.json"}]}]}"#,
+            ),
+            ("models/bg.json", r#"{"material": "materials/bg.json"}"#),
+            (
+                "materials/bg.json",
+                r#"{"passes": [{"shader": "genericimage2", "textures": ["bg"]}]}"#,
+            ),
+            (
+                "effects/glow/effect.json",
+                r#"{"fbos": [{"name": "_rt_Half", "scale": 2}],
+                    "passes": [
+                      {"material": "materials/glow_blur.json", "target": "_rt_Half"},
+                      {"material": "materials/glow_combine.json",
+                       "bind": [{"name": "_rt_Half", "index": 1}, {"name": "previous", "index": 0}]}
+                    ]}"#,
+            ),
+            (
+                "materials/glow_blur.json",
+                r#"{"passes": [{"shader": "effects/blur_k3", "textures": ["previous"]}]}"#,
+            ),
+            (
+                "materials/glow_combine.json",
+                r#"{"passes": [{"shader": "effects/glow_combine", "textures": ["previous", "_rt_Half"]}]}"#,
+            ),
+        ];
+        write_scene_files(dir.path(), &files);
+        let graph = crate::scene_gpu_graph::build_scene_gpu_graph(dir.path()).unwrap();
+        let chains = graph.render_target_chains();
+        assert_eq!(chains.len(), 1);
+        let steps = &chains[0].steps;
+        assert_eq!(steps[0].output, "_rt_Half");
+        assert_eq!(steps[1].inputs, ["previous", "_rt_Half"]);
+        assert_eq!(steps[1].output, "layer");
+        assert_eq!(graph.effect_nodes[1].render_targets[0].scale, 2);
+
+        let plan = build_native_runtime_plan(&graph);
+        let drawn = plan
+            .draw_layers
+            .iter()
+            .filter(|l| matches!(l.tier, NativeSupportTier::Ready))
+            .collect::<Vec<_>>();
+        assert_eq!(drawn.len(), 1);
+        let chain = &drawn[0].chain;
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].blur_sigma, Some(DEFAULT_BLUR_SIGMA));
+        assert_eq!(chain[1].blur_sigma, None);
+        assert!(plan.passes[2].reason.contains("chained into"));
     }
 }
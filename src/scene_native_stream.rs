@@ -0,0 +1,234 @@
+use crate::scene_gpu_backend::budgeted_native_plan;
+use crate::scene_native_renderer::NativeFrameStream;
+use anyhow::{Context, Result, bail};
+use image::RgbaImage;
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// yuv4mpeg stream header; mpv/lavf probe it from the `YUV4MPEG2` magic, so
+/// the pipe needs no demuxer options. Dimensions must be even for 4:2:0.
+/// Frames are full range, so the header says so; untagged y4m is read as
+/// limited range, which crushes blacks and clips whites.
+pub fn y4m_header(width: u32, height: u32, fps: u32) -> String {
+    format!(
+        "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg XYSCSS=420JPEG XCOLORRANGE=FULL\n",
+        width, height, fps
+    )
+}
+
+/// Largest even size that fits `(width, height)`.
+pub fn y4m_dimensions((width, height): (u32, u32)) -> (u32, u32) {
+    ((width & !1).max(2), (height & !1).max(2))
+}
+
+/// Luma weights (red, blue) mpv assumes for an untagged stream of this size:
+/// y4m has no matrix field, so HD frames are read as BT.709 and SD as BT.601.
+fn y4m_matrix(width: u32, height: u32) -> (f32, f32) {
+    if width >= 1280 || height > 576 {
+        (0.2126, 0.0722)
+    } else {
+        (0.299, 0.114)
+    }
+}
+
+/// Full-range (JPEG) luma and chroma of an RGB pixel with the `(kr, kb)` matrix.
+fn rgb_to_yuv((kr, kb): (f32, f32), r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let y = kr * r + (1.0 - kr - kb) * g + kb * b;
+    let u = 128.0 + (b - y) / (2.0 * (1.0 - kb));
+    let v = 128.0 + (r - y) / (2.0 * (1.0 - kr));
+    (y, u, v)
+}
+
+/// Encodes one frame (`FRAME` marker plus Y, U and V planes) into `buf`,
+/// cropping odd edges. Alpha is ignored: the canvas is opaque.
+pub fn encode_y4m_frame(frame: &RgbaImage, buf: &mut Vec<u8>) {
+    let (width, height) = y4m_dimensions(frame.dimensions());
+    let (cw, ch) = (width / 2, height / 2);
+    let matrix = y4m_matrix(width, height);
+    buf.clear();
+    buf.extend_from_slice(b"FRAME\n");
+    let luma_start = buf.len();
+    buf.resize(luma_start + (width * height + 2 * cw * ch) as usize, 0);
+    let (luma, chroma) = buf[luma_start..].split_at_mut((width * height) as usize);
+    let (u_plane, v_plane) = chroma.split_at_mut((cw * ch) as usize);
+    for cy in 0..ch {
+        for cx in 0..cw {
+            let (mut u_sum, mut v_sum) = (0.0, 0.0);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let (x, y) = (cx * 2 + dx, cy * 2 + dy);
+                let [r, g, b, _] = frame.get_pixel(x, y).0;
+                let (luma_value, u, v) = rgb_to_yuv(matrix, r as f32, g as f32, b as f32);
+                luma[(y * width + x) as usize] = luma_value.round().clamp(0.0, 255.0) as u8;
+                u_sum += u;
+                v_sum += v;
+            }
+            let idx = (cy * cw + cx) as usize;
+            u_plane[idx] = (u_sum / 4.0).round().clamp(0.0, 255.0) as u8;
+            v_plane[idx] = (v_sum / 4.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// Writes frames of `stream` to `out` in real time at `fps` until the reader
+/// goes away (or `max_frames` are written) and returns how many were sent.
+/// Frames that cannot be rendered in time are skipped so the drift follows
+/// the wall clock instead of slowing down.
+pub fn stream_native_frames<W: Write>(
+    stream: &NativeFrameStream,
+    fps: u32,
+    out: &mut W,
+    max_frames: Option<u64>,
+) -> Result<u64> {
+    let fps = fps.max(1);
+    let frame_time = Duration::from_secs_f64(1.0 / fps as f64);
+    let (width, height) = y4m_dimensions(stream.dimensions());
+    let mut buf = Vec::new();
+    let mut sent = 0u64;
+    let mut index = 0u64;
+    let started = Instant::now();
+
+    let result = (|| -> io::Result<()> {
+        out.write_all(y4m_header(width, height, fps).as_bytes())?;
+        while max_frames.is_none_or(|max| sent < max) {
+            encode_y4m_frame(&stream.render(index as f32 / fps as f32), &mut buf);
+            out.write_all(&buf)?;
+            out.flush()?;
+            sent += 1;
+            index += 1;
+            let due = frame_time * index as u32;
+            let elapsed = started.elapsed();
+            if let Some(wait) = due.checked_sub(elapsed) {
+                thread::sleep(wait);
+            } else {
+                // Behind schedule: jump to the frame the clock is at.
+                index = (elapsed.as_secs_f64() * fps as f64) as u64;
+            }
+        }
+        Ok(())
+    })();
+    match result {
+        Ok(()) => Ok(sent),
+        // mpv closed the pipe (wallpaper stopped or replaced).
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(sent),
+        Err(err) => Err(err).context("Failed writing native stream frame"),
+    }
+}
+
+/// Body of the internal `native-stream` command: rebuilds the native plan
+/// of `root` and feeds it to the mpv reading `fifo` until mpv stops.
+pub fn run_native_stream(
+    root: &Path,
+    monitor: &str,
+    session_dir: &Path,
+    fifo: &Path,
+    fps: u32,
+) -> Result<()> {
+    let (graph, plan) = budgeted_native_plan(root, monitor)?;
+    let Some(stream) = NativeFrameStream::build(
+        root,
+        session_dir,
+        graph.scene_width,
+        graph.scene_height,
+        &plan,
+    )?
+    else {
+        bail!("native stream has no drawable layer for {}", root.display());
+    };
+    // Blocks until mpv opens the other end.
+    let pipe = OpenOptions::new()
+        .write(true)
+        .open(fifo)
+        .with_context(|| format!("Failed opening {}", fifo.display()))?;
+    let sent = stream_native_frames(&stream, fps, &mut BufWriter::new(pipe), None)?;
+    eprintln!("[ok] native stream closed after {} frames", sent);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_gpu_graph::{build_scene_gpu_graph, write_scene_files};
+    use crate::scene_native_runtime::build_native_runtime_plan;
+
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn frames_stream_as_paced_y4m_until_the_reader_leaves() {
+        let frame = RgbaImage::from_fn(5, 3, |x, _| {
+            if x < 2 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([255, 255, 255, 255])
+            }
+        });
+        let mut buf = Vec::new();
+        encode_y4m_frame(&frame, &mut buf);
+        // Cropped to 4x2: 8 luma samples, then 2 U and 2 V.
+        assert_eq!(&buf[..6], b"FRAME\n");
+        let planes = &buf[6..];
+        assert_eq!(planes.len(), 12);
+        assert_eq!((planes[0], planes[2]), (76, 255));
+        assert_eq!(&planes[8..], &[85, 128, 255, 128]);
+        let hd_red = rgb_to_yuv(y4m_matrix(1920, 1080), 255.0, 0.0, 0.0);
+        assert_eq!(hd_red.0.round(), 54.0);
+        assert_eq!(hd_red.1.round(), 99.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "scene.json",
+                r#"{"general": {"orthogonalprojection": {"width": 64, "height": 32}},
+                    "objects": [{"id": 1, "name": "sky", "image": "models/sky.json", "size": "64 32"}]}"#,
+            ),
+            ("models/sky.json", r#"{"material": "materials/sky.json"}"#),
+            (
+                "materials/sky.json",
+                r#"{"passes": [{"shader": "genericimage2", "textures": ["a.png"]}]}"#,
+            ),
+        ];
+        write_scene_files(dir.path(), &files);
+        RgbaImage::from_pixel(8, 8, image::Rgba([200, 10, 10, 255]))
+            .save(dir.path().join("a.png"))
+            .unwrap();
+        let graph = build_scene_gpu_graph(dir.path()).unwrap();
+        let plan = build_native_runtime_plan(&graph);
+        let stream = NativeFrameStream::build(dir.path(), dir.path(), 64, 32, &plan)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stream.rendered_layers, 1);
+
+        let mut out = Vec::new();
+        let started = Instant::now();
+        assert_eq!(
+            stream_native_frames(&stream, 50, &mut out, Some(3)).unwrap(),
+            3
+        );
+        // Frames 2 and 3 wait for their 20 ms slots.
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        let header = y4m_header(64, 32, 50);
+        assert_eq!(
+            header,
+            "YUV4MPEG2 W64 H32 F50:1 Ip A1:1 C420jpeg XYSCSS=420JPEG XCOLORRANGE=FULL\n"
+        );
+        assert!(out.starts_with(header.as_bytes()));
+        assert_eq!(out.len(), header.len() + 3 * (6 + 64 * 32 * 3 / 2));
+
+        assert_eq!(
+            stream_native_frames(&stream, 50, &mut ClosedPipe, None).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use crate::asset_resolver::AssetResolver;
+use crate::scene_gpu_graph::{parse_json_asset, parse_vec3, texture_candidates};
+use anyhow::{Result, bail};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Operators the planned particle simulation models; anything else is
+/// reported in `unsupported_operators`.
+const SUPPORTED_OPERATORS: &[&str] = &[
+    "movement",
+    "angularmovement",
+    "alphafade",
+    "alphachange",
+    "sizechange",
+    "colorchange",
+    "oscillatealpha",
+    "oscillatesize",
+    "oscillateposition",
+    "turbulence",
+    "vortex",
+    "controlpointattract",
+];
+
+/// Operators that push particles around (as opposed to fading/resizing).
+const FORCE_OPERATORS: &[&str] = &[
+    "movement",
+    "angularmovement",
+    "oscillateposition",
+    "turbulence",
+    "vortex",
+    "controlpointattract",
+];
+
+/// Child systems nest rarely more than two levels; the bound only guards
+/// against self-referencing assets.
+const MAX_CHILD_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticleEmitterSummary {
+    pub name: String,
+    /// Particles per second.
+    pub rate: Option<f64>,
+    pub distance_min: Option<f64>,
+    pub distance_max: Option<f64>,
+    pub directions: Option<[f32; 3]>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticleForceSummary {
+    pub operator: String,
+    pub gravity: Option<[f32; 3]>,
+    pub drag: Option<f64>,
+    pub strength: Option<f64>,
+    pub speed: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticleSystemSummary {
+    pub asset: String,
+    pub material: Option<String>,
+    pub textures: Vec<String>,
+    pub max_count: Option<u64>,
+    pub emitters: Vec<ParticleEmitterSummary>,
+    /// `[min, max]` seconds from the `lifetimerandom` initializer.
+    pub lifetime: Option<[f64; 2]>,
+    pub initializers: Vec<String>,
+    pub operators: Vec<String>,
+    pub renderers: Vec<String>,
+    pub forces: Vec<ParticleForceSummary>,
+    pub unsupported_operators: Vec<String>,
+    pub children: Vec<ParticleSystemSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneParticleObject {
+    pub object_id: u64,
+    pub object_name: String,
+    pub instance_override: Value,
+    pub system: ParticleSystemSummary,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneParticleReport {
+    pub objects: Vec<SceneParticleObject>,
+    /// Operator name -> number of systems using it, for every operator the
+    /// simulation does not cover yet.
+    pub unsupported_operators: BTreeMap<String, usize>,
+    pub missing_assets: Vec<String>,
+}
+
+fn component_names(data: &Value, key: &str) -> Vec<String> {
+    data.get(key)
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|c| c.get("name").and_then(|v| v.as_str()))
+        .map(str::to_string)
+        .collect()
+}
+
+fn number(value: &Value, key: &str) -> Option<f64> {
+    let v = value.get(key)?;
+    v.as_f64()
+        .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn parse_emitter(emitter: &Value) -> ParticleEmitterSummary {
+    ParticleEmitterSummary {
+        name: emitter
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        rate: number(emitter, "rate"),
+        distance_min: number(emitter, "distancemin"),
+        distance_max: number(emitter, "distancemax"),
+        directions: emitter.get("directions").and_then(parse_vec3),
+    }
+}
+
+fn parse_force(operator: &Value) -> Option<ParticleForceSummary> {
+    let name = operator.get("name").and_then(|v| v.as_str())?;
+    FORCE_OPERATORS
+        .contains(&name)
+        .then(|| ParticleForceSummary {
+            operator: name.to_string(),
+            gravity: operator.get("gravity").and_then(parse_vec3),
+            drag: number(operator, "drag"),
+            strength: number(operator, "strength").or_else(|| number(operator, "scale")),
+            speed: number(operator, "speed").or_else(|| number(operator, "speedmax")),
+        })
+}
+
+fn parse_lifetime(data: &Value) -> Option<[f64; 2]> {
+    let init = data
+        .get("initializer")
+        .and_then(|v| v.as_array())?
+        .iter()
+        .find(|c| c.get("name").and_then(|v| v.as_str()) == Some("lifetimerandom"))?;
+    let min = number(init, "min").unwrap_or(1.0);
+    let max = number(init, "max").unwrap_or(min);
+    Some([min, max])
+}
+
+fn material_textures(resolver: &AssetResolver, material: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    let passes = material.get("passes").and_then(|v| v.as_array());
+    for pass in passes.into_iter().flatten() {
+        let textures = pass.get("textures").and_then(|v| v.as_array());
+        for tex in textures.into_iter().flatten().filter_map(|t| t.as_str()) {
+            let resolved = resolver
+                .resolve_first(&texture_candidates(tex))
+                .map(|a| a.resolved_path)
+                .unwrap_or_else(|| tex.to_string());
+            if !out.contains(&resolved) {
+                out.push(resolved);
+            }
+        }
+    }
+    out
+}
+
+fn summarize_particle_system(
+    resolver: &AssetResolver,
+    asset_ref: &str,
+    depth: usize,
+    missing: &mut Vec<String>,
+) -> Option<ParticleSystemSummary> {
+    let Some((data, asset)) = parse_json_asset(resolver, asset_ref) else {
+        missing.push(asset_ref.to_string());
+        return None;
+    };
+    let material_ref = data.get("material").and_then(|v| v.as_str());
+    let (material, textures) = match material_ref.map(|m| (m, parse_json_asset(resolver, m))) {
+        Some((_, Some((material_data, resolved)))) => {
+            (Some(resolved), material_textures(resolver, &material_data))
+        }
+        Some((m, None)) => {
+            missing.push(m.to_string());
+            (Some(m.to_string()), Vec::new())
+        }
+        None => (None, Vec::new()),
+    };
+
+    let operators = component_names(&data, "operator");
+    let mut unsupported_operators: Vec<String> = operators
+        .iter()
+        .filter(|op| !SUPPORTED_OPERATORS.contains(&op.as_str()))
+        .cloned()
+        .collect();
+    unsupported_operators.sort();
+    unsupported_operators.dedup();
+
+    let mut children = Vec::new();
+    if depth < MAX_CHILD_DEPTH {
+        let child_refs = data.get("children").and_then(|v| v.as_array());
+        for child in child_refs.into_iter().flatten() {
+            if let Some(name) = child.get("name").and_then(|v| v.as_str())
+                && let Some(summary) = summarize_particle_system(resolver, name, depth + 1, missing)
+            {
+                children.push(summary);
+            }
+        }
+    }
+
+    Some(ParticleSystemSummary {
+        asset,
+        material,
+        textures,
+        max_count: data.get("maxcount").and_then(|v| v.as_u64()),
+        emitters: data
+            .get("emitter")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(parse_emitter)
+            .collect(),
+        lifetime: parse_lifetime(&data),
+        initializers: component_names(&data, "initializer"),
+        forces: data
+            .get("operator")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(parse_force)
+            .collect(),
+        operators,
+        renderers: component_names(&data, "renderer"),
+        unsupported_operators,
+        children,
+    })
+}
+
+fn count_unsupported(system: &ParticleSystemSummary, counts: &mut BTreeMap<String, usize>) {
+    for op in &system.unsupported_operators {
+        *counts.entry(op.clone()).or_insert(0) += 1;
+    }
+    for child in &system.children {
+        count_unsupported(child, counts);
+    }
+}
+
+pub fn build_scene_particle_report(root: &Path) -> Result<SceneParticleReport> {
+    let resolver = AssetResolver::new(root)?;
+    let Some(scene_asset) = resolver
+        .resolve("scene.json")
+        .or_else(|| resolver.resolve("gifscene.json"))
+    else {
+        bail!("No scene.json/gifscene.json found in {}", root.display());
+    };
+    let scene_json: Value = serde_json::from_slice(&scene_asset.bytes)?;
+
+    let mut objects = Vec::new();
+    let mut missing_assets = Vec::new();
+    let scene_objects = scene_json.get("objects").and_then(|v| v.as_array());
+    for object in scene_objects.into_iter().flatten() {
+        let Some(particle) = object.get("particle").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let Some(system) = summarize_particle_system(&resolver, particle, 0, &mut missing_assets)
+        else {
+            continue;
+        };
+        objects.push(SceneParticleObject {
+            object_id: object.get("id").and_then(|v| v.as_u64()).unwrap_or(0),
+            object_name: object
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            instance_override: object
+                .get("instanceoverride")
+                .cloned()
+                .unwrap_or(Value::Null),
+            system,
+        });
+    }
+
+    let mut unsupported_operators = BTreeMap::new();
+    for object in &objects {
+        count_unsupported(&object.system, &mut unsupported_operators);
+    }
+    missing_assets.sort();
+    missing_assets.dedup();
+    Ok(SceneParticleReport {
+        objects,
+        unsupported_operators,
+        missing_assets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn summarizes_particles_and_flags_unsupported_operators() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("particles")).unwrap();
+        fs::create_dir_all(root.join("materials/particle")).unwrap();
+        fs::write(
+            root.join("scene.json"),
+            json!({"objects": [
+                {"id": 7, "name": "Snow", "particle": "particles/snow.json",
+                 "instanceoverride": {"rate": 2.0}},
+                {"id": 8, "name": "Gone", "particle": "particles/missing.json"}
+            ]})
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            root.join("particles/snow.json"),
+            json!({
+                "material": "materials/particle/snow.json",
+                "maxcount": 500,
+                "emitter": [{"name": "boxrandom", "rate": 40, "directions": "1 1 0"}],
+                "initializer": [{"name": "lifetimerandom", "min": 3, "max": 6}],
+                "operator": [
+                    {"name": "movement", "gravity": "0 -30 0", "drag": 0.1},
+                    {"name": "alphafade"},
+                    {"name": "remapinitialvalue"}
+                ],
+                "renderer": [{"name": "sprite"}],
+                "children": [{"name": "particles/spark.json"}]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            root.join("particles/spark.json"),
+            json!({"operator": [{"name": "capvelocity"}]}).to_string(),
+        )
+        .unwrap();
+        fs::write(
+            root.join("materials/particle/snow.json"),
+            json!({"passes": [{"textures": ["particle/snowflake"]}]}).to_string(),
+        )
+        .unwrap();
+
+        let report = build_scene_particle_report(root).unwrap();
+        assert_eq!(report.objects.len(), 1);
+        let snow = &report.objects[0].system;
+        assert_eq!(snow.max_count, Some(500));
+        assert_eq!(snow.emitters[0].rate, Some(40.0));
+        assert_eq!(snow.lifetime, Some([3.0, 6.0]));
+        assert_eq!(snow.forces[0].gravity, Some([0.0, -30.0, 0.0]));
+        assert_eq!(snow.textures, vec!["particle/snowflake".to_string()]);
+        assert_eq!(snow.unsupported_operators, vec!["remapinitialvalue"]);
+        assert_eq!(snow.children.len(), 1);
+        assert_eq!(report.unsupported_operators.len(), 2);
+        assert_eq!(report.unsupported_operators["capvelocity"], 1);
+        assert_eq!(report.missing_assets, vec!["particles/missing.json"]);
+    }
+}
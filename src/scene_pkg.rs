@@ -1,13 +1,45 @@
+use crate::i18n::{Msg, bail_msg};
+use crate::library_guard::ensure_outside_library;
+use crate::pkg_obfuscation::{
+    HeaderCheck, OBFUSCATION_PROBE_BYTES, ObfuscationVariant, XorReader, allow_obfuscated,
+    check_pkg_header,
+};
 use anyhow::{Context, Result, bail};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 const VIDEO_EXTS: [&str; 6] = ["mp4", "webm", "gif", "mkv", "avi", "mov"];
+const DIGEST_INDEX_FILE: &str = "pkg-index.json";
+const DIGEST_INDEX_VERSION: u32 = 1;
+const PKG_TABLE_VERSION: u32 = 1;
+/// Longer header or file names mean a damaged or still obfuscated table.
+const MAX_PKG_STRING_BYTES: usize = 4096;
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// FNV-1a is used instead of DefaultHasher so fingerprints stay comparable
+// across toolchain upgrades.
+fn fnv1a_feed(hash: &mut u64, bytes: &[u8]) {
+    for b in bytes {
+        *hash ^= *b as u64;
+        *hash = hash.wrapping_mul(FNV_PRIME);
+    }
+}
 
-#[derive(Debug, Clone, Serialize)]
+pub(crate) fn fnv1a_update(hash: &mut u64, bytes: &[u8]) {
+    fnv1a_feed(hash, bytes);
+    // Field separator so ("ab","c") and ("a","bc") differ.
+    *hash ^= 0xff;
+    *hash = hash.wrapping_mul(FNV_PRIME);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScenePkgEntry {
     pub filename: String,
     pub offset: u32,
@@ -21,14 +53,17 @@ pub struct ScenePkg {
     pub entries: Vec<ScenePkgEntry>,
 }
 
-fn read_u32_le(file: &mut File) -> Result<u32> {
+fn read_u32_le(file: &mut impl Read) -> Result<u32> {
     let mut b = [0u8; 4];
     file.read_exact(&mut b).context("Failed to read u32")?;
     Ok(u32::from_le_bytes(b))
 }
 
-fn read_sized_string(file: &mut File) -> Result<String> {
+fn read_sized_string(file: &mut impl Read) -> Result<String> {
     let len = read_u32_le(file)? as usize;
+    if len > MAX_PKG_STRING_BYTES {
+        bail!("Package string of {} bytes is not plausible", len);
+    }
     let mut buf = vec![0u8; len];
     file.read_exact(&mut buf)
         .context("Failed to read sized string")?;
@@ -39,18 +74,57 @@ pub fn parse_scene_pkg(path: &Path) -> Result<ScenePkg> {
     let mut file =
         File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
 
-    let header = read_sized_string(&mut file)?;
+    let mut prefix = Vec::new();
+    (&mut file)
+        .take(OBFUSCATION_PROBE_BYTES)
+        .read_to_end(&mut prefix)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let variant = match check_pkg_header(&prefix) {
+        HeaderCheck::Plain => None,
+        HeaderCheck::Obfuscated(variant) if allow_obfuscated() => {
+            eprintln!(
+                "[ok] obfuscated pkg {} ({}): reading best-effort",
+                path.display(),
+                variant
+            );
+            Some(variant)
+        }
+        HeaderCheck::Obfuscated(variant) => bail!(
+            "{} is obfuscated ({}); pass --allow-obfuscated to read it best-effort",
+            path.display(),
+            variant
+        ),
+        HeaderCheck::Unknown => bail!(
+            "Invalid package header in {}, expected PKGV* and no known obfuscation variant matches",
+            path.display()
+        ),
+    };
+    let (start, key) = match variant {
+        Some(ObfuscationVariant::OffsetHeader { offset }) => (offset, 0),
+        Some(ObfuscationVariant::XorTable { key } | ObfuscationVariant::XorFile { key }) => {
+            (0, key)
+        }
+        None => (0, 0),
+    };
+    file.seek(SeekFrom::Start(start))
+        .context("Failed to seek to the package header")?;
+    let mut table = XorReader {
+        inner: &mut file,
+        key,
+    };
+
+    let header = read_sized_string(&mut table)?;
     if !header.starts_with("PKGV") {
         bail!("Invalid package header '{}', expected PKGV*", header);
     }
 
-    let files_count = read_u32_le(&mut file)? as usize;
-    let mut entries = Vec::with_capacity(files_count);
+    let files_count = read_u32_le(&mut table)? as usize;
+    let mut entries = Vec::with_capacity(files_count.min(OBFUSCATION_PROBE_BYTES as usize));
 
     for _ in 0..files_count {
-        let filename = read_sized_string(&mut file)?;
-        let offset = read_u32_le(&mut file)?;
-        let length = read_u32_le(&mut file)?;
+        let filename = read_sized_string(&mut table)?;
+        let offset = read_u32_le(&mut table)?;
+        let length = read_u32_le(&mut table)?;
         entries.push(ScenePkgEntry {
             filename,
             offset,
@@ -61,6 +135,21 @@ pub fn parse_scene_pkg(path: &Path) -> Result<ScenePkg> {
     let base_offset = file
         .stream_position()
         .context("Failed to read package base offset")?;
+    if let Some(variant) = variant {
+        let data_bytes = file
+            .metadata()
+            .map(|m| m.len().saturating_sub(base_offset))
+            .unwrap_or(0);
+        let issues = pkg_table_issues(&entries, data_bytes);
+        if !issues.is_empty() {
+            bail!(
+                "{} is still unreadable after undoing {}: {}",
+                path.display(),
+                variant,
+                issues.join("; ")
+            );
+        }
+    }
 
     Ok(ScenePkg {
         path: path.to_path_buf(),
@@ -158,11 +247,32 @@ pub fn best_video_entry(pkg: &ScenePkg, allow_preview_fallback: bool) -> Option<
     candidates.into_iter().next()
 }
 
+/// `name` (a package entry or a path a manifest stores) under `root`.
+/// Absolute names and `..` are refused so a crafted package cannot write or
+/// point outside `root`.
+pub fn entry_path_under(root: &Path, name: &str) -> Result<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let rel = Path::new(&normalized);
+    let plain = rel
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if normalized.is_empty() || !plain {
+        bail!("Unsafe package entry name '{}'", name);
+    }
+    Ok(root.join(rel))
+}
+
 pub fn extract_entry_to_cache(
     pkg: &ScenePkg,
     entry: &ScenePkgEntry,
     cache_root: &Path,
 ) -> Result<PathBuf> {
+    let out_path = entry_path_under(cache_root, &entry.filename)?;
+    ensure_outside_library(&out_path, "pkg extraction")?;
+    if extracted_copy_is_current(pkg, entry, &out_path) {
+        return Ok(out_path);
+    }
+
     let mut in_file = File::open(&pkg.path)
         .with_context(|| format!("Failed to open pkg for extraction: {}", pkg.path.display()))?;
 
@@ -171,7 +281,6 @@ pub fn extract_entry_to_cache(
         .seek(SeekFrom::Start(seek_pos))
         .with_context(|| format!("Failed to seek pkg to {}", seek_pos))?;
 
-    let out_path = cache_root.join(&entry.filename);
     if let Some(parent) = out_path.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("Failed to create cache dir {}", parent.display()))?;
@@ -196,10 +305,406 @@ pub fn extract_entry_to_cache(
     Ok(out_path)
 }
 
+/// Content digest of one entry, independent of where it sits in the package.
+pub fn entry_digest(pkg: &ScenePkg, entry: &ScenePkgEntry) -> Result<String> {
+    let mut in_file = File::open(&pkg.path)
+        .with_context(|| format!("Failed to open pkg for read: {}", pkg.path.display()))?;
+    let seek_pos = pkg.base_offset + entry.offset as u64;
+    in_file
+        .seek(SeekFrom::Start(seek_pos))
+        .with_context(|| format!("Failed to seek pkg to {}", seek_pos))?;
+
+    let mut hash = FNV_OFFSET;
+    let mut remaining = entry.length as usize;
+    let mut chunk = vec![0u8; 64 * 1024];
+    while remaining > 0 {
+        let read_n = remaining.min(chunk.len());
+        in_file
+            .read_exact(&mut chunk[..read_n])
+            .context("Failed while reading pkg entry bytes")?;
+        fnv1a_feed(&mut hash, &chunk[..read_n]);
+        remaining -= read_n;
+    }
+    fnv1a_update(&mut hash, &entry.length.to_le_bytes());
+    Ok(format!("{:016x}", hash))
+}
+
+/// Per-entry digests of the package version last seen by the scene cache.
+/// `pkg_size`/`pkg_modified` tell whether the digests still describe the
+/// package on disk without re-hashing it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PkgDigestIndex {
+    pub version: u32,
+    pub pkg_size: u64,
+    pub pkg_modified: u128,
+    pub digests: BTreeMap<String, String>,
+}
+
+/// What changed in a package since the previous digest index.
+#[derive(Debug, Default, Serialize)]
+pub struct PkgDelta {
+    /// No previous index: everything counts as added, nothing was invalidated.
+    pub first_index: bool,
+    pub changed: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    /// Extracted copies deleted because their entry changed or disappeared.
+    pub invalidated: Vec<String>,
+}
+
+impl PkgDelta {
+    pub fn is_update(&self) -> bool {
+        let touched =
+            !self.changed.is_empty() || !self.added.is_empty() || !self.removed.is_empty();
+        !self.first_index && touched
+    }
+}
+
+fn pkg_stamp(path: &Path) -> Result<(u64, u128)> {
+    let meta = fs::metadata(path).with_context(|| format!("Failed to stat {}", path.display()))?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    Ok((meta.len(), modified))
+}
+
+/// Scene cache directory of the wallpaper that owns `pkg`.
+pub fn pkg_scene_cache_root(pkg: &ScenePkg) -> PathBuf {
+    let root = pkg.path.parent().unwrap_or(Path::new("."));
+    let cache_key = root
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().replace('/', "_"));
+    default_scene_cache_root(&cache_key)
+}
+
+fn load_digest_index(scene_cache: &Path) -> Option<PkgDigestIndex> {
+    let raw = fs::read(scene_cache.join(DIGEST_INDEX_FILE)).ok()?;
+    serde_json::from_slice::<PkgDigestIndex>(&raw)
+        .ok()
+        .filter(|index| index.version == DIGEST_INDEX_VERSION)
+}
+
+fn remove_extracted_copies(dir: &Path, entry: &str, removed: &mut Vec<String>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in read_dir.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.is_dir() {
+            remove_extracted_copies(&path, entry, removed);
+        } else if path.ends_with(entry) && fs::remove_file(&path).is_ok() {
+            removed.push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+/// Brings the digest index under `scene_cache` up to date with `pkg` and
+/// deletes extracted copies of entries whose content changed or that are gone,
+/// so the next extraction (and every conversion keyed on its mtime) redoes
+/// only those. Unchanged entries keep their extracted files and proxies.
+pub fn sync_pkg_digest_index(pkg: &ScenePkg, scene_cache: &Path) -> Result<PkgDelta> {
+    let (pkg_size, pkg_modified) = pkg_stamp(&pkg.path)?;
+    let previous = load_digest_index(scene_cache);
+    if let Some(index) = &previous
+        && index.pkg_size == pkg_size
+        && index.pkg_modified == pkg_modified
+    {
+        return Ok(PkgDelta {
+            unchanged: index.digests.len(),
+            ..PkgDelta::default()
+        });
+    }
+
+    let mut digests = BTreeMap::new();
+    for entry in &pkg.entries {
+        digests.insert(entry.filename.clone(), entry_digest(pkg, entry)?);
+    }
+    let mut delta = PkgDelta {
+        first_index: previous.is_none(),
+        ..PkgDelta::default()
+    };
+    let old = previous.map(|index| index.digests).unwrap_or_default();
+    for (name, digest) in &digests {
+        match old.get(name) {
+            Some(prev) if prev == digest => delta.unchanged += 1,
+            Some(_) => delta.changed.push(name.clone()),
+            None => delta.added.push(name.clone()),
+        }
+    }
+    delta.removed = old
+        .keys()
+        .filter(|name| !digests.contains_key(*name))
+        .cloned()
+        .collect();
+    for name in delta.changed.iter().chain(&delta.removed) {
+        remove_extracted_copies(scene_cache, name, &mut delta.invalidated);
+    }
+
+    fs::create_dir_all(scene_cache)
+        .with_context(|| format!("Failed to create {}", scene_cache.display()))?;
+    let index = PkgDigestIndex {
+        version: DIGEST_INDEX_VERSION,
+        pkg_size,
+        pkg_modified,
+        digests,
+    };
+    let index_path = scene_cache.join(DIGEST_INDEX_FILE);
+    fs::write(&index_path, serde_json::to_vec_pretty(&index)?)
+        .with_context(|| format!("Failed writing {}", index_path.display()))?;
+    Ok(delta)
+}
+
+/// [`sync_pkg_digest_index`] against the wallpaper's own scene cache, logging
+/// what a workshop update actually touched.
+pub fn refresh_scene_cache(pkg: &ScenePkg) -> Result<PkgDelta> {
+    let delta = sync_pkg_digest_index(pkg, &pkg_scene_cache_root(pkg))?;
+    if delta.is_update() {
+        eprintln!(
+            "[ok] scene.pkg updated: {} changed, {} added, {} removed, {} unchanged entries kept",
+            delta.changed.len(),
+            delta.added.len(),
+            delta.removed.len(),
+            delta.unchanged
+        );
+    }
+    Ok(delta)
+}
+
+/// True when `out_path` already holds `entry` as indexed for this exact
+/// package version, so extraction can be skipped. Only copies inside the
+/// scene cache qualify: those are the ones the index sync cleans up.
+fn extracted_copy_is_current(pkg: &ScenePkg, entry: &ScenePkgEntry, out_path: &Path) -> bool {
+    let scene_cache = pkg_scene_cache_root(pkg);
+    if !out_path.starts_with(&scene_cache) {
+        return false;
+    }
+    let same_length = fs::metadata(out_path)
+        .map(|meta| meta.is_file() && meta.len() == entry.length as u64)
+        .unwrap_or(false);
+    if !same_length {
+        return false;
+    }
+    let Some(index) = load_digest_index(&scene_cache) else {
+        return false;
+    };
+    pkg_stamp(&pkg.path)
+        .map(|(size, modified)| index.pkg_size == size && index.pkg_modified == modified)
+        .unwrap_or(false)
+        && index.digests.contains_key(&entry.filename)
+}
+
+/// Writes `entries` as a PKGV container readable by `parse_scene_pkg`.
+pub fn write_scene_pkg(path: &Path, entries: &[(String, Vec<u8>)]) -> Result<()> {
+    fn push_sized_string(out: &mut Vec<u8>, value: &str) {
+        out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        out.extend_from_slice(value.as_bytes());
+    }
+
+    ensure_outside_library(path, "pkg write")?;
+    let mut out = Vec::new();
+    push_sized_string(&mut out, "PKGV0001");
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    let mut offset = 0u64;
+    for (name, bytes) in entries {
+        let length = u32::try_from(bytes.len())
+            .with_context(|| format!("Package entry {} is too large", name))?;
+        let start = u32::try_from(offset).context("Package exceeds 4 GiB")?;
+        push_sized_string(&mut out, name);
+        out.extend_from_slice(&start.to_le_bytes());
+        out.extend_from_slice(&length.to_le_bytes());
+        offset += bytes.len() as u64;
+    }
+    for (_, bytes) in entries {
+        out.extend_from_slice(bytes);
+    }
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, out).with_context(|| format!("Failed to write package {}", path.display()))
+}
+
+/// Entry table of a package as an editable JSON document. Offsets are
+/// relative to `base_offset`, the end of the header in the source file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PkgTable {
+    pub version: u32,
+    pub pkg: String,
+    pub base_offset: u64,
+    /// Bytes after the header; an entry must end within this.
+    pub data_bytes: u64,
+    pub entries: Vec<ScenePkgEntry>,
+    /// Problems found in the table. Ignored on import.
+    #[serde(default)]
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PkgTableImportReport {
+    pub source: String,
+    pub out: String,
+    pub entries: usize,
+    pub total_bytes: u64,
+    pub dry_run: bool,
+}
+
+pub fn find_scene_pkg_path(root: &Path) -> Result<PathBuf> {
+    ["scene.pkg", "gifscene.pkg"]
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file())
+        .with_context(|| format!("No scene.pkg/gifscene.pkg found in {}", root.display()))
+}
+
+/// Package in `root` holding the scene description, and that entry's name.
+/// For items that ship `scene.json` only inside their pkg. Unreadable
+/// packages count as no scene.
+pub fn find_packed_scene_json(root: &Path) -> Option<(PathBuf, String)> {
+    ["scene.pkg", "gifscene.pkg"]
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .find_map(|path| {
+            let pkg = parse_scene_pkg(&path).ok()?;
+            let entry =
+                find_entry(&pkg, "scene.json").or_else(|| find_entry(&pkg, "gifscene.json"))?;
+            Some((path, entry.filename))
+        })
+}
+
+/// Out-of-range, overlapping and duplicate entries, in table order.
+pub fn pkg_table_issues(entries: &[ScenePkgEntry], data_bytes: u64) -> Vec<String> {
+    let mut issues = Vec::new();
+    for entry in entries {
+        let end = entry.offset as u64 + entry.length as u64;
+        if end > data_bytes {
+            issues.push(format!(
+                "{} ends at {} past the {} data bytes",
+                entry.filename, end, data_bytes
+            ));
+        }
+    }
+    let mut by_offset = entries.iter().filter(|e| e.length > 0).collect::<Vec<_>>();
+    by_offset.sort_by_key(|e| e.offset);
+    for pair in by_offset.windows(2) {
+        if pair[0].offset as u64 + pair[0].length as u64 > pair[1].offset as u64 {
+            issues.push(format!(
+                "{} overlaps {}",
+                pair[0].filename, pair[1].filename
+            ));
+        }
+    }
+    let mut seen = BTreeSet::new();
+    for entry in entries {
+        if !seen.insert(entry.filename.to_ascii_lowercase()) {
+            issues.push(format!("{} appears more than once", entry.filename));
+        }
+    }
+    issues
+}
+
+pub fn export_pkg_table(pkg_path: &Path) -> Result<PkgTable> {
+    let pkg = parse_scene_pkg(pkg_path)?;
+    let size = fs::metadata(pkg_path)
+        .with_context(|| format!("Failed to stat {}", pkg_path.display()))?
+        .len();
+    let data_bytes = size.saturating_sub(pkg.base_offset);
+    Ok(PkgTable {
+        version: PKG_TABLE_VERSION,
+        pkg: pkg_path.display().to_string(),
+        base_offset: pkg.base_offset,
+        data_bytes,
+        issues: pkg_table_issues(&pkg.entries, data_bytes),
+        entries: pkg.entries,
+    })
+}
+
+/// Rebuilds a package from an edited table: each entry's bytes are read from
+/// `source` at `base_offset + offset` and written under its (possibly new)
+/// name. The source header is never parsed, so a hand-written table can
+/// recover a package whose own table is damaged.
+pub fn import_pkg_table(
+    source: &Path,
+    table: &PkgTable,
+    out: &Path,
+    dry_run: bool,
+) -> Result<PkgTableImportReport> {
+    if table.version != PKG_TABLE_VERSION {
+        bail!(
+            "Unsupported pkg table version {} (expected {})",
+            table.version,
+            PKG_TABLE_VERSION
+        );
+    }
+    let mut seen = BTreeSet::new();
+    for entry in &table.entries {
+        if entry.filename.trim().is_empty() {
+            bail!("Table has an entry without a filename");
+        }
+        if !seen.insert(entry.filename.to_ascii_lowercase()) {
+            bail!("Entry {} appears more than once", entry.filename);
+        }
+    }
+    if out.exists() && fs::canonicalize(out).ok() == fs::canonicalize(source).ok() {
+        bail!("Refusing to overwrite the source package; pass another --out");
+    }
+    let bytes = fs::read(source).with_context(|| format!("Failed to read {}", source.display()))?;
+    let mut entries = Vec::with_capacity(table.entries.len());
+    for entry in &table.entries {
+        // The table is hand-edited: sums past u64 are an error, not a wrap.
+        let Some((start, end)) = table
+            .base_offset
+            .checked_add(entry.offset as u64)
+            .and_then(|start| Some((start, start.checked_add(entry.length as u64)?)))
+        else {
+            bail_msg!(
+                Msg::PkgTableOffsetOverflow,
+                entry.filename,
+                table.base_offset,
+                entry.offset,
+                entry.length
+            );
+        };
+        if end > bytes.len() as u64 {
+            bail!(
+                "Entry {} ends at byte {} but {} has {} bytes",
+                entry.filename,
+                end,
+                source.display(),
+                bytes.len()
+            );
+        }
+        entries.push((
+            entry.filename.clone(),
+            bytes[start as usize..end as usize].to_vec(),
+        ));
+    }
+    let report = PkgTableImportReport {
+        source: source.display().to_string(),
+        out: out.display().to_string(),
+        entries: entries.len(),
+        total_bytes: entries.iter().map(|(_, b)| b.len() as u64).sum(),
+        dry_run,
+    };
+    if dry_run {
+        eprintln!("[dry-run] would write {}", out.display());
+        return Ok(report);
+    }
+    write_scene_pkg(out, &entries)?;
+    eprintln!("[ok] wrote {} ({} entries)", out.display(), entries.len());
+    Ok(report)
+}
+
 pub fn default_scene_cache_root(workshop_id_or_name: &str) -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home)
-        .join(".cache/kitsune-livewallpaper/scene")
+    crate::app_paths::app_cache_dir()
+        .join("scene")
         .join(workshop_id_or_name)
 }
 
@@ -234,4 +739,134 @@ mod tests {
         let best = best_video_entry(&pkg, false).expect("expected candidate");
         assert_eq!(best.filename, "video/main.mp4");
     }
+
+    #[test]
+    fn written_pkg_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.pkg");
+        write_scene_pkg(
+            &path,
+            &[
+                ("scene.json".to_string(), b"{}".to_vec()),
+                ("textures/a.png".to_string(), vec![1, 2, 3, 4]),
+            ],
+        )
+        .unwrap();
+
+        let pkg = parse_scene_pkg(&path).unwrap();
+        assert_eq!(pkg.entries.len(), 2);
+        let entry = find_entry(&pkg, "textures/a.png").unwrap();
+        assert_eq!(read_entry_bytes(&pkg, &entry).unwrap(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn crafted_entry_names_cannot_escape_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("evil.pkg");
+        write_scene_pkg(
+            &path,
+            &[
+                ("ok/a.png".to_string(), vec![1]),
+                ("../escaped.txt".to_string(), vec![2]),
+                ("/tmp/kwe-absolute.txt".to_string(), vec![3]),
+                ("sub\\..\\..\\win.txt".to_string(), vec![4]),
+            ],
+        )
+        .unwrap();
+        let pkg = parse_scene_pkg(&path).unwrap();
+        let cache = dir.path().join("cache");
+
+        assert!(extract_entry_to_cache(&pkg, &pkg.entries[0], &cache).is_ok());
+        for entry in &pkg.entries[1..] {
+            let err = extract_entry_to_cache(&pkg, entry, &cache).unwrap_err();
+            assert!(err.to_string().contains("Unsafe"), "{err}");
+        }
+        assert!(!dir.path().join("escaped.txt").exists());
+        assert!(!dir.path().join("win.txt").exists());
+        assert!(!Path::new("/tmp/kwe-absolute.txt").exists());
+    }
+
+    #[test]
+    fn digest_index_invalidates_only_changed_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scene.pkg");
+        let cache = dir.path().join("cache");
+        let write = |tex_a: &[u8]| {
+            write_scene_pkg(
+                &path,
+                &[
+                    ("materials/a.tex".to_string(), tex_a.to_vec()),
+                    ("materials/b.tex".to_string(), vec![9; 16]),
+                    ("scene.json".to_string(), b"{}".to_vec()),
+                ],
+            )
+            .unwrap();
+            parse_scene_pkg(&path).unwrap()
+        };
+
+        let pkg = write(&[1, 2, 3]);
+        let first = sync_pkg_digest_index(&pkg, &cache).unwrap();
+        assert!(first.first_index && !first.is_update());
+        for entry in &pkg.entries {
+            extract_entry_to_cache(&pkg, entry, &cache.join("assets")).unwrap();
+        }
+
+        // Same size, new content in a.tex only.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let pkg = write(&[3, 2, 1]);
+        let delta = sync_pkg_digest_index(&pkg, &cache).unwrap();
+        assert!(delta.is_update());
+        assert_eq!(delta.changed, ["materials/a.tex"]);
+        assert_eq!(delta.unchanged, 2);
+        assert_eq!(delta.invalidated.len(), 1);
+        assert!(!cache.join("assets/materials/a.tex").exists());
+        assert!(cache.join("assets/materials/b.tex").is_file());
+
+        let again = sync_pkg_digest_index(&pkg, &cache).unwrap();
+        assert!(!again.is_update() && again.unchanged == 3);
+    }
+
+    #[test]
+    fn edited_table_renames_and_repoints_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scene.pkg");
+        write_scene_pkg(
+            &path,
+            &[
+                ("scene.json".to_string(), b"{}".to_vec()),
+                ("materials/a.tex".to_string(), vec![1, 2, 3, 4]),
+            ],
+        )
+        .unwrap();
+
+        let mut table = export_pkg_table(&path).unwrap();
+        assert_eq!(table.data_bytes, 6);
+        assert!(table.issues.is_empty());
+        table.entries[1].filename = "materials/b.tex".to_string();
+        table.entries[1].offset = 3;
+        table.entries[1].length = 3;
+        assert!(pkg_table_issues(&table.entries, table.data_bytes).is_empty());
+        table.entries[1].length = 4;
+        assert_eq!(
+            pkg_table_issues(&table.entries, table.data_bytes),
+            ["materials/b.tex ends at 7 past the 6 data bytes"]
+        );
+        table.entries[1].length = 3;
+
+        let out = dir.path().join("fixed.pkg");
+        import_pkg_table(&path, &table, &out, false).unwrap();
+        let pkg = parse_scene_pkg(&out).unwrap();
+        let entry = find_entry(&pkg, "materials/b.tex").unwrap();
+        assert_eq!(read_entry_bytes(&pkg, &entry).unwrap(), vec![2, 3, 4]);
+        assert!(import_pkg_table(&path, &table, &path, false).is_err());
+
+        let overflow = dir.path().join("overflow.pkg");
+        table.base_offset = u64::MAX;
+        let err = import_pkg_table(&path, &table, &overflow, false).unwrap_err();
+        assert!(err.to_string().contains("past any file"), "{err}");
+        table.base_offset = u64::MAX - 3;
+        table.entries[1].offset = u32::MAX;
+        assert!(import_pkg_table(&path, &table, &overflow, false).is_err());
+        assert!(!overflow.exists());
+    }
 }
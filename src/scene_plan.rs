@@ -1,5 +1,6 @@
 use crate::scene_gpu_graph::build_scene_gpu_graph;
-use crate::scene_pkg::{find_entry, parse_scene_pkg, read_entry_bytes};
+use crate::scene_pkg::{ScenePkg, find_entry, parse_scene_pkg, read_entry_bytes};
+use crate::tex_payload::{TexVideoInfo, tex_video_info};
 use anyhow::{Context, Result};
 use serde::Serialize;
 use serde_json::Value;
@@ -20,6 +21,10 @@ pub struct ScenePlan {
     pub scene_json_parse_ok: bool,
     pub primary_visual_asset: Option<String>,
     pub primary_music_asset: Option<String>,
+    /// Timing of the video embedded in the primary visual, when it is a
+    /// TEXB0004 .tex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_video: Option<TexVideoInfo>,
     pub texture_candidates: Vec<AssetCandidate>,
     pub image_candidates: Vec<AssetCandidate>,
     pub audio_candidates: Vec<AssetCandidate>,
@@ -127,6 +132,24 @@ fn collect_files_recursive(
     }
 }
 
+/// Reads the .tex behind `asset` (a texture ref or a materials/ path) from
+/// the pkg or the wallpaper folder and returns its embedded video timing.
+fn primary_video_info(root: &Path, pkg: Option<&ScenePkg>, asset: &str) -> Option<TexVideoInfo> {
+    let candidates = [
+        asset.to_string(),
+        format!("{}.tex", asset),
+        format!("materials/{}.tex", asset),
+    ];
+    let bytes = candidates
+        .iter()
+        .filter(|name| name.to_ascii_lowercase().ends_with(".tex"))
+        .find_map(|name| match pkg {
+            Some(pkg) => find_entry(pkg, name).and_then(|e| read_entry_bytes(pkg, &e).ok()),
+            None => fs::read(root.join(name)).ok(),
+        })?;
+    tex_video_info(&bytes)
+}
+
 pub fn build_scene_plan(root: &Path) -> Result<ScenePlan> {
     let pkg_path = pick_pkg_path(root);
     let pkg = if let Some(path) = &pkg_path {
@@ -201,6 +224,9 @@ pub fn build_scene_plan(root: &Path) -> Result<ScenePlan> {
         .or_else(|| image_candidates.first().map(|v| v.filename.clone()));
 
     let primary_music_asset = audio_candidates.first().map(|v| v.filename.clone());
+    let primary_video = primary_visual_asset
+        .as_deref()
+        .and_then(|asset| primary_video_info(root, pkg.as_ref(), asset));
 
     let mut reactive_hints = Vec::new();
     let mut scene_json_parse_ok = false;
@@ -233,6 +259,12 @@ pub fn build_scene_plan(root: &Path) -> Result<ScenePlan> {
     if primary_music_asset.is_none() {
         notes.push("No audio asset candidate found".to_string());
     }
+    if let Some(video) = &primary_video {
+        notes.push(format!(
+            "Primary visual embeds a video: {} frames at {:.2} fps ({:.1}s)",
+            video.frame_count, video.fps, video.duration_seconds
+        ));
+    }
     if likely_audio_reactive {
         notes.push("Audio-reactive hints detected in scene.json keys".to_string());
     }
@@ -246,6 +278,7 @@ pub fn build_scene_plan(root: &Path) -> Result<ScenePlan> {
         scene_json_parse_ok,
         primary_visual_asset,
         primary_music_asset,
+        primary_video,
         texture_candidates,
         image_candidates,
         audio_candidates,
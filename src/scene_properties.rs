@@ -0,0 +1,77 @@
+use crate::asset_resolver::AssetResolver;
+use crate::scene_gpu_graph::{SceneObjectGroup, build_scene_gpu_graph};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// One entry of `project.json` `general.properties`, as WE's property panel
+/// shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenePropertyEntry {
+    pub name: String,
+    pub kind: String,
+    pub text: Option<String>,
+    pub default: Option<Value>,
+    pub order: Option<i64>,
+    /// Panel condition (`showclock.value == true`) hiding this property.
+    pub condition: Option<String>,
+    pub options: Value,
+    /// Object groups whose visibility this property toggles.
+    pub groups: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenePropertySchema {
+    pub properties: Vec<ScenePropertyEntry>,
+    pub groups: Vec<SceneObjectGroup>,
+}
+
+pub fn build_scene_property_schema(root: &Path) -> Result<ScenePropertySchema> {
+    let graph = build_scene_gpu_graph(root)?;
+    let project = AssetResolver::new(root)?
+        .resolve("project.json")
+        .and_then(|a| serde_json::from_slice::<Value>(&a.bytes).ok());
+    let mut properties: Vec<ScenePropertyEntry> = project
+        .as_ref()
+        .and_then(|p| p.pointer("/general/properties"))
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flatten()
+        .map(|(name, prop)| ScenePropertyEntry {
+            name: name.clone(),
+            kind: prop
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            text: prop
+                .get("text")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            default: prop.get("value").cloned(),
+            order: prop.get("order").and_then(|v| v.as_i64()),
+            condition: prop
+                .get("condition")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            options: prop.get("options").cloned().unwrap_or(Value::Null),
+            groups: graph
+                .groups
+                .iter()
+                .filter(|g| g.visibility_property.as_deref() == Some(name.as_str()))
+                .map(|g| g.id)
+                .collect(),
+        })
+        .collect();
+    properties.sort_by(|a, b| {
+        a.order
+            .unwrap_or(i64::MAX)
+            .cmp(&b.order.unwrap_or(i64::MAX))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(ScenePropertySchema {
+        properties,
+        groups: graph.groups,
+    })
+}
@@ -1,5 +1,6 @@
 use crate::scene_pkg::{
-    default_scene_cache_root, extract_entry_to_cache, find_entry, parse_scene_pkg,
+    FNV_OFFSET, ScenePkg, default_scene_cache_root, entry_digest, extract_entry_to_cache,
+    find_entry, fnv1a_update, parse_scene_pkg, read_entry_bytes, refresh_scene_cache,
 };
 use crate::scene_runtime::{SceneRuntimeResult, run_scene_runtime};
 use anyhow::{Context, Result, bail};
@@ -23,6 +24,8 @@ pub struct SceneRenderSession {
     pub music_asset_path: Option<String>,
     pub uniforms_path: String,
     pub manifest_path: String,
+    pub inputs_fingerprint: String,
+    pub assets_reused: bool,
     pub runtime: SceneRuntimeResult,
     pub uniform_schema: UniformSchema,
     pub frame_count: usize,
@@ -31,6 +34,7 @@ pub struct SceneRenderSession {
 #[derive(Debug, Serialize)]
 struct SessionManifest {
     pub version: u32,
+    pub inputs_fingerprint: String,
     pub visual_asset_path: String,
     pub music_asset_path: Option<String>,
     pub uniforms_path: String,
@@ -50,17 +54,118 @@ fn pick_pkg_path(root: &Path) -> Option<PathBuf> {
     }
 }
 
+fn scene_cache_dir_for_root(root: &Path) -> PathBuf {
+    let cache_key = root
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().replace('/', "_"));
+    default_scene_cache_root(&cache_key)
+}
+
+pub fn scene_session_dir_for_root(root: &Path) -> PathBuf {
+    scene_cache_dir_for_root(root).join("render-session")
+}
+
+/// Kept outside `render-session` so a rebuild never rewrites the file that
+/// is being shown while it runs.
+pub fn scene_warm_start_dir(root: &Path) -> PathBuf {
+    scene_cache_dir_for_root(root).join("warm-start")
+}
+
+pub fn find_warm_start_entry(root: &Path) -> Option<PathBuf> {
+    let dir = scene_warm_start_dir(root);
+    fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|p| {
+            p.is_file()
+                && p.file_name()
+                    .map(|n| !n.to_string_lossy().starts_with('.'))
+                    .unwrap_or(false)
+        })
+}
+
+/// Snapshots the entry that was just launched so the next run can show it
+/// immediately.
+pub fn save_warm_start_entry(root: &Path, entry: &Path) -> Result<PathBuf> {
+    let dir = scene_warm_start_dir(root);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let name = entry
+        .file_name()
+        .map(|v| v.to_string_lossy().to_string())
+        .unwrap_or_else(|| "entry".to_string());
+    let target = dir.join(&name);
+    if entry == target {
+        return Ok(target);
+    }
+    let tmp = dir.join(format!(".{}.tmp", name));
+    fs::copy(entry, &tmp)
+        .with_context(|| format!("Failed to copy {} to {}", entry.display(), tmp.display()))?;
+    if let Ok(existing) = fs::read_dir(&dir) {
+        for old in existing.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if old != tmp && old.is_file() {
+                let _ = fs::remove_file(&old);
+            }
+        }
+    }
+    fs::rename(&tmp, &target)
+        .with_context(|| format!("Failed to move warm-start entry to {}", target.display()))?;
+    Ok(target)
+}
+
+/// Hash of everything that determines a session's extracted assets: the scene
+/// and project json, the content of every pkg entry and the CLI options.
+pub fn compute_inputs_fingerprint(
+    root: &Path,
+    pkg: &ScenePkg,
+    source: Option<&str>,
+    seconds: u64,
+    frame_ms: u64,
+) -> Result<String> {
+    let mut hash = FNV_OFFSET;
+    fnv1a_update(&mut hash, b"kwe-session-v1");
+    for name in ["scene.json", "gifscene.json"] {
+        if let Some(entry) = find_entry(pkg, name) {
+            fnv1a_update(&mut hash, name.as_bytes());
+            fnv1a_update(&mut hash, &read_entry_bytes(pkg, &entry)?);
+        }
+    }
+    match fs::read(root.join("project.json")) {
+        Ok(bytes) => fnv1a_update(&mut hash, &bytes),
+        Err(_) => fnv1a_update(&mut hash, b"no-project"),
+    }
+    for entry in &pkg.entries {
+        fnv1a_update(&mut hash, entry.filename.as_bytes());
+        fnv1a_update(&mut hash, entry_digest(pkg, entry)?.as_bytes());
+    }
+    fnv1a_update(&mut hash, source.unwrap_or("default").as_bytes());
+    fnv1a_update(&mut hash, &seconds.to_le_bytes());
+    fnv1a_update(&mut hash, &frame_ms.to_le_bytes());
+    Ok(format!("{:016x}", hash))
+}
+
+pub(crate) fn previous_inputs_fingerprint(manifest_path: &Path) -> Option<String> {
+    let raw = fs::read(manifest_path).ok()?;
+    let manifest: serde_json::Value = serde_json::from_slice(&raw).ok()?;
+    manifest
+        .get("inputs_fingerprint")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string())
+}
+
 pub fn build_scene_render_session(
     root: &Path,
     source: Option<String>,
     seconds: u64,
     frame_ms: u64,
 ) -> Result<SceneRenderSession> {
-    let runtime = run_scene_runtime(root, source, seconds, frame_ms, true)?;
-
     let pkg_path = pick_pkg_path(root)
         .with_context(|| format!("No scene.pkg/gifscene.pkg found in {}", root.display()))?;
     let pkg = parse_scene_pkg(&pkg_path)?;
+    let pkg_delta = refresh_scene_cache(&pkg)?;
+
+    let runtime = run_scene_runtime(root, source.clone(), seconds, frame_ms, true)?;
 
     let visual_name = runtime
         .scene_plan
@@ -75,26 +180,39 @@ pub fn build_scene_render_session(
         )
     })?;
 
-    let cache_key = root
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_else(|| root.to_string_lossy().replace('/', "_"));
-
-    let session_dir = default_scene_cache_root(&cache_key).join("render-session");
+    let session_dir = scene_session_dir_for_root(root);
     fs::create_dir_all(&session_dir)
         .with_context(|| format!("Failed to create session dir {}", session_dir.display()))?;
 
+    let inputs_fingerprint =
+        compute_inputs_fingerprint(root, &pkg, source.as_deref(), seconds, frame_ms)?;
+    let manifest_path = session_dir.join("manifest.json");
+    let fingerprint_matches =
+        previous_inputs_fingerprint(&manifest_path).as_deref() == Some(inputs_fingerprint.as_str());
+
     let assets_dir = session_dir.join("assets");
-    let visual_asset = extract_entry_to_cache(&pkg, &visual_entry, &assets_dir)?;
+    let music_entry = runtime
+        .scene_plan
+        .primary_music_asset
+        .as_ref()
+        .and_then(|music_name| find_entry(&pkg, music_name));
+    let cached_visual = assets_dir.join(&visual_entry.filename);
+    let cached_music = music_entry
+        .as_ref()
+        .map(|entry| assets_dir.join(&entry.filename));
+    let assets_reused = fingerprint_matches
+        && cached_visual.is_file()
+        && cached_music.as_ref().is_none_or(|p| p.is_file());
 
-    let music_asset = if let Some(music_name) = &runtime.scene_plan.primary_music_asset {
-        if let Some(entry) = find_entry(&pkg, music_name) {
-            Some(extract_entry_to_cache(&pkg, &entry, &assets_dir)?)
-        } else {
-            None
-        }
+    let (visual_asset, music_asset) = if assets_reused {
+        (cached_visual, cached_music)
     } else {
-        None
+        let visual_asset = extract_entry_to_cache(&pkg, &visual_entry, &assets_dir)?;
+        let music_asset = match &music_entry {
+            Some(entry) => Some(extract_entry_to_cache(&pkg, entry, &assets_dir)?),
+            None => None,
+        };
+        (visual_asset, music_asset)
     };
 
     let uniforms_path = session_dir.join("uniforms.json");
@@ -110,8 +228,22 @@ pub fn build_scene_render_session(
         beat: "u_audio_beat".to_string(),
     };
 
+    let mut notes = vec![
+        "Headless render session generated (GPU backend pending)".to_string(),
+        "Use uniform_schema names directly as shader uniforms".to_string(),
+    ];
+    if assets_reused {
+        notes.push("Inputs fingerprint unchanged: reused extracted assets".to_string());
+    } else if pkg_delta.is_update() {
+        notes.push(format!(
+            "Package update: re-extracted {} changed entries, kept {} unchanged",
+            pkg_delta.changed.len() + pkg_delta.added.len(),
+            pkg_delta.unchanged
+        ));
+    }
     let manifest = SessionManifest {
         version: 1,
+        inputs_fingerprint: inputs_fingerprint.clone(),
         visual_asset_path: visual_asset.to_string_lossy().to_string(),
         music_asset_path: music_asset
             .as_ref()
@@ -126,13 +258,9 @@ pub fn build_scene_render_session(
             energy: uniform_schema.energy.clone(),
             beat: uniform_schema.beat.clone(),
         },
-        notes: vec![
-            "Headless render session generated (GPU backend pending)".to_string(),
-            "Use uniform_schema names directly as shader uniforms".to_string(),
-        ],
+        notes,
     };
 
-    let manifest_path = session_dir.join("manifest.json");
     let manifest_json = serde_json::to_vec_pretty(&manifest)?;
     fs::write(&manifest_path, manifest_json)
         .with_context(|| format!("Failed writing manifest file {}", manifest_path.display()))?;
@@ -147,8 +275,59 @@ pub fn build_scene_render_session(
         music_asset_path: music_asset.map(|p| p.to_string_lossy().to_string()),
         uniforms_path: uniforms_path.to_string_lossy().to_string(),
         manifest_path: manifest_path.to_string_lossy().to_string(),
+        inputs_fingerprint,
+        assets_reused,
         frame_count: runtime.uniforms.len(),
         runtime,
         uniform_schema,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_pkg::write_scene_pkg;
+
+    #[test]
+    fn fingerprint_tracks_scene_and_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let pkg_path = dir.path().join("scene.pkg");
+        write_scene_pkg(
+            &pkg_path,
+            &[("scene.json".to_string(), br#"{"objects":[]}"#.to_vec())],
+        )
+        .unwrap();
+        let pkg = parse_scene_pkg(&pkg_path).unwrap();
+
+        let a = compute_inputs_fingerprint(dir.path(), &pkg, None, 4, 50).unwrap();
+        let b = compute_inputs_fingerprint(dir.path(), &pkg, None, 4, 50).unwrap();
+        let c = compute_inputs_fingerprint(dir.path(), &pkg, None, 8, 50).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        fs::write(dir.path().join("project.json"), b"{}").unwrap();
+        let d = compute_inputs_fingerprint(dir.path(), &pkg, None, 4, 50).unwrap();
+        assert_ne!(a, d);
+    }
+
+    #[test]
+    fn fingerprint_tracks_entry_content_not_just_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let pkg_path = dir.path().join("scene.pkg");
+        let write = |texture: &[u8]| {
+            write_scene_pkg(
+                &pkg_path,
+                &[
+                    ("scene.json".to_string(), br#"{"objects":[]}"#.to_vec()),
+                    ("materials/a.tex".to_string(), texture.to_vec()),
+                ],
+            )
+            .unwrap();
+            let pkg = parse_scene_pkg(&pkg_path).unwrap();
+            compute_inputs_fingerprint(dir.path(), &pkg, None, 4, 50).unwrap()
+        };
+        let a = write(b"AAAA");
+        let b = write(b"BBBB");
+        assert_ne!(a, b);
+    }
+}
@@ -41,7 +41,10 @@ fn clamp01(v: f32) -> f32 {
     v.clamp(0.0, 1.0)
 }
 
-fn build_uniform_timeline(frames: &[AudioLevelFrame], frame_ms: u64) -> Vec<UniformFrame> {
+pub(crate) fn build_uniform_timeline(
+    frames: &[AudioLevelFrame],
+    frame_ms: u64,
+) -> Vec<UniformFrame> {
     let mut out = Vec::with_capacity(frames.len());
     let mut ema_rms = 0.0f32;
 
@@ -1,8 +1,8 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScriptAssignment {
     pub source_path: String,
     pub target_property: String,
@@ -273,6 +273,310 @@ pub fn apply_scene_scripts(
     ScriptEvalResult { assignments, notes }
 }
 
+/// Time unit an analog clock hand script turns its object with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockHandUnit {
+    Hours,
+    Minutes,
+    Seconds,
+}
+
+/// Rotation driven by a `Date`-based script on an object's `angles`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockHand {
+    pub unit: ClockHandUnit,
+    /// Sweeps continuously instead of stepping once per unit.
+    pub smooth: bool,
+}
+
+impl ClockHand {
+    /// Seconds for one full turn of the dial.
+    pub fn period_seconds(self) -> u32 {
+        match self.unit {
+            ClockHandUnit::Hours => 12 * 3600,
+            ClockHandUnit::Minutes => 3600,
+            ClockHandUnit::Seconds => 60,
+        }
+    }
+
+    fn step_seconds(self) -> u32 {
+        match self.unit {
+            ClockHandUnit::Hours => 3600,
+            ClockHandUnit::Minutes => 60,
+            ClockHandUnit::Seconds => 1,
+        }
+    }
+
+    /// ffmpeg expression (commas escaped for a filter graph) giving the
+    /// clockwise angle in radians from the wall clock, `utc_offset` seconds
+    /// ahead of UTC.
+    pub fn rotate_expr(self, utc_offset: i32) -> String {
+        let period = self.period_seconds();
+        let mut phase = format!("mod(time(0)+{}\\,{})", utc_offset, period);
+        if !self.smooth {
+            let step = self.step_seconds();
+            phase = format!("floor({}/{})*{}", phase, step, step);
+        }
+        format!("2*PI*{}/{}", phase, period)
+    }
+}
+
+fn clock_unit_from_value(v: &Value) -> Option<ClockHandUnit> {
+    let v = v.get("value").unwrap_or(v);
+    if let Some(n) = v.as_u64() {
+        return match n {
+            0 => Some(ClockHandUnit::Hours),
+            1 => Some(ClockHandUnit::Minutes),
+            2 => Some(ClockHandUnit::Seconds),
+            _ => None,
+        };
+    }
+    let s = v.as_str()?.to_ascii_lowercase();
+    if s.contains("hour") {
+        Some(ClockHandUnit::Hours)
+    } else if s.contains("minute") {
+        Some(ClockHandUnit::Minutes)
+    } else if s.contains("second") {
+        Some(ClockHandUnit::Seconds)
+    } else {
+        None
+    }
+}
+
+/// Recognizes clock hand scripts on an `angles` property. The hand setting
+/// in `scriptproperties` wins; otherwise the coarsest `Date` getter used is
+/// the unit and a finer one means the hand sweeps smoothly.
+pub fn detect_clock_hand(angles: &Value) -> Option<ClockHand> {
+    let script = angles.get("script")?.as_str()?;
+    if !script.contains("Date") {
+        return None;
+    }
+    let uses = |getter: &str| script.contains(getter);
+    let props = angles.get("scriptproperties").and_then(|v| v.as_object());
+    let prop = |keys: &[&str]| props.and_then(|p| keys.iter().find_map(|k| p.get(*k)));
+
+    let unit = prop(&["mode", "hand", "type", "unit"])
+        .and_then(clock_unit_from_value)
+        .or_else(|| {
+            if uses("getHours") {
+                Some(ClockHandUnit::Hours)
+            } else if uses("getMinutes") {
+                Some(ClockHandUnit::Minutes)
+            } else if uses("getSeconds") {
+                Some(ClockHandUnit::Seconds)
+            } else {
+                None
+            }
+        })?;
+    let smooth = prop(&["smooth", "smoothmovement"])
+        .map(|v| v.get("value").unwrap_or(v))
+        .and_then(|v| v.as_bool())
+        .unwrap_or_else(|| match unit {
+            ClockHandUnit::Hours => uses("getMinutes"),
+            ClockHandUnit::Minutes => uses("getSeconds"),
+            ClockHandUnit::Seconds => uses("getMilliseconds"),
+        });
+    Some(ClockHand { unit, smooth })
+}
+
+/// Object properties whose scripts are re-evaluated while the wallpaper plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimatedProperty {
+    Color,
+    Alpha,
+    Brightness,
+}
+
+impl AnimatedProperty {
+    const ALL: [(&'static str, AnimatedProperty); 3] = [
+        ("color", AnimatedProperty::Color),
+        ("alpha", AnimatedProperty::Alpha),
+        ("brightness", AnimatedProperty::Brightness),
+    ];
+}
+
+/// Time-varying value a property script returns (rainbow cycles, pulsing
+/// alpha), in a form live pipelines can evaluate every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ScriptAnimation {
+    /// Hue turns once per period, keeping the base saturation and value.
+    HueCycle { period_seconds: f32 },
+    /// Sine swing of `amplitude` around the base value.
+    Oscillate { period_seconds: f32, amplitude: f32 },
+}
+
+impl ScriptAnimation {
+    pub fn period_seconds(self) -> f32 {
+        match self {
+            ScriptAnimation::HueCycle { period_seconds }
+            | ScriptAnimation::Oscillate { period_seconds, .. } => period_seconds,
+        }
+    }
+
+    /// `base` `t` seconds into playback.
+    pub fn scalar_at(self, base: f32, t: f32) -> f32 {
+        match self {
+            ScriptAnimation::Oscillate {
+                period_seconds,
+                amplitude,
+            } => base + amplitude * (std::f32::consts::TAU * t / period_seconds).sin(),
+            ScriptAnimation::HueCycle { .. } => base,
+        }
+    }
+
+    /// `base` colour `t` seconds into playback. Grey or black bases cycle
+    /// through fully saturated hues, as rainbow scripts ignore the base.
+    pub fn color_at(self, base: [f32; 3], t: f32) -> [f32; 3] {
+        let ScriptAnimation::HueCycle { period_seconds } = self else {
+            return base.map(|c| self.scalar_at(c, t));
+        };
+        let (hue, saturation, value) = rgb_to_hsv(base);
+        let saturation = if saturation < 0.05 { 1.0 } else { saturation };
+        let value = if value < 0.05 { 1.0 } else { value };
+        hsv_to_rgb(
+            (hue + t / period_seconds).rem_euclid(1.0),
+            saturation,
+            value,
+        )
+    }
+}
+
+/// Hue in turns (0..1), saturation and value.
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let hue = if delta <= f32::EPSILON {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0) / 6.0
+    } else if max == g {
+        ((b - r) / delta + 2.0) / 6.0
+    } else {
+        ((r - g) / delta + 4.0) / 6.0
+    };
+    let saturation = if max <= f32::EPSILON {
+        0.0
+    } else {
+        delta / max
+    };
+    (hue, saturation, max)
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> [f32; 3] {
+    let channel = |n: f32| {
+        let k = (n + hue * 6.0).rem_euclid(6.0);
+        value - value * saturation * k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+    [channel(5.0), channel(3.0), channel(1.0)]
+}
+
+/// Number multiplying `engine.runtime` (`engine.runtime * 0.5`,
+/// `2 * engine.runtime`): how fast the script runs through its cycle.
+fn runtime_rate(script: &str) -> Option<f32> {
+    let idx = script.find("engine.runtime")?;
+    let after = script[idx + "engine.runtime".len()..].trim_start();
+    let number = |s: &str| {
+        s.trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|n| n.is_finite() && *n > 0.0)
+    };
+    if let Some(rest) = after.strip_prefix('*') {
+        let rest = rest.trim_start();
+        let len = rest
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .count();
+        return number(&rest[..len]);
+    }
+    let before = script[..idx].trim_end().strip_suffix('*')?.trim_end();
+    let len = before
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .count();
+    number(&before[before.len() - len..])
+}
+
+/// Recognizes a script on `property` that keeps changing with time. The
+/// period comes from `scriptproperties` (`period`/`duration` in seconds, or
+/// `speed`), else from the `engine.runtime` rate.
+pub fn detect_script_animation(
+    property: AnimatedProperty,
+    value: &Value,
+) -> Option<ScriptAnimation> {
+    let script = value.get("script")?.as_str()?;
+    let timed = [
+        "engine.runtime",
+        "engine.frametime",
+        "Date",
+        "performance.now",
+    ]
+    .iter()
+    .any(|source| script.contains(source));
+    if !timed {
+        return None;
+    }
+    let props = value.get("scriptproperties").and_then(|v| v.as_object());
+    let prop = |keys: &[&str]| {
+        props.and_then(|p| {
+            keys.iter().find_map(|k| {
+                let v = p.get(*k)?;
+                v.get("value").unwrap_or(v).as_f64().map(|n| n as f32)
+            })
+        })
+    };
+    let periodic = script.contains("Math.sin") || script.contains("Math.cos");
+    let rate = runtime_rate(script).unwrap_or(1.0);
+    // sin(runtime * k) repeats every 2π/k seconds; `% 1` hue ramps every 1/k.
+    let script_period = if periodic {
+        std::f32::consts::TAU / rate
+    } else if runtime_rate(script).is_some() {
+        1.0 / rate
+    } else {
+        10.0
+    };
+    let period_seconds = prop(&["period", "duration", "cycle"])
+        .or_else(|| prop(&["speed", "rate"]).map(|speed| script_period / speed.max(0.01)))
+        .unwrap_or(script_period)
+        .clamp(0.1, 3600.0);
+
+    if property == AnimatedProperty::Color {
+        return Some(ScriptAnimation::HueCycle { period_seconds });
+    }
+    if !periodic {
+        return None;
+    }
+    let amplitude = prop(&["amplitude", "amount", "range"]).unwrap_or(0.5);
+    Some(ScriptAnimation::Oscillate {
+        period_seconds,
+        amplitude,
+    })
+}
+
+/// Script animation of one object property.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PropertyAnimation {
+    pub property: AnimatedProperty,
+    pub animation: ScriptAnimation,
+}
+
+/// Time-driven scripts on the `color`, `alpha` and `brightness` of `object`.
+pub fn detect_object_animations(object: &Value) -> Vec<PropertyAnimation> {
+    AnimatedProperty::ALL
+        .iter()
+        .filter_map(|(key, property)| {
+            Some(PropertyAnimation {
+                property: *property,
+                animation: detect_script_animation(*property, object.get(*key)?)?,
+            })
+        })
+        .collect()
+}
+
 pub fn to_json_object(values: &BTreeMap<String, Value>) -> Value {
     let mut obj = Map::new();
     for (k, v) in values {
@@ -295,4 +599,62 @@ mod tests {
         assert_eq!(got[0].target_property, "bloomstrength");
         assert!(got[0].resolved_value.is_some());
     }
+
+    #[test]
+    fn detects_clock_hand_scripts() {
+        let minutes = serde_json::json!({
+            "script": "export function update(value) { let d = new Date(); value.z = -(d.getMinutes() + d.getSeconds() / 60) * 6; return value; }",
+            "value": "0 0 0"
+        });
+        let hand = detect_clock_hand(&minutes).unwrap();
+        assert_eq!(hand.unit, ClockHandUnit::Minutes);
+        assert!(hand.smooth);
+
+        let configured = serde_json::json!({
+            "script": "let d = new Date(); d.getHours(); d.getMinutes(); d.getSeconds();",
+            "scriptproperties": {"mode": {"value": 2}, "smooth": false}
+        });
+        let hand = detect_clock_hand(&configured).unwrap();
+        assert_eq!(hand.unit, ClockHandUnit::Seconds);
+        assert_eq!(
+            hand.rotate_expr(3600),
+            "2*PI*floor(mod(time(0)+3600\\,60)/1)*1/60"
+        );
+        assert!(detect_clock_hand(&serde_json::json!({"script": "value.z += 1;"})).is_none());
+    }
+
+    #[test]
+    fn time_driven_property_scripts_become_animations() {
+        let object = serde_json::json!({
+            "color": {
+                "script": "export function update(value) { return hsv((engine.runtime * 0.25) % 1, 1, 1); }",
+                "value": "1 1 1"
+            },
+            "alpha": {
+                "script": "export function update(value) { return 0.6 + Math.sin(engine.runtime * 2) * 0.3; }",
+                "scriptproperties": {"amplitude": {"value": 0.3}},
+                "value": 0.6
+            },
+            "brightness": {"script": "export function update(value) { return 2; }", "value": 1}
+        });
+        let animations = detect_object_animations(&object);
+        assert_eq!(animations.len(), 2);
+        let hue = animations[0].animation;
+        assert_eq!(
+            hue,
+            ScriptAnimation::HueCycle {
+                period_seconds: 4.0
+            }
+        );
+        assert_eq!(animations[1].property, AnimatedProperty::Alpha);
+        let pulse = animations[1].animation;
+        assert!((pulse.period_seconds() - std::f32::consts::PI).abs() < 1e-4);
+
+        // White cycles through red, green and blue.
+        let red = hue.color_at([1.0, 1.0, 1.0], 0.0);
+        let green = hue.color_at([1.0, 1.0, 1.0], 4.0 / 3.0);
+        assert!(red[0] > 0.99 && red[1] < 0.01 && red[2] < 0.01, "{red:?}");
+        assert!(green[1] > 0.99 && green[0] < 0.01, "{green:?}");
+        assert!((pulse.scalar_at(0.6, std::f32::consts::PI / 4.0) - 0.9).abs() < 1e-4);
+    }
 }
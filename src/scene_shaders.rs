@@ -0,0 +1,239 @@
+use crate::asset_resolver::{AssetResolver, ResolvedAsset};
+use crate::i18n::{Msg, warn_msg};
+use crate::library_guard::ensure_outside_library;
+use crate::scene_gpu_graph::{
+    ShaderUniformBinding, build_scene_gpu_graph, parse_uniform_meta_from_shader, shader_candidates,
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Max `#include` nesting followed while collecting shader headers.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShaderUse {
+    pub object_id: u64,
+    pub object_name: String,
+    pub effect_file: String,
+    pub pass_index: usize,
+    pub defines: Vec<String>,
+    pub combos: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DumpedShader {
+    pub shader: String,
+    pub stage: String,
+    pub resolved_path: String,
+    /// Path of the dumped copy, relative to the output dir.
+    pub file: String,
+    pub includes: Vec<String>,
+    pub uniforms: Vec<ShaderUniformBinding>,
+    pub uses: Vec<ShaderUse>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingShader {
+    pub shader: String,
+    pub stage: String,
+    pub objects: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShaderDumpIndex {
+    pub wallpaper_root: String,
+    pub out_dir: String,
+    pub shaders: Vec<DumpedShader>,
+    /// Headers pulled in through `#include`, relative to the output dir.
+    pub includes: Vec<String>,
+    pub missing: Vec<MissingShader>,
+}
+
+/// Where a resolved asset lands inside the dump dir. Workshop and global
+/// assets resolve to absolute paths, so those keep the part from `shaders/`.
+fn dump_rel_path(resolved: &str) -> String {
+    let path = resolved.replace('\\', "/");
+    if !path.starts_with('/') {
+        return path.trim_start_matches("./").to_string();
+    }
+    match path.rfind("/shaders/") {
+        Some(idx) => path[idx + 1..].to_string(),
+        None => format!(
+            "external/{}",
+            path.rsplit('/').next().unwrap_or("shader.glsl")
+        ),
+    }
+}
+
+/// `#include "file"` targets, in source order.
+fn shader_includes(src: &str) -> Vec<String> {
+    src.lines()
+        .filter_map(|line| line.trim().strip_prefix("#include"))
+        .filter_map(|rest| {
+            let name = rest
+                .trim()
+                .trim_matches(|c| c == '"' || c == '<' || c == '>');
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .collect()
+}
+
+fn include_candidates(name: &str) -> Vec<String> {
+    vec![
+        format!("shaders/{name}"),
+        format!("assets/shaders/{name}"),
+        name.to_string(),
+    ]
+}
+
+fn write_dumped(out_dir: &Path, rel: &str, bytes: &[u8]) -> Result<()> {
+    let path = out_dir.join(rel);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, bytes).with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// Writes every header reachable from `src` and returns their dump paths.
+fn dump_includes(
+    resolver: &AssetResolver,
+    src: &str,
+    out_dir: &Path,
+    seen: &mut BTreeSet<String>,
+    depth: usize,
+) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    if depth >= MAX_INCLUDE_DEPTH {
+        return Ok(out);
+    }
+    for name in shader_includes(src) {
+        let Some(asset) = resolver.resolve_first(&include_candidates(&name)) else {
+            warn_msg!(Msg::ShaderIncludeNotFound, name);
+            continue;
+        };
+        let rel = dump_rel_path(&asset.resolved_path);
+        out.push(rel.clone());
+        if !seen.insert(rel.clone()) {
+            continue;
+        }
+        write_dumped(out_dir, &rel, &asset.bytes)?;
+        let nested = String::from_utf8_lossy(&asset.bytes).to_string();
+        dump_includes(resolver, &nested, out_dir, seen, depth + 1)?;
+    }
+    Ok(out)
+}
+
+/// Resolves every vertex/fragment shader referenced by the scene graph and
+/// copies it (plus its includes) into `out_dir` with an `index.json`.
+pub fn dump_scene_shaders(root: &Path, out_dir: &Path) -> Result<ShaderDumpIndex> {
+    ensure_outside_library(out_dir, "shader dump")?;
+    let graph = build_scene_gpu_graph(root)?;
+    let resolver = AssetResolver::new(root)?;
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed creating {}", out_dir.display()))?;
+
+    let mut dumped = BTreeMap::<(String, String), DumpedShader>::new();
+    let mut missing = BTreeMap::<(String, String), BTreeSet<String>>::new();
+    let mut include_files = BTreeSet::<String>::new();
+
+    for node in &graph.effect_nodes {
+        for pass in &node.passes {
+            let shader = if pass.shader.trim().is_empty() {
+                node.pass_shader.clone()
+            } else {
+                pass.shader.clone()
+            };
+            if shader.trim().is_empty() {
+                continue;
+            }
+            for stage in ["vert", "frag"] {
+                let key = (shader.clone(), stage.to_string());
+                let shader_use = ShaderUse {
+                    object_id: node.object_id,
+                    object_name: node.object_name.clone(),
+                    effect_file: node.effect_file.clone(),
+                    pass_index: pass.pass_index,
+                    defines: pass.shader_defines.clone(),
+                    combos: pass.combos.clone(),
+                };
+                if let Some(entry) = dumped.get_mut(&key) {
+                    entry.uses.push(shader_use);
+                    continue;
+                }
+                let Some(ResolvedAsset {
+                    resolved_path,
+                    bytes,
+                    ..
+                }) = resolver.resolve_first(&shader_candidates(&shader, stage))
+                else {
+                    missing
+                        .entry(key)
+                        .or_default()
+                        .insert(node.object_name.clone());
+                    continue;
+                };
+                let file = dump_rel_path(&resolved_path);
+                write_dumped(out_dir, &file, &bytes)?;
+                let src = String::from_utf8_lossy(&bytes).to_string();
+                let includes = dump_includes(&resolver, &src, out_dir, &mut include_files, 0)?;
+                dumped.insert(
+                    key,
+                    DumpedShader {
+                        shader: shader.clone(),
+                        stage: stage.to_string(),
+                        resolved_path,
+                        file,
+                        includes,
+                        uniforms: parse_uniform_meta_from_shader(&src, stage),
+                        uses: vec![shader_use],
+                    },
+                );
+            }
+        }
+    }
+
+    let index = ShaderDumpIndex {
+        wallpaper_root: root.to_string_lossy().to_string(),
+        out_dir: out_dir.to_string_lossy().to_string(),
+        shaders: dumped.into_values().collect(),
+        includes: include_files.into_iter().collect(),
+        missing: missing
+            .into_iter()
+            .map(|((shader, stage), objects)| MissingShader {
+                shader,
+                stage,
+                objects: objects.into_iter().collect(),
+            })
+            .collect(),
+    };
+    let index_path = out_dir.join("index.json");
+    fs::write(&index_path, serde_json::to_vec_pretty(&index)?)
+        .with_context(|| format!("Failed writing {}", index_path.display()))?;
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_paths_and_includes() {
+        assert_eq!(
+            dump_rel_path("shaders/genericimage2.frag"),
+            "shaders/genericimage2.frag"
+        );
+        assert_eq!(
+            dump_rel_path("/steam/workshop/content/431960/123/shaders/effects/wave.frag"),
+            "shaders/effects/wave.frag"
+        );
+        assert_eq!(dump_rel_path("/tmp/odd/wave.vert"), "external/wave.vert");
+
+        let src = "#include \"common.h\"\n  #include \"common_blending.h\"\nvoid main() {}\n";
+        assert_eq!(shader_includes(src), vec!["common.h", "common_blending.h"]);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::asset_resolver::AssetResolver;
+use crate::command_runner::{CommandExt, shell_command_line};
+use crate::i18n::{Msg, warn_msg};
+use crate::scene_pkg::{FNV_OFFSET, fnv1a_update};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Track length when the entry has no duration of its own (stills, streams).
+const AMBIENT_DEFAULT_SECONDS: f64 = 60.0;
+/// `aloop` buffer in samples: far longer than any ambient loop.
+const ALOOP_SIZE: &str = "2e9";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SceneSoundMode {
+    Loop,
+    /// Wallpaper Engine picks a random file after a random pause; played
+    /// here as the file sequence looping back to back.
+    Random,
+    Single,
+}
+
+/// A `sound` object of scene.json: an ambient loop played under the scene.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneSound {
+    pub object_index: usize,
+    pub object_id: u64,
+    pub object_name: String,
+    pub files: Vec<String>,
+    pub volume: f32,
+    pub mode: SceneSoundMode,
+}
+
+/// `object` as a scene sound, or `None` when it has no sound files, is hidden,
+/// starts silent (scripts start those) or has no volume.
+pub(crate) fn parse_scene_sound(
+    object_index: usize,
+    object: &Value,
+    visible: bool,
+    user_values: &BTreeMap<String, Value>,
+) -> Option<SceneSound> {
+    let files = object
+        .get("sound")?
+        .as_array()?
+        .iter()
+        .filter_map(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if files.is_empty() || !visible {
+        return None;
+    }
+    if object.get("startsilent").and_then(|v| v.as_bool()) == Some(true) {
+        return None;
+    }
+    let volume = match object.get("volume") {
+        Some(v) => resolve_scalar(v, user_values)?,
+        None => 1.0,
+    };
+    if volume <= 0.0 {
+        return None;
+    }
+    let mode = match object.get("playbackmode").and_then(|v| v.as_str()) {
+        Some("random") => SceneSoundMode::Random,
+        Some("single") => SceneSoundMode::Single,
+        _ => SceneSoundMode::Loop,
+    };
+    Some(SceneSound {
+        object_index,
+        object_id: object.get("id").and_then(|v| v.as_u64()).unwrap_or(0),
+        object_name: object
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        files,
+        volume: volume.min(2.0),
+        mode,
+    })
+}
+
+/// Plain number, or `{"user": name, "value": default}` bound to a property.
+fn resolve_scalar(value: &Value, user_values: &BTreeMap<String, Value>) -> Option<f32> {
+    if let Some(v) = value.as_f64() {
+        return Some(v as f32);
+    }
+    let obj = value.as_object()?;
+    obj.get("user")
+        .and_then(|u| u.as_str())
+        .and_then(|name| user_values.get(name))
+        .and_then(|v| v.as_f64())
+        .or_else(|| obj.get("value").and_then(|v| v.as_f64()))
+        .map(|v| v as f32)
+}
+
+/// ffmpeg graph mixing the sounds into `[a]`. Input numbers follow the order
+/// of `inputs`: one `(file count, volume, mode)` per sound. Files of a sound
+/// play in sequence; loop and random sequences repeat for the whole track.
+pub fn ambient_mix_filter(inputs: &[(usize, f32, SceneSoundMode)]) -> String {
+    let mut parts = Vec::new();
+    let mut next_input = 0;
+    for (sound, (files, volume, mode)) in inputs.iter().enumerate() {
+        let mut chain = String::new();
+        for file in 0..*files {
+            let input = next_input + file;
+            parts.push(format!(
+                "[{}:a]aformat=sample_rates=48000:channel_layouts=stereo[i{}]",
+                input, input
+            ));
+            chain.push_str(&format!("[i{}]", input));
+        }
+        next_input += files;
+        if *files > 1 {
+            chain.push_str(&format!("concat=n={}:v=0:a=1,", files));
+        }
+        if *mode != SceneSoundMode::Single {
+            chain.push_str(&format!("aloop=loop=-1:size={},", ALOOP_SIZE));
+        }
+        chain.push_str(&format!("volume={:.3}[s{}]", volume, sound));
+        parts.push(chain);
+    }
+    let labels = (0..inputs.len())
+        .map(|i| format!("[s{}]", i))
+        .collect::<String>();
+    if inputs.len() > 1 {
+        parts.push(format!(
+            "{}amix=inputs={}:duration=longest:normalize=0,apad[a]",
+            labels,
+            inputs.len()
+        ));
+    } else {
+        parts.push(format!("{}apad[a]", labels));
+    }
+    parts.join(";")
+}
+
+/// mpv option that plays `track` with the wallpaper, so the mute toggle and
+/// the per-monitor IPC control it like the entry's own audio.
+pub fn ambient_sound_option(track: &Path) -> String {
+    format!("audio-file={}", track.display())
+}
+
+/// Extracts the sound files of `sounds` and mixes them into one track of
+/// `seconds` (the entry's loop length, so both wrap together). `None` when no
+/// sound file could be found.
+pub fn build_ambient_sound_track(
+    root: &Path,
+    sounds: &[SceneSound],
+    session_dir: &Path,
+    seconds: Option<f64>,
+    dry_run: bool,
+) -> Result<Option<PathBuf>> {
+    if sounds.is_empty() {
+        return Ok(None);
+    }
+    let resolver = AssetResolver::new(root)?;
+    let sounds_dir = session_dir.join("sounds");
+    let seconds = seconds
+        .filter(|s| *s > 0.5)
+        .unwrap_or(AMBIENT_DEFAULT_SECONDS);
+
+    let mut hash = FNV_OFFSET;
+    fnv1a_update(&mut hash, &seconds.to_le_bytes());
+    let mut files = Vec::new();
+    let mut inputs = Vec::new();
+    for sound in sounds {
+        let mut found = 0;
+        for file in &sound.files {
+            let Some(asset) = resolver.resolve(file) else {
+                warn_msg!(Msg::SceneSoundMissing, file, sound.object_name);
+                continue;
+            };
+            let name = asset.resolved_path.replace(['/', '\\'], "_");
+            let path = sounds_dir.join(&name);
+            if !dry_run && fs::read(&path).ok().as_deref() != Some(asset.bytes.as_slice()) {
+                fs::create_dir_all(&sounds_dir)
+                    .with_context(|| format!("Failed creating {}", sounds_dir.display()))?;
+                fs::write(&path, &asset.bytes)
+                    .with_context(|| format!("Failed writing {}", path.display()))?;
+            }
+            fnv1a_update(&mut hash, name.as_bytes());
+            fnv1a_update(&mut hash, &asset.bytes);
+            files.push(path);
+            found += 1;
+        }
+        if found > 0 {
+            fnv1a_update(&mut hash, &sound.volume.to_le_bytes());
+            fnv1a_update(&mut hash, format!("{:?}", sound.mode).as_bytes());
+            inputs.push((found, sound.volume, sound.mode));
+        }
+    }
+    if inputs.is_empty() {
+        return Ok(None);
+    }
+
+    let out = sounds_dir.join(format!("ambient-{:016x}.ogg", hash));
+    if out.is_file() {
+        return Ok(Some(out));
+    }
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y"]);
+    for file in &files {
+        cmd.arg("-i").arg(file);
+    }
+    cmd.arg("-filter_complex")
+        .arg(ambient_mix_filter(&inputs))
+        .args(["-map", "[a]", "-t"])
+        .arg(format!("{:.3}", seconds))
+        .args(["-c:a", "libopus", "-b:a", "128k"])
+        .arg(&out);
+    if dry_run {
+        eprintln!("[dry-run] {}", shell_command_line(&cmd));
+        return Ok(Some(out));
+    }
+    let output = cmd.run_output().context("Failed running ffmpeg")?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&out);
+        bail!(
+            "ffmpeg failed mixing scene sounds: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    eprintln!(
+        "[ok] ambient sound: {} sound object(s) mixed into {}",
+        inputs.len(),
+        out.display()
+    );
+    Ok(Some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sound_objects_are_parsed_and_mixed_with_their_volume() {
+        let user_values = BTreeMap::from([("rainvolume".to_string(), json!(0.25))]);
+        let rain = json!({
+            "id": 7,
+            "name": "Rain",
+            "sound": ["sounds/rain_a.mp3", "sounds/rain_b.mp3"],
+            "volume": {"user": "rainvolume", "value": 1.0},
+            "playbackmode": "loop"
+        });
+        let sound = parse_scene_sound(3, &rain, true, &user_values).unwrap();
+        assert_eq!(sound.object_id, 7);
+        assert_eq!(sound.files.len(), 2);
+        assert_eq!(sound.volume, 0.25);
+        assert_eq!(sound.mode, SceneSoundMode::Loop);
+
+        assert!(parse_scene_sound(3, &rain, false, &user_values).is_none());
+        let silent = json!({"sound": ["a.mp3"], "startsilent": true});
+        assert!(parse_scene_sound(0, &silent, true, &user_values).is_none());
+        let muted = json!({"sound": ["a.mp3"], "volume": 0.0});
+        assert!(parse_scene_sound(0, &muted, true, &user_values).is_none());
+        assert!(parse_scene_sound(0, &json!({"image": "x.json"}), true, &user_values).is_none());
+
+        assert_eq!(
+            ambient_mix_filter(&[
+                (2, 0.25, SceneSoundMode::Loop),
+                (1, 1.0, SceneSoundMode::Single)
+            ]),
+            "[0:a]aformat=sample_rates=48000:channel_layouts=stereo[i0];\
+             [1:a]aformat=sample_rates=48000:channel_layouts=stereo[i1];\
+             [i0][i1]concat=n=2:v=0:a=1,aloop=loop=-1:size=2e9,volume=0.250[s0];\
+             [2:a]aformat=sample_rates=48000:channel_layouts=stereo[i2];\
+             [i2]volume=1.000[s1];\
+             [s0][s1]amix=inputs=2:duration=longest:normalize=0,apad[a]"
+        );
+    }
+}
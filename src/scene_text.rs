@@ -1,10 +1,19 @@
+use crate::app_paths::filter_safe_path;
+use crate::command_runner::CommandExt;
+use crate::display_orientation::{CanvasCrop, OutputFraming};
+use crate::i18n::{Msg, warn_msg};
+use crate::layer_overrides::{apply_layer_overrides, load_layer_override};
+use crate::playback::mpv_ipc_command_at;
 use crate::scene_pkg::{
-    default_scene_cache_root, extract_entry_to_cache, find_entry, parse_scene_pkg, read_entry_bytes,
+    ScenePkg, default_scene_cache_root, extract_entry_to_cache, find_entry, parse_scene_pkg,
+    read_entry_bytes,
 };
 use anyhow::{Context, Result};
-use chrono::{Local, Timelike};
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -15,13 +24,129 @@ use std::time::Duration;
 struct RefreshEntry {
     file_path: String,
     object: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timezone: Option<String>,
+    /// Placement on the OSD; only set for `osd` specs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<TextLayout>,
+}
+
+/// IANA name (`Europe/Madrid`) to timezone.
+pub fn parse_timezone(raw: &str) -> Result<Tz, String> {
+    raw.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone '{}', expected e.g. Europe/Madrid", raw))
+}
+
+/// Property override key pinning one text layer (by object id) to a timezone.
+pub const LAYER_TIMEZONE_PREFIX: &str = "timezone.";
+
+/// Timezone of clock/date text: per text layer, else the wallpaper's, else
+/// local time.
+#[derive(Debug, Clone, Default)]
+pub struct TextTimezones {
+    pub wallpaper: Option<Tz>,
+    pub layers: BTreeMap<u64, Tz>,
+}
+
+impl TextTimezones {
+    /// Reads `timezone.<object id>` keys of the monitor's property overrides;
+    /// invalid ones are skipped with a warning.
+    pub fn with_overrides(wallpaper: Option<Tz>, overrides: &BTreeMap<String, Value>) -> Self {
+        let mut layers = BTreeMap::new();
+        for (key, value) in overrides {
+            let Some(id) = key.strip_prefix(LAYER_TIMEZONE_PREFIX) else {
+                continue;
+            };
+            match (id.parse::<u64>(), value.as_str().map(parse_timezone)) {
+                (Ok(id), Some(Ok(tz))) => {
+                    layers.insert(id, tz);
+                }
+                _ => warn_msg!(Msg::TextTimezoneIgnored, key, value),
+            }
+        }
+        Self { wallpaper, layers }
+    }
+
+    fn for_object(&self, object: &Value) -> Option<Tz> {
+        self.layers
+            .get(&object_id(object))
+            .copied()
+            .or(self.wallpaper)
+    }
+}
+
+/// Wall-clock time shown by a text layer pinned to `tz` (local time without).
+fn text_now(tz: Option<Tz>) -> DateTime<FixedOffset> {
+    match tz {
+        Some(tz) => Utc::now().with_timezone(&tz).fixed_offset(),
+        None => Local::now().fixed_offset(),
+    }
+}
+
+fn entry_timezone(entry: &RefreshEntry) -> Option<Tz> {
+    entry
+        .timezone
+        .as_deref()
+        .and_then(|name| parse_timezone(name).ok())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct RefreshSpec {
     entries: Vec<RefreshEntry>,
+    /// mpv IPC socket the layers are drawn on with `osd-overlay`, instead of
+    /// files re-read by drawtext.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    osd_socket: Option<String>,
+}
+
+/// Where and how big a text layer is drawn, as fractions of the output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TextLayout {
+    x_ratio: f32,
+    y_ratio: f32,
+    size_ratio: f32,
+    rgb: [u8; 3],
+    h_align: String,
+    v_align: String,
+    #[serde(default)]
+    style: TextStyle,
 }
 
+/// Shadow, outline and glow of a text layer in font-size units, so they keep
+/// their look at any output resolution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TextStyle {
+    shadow: f32,
+    shadow_alpha: f32,
+    outline: f32,
+    outline_rgb: [u8; 3],
+    glow: f32,
+    glow_rgb: [u8; 3],
+    glow_alpha: f32,
+}
+
+impl Default for TextStyle {
+    /// The small dark shadow and hairline outline used before scenes could
+    /// set their own.
+    fn default() -> Self {
+        Self {
+            shadow: 0.04,
+            shadow_alpha: 0.6,
+            outline: 0.02,
+            outline_rgb: [0, 0, 0],
+            glow: 0.0,
+            glow_rgb: [255, 255, 255],
+            glow_alpha: 0.0,
+        }
+    }
+}
+
+/// Overlay id kwe owns on the mpv OSD; other ids are left alone.
+const OSD_OVERLAY_ID: u32 = 4260;
+/// Virtual OSD canvas; libass scales it to the real output.
+const OSD_RES: (u32, u32) = (1920, 1080);
+
 fn pick_pkg_path(root: &Path) -> Option<PathBuf> {
     if root.join("scene.pkg").is_file() {
         Some(root.join("scene.pkg"))
@@ -42,6 +167,23 @@ fn text_layers_dir(root: &Path) -> PathBuf {
     default_scene_cache_root(&cache_key_for_root(root)).join("text-layers")
 }
 
+fn load_scene_json(root: &Path) -> Result<Option<(ScenePkg, Value)>> {
+    let Some(pkg_path) = pick_pkg_path(root) else {
+        return Ok(None);
+    };
+    let pkg = parse_scene_pkg(&pkg_path)?;
+    let Some(scene_entry) =
+        find_entry(&pkg, "scene.json").or_else(|| find_entry(&pkg, "gifscene.json"))
+    else {
+        return Ok(None);
+    };
+    let mut scene_json = serde_json::from_slice(&read_entry_bytes(&pkg, &scene_entry)?)?;
+    if let Some(layer_override) = load_layer_override(root) {
+        apply_layer_overrides(&mut scene_json, &layer_override);
+    }
+    Ok(Some((pkg, scene_json)))
+}
+
 fn runtime_spec_path(root: &Path) -> PathBuf {
     text_layers_dir(root).join("runtime_spec.json")
 }
@@ -50,6 +192,10 @@ fn updater_pid_path(root: &Path) -> PathBuf {
     text_layers_dir(root).join("updater.pid")
 }
 
+pub fn is_text_object(object: &Value) -> bool {
+    object.get("text").is_some() || object.get("font").is_some()
+}
+
 fn is_dynamic_text_object(object: &Value) -> bool {
     let name = object
         .get("name")
@@ -213,7 +359,9 @@ fn write_text_layer_file(text_dir: &Path, object: &Value, text: &str) -> Option<
     if fs::write(&path, text).is_err() {
         return None;
     }
-    Some(escape_filter_value(&path.to_string_lossy()))
+    Some(escape_filter_value(
+        &filter_safe_path(&path).to_string_lossy(),
+    ))
 }
 
 fn read_xy_value(v: &Value) -> Option<(f32, f32)> {
@@ -265,6 +413,90 @@ fn extract_transform_effect(object: &Value) -> (f32, f32, f32) {
     (offset_x, offset_y, scale_y)
 }
 
+/// A numeric shader constant, plain or wrapped in `{"value": ..}`.
+fn csv_f32(csv: &serde_json::Map<String, Value>, keys: &[&str]) -> Option<f32> {
+    keys.iter().find_map(|key| {
+        let value = csv.get(*key)?;
+        let value = value.get("value").unwrap_or(value);
+        match value {
+            Value::Number(n) => n.as_f64().map(|v| v as f32),
+            Value::String(s) => s.trim().parse::<f32>().ok(),
+            _ => None,
+        }
+    })
+}
+
+fn csv_rgb(csv: &serde_json::Map<String, Value>, keys: &[&str]) -> Option<[u8; 3]> {
+    let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    keys.iter().find_map(|key| {
+        let value = csv.get(*key)?;
+        let (r, g, b) = value
+            .get("value")
+            .unwrap_or(value)
+            .as_str()
+            .and_then(parse_vec3)?;
+        Some([to_byte(r), to_byte(g), to_byte(b)])
+    })
+}
+
+/// Shadow, outline and glow from the object's effect chain. Sizes in the
+/// shader constants are scene pixels, the same unit as `pointsize`.
+fn extract_text_style(object: &Value, point_size: f32) -> TextStyle {
+    let mut style = TextStyle::default();
+    let Some(effects) = object.get("effects").and_then(|v| v.as_array()) else {
+        return style;
+    };
+    let em = |px: f32| (px.abs() / point_size.max(1.0)).min(1.0);
+
+    for effect in effects {
+        if !prop_bool(effect.get("visible"), true) {
+            continue;
+        }
+        let file = effect
+            .get("file")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let csv = effect
+            .get("passes")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|pass| pass.get("constantshadervalues")?.as_object())
+            .fold(serde_json::Map::new(), |mut acc, csv| {
+                acc.extend(csv.clone());
+                acc
+            });
+        let alpha = csv_f32(&csv, &["alpha", "opacity", "strength", "intensity"]);
+
+        if file.contains("glow") {
+            let radius = csv_f32(&csv, &["radius", "blurradius", "size", "blur"]).unwrap_or(8.0);
+            style.glow = em(radius).max(0.02);
+            style.glow_rgb = csv_rgb(&csv, &["color", "glowcolor"]).unwrap_or(style.glow_rgb);
+            style.glow_alpha = alpha.unwrap_or(0.8).clamp(0.0, 1.0);
+        } else if file.contains("shadow") {
+            let offset = csv
+                .get("offset")
+                .and_then(read_xy_value)
+                .map(|(x, y)| x.hypot(y))
+                .or_else(|| csv_f32(&csv, &["distance", "offset"]))
+                .unwrap_or(3.0);
+            style.shadow = em(offset);
+            style.shadow_alpha = alpha.unwrap_or(style.shadow_alpha).clamp(0.0, 1.0);
+        } else if ["outline", "stroke", "border"]
+            .iter()
+            .any(|kind| file.contains(kind))
+        {
+            let width =
+                csv_f32(&csv, &["size", "width", "thickness", "outlinesize"]).unwrap_or(2.0);
+            style.outline = em(width);
+            style.outline_rgb =
+                csv_rgb(&csv, &["color", "outlinecolor"]).unwrap_or(style.outline_rgb);
+        }
+    }
+    style
+}
+
 fn prop_bool(v: Option<&Value>, default: bool) -> bool {
     match v {
         Some(Value::Bool(b)) => *b,
@@ -288,8 +520,8 @@ fn build_day_or_date_text(
     name: &str,
     script: &str,
     props: Option<&serde_json::Map<String, Value>>,
+    now: DateTime<FixedOffset>,
 ) -> Option<String> {
-    let now = Local::now();
     let script_lower = script.to_ascii_lowercase();
     let show_day = prop_bool(props.and_then(|m| m.get("showDay")), name.contains("day"));
     let day_format = props
@@ -354,8 +586,10 @@ fn build_day_or_date_text(
     Some(out.split_whitespace().collect::<Vec<_>>().join(" "))
 }
 
-fn build_clock_text(props: Option<&serde_json::Map<String, Value>>) -> Option<String> {
-    let now = Local::now();
+fn build_clock_text(
+    props: Option<&serde_json::Map<String, Value>>,
+    now: DateTime<FixedOffset>,
+) -> Option<String> {
     let use_24h = prop_bool(props.and_then(|m| m.get("use24hFormat")), true);
     let show_seconds = prop_bool(props.and_then(|m| m.get("showSeconds")), false);
     let delimiter = prop_str(props.and_then(|m| m.get("delimiter"))).unwrap_or(":");
@@ -380,8 +614,9 @@ fn build_clock_text(props: Option<&serde_json::Map<String, Value>>) -> Option<St
     Some(format!("{} {}", base, meridiem))
 }
 
-fn infer_text_expr(object: &Value) -> Option<String> {
-    let now = Local::now();
+/// Text a layer shows at `now`. chrono formats day and month names in
+/// English whatever the system locale, as Wallpaper Engine scenes expect.
+fn infer_text_expr(object: &Value, now: DateTime<FixedOffset>) -> Option<String> {
     let name = object
         .get("name")
         .and_then(|v| v.as_str())
@@ -411,7 +646,7 @@ fn infer_text_expr(object: &Value) -> Option<String> {
         .and_then(|v| v.as_object());
 
     if script.contains("gethours") && script.contains("getminutes") {
-        if let Some(clock) = build_clock_text(props) {
+        if let Some(clock) = build_clock_text(props, now) {
             return Some(clock);
         }
         return Some(now.format("%H:%M").to_string());
@@ -424,7 +659,7 @@ fn infer_text_expr(object: &Value) -> Option<String> {
         || raw_value.eq_ignore_ascii_case("day")
         || raw_value.eq_ignore_ascii_case("<date>")
     {
-        if let Some(v) = build_day_or_date_text(&name, &script, props) {
+        if let Some(v) = build_day_or_date_text(&name, &script, props, now) {
             return Some(v.trim().to_string());
         }
     }
@@ -447,7 +682,7 @@ fn resolve_fontfile(
     object: &Value,
     pkg: &crate::scene_pkg::ScenePkg,
     font_cache_dir: &Path,
-) -> Option<String> {
+) -> Option<PathBuf> {
     let font_name = object.get("font").and_then(|v| v.as_str())?;
     let debug_font = std::env::var("KWE_DEBUG_TEXT_FONT").ok().as_deref() == Some("1");
     if font_name.eq_ignore_ascii_case("systemfont_arial") {
@@ -480,23 +715,117 @@ fn resolve_fontfile(
     if debug_font {
         eprintln!("[dbg-font] using fontfile {}", out.display());
     }
-    Some(escape_filter_value(&out.to_string_lossy()))
+    Some(out)
 }
 
-fn build_drawtext_for_object(
-    object: &Value,
-    scene_w: f32,
-    scene_h: f32,
-    pkg: &crate::scene_pkg::ScenePkg,
-    font_cache_dir: &Path,
-    text_cache_dir: &Path,
-) -> Option<String> {
-    if !visible_enabled(object) {
+/// Code points drawtext needs glyphs for beyond what every font ships (ASCII).
+fn required_codepoints(text: &str) -> Vec<u32> {
+    let mut cps: Vec<u32> = text
+        .chars()
+        .filter(|c| !c.is_ascii() && !c.is_whitespace() && !c.is_control())
+        .map(|c| c as u32)
+        .collect();
+    cps.sort_unstable();
+    cps.dedup();
+    cps
+}
+
+fn is_emoji_codepoint(cp: u32) -> bool {
+    (0x1F000..=0x1FAFF).contains(&cp) || (0x2600..=0x27BF).contains(&cp)
+}
+
+/// Parses a fontconfig charset (`%{charset}`): space separated hex code
+/// points or `first-last` ranges.
+fn parse_fc_charset(raw: &str) -> Vec<(u32, u32)> {
+    raw.split_whitespace()
+        .filter_map(|token| {
+            let (a, b) = token.split_once('-').unwrap_or((token, token));
+            Some((
+                u32::from_str_radix(a, 16).ok()?,
+                u32::from_str_radix(b, 16).ok()?,
+            ))
+        })
+        .collect()
+}
+
+fn charset_covers(ranges: &[(u32, u32)], cps: &[u32]) -> bool {
+    cps.iter()
+        .all(|cp| ranges.iter().any(|(a, b)| (*a..=*b).contains(cp)))
+}
+
+fn font_charset(fontfile: &Path) -> Option<Vec<(u32, u32)>> {
+    let output = Command::new("fc-query")
+        .arg("--format=%{charset}")
+        .arg(fontfile)
+        .run_output()
+        .ok()?;
+    if !output.status.success() {
         return None;
     }
+    Some(parse_fc_charset(&String::from_utf8_lossy(&output.stdout)))
+}
 
-    let text_expr = infer_text_expr(object)?;
-    let text_file = write_text_layer_file(text_cache_dir, object, &text_expr);
+/// Asks fontconfig for one system font covering `cps` (drawtext cannot fall
+/// back per glyph, so a single font has to cover the whole layer).
+fn fontconfig_fallback(cps: &[u32]) -> Option<(PathBuf, bool)> {
+    let family = if cps.iter().any(|cp| is_emoji_codepoint(*cp)) {
+        "emoji"
+    } else {
+        "sans-serif"
+    };
+    let charset = cps
+        .iter()
+        .map(|cp| format!("{:x}", cp))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let output = Command::new("fc-match")
+        .arg("--format=%{file}\n%{charset}")
+        .arg(format!("{}:charset={}", family, charset))
+        .run_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (file, ranges) = stdout.split_once('\n').unwrap_or((&stdout, ""));
+    let file = file.trim();
+    if file.is_empty() {
+        return None;
+    }
+    Some((
+        PathBuf::from(file),
+        charset_covers(&parse_fc_charset(ranges), cps),
+    ))
+}
+
+/// Keeps the scene-packaged font when it has every glyph of `text`; otherwise
+/// substitutes a fontconfig match so CJK/emoji text does not render as boxes.
+fn pick_text_font(packaged: Option<PathBuf>, text: &str) -> Option<PathBuf> {
+    let cps = required_codepoints(text);
+    if cps.is_empty() {
+        return packaged;
+    }
+    if let Some(path) = &packaged
+        && font_charset(path).is_some_and(|ranges| charset_covers(&ranges, &cps))
+    {
+        return packaged;
+    }
+    match fontconfig_fallback(&cps) {
+        Some((fallback, covers)) => {
+            if !covers {
+                warn_msg!(Msg::FontFallback, text, fallback.display());
+            }
+            Some(fallback)
+        }
+        None => packaged,
+    }
+}
+
+fn text_layout(
+    object: &Value,
+    (scene_w, scene_h): (f32, f32),
+    canvas: Option<&CanvasCrop>,
+) -> Option<TextLayout> {
     let origin = object
         .get("origin")
         .and_then(|v| v.as_str())
@@ -509,6 +838,7 @@ fn build_drawtext_for_object(
         .and_then(|v| v.as_f64())
         .unwrap_or(30.0)
         .max(8.0);
+    let style = extract_text_style(object, point_size as f32);
     let scale_y = object
         .get("scale")
         .and_then(|v| v.as_str())
@@ -524,35 +854,69 @@ fn build_drawtext_for_object(
         .clamp(0.5, 6.0);
     let effective_scale_y = (scale_y as f64) * (effect_scale_y as f64);
     let point_size = point_size * effective_scale_y * user_scale;
-    let (color, _r, _g, _b) = parse_color(object);
+    let (_color, r, g, b) = parse_color(object);
+    let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
 
     let x_ratio = origin_x / scene_w;
     // Wallpaper Engine scene coordinates are bottom-origin in many scene packs.
     // drawtext y uses top-origin, so invert Y to keep text in expected screen region.
     let y_ratio = 1.0 - (origin_y / scene_h);
-    let size_ratio = (point_size as f32 / scene_h).clamp(0.004, 0.2);
-    let font_opt = resolve_fontfile(object, pkg, font_cache_dir)
-        .map(|fontfile| format!(":fontfile={}", fontfile))
+    let mut size_ratio = point_size as f32 / scene_h;
+    let (x_ratio, y_ratio) = match canvas {
+        Some(canvas) => {
+            size_ratio = canvas.map_height_ratio(size_ratio);
+            canvas.map_ratio(x_ratio, y_ratio)
+        }
+        None => (x_ratio, y_ratio),
+    };
+    let align = |key: &str| {
+        object
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or("center")
+            .to_ascii_lowercase()
+    };
+    Some(TextLayout {
+        x_ratio,
+        y_ratio,
+        size_ratio: size_ratio.clamp(0.004, 0.2),
+        rgb: [to_byte(r), to_byte(g), to_byte(b)],
+        h_align: align("horizontalalign"),
+        v_align: align("verticalalign"),
+        style,
+    })
+}
+
+fn build_drawtext_for_object(
+    object: &Value,
+    scene: (f32, f32),
+    now: DateTime<FixedOffset>,
+    pkg: &crate::scene_pkg::ScenePkg,
+    font_cache_dir: &Path,
+    text_cache_dir: &Path,
+    canvas: Option<&CanvasCrop>,
+) -> Option<String> {
+    if !visible_enabled(object) {
+        return None;
+    }
+
+    let text_expr = infer_text_expr(object, now)?;
+    let text_file = write_text_layer_file(text_cache_dir, object, &text_expr);
+    let layout = text_layout(object, scene, canvas)?;
+    let (color, _r, _g, _b) = parse_color(object);
+    let fontfile = pick_text_font(resolve_fontfile(object, pkg, font_cache_dir), &text_expr);
+    let font_opt = fontfile
+        .map(|f| format!(":fontfile={}", escape_filter_value(&f.to_string_lossy())))
         .unwrap_or_default();
-    let h_align = object
-        .get("horizontalalign")
-        .and_then(|v| v.as_str())
-        .unwrap_or("center")
-        .to_ascii_lowercase();
-    let v_align = object
-        .get("verticalalign")
-        .and_then(|v| v.as_str())
-        .unwrap_or("center")
-        .to_ascii_lowercase();
 
-    let x_base = format!("(w*{:.6})", x_ratio);
-    let y_base = format!("(h*{:.6})", y_ratio);
-    let x_expr = match h_align.as_str() {
+    let x_base = format!("(w*{:.6})", layout.x_ratio);
+    let y_base = format!("(h*{:.6})", layout.y_ratio);
+    let x_expr = match layout.h_align.as_str() {
         "left" => x_base.clone(),
         "right" => format!("{}-text_w", x_base),
         _ => format!("{}-(text_w/2)", x_base),
     };
-    let y_expr = match v_align.as_str() {
+    let y_expr = match layout.v_align.as_str() {
         "top" => y_base.clone(),
         "bottom" => format!("{}-text_h", y_base),
         _ => format!("{}-(text_h/2)", y_base),
@@ -563,30 +927,140 @@ fn build_drawtext_for_object(
         format!("text={}", escape_static_text(&text_expr))
     };
 
-    Some(format!(
-        "drawtext={}:fontcolor={}:fontsize=h*{:.5}:x={}:y={}:borderw=0:shadowx=1:shadowy=1:shadowcolor=0x00000099{font}",
+    // drawtext takes shadow and border widths as plain pixels, so they are
+    // sized against the frame the filter runs on (the scene or its canvas).
+    let frame_h = canvas.map(|c| c.out_h as f32).unwrap_or(scene.1);
+    let style = &layout.style;
+    let px = |em: f32| (em * layout.size_ratio * frame_h).round() as u32;
+    let hex = |[r, g, b]: [u8; 3], alpha: f32| {
+        format!(
+            "0x{:02X}{:02X}{:02X}{:02X}",
+            r,
+            g,
+            b,
+            (alpha.clamp(0.0, 1.0) * 255.0).round() as u8
+        )
+    };
+    let base = format!(
+        "drawtext={}:fontsize=h*{:.5}:x={}:y={}{font}",
         text_input,
-        color,
-        size_ratio,
+        layout.size_ratio,
         x_expr,
         y_expr,
         font = font_opt
+    );
+    let shadow = if style.shadow > 0.0 {
+        px(style.shadow).max(1)
+    } else {
+        0
+    };
+    let text = format!(
+        "{base}:fontcolor={}:borderw={}:bordercolor={}:shadowx={shadow}:shadowy={shadow}:shadowcolor={}",
+        color,
+        px(style.outline),
+        hex(style.outline_rgb, 1.0),
+        hex([0, 0, 0], style.shadow_alpha),
+    );
+    // No blur in drawtext: the glow is a wide translucent border drawn first.
+    let glow = px(style.glow);
+    if glow == 0 || style.glow_alpha <= 0.0 {
+        return Some(text);
+    }
+    Some(format!(
+        "{base}:fontcolor={}:borderw={}:bordercolor={},{}",
+        hex(style.glow_rgb, style.glow_alpha * 0.5),
+        glow,
+        hex(style.glow_rgb, style.glow_alpha * 0.35),
+        text
     ))
 }
 
-pub fn build_scene_drawtext_filter(root: &Path, max_layers: usize) -> Result<Option<String>> {
-    let Some(pkg_path) = pick_pkg_path(root) else {
-        return Ok(None);
+/// Text with ASS override braces and line breaks escaped.
+fn escape_ass_text(text: &str) -> String {
+    text.replace('\\', "\\\u{200b}")
+        .replace('{', "\\{")
+        .replace('}', "\\}")
+        .replace('\n', "\\N")
+}
+
+/// One ASS event line for `text` placed by `layout` on the [`OSD_RES`] canvas.
+fn ass_event(layout: &TextLayout, text: &str) -> String {
+    let column = match layout.h_align.as_str() {
+        "left" => 1,
+        "right" => 3,
+        _ => 2,
     };
-    let pkg = parse_scene_pkg(&pkg_path)?;
-    let Some(scene_entry) =
-        find_entry(&pkg, "scene.json").or_else(|| find_entry(&pkg, "gifscene.json"))
-    else {
-        return Ok(None);
+    // ASS numpad alignment: 1-3 bottom row, 4-6 middle, 7-9 top.
+    let row = match layout.v_align.as_str() {
+        "top" => 6,
+        "bottom" => 0,
+        _ => 3,
+    };
+    let [r, g, b] = layout.rgb;
+    let style = &layout.style;
+    let font_size = layout.size_ratio * OSD_RES.1 as f32;
+    let bgr = |[r, g, b]: [u8; 3]| format!("&H{:02X}{:02X}{:02X}&", b, g, r);
+    let ass_alpha = |alpha: f32| {
+        format!(
+            "&H{:02X}&",
+            ((1.0 - alpha.clamp(0.0, 1.0)) * 255.0).round() as u8
+        )
     };
+    let head = format!(
+        "\\an{}\\pos({:.1},{:.1})\\fs{:.1}",
+        row + column,
+        layout.x_ratio * OSD_RES.0 as f32,
+        layout.y_ratio * OSD_RES.1 as f32,
+        font_size,
+    );
+    let text = escape_ass_text(text);
+    let main = format!(
+        "{{{head}\\c{}\\bord{:.1}\\3c{}\\shad{:.1}\\4a{}}}{text}",
+        bgr([r, g, b]),
+        style.outline * font_size,
+        bgr(style.outline_rgb),
+        style.shadow * font_size,
+        ass_alpha(style.shadow_alpha),
+    );
+    if style.glow <= 0.0 || style.glow_alpha <= 0.0 {
+        return main;
+    }
+    // libass blurs the border with a real gaussian; the fill of this copy is
+    // transparent so only the halo shows under the text event drawn next.
+    let glow = style.glow * font_size;
+    format!(
+        "{{{head}\\1a&HFF&\\bord{:.1}\\blur{:.1}\\3c{}\\3a{}\\shad0}}{text}\n{main}",
+        glow,
+        glow,
+        bgr(style.glow_rgb),
+        ass_alpha(style.glow_alpha),
+    )
+}
+
+fn osd_overlay_command(events: &[String]) -> Value {
+    serde_json::json!({
+        "name": "osd-overlay",
+        "id": OSD_OVERLAY_ID,
+        "format": "ass-events",
+        "data": events.join("\n"),
+        "res_x": OSD_RES.0,
+        "res_y": OSD_RES.1,
+    })
+}
 
-    let scene_json: Value = serde_json::from_slice(&read_entry_bytes(&pkg, &scene_entry)?)?;
+/// With a `framing` other than `Full` the layers are placed on the cropped
+/// canvas (portrait proxy or `--crop-region`) instead of the full scene.
+pub fn build_scene_drawtext_filter(
+    root: &Path,
+    max_layers: usize,
+    framing: OutputFraming,
+    timezones: &TextTimezones,
+) -> Result<Option<String>> {
+    let Some((pkg, scene_json)) = load_scene_json(root)? else {
+        return Ok(None);
+    };
     let (scene_w, scene_h) = parse_scene_size(&scene_json);
+    let canvas = framing.canvas(scene_w as u32, scene_h as u32);
     let cache_key = cache_key_for_root(root);
     let font_cache_dir = default_scene_cache_root(&cache_key).join("text-fonts");
     let text_cache_dir = default_scene_cache_root(&cache_key).join("text-layers");
@@ -595,17 +1069,18 @@ pub fn build_scene_drawtext_filter(root: &Path, max_layers: usize) -> Result<Opt
     let mut refresh_entries = Vec::new();
     if let Some(objects) = scene_json.get("objects").and_then(|v| v.as_array()) {
         for object in objects {
-            let has_text = object.get("text").is_some() || object.get("font").is_some();
-            if !has_text {
+            if !is_text_object(object) {
                 continue;
             }
+            let timezone = timezones.for_object(object);
             if let Some(layer) = build_drawtext_for_object(
                 object,
-                scene_w,
-                scene_h,
+                (scene_w, scene_h),
+                text_now(timezone),
                 &pkg,
                 &font_cache_dir,
                 &text_cache_dir,
+                canvas.as_ref(),
             ) {
                 layers.push(layer);
                 if is_dynamic_text_object(object) {
@@ -613,6 +1088,8 @@ pub fn build_scene_drawtext_filter(root: &Path, max_layers: usize) -> Result<Opt
                     refresh_entries.push(RefreshEntry {
                         file_path: file_path.to_string_lossy().to_string(),
                         object: object.clone(),
+                        timezone: timezone.map(|tz| tz.name().to_string()),
+                        layout: None,
                     });
                 }
                 if layers.len() >= max_layers.max(1) {
@@ -629,39 +1106,252 @@ pub fn build_scene_drawtext_filter(root: &Path, max_layers: usize) -> Result<Opt
     if !refresh_entries.is_empty() && fs::create_dir_all(&text_cache_dir).is_ok() {
         let spec = RefreshSpec {
             entries: refresh_entries,
+            osd_socket: None,
         };
         let spec_json = serde_json::to_vec_pretty(&spec)?;
         fs::write(runtime_spec_path(root), spec_json).ok();
+    } else {
+        // A spec left by an earlier OSD run would draw the text twice.
+        fs::remove_file(runtime_spec_path(root)).ok();
     }
 
     Ok(Some(format!("vf={}", layers.join(","))))
 }
 
-pub fn run_text_refresh(spec_path: &Path) -> Result<usize> {
+/// OSD counterpart of [`build_scene_drawtext_filter`]: writes a refresh spec
+/// that draws every text layer (static ones too) on the mpv at `socket`.
+/// Returns how many layers the spec holds; the refresh daemon draws them.
+pub fn build_scene_osd_text(
+    root: &Path,
+    max_layers: usize,
+    framing: OutputFraming,
+    timezones: &TextTimezones,
+    socket: &Path,
+) -> Result<usize> {
+    let Some((_pkg, scene_json)) = load_scene_json(root)? else {
+        return Ok(0);
+    };
+    let (scene_w, scene_h) = parse_scene_size(&scene_json);
+    let canvas = framing.canvas(scene_w as u32, scene_h as u32);
+    let entries = scene_json
+        .get("objects")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|object| is_text_object(object) && visible_enabled(object))
+        .filter_map(|object| {
+            let layout = text_layout(object, (scene_w, scene_h), canvas.as_ref())?;
+            Some(RefreshEntry {
+                file_path: String::new(),
+                object: object.clone(),
+                timezone: timezones.for_object(object).map(|tz| tz.name().to_string()),
+                layout: Some(layout),
+            })
+        })
+        .take(max_layers.max(1))
+        .collect::<Vec<_>>();
+    let spec_path = runtime_spec_path(root);
+    if entries.is_empty() {
+        fs::remove_file(&spec_path).ok();
+        return Ok(0);
+    }
+    let count = entries.len();
+    let spec = RefreshSpec {
+        entries,
+        osd_socket: Some(socket.to_string_lossy().to_string()),
+    };
+    fs::create_dir_all(text_layers_dir(root))
+        .with_context(|| format!("Failed creating {}", text_layers_dir(root).display()))?;
+    fs::write(&spec_path, serde_json::to_vec_pretty(&spec)?)
+        .with_context(|| format!("Failed writing {}", spec_path.display()))?;
+    Ok(count)
+}
+
+/// How often a dynamic text layer can change, ordered finest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RefreshGranularity {
+    Second,
+    Minute,
+    Day,
+}
+
+/// Upper bound for one sleep: monotonic sleeps do not advance during
+/// suspend, so a long sleep could leave a stale clock after resume.
+const MAX_REFRESH_SLEEP_MS: u64 = 60_000;
+/// Writes land slightly after the boundary so the new value is already due.
+const MAX_REFRESH_JITTER_MS: u64 = 120;
+
+fn refresh_granularity(object: &Value) -> RefreshGranularity {
+    let text_obj = object.get("text");
+    let script = text_obj
+        .and_then(|v| v.get("script"))
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let props = text_obj
+        .and_then(|v| v.get("scriptproperties"))
+        .and_then(|v| v.as_object());
+    if script.contains("getseconds") || prop_bool(props.and_then(|m| m.get("showSeconds")), false) {
+        return RefreshGranularity::Second;
+    }
+    let name = object
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let is_clock = script.contains("gethours")
+        || script.contains("getminutes")
+        || name.contains("clock")
+        || name.contains("time");
+    if is_clock {
+        RefreshGranularity::Minute
+    } else {
+        RefreshGranularity::Day
+    }
+}
+
+fn next_refresh_delay<T: TimeZone>(
+    now: DateTime<T>,
+    granularity: RefreshGranularity,
+    jitter_ms: u64,
+) -> Duration {
+    let into_second_ms = (now.timestamp_subsec_millis() as u64).min(999);
+    let until_next_second = 1000 - into_second_ms;
+    let ms = match granularity {
+        RefreshGranularity::Second => until_next_second,
+        RefreshGranularity::Minute => {
+            (59 - now.second() as u64) * 1000 + until_next_second + jitter_ms
+        }
+        RefreshGranularity::Day => {
+            let secs_today = now.num_seconds_from_midnight() as u64;
+            (86_399 - secs_today.min(86_399)) * 1000 + until_next_second + jitter_ms
+        }
+    };
+    Duration::from_millis(ms.min(MAX_REFRESH_SLEEP_MS))
+}
+
+/// Per-process jitter so several daemons (one per monitor) do not all wake
+/// and write at exactly the same instant.
+fn refresh_jitter_ms() -> u64 {
+    std::process::id() as u64 % MAX_REFRESH_JITTER_MS
+}
+
+fn load_refresh_spec(spec_path: &Path) -> Result<RefreshSpec> {
     let raw = fs::read(spec_path)
         .with_context(|| format!("Failed reading refresh spec {}", spec_path.display()))?;
-    let spec: RefreshSpec = serde_json::from_slice(&raw)
-        .with_context(|| format!("Invalid refresh spec JSON {}", spec_path.display()))?;
+    serde_json::from_slice(&raw)
+        .with_context(|| format!("Invalid refresh spec JSON {}", spec_path.display()))
+}
 
+/// Rewrites only the layers whose text changed since the last pass.
+fn refresh_changed_entries(spec: &RefreshSpec, last: &mut [Option<String>]) -> usize {
+    if let Some(socket) = spec.osd_socket.as_deref() {
+        return refresh_osd_entries(spec, Path::new(socket), last);
+    }
     let mut updated = 0usize;
-    for entry in &spec.entries {
-        let text = infer_text_expr(&entry.object).unwrap_or_default();
-        if fs::write(&entry.file_path, text).is_ok() {
+    for (entry, previous) in spec.entries.iter().zip(last.iter_mut()) {
+        let text =
+            infer_text_expr(&entry.object, text_now(entry_timezone(entry))).unwrap_or_default();
+        if previous.as_deref() == Some(text.as_str()) {
+            continue;
+        }
+        if fs::write(&entry.file_path, &text).is_ok() {
             updated += 1;
+            *previous = Some(text);
         }
     }
-    Ok(updated)
+    updated
 }
 
-pub fn run_text_refresh_loop(spec_path: &Path, interval_seconds: u64) -> Result<()> {
-    let interval = interval_seconds.max(1);
+/// The OSD overlay replaces all layers at once, so one changed text resends
+/// every event. A failed send (mpv not up yet) forgets `last` to retry.
+fn refresh_osd_entries(spec: &RefreshSpec, socket: &Path, last: &mut [Option<String>]) -> usize {
+    let texts = spec
+        .entries
+        .iter()
+        .map(|entry| {
+            infer_text_expr(&entry.object, text_now(entry_timezone(entry))).unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let changed = texts
+        .iter()
+        .zip(last.iter())
+        .filter(|(text, previous)| previous.as_deref() != Some(text.as_str()))
+        .count();
+    if changed == 0 {
+        return 0;
+    }
+    let events = spec
+        .entries
+        .iter()
+        .zip(&texts)
+        .filter_map(|(entry, text)| Some(ass_event(entry.layout.as_ref()?, text)))
+        .collect::<Vec<_>>();
+    if mpv_ipc_command_at(socket, osd_overlay_command(&events)).is_err() {
+        last.iter_mut().for_each(|previous| *previous = None);
+        return 0;
+    }
+    for (previous, text) in last.iter_mut().zip(texts) {
+        *previous = Some(text);
+    }
+    changed
+}
+
+pub fn run_text_refresh(spec_path: &Path) -> Result<usize> {
+    let spec = load_refresh_spec(spec_path)?;
+    let mut last = vec![None; spec.entries.len()];
+    Ok(refresh_changed_entries(&spec, &mut last))
+}
+
+/// Without `interval_seconds` the loop sleeps until the next second, minute or
+/// day boundary depending on the finest text layer in the spec.
+pub fn run_text_refresh_loop(spec_path: &Path, interval_seconds: Option<u64>) -> Result<()> {
+    let spec = load_refresh_spec(spec_path)?;
+    let granularity = spec
+        .entries
+        .iter()
+        .map(|e| refresh_granularity(&e.object))
+        .min()
+        .unwrap_or(RefreshGranularity::Day);
+    let jitter_ms = refresh_jitter_ms();
+    let mut last = spec
+        .entries
+        .iter()
+        .map(|e| match spec.osd_socket {
+            Some(_) => None,
+            None => fs::read_to_string(&e.file_path).ok(),
+        })
+        .collect::<Vec<_>>();
     loop {
-        let _ = run_text_refresh(spec_path);
-        thread::sleep(Duration::from_secs(interval));
+        refresh_changed_entries(&spec, &mut last);
+        let osd_pending = spec.osd_socket.is_some() && last.iter().any(Option::is_none);
+        let delay = match interval_seconds {
+            // The OSD was not drawn yet: retry soon instead of at the boundary.
+            _ if osd_pending => Duration::from_secs(1),
+            Some(secs) => Duration::from_secs(secs.max(1)),
+            // Day boundaries differ per timezone: wake for the earliest one.
+            None => spec
+                .entries
+                .iter()
+                .map(|e| next_refresh_delay(text_now(entry_timezone(e)), granularity, jitter_ms))
+                .min()
+                .unwrap_or(Duration::from_millis(MAX_REFRESH_SLEEP_MS)),
+        };
+        thread::sleep(delay);
     }
 }
 
 pub fn start_text_refresh_daemon(root: &Path, dry_run: bool) -> Result<()> {
+    // Stop the previous updater first: an OSD one left running would keep
+    // drawing on the new mpv even when this run needs no refresh.
+    let pid_file = updater_pid_path(root);
+    if let Ok(pid_raw) = fs::read_to_string(&pid_file) {
+        if let Ok(pid) = pid_raw.trim().parse::<u32>() {
+            let _ = Command::new("kill").arg(pid.to_string()).status();
+        }
+        fs::remove_file(&pid_file).ok();
+    }
+
     let spec = runtime_spec_path(root);
     if !spec.is_file() {
         return Ok(());
@@ -674,17 +1364,10 @@ pub fn start_text_refresh_daemon(root: &Path, dry_run: bool) -> Result<()> {
         return Ok(());
     }
 
-    let pid_file = updater_pid_path(root);
-    if let Ok(pid_raw) = fs::read_to_string(&pid_file) {
-        if let Ok(pid) = pid_raw.trim().parse::<u32>() {
-            let _ = Command::new("kill").arg(pid.to_string()).status();
-        }
-    }
-
     let exe = std::env::current_exe().context("Failed to resolve current executable path")?;
     if dry_run {
-        println!(
-            "[dry-run] {} text-refresh --spec {} --loop --interval-seconds 1",
+        eprintln!(
+            "[dry-run] {} text-refresh --spec {} --loop",
             exe.display(),
             spec.display()
         );
@@ -696,8 +1379,6 @@ pub fn start_text_refresh_daemon(root: &Path, dry_run: bool) -> Result<()> {
         .arg("--spec")
         .arg(&spec)
         .arg("--loop")
-        .arg("--interval-seconds")
-        .arg("1")
         .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
@@ -715,6 +1396,68 @@ pub fn start_text_refresh_daemon(root: &Path, dry_run: bool) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn refresh_sleeps_until_next_visible_change() {
+        use chrono::TimeZone;
+        let now = Local.with_ymd_and_hms(2026, 3, 14, 23, 58, 30).unwrap()
+            + chrono::Duration::milliseconds(250);
+        assert_eq!(
+            next_refresh_delay(now, RefreshGranularity::Second, 40),
+            Duration::from_millis(750)
+        );
+        assert_eq!(
+            next_refresh_delay(now, RefreshGranularity::Minute, 40),
+            Duration::from_millis(29_790)
+        );
+        assert_eq!(
+            next_refresh_delay(now, RefreshGranularity::Day, 40),
+            Duration::from_millis(MAX_REFRESH_SLEEP_MS)
+        );
+
+        let clock = serde_json::json!({
+            "name": "Clock",
+            "text": {"script": "getHours getMinutes", "scriptproperties": {"showSeconds": false}}
+        });
+        let clock_secs = serde_json::json!({
+            "name": "Clock",
+            "text": {"script": "getHours getMinutes", "scriptproperties": {"showSeconds": true}}
+        });
+        let date = serde_json::json!({"name": "Date", "text": {"value": "date"}});
+        assert_eq!(refresh_granularity(&clock), RefreshGranularity::Minute);
+        assert_eq!(refresh_granularity(&clock_secs), RefreshGranularity::Second);
+        assert_eq!(refresh_granularity(&date), RefreshGranularity::Day);
+    }
+
+    #[test]
+    fn cjk_text_falls_back_to_fontconfig_match() {
+        assert_eq!(required_codepoints("12:30 日本 日"), vec![0x65e5, 0x672c]);
+        let ranges = parse_fc_charset("20-7e a0-17f 4e00-9fff");
+        assert!(charset_covers(&ranges, &[0x65e5, 0x672c]));
+        assert!(!charset_covers(&ranges, &[0x1f600]));
+
+        let mock = std::rc::Rc::new(crate::command_runner::MockRunner::new());
+        mock.respond("fc-query", true, "20-7e a0-17f");
+        mock.respond(
+            "fc-match",
+            true,
+            "/usr/share/fonts/noto-cjk/NotoSansCJK.ttc\n20-7e 4e00-9fff",
+        );
+        let picked = crate::command_runner::with_runner(mock.clone(), || {
+            pick_text_font(Some(PathBuf::from("/tmp/scene.ttf")), "日本")
+        });
+        assert_eq!(
+            picked,
+            Some(PathBuf::from("/usr/share/fonts/noto-cjk/NotoSansCJK.ttc"))
+        );
+        assert_eq!(
+            mock.commands()[1],
+            "fc-match --format=%{file}\n%{charset} sans-serif:charset=65e5 672c"
+        );
+
+        let ascii = pick_text_font(Some(PathBuf::from("/tmp/scene.ttf")), "12:30");
+        assert_eq!(ascii, Some(PathBuf::from("/tmp/scene.ttf")));
+    }
+
     #[test]
     fn infer_dynamic_patterns() {
         let day = serde_json::json!({
@@ -730,11 +1473,12 @@ mod tests {
         };
         let s = build_drawtext_for_object(
             &day,
-            3840.0,
-            2160.0,
+            (3840.0, 2160.0),
+            Local::now().fixed_offset(),
             &pkg,
             Path::new("/tmp"),
             Path::new("/tmp"),
+            None,
         )
         .unwrap();
         assert!(!s.contains("%{localtime"));
@@ -747,13 +1491,121 @@ mod tests {
         });
         let s2 = build_drawtext_for_object(
             &time,
-            3840.0,
-            2160.0,
+            (3840.0, 2160.0),
+            Local::now().fixed_offset(),
             &pkg,
             Path::new("/tmp"),
             Path::new("/tmp"),
+            None,
         )
         .unwrap();
         assert!(!s2.contains("%{localtime"));
     }
+
+    #[test]
+    fn text_layers_follow_their_timezone() {
+        let instant = Utc.with_ymd_and_hms(2026, 3, 14, 23, 58, 30).unwrap();
+        let tokyo = instant
+            .with_timezone(&chrono_tz::Asia::Tokyo)
+            .fixed_offset();
+        let madrid = instant
+            .with_timezone(&chrono_tz::Europe::Madrid)
+            .fixed_offset();
+        let clock = serde_json::json!({
+            "id": 7,
+            "name": "Clock",
+            "text": {"script": "getHours getMinutes", "scriptproperties": {"use24hFormat": true}}
+        });
+        let day = serde_json::json!({
+            "id": 8,
+            "name": "Day",
+            "text": {"script": "getDay", "scriptproperties": {"showDay": true}}
+        });
+        assert_eq!(infer_text_expr(&clock, tokyo).as_deref(), Some("08:58"));
+        assert_eq!(infer_text_expr(&clock, madrid).as_deref(), Some("00:58"));
+        assert_eq!(infer_text_expr(&day, tokyo).as_deref(), Some("SUNDAY"));
+
+        let overrides = BTreeMap::from([
+            ("timezone.7".to_string(), Value::from("Asia/Tokyo")),
+            ("timezone.8".to_string(), Value::from("Mars/Olympus")),
+            ("speed".to_string(), Value::from(1.5)),
+        ]);
+        let timezones = TextTimezones::with_overrides(Some(chrono_tz::Europe::Madrid), &overrides);
+        assert_eq!(timezones.for_object(&clock), Some(chrono_tz::Asia::Tokyo));
+        assert_eq!(timezones.for_object(&day), Some(chrono_tz::Europe::Madrid));
+        assert!(parse_timezone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn osd_text_is_sent_as_positioned_ass_events() {
+        let layout = TextLayout {
+            x_ratio: 0.5,
+            y_ratio: 0.25,
+            size_ratio: 0.05,
+            rgb: [255, 128, 0],
+            h_align: "center".to_string(),
+            v_align: "top".to_string(),
+            style: TextStyle::default(),
+        };
+        assert_eq!(
+            ass_event(&layout, "12:30 {x}"),
+            "{\\an8\\pos(960.0,270.0)\\fs54.0\\c&H0080FF&\\bord1.1\\3c&H000000&\\shad2.2\\4a&H66&}12:30 \\{x\\}"
+        );
+
+        let command = osd_overlay_command(&["a".to_string(), "b".to_string()]);
+        assert_eq!(command["name"], "osd-overlay");
+        assert_eq!(command["format"], "ass-events");
+        assert_eq!(command["data"], "a\nb");
+        assert_eq!(command["res_y"], 1080);
+    }
+
+    #[test]
+    fn text_effects_scale_with_font_size_and_glow_is_blurred() {
+        let object = serde_json::json!({
+            "origin": "960 540 0",
+            "pointsize": 40.0,
+            "effects": [
+                {
+                    "file": "effects/glow/effect.json",
+                    "passes": [{"constantshadervalues": {
+                        "radius": {"value": 10.0},
+                        "color": "0 0.5 1",
+                        "alpha": 0.5
+                    }}]
+                },
+                {
+                    "file": "effects/dropshadow/effect.json",
+                    "passes": [{"constantshadervalues": {"offset": "3 4"}}]
+                },
+                {
+                    "file": "effects/outline/effect.json",
+                    "visible": false,
+                    "passes": [{"constantshadervalues": {"size": 8.0}}]
+                }
+            ]
+        });
+        let style = extract_text_style(&object, 40.0);
+        assert_eq!(style.glow, 0.25);
+        assert_eq!(style.glow_rgb, [0, 128, 255]);
+        assert_eq!(style.glow_alpha, 0.5);
+        assert_eq!(style.shadow, 0.125);
+        assert_eq!(style.outline, TextStyle::default().outline);
+
+        let layout = TextLayout {
+            x_ratio: 0.5,
+            y_ratio: 0.5,
+            size_ratio: 0.1,
+            rgb: [255, 255, 255],
+            h_align: "center".to_string(),
+            v_align: "center".to_string(),
+            style,
+        };
+        let events = ass_event(&layout, "hi");
+        let (glow, main) = events.split_once('\n').unwrap();
+        assert_eq!(
+            glow,
+            "{\\an5\\pos(960.0,540.0)\\fs108.0\\1a&HFF&\\bord27.0\\blur27.0\\3c&HFF8000&\\3a&H80&\\shad0}hi"
+        );
+        assert!(main.contains("\\shad13.5"), "{}", main);
+    }
 }
@@ -0,0 +1,463 @@
+use crate::library_guard::ensure_outside_library;
+use crate::scene_effect_proxy::maybe_build_scene_animated_proxy;
+use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_native_renderer::{render_native_animated_proxy, render_native_static_frame};
+use crate::scene_native_runtime::build_native_runtime_plan;
+use crate::scene_pkg::default_scene_cache_root;
+use crate::scene_renderer::build_scene_render_session;
+use crate::tex_payload::extract_playable_proxy_from_tex;
+use crate::types::{ProjectJson, WallpaperType};
+use crate::video_opt::probe_duration_seconds;
+use crate::wallpaper::{detect_type, find_video_entry, is_video_file};
+use anyhow::{Context, Result, bail};
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PREVIEW_NAMES: [&str; 8] = [
+    "preview.jpg",
+    "preview.jpeg",
+    "preview.png",
+    "preview.webp",
+    "preview.gif",
+    "thumbnail.jpg",
+    "thumbnail.png",
+    "thumbnail.webp",
+];
+
+#[derive(Debug, Serialize)]
+pub struct ThumbnailReport {
+    pub output: String,
+    /// native-static | primary-visual | video-frame | preview
+    pub source: String,
+    pub source_path: String,
+    pub width: u32,
+    pub height: u32,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewStripReport {
+    pub output: String,
+    /// native-animated | scene-proxy | video | preview-gif
+    pub source: String,
+    pub source_path: String,
+    pub frames: usize,
+    /// Seconds into the source of each frame, left to right.
+    pub timestamps: Vec<f64>,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub notes: Vec<String>,
+}
+
+pub fn parse_thumbnail_size(raw: &str) -> Result<(u32, u32)> {
+    let (w, h) = raw
+        .trim()
+        .split_once(['x', 'X'])
+        .with_context(|| format!("Invalid size '{}', expected WIDTHxHEIGHT", raw))?;
+    let w = w
+        .trim()
+        .parse::<u32>()
+        .with_context(|| format!("Invalid width in '{}'", raw))?;
+    let h = h
+        .trim()
+        .parse::<u32>()
+        .with_context(|| format!("Invalid height in '{}'", raw))?;
+    if w == 0 || h == 0 || w > 7680 || h > 4320 {
+        bail!("Thumbnail size {}x{} out of range", w, h);
+    }
+    Ok((w, h))
+}
+
+fn thumbnail_work_dir(root: &Path) -> PathBuf {
+    let cache_key = root
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().replace('/', "_"));
+    default_scene_cache_root(&cache_key).join("thumbnail")
+}
+
+pub fn default_thumbnail_path(root: &Path, width: u32, height: u32) -> PathBuf {
+    thumbnail_work_dir(root).join(format!("thumbnail_{}x{}.png", width, height))
+}
+
+pub fn default_preview_strip_path(root: &Path, frames: usize) -> PathBuf {
+    thumbnail_work_dir(root).join(format!("strip_{}.png", frames))
+}
+
+fn load_project(root: &Path) -> Option<ProjectJson> {
+    let raw = std::fs::read_to_string(root.join("project.json")).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// Source rectangle `(x, y, w, h)` that fills `dst` without letterboxing.
+fn cover_crop(src_w: u32, src_h: u32, dst_w: u32, dst_h: u32) -> (u32, u32, u32, u32) {
+    let src_ratio = src_w as f64 / src_h.max(1) as f64;
+    let dst_ratio = dst_w as f64 / dst_h.max(1) as f64;
+    if src_ratio > dst_ratio {
+        let w = ((src_h as f64 * dst_ratio).round() as u32).clamp(1, src_w);
+        ((src_w - w) / 2, 0, w, src_h)
+    } else {
+        let h = ((src_w as f64 / dst_ratio).round() as u32).clamp(1, src_h);
+        (0, (src_h - h) / 2, src_w, h)
+    }
+}
+
+pub(crate) fn grab_video_frame(video: &Path, out: &Path) -> Result<()> {
+    // Skip the first second: many loops open on a fade from black.
+    grab_frame_at(video, 1.0, out)
+}
+
+fn grab_frame_at(video: &Path, seconds: f64, out: &Path) -> Result<()> {
+    let output = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-y")
+        .arg("-ss")
+        .arg(format!("{:.3}", seconds))
+        .arg("-i")
+        .arg(video)
+        .arg("-frames:v")
+        .arg("1")
+        .arg(out)
+        .output()
+        .context("Failed running ffmpeg for thumbnail frame")?;
+    if !output.status.success() || !out.is_file() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        bail!("ffmpeg frame grab failed: {}", err.trim());
+    }
+    Ok(())
+}
+
+fn image_from_visual(visual: &Path, work_dir: &Path) -> Result<Option<PathBuf>> {
+    if is_video_file(visual) {
+        let frame = work_dir.join("video_frame.png");
+        grab_video_frame(visual, &frame)?;
+        return Ok(Some(frame));
+    }
+    let is_tex = visual
+        .extension()
+        .map(|e| e.to_string_lossy().eq_ignore_ascii_case("tex"))
+        .unwrap_or(false);
+    if is_tex {
+        return extract_playable_proxy_from_tex(visual, &work_dir.join("proxy"));
+    }
+    Ok(Some(visual.to_path_buf()))
+}
+
+fn native_static_source(root: &Path, work_dir: &Path) -> Result<Option<PathBuf>> {
+    let graph = build_scene_gpu_graph(root)?;
+    let plan = build_native_runtime_plan(&graph);
+    let report =
+        render_native_static_frame(root, work_dir, graph.scene_width, graph.scene_height, &plan)?;
+    Ok(report
+        .filter(|r| r.rendered_layers > 0)
+        .map(|r| PathBuf::from(r.output_image)))
+}
+
+fn primary_visual_source(root: &Path, work_dir: &Path) -> Result<Option<PathBuf>> {
+    let session = build_scene_render_session(root, None, 1, 50)?;
+    image_from_visual(Path::new(&session.visual_asset_path), work_dir)
+}
+
+/// Renders a representative frame for library tooling, preferring the
+/// native layer composite over the (often low-res) workshop preview.
+pub fn render_scene_thumbnail(
+    root: &Path,
+    out: &Path,
+    width: u32,
+    height: u32,
+) -> Result<ThumbnailReport> {
+    ensure_outside_library(out, "thumbnail")?;
+    let work_dir = thumbnail_work_dir(root);
+    std::fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed creating {}", work_dir.display()))?;
+    let project = load_project(root);
+    let mut notes = Vec::new();
+    let mut picked: Option<(&str, PathBuf)> = None;
+
+    match detect_type(root, project.as_ref()) {
+        WallpaperType::Scene => {
+            match native_static_source(root, &work_dir) {
+                Ok(Some(p)) => picked = Some(("native-static", p)),
+                Ok(None) => notes.push("Native plan has no renderable layers".to_string()),
+                Err(err) => notes.push(format!("Native static render failed: {err:#}")),
+            }
+            if picked.is_none() {
+                match primary_visual_source(root, &work_dir) {
+                    Ok(Some(p)) => picked = Some(("primary-visual", p)),
+                    Ok(None) => notes.push("Primary visual is not decodable".to_string()),
+                    Err(err) => notes.push(format!("Primary visual unavailable: {err:#}")),
+                }
+            }
+        }
+        WallpaperType::Video => {
+            if let Some(video) = find_video_entry(root, project.as_ref()) {
+                let frame = work_dir.join("video_frame.png");
+                match grab_video_frame(&video, &frame) {
+                    Ok(()) => picked = Some(("video-frame", frame)),
+                    Err(err) => notes.push(format!("Video frame grab failed: {err:#}")),
+                }
+            }
+        }
+        _ => notes.push("Unsupported wallpaper type; using preview file".to_string()),
+    }
+
+    if picked.is_none()
+        && let Some(preview) = PREVIEW_NAMES
+            .iter()
+            .map(|name| root.join(name))
+            .find(|p| p.is_file())
+    {
+        picked = Some(("preview", preview));
+    }
+    let Some((source, source_path)) = picked else {
+        bail!(
+            "No thumbnail source found for {}: {}",
+            root.display(),
+            notes.join("; ")
+        );
+    };
+
+    let img = image::open(&source_path)
+        .with_context(|| format!("Failed decoding {}", source_path.display()))?;
+    let (x, y, w, h) = cover_crop(img.width(), img.height(), width, height);
+    let thumb = DynamicImage::ImageRgba8(
+        img.crop_imm(x, y, w, h)
+            .resize_exact(width, height, FilterType::Lanczos3)
+            .to_rgba8(),
+    );
+    if let Some(parent) = out.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    // JPEG has no alpha channel.
+    let is_jpeg = out
+        .extension()
+        .map(|e| {
+            matches!(
+                e.to_string_lossy().to_ascii_lowercase().as_str(),
+                "jpg" | "jpeg"
+            )
+        })
+        .unwrap_or(false);
+    let saved = if is_jpeg {
+        DynamicImage::ImageRgb8(thumb.to_rgb8()).save(out)
+    } else {
+        thumb.save(out)
+    };
+    saved.with_context(|| format!("Failed writing thumbnail {}", out.display()))?;
+
+    Ok(ThumbnailReport {
+        output: out.to_string_lossy().to_string(),
+        source: source.to_string(),
+        source_path: source_path.to_string_lossy().to_string(),
+        width,
+        height,
+        notes,
+    })
+}
+
+/// Middle of `frames` equal slices of the clip, so neither end (often a
+/// fade) is picked.
+fn strip_timestamps(duration: f64, frames: usize) -> Vec<f64> {
+    let frames = frames.max(1);
+    (0..frames)
+        .map(|i| duration.max(0.0) * (i as f64 + 0.5) / frames as f64)
+        .collect()
+}
+
+/// Frames cover-cropped to `width`x`height` and laid side by side.
+fn compose_strip(frames: &[DynamicImage], width: u32, height: u32) -> RgbaImage {
+    let mut strip = RgbaImage::new(width * frames.len() as u32, height);
+    for (i, frame) in frames.iter().enumerate() {
+        let (x, y, w, h) = cover_crop(frame.width(), frame.height(), width, height);
+        let tile = frame
+            .crop_imm(x, y, w, h)
+            .resize_exact(width, height, FilterType::Lanczos3)
+            .to_rgba8();
+        image::imageops::replace(&mut strip, &tile, (i as u32 * width) as i64, 0);
+    }
+    strip
+}
+
+/// Moving source for the strip: the native animated render of a scene (or
+/// its effect proxy), the video of a video wallpaper, or an animated preview.
+fn strip_source(
+    root: &Path,
+    work_dir: &Path,
+    notes: &mut Vec<String>,
+) -> Result<Option<(&'static str, PathBuf)>> {
+    let project = load_project(root);
+    match detect_type(root, project.as_ref()) {
+        WallpaperType::Scene => {
+            let graph = build_scene_gpu_graph(root)?;
+            let plan = build_native_runtime_plan(&graph);
+            match render_native_animated_proxy(
+                root,
+                work_dir,
+                graph.scene_width,
+                graph.scene_height,
+                None,
+                15,
+                false,
+                &plan,
+            ) {
+                Ok(Some(r)) if r.rendered_layers > 0 => {
+                    return Ok(Some(("native-animated", PathBuf::from(r.output_video))));
+                }
+                Ok(_) => notes.push("Native plan has no animated layers".to_string()),
+                Err(err) => notes.push(format!("Native animated render failed: {err:#}")),
+            }
+            if let Some(visual) = primary_visual_source(root, work_dir)? {
+                match maybe_build_scene_animated_proxy(
+                    root, work_dir, &visual, None, false, None, false,
+                ) {
+                    Ok(Some(proxy)) => return Ok(Some(("scene-proxy", proxy))),
+                    Ok(None) => notes.push("Scene has no animated proxy".to_string()),
+                    Err(err) => notes.push(format!("Scene proxy failed: {err:#}")),
+                }
+            }
+        }
+        WallpaperType::Video => {
+            if let Some(video) = find_video_entry(root, project.as_ref()) {
+                return Ok(Some(("video", video)));
+            }
+        }
+        _ => {}
+    }
+    Ok(root
+        .join("preview.gif")
+        .is_file()
+        .then(|| ("preview-gif", root.join("preview.gif"))))
+}
+
+/// Contact sheet of `frames` frames sampled across the wallpaper's motion,
+/// for library UIs that should not embed a video player.
+pub fn render_preview_strip(
+    root: &Path,
+    out: &Path,
+    frames: usize,
+    frame_width: u32,
+    frame_height: u32,
+) -> Result<PreviewStripReport> {
+    ensure_outside_library(out, "preview strip")?;
+    if !(1..=32).contains(&frames) {
+        bail!("--frames must be between 1 and 32, got {}", frames);
+    }
+    let work_dir = thumbnail_work_dir(root).join("strip");
+    std::fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed creating {}", work_dir.display()))?;
+    let mut notes = Vec::new();
+    let Some((source, source_path)) = strip_source(root, &work_dir, &mut notes)? else {
+        bail!(
+            "No animated source found for {}: {}",
+            root.display(),
+            notes.join("; ")
+        );
+    };
+
+    let duration = probe_duration_seconds(&source_path)?;
+    if duration <= 0.0 {
+        notes.push("Source duration unknown; every frame is the first one".to_string());
+    }
+    let timestamps = strip_timestamps(duration, frames);
+    let mut images = Vec::with_capacity(frames);
+    for (i, at) in timestamps.iter().enumerate() {
+        let frame_path = work_dir.join(format!("frame_{:02}.png", i));
+        grab_frame_at(&source_path, *at, &frame_path)?;
+        images.push(
+            image::open(&frame_path)
+                .with_context(|| format!("Failed decoding {}", frame_path.display()))?,
+        );
+    }
+
+    let strip = compose_strip(&images, frame_width, frame_height);
+    if let Some(parent) = out.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    DynamicImage::ImageRgba8(strip)
+        .to_rgb8()
+        .save(out)
+        .with_context(|| format!("Failed writing preview strip {}", out.display()))?;
+
+    Ok(PreviewStripReport {
+        output: out.to_string_lossy().to_string(),
+        source: source.to_string(),
+        source_path: source_path.to_string_lossy().to_string(),
+        frames,
+        timestamps,
+        frame_width,
+        frame_height,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_parsing() {
+        assert_eq!(parse_thumbnail_size("1280x720").unwrap(), (1280, 720));
+        assert_eq!(parse_thumbnail_size(" 640X360 ").unwrap(), (640, 360));
+        assert!(parse_thumbnail_size("1280").is_err());
+        assert!(parse_thumbnail_size("0x720").is_err());
+    }
+
+    #[test]
+    fn cover_crop_keeps_center() {
+        // 4:3 source into 16:9: trims top and bottom.
+        assert_eq!(cover_crop(1600, 1200, 1280, 720), (0, 150, 1600, 900));
+        // Ultrawide source into 16:9: trims the sides.
+        assert_eq!(cover_crop(3440, 1440, 1280, 720), (440, 0, 2560, 1440));
+    }
+    #[test]
+    fn preview_strip_samples_slice_midpoints_side_by_side() {
+        assert_eq!(strip_timestamps(12.0, 3), [2.0, 6.0, 10.0]);
+        assert_eq!(strip_timestamps(0.0, 2), [0.0, 0.0]);
+
+        let red =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 48, image::Rgba([255, 0, 0, 255])));
+        let blue = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            100,
+            50,
+            image::Rgba([0, 0, 255, 255]),
+        ));
+        let strip = compose_strip(&[red, blue], 32, 18);
+        assert_eq!(strip.dimensions(), (64, 18));
+        assert_eq!(strip.get_pixel(5, 9)[0], 255);
+        assert_eq!(strip.get_pixel(40, 9)[2], 255);
+    }
+
+    #[test]
+    fn workshop_gif_previews_decode() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("project.json"), r#"{"type":"web"}"#).unwrap();
+        let mut gif = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(32, 18, image::Rgba([0, 200, 0, 255])))
+            .write_to(&mut std::io::Cursor::new(&mut gif), image::ImageFormat::Gif)
+            .unwrap();
+        std::fs::write(root.path().join("preview.gif"), gif).unwrap();
+
+        let dirs = tempfile::tempdir().unwrap();
+        let out = dirs.path().join("thumb.png");
+        let report = crate::app_paths::with_app_dirs_root(dirs.path(), || {
+            render_scene_thumbnail(root.path(), &out, 16, 9)
+        })
+        .unwrap();
+        assert_eq!(report.source, "preview");
+        assert_eq!(
+            image::open(&out).unwrap().to_rgba8().get_pixel(8, 4)[1],
+            200
+        );
+    }
+}
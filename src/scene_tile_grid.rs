@@ -0,0 +1,132 @@
+use image::RgbaImage;
+use image::imageops;
+
+/// Largest grid probed per axis; real scenes split into a few tiles at most.
+pub const MAX_GRID_TILES: u32 = 16;
+
+/// A texture named like one piece of a tiled background
+/// (`materials/bg_0_1.tex` is row 0, column 1 of `materials/bg`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileName {
+    pub prefix: String,
+    pub row: u32,
+    pub col: u32,
+    pub ext: String,
+}
+
+impl TileName {
+    /// Path of the tile at `(row, col)` in the same set.
+    pub fn sibling(&self, row: u32, col: u32) -> String {
+        format!("{}_{}_{}{}", self.prefix, row, col, self.ext)
+    }
+}
+
+/// Splits `path` into its grid prefix and `_row_col` suffix, if it has one.
+pub fn parse_tile_name(path: &str) -> Option<TileName> {
+    let (dir, file) = match path.rsplit_once('/') {
+        Some((dir, file)) => (format!("{}/", dir), file),
+        None => (String::new(), path),
+    };
+    let (stem, ext) = match file.rfind('.') {
+        Some(dot) => (&file[..dot], &file[dot..]),
+        None => (file, ""),
+    };
+    let mut parts = stem.rsplitn(3, '_');
+    let col = parts.next()?.parse::<u32>().ok()?;
+    let row = parts.next()?.parse::<u32>().ok()?;
+    let base = parts.next().filter(|b| !b.is_empty())?;
+    if row >= MAX_GRID_TILES || col >= MAX_GRID_TILES {
+        return None;
+    }
+    Some(TileName {
+        prefix: format!("{}{}", dir, base),
+        row,
+        col,
+        ext: ext.to_string(),
+    })
+}
+
+/// Grid positions present in a tile set, probed row by row from `(0, 0)`:
+/// a row ends at its first missing column and the grid at the first row
+/// without a column 0.
+pub fn probe_tile_grid(exists: impl Fn(u32, u32) -> bool) -> Vec<(u32, u32)> {
+    let mut cells = Vec::new();
+    for row in 0..MAX_GRID_TILES {
+        if !exists(row, 0) {
+            break;
+        }
+        for col in 0..MAX_GRID_TILES {
+            if !exists(row, col) {
+                break;
+            }
+            cells.push((row, col));
+        }
+    }
+    cells
+}
+
+/// Lays tiles out in one image. Column widths come from the widest tile in
+/// each column and row heights from the tallest in each row, so slightly
+/// uneven edge tiles still line up.
+pub fn stitch_tiles(tiles: &[(u32, u32, RgbaImage)]) -> Option<RgbaImage> {
+    let rows = tiles.iter().map(|(r, _, _)| r + 1).max()? as usize;
+    let cols = tiles.iter().map(|(_, c, _)| c + 1).max()? as usize;
+    let mut col_widths = vec![0u32; cols];
+    let mut row_heights = vec![0u32; rows];
+    for (row, col, img) in tiles {
+        col_widths[*col as usize] = col_widths[*col as usize].max(img.width());
+        row_heights[*row as usize] = row_heights[*row as usize].max(img.height());
+    }
+    let offset = |sizes: &[u32], i: u32| sizes[..i as usize].iter().sum::<u32>();
+    let mut canvas = RgbaImage::new(
+        col_widths.iter().sum::<u32>().max(1),
+        row_heights.iter().sum::<u32>().max(1),
+    );
+    for (row, col, img) in tiles {
+        let x = offset(&col_widths, *col);
+        let y = offset(&row_heights, *row);
+        imageops::replace(&mut canvas, img, x as i64, y as i64);
+    }
+    Some(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn grid_named_tiles_are_probed_and_stitched_in_order() {
+        let tile = parse_tile_name("materials/bg_0_1.tex").unwrap();
+        assert_eq!(tile.prefix, "materials/bg");
+        assert_eq!((tile.row, tile.col), (0, 1));
+        assert_eq!(tile.sibling(1, 0), "materials/bg_1_0.tex");
+        assert_eq!(parse_tile_name("big_sky_2_0").unwrap().prefix, "big_sky");
+        assert_eq!(parse_tile_name("materials/layer_2.tex"), None);
+        assert_eq!(parse_tile_name("materials/_0_0.tex"), None);
+
+        let present = ["0_0", "0_1", "1_0", "1_1", "3_0"];
+        let cells = probe_tile_grid(|r, c| present.contains(&format!("{}_{}", r, c).as_str()));
+        assert_eq!(cells, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+
+        let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0]];
+        let tiles = cells
+            .iter()
+            .zip(colors)
+            .map(|(&(r, c), [red, green, blue])| {
+                let w = if c == 0 { 4 } else { 2 };
+                (
+                    r,
+                    c,
+                    RgbaImage::from_pixel(w, 3, Rgba([red, green, blue, 255])),
+                )
+            })
+            .collect::<Vec<_>>();
+        let stitched = stitch_tiles(&tiles).unwrap();
+        assert_eq!(stitched.dimensions(), (6, 6));
+        assert_eq!(stitched.get_pixel(3, 2).0, [255, 0, 0, 255]);
+        assert_eq!(stitched.get_pixel(4, 0).0, [0, 255, 0, 255]);
+        assert_eq!(stitched.get_pixel(0, 3).0, [0, 0, 255, 255]);
+        assert_eq!(stitched.get_pixel(5, 5).0, [255, 255, 0, 255]);
+    }
+}
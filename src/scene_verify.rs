@@ -0,0 +1,227 @@
+use crate::scene_gpu_graph::build_scene_gpu_graph;
+use crate::scene_native_renderer::render_native_static_layers;
+use crate::scene_native_runtime::{NativeSupportTier, build_native_runtime_plan};
+use crate::scene_pkg::default_scene_cache_root;
+use crate::types::{ProjectJson, WallpaperType};
+use crate::wallpaper::detect_type;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What `verify-render` must see for the wallpaper to pass.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyExpectations {
+    /// Exact number of layers the native compositor must draw.
+    pub layers: Option<usize>,
+    /// Fail when the native path draws nothing and playback would fall back
+    /// to the mp4 proxy or the workshop preview.
+    pub no_fallback: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DroppedLayer {
+    pub object_id: u64,
+    pub object_name: String,
+    pub shader_family: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MissingTexture {
+    pub object_id: u64,
+    pub object_name: String,
+    pub texture_ref: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyRenderReport {
+    pub wallpaper: String,
+    pub passed: bool,
+    /// `native` when the compositor drew the scene, `fallback` otherwise.
+    pub path: String,
+    pub rendered_layers: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_layers: Option<usize>,
+    pub dropped_layers: Vec<DroppedLayer>,
+    pub missing_textures: Vec<MissingTexture>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_image: Option<String>,
+    pub failures: Vec<String>,
+}
+
+fn verify_work_dir(root: &Path) -> PathBuf {
+    let cache_key = root
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().replace('/', "_"));
+    default_scene_cache_root(&cache_key).join("verify")
+}
+
+/// Renders a scene headlessly through the native static compositor and
+/// checks it against `expect`. Missing textures always fail; the report is
+/// returned either way so CI logs show what went wrong.
+pub fn verify_scene_render(
+    root: &Path,
+    expect: &VerifyExpectations,
+    out: Option<&Path>,
+) -> Result<VerifyRenderReport> {
+    verify_scene_render_in(root, &verify_work_dir(root), expect, out)
+}
+
+fn verify_scene_render_in(
+    root: &Path,
+    work_dir: &Path,
+    expect: &VerifyExpectations,
+    out: Option<&Path>,
+) -> Result<VerifyRenderReport> {
+    let project = fs::read_to_string(root.join("project.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<ProjectJson>(&raw).ok());
+    let kind = detect_type(root, project.as_ref());
+    if !matches!(kind, WallpaperType::Scene) {
+        bail!(
+            "verify-render checks scene wallpapers; {} is {:?}",
+            root.display(),
+            kind
+        );
+    }
+
+    let graph = build_scene_gpu_graph(root)?;
+    let plan = build_native_runtime_plan(&graph);
+    fs::create_dir_all(work_dir)
+        .with_context(|| format!("Failed creating {}", work_dir.display()))?;
+    let render =
+        render_native_static_layers(root, work_dir, graph.scene_width, graph.scene_height, &plan)?;
+
+    let dropped_layers = plan
+        .passes
+        .iter()
+        .zip(&plan.draw_layers)
+        .filter(|(_, layer)| {
+            let region_blur = matches!(layer.tier, NativeSupportTier::ExperimentalReady)
+                && layer.blur_sigma.is_some();
+            !region_blur && !matches!(layer.tier, NativeSupportTier::Ready)
+        })
+        .map(|(pass, layer)| DroppedLayer {
+            object_id: layer.object_id,
+            object_name: layer.object_name.clone(),
+            shader_family: layer.shader_family.clone(),
+            reason: format!("tier {:?}: {}", pass.tier, pass.reason),
+        })
+        .collect::<Vec<_>>();
+    let missing_textures = render
+        .layers
+        .iter()
+        .filter(|layer| !layer.loaded)
+        .map(|layer| MissingTexture {
+            object_id: layer.object_id,
+            object_name: layer.object_name.clone(),
+            texture_ref: layer.texture_ref.clone(),
+            reason: layer.reason.clone().unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+
+    let native = render.rendered_layers > 0;
+    let mut failures = Vec::new();
+    if expect.no_fallback && !native {
+        failures.push("native compositor drew no layer: playback would fall back".to_string());
+    }
+    if let Some(expected) = expect.layers
+        && expected != render.rendered_layers
+    {
+        failures.push(format!(
+            "expected {} rendered layers, got {} ({} dropped)",
+            expected,
+            render.rendered_layers,
+            dropped_layers.len()
+        ));
+    }
+    for miss in &missing_textures {
+        failures.push(format!(
+            "object {} ({}) is missing texture '{}'",
+            miss.object_id, miss.object_name, miss.texture_ref
+        ));
+    }
+
+    let mut output_image = (!render.output_image.is_empty()).then(|| render.output_image.clone());
+    if let (Some(out), Some(rendered)) = (out, &output_image) {
+        if let Some(parent) = out.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed creating {}", parent.display()))?;
+        }
+        fs::copy(rendered, out)
+            .with_context(|| format!("Failed copying frame to {}", out.display()))?;
+        output_image = Some(out.display().to_string());
+    }
+
+    Ok(VerifyRenderReport {
+        wallpaper: root.display().to_string(),
+        passed: failures.is_empty(),
+        path: if native { "native" } else { "fallback" }.to_string(),
+        rendered_layers: render.rendered_layers,
+        expected_layers: expect.layers,
+        dropped_layers,
+        missing_textures,
+        output_image,
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_gpu_graph::write_scene_files;
+
+    #[test]
+    fn verify_flags_missing_textures_and_layer_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "scene.json",
+                r#"{"general": {"orthogonalprojection": {"width": 128, "height": 128}},
+                    "objects": [
+                      {"id": 1, "name": "sky", "image": "models/sky.json", "size": "64 64"},
+                      {"id": 2, "name": "fox", "image": "models/fox.json", "size": "64 64"}]}"#,
+            ),
+            ("models/sky.json", r#"{"material": "materials/sky.json"}"#),
+            ("models/fox.json", r#"{"material": "materials/fox.json"}"#),
+            (
+                "materials/sky.json",
+                r#"{"passes": [{"shader": "genericimage2", "textures": ["a.png"]}]}"#,
+            ),
+            (
+                "materials/fox.json",
+                r#"{"passes": [{"shader": "genericimage2", "textures": ["missing.png"]}]}"#,
+            ),
+        ];
+        write_scene_files(dir.path(), &files);
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([200, 10, 10, 255]))
+            .save(dir.path().join("a.png"))
+            .unwrap();
+        let work = dir.path().join("work");
+
+        let expect = VerifyExpectations {
+            layers: Some(1),
+            no_fallback: true,
+        };
+        let report = verify_scene_render_in(dir.path(), &work, &expect, None).unwrap();
+        assert_eq!(report.path, "native");
+        assert_eq!(report.rendered_layers, 1);
+        assert_eq!(report.missing_textures.len(), 1);
+        assert_eq!(report.missing_textures[0].texture_ref, "missing.png");
+        assert_eq!(report.failures.len(), 1);
+        assert!(!report.passed);
+
+        fs::copy(dir.path().join("a.png"), dir.path().join("missing.png")).unwrap();
+        let expect = VerifyExpectations {
+            layers: Some(2),
+            no_fallback: true,
+        };
+        let report = verify_scene_render_in(dir.path(), &work, &expect, None).unwrap();
+        assert!(report.passed, "{:?}", report.failures);
+    }
+}
@@ -0,0 +1,420 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Evaluator for the JavaScript-like `visible` conditions of scene objects
+/// (`style.value == 1 && glow*2 > 3`, `a ? b : c`, `x.contains('y')`).
+/// `None` when the expression is malformed or reads a property the scene
+/// does not define, so the caller can fall back to the static value.
+pub fn eval_visible_expr(expr: &str, user_values: &BTreeMap<String, Value>) -> Option<bool> {
+    let tokens = tokenize(expr)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        user_values,
+    };
+    let value = parser.ternary()?;
+    if parser.pos != tokens.len() {
+        return None;
+    }
+    value.as_ref().map(truthy)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    /// Property path, dots included (`style.value`, `name.contains`).
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 22] = [
+    "===", "!==", "==", "!=", ">=", "<=", "&&", "||", ">", "<", "!", "?", ":", "(", ")", ",", "+",
+    "-", "*", "/", "%", "=",
+];
+
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0usize;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Num(text.parse().ok()?));
+        } else if c == '\'' || c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i)? {
+                    '\\' => {
+                        text.push(*chars.get(i + 1)?);
+                        i += 2;
+                    }
+                    ch if *ch == c => break,
+                    ch => {
+                        text.push(*ch);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push(Token::Str(text));
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '$' | '.'))
+            {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            let op = OPERATORS.iter().find(|op| rest.starts_with(**op))?;
+            // A lone `=` in a condition means comparison, never assignment.
+            tokens.push(Token::Op(if *op == "=" { "==" } else { op }));
+            i += op.len();
+        }
+    }
+    Some(tokens)
+}
+
+/// Scene user properties keep JavaScript-ish truthiness, except that combo
+/// values `"0"`/`"false"` are off.
+fn truthy(v: &Value) -> bool {
+    match v {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0 && !n.is_nan()),
+        Value::String(s) => !s.trim().is_empty() && s != "0" && !s.eq_ignore_ascii_case("false"),
+        Value::Null => false,
+        _ => true,
+    }
+}
+
+fn value_as_f64(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+fn value_as_str(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn loosely_equal(left: &Value, right: &Value) -> bool {
+    if let (Some(a), Some(b)) = (value_as_f64(left), value_as_f64(right)) {
+        return (a - b).abs() < 1e-6;
+    }
+    if let (Some(a), Some(b)) = (value_as_str(left), value_as_str(right)) {
+        return a == b;
+    }
+    left == right
+}
+
+fn number(v: f64) -> Option<Value> {
+    serde_json::Number::from_f64(v).map(Value::Number)
+}
+
+const STRING_METHODS: [&str; 5] = [
+    "contains",
+    "startsWith",
+    "startswith",
+    "endsWith",
+    "endswith",
+];
+
+/// Precedence climbing over the tokens. Each level yields `Some(None)` for a
+/// well-formed expression reading an unknown property, and `None` for a
+/// syntax error.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    user_values: &'a BTreeMap<String, Value>,
+}
+
+type Eval = Option<Option<Value>>;
+
+impl Parser<'_> {
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek_op(&self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => ops.iter().copied().find(|o| o == op),
+            _ => None,
+        }
+    }
+
+    fn ternary(&mut self) -> Eval {
+        let cond = self.or()?;
+        if !self.eat("?") {
+            return Some(cond);
+        }
+        let then = self.ternary()?;
+        if !self.eat(":") {
+            return None;
+        }
+        let otherwise = self.ternary()?;
+        Some(match cond {
+            Some(c) if truthy(&c) => then,
+            Some(_) => otherwise,
+            None => None,
+        })
+    }
+
+    /// `||`/`&&` read an unknown side as false, as conditions mixing known
+    /// and unknown properties still decide most layers.
+    fn or(&mut self) -> Eval {
+        let mut left = self.and()?;
+        while self.eat("||") {
+            let right = self.and()?;
+            let value = left.as_ref().is_some_and(truthy) || right.as_ref().is_some_and(truthy);
+            left = Some(Value::Bool(value));
+        }
+        Some(left)
+    }
+
+    fn and(&mut self) -> Eval {
+        let mut left = self.equality()?;
+        while self.eat("&&") {
+            let right = self.equality()?;
+            let value = left.as_ref().is_some_and(truthy) && right.as_ref().is_some_and(truthy);
+            left = Some(Value::Bool(value));
+        }
+        Some(left)
+    }
+
+    fn equality(&mut self) -> Eval {
+        let mut left = self.relational()?;
+        while let Some(op) = self.peek_op(&["==", "!=", "===", "!=="]) {
+            self.pos += 1;
+            let right = self.relational()?;
+            left = match (left, right) {
+                (Some(l), Some(r)) => {
+                    let equal = loosely_equal(&l, &r);
+                    Some(Value::Bool(if op.starts_with('!') {
+                        !equal
+                    } else {
+                        equal
+                    }))
+                }
+                _ => None,
+            };
+        }
+        Some(left)
+    }
+
+    fn relational(&mut self) -> Eval {
+        let mut left = self.additive()?;
+        while let Some(op) = self.peek_op(&[">=", "<=", ">", "<"]) {
+            self.pos += 1;
+            let right = self.additive()?;
+            let operands = left
+                .as_ref()
+                .and_then(value_as_f64)
+                .zip(right.as_ref().and_then(value_as_f64));
+            left = operands.map(|(l, r)| {
+                Value::Bool(match op {
+                    ">=" => l >= r,
+                    "<=" => l <= r,
+                    ">" => l > r,
+                    _ => l < r,
+                })
+            });
+        }
+        Some(left)
+    }
+
+    fn additive(&mut self) -> Eval {
+        let mut left = self.multiplicative()?;
+        while let Some(op) = self.peek_op(&["+", "-"]) {
+            self.pos += 1;
+            let right = self.multiplicative()?;
+            left = match (left, right) {
+                // `+` concatenates as soon as one side is a non-numeric string.
+                (Some(l), Some(r))
+                    if op == "+"
+                        && (matches!(l, Value::String(_)) || matches!(r, Value::String(_)))
+                        && (value_as_f64(&l).is_none() || value_as_f64(&r).is_none()) =>
+                {
+                    Some(Value::String(format!(
+                        "{}{}",
+                        value_as_str(&l).unwrap_or_default(),
+                        value_as_str(&r).unwrap_or_default()
+                    )))
+                }
+                (Some(l), Some(r)) => {
+                    let (l, r) = (value_as_f64(&l), value_as_f64(&r));
+                    l.zip(r)
+                        .and_then(|(l, r)| number(if op == "+" { l + r } else { l - r }))
+                }
+                _ => None,
+            };
+        }
+        Some(left)
+    }
+
+    fn multiplicative(&mut self) -> Eval {
+        let mut left = self.unary()?;
+        while let Some(op) = self.peek_op(&["*", "/", "%"]) {
+            self.pos += 1;
+            let right = self.unary()?;
+            let operands = left
+                .as_ref()
+                .and_then(value_as_f64)
+                .zip(right.as_ref().and_then(value_as_f64));
+            left = operands.and_then(|(l, r)| {
+                number(match op {
+                    "*" => l * r,
+                    "/" => l / r,
+                    _ => l % r,
+                })
+            });
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Eval {
+        if self.eat("!") {
+            let value = self.unary()?;
+            return Some(value.map(|v| Value::Bool(!truthy(&v))));
+        }
+        if self.eat("-") {
+            let value = self.unary()?;
+            return Some(
+                value
+                    .as_ref()
+                    .and_then(value_as_f64)
+                    .and_then(|v| number(-v)),
+            );
+        }
+        if self.eat("+") {
+            let value = self.unary()?;
+            return Some(value.as_ref().and_then(value_as_f64).and_then(number));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Eval {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Some(number(n)),
+            Token::Str(s) => Some(Some(Value::String(s))),
+            Token::Op("(") => {
+                let value = self.ternary()?;
+                self.eat(")").then_some(value)
+            }
+            Token::Ident(name) => self.identifier(&name),
+            Token::Op(_) => None,
+        }
+    }
+
+    fn identifier(&mut self, name: &str) -> Eval {
+        if let Some((target, method)) = name.rsplit_once('.')
+            && STRING_METHODS.contains(&method)
+            && self.eat("(")
+        {
+            let arg = self.ternary()?;
+            if !self.eat(")") {
+                return None;
+            }
+            let subject = self.lookup(target);
+            let (Some(subject), Some(arg)) = (
+                subject.as_ref().and_then(value_as_str),
+                arg.as_ref().and_then(value_as_str),
+            ) else {
+                return Some(None);
+            };
+            let result = match method.to_ascii_lowercase().as_str() {
+                "contains" => subject.contains(&arg),
+                "startswith" => subject.starts_with(&arg),
+                _ => subject.ends_with(&arg),
+            };
+            return Some(Some(Value::Bool(result)));
+        }
+        Some(self.lookup(name))
+    }
+
+    fn lookup(&self, name: &str) -> Option<Value> {
+        if name.eq_ignore_ascii_case("true") {
+            return Some(Value::Bool(true));
+        }
+        if name.eq_ignore_ascii_case("false") {
+            return Some(Value::Bool(false));
+        }
+        self.user_values
+            .get(name.trim_end_matches(".value"))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workshop_conditions_with_ternaries_and_arithmetic() {
+        let users = BTreeMap::from([
+            ("glow".to_string(), Value::from(2.0)),
+            ("style".to_string(), Value::from("2")),
+            ("scheme".to_string(), Value::from("night_blue")),
+            ("showclock".to_string(), Value::Bool(true)),
+            ("opacity".to_string(), Value::from(0.35)),
+        ]);
+        let eval = |expr: &str| eval_visible_expr(expr, &users);
+
+        assert_eq!(eval("glow*2 > 3"), Some(true));
+        assert_eq!(eval("glow * 2 + 1 >= 6"), Some(false));
+        assert_eq!(eval("(style.value - 1) % 2 == 1"), Some(true));
+        assert_eq!(eval("opacity*100 < 50 && showclock.value"), Some(true));
+        assert_eq!(eval("-glow < 0"), Some(true));
+        assert_eq!(eval("style.value == 2 ? glow > 1 : false"), Some(true));
+        assert_eq!(
+            eval("style.value == 1 ? true : scheme.value.startsWith('day')"),
+            Some(false)
+        );
+        assert_eq!(
+            eval("showclock ? style.value == 1 ? 1 : 0 : 1"),
+            Some(false)
+        );
+        assert_eq!(
+            eval("scheme.value + '_alt' == 'night_blue_alt'"),
+            Some(true)
+        );
+        assert_eq!(
+            eval("style.value === '2' || missing.value == 1"),
+            Some(true)
+        );
+        assert_eq!(eval("style = 2"), Some(true));
+        // Unknown properties leave the decision to the static value...
+        assert_eq!(eval("missing.value * 2 > 1"), None);
+        assert_eq!(eval("missing ? 1 : 0"), None);
+        // ...and so does a malformed condition.
+        assert_eq!(eval("glow >"), None);
+        assert_eq!(eval("(glow > 1"), None);
+        assert_eq!(eval("glow > 1 ? 1"), None);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::i18n::{Msg, warn_msg};
+use crate::playback::{get_mpv_property, mpv_ipc_socket_path};
+use anyhow::Result;
+use std::time::Duration;
+
+/// Status refresh when systemd did not ask for watchdog pings.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sends `state` (`READY=1`, `WATCHDOG=1`, `STATUS=...`, newline separated)
+/// to the systemd notify socket. `Ok(false)` when not started by systemd.
+#[cfg(feature = "systemd")]
+pub fn notify(state: &str) -> Result<bool> {
+    use anyhow::Context;
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return Ok(false);
+    };
+    let path = path.to_string_lossy().to_string();
+    let addr = match path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name.as_bytes()),
+        None => SocketAddr::from_pathname(&path),
+    }
+    .with_context(|| format!("Invalid NOTIFY_SOCKET {}", path))?;
+    let socket = UnixDatagram::unbound().context("Failed creating notify socket")?;
+    socket
+        .send_to_addr(state.as_bytes(), &addr)
+        .with_context(|| format!("Failed notifying systemd at {}", path))?;
+    Ok(true)
+}
+
+/// Built without the `systemd` feature: nothing is sent.
+#[cfg(not(feature = "systemd"))]
+pub fn notify(_state: &str) -> Result<bool> {
+    Ok(false)
+}
+
+/// Half of `WATCHDOG_USEC` when the watchdog is meant for this process.
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec = std::env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+    if let Ok(pid) = std::env::var("WATCHDOG_PID")
+        && pid.parse::<u32>().ok()? != std::process::id()
+    {
+        return None;
+    }
+    (usec > 0).then(|| Duration::from_micros(usec / 2))
+}
+
+/// Whether the mpv on `monitor` still answers IPC. A wedged mpv never
+/// replies, so the request times out.
+pub fn playback_responding(monitor: &str) -> bool {
+    get_mpv_property(monitor, "pid").is_ok()
+}
+
+/// Monitors whose playback runs through mpv and can be supervised; static
+/// backends (swww, hyprpaper) have no process of ours to watch.
+pub fn supervised_monitors(monitors: &[String]) -> Vec<String> {
+    monitors
+        .iter()
+        .filter(|monitor| mpv_ipc_socket_path(monitor).exists())
+        .cloned()
+        .collect()
+}
+
+/// Notify message for one health check: a watchdog ping only when every
+/// supervised monitor answers, so systemd restarts a wedged engine.
+pub fn watchdog_message(stalled: &[String], supervised: usize) -> String {
+    if stalled.is_empty() {
+        return format!("WATCHDOG=1\nSTATUS={} monitors playing", supervised);
+    }
+    format!("STATUS=playback not responding on {}", stalled.join(", "))
+}
+
+/// Reports `READY=1` and then pings the watchdog while the playback on
+/// `monitors` stays healthy. Never returns unless notifying fails.
+pub fn run_watchdog(monitors: &[String]) -> Result<()> {
+    let supervised = supervised_monitors(monitors);
+    let sent = notify(&format!(
+        "READY=1\nSTATUS={} monitors playing",
+        supervised.len()
+    ))?;
+    if !sent && std::env::var_os("NOTIFY_SOCKET").is_some() {
+        warn_msg!(Msg::SystemdFeatureMissing);
+    }
+    let interval = watchdog_interval().unwrap_or(IDLE_CHECK_INTERVAL);
+    eprintln!(
+        "[ok] supervising {} monitors, checking every {}s",
+        supervised.len(),
+        interval.as_secs_f32()
+    );
+    loop {
+        std::thread::sleep(interval);
+        let stalled = supervised
+            .iter()
+            .filter(|monitor| !playback_responding(monitor))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !stalled.is_empty() {
+            warn_msg!(Msg::PlaybackStalled, stalled.join(", "));
+        }
+        notify(&watchdog_message(&stalled, supervised.len()))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdog_is_only_pinged_while_every_monitor_answers() {
+        assert_eq!(
+            watchdog_message(&[], 2),
+            "WATCHDOG=1\nSTATUS=2 monitors playing"
+        );
+        let message = watchdog_message(&["DP-1".to_string()], 2);
+        assert!(!message.contains("WATCHDOG=1"), "{}", message);
+        assert_eq!(message, "STATUS=playback not responding on DP-1");
+    }
+}
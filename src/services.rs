@@ -1,4 +1,10 @@
+use crate::app_paths::user_config_path;
+use crate::command_runner::CommandExt;
+use crate::i18n::{Msg, warn_msg};
+use crate::instance::{DEFAULT_INSTANCE, current_instance, force_teardown};
+use crate::startup_config::load_config;
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
@@ -18,87 +24,214 @@ pub fn default_services() -> Vec<String> {
     DEFAULT_SERVICES.iter().map(|s| (*s).to_string()).collect()
 }
 
-pub fn stop_services(services: &[String], dry_run: bool) -> Result<()> {
-    for svc in services {
-        if dry_run {
-            println!("[dry-run] systemctl --user stop {}", svc);
-            continue;
+/// `conflicting_services` from the user (or system) config, or
+/// [`DEFAULT_SERVICES`] when it is not set.
+pub fn configured_services() -> Vec<String> {
+    match load_config(&user_config_path()) {
+        Ok(cfg) => cfg.conflicting_services.unwrap_or_else(default_services),
+        Err(err) => {
+            warn_msg!(Msg::DefaultServicesList, err);
+            default_services()
         }
+    }
+}
 
-        let output = Command::new("systemctl")
-            .arg("--user")
-            .arg("stop")
-            .arg(svc)
-            .output()
-            .with_context(|| format!("Failed running systemctl for {}", svc))?;
+/// `--service` values when given, otherwise the configured list.
+pub fn effective_services(services: Vec<String>) -> Vec<String> {
+    if services.is_empty() {
+        configured_services()
+    } else {
+        services
+    }
+}
 
-        if output.status.success() {
-            println!("[ok] stopped {}", svc);
-        } else {
-            let err = String::from_utf8_lossy(&output.stderr);
-            eprintln!("[warn] could not stop {}: {}", svc, err.trim());
+/// A conflicting daemon: a systemd user unit, or (`process:<name>`) a
+/// process started outside systemd.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceTarget {
+    Unit(String),
+    Process(String),
+}
+
+impl ServiceTarget {
+    pub fn parse(raw: &str) -> Self {
+        match raw.strip_prefix("process:") {
+            Some(name) => ServiceTarget::Process(name.trim().to_string()),
+            None => ServiceTarget::Unit(raw.trim().to_string()),
+        }
+    }
+
+    /// Unit systemd manages this target through, if any: process entries
+    /// use `<name>.service` when such a user unit is installed.
+    fn systemd_unit(&self) -> Result<Option<String>> {
+        match self {
+            ServiceTarget::Unit(unit) => Ok(Some(unit.clone())),
+            ServiceTarget::Process(name) => {
+                let unit = format!("{}.service", name);
+                Ok(unit_loaded(&unit)?.then_some(unit))
+            }
+        }
+    }
+}
+
+fn unit_loaded(unit: &str) -> Result<bool> {
+    let out = Command::new("systemctl")
+        .args(["--user", "show", "-p", "LoadState", "--value", unit])
+        .run_output()
+        .with_context(|| format!("Failed checking {}", unit))?;
+    Ok(out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "loaded")
+}
+
+fn process_pids(name: &str) -> Result<Vec<u32>> {
+    let out = Command::new("pgrep")
+        .args(["-x", name])
+        .run_output()
+        .with_context(|| format!("Failed running pgrep for {}", name))?;
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|l| l.trim().parse().ok())
+        .collect())
+}
+
+fn systemctl_unit(verb: &str, unit: &str) -> Result<std::process::Output> {
+    Command::new("systemctl")
+        .args(["--user", verb, unit])
+        .run_output()
+        .with_context(|| format!("Failed running systemctl for {}", unit))
+}
+
+pub fn stop_services(services: &[String], dry_run: bool) -> Result<()> {
+    // The default instance owns the desktop's daemons; other instances leave
+    // them to it.
+    let instance = current_instance();
+    if instance != DEFAULT_INSTANCE && !force_teardown() {
+        warn_msg!(Msg::InstanceLeavesServices, instance);
+        return Ok(());
+    }
+    for svc in services {
+        let target = ServiceTarget::parse(svc);
+        match (&target, target.systemd_unit()?) {
+            (_, Some(unit)) => {
+                if dry_run {
+                    eprintln!("[dry-run] systemctl --user stop {}", unit);
+                    continue;
+                }
+                let output = systemctl_unit("stop", &unit)?;
+                if output.status.success() {
+                    eprintln!("[ok] stopped {}", unit);
+                } else {
+                    let err = String::from_utf8_lossy(&output.stderr);
+                    warn_msg!(Msg::StopServiceFailed, unit, err.trim());
+                }
+            }
+            (ServiceTarget::Process(name), None) => {
+                if dry_run {
+                    eprintln!("[dry-run] pkill -x {}", name);
+                    continue;
+                }
+                // pkill exits 1 when nothing matched, which is fine here.
+                let output = Command::new("pkill")
+                    .args(["-x", name])
+                    .run_output()
+                    .with_context(|| format!("Failed running pkill for {}", name))?;
+                if output.status.success() {
+                    eprintln!("[ok] killed {}", name);
+                }
+            }
+            (ServiceTarget::Unit(_), None) => {}
         }
     }
     Ok(())
 }
 
 fn unit_active(service: &str) -> Result<bool> {
-    let out = Command::new("systemctl")
-        .arg("--user")
-        .arg("is-active")
-        .arg(service)
-        .output()
-        .with_context(|| format!("Failed checking state for {}", service))?;
-    Ok(out.status.success())
+    Ok(systemctl_unit("is-active", service)?.status.success())
 }
 
 pub fn start_services(services: &[String], dry_run: bool) -> Result<()> {
     let mut failures = Vec::new();
     for svc in services {
+        let target = ServiceTarget::parse(svc);
+        let Some(unit) = target.systemd_unit()? else {
+            warn_msg!(Msg::NoSystemdUnit, svc);
+            continue;
+        };
         if dry_run {
-            println!("[dry-run] systemctl --user start {}", svc);
+            eprintln!("[dry-run] systemctl --user start {}", unit);
             continue;
         }
 
-        let output = Command::new("systemctl")
-            .arg("--user")
-            .arg("start")
-            .arg(svc)
-            .output()
-            .with_context(|| format!("Failed running systemctl for {}", svc))?;
-
+        let output = systemctl_unit("start", &unit)?;
         if !output.status.success() {
             let err = String::from_utf8_lossy(&output.stderr);
-            failures.push(format!("{}: {}", svc, err.trim()));
+            failures.push(format!("{}: {}", unit, err.trim()));
             continue;
         }
 
         let deadline = Instant::now() + Duration::from_secs(8);
         let mut is_up = false;
         while Instant::now() < deadline {
-            if unit_active(svc)? {
+            if unit_active(&unit)? {
                 is_up = true;
                 break;
             }
             thread::sleep(Duration::from_millis(250));
         }
         if is_up {
-            println!("[ok] started {}", svc);
+            eprintln!("[ok] started {}", unit);
         } else {
-            failures.push(format!("{}: started but did not become active", svc));
+            failures.push(format!("{}: started but did not become active", unit));
         }
     }
 
     if failures.is_empty() {
         return Ok(());
     }
-    anyhow::bail!("Some services failed to start:\n- {}", failures.join("\n- "));
+    anyhow::bail!(
+        "Some services failed to start:\n- {}",
+        failures.join("\n- ")
+    );
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatus {
+    pub service: String,
+    /// `systemd` when managed through a user unit, `process` otherwise.
+    pub managed_by: &'static str,
+    pub unit: Option<String>,
+    pub running: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pids: Vec<u32>,
+}
+
+/// Detection only: which conflicting daemons are running right now.
+pub fn services_status(services: &[String]) -> Result<Vec<ServiceStatus>> {
+    let mut out = Vec::new();
+    for svc in services {
+        let target = ServiceTarget::parse(svc);
+        let unit = target.systemd_unit()?;
+        let pids = match &target {
+            ServiceTarget::Process(name) => process_pids(name)?,
+            ServiceTarget::Unit(_) => Vec::new(),
+        };
+        let running = match &unit {
+            Some(unit) => unit_active(unit)?,
+            None => !pids.is_empty(),
+        };
+        out.push(ServiceStatus {
+            service: svc.clone(),
+            managed_by: if unit.is_some() { "systemd" } else { "process" },
+            unit,
+            running,
+            pids,
+        });
+    }
+    Ok(out)
 }
 
 fn autostart_unit_path() -> PathBuf {
-    if let Ok(home) = std::env::var("HOME") {
-        return PathBuf::from(home)
-            .join(".config/systemd/user/kitsune-livewallpaper.service");
+    if let Some(config) = crate::app_paths::config_home() {
+        return config.join("systemd/user/kitsune-livewallpaper.service");
     }
     PathBuf::from("kitsune-livewallpaper.service")
 }
@@ -113,11 +246,7 @@ fn systemctl_user(args: &[&str]) -> Result<()> {
         return Ok(());
     }
     let err = String::from_utf8_lossy(&output.stderr);
-    anyhow::bail!(
-        "systemctl --user {} failed: {}",
-        args.join(" "),
-        err.trim()
-    );
+    anyhow::bail!("systemctl --user {} failed: {}", args.join(" "), err.trim());
 }
 
 pub fn install_autostart_service(overwrite: bool, dry_run: bool) -> Result<()> {
@@ -144,8 +273,8 @@ WantedBy=default.target
 "#;
 
     if dry_run {
-        println!("[dry-run] write {}", unit_path.display());
-        println!("[dry-run] systemctl --user daemon-reload");
+        eprintln!("[dry-run] write {}", unit_path.display());
+        eprintln!("[dry-run] systemctl --user daemon-reload");
         return Ok(());
     }
 
@@ -153,38 +282,39 @@ WantedBy=default.target
         fs::create_dir_all(parent)
             .with_context(|| format!("Failed creating {}", parent.display()))?;
     }
-    fs::write(&unit_path, unit).with_context(|| format!("Failed writing {}", unit_path.display()))?;
+    fs::write(&unit_path, unit)
+        .with_context(|| format!("Failed writing {}", unit_path.display()))?;
     systemctl_user(&["daemon-reload"])?;
-    println!("[ok] installed {}", unit_path.display());
+    eprintln!("[ok] installed {}", unit_path.display());
     Ok(())
 }
 
 pub fn enable_autostart_service(dry_run: bool) -> Result<()> {
     if dry_run {
-        println!("[dry-run] systemctl --user enable --now kitsune-livewallpaper.service");
+        eprintln!("[dry-run] systemctl --user enable --now kitsune-livewallpaper.service");
         return Ok(());
     }
     systemctl_user(&["enable", "--now", "kitsune-livewallpaper.service"])?;
-    println!("[ok] enabled kitsune-livewallpaper.service");
+    eprintln!("[ok] enabled kitsune-livewallpaper.service");
     Ok(())
 }
 
 pub fn disable_autostart_service(dry_run: bool) -> Result<()> {
     if dry_run {
-        println!("[dry-run] systemctl --user disable --now kitsune-livewallpaper.service");
+        eprintln!("[dry-run] systemctl --user disable --now kitsune-livewallpaper.service");
         return Ok(());
     }
     systemctl_user(&["disable", "--now", "kitsune-livewallpaper.service"])?;
-    println!("[ok] disabled kitsune-livewallpaper.service");
+    eprintln!("[ok] disabled kitsune-livewallpaper.service");
     Ok(())
 }
 
 pub fn remove_autostart_service(dry_run: bool) -> Result<()> {
     let unit_path = autostart_unit_path();
     if dry_run {
-        println!("[dry-run] systemctl --user disable --now kitsune-livewallpaper.service");
-        println!("[dry-run] rm {}", unit_path.display());
-        println!("[dry-run] systemctl --user daemon-reload");
+        eprintln!("[dry-run] systemctl --user disable --now kitsune-livewallpaper.service");
+        eprintln!("[dry-run] rm {}", unit_path.display());
+        eprintln!("[dry-run] systemctl --user daemon-reload");
         return Ok(());
     }
     let _ = systemctl_user(&["disable", "--now", "kitsune-livewallpaper.service"]);
@@ -193,32 +323,72 @@ pub fn remove_autostart_service(dry_run: bool) -> Result<()> {
             .with_context(|| format!("Failed removing {}", unit_path.display()))?;
     }
     systemctl_user(&["daemon-reload"])?;
-    println!("[ok] removed autostart service");
+    eprintln!("[ok] removed autostart service");
     Ok(())
 }
 
-pub fn autostart_service_status() -> Result<()> {
+#[derive(Debug, Clone, Serialize)]
+pub struct AutostartStatus {
+    pub unit_file: String,
+    pub exists: bool,
+    /// `systemctl --user is-enabled` output.
+    pub enabled: String,
+    /// `systemctl --user is-active` output.
+    pub active: String,
+}
+
+pub fn autostart_service_status() -> Result<AutostartStatus> {
     let unit_path = autostart_unit_path();
-    println!(
-        "[ok] unit_file={} exists={}",
-        unit_path.display(),
-        unit_path.is_file()
-    );
-    for args in [
-        ["is-enabled", "kitsune-livewallpaper.service"],
-        ["is-active", "kitsune-livewallpaper.service"],
-    ] {
+    let query = |verb: &str| -> Result<String> {
         let out = Command::new("systemctl")
             .arg("--user")
-            .args(args)
+            .args([verb, "kitsune-livewallpaper.service"])
             .output()
             .with_context(|| "Failed querying systemctl status")?;
         let val = String::from_utf8_lossy(&out.stdout).trim().to_string();
-        println!(
-            "[ok] {} => {}",
-            args.join(" "),
-            if val.is_empty() { "unknown" } else { &val }
-        );
+        Ok(if val.is_empty() {
+            "unknown".to_string()
+        } else {
+            val
+        })
+    };
+    Ok(AutostartStatus {
+        unit_file: unit_path.to_string_lossy().to_string(),
+        exists: unit_path.is_file(),
+        enabled: query("is-enabled")?,
+        active: query("is-active")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_runner::{MockRunner, with_runner};
+    use std::rc::Rc;
+
+    #[test]
+    fn process_entries_fall_back_to_pkill_without_a_unit() {
+        let mock = Rc::new(MockRunner::new());
+        // No user unit is loaded and nothing reports active.
+        mock.respond("systemctl", false, "not-found\n");
+        mock.respond("pgrep", true, "4242\n");
+        let services = vec![
+            "process:hyprpaper".to_string(),
+            "swww-daemon.service".to_string(),
+        ];
+        with_runner(mock.clone(), || {
+            let status = services_status(&services).unwrap();
+            assert_eq!(status[0].managed_by, "process");
+            assert!(status[0].running);
+            assert_eq!(status[0].pids, vec![4242]);
+            assert_eq!(status[1].managed_by, "systemd");
+            assert!(!status[1].running);
+
+            stop_services(&services, false).unwrap();
+        });
+        let commands = mock.commands();
+        assert!(commands.contains(&"pkill -x hyprpaper".to_string()));
+        assert!(commands.contains(&"systemctl --user stop swww-daemon.service".to_string()));
+        assert!(!commands.iter().any(|c| c.contains("stop hyprpaper")));
     }
-    Ok(())
 }
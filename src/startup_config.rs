@@ -1,5 +1,13 @@
-use crate::cli::PlaybackProfile;
+use crate::app_paths::{system_config_path, user_config_path};
+use crate::cli::{PlaybackProfile, TextRenderMode};
+use crate::custom_profiles::CustomProfile;
+use crate::i18n::{Msg, warn_msg};
+use crate::layer_shell::LayerShellPrefs;
+use crate::profile_rules::ProfileRule;
+use crate::scene_text::parse_timezone;
+use crate::static_backend::StaticBackendPref;
 use anyhow::{Context, Result};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fs;
@@ -10,6 +18,43 @@ use std::path::{Path, PathBuf};
 pub struct StartupConfig {
     pub version: u32,
     pub entries: Vec<MonitorEntry>,
+    /// Global overlay defaults for scene-play/scene-gpu-play.
+    #[serde(default)]
+    pub overlay_defaults: OverlayPrefs,
+    /// Per-wallpaper overlay preferences keyed by wallpaper folder (workshop id).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub wallpaper_overlays: BTreeMap<String, OverlayPrefs>,
+    /// Daemons stopped before a wallpaper starts: systemd user units, or
+    /// `process:<name>` for daemons started outside systemd. Unset keeps the
+    /// built-in list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflicting_services: Option<Vec<String>>,
+    /// Same as passing `--safe` to every command.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub safe_mode: bool,
+    /// Image daemon for still wallpapers (`auto`, `swww`, `hyprpaper`, `off`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub static_backend: Option<StaticBackendPref>,
+    /// Refresh rate (Hz) per output, for compositors that cannot be queried;
+    /// used as mpv's display fps when `--display-fps` is not given.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub monitor_refresh: BTreeMap<String, u32>,
+    /// Duration of live property transitions (`property set`); 0 jumps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property_transition_ms: Option<u32>,
+    /// Extra mpv options per playback profile (`performance`, `balanced`,
+    /// `quality`) or for every profile (`all`), as `key=value`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mpv_options: BTreeMap<String, Vec<String>>,
+    /// User playback profiles, selectable with `--profile <name>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, CustomProfile>,
+    /// Layer-shell layer and namespace of the mpvpaper surface per output.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub monitor_layers: BTreeMap<String, LayerShellPrefs>,
+    /// Default options picked by project.json tags and type at apply time.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profile_rules: Vec<ProfileRule>,
 }
 
 impl Default for StartupConfig {
@@ -17,10 +62,74 @@ impl Default for StartupConfig {
         Self {
             version: 1,
             entries: Vec::new(),
+            overlay_defaults: OverlayPrefs::default(),
+            wallpaper_overlays: BTreeMap::new(),
+            conflicting_services: None,
+            safe_mode: false,
+            static_backend: None,
+            monitor_refresh: BTreeMap::new(),
+            property_transition_ms: None,
+            mpv_options: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            monitor_layers: BTreeMap::new(),
+            profile_rules: Vec::new(),
         }
     }
 }
 
+/// Unset fields fall through to the next level (CLI > wallpaper > global).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverlayPrefs {
+    /// Text layers taken from scene.json.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_overlay: Option<bool>,
+    /// Generic drawtext clock for scenes without text objects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_clock: Option<bool>,
+    /// IANA timezone for clock/date text layers (local time when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Drawtext filter (default) or mpv OSD for the text layers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_mode: Option<TextRenderMode>,
+    /// Color or wallpaper shown through transparent (alpha) videos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha_background: Option<String>,
+}
+
+impl OverlayPrefs {
+    pub fn is_empty(&self) -> bool {
+        self.text_overlay.is_none()
+            && self.fallback_clock.is_none()
+            && self.timezone.is_none()
+            && self.text_mode.is_none()
+            && self.alpha_background.is_none()
+    }
+
+    /// Applies the fields set in `other`; `clear` drops the stored values first.
+    pub fn update(&mut self, other: &OverlayPrefs, clear: bool) {
+        if clear {
+            *self = OverlayPrefs::default();
+        }
+        self.text_overlay = other.text_overlay.or(self.text_overlay);
+        self.fallback_clock = other.fallback_clock.or(self.fallback_clock);
+        self.timezone = other.timezone.clone().or(self.timezone.take());
+        self.text_mode = other.text_mode.or(self.text_mode);
+        self.alpha_background = other
+            .alpha_background
+            .clone()
+            .or(self.alpha_background.take());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedOverlays {
+    pub text_overlay: bool,
+    pub fallback_clock: bool,
+    pub timezone: Option<Tz>,
+    pub text_mode: TextRenderMode,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorEntry {
     pub monitor: String,
@@ -62,10 +171,17 @@ pub struct StartupState {
     pub monitor_fingerprints: BTreeMap<String, u64>,
 }
 
+/// Reads `path`; when it is the (missing) per-user default, the system-wide
+/// `/etc/kitsune-livewallpaper/config.json` is used instead.
 pub fn load_config(path: &Path) -> Result<StartupConfig> {
-    if !path.is_file() {
+    let system = system_config_path();
+    let path = if path.is_file() {
+        path
+    } else if path == user_config_path() && system.is_file() {
+        system.as_path()
+    } else {
         return Ok(StartupConfig::default());
-    }
+    };
     let raw = fs::read(path).with_context(|| format!("Failed reading {}", path.display()))?;
     let cfg: StartupConfig = serde_json::from_slice(&raw)
         .with_context(|| format!("Invalid JSON in {}", path.display()))?;
@@ -97,11 +213,66 @@ pub fn remove_entry(cfg: &mut StartupConfig, monitor: &str) -> bool {
     before != cfg.entries.len()
 }
 
-fn default_state_path() -> PathBuf {
-    if let Ok(home) = std::env::var("HOME") {
-        return PathBuf::from(home).join(".local/state/kitsune-livewallpaper/start-config-state.json");
+pub fn wallpaper_overlay_key(root: &Path) -> String {
+    root.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().to_string())
+}
+
+pub fn resolve_overlays(cfg: &StartupConfig, root: &Path, cli: &OverlayPrefs) -> ResolvedOverlays {
+    let wallpaper = cfg
+        .wallpaper_overlays
+        .get(&wallpaper_overlay_key(root))
+        .cloned()
+        .unwrap_or_default();
+    let levels = [cli, &wallpaper, &cfg.overlay_defaults];
+    ResolvedOverlays {
+        text_overlay: levels.iter().find_map(|p| p.text_overlay).unwrap_or(true),
+        fallback_clock: levels
+            .iter()
+            .find_map(|p| p.fallback_clock)
+            .unwrap_or(false),
+        timezone: levels
+            .iter()
+            .find_map(|p| p.timezone.as_deref())
+            .and_then(|name| match parse_timezone(name) {
+                Ok(tz) => Some(tz),
+                Err(err) => {
+                    warn_msg!(Msg::TimezoneLocalFallback, err);
+                    None
+                }
+            }),
+        text_mode: levels.iter().find_map(|p| p.text_mode).unwrap_or_default(),
     }
-    PathBuf::from("/tmp/kitsune-livewallpaper-start-config-state.json")
+}
+
+/// Configured alpha-video background for `root` (wallpaper, then global).
+pub fn alpha_background_pref(cfg: &StartupConfig, root: Option<&Path>) -> Option<String> {
+    root.and_then(|root| cfg.wallpaper_overlays.get(&wallpaper_overlay_key(root)))
+        .and_then(|p| p.alpha_background.clone())
+        .or_else(|| cfg.overlay_defaults.alpha_background.clone())
+}
+
+/// [`alpha_background_pref`] read from the user (or system) config.
+pub fn alpha_background_for_wallpaper(root: Option<&Path>) -> Option<String> {
+    let cfg = load_config(&user_config_path()).unwrap_or_else(|err| {
+        warn_msg!(Msg::AlphaBackgroundPrefIgnored, err);
+        StartupConfig::default()
+    });
+    alpha_background_pref(&cfg, root)
+}
+
+/// Overlay settings for a scene launch, read from the user (or system) config.
+pub fn overlays_for_wallpaper(root: &Path, cli: &OverlayPrefs) -> ResolvedOverlays {
+    let cfg = load_config(&user_config_path()).unwrap_or_else(|err| {
+        warn_msg!(Msg::OverlayPrefsIgnored, err);
+        StartupConfig::default()
+    });
+    resolve_overlays(&cfg, root, cli)
+}
+
+fn default_state_path() -> PathBuf {
+    crate::app_paths::app_state_dir().join("start-config-state.json")
 }
 
 pub fn load_state() -> Result<StartupState> {
@@ -134,3 +305,62 @@ pub fn entry_fingerprint(entry: &MonitorEntry) -> u64 {
     }
     hasher.finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_prefs_resolve_cli_then_wallpaper_then_global() {
+        let root = Path::new("/data/downloads/123456");
+        let mut cfg = StartupConfig::default();
+        let none = OverlayPrefs::default();
+        assert_eq!(
+            resolve_overlays(&cfg, root, &none),
+            ResolvedOverlays {
+                text_overlay: true,
+                fallback_clock: false,
+                timezone: None,
+                text_mode: TextRenderMode::Drawtext,
+            }
+        );
+
+        cfg.overlay_defaults.fallback_clock = Some(true);
+        cfg.wallpaper_overlays.insert(
+            "123456".to_string(),
+            OverlayPrefs {
+                text_overlay: Some(false),
+                fallback_clock: None,
+                timezone: Some("Asia/Tokyo".to_string()),
+                text_mode: Some(TextRenderMode::Osd),
+                alpha_background: Some("#203040".to_string()),
+            },
+        );
+        let resolved = resolve_overlays(&cfg, root, &none);
+        assert!(!resolved.text_overlay);
+        assert!(resolved.fallback_clock);
+
+        assert_eq!(resolved.timezone, Some(chrono_tz::Asia::Tokyo));
+        assert_eq!(resolved.text_mode, TextRenderMode::Osd);
+
+        let cli = OverlayPrefs {
+            text_overlay: Some(true),
+            fallback_clock: Some(false),
+            timezone: Some("Europe/Madrid".to_string()),
+            text_mode: None,
+            alpha_background: None,
+        };
+        let resolved = resolve_overlays(&cfg, root, &cli);
+        assert!(resolved.text_overlay);
+        assert!(!resolved.fallback_clock);
+        assert_eq!(resolved.timezone, Some(chrono_tz::Europe::Madrid));
+        assert_eq!(
+            alpha_background_pref(&cfg, Some(root)).as_deref(),
+            Some("#203040")
+        );
+        assert_eq!(alpha_background_pref(&cfg, None), None);
+
+        let legacy: StartupConfig = serde_json::from_str(r#"{"version":1,"entries":[]}"#).unwrap();
+        assert!(legacy.overlay_defaults.is_empty());
+    }
+}
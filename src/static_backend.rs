@@ -0,0 +1,252 @@
+use crate::app_paths::{app_state_dir, user_config_path};
+use crate::command_runner::CommandExt;
+use crate::i18n::{Msg, warn_msg};
+use crate::startup_config::load_config;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Image wallpaper daemon a still result can be handed to instead of mpvpaper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaticBackend {
+    Swww,
+    Hyprpaper,
+}
+
+/// `static_backend` in config.json; unset behaves as `auto`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaticBackendPref {
+    /// First running daemon: swww, then hyprpaper.
+    Auto,
+    Swww,
+    Hyprpaper,
+    /// Always play still images with mpvpaper.
+    Off,
+}
+
+impl StaticBackend {
+    fn daemon(self) -> &'static str {
+        match self {
+            StaticBackend::Swww => "swww-daemon",
+            StaticBackend::Hyprpaper => "hyprpaper",
+        }
+    }
+
+    fn show_commands(self, monitor: &str, image: &str) -> Vec<Vec<String>> {
+        let target = format!("{},{}", monitor, image);
+        let cmds: Vec<Vec<&str>> = match self {
+            StaticBackend::Swww => vec![vec![
+                "swww",
+                "img",
+                "--outputs",
+                monitor,
+                "--transition-type",
+                "none",
+                image,
+            ]],
+            StaticBackend::Hyprpaper => vec![
+                vec!["hyprctl", "hyprpaper", "preload", image],
+                vec!["hyprctl", "hyprpaper", "wallpaper", &target],
+            ],
+        };
+        cmds.into_iter()
+            .map(|c| c.into_iter().map(String::from).collect())
+            .collect()
+    }
+
+    /// Frees what the daemon holds for `monitor`; the daemon itself may be
+    /// shared with other outputs, so it keeps running.
+    fn clear_commands(self, monitor: &str, image: &str) -> Vec<String> {
+        let cmd = match self {
+            StaticBackend::Swww => ["swww", "clear", "--outputs", monitor],
+            StaticBackend::Hyprpaper => ["hyprctl", "hyprpaper", "unload", image],
+        };
+        cmd.into_iter().map(String::from).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaticWallpaper {
+    pub backend: StaticBackend,
+    pub image: String,
+}
+
+/// Monitors whose current wallpaper is held by an image daemon.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StaticWallpaperState {
+    pub monitors: BTreeMap<String, StaticWallpaper>,
+}
+
+fn state_path() -> PathBuf {
+    app_state_dir().join("static-wallpapers.json")
+}
+
+fn load_state() -> Result<StaticWallpaperState> {
+    let path = state_path();
+    if !path.is_file() {
+        return Ok(StaticWallpaperState::default());
+    }
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+fn save_state(state: &StaticWallpaperState) -> Result<()> {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(state)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+pub fn is_still_image(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .is_some_and(|e| matches!(e.as_str(), "png" | "jpg" | "jpeg" | "webp" | "bmp"))
+}
+
+fn daemon_running(backend: StaticBackend) -> bool {
+    Command::new("pgrep")
+        .args(["-x", backend.daemon()])
+        .run_output()
+        .is_ok_and(|out| out.status.success())
+}
+
+fn static_backend_pref() -> StaticBackendPref {
+    match load_config(&user_config_path()) {
+        Ok(cfg) => cfg.static_backend.unwrap_or(StaticBackendPref::Auto),
+        Err(err) => {
+            warn_msg!(Msg::StaticBackendConfigIgnored, err);
+            StaticBackendPref::Auto
+        }
+    }
+}
+
+fn pick_backend(pref: StaticBackendPref) -> Option<StaticBackend> {
+    let candidates: &[StaticBackend] = match pref {
+        StaticBackendPref::Auto => &[StaticBackend::Swww, StaticBackend::Hyprpaper],
+        StaticBackendPref::Swww => &[StaticBackend::Swww],
+        StaticBackendPref::Hyprpaper => &[StaticBackend::Hyprpaper],
+        StaticBackendPref::Off => &[],
+    };
+    candidates.iter().copied().find(|b| daemon_running(*b))
+}
+
+fn run_step(cmd: &[String], dry_run: bool) -> Result<bool> {
+    if dry_run {
+        eprintln!("[dry-run] {}", cmd.join(" "));
+        return Ok(true);
+    }
+    let output = Command::new(&cmd[0])
+        .args(&cmd[1..])
+        .run_output()
+        .with_context(|| format!("Failed running {}", cmd[0]))?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        warn_msg!(Msg::CommandFailed, cmd.join(" "), err.trim());
+    }
+    Ok(output.status.success())
+}
+
+/// Shows a still image through a running swww/hyprpaper daemon. Returns
+/// `false` (mpvpaper should play it) for non-images, when no daemon is up
+/// or when the daemon rejects the image.
+pub fn delegate_static_wallpaper(monitor: &str, entry: &Path, dry_run: bool) -> Result<bool> {
+    if !is_still_image(entry) {
+        return Ok(false);
+    }
+    let Some(backend) = pick_backend(static_backend_pref()) else {
+        return Ok(false);
+    };
+    let mut state = load_state()?;
+    let image = entry.to_string_lossy().to_string();
+    for cmd in backend.show_commands(monitor, &image) {
+        if !run_step(&cmd, dry_run)? {
+            warn_msg!(Msg::StaticBackendRejected, backend.daemon());
+            return Ok(false);
+        }
+    }
+    eprintln!(
+        "[ok] static wallpaper via {} monitor={} image={}",
+        backend.daemon(),
+        monitor,
+        image
+    );
+    if dry_run {
+        return Ok(true);
+    }
+    let previous = state
+        .monitors
+        .insert(monitor.to_string(), StaticWallpaper { backend, image });
+    // Switching daemons (or images under hyprpaper) leaves the old one loaded.
+    if let Some(previous) = previous
+        && previous != state.monitors[monitor]
+        && (previous.backend != backend || backend == StaticBackend::Hyprpaper)
+    {
+        run_step(
+            &previous.backend.clear_commands(monitor, &previous.image),
+            false,
+        )?;
+    }
+    save_state(&state)?;
+    Ok(true)
+}
+
+/// Hands `monitor` back to mpvpaper: clears the image a daemon shows there.
+pub fn release_static_wallpaper(monitor: &str, dry_run: bool) -> Result<()> {
+    let mut state = load_state()?;
+    let Some(current) = state.monitors.remove(monitor) else {
+        return Ok(());
+    };
+    run_step(
+        &current.backend.clear_commands(monitor, &current.image),
+        dry_run,
+    )?;
+    if dry_run {
+        return Ok(());
+    }
+    save_state(&state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_backends_build_show_and_clear_commands() {
+        assert!(is_still_image(Path::new("/w/preview.JPG")));
+        assert!(!is_still_image(Path::new("/w/preview.gif")));
+
+        let swww = StaticBackend::Swww.show_commands("DP-1", "/w/a.png");
+        assert_eq!(
+            swww[0].join(" "),
+            "swww img --outputs DP-1 --transition-type none /w/a.png"
+        );
+        assert_eq!(
+            StaticBackend::Swww
+                .clear_commands("DP-1", "/w/a.png")
+                .join(" "),
+            "swww clear --outputs DP-1"
+        );
+
+        let hypr = StaticBackend::Hyprpaper.show_commands("DP-1", "/w/a.png");
+        assert_eq!(hypr[0].join(" "), "hyprctl hyprpaper preload /w/a.png");
+        assert_eq!(
+            hypr[1].join(" "),
+            "hyprctl hyprpaper wallpaper DP-1,/w/a.png"
+        );
+        assert_eq!(
+            StaticBackend::Hyprpaper
+                .clear_commands("DP-1", "/w/a.png")
+                .join(" "),
+            "hyprctl hyprpaper unload /w/a.png"
+        );
+        assert_eq!(pick_backend(StaticBackendPref::Off), None);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::command_runner::CommandExt;
+use anyhow::{Context, Result, bail};
+use image::RgbaImage;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Output name of the first virtual output of the wlroots headless backend.
+pub const HEADLESS_OUTPUT: &str = "HEADLESS-1";
+/// `--instance` the helpers apply with, so a test never stops the wallpaper
+/// of the desktop it runs on.
+pub const TEST_INSTANCE: &str = "testsupport";
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static SESSION_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub width: u32,
+    pub height: u32,
+    /// wlroots renderer; `pixman` needs no GPU, `gles2`/`vulkan` test the
+    /// real one.
+    pub renderer: String,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            renderer: "pixman".to_string(),
+        }
+    }
+}
+
+/// sway config for a session with only the headless output.
+pub fn sway_config(options: &HeadlessOptions) -> String {
+    format!(
+        "output {} resolution {}x{}\ndefault_border none\n",
+        HEADLESS_OUTPUT, options.width, options.height
+    )
+}
+
+/// Wayland socket the compositor created in `runtime_dir`.
+pub fn find_wayland_socket(runtime_dir: &Path) -> Option<String> {
+    let mut sockets = fs::read_dir(runtime_dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_socket()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("wayland-"))
+        .collect::<Vec<_>>();
+    sockets.sort();
+    sockets.into_iter().next()
+}
+
+/// A sway running on the wlroots headless backend, with its own runtime,
+/// config and state dirs. Stopped and cleaned up on drop.
+pub struct HeadlessCompositor {
+    child: Child,
+    session_dir: PathBuf,
+    wayland_display: String,
+    pub options: HeadlessOptions,
+}
+
+impl HeadlessCompositor {
+    pub fn start(options: HeadlessOptions) -> Result<Self> {
+        let session_dir = std::env::temp_dir().join(format!(
+            "kwe-testsupport-{}-{}",
+            std::process::id(),
+            SESSION_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let runtime_dir = session_dir.join("runtime");
+        fs::create_dir_all(&runtime_dir)
+            .with_context(|| format!("Failed creating {}", runtime_dir.display()))?;
+        // Wayland clients refuse a runtime dir others can read.
+        fs::set_permissions(&runtime_dir, fs::Permissions::from_mode(0o700))?;
+        let config = session_dir.join("sway.conf");
+        fs::write(&config, sway_config(&options))
+            .with_context(|| format!("Failed writing {}", config.display()))?;
+
+        let child = Command::new("sway")
+            .arg("-c")
+            .arg(&config)
+            .env("WLR_BACKENDS", "headless")
+            .env("WLR_LIBINPUT_NO_DEVICES", "1")
+            .env("WLR_RENDERER", &options.renderer)
+            .env("XDG_RUNTIME_DIR", &runtime_dir)
+            .env_remove("WAYLAND_DISPLAY")
+            .env_remove("DISPLAY")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed starting sway (is it installed?)")?;
+        let mut compositor = Self {
+            child,
+            session_dir,
+            wayland_display: String::new(),
+            options,
+        };
+
+        let started = Instant::now();
+        compositor.wayland_display = loop {
+            if let Some(socket) = find_wayland_socket(&runtime_dir) {
+                break socket;
+            }
+            if let Some(status) = compositor.child.try_wait()? {
+                bail!("sway exited during startup ({})", status);
+            }
+            if started.elapsed() > STARTUP_TIMEOUT {
+                bail!(
+                    "sway created no Wayland socket in {}s",
+                    STARTUP_TIMEOUT.as_secs()
+                );
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+        Ok(compositor)
+    }
+
+    pub fn wayland_display(&self) -> &str {
+        &self.wayland_display
+    }
+
+    pub fn runtime_dir(&self) -> PathBuf {
+        self.session_dir.join("runtime")
+    }
+
+    /// `program` set up to run inside the headless session. Config and state
+    /// dirs point into the session so the user's config.json and history
+    /// stay untouched; the proxy cache is shared.
+    pub fn command(&self, program: impl AsRef<std::ffi::OsStr>) -> Command {
+        let mut cmd = Command::new(program);
+        cmd.env("WAYLAND_DISPLAY", &self.wayland_display)
+            .env("XDG_RUNTIME_DIR", self.runtime_dir())
+            .env("XDG_CONFIG_HOME", self.session_dir.join("config"))
+            .env("XDG_STATE_HOME", self.session_dir.join("state"))
+            .env("KWE_INSTANCE", TEST_INSTANCE)
+            .env_remove("DISPLAY")
+            .env_remove("HYPRLAND_INSTANCE_SIGNATURE");
+        cmd
+    }
+
+    /// Runs `<exe> apply <wallpaper>` on the virtual output. `exe` is the
+    /// engine binary, `env!("CARGO_BIN_EXE_kitsune-livewallpaper")` in an
+    /// integration test.
+    pub fn apply_wallpaper(
+        &self,
+        exe: &Path,
+        wallpaper: &str,
+        downloads_root: &Path,
+        extra_args: &[&str],
+    ) -> Result<()> {
+        let output = self
+            .command(exe)
+            .arg("apply")
+            .arg(wallpaper)
+            .args(["--monitor", HEADLESS_OUTPUT, "--keep-services"])
+            .arg("--downloads-root")
+            .arg(downloads_root)
+            .args(extra_args)
+            .run_output()
+            .with_context(|| format!("Failed running {}", exe.display()))?;
+        if !output.status.success() {
+            bail!(
+                "apply {} failed in the headless session: {}",
+                wallpaper,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// One screencopy frame of the virtual output (`grim`).
+    pub fn capture_frame(&self) -> Result<RgbaImage> {
+        let output = self
+            .command("grim")
+            .args(["-o", HEADLESS_OUTPUT, "-t", "png", "-"])
+            .run_output()
+            .context("Failed running grim (is it installed?)")?;
+        if !output.status.success() {
+            bail!(
+                "grim failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let frame = image::load_from_memory(&output.stdout).context("grim returned no PNG")?;
+        Ok(frame.to_rgba8())
+    }
+
+    /// Captures frames until `accept` holds, e.g. until mpvpaper has drawn
+    /// over the empty output. On timeout the last frame is saved in the
+    /// session dir, which is kept when the test panics.
+    pub fn wait_for_frame(
+        &self,
+        timeout: Duration,
+        accept: impl Fn(&RgbaImage) -> bool,
+    ) -> Result<RgbaImage> {
+        let started = Instant::now();
+        loop {
+            let frame = self.capture_frame()?;
+            if accept(&frame) {
+                return Ok(frame);
+            }
+            if started.elapsed() > timeout {
+                let path = self.session_dir.join("last-frame.png");
+                frame.save(&path).ok();
+                bail!(
+                    "No accepted frame after {}s; last one saved to {}",
+                    timeout.as_secs_f32(),
+                    path.display()
+                );
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Drop for HeadlessCompositor {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+        if !std::thread::panicking() {
+            fs::remove_dir_all(&self.session_dir).ok();
+        }
+    }
+}
+
+/// Average colour of `frame` as 0..255 RGB.
+pub fn mean_rgb(frame: &RgbaImage) -> [f32; 3] {
+    let mut sum = [0u64; 3];
+    for pixel in frame.pixels() {
+        for (acc, value) in sum.iter_mut().zip(pixel.0) {
+            *acc += value as u64;
+        }
+    }
+    let count = (frame.width() as u64 * frame.height() as u64).max(1) as f32;
+    sum.map(|v| v as f32 / count)
+}
+
+/// Share of pixels that differ from `background` by more than `tolerance`
+/// on some channel: how much of the output the wallpaper covers.
+pub fn coverage(frame: &RgbaImage, background: [u8; 3], tolerance: u8) -> f32 {
+    let covered = frame
+        .pixels()
+        .filter(|pixel| {
+            pixel.0[..3]
+                .iter()
+                .zip(background)
+                .any(|(v, b)| v.abs_diff(b) > tolerance)
+        })
+        .count();
+    covered as f32 / (frame.width() as f32 * frame.height() as f32).max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_setup_and_frame_helpers() {
+        let config = sway_config(&HeadlessOptions {
+            width: 800,
+            height: 600,
+            ..HeadlessOptions::default()
+        });
+        assert!(config.contains("output HEADLESS-1 resolution 800x600"));
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(find_wayland_socket(dir.path()), None);
+        fs::write(dir.path().join("wayland-0.lock"), "").unwrap();
+        let _listener =
+            std::os::unix::net::UnixListener::bind(dir.path().join("wayland-1")).unwrap();
+        assert_eq!(
+            find_wayland_socket(dir.path()),
+            Some("wayland-1".to_string())
+        );
+
+        let frame = RgbaImage::from_fn(4, 2, |x, _| {
+            if x < 1 {
+                image::Rgba([200, 100, 0, 255])
+            } else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        });
+        assert_eq!(mean_rgb(&frame), [50.0, 25.0, 0.0]);
+        assert_eq!(coverage(&frame, [0, 0, 0], 8), 0.25);
+    }
+}
@@ -1,12 +1,30 @@
+use crate::colorspace::tag_png_srgb;
+use crate::library_guard::ensure_outside_library;
+use crate::pkg_obfuscation::{
+    HeaderCheck, OBFUSCATION_PROBE_BYTES, allow_obfuscated, check_tex_header, deobfuscate,
+};
 use anyhow::{Context, Result, bail};
 use image::codecs::png::PngEncoder;
 use image::{ColorType, ImageEncoder};
 use lz4_flex::block::decompress;
+use serde::Serialize;
 use std::fs;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 
 const FIF_WEBP_AS_MP4: i32 = 35;
+/// TEXI header flag: sample the texture clamped instead of repeating it.
+pub const TEX_FLAG_CLAMP_UVS: u32 = 2;
+
+/// Timing of the first video track of an mp4 payload (TEXB0004 video mode).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TexVideoInfo {
+    pub frame_count: u64,
+    pub fps: f32,
+    pub duration_seconds: f32,
+    pub width: u32,
+    pub height: u32,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ContainerVersion {
@@ -97,7 +115,7 @@ fn encode_raw_to_png(payload: &[u8], width: u32, height: u32) -> Option<Vec<u8>>
     let enc = PngEncoder::new(&mut out);
     enc.write_image(&bytes, width, height, color_type.into())
         .ok()?;
-    Some(out)
+    Some(tag_png_srgb(out))
 }
 
 fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
@@ -107,6 +125,128 @@ fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     haystack.windows(needle.len()).position(|w| w == needle)
 }
 
+fn be_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Iterates the `(type, body)` of the ISO-BMFF boxes in `bytes`.
+fn mp4_boxes(bytes: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || {
+        let size = be_u32(bytes, pos)? as usize;
+        let kind: [u8; 4] = bytes.get(pos + 4..pos + 8)?.try_into().ok()?;
+        let (header, size) = match size {
+            0 => (8, bytes.len() - pos),
+            1 => (16, usize::try_from(be_u64(bytes, pos + 8)?).ok()?),
+            n => (8, n),
+        };
+        let body = bytes.get(pos + header..pos.checked_add(size)?)?;
+        pos += size.max(header);
+        Some((kind, body))
+    })
+}
+
+fn mp4_child<'a>(bytes: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    mp4_boxes(bytes).find(|(k, _)| k == kind).map(|(_, b)| b)
+}
+
+fn mp4_path<'a>(bytes: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    path.iter()
+        .try_fold(bytes, |body, kind| mp4_child(body, kind))
+}
+
+/// Frame count, fps and size of the first `vide` track, from the mp4 headers
+/// alone (`mdhd` timescale/duration and the `stts` sample table).
+pub fn parse_mp4_video_info(bytes: &[u8]) -> Option<TexVideoInfo> {
+    let moov = mp4_child(bytes, b"moov")?;
+    mp4_boxes(moov)
+        .filter(|(kind, _)| kind == b"trak")
+        .find_map(|(_, trak)| {
+            let mdia = mp4_child(trak, b"mdia")?;
+            if mp4_child(mdia, b"hdlr")?.get(8..12)? != b"vide" {
+                return None;
+            }
+            let mdhd = mp4_child(mdia, b"mdhd")?;
+            let (timescale, duration) = if mdhd.first()? == &1 {
+                (be_u32(mdhd, 20)?, be_u64(mdhd, 24)?)
+            } else {
+                (be_u32(mdhd, 12)?, u64::from(be_u32(mdhd, 16)?))
+            };
+            let stts = mp4_path(mdia, &[b"minf", b"stbl", b"stts"])?;
+            let entries = be_u32(stts, 4)? as usize;
+            let frame_count = (0..entries)
+                .map(|i| be_u32(stts, 8 + i * 8).map(u64::from))
+                .sum::<Option<u64>>()?;
+            let duration_seconds = duration as f32 / timescale.max(1) as f32;
+            if frame_count == 0 || duration_seconds <= 0.0 {
+                return None;
+            }
+            // tkhd ends with 16.16 fixed-point width and height.
+            let tkhd = mp4_child(trak, b"tkhd")?;
+            let size_at = tkhd.len().checked_sub(8)?;
+            Some(TexVideoInfo {
+                frame_count,
+                fps: frame_count as f32 / duration_seconds,
+                duration_seconds,
+                width: be_u32(tkhd, size_at)? >> 16,
+                height: be_u32(tkhd, size_at + 4)? >> 16,
+            })
+        })
+}
+
+/// Video timing of an mp4 file on disk, e.g. a payload extracted from a
+/// TEXB0004 texture.
+/// Only the top-level box headers and `moov` are read, so large videos are
+/// not loaded whole.
+pub fn probe_mp4_video_info(path: &Path) -> Option<TexVideoInfo> {
+    let mut f = fs::File::open(path).ok()?;
+    let len = f.metadata().ok()?.len();
+    let mut pos = 0u64;
+    while pos + 8 <= len {
+        f.seek(SeekFrom::Start(pos)).ok()?;
+        let header = read_exact::<8>(&mut f).ok()?;
+        let kind = &header[4..8];
+        if pos == 0 && kind != b"ftyp" {
+            return None;
+        }
+        let size = match u32::from_be_bytes(header[..4].try_into().ok()?) {
+            0 => len - pos,
+            1 => u64::from_be_bytes(read_exact::<8>(&mut f).ok()?),
+            n => u64::from(n),
+        };
+        if kind == b"moov" {
+            f.seek(SeekFrom::Start(pos)).ok()?;
+            let mut moov = vec![0u8; usize::try_from(size.min(len - pos)).ok()?];
+            f.read_exact(&mut moov).ok()?;
+            return parse_mp4_video_info(&moov);
+        }
+        pos += size.max(8);
+    }
+    None
+}
+
+/// Flags word of a texture's TEXI header (`TEX_FLAG_*`).
+pub fn tex_header_flags(tex_bytes: &[u8]) -> Option<u32> {
+    if !tex_bytes.starts_with(b"TEXV0005\0") || tex_bytes.get(9..18)? != b"TEXI0001\0" {
+        return None;
+    }
+    Some(u32::from_le_bytes(tex_bytes.get(22..26)?.try_into().ok()?))
+}
+
+/// Video timing of a TEXB0004 texture in video mode, read from the embedded
+/// mp4 without extracting it.
+pub fn tex_video_info(tex_bytes: &[u8]) -> Option<TexVideoInfo> {
+    if !tex_bytes.starts_with(b"TEXV0005\0") || tex_bytes.get(46..55)? != b"TEXB0004\0" {
+        return None;
+    }
+    let ftyp = find_subslice(tex_bytes, b"ftyp")?;
+    parse_mp4_video_info(tex_bytes.get(ftyp.checked_sub(4)?..)?)
+}
+
 fn extract_payload_by_signature(tex_path: &Path, out_dir: &Path) -> Result<Option<PathBuf>> {
     let bytes = fs::read(tex_path)
         .with_context(|| format!("Failed to read texture file {}", tex_path.display()))?;
@@ -176,17 +316,64 @@ fn extract_payload_by_signature(tex_path: &Path, out_dir: &Path) -> Result<Optio
         .map(|v| v.to_string_lossy().replace(' ', "_"))
         .unwrap_or_else(|| "scene_visual".to_string());
     let out = out_dir.join(format!("{}_proxy_sig.{}", stem, ext));
+    let payload = if ext == "png" {
+        tag_png_srgb(payload)
+    } else {
+        payload
+    };
     fs::write(&out, payload).with_context(|| format!("Failed writing proxy {}", out.display()))?;
     Ok(Some(out))
 }
 
+/// Undoes a known tex obfuscation into a clean copy under `out_dir` and
+/// extracts that, when `--allow-obfuscated` is set.
+fn extract_obfuscated_tex(tex_path: &Path, out_dir: &Path) -> Result<Option<PathBuf>> {
+    let bytes = fs::read(tex_path)
+        .with_context(|| format!("Failed to read texture file {}", tex_path.display()))?;
+    let probe = &bytes[..bytes.len().min(OBFUSCATION_PROBE_BYTES as usize)];
+    let variant = match check_tex_header(probe) {
+        HeaderCheck::Obfuscated(variant) if allow_obfuscated() => variant,
+        HeaderCheck::Obfuscated(variant) => bail!(
+            "{} is obfuscated ({}); pass --allow-obfuscated to read it best-effort",
+            tex_path.display(),
+            variant
+        ),
+        HeaderCheck::Plain | HeaderCheck::Unknown => bail!(
+            "Unexpected TEX header magic1 in {}, and no known obfuscation variant matches",
+            tex_path.display()
+        ),
+    };
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create proxy dir {}", out_dir.display()))?;
+    let stem = tex_path
+        .file_stem()
+        .map(|v| v.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_else(|| "scene_visual".to_string());
+    let clean = out_dir.join(format!("{}_deobfuscated.tex", stem));
+    fs::write(&clean, deobfuscate(&bytes, variant))
+        .with_context(|| format!("Failed writing {}", clean.display()))?;
+    eprintln!(
+        "[ok] obfuscated tex {} ({}): reading best-effort",
+        tex_path.display(),
+        variant
+    );
+    extract_playable_proxy_from_tex(&clean, out_dir).with_context(|| {
+        format!(
+            "{} is still unreadable after undoing {}",
+            tex_path.display(),
+            variant
+        )
+    })
+}
+
 pub fn extract_playable_proxy_from_tex(tex_path: &Path, out_dir: &Path) -> Result<Option<PathBuf>> {
+    ensure_outside_library(out_dir, "tex proxy extraction")?;
     let mut f = std::fs::File::open(tex_path)
         .with_context(|| format!("Failed to open texture file {}", tex_path.display()))?;
 
     let magic1 = read_exact::<9>(&mut f)?;
     if &magic1 != b"TEXV0005\0" {
-        bail!("Unexpected TEX header magic1 in {}", tex_path.display());
+        return extract_obfuscated_tex(tex_path, out_dir);
     }
 
     let magic2 = read_exact::<9>(&mut f)?;
@@ -330,7 +517,71 @@ pub fn extract_playable_proxy_from_tex(tex_path: &Path, out_dir: &Path) -> Resul
         .unwrap_or_else(|| "scene_visual".to_string());
 
     let out = out_dir.join(format!("{}_proxy.{}", stem, ext));
+    let payload = if ext == "png" {
+        tag_png_srgb(payload)
+    } else {
+        payload
+    };
     fs::write(&out, payload).with_context(|| format!("Failed writing proxy {}", out.display()))?;
 
     Ok(Some(out))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn texb0004_mp4_timing_is_read_from_headers() {
+        // 24 fps for 2 s (48 samples of 1000 ticks at 24000), 1280x720.
+        let mut tkhd = vec![0u8; 76];
+        tkhd.extend(words(&[1280 << 16, 720 << 16]));
+        let hdlr = [words(&[0, 0]), b"vide".to_vec(), vec![0; 13]].concat();
+        let mdhd = words(&[0, 0, 0, 24_000, 48_000, 0]);
+        let stts = words(&[0, 2, 40, 1000, 8, 1000]);
+        let stbl = mp4_box(b"stbl", &mp4_box(b"stts", &stts));
+        let mdia = [
+            mp4_box(b"mdhd", &mdhd),
+            mp4_box(b"hdlr", &hdlr),
+            mp4_box(b"minf", &stbl),
+        ]
+        .concat();
+        let trak = [mp4_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mdia)].concat();
+        let mp4 = [
+            mp4_box(b"ftyp", b"isom\0\0\0\0"),
+            mp4_box(b"mdat", &[0; 32]),
+            mp4_box(b"moov", &mp4_box(b"trak", &trak)),
+        ]
+        .concat();
+
+        let info = parse_mp4_video_info(&mp4).unwrap();
+        assert_eq!(info.frame_count, 48);
+        assert_eq!(info.fps, 24.0);
+        assert_eq!(info.duration_seconds, 2.0);
+        assert_eq!((info.width, info.height), (1280, 720));
+
+        let mut tex = b"TEXV0005\0TEXI0001\0".to_vec();
+        tex.extend(vec![0u8; 28]);
+        tex.extend_from_slice(b"TEXB0004\0");
+        tex.extend(vec![0u8; 24]);
+        tex.extend_from_slice(&mp4);
+        assert_eq!(tex_video_info(&tex), Some(info));
+        assert_eq!(tex_video_info(&mp4), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("payload_proxy.mp4");
+        fs::write(&path, &mp4).unwrap();
+        assert_eq!(probe_mp4_video_info(&path), Some(info));
+    }
+}
@@ -32,8 +32,32 @@ pub struct ProjectJson {
     pub title: String,
     #[serde(default)]
     pub workshopid: String,
+    /// Not written by the editor; some repacked wallpapers carry it.
+    #[serde(default)]
+    pub author: serde_json::Value,
     #[serde(default)]
     pub general: serde_json::Value,
+    /// Playback rate hint of curated video wallpapers: a number (`0.5`),
+    /// `"0.5x"`, `"slowmotion"` or `"normal"`.
+    #[serde(default)]
+    pub playbackmode: serde_json::Value,
+    #[serde(default)]
+    pub playbackrate: serde_json::Value,
+    #[serde(default)]
+    pub fps: serde_json::Value,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Playback hints a video wallpaper's `project.json` carries.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct VideoPlaybackHints {
+    /// mpv `speed`; only set when it differs from 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    /// Intended frame rate, used for mpv and as proxy encode fps cap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -42,6 +66,10 @@ pub struct SceneDiagnostics {
     pub has_gifscene_json: bool,
     pub has_scene_pkg: bool,
     pub has_gifscene_pkg: bool,
+    /// Scene description found inside the pkg (`scene.pkg#scene.json`)
+    /// when there is no loose one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packed_scene_json: Option<String>,
     pub supports_audio_processing: bool,
     pub supports_video: bool,
     pub best_video_candidate: Option<String>,
@@ -55,7 +83,16 @@ pub struct InspectOutput {
     pub wallpaper_type: WallpaperType,
     pub entry: Option<String>,
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
     pub workshopid: Option<String>,
     pub project_file_found: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub scene: Option<SceneDiagnostics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_hints: Option<VideoPlaybackHints>,
+    /// Only filled by `inspect`; apply paths skip the graph build.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<crate::scene_capabilities::WallpaperCapabilities>,
 }
@@ -0,0 +1,193 @@
+use crate::cli::GpuTransport;
+use crate::command_runner::CommandExt;
+use crate::report_bundle::first_output_line;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+/// External tools the engine shells out to, with the flag that prints their
+/// version.
+pub const TOOLS: [(&str, &str); 7] = [
+    ("mpv", "--version"),
+    ("mpvpaper", "--help"),
+    ("ffmpeg", "-version"),
+    ("ffprobe", "-version"),
+    ("hyprctl", "version"),
+    ("swww", "--version"),
+    ("parec", "--version"),
+];
+
+/// Playback backends and the tools each needs.
+const BACKENDS: [(&str, &[&str]); 4] = [
+    ("mpvpaper", &["mpvpaper", "mpv"]),
+    ("swww", &["swww"]),
+    ("hyprpaper", &["hyprctl"]),
+    ("greeter", &["ffmpeg"]),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub found: bool,
+    /// First line the tool printed for its version flag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    pub name: String,
+    pub available: bool,
+    /// Required tools that were not found.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionReport {
+    pub name: String,
+    pub version: String,
+    /// Cargo features this binary was built with.
+    pub features: BTreeMap<String, bool>,
+    pub tools: Vec<ToolInfo>,
+    /// `scene-gpu-play --transport` values.
+    pub transports: Vec<Capability>,
+    pub backends: Vec<Capability>,
+}
+
+/// Dotted version number in a banner (`mpv v0.38.0 ...`, `ffmpeg version
+/// n7.0.1 ...`), without its `v`/`n` prefix.
+pub fn parse_tool_version(banner: &str) -> Option<String> {
+    banner.split_whitespace().find_map(|word| {
+        let word = word
+            .trim_start_matches(['v', 'n'])
+            .trim_end_matches([',', ':']);
+        let dotted = word.split('.').take(2).collect::<Vec<_>>();
+        (dotted.len() == 2 && dotted.iter().all(|p| p.parse::<u32>().is_ok()))
+            .then(|| word.to_string())
+    })
+}
+
+pub fn detect_tools() -> Vec<ToolInfo> {
+    TOOLS
+        .iter()
+        .map(|(tool, flag)| {
+            let banner = Command::new(tool)
+                .arg(flag)
+                .run_output()
+                .ok()
+                .map(|output| first_output_line(&output.stdout, &output.stderr));
+            let version = banner
+                .as_ref()
+                .and_then(|b| parse_tool_version(b.as_deref()?));
+            ToolInfo {
+                name: tool.to_string(),
+                found: banner.is_some(),
+                banner: banner.flatten(),
+                version,
+            }
+        })
+        .collect()
+}
+
+fn capability(name: &str, requires: &[&str], tools: &[ToolInfo]) -> Capability {
+    let missing = requires
+        .iter()
+        .filter(|req| !tools.iter().any(|t| t.name == **req && t.found))
+        .map(|req| req.to_string())
+        .collect::<Vec<_>>();
+    Capability {
+        name: name.to_string(),
+        available: missing.is_empty(),
+        missing,
+    }
+}
+
+/// Tools a transport needs besides mpvpaper itself.
+fn transport_requires(transport: GpuTransport) -> &'static [&'static str] {
+    match transport {
+        GpuTransport::Mp4Proxy | GpuTransport::NativeRealtime => &["ffmpeg"],
+        GpuTransport::NativeStream => &["mpv"],
+    }
+}
+
+pub fn build_version_report_with(tools: Vec<ToolInfo>) -> VersionReport {
+    let transports = GpuTransport::value_variants()
+        .iter()
+        .filter_map(|t| {
+            let name = t.to_possible_value()?.get_name().to_string();
+            Some(capability(&name, transport_requires(*t), &tools))
+        })
+        .collect();
+    let backends = BACKENDS
+        .iter()
+        .map(|(name, requires)| capability(name, requires, &tools))
+        .collect();
+    VersionReport {
+        name: env!("CARGO_PKG_NAME").to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features: BTreeMap::from([
+            ("systemd".to_string(), cfg!(feature = "systemd")),
+            ("testsupport".to_string(), cfg!(feature = "testsupport")),
+        ]),
+        tools,
+        transports,
+        backends,
+    }
+}
+
+pub fn build_version_report() -> VersionReport {
+    build_version_report_with(detect_tools())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_versions_and_capabilities_follow_detected_tools() {
+        assert_eq!(
+            parse_tool_version("mpv v0.38.0 Copyright © 2000-2024 mpv/MPlayer/mplayer2 projects"),
+            Some("0.38.0".to_string())
+        );
+        assert_eq!(
+            parse_tool_version("ffmpeg version n7.0.1 Copyright (c) 2000-2024"),
+            Some("7.0.1".to_string())
+        );
+        assert_eq!(
+            parse_tool_version("Hyprland 0.41.2 built from branch"),
+            Some("0.41.2".to_string())
+        );
+        assert_eq!(parse_tool_version("Usage: mpvpaper <options>"), None);
+
+        let tool = |name: &str, found: bool| ToolInfo {
+            name: name.to_string(),
+            found,
+            banner: None,
+            version: None,
+        };
+        let report = build_version_report_with(vec![
+            tool("mpv", true),
+            tool("mpvpaper", true),
+            tool("ffmpeg", false),
+        ]);
+        let by_name = |caps: &[Capability], name: &str| {
+            caps.iter().find(|c| c.name == name).cloned().unwrap()
+        };
+        assert!(by_name(&report.transports, "native-stream").available);
+        let proxy = by_name(&report.transports, "mp4-proxy");
+        assert_eq!(
+            (proxy.available, proxy.missing),
+            (false, vec!["ffmpeg".to_string()])
+        );
+        assert!(by_name(&report.backends, "mpvpaper").available);
+        assert!(!by_name(&report.backends, "swww").available);
+        assert_eq!(
+            report.features.get("systemd"),
+            Some(&cfg!(feature = "systemd"))
+        );
+    }
+}
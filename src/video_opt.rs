@@ -1,6 +1,16 @@
+use crate::colorspace::{SRGB_BT709_TAGS, YUV420P_BT709};
+use crate::command_runner::CommandExt;
+use crate::i18n::{Msg, warn_msg};
+use crate::journal::record_proxy_build;
+use crate::library_guard::ensure_outside_library;
+use crate::proxy_meta::ProxyMeta;
+use crate::tex_payload::probe_mp4_video_info;
 use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Instant;
 
 fn is_video_like(path: &Path) -> bool {
     let ext = path
@@ -43,12 +53,26 @@ pub fn maybe_build_optimized_proxy(
         return Ok(input.to_path_buf());
     }
 
+    // Duplicating frames above the source rate only costs encode time.
+    let fps = match probe_mp4_video_info(input) {
+        Some(info) if info.fps >= 1.0 && (info.fps.round() as u32) < fps => {
+            let source_fps = info.fps.round() as u32;
+            eprintln!(
+                "[ok] source video is {:.2} fps ({} frames, {:.1}s): proxy fps {} -> {}",
+                info.fps, info.frame_count, info.duration_seconds, fps, source_fps
+            );
+            source_fps
+        }
+        _ => fps,
+    };
+
     let stem = input
         .file_stem()
         .map(|v| v.to_string_lossy().replace(' ', "_"))
         .unwrap_or_else(|| "scene_proxy".to_string());
 
     let proxy_dir = session_dir.join("proxy-opt");
+    ensure_outside_library(&proxy_dir, "optimized proxy")?;
     let out = proxy_dir.join(format!("{}_opt_{}w_{}fps_crf{}.mp4", stem, width, fps, crf));
 
     if out.is_file() && !is_outdated(input, &out) {
@@ -56,7 +80,7 @@ pub fn maybe_build_optimized_proxy(
     }
 
     if dry_run {
-        println!(
+        eprintln!(
             "[dry-run] ffmpeg -hide_banner -loglevel error -y -i '{}' -an -vf \"scale='min(iw,{})':-2:flags=bicubic,fps={},format=yuv420p\" -c:v libx264 -preset veryfast -crf {} -movflags +faststart '{}'",
             input.display(),
             width,
@@ -79,6 +103,8 @@ pub fn maybe_build_optimized_proxy(
         width, fps
     );
 
+    let meta = ProxyMeta::new("optimized", input, &format!("{} crf{}", vf, crf));
+    let started = Instant::now();
     let output = Command::new("ffmpeg")
         .arg("-hide_banner")
         .arg("-loglevel")
@@ -97,23 +123,126 @@ pub fn maybe_build_optimized_proxy(
         .arg(crf.to_string())
         .arg("-movflags")
         .arg("+faststart")
+        .args(meta.ffmpeg_args())
         .arg(&out)
-        .output()
+        .run_output()
         .context("Failed running ffmpeg for optimized scene proxy")?;
+    record_proxy_build("optimized", &out, started, output.status.success());
 
     if output.status.success() {
         Ok(out)
     } else {
         let err = String::from_utf8_lossy(&output.stderr);
+        warn_msg!(Msg::OptimizedProxyFailed, err.trim());
+        Ok(input.to_path_buf())
+    }
+}
+
+/// Where ffmpeg writes `out` until it succeeds, so an interrupted encode
+/// never leaves a truncated file that later runs would take as cached. The
+/// extension is kept so ffmpeg still picks the muxer from it.
+fn partial_output(out: &Path) -> PathBuf {
+    let name = out
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    out.with_file_name(format!(".partial-{}", name))
+}
+
+fn is_gif(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gif"))
+}
+
+/// mpv decodes GIFs frame by frame on the CPU; large ones are much cheaper as
+/// H.264. Converts one loop of `input` (original per-frame delays kept via
+/// VFR timestamps) into `cache_dir` and reuses it while the GIF is unchanged.
+/// Non-GIF inputs are returned as-is.
+pub fn maybe_build_gif_proxy(input: &Path, cache_dir: &Path, dry_run: bool) -> Result<PathBuf> {
+    if !is_gif(input) {
+        return Ok(input.to_path_buf());
+    }
+
+    let stem = input
+        .file_stem()
+        .map(|v| v.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_else(|| "gif".to_string());
+    let mut hasher = DefaultHasher::new();
+    input.to_string_lossy().hash(&mut hasher);
+    let out = cache_dir.join(format!("{}_{:x}.mp4", stem, hasher.finish()));
+    ensure_outside_library(&out, "GIF proxy")?;
+
+    if out.is_file() && !is_outdated(input, &out) {
+        return Ok(out);
+    }
+
+    // Palette frames go through full-range RGB before yuv420p so flat GIF
+    // colors do not band; transparency is flattened onto black.
+    let vf = format!(
+        "format=rgb24,scale=trunc(iw/2)*2:trunc(ih/2)*2:flags=neighbor,{}",
+        YUV420P_BT709
+    );
+    if dry_run {
         eprintln!(
-            "[warn] could not build optimized scene proxy, using original media: {}",
-            err.trim()
+            "[dry-run] ffmpeg -hide_banner -loglevel error -y -ignore_loop 1 -i '{}' -an -vf \"{}\" -fps_mode vfr -c:v libx264 -preset veryfast -tune animation -crf 20 {} -movflags +faststart '{}'",
+            input.display(),
+            vf,
+            SRGB_BT709_TAGS.join(" "),
+            out.display()
         );
+        return Ok(out);
+    }
+
+    std::fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create GIF proxy dir {}", cache_dir.display()))?;
+
+    let partial = partial_output(&out);
+    let started = Instant::now();
+    let output = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-y")
+        // One pass over the frames; mpv provides the loop.
+        .arg("-ignore_loop")
+        .arg("1")
+        .arg("-i")
+        .arg(input)
+        .arg("-an")
+        .arg("-vf")
+        .arg(&vf)
+        .arg("-fps_mode")
+        .arg("vfr")
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("veryfast")
+        .arg("-tune")
+        .arg("animation")
+        .arg("-crf")
+        .arg("20")
+        .args(SRGB_BT709_TAGS)
+        .arg("-movflags")
+        .arg("+faststart")
+        .args(ProxyMeta::new("gif", input, &vf).ffmpeg_args())
+        .arg(&partial)
+        .run_output()
+        .context("Failed running ffmpeg for GIF proxy")?;
+    record_proxy_build("gif", &out, started, output.status.success());
+
+    if output.status.success() {
+        std::fs::rename(&partial, &out)
+            .with_context(|| format!("Failed moving {} to {}", partial.display(), out.display()))?;
+        Ok(out)
+    } else {
+        let _ = std::fs::remove_file(&partial);
+        let err = String::from_utf8_lossy(&output.stderr);
+        warn_msg!(Msg::GifConvertFailed, err.trim());
         Ok(input.to_path_buf())
     }
 }
 
-fn probe_duration_seconds(input: &Path) -> Result<f64> {
+pub(crate) fn probe_duration_seconds(input: &Path) -> Result<f64> {
     let output = Command::new("ffprobe")
         .arg("-v")
         .arg("error")
@@ -122,7 +251,7 @@ fn probe_duration_seconds(input: &Path) -> Result<f64> {
         .arg("-of")
         .arg("default=noprint_wrappers=1:nokey=1")
         .arg(input)
-        .output()
+        .run_output()
         .context("Failed running ffprobe for video duration")?;
 
     if !output.status.success() {
@@ -133,6 +262,28 @@ fn probe_duration_seconds(input: &Path) -> Result<f64> {
     Ok(raw.parse::<f64>().unwrap_or(0.0))
 }
 
+/// Width/height of the first video stream.
+pub fn probe_video_size(input: &Path) -> Option<(u32, u32)> {
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
+        .arg("-show_entries")
+        .arg("stream=width,height")
+        .arg("-of")
+        .arg("csv=p=0:s=x")
+        .arg(input)
+        .run_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let raw = String::from_utf8_lossy(&output.stdout);
+    let (w, h) = raw.trim().split_once('x')?;
+    Some((w.parse().ok()?, h.parse().ok()?))
+}
+
 pub fn maybe_build_loop_crossfade_proxy(
     input: &Path,
     session_dir: &Path,
@@ -149,10 +300,7 @@ pub fn maybe_build_loop_crossfade_proxy(
     let fade = crossfade_seconds.clamp(0.05, 2.5) as f64;
     let duration = probe_duration_seconds(input)?;
     if duration <= fade + 0.15 {
-        eprintln!(
-            "[warn] loop-crossfade skipped: video is too short for fade window (duration={:.3}s, fade={:.3}s)",
-            duration, fade
-        );
+        warn_msg!(Msg::CrossfadeTooShort, duration, fade);
         return maybe_build_optimized_proxy(input, session_dir, width, fps, crf, dry_run);
     }
 
@@ -162,6 +310,7 @@ pub fn maybe_build_loop_crossfade_proxy(
         .unwrap_or_else(|| "scene_proxy".to_string());
 
     let proxy_dir = session_dir.join("proxy-loop");
+    ensure_outside_library(&proxy_dir, "loop proxy")?;
     let out = proxy_dir.join(format!(
         "{}_loopxfade_{}w_{}fps_crf{}_f{:.2}.mp4",
         stem, width, fps, crf, fade
@@ -188,7 +337,7 @@ pub fn maybe_build_loop_crossfade_proxy(
     );
 
     if dry_run {
-        println!(
+        eprintln!(
             "[dry-run] ffmpeg -hide_banner -loglevel error -y -i '{in0}' -i '{in1}' -filter_complex \"{vf}\" -map '[v]' -an -c:v libx264 -preset veryfast -crf {crf} -movflags +faststart '{out}'",
             in0 = input.display(),
             in1 = input.display(),
@@ -202,6 +351,8 @@ pub fn maybe_build_loop_crossfade_proxy(
     std::fs::create_dir_all(&proxy_dir)
         .with_context(|| format!("Failed to create loop proxy dir {}", proxy_dir.display()))?;
 
+    let meta = ProxyMeta::new("loop-crossfade", input, &format!("{} crf{}", vf, crf));
+    let started = Instant::now();
     let output = Command::new("ffmpeg")
         .arg("-hide_banner")
         .arg("-loglevel")
@@ -224,18 +375,635 @@ pub fn maybe_build_loop_crossfade_proxy(
         .arg(crf.to_string())
         .arg("-movflags")
         .arg("+faststart")
+        .args(meta.ffmpeg_args())
         .arg(&out)
-        .output()
+        .run_output()
         .context("Failed running ffmpeg for loop-crossfade proxy")?;
+    record_proxy_build("loop-crossfade", &out, started, output.status.success());
 
     if output.status.success() {
         Ok(out)
     } else {
         let err = String::from_utf8_lossy(&output.stderr);
+        warn_msg!(Msg::CrossfadeFailed, err.trim());
+        maybe_build_optimized_proxy(input, session_dir, width, fps, crf, dry_run)
+    }
+}
+
+const ENVELOPE_RATE_HZ: u32 = 8000;
+const ENVELOPE_STEP_SECONDS: f64 = 0.1;
+const AUDIO_REACTIVE_MAX_SECONDS: f64 = 180.0;
+
+/// RMS energy of `music` per `ENVELOPE_STEP_SECONDS` window, normalized to 0..1.
+fn analyze_energy_envelope(music: &Path, seconds: f64) -> Result<Vec<f32>> {
+    let output = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-i")
+        .arg(music)
+        .arg("-t")
+        .arg(format!("{:.3}", seconds))
+        .arg("-ac")
+        .arg("1")
+        .arg("-ar")
+        .arg(ENVELOPE_RATE_HZ.to_string())
+        .arg("-f")
+        .arg("s16le")
+        .arg("-")
+        .run_output()
+        .context("Failed running ffmpeg for music energy analysis")?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("ffmpeg music analysis failed: {}", err.trim());
+    }
+
+    let samples = output
+        .stdout
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32)
+        .collect::<Vec<_>>();
+    Ok(energy_envelope_from_samples(
+        &samples,
+        (ENVELOPE_RATE_HZ as f64 * ENVELOPE_STEP_SECONDS) as usize,
+    ))
+}
+
+fn energy_envelope_from_samples(samples: &[f32], window: usize) -> Vec<f32> {
+    let window = window.max(1);
+    let rms = samples
+        .chunks(window)
+        .map(|w| (w.iter().map(|s| s * s).sum::<f32>() / w.len() as f32).sqrt())
+        .collect::<Vec<_>>();
+    let peak = rms.iter().cloned().fold(0.0f32, f32::max);
+    if peak <= f32::EPSILON {
+        return vec![0.0; rms.len()];
+    }
+    // Fast attack, slow release so the grade pulses instead of flickering.
+    let mut smoothed = 0.0f32;
+    rms.iter()
+        .map(|v| {
+            let target = v / peak;
+            let k = if target > smoothed { 0.6 } else { 0.15 };
+            smoothed += (target - smoothed) * k;
+            smoothed
+        })
+        .collect()
+}
+
+fn envelope_to_sendcmd(envelope: &[f32], step: f64, strength: f32) -> String {
+    let strength = strength.clamp(0.0, 2.0);
+    let mut out = String::new();
+    for (i, e) in envelope.iter().enumerate() {
+        let e = e.clamp(0.0, 1.0);
+        out.push_str(&format!(
+            "{:.3} eq@ar brightness {:.4}, eq@ar contrast {:.4}, eq@ar saturation {:.4};\n",
+            i as f64 * step,
+            (e - 0.35) * 0.06 * strength,
+            1.0 + e * 0.08 * strength,
+            1.0 + e * 0.12 * strength
+        ));
+    }
+    out
+}
+
+/// Loops `input` over the scene soundtrack and bakes an eq pulse that follows
+/// the soundtrack's energy, muxing the music so both stay in sync.
+pub fn maybe_build_audio_reactive_proxy(
+    input: &Path,
+    music: &Path,
+    session_dir: &Path,
+    strength: f32,
+    dry_run: bool,
+) -> Result<PathBuf> {
+    if !is_video_like(input) {
+        return Ok(input.to_path_buf());
+    }
+
+    let stem = input
+        .file_stem()
+        .map(|v| v.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_else(|| "scene_proxy".to_string());
+    let proxy_dir = session_dir.join("proxy-audio");
+    ensure_outside_library(&proxy_dir, "audio-reactive proxy")?;
+    let out = proxy_dir.join(format!("{}_audio_s{:.2}.mp4", stem, strength));
+    let cmds_path = proxy_dir.join(format!("{}_audio_s{:.2}.cmd", stem, strength));
+
+    if out.is_file() && !is_outdated(input, &out) && !is_outdated(music, &out) {
+        return Ok(out);
+    }
+
+    let music_duration = probe_duration_seconds(music)?;
+    if music_duration <= 0.5 {
+        warn_msg!(Msg::AudioReactiveNoDuration, music.display());
+        return Ok(input.to_path_buf());
+    }
+    let duration = music_duration.min(AUDIO_REACTIVE_MAX_SECONDS);
+
+    let vf = format!(
+        "sendcmd=f='{}',eq@ar=contrast=1:brightness=0:saturation=1,format=yuv420p",
+        cmds_path.display()
+    );
+
+    if dry_run {
         eprintln!(
-            "[warn] loop-crossfade proxy failed, falling back to optimized proxy: {}",
-            err.trim()
+            "[dry-run] ffmpeg -hide_banner -loglevel error -y -stream_loop -1 -i '{}' -i '{}' -t {:.3} -map 0:v -map 1:a -vf \"{}\" -c:v libx264 -preset veryfast -crf 20 -c:a aac -b:a 160k -movflags +faststart '{}'",
+            input.display(),
+            music.display(),
+            duration,
+            vf,
+            out.display()
         );
-        maybe_build_optimized_proxy(input, session_dir, width, fps, crf, dry_run)
+        return Ok(out);
+    }
+
+    let envelope = analyze_energy_envelope(music, duration)?;
+    std::fs::create_dir_all(&proxy_dir).with_context(|| {
+        format!(
+            "Failed to create audio-reactive proxy dir {}",
+            proxy_dir.display()
+        )
+    })?;
+    std::fs::write(
+        &cmds_path,
+        envelope_to_sendcmd(&envelope, ENVELOPE_STEP_SECONDS, strength),
+    )
+    .with_context(|| format!("Failed writing {}", cmds_path.display()))?;
+
+    let started = Instant::now();
+    let output = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-y")
+        .arg("-stream_loop")
+        .arg("-1")
+        .arg("-i")
+        .arg(input)
+        .arg("-i")
+        .arg(music)
+        .arg("-t")
+        .arg(format!("{:.3}", duration))
+        .arg("-map")
+        .arg("0:v")
+        .arg("-map")
+        .arg("1:a")
+        .arg("-vf")
+        .arg(&vf)
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("veryfast")
+        .arg("-crf")
+        .arg("20")
+        .arg("-c:a")
+        .arg("aac")
+        .arg("-b:a")
+        .arg("160k")
+        .arg("-movflags")
+        .arg("+faststart")
+        .args(ProxyMeta::new("audio-reactive", input, &vf).ffmpeg_args())
+        .arg(&out)
+        .run_output()
+        .context("Failed running ffmpeg for audio-reactive proxy")?;
+    record_proxy_build("audio-reactive", &out, started, output.status.success());
+
+    if output.status.success() {
+        Ok(out)
+    } else {
+        let err = String::from_utf8_lossy(&output.stderr);
+        warn_msg!(Msg::AudioReactiveProxyFailed, err.trim());
+        Ok(input.to_path_buf())
+    }
+}
+
+/// Video stream that carries an alpha channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaStream {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+}
+
+impl AlphaStream {
+    /// ffmpeg's native VP8/VP9 decoders drop the alpha plane; libvpx keeps it.
+    fn decoder(&self) -> Option<&'static str> {
+        match self.codec.as_str() {
+            "vp9" => Some("libvpx-vp9"),
+            "vp8" => Some("libvpx"),
+            _ => None,
+        }
+    }
+}
+
+fn pix_fmt_has_alpha(pix_fmt: &str) -> bool {
+    pix_fmt.starts_with("yuva")
+        || pix_fmt.starts_with("gbrap")
+        || pix_fmt.starts_with("ya")
+        || ["rgba", "bgra", "argb", "abgr", "rgb32"]
+            .iter()
+            .any(|f| pix_fmt.starts_with(f))
+}
+
+/// Parses `ffprobe -of default=noprint_wrappers=1` output for the first video
+/// stream. VP9 alpha decodes as `yuv420p` natively, so the `alpha_mode` tag
+/// the webm muxer writes counts as well.
+fn parse_alpha_probe(raw: &str) -> Option<AlphaStream> {
+    let mut fields = std::collections::HashMap::new();
+    for line in raw.lines() {
+        if let Some((key, value)) = line.trim().split_once('=') {
+            fields
+                .entry(key.to_ascii_lowercase())
+                .or_insert(value.trim());
+        }
+    }
+    let tagged = fields.get("tag:alpha_mode").is_some_and(|v| *v == "1");
+    let pix_fmt = fields.get("pix_fmt").copied().unwrap_or_default();
+    if !tagged && !pix_fmt_has_alpha(pix_fmt) {
+        return None;
+    }
+    let fps = fields
+        .get("r_frame_rate")
+        .and_then(|r| match r.split_once('/') {
+            Some((n, d)) => Some(n.parse::<f32>().ok()? / d.parse::<f32>().ok()?),
+            None => r.parse::<f32>().ok(),
+        })
+        .filter(|fps| fps.is_finite() && *fps > 0.0)
+        .unwrap_or(30.0);
+    Some(AlphaStream {
+        codec: fields
+            .get("codec_name")
+            .copied()
+            .unwrap_or_default()
+            .to_string(),
+        width: fields.get("width")?.parse().ok()?,
+        height: fields.get("height")?.parse().ok()?,
+        fps,
+    })
+}
+
+/// Alpha stream of `input`, or `None` for opaque (or unprobeable) media.
+pub fn probe_alpha_stream(input: &Path) -> Option<AlphaStream> {
+    // Only Matroska/WebM (VP8/VP9 alpha) and QuickTime (ProRes 4444, PNG,
+    // qtrle) carry alpha that ffmpeg can decode; skip the probe otherwise.
+    let ext = input
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if !matches!(ext.as_str(), "webm" | "mkv" | "mov") {
+        return None;
+    }
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
+        .arg("-show_entries")
+        .arg("stream=codec_name,width,height,pix_fmt,r_frame_rate:stream_tags=alpha_mode")
+        .arg("-of")
+        .arg("default=noprint_wrappers=1")
+        .arg(input)
+        .run_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_alpha_probe(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// What shows through the transparent parts of an alpha video.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum AlphaBackground {
+    /// ffmpeg color: `black`, `#102030`, `0x102030`.
+    Color(String),
+    /// Still image or video, scaled to cover the frame.
+    Media(PathBuf),
+}
+
+impl std::fmt::Display for AlphaBackground {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlphaBackground::Color(color) => write!(f, "color {}", color),
+            AlphaBackground::Media(media) => write!(f, "{}", media.display()),
+        }
+    }
+}
+
+fn alpha_composite_args(
+    input: &Path,
+    stream: &AlphaStream,
+    background: &AlphaBackground,
+    out: &Path,
+) -> Vec<String> {
+    // libx264 yuv420p needs even dimensions.
+    let (w, h) = (stream.width.max(2) & !1, stream.height.max(2) & !1);
+    let fps = format!("{:.3}", stream.fps);
+    let mut args: Vec<String> = ["-hide_banner", "-loglevel", "error", "-y"]
+        .map(String::from)
+        .to_vec();
+    if let Some(decoder) = stream.decoder() {
+        args.extend(["-c:v".to_string(), decoder.to_string()]);
+    }
+    args.extend(["-i".to_string(), input.to_string_lossy().to_string()]);
+    match background {
+        AlphaBackground::Color(color) => args.extend([
+            "-f".to_string(),
+            "lavfi".to_string(),
+            "-i".to_string(),
+            format!("color=c={}:s={}x{}:r={}", color, w, h, fps),
+        ]),
+        AlphaBackground::Media(media) => {
+            let still = media
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .is_some_and(|e| matches!(e.as_str(), "png" | "jpg" | "jpeg" | "webp" | "bmp"));
+            if still {
+                args.extend(["-loop", "1", "-framerate", &fps].map(String::from));
+            } else {
+                args.extend(["-stream_loop", "-1"].map(String::from));
+            }
+            args.extend(["-i".to_string(), media.to_string_lossy().to_string()]);
+        }
+    }
+    args.extend([
+        "-filter_complex".to_string(),
+        format!(
+            "[1:v]scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},fps={fps},setsar=1[bg];\
+[0:v]scale={w}:{h},format=rgba[fg];[bg][fg]overlay=shortest=1:format=auto,{YUV420P_BT709}[v]"
+        ),
+    ]);
+    let encode = "-map [v] -map 0:a? -c:v libx264 -preset veryfast -crf 18 -c:a aac -b:a 160k -movflags +faststart";
+    args.extend(encode.split(' ').map(String::from));
+    args.push(out.to_string_lossy().to_string());
+    args
+}
+
+/// mpvpaper has nothing behind the wallpaper surface, so an alpha video is
+/// flattened onto `background` once (H.264 proxy in `cache_dir`, reused while
+/// the source and background are unchanged).
+pub fn maybe_build_alpha_composite_proxy(
+    input: &Path,
+    stream: &AlphaStream,
+    background: &AlphaBackground,
+    cache_dir: &Path,
+    dry_run: bool,
+) -> Result<PathBuf> {
+    let stem = input
+        .file_stem()
+        .map(|v| v.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_else(|| "alpha".to_string());
+    let mut hasher = DefaultHasher::new();
+    input.to_string_lossy().hash(&mut hasher);
+    background.hash(&mut hasher);
+    let out = cache_dir.join(format!("{}_alpha_{:x}.mp4", stem, hasher.finish()));
+    ensure_outside_library(&out, "alpha proxy")?;
+
+    let background_changed = match background {
+        AlphaBackground::Media(media) => is_outdated(media, &out),
+        AlphaBackground::Color(_) => false,
+    };
+    if out.is_file() && !is_outdated(input, &out) && !background_changed {
+        return Ok(out);
+    }
+
+    let args = alpha_composite_args(input, stream, background, &out);
+    if dry_run {
+        eprintln!("[dry-run] ffmpeg {}", args.join(" "));
+        return Ok(out);
+    }
+
+    std::fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create alpha proxy dir {}", cache_dir.display()))?;
+
+    let meta = ProxyMeta::new("alpha-composite", input, &args.join(" "));
+    let (out_arg, encode) = args
+        .split_last()
+        .expect("composite args end with the output");
+    let started = Instant::now();
+    let output = Command::new("ffmpeg")
+        .args(encode)
+        .args(meta.ffmpeg_args())
+        .arg(out_arg)
+        .run_output()
+        .context("Failed running ffmpeg for alpha composite proxy")?;
+    record_proxy_build("alpha-composite", &out, started, output.status.success());
+
+    if output.status.success() {
+        Ok(out)
+    } else {
+        let err = String::from_utf8_lossy(&output.stderr);
+        warn_msg!(Msg::AlphaCompositeFailed, err.trim());
+        Ok(input.to_path_buf())
+    }
+}
+
+/// Overlay wallpapers are read by mpv's `movie=` filter, which always uses
+/// ffmpeg's native decoders; a ProRes 4444 copy keeps the alpha plane there.
+pub fn maybe_build_alpha_overlay_proxy(
+    input: &Path,
+    stream: &AlphaStream,
+    cache_dir: &Path,
+    dry_run: bool,
+) -> Result<PathBuf> {
+    if stream.decoder().is_none() {
+        return Ok(input.to_path_buf());
+    }
+    let stem = input
+        .file_stem()
+        .map(|v| v.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_else(|| "alpha".to_string());
+    let mut hasher = DefaultHasher::new();
+    input.to_string_lossy().hash(&mut hasher);
+    let out = cache_dir.join(format!("{}_{:x}_4444.mov", stem, hasher.finish()));
+    ensure_outside_library(&out, "alpha overlay proxy")?;
+    if out.is_file() && !is_outdated(input, &out) {
+        return Ok(out);
+    }
+
+    let decoder = stream.decoder().unwrap_or_default();
+    if dry_run {
+        eprintln!(
+            "[dry-run] ffmpeg -hide_banner -loglevel error -y -c:v {} -i '{}' -an -c:v prores_ks -profile:v 4444 -pix_fmt yuva444p10le '{}'",
+            decoder,
+            input.display(),
+            out.display()
+        );
+        return Ok(out);
+    }
+
+    std::fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create alpha proxy dir {}", cache_dir.display()))?;
+
+    let started = Instant::now();
+    let output = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-y")
+        .arg("-c:v")
+        .arg(decoder)
+        .arg("-i")
+        .arg(input)
+        .arg("-an")
+        .arg("-c:v")
+        .arg("prores_ks")
+        .arg("-profile:v")
+        .arg("4444")
+        .arg("-pix_fmt")
+        .arg("yuva444p10le")
+        .args(ProxyMeta::new("alpha-overlay", input, "prores_ks 4444 yuva444p10le").ffmpeg_args())
+        .arg(&out)
+        .run_output()
+        .context("Failed running ffmpeg for alpha overlay proxy")?;
+    record_proxy_build("alpha-overlay", &out, started, output.status.success());
+
+    if output.status.success() {
+        Ok(out)
+    } else {
+        let err = String::from_utf8_lossy(&output.stderr);
+        warn_msg!(Msg::ProresOverlayFailed, err.trim());
+        Ok(input.to_path_buf())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_webm_is_detected_and_composited() {
+        let vp9 = "codec_name=vp9\nwidth=1279\nheight=720\npix_fmt=yuv420p\nr_frame_rate=30000/1001\nTAG:alpha_mode=1\n";
+        let stream = parse_alpha_probe(vp9).expect("alpha_mode tag means alpha");
+        assert_eq!((stream.width, stream.height), (1279, 720));
+        assert!((stream.fps - 29.97).abs() < 0.01);
+        assert_eq!(stream.decoder(), Some("libvpx-vp9"));
+        let prores =
+            "codec_name=prores\nwidth=64\nheight=64\npix_fmt=yuva444p10le\nr_frame_rate=25/1\n";
+        assert_eq!(parse_alpha_probe(prores).unwrap().decoder(), None);
+        let opaque = "codec_name=h264\nwidth=64\nheight=64\npix_fmt=yuv420p\nr_frame_rate=25/1\n";
+        assert_eq!(parse_alpha_probe(opaque), None);
+
+        let out = Path::new("/tmp/out.mp4");
+        let color = AlphaBackground::Color("#203040".to_string());
+        let args = alpha_composite_args(Path::new("a.webm"), &stream, &color, out).join(" ");
+        assert!(args.starts_with("-hide_banner -loglevel error -y -c:v libvpx-vp9 -i a.webm"));
+        assert!(args.contains("color=c=#203040:s=1278x720:r=29.970"));
+        assert!(args.contains("[bg][fg]overlay=shortest=1"));
+        let still = AlphaBackground::Media(PathBuf::from("bg.jpg"));
+        let args = alpha_composite_args(Path::new("a.webm"), &stream, &still, out).join(" ");
+        assert!(args.contains("-loop 1 -framerate 29.970 -i bg.jpg"));
+    }
+
+    #[test]
+    fn envelope_is_normalized_and_smoothed() {
+        let mut samples = vec![0.0f32; 800];
+        samples.extend(vec![0.5f32; 800]);
+        samples.extend(vec![0.0f32; 800]);
+        let env = energy_envelope_from_samples(&samples, 800);
+        assert_eq!(env.len(), 3);
+        assert_eq!(env[0], 0.0);
+        assert!(env[1] > 0.5 && env[1] <= 1.0);
+        // Release is slower than attack.
+        assert!(env[2] > 0.0 && env[2] < env[1]);
+    }
+
+    #[test]
+    fn sendcmd_targets_named_eq() {
+        let cmds = envelope_to_sendcmd(&[0.0, 1.0], 0.1, 1.0);
+        let lines = cmds.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("0.000 eq@ar brightness"));
+        assert!(lines[1].starts_with("0.100 eq@ar brightness 0.0390"));
+        assert!(lines[1].contains("eq@ar contrast 1.0800"));
+    }
+
+    #[test]
+    fn optimized_proxy_command_graph() {
+        use crate::command_runner::{MockRunner, with_runner};
+        use std::rc::Rc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("clip.mp4");
+        std::fs::write(&input, b"x").unwrap();
+        let mock = Rc::new(MockRunner::touching_outputs());
+        let app_dirs = dir.path().join("app");
+        let out = crate::app_paths::with_app_dirs_root(&app_dirs, || {
+            with_runner(mock.clone(), || {
+                maybe_build_optimized_proxy(&input, dir.path(), 2560, 30, 24, false).unwrap()
+            })
+        });
+        // The build is journaled in the test's own cache dir.
+        let journal = std::fs::read_to_string(app_dirs.join("cache/journal.jsonl")).unwrap();
+        assert_eq!(journal.lines().count(), 1);
+        assert!(
+            journal.contains(r#""kind":"proxy-build","ok":true"#),
+            "{journal}"
+        );
+        assert_eq!(
+            out,
+            dir.path().join("proxy-opt/clip_opt_2560w_30fps_crf24.mp4")
+        );
+        let vf = "scale='min(iw,2560)':-2:flags=bicubic,fps=30,format=yuv420p";
+        let tags = ProxyMeta::new("optimized", &input, &format!("{} crf24", vf)).ffmpeg_args();
+        assert_eq!(
+            mock.commands(),
+            vec![format!(
+                "ffmpeg -hide_banner -loglevel error -y -i {} -an -vf {} -c:v libx264 -preset veryfast -crf 24 -movflags +faststart {} {}",
+                input.display(),
+                vf,
+                tags.join(" "),
+                out.display()
+            )]
+        );
+
+        // A fresh proxy is reused without running ffmpeg again.
+        with_runner(mock.clone(), || {
+            maybe_build_optimized_proxy(&input, dir.path(), 2560, 30, 24, false).unwrap()
+        });
+        assert_eq!(mock.commands().len(), 1);
+    }
+
+    #[test]
+    fn gif_proxy_is_cached_and_skips_other_media() {
+        use crate::command_runner::{MockRunner, with_runner};
+        use std::rc::Rc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let gif = dir.path().join("loop.GIF");
+        let mp4 = dir.path().join("clip.mp4");
+        std::fs::write(&gif, b"x").unwrap();
+        std::fs::write(&mp4, b"x").unwrap();
+        let cache = dir.path().join("gif");
+        let mock = Rc::new(MockRunner::touching_outputs());
+
+        let app_dirs = dir.path().join("app");
+        let (proxy, passthrough) = crate::app_paths::with_app_dirs_root(&app_dirs, || {
+            with_runner(mock.clone(), || {
+                (
+                    maybe_build_gif_proxy(&gif, &cache, false).unwrap(),
+                    maybe_build_gif_proxy(&mp4, &cache, false).unwrap(),
+                )
+            })
+        });
+        assert_eq!(passthrough, mp4);
+        assert!(proxy.starts_with(&cache));
+        assert_eq!(proxy.extension().unwrap(), "mp4");
+        let cmds = mock.commands();
+        assert_eq!(cmds.len(), 1);
+        assert!(cmds[0].contains("-ignore_loop 1 -i"));
+        assert!(cmds[0].contains("-fps_mode vfr"));
+        // Encoded under a temporary name, then moved into place.
+        assert!(cmds[0].ends_with(&partial_output(&proxy).display().to_string()));
+        let cached = std::fs::read_dir(&cache)
+            .unwrap()
+            .flatten()
+            .map(|e| e.path());
+        assert_eq!(cached.collect::<Vec<_>>(), vec![proxy.clone()]);
+
+        with_runner(mock.clone(), || {
+            maybe_build_gif_proxy(&gif, &cache, false).unwrap()
+        });
+        assert_eq!(mock.commands().len(), 1);
     }
 }
@@ -1,5 +1,14 @@
-use crate::types::{InspectOutput, ProjectJson, SceneDiagnostics, WallpaperType};
-use anyhow::{Context, Result, bail};
+use crate::app_paths::utf8_safe_path;
+use crate::i18n::{Msg, bail_msg};
+use crate::scene_bundle::is_kwescene_path;
+use crate::scene_pkg::find_packed_scene_json;
+use crate::types::{
+    InspectOutput, ProjectJson, SceneDiagnostics, VideoPlaybackHints, WallpaperType,
+};
+use crate::workshop::is_workshop_id;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -16,6 +25,89 @@ pub fn resolve_wallpaper_path(wallpaper: &str, downloads_root: &Path) -> PathBuf
     direct
 }
 
+/// Like [`resolve_wallpaper_path`], but fails with a message that tells a
+/// missing download apart from a mistyped id or path. Besides wallpaper
+/// folders, `.kwescene` bundles are let through for the commands that play
+/// them.
+pub fn resolve_existing_wallpaper(wallpaper: &str, downloads_root: &Path) -> Result<PathBuf> {
+    let root = resolve_wallpaper_path(wallpaper, downloads_root);
+    if root.is_dir() || (root.is_file() && is_kwescene_path(&root)) {
+        return Ok(root);
+    }
+    if root.exists() {
+        bail_msg!(Msg::WallpaperNotDirectory, root.display());
+    }
+    if is_workshop_id(wallpaper) {
+        bail_msg!(
+            Msg::WorkshopNotDownloaded,
+            wallpaper,
+            downloads_root.display()
+        );
+    }
+    if is_workshop_id(wallpaper.trim()) {
+        bail_msg!(Msg::WorkshopIdSpaces, format!("{:?}", wallpaper));
+    }
+    bail_msg!(Msg::WallpaperMissing, root.display());
+}
+
+/// Title and author of a wallpaper for logs and reports. `id` is the
+/// workshop id or path as the user gave it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WallpaperName {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl WallpaperName {
+    /// `"Rainy Window" (123456) by ana`; just the id without a title.
+    pub fn label(&self) -> String {
+        let mut label = match &self.title {
+            Some(title) => format!("\"{}\" ({})", title, self.id),
+            None => self.id.clone(),
+        };
+        if let Some(author) = &self.author {
+            label.push_str(&format!(" by {}", author));
+        }
+        label
+    }
+}
+
+fn project_title(project: &ProjectJson) -> Option<String> {
+    Some(project.title.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn project_author(project: &ProjectJson) -> Option<String> {
+    project
+        .author
+        .as_str()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Name of the wallpaper in `root`. Never fails: a missing or broken
+/// `project.json` leaves only the id.
+pub fn wallpaper_name_at(id: &str, root: &Path) -> WallpaperName {
+    let project = fs::read_to_string(root.join("project.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<ProjectJson>(&raw).ok());
+    WallpaperName {
+        id: id.to_string(),
+        title: project.as_ref().and_then(project_title),
+        author: project.as_ref().and_then(project_author),
+    }
+}
+
+pub fn wallpaper_name(wallpaper: &str, downloads_root: &Path) -> WallpaperName {
+    wallpaper_name_at(
+        wallpaper,
+        &resolve_wallpaper_path(wallpaper, downloads_root),
+    )
+}
+
 pub fn is_video_file(path: &Path) -> bool {
     if !path.is_file() {
         return false;
@@ -159,11 +251,19 @@ pub fn inspect_scene_diagnostics(root: &Path, project: Option<&ProjectJson>) ->
     collect_video_files_recursive(root, 0, 6, &mut candidates);
     let best = choose_best_video_candidate(candidates);
 
+    let has_scene_json = root.join("scene.json").is_file();
+    let has_gifscene_json = root.join("gifscene.json").is_file();
+    let packed_scene_json = (!has_scene_json && !has_gifscene_json)
+        .then(|| find_packed_scene_json(root))
+        .flatten()
+        .map(|(pkg, entry)| packed_entry_name(&pkg, &entry));
+
     SceneDiagnostics {
-        has_scene_json: root.join("scene.json").is_file(),
-        has_gifscene_json: root.join("gifscene.json").is_file(),
+        has_scene_json,
+        has_gifscene_json,
         has_scene_pkg: root.join("scene.pkg").is_file(),
         has_gifscene_pkg: root.join("gifscene.pkg").is_file(),
+        packed_scene_json,
         supports_audio_processing: project_supports_audio_processing(project),
         supports_video: project_supports_video(project),
         best_video_candidate: best.as_ref().map(|p| p.to_string_lossy().to_string()),
@@ -172,6 +272,11 @@ pub fn inspect_scene_diagnostics(root: &Path, project: Option<&ProjectJson>) ->
     }
 }
 
+/// `scene.pkg#scene.json`, naming an entry inside a package.
+fn packed_entry_name(pkg: &Path, entry: &str) -> String {
+    format!("{}#{}", pkg.to_string_lossy(), entry)
+}
+
 pub fn detect_type(root: &Path, project: Option<&ProjectJson>) -> WallpaperType {
     if let Some(project) = project {
         let t = WallpaperType::from_str(&project.r#type);
@@ -186,17 +291,63 @@ pub fn detect_type(root: &Path, project: Option<&ProjectJson>) -> WallpaperType
     if find_video_entry(root, project).is_some() {
         return WallpaperType::Video;
     }
+    // Some items keep scene.json only inside their pkg.
+    if find_packed_scene_json(root).is_some() {
+        return WallpaperType::Scene;
+    }
     WallpaperType::Unknown
 }
 
+fn parse_playback_rate(v: &Value) -> Option<f32> {
+    let rate = match v {
+        Value::Number(n) => n.as_f64()? as f32,
+        Value::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+            "normal" | "" => 1.0,
+            "slow" | "slowmotion" | "slow-motion" => 0.5,
+            other => other.trim_end_matches('x').trim().parse::<f32>().ok()?,
+        },
+        _ => return None,
+    };
+    (rate > 0.0).then(|| rate.clamp(0.05, 8.0))
+}
+
+fn parse_fps_hint(v: &Value) -> Option<u32> {
+    let fps = match v {
+        Value::Number(n) => n.as_f64()?,
+        Value::String(s) => s.trim().parse::<f64>().ok()?,
+        _ => return None,
+    };
+    (fps >= 1.0).then(|| fps.round().min(240.0) as u32)
+}
+
+/// Rate/fps hints from the top level of `project.json`, `general`, or a
+/// `general.properties` entry of the same name.
+pub fn video_playback_hints(project: &ProjectJson) -> Option<VideoPlaybackHints> {
+    let lookup = |top: &Value, key: &str| -> Option<Value> {
+        [
+            Some(top),
+            project.general.get(key),
+            project
+                .general
+                .get("properties")
+                .and_then(|p| p.get(key))
+                .and_then(|p| p.get("value")),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|v| !v.is_null())
+        .cloned()
+    };
+    let speed = lookup(&project.playbackrate, "playbackrate")
+        .or_else(|| lookup(&project.playbackmode, "playbackmode"))
+        .and_then(|v| parse_playback_rate(&v))
+        .filter(|rate| (rate - 1.0).abs() > 0.001);
+    let fps = lookup(&project.fps, "fps").and_then(|v| parse_fps_hint(&v));
+    (speed.is_some() || fps.is_some()).then_some(VideoPlaybackHints { speed, fps })
+}
+
 pub fn inspect_wallpaper(wallpaper: &str, downloads_root: &Path) -> Result<InspectOutput> {
-    let root = resolve_wallpaper_path(wallpaper, downloads_root);
-    if !root.exists() {
-        bail!("Wallpaper path does not exist: {}", root.display());
-    }
-    if !root.is_dir() {
-        bail!("Wallpaper path is not a directory: {}", root.display());
-    }
+    let root = resolve_existing_wallpaper(wallpaper, downloads_root)?;
 
     let project_path = root.join("project.json");
     let project = if project_path.is_file() {
@@ -212,21 +363,29 @@ pub fn inspect_wallpaper(wallpaper: &str, downloads_root: &Path) -> Result<Inspe
 
     let wallpaper_type = detect_type(&root, project.as_ref());
     let entry = match wallpaper_type {
-        WallpaperType::Video => {
-            find_video_entry(&root, project.as_ref()).map(|p| p.to_string_lossy().to_string())
-        }
+        WallpaperType::Video => find_video_entry(&root, project.as_ref())
+            .map(|p| utf8_safe_path(&p).to_string_lossy().to_string()),
         WallpaperType::Scene => {
             let p = root.join("scene.json");
             if p.is_file() {
                 Some(p.to_string_lossy().to_string())
             } else {
                 let p = root.join("gifscene.json");
-                p.is_file().then(|| p.to_string_lossy().to_string())
+                if p.is_file() {
+                    Some(p.to_string_lossy().to_string())
+                } else {
+                    find_packed_scene_json(&root)
+                        .map(|(pkg, entry)| packed_entry_name(&pkg, &entry))
+                }
             }
         }
         _ => None,
     };
 
+    let video_hints = project
+        .as_ref()
+        .filter(|_| wallpaper_type == WallpaperType::Video)
+        .and_then(video_playback_hints);
     let scene = if wallpaper_type == WallpaperType::Scene {
         Some(inspect_scene_diagnostics(&root, project.as_ref()))
     } else {
@@ -237,22 +396,25 @@ pub fn inspect_wallpaper(wallpaper: &str, downloads_root: &Path) -> Result<Inspe
         root: root.to_string_lossy().to_string(),
         wallpaper_type,
         entry,
-        title: project
-            .as_ref()
-            .map(|p| p.title.trim().to_string())
-            .filter(|v| !v.is_empty()),
+        title: project.as_ref().and_then(project_title),
+        author: project.as_ref().and_then(project_author),
         workshopid: project
             .as_ref()
             .map(|p| p.workshopid.trim().to_string())
             .filter(|v| !v.is_empty()),
         project_file_found: project.is_some(),
+        tags: project.as_ref().map(|p| p.tags.clone()).unwrap_or_default(),
+        video_hints,
         scene,
+        capabilities: None,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::Lang;
+    use crate::i18n::with_lang;
     use std::fs;
     use tempfile::tempdir;
 
@@ -273,6 +435,35 @@ mod tests {
         assert!(out.entry.unwrap().ends_with("demo.mp4"));
     }
 
+    #[test]
+    fn video_project_playback_hints() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("321");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("slow.mp4"), b"x").unwrap();
+        fs::write(
+            root.join("project.json"),
+            r#"{"type":"Video","file":"slow.mp4","playbackmode":"0.5x",
+                "general":{"properties":{"fps":{"type":"slider","value":24}}}}"#,
+        )
+        .unwrap();
+
+        let out = inspect_wallpaper("321", dir.path()).unwrap();
+        assert_eq!(
+            out.video_hints,
+            Some(VideoPlaybackHints {
+                speed: Some(0.5),
+                fps: Some(24)
+            })
+        );
+
+        let normal: ProjectJson =
+            serde_json::from_str(r#"{"playbackmode":"normal","fps":0}"#).unwrap();
+        assert_eq!(video_playback_hints(&normal), None);
+        let rate: ProjectJson = serde_json::from_str(r#"{"playbackrate":0.25}"#).unwrap();
+        assert_eq!(video_playback_hints(&rate).unwrap().speed, Some(0.25));
+    }
+
     #[test]
     fn detects_scene_without_project_type() {
         let dir = tempdir().unwrap();
@@ -284,6 +475,34 @@ mod tests {
         assert_eq!(out.wallpaper_type, WallpaperType::Scene);
     }
 
+    #[test]
+    fn detects_scene_packed_only_in_pkg() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("789");
+        fs::create_dir_all(&root).unwrap();
+        crate::scene_pkg::write_scene_pkg(
+            &root.join("scene.pkg"),
+            &[("scene.json".to_string(), b"{}".to_vec())],
+        )
+        .unwrap();
+        fs::write(root.join("project.json"), r#"{"title":"Packed"}"#).unwrap();
+
+        let out = inspect_wallpaper("789", dir.path()).unwrap();
+        assert_eq!(out.wallpaper_type, WallpaperType::Scene);
+        assert!(out.entry.unwrap().ends_with("scene.pkg#scene.json"));
+        let scene = out.scene.unwrap();
+        assert!(!scene.has_scene_json && scene.has_scene_pkg);
+        assert!(scene.packed_scene_json.is_some());
+
+        // A pkg without a scene description is not enough.
+        crate::scene_pkg::write_scene_pkg(
+            &root.join("scene.pkg"),
+            &[("materials/a.json".to_string(), b"{}".to_vec())],
+        )
+        .unwrap();
+        assert_eq!(detect_type(&root, None), WallpaperType::Unknown);
+    }
+
     #[test]
     fn finds_scene_compatible_video_recursively() {
         let dir = tempdir().unwrap();
@@ -308,4 +527,43 @@ mod tests {
         assert!(find_scene_compatible_video(&root, false).is_none());
         assert!(find_scene_compatible_video(&root, true).is_some());
     }
+
+    #[test]
+    fn wallpaper_names_come_from_project_json() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("123");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("project.json"),
+            r#"{"type":"Video","title":" Rainy Window ","author":"ana"}"#,
+        )
+        .unwrap();
+
+        let name = wallpaper_name("123", dir.path());
+        assert_eq!(name.title.as_deref(), Some("Rainy Window"));
+        assert_eq!(name.label(), "\"Rainy Window\" (123) by ana");
+        assert_eq!(
+            inspect_wallpaper("123", dir.path())
+                .unwrap()
+                .author
+                .as_deref(),
+            Some("ana")
+        );
+        assert_eq!(wallpaper_name("999", dir.path()).label(), "999");
+
+        // Error texts follow the locale; pin English like `LANG=C` would.
+        let resolve = |id: &str| {
+            with_lang(Some(Lang::En), || {
+                resolve_existing_wallpaper(id, dir.path())
+            })
+        };
+        let missing = resolve("999").unwrap_err();
+        assert!(
+            missing
+                .to_string()
+                .contains("Workshop id 999 is not downloaded")
+        );
+        let spaced = resolve(" 999").unwrap_err();
+        assert!(spaced.to_string().contains("Invalid workshop id"));
+    }
 }
@@ -0,0 +1,229 @@
+use crate::app_paths::{app_config_dir, system_quirks_path};
+use crate::cli::GpuTransport;
+use crate::i18n::{Msg, warn_msg};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+const BUNDLED_QUIRKS: &str = include_str!("../data/quirks.json");
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WallpaperQuirk {
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Object ids (numbers) or names (strings) that must render regardless
+    /// of their `visible` expression.
+    #[serde(default)]
+    pub force_visible: Vec<Value>,
+    #[serde(default)]
+    pub scene_size: Option<[u32; 2]>,
+    #[serde(default)]
+    pub transport: Option<GpuTransport>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuirkDatabase {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub wallpapers: BTreeMap<String, WallpaperQuirk>,
+}
+
+pub fn default_user_quirks_path() -> PathBuf {
+    if let Ok(path) = env::var("KWE_QUIRKS_FILE") {
+        return PathBuf::from(path);
+    }
+    app_config_dir().join("quirks.json")
+}
+
+fn parse_quirk_database(raw: &str) -> Result<QuirkDatabase> {
+    serde_json::from_str(raw).context("Failed parsing quirk database")
+}
+
+fn merge_quirk(base: &mut WallpaperQuirk, patch: WallpaperQuirk) {
+    if patch.note.is_some() {
+        base.note = patch.note;
+    }
+    for item in patch.force_visible {
+        if !base.force_visible.contains(&item) {
+            base.force_visible.push(item);
+        }
+    }
+    if patch.scene_size.is_some() {
+        base.scene_size = patch.scene_size;
+    }
+    if patch.transport.is_some() {
+        base.transport = patch.transport;
+    }
+}
+
+/// Bundled quirks, then the system file, then the user file (last wins).
+pub fn load_quirk_database() -> Result<QuirkDatabase> {
+    let mut db = parse_quirk_database(BUNDLED_QUIRKS)?;
+    for path in [system_quirks_path(), default_user_quirks_path()] {
+        if !path.is_file() {
+            continue;
+        }
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed reading {}", path.display()))?;
+        let layer = parse_quirk_database(&raw)
+            .with_context(|| format!("Invalid quirk file {}", path.display()))?;
+        for (id, quirk) in layer.wallpapers {
+            merge_quirk(db.wallpapers.entry(id).or_default(), quirk);
+        }
+    }
+    Ok(db)
+}
+
+pub(crate) fn workshop_id_for_root(root: &Path, project_json: Option<&Value>) -> Option<String> {
+    let from_project = project_json
+        .and_then(|p| p.get("workshopid"))
+        .and_then(|v| match v {
+            Value::String(s) => Some(s.trim().to_string()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+        .filter(|s| !s.is_empty());
+    from_project.or_else(|| {
+        root.file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .filter(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+pub fn quirk_for_wallpaper(root: &Path, project_json: Option<&Value>) -> Option<WallpaperQuirk> {
+    let id = workshop_id_for_root(root, project_json)?;
+    let db = match load_quirk_database() {
+        Ok(db) => db,
+        Err(err) => {
+            warn_msg!(Msg::QuirksIgnored, err);
+            return None;
+        }
+    };
+    db.wallpapers.get(&id).cloned()
+}
+
+/// Same as `quirk_for_wallpaper`, reading `project.json` from the wallpaper dir.
+pub fn load_wallpaper_quirk(root: &Path) -> Option<WallpaperQuirk> {
+    let project_json = std::fs::read(root.join("project.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<Value>(&raw).ok());
+    quirk_for_wallpaper(root, project_json.as_ref())
+}
+
+pub(crate) fn object_matches(object: &Value, selector: &Value) -> bool {
+    match selector {
+        Value::Number(n) => object.get("id").and_then(|v| v.as_u64()) == n.as_u64(),
+        Value::String(s) => object
+            .get("name")
+            .and_then(|v| v.as_str())
+            .is_some_and(|name| name.eq_ignore_ascii_case(s)),
+        _ => false,
+    }
+}
+
+/// Patches `scene_json` in place and returns notes describing what changed.
+pub fn apply_scene_quirks(scene_json: &mut Value, quirk: &WallpaperQuirk) -> Vec<String> {
+    let mut notes = Vec::new();
+    if let Some(note) = &quirk.note {
+        notes.push(format!("Quirk: {note}"));
+    }
+
+    if let Some([w, h]) = quirk.scene_size
+        && w > 0
+        && h > 0
+        && let Some(scene) = scene_json.as_object_mut()
+    {
+        let general = scene
+            .entry("general")
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Some(general) = general.as_object_mut() {
+            let projection = general
+                .entry("orthogonalprojection")
+                .or_insert_with(|| Value::Object(Default::default()));
+            if !projection.is_object() {
+                *projection = Value::Object(Default::default());
+            }
+            if let Some(projection) = projection.as_object_mut() {
+                projection.insert("width".to_string(), w.into());
+                projection.insert("height".to_string(), h.into());
+                notes.push(format!("Quirk: scene size overridden to {w}x{h}"));
+            }
+        }
+    }
+
+    if !quirk.force_visible.is_empty()
+        && let Some(objects) = scene_json.get_mut("objects").and_then(|v| v.as_array_mut())
+    {
+        for object in objects.iter_mut() {
+            if !quirk
+                .force_visible
+                .iter()
+                .any(|s| object_matches(object, s))
+            {
+                continue;
+            }
+            let label = object
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            if let Some(obj) = object.as_object_mut() {
+                obj.insert("visible".to_string(), Value::Bool(true));
+                notes.push(format!("Quirk: object '{label}' forced visible"));
+            }
+        }
+    }
+
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bundled_database_parses() {
+        let db = parse_quirk_database(BUNDLED_QUIRKS).unwrap();
+        assert_eq!(db.version, 1);
+    }
+
+    #[test]
+    fn quirk_patches_size_and_visibility() {
+        let mut scene = json!({
+            "general": {"orthogonalprojection": {"width": 100, "height": 100}},
+            "objects": [
+                {"id": 7, "name": "Clock", "visible": {"user": "showclock", "value": false}},
+                {"id": 8, "name": "Other", "visible": false}
+            ]
+        });
+        let quirk = WallpaperQuirk {
+            force_visible: vec![json!("clock")],
+            scene_size: Some([3840, 2160]),
+            ..Default::default()
+        };
+        let notes = apply_scene_quirks(&mut scene, &quirk);
+        assert_eq!(notes.len(), 2);
+        assert_eq!(scene["general"]["orthogonalprojection"]["width"], 3840);
+        assert_eq!(scene["objects"][0]["visible"], true);
+        assert_eq!(scene["objects"][1]["visible"], false);
+    }
+
+    #[test]
+    fn workshop_id_falls_back_to_dir_name() {
+        let root = Path::new("/tmp/downloads/3299228616");
+        assert_eq!(
+            workshop_id_for_root(root, None).as_deref(),
+            Some("3299228616")
+        );
+        let project = json!({"workshopid": 42});
+        assert_eq!(
+            workshop_id_for_root(root, Some(&project)).as_deref(),
+            Some("42")
+        );
+    }
+}
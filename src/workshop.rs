@@ -0,0 +1,340 @@
+use crate::app_paths::{app_cache_dir, app_state_dir};
+use crate::command_runner::CommandExt;
+use crate::i18n::{Msg, bail_msg, warn_msg};
+use crate::library_guard::read_only_library;
+use crate::library_scan::{LibraryScanReport, scan_library_items};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const WALLPAPER_ENGINE_APP_ID: &str = "431960";
+const COLLECTION_DETAILS_URL: &str =
+    "https://api.steampowered.com/ISteamRemoteStorage/GetCollectionDetails/v1/";
+/// `filetype` of a collection child that is itself a collection.
+const NESTED_COLLECTION_FILETYPE: u64 = 2;
+
+/// Items each collection brought into the library, so `--prune` only removes
+/// what a previous sync downloaded and never touches manual downloads.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkshopSyncState {
+    pub collections: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectionSyncReport {
+    pub collection_id: String,
+    pub items: Vec<String>,
+    pub downloaded: Vec<String>,
+    pub already_present: Vec<String>,
+    pub failed: Vec<String>,
+    pub removed: Vec<String>,
+    pub scan: Option<LibraryScanReport>,
+}
+
+pub struct CollectionSyncArgs {
+    pub collection_id: String,
+    pub downloads_root: PathBuf,
+    pub steam_user: String,
+    pub prune: bool,
+    pub dry_run: bool,
+}
+
+pub(crate) fn sync_state_path() -> PathBuf {
+    app_state_dir().join("workshop-collections.json")
+}
+
+fn load_sync_state() -> Result<WorkshopSyncState> {
+    let path = sync_state_path();
+    if !path.is_file() {
+        return Ok(WorkshopSyncState::default());
+    }
+    let raw = fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+fn save_sync_state(state: &WorkshopSyncState) -> Result<()> {
+    let path = sync_state_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(state)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+pub(crate) fn is_workshop_id(id: &str) -> bool {
+    !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Item ids from a `GetCollectionDetails` response. Nested collections are
+/// reported by the caller as a warning instead of being expanded.
+pub fn parse_collection_details(raw: &str) -> Result<(Vec<String>, Vec<String>)> {
+    let json: Value = serde_json::from_str(raw).context("Invalid collection details JSON")?;
+    let details = json
+        .pointer("/response/collectiondetails/0")
+        .context("Collection details missing from response")?;
+    if details.get("result").and_then(|v| v.as_u64()) != Some(1) {
+        bail!("Steam rejected the collection (private, deleted or not a collection)");
+    }
+    let mut items = Vec::new();
+    let mut nested = Vec::new();
+    for child in details
+        .get("children")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let Some(id) = child.get("publishedfileid").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if !is_workshop_id(id) {
+            continue;
+        }
+        if child.get("filetype").and_then(|v| v.as_u64()) == Some(NESTED_COLLECTION_FILETYPE) {
+            nested.push(id.to_string());
+        } else if !items.iter().any(|v| v == id) {
+            items.push(id.to_string());
+        }
+    }
+    Ok((items, nested))
+}
+
+pub fn fetch_collection_items(collection_id: &str) -> Result<Vec<String>> {
+    let output = Command::new("curl")
+        .arg("-sS")
+        .arg("--fail")
+        .arg("--max-time")
+        .arg("30")
+        .arg("-d")
+        .arg("collectioncount=1")
+        .arg("-d")
+        .arg(format!("publishedfileids[0]={collection_id}"))
+        .arg(COLLECTION_DETAILS_URL)
+        .run_output()
+        .context("Failed to run curl (is it installed?)")?;
+    if !output.status.success() {
+        bail!(
+            "Steam web API request failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let (items, nested) = parse_collection_details(&String::from_utf8_lossy(&output.stdout))?;
+    for id in nested {
+        warn_msg!(Msg::NestedCollection, id);
+    }
+    Ok(items)
+}
+
+fn steamcmd_install_dir() -> PathBuf {
+    app_cache_dir().join("steamcmd")
+}
+
+fn download_item(id: &str, steam_user: &str, downloads_root: &Path) -> Result<()> {
+    let install_dir = steamcmd_install_dir();
+    fs::create_dir_all(&install_dir)
+        .with_context(|| format!("Failed creating {}", install_dir.display()))?;
+    let status = Command::new("steamcmd")
+        .arg("+force_install_dir")
+        .arg(&install_dir)
+        .arg("+login")
+        .arg(steam_user)
+        .arg("+workshop_download_item")
+        .arg(WALLPAPER_ENGINE_APP_ID)
+        .arg(id)
+        .arg("+quit")
+        .run_status()
+        .context("Failed to run steamcmd (is it installed?)")?;
+    if !status.success() {
+        bail!("steamcmd failed for item {}", id);
+    }
+    let content = install_dir
+        .join("steamapps/workshop/content")
+        .join(WALLPAPER_ENGINE_APP_ID)
+        .join(id);
+    if !content.is_dir() {
+        bail!("steamcmd did not produce {}", content.display());
+    }
+    let dest = downloads_root.join(id);
+    move_dir(&content, &dest)
+        .with_context(|| format!("Failed moving {} to {}", content.display(), dest.display()))
+}
+
+/// `fs::rename`, falling back to copy-then-remove when the steamcmd cache and
+/// the library sit on different filesystems.
+fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => copy_then_remove(from, to),
+        other => other,
+    }
+}
+
+/// Copies `from` to `to` and removes `from`; a failed copy is removed so the
+/// next sync downloads the item again instead of keeping half of it.
+fn copy_then_remove(from: &Path, to: &Path) -> io::Result<()> {
+    if let Err(err) = copy_dir(from, to) {
+        let _ = fs::remove_dir_all(to);
+        return Err(err);
+    }
+    fs::remove_dir_all(from)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if kind.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn sync_collection(args: CollectionSyncArgs) -> Result<CollectionSyncReport> {
+    if !is_workshop_id(&args.collection_id) {
+        bail_msg!(Msg::InvalidCollectionId, args.collection_id);
+    }
+    if read_only_library() {
+        bail_msg!(Msg::ReadOnlyLibrarySync, args.downloads_root.display());
+    }
+    let items = fetch_collection_items(&args.collection_id)?;
+    let mut state = load_sync_state()?;
+    let previously_synced = state
+        .collections
+        .get(&args.collection_id)
+        .cloned()
+        .unwrap_or_default();
+
+    if !args.dry_run {
+        fs::create_dir_all(&args.downloads_root)
+            .with_context(|| format!("Failed creating {}", args.downloads_root.display()))?;
+    }
+
+    let mut report = CollectionSyncReport {
+        collection_id: args.collection_id.clone(),
+        items: items.clone(),
+        downloaded: Vec::new(),
+        already_present: Vec::new(),
+        failed: Vec::new(),
+        removed: Vec::new(),
+        scan: None,
+    };
+    let mut synced = BTreeSet::new();
+
+    for id in &items {
+        if args.downloads_root.join(id).is_dir() {
+            report.already_present.push(id.clone());
+            if previously_synced.contains(id) {
+                synced.insert(id.clone());
+            }
+            continue;
+        }
+        if args.dry_run {
+            eprintln!(
+                "[dry-run] steamcmd workshop_download_item {} {}",
+                WALLPAPER_ENGINE_APP_ID, id
+            );
+            report.downloaded.push(id.clone());
+            continue;
+        }
+        match download_item(id, &args.steam_user, &args.downloads_root) {
+            Ok(()) => {
+                eprintln!("[ok] downloaded {}", id);
+                report.downloaded.push(id.clone());
+                synced.insert(id.clone());
+            }
+            Err(err) => {
+                warn_msg!(Msg::WorkshopDownloadFailed, id, err);
+                report.failed.push(id.clone());
+            }
+        }
+    }
+
+    let stale = previously_synced.iter().filter(|id| !items.contains(id));
+    for id in stale {
+        let dir = args.downloads_root.join(id);
+        if !args.prune {
+            if dir.is_dir() {
+                synced.insert(id.clone());
+            }
+            continue;
+        }
+        if !dir.is_dir() {
+            continue;
+        }
+        if args.dry_run {
+            eprintln!("[dry-run] remove {}", dir.display());
+        } else {
+            fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed removing {}", dir.display()))?;
+            eprintln!("[ok] removed {}", id);
+        }
+        report.removed.push(id.clone());
+    }
+
+    if !args.dry_run {
+        state.collections.insert(args.collection_id.clone(), synced);
+        save_sync_state(&state)?;
+        if !report.downloaded.is_empty() {
+            report.scan = Some(scan_library_items(
+                &args.downloads_root,
+                &report.downloaded,
+                20,
+                false,
+            )?);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cross_device_moves_copy_the_whole_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("content/123");
+        fs::create_dir_all(from.join("materials")).unwrap();
+        fs::write(from.join("project.json"), b"{}").unwrap();
+        fs::write(from.join("materials/a.png"), b"png").unwrap();
+        std::os::unix::fs::symlink("materials/a.png", from.join("preview.png")).unwrap();
+        let to = dir.path().join("downloads/123");
+
+        copy_then_remove(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read(to.join("materials/a.png")).unwrap(), b"png");
+        assert_eq!(fs::read(to.join("preview.png")).unwrap(), b"png");
+        assert_eq!(
+            fs::read_link(to.join("preview.png")).unwrap(),
+            Path::new("materials/a.png")
+        );
+    }
+
+    #[test]
+    fn collection_details_split_items_and_nested_collections() {
+        let raw = r#"{"response":{"result":1,"resultcount":1,"collectiondetails":[{
+            "publishedfileid":"900","result":1,"children":[
+                {"publishedfileid":"111","sortorder":1,"filetype":0},
+                {"publishedfileid":"222","sortorder":2,"filetype":0},
+                {"publishedfileid":"111","sortorder":3,"filetype":0},
+                {"publishedfileid":"333","sortorder":4,"filetype":2}
+            ]}]}}"#;
+        let (items, nested) = parse_collection_details(raw).unwrap();
+        assert_eq!(items, vec!["111", "222"]);
+        assert_eq!(nested, vec!["333"]);
+
+        let rejected = r#"{"response":{"collectiondetails":[{"publishedfileid":"1","result":9}]}}"#;
+        assert!(parse_collection_details(rejected).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! End-to-end playback on a headless sway session. Needs sway, grim, mpvpaper
+//! and ffmpeg; run with `cargo test --features testsupport -- --ignored`.
+#![cfg(feature = "testsupport")]
+
+use kitsune_livewallpaper::testsupport::{HeadlessCompositor, HeadlessOptions, coverage, mean_rgb};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+const TOOLS: [&str; 4] = ["sway", "grim", "mpvpaper", "ffmpeg"];
+
+fn missing_tools() -> Vec<&'static str> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    TOOLS
+        .into_iter()
+        .filter(|tool| !std::env::split_paths(&path).any(|dir| dir.join(tool).is_file()))
+        .collect()
+}
+
+/// Video wallpaper `<downloads>/<id>` playing a solid red clip.
+fn write_video_wallpaper(downloads: &Path, id: &str) {
+    let root = downloads.join(id);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(
+        root.join("project.json"),
+        r#"{"type":"video","file":"clip.mp4","title":"Fixture video"}"#,
+    )
+    .unwrap();
+    let status = Command::new("ffmpeg")
+        .args(["-v", "error", "-y", "-f", "lavfi", "-i"])
+        .arg("color=c=red:s=320x180:d=2:r=10")
+        .args(["-pix_fmt", "yuv420p"])
+        .arg(root.join("clip.mp4"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "ffmpeg could not write the fixture clip");
+}
+
+#[test]
+#[ignore = "needs sway, grim, mpvpaper and ffmpeg"]
+fn video_wallpaper_reaches_the_headless_output() {
+    let missing = missing_tools();
+    if !missing.is_empty() {
+        eprintln!("skipping: {} not in PATH", missing.join(", "));
+        return;
+    }
+    let downloads = tempfile::tempdir().unwrap();
+    write_video_wallpaper(downloads.path(), "1001");
+
+    let options = HeadlessOptions::default();
+    let session = HeadlessCompositor::start(options.clone()).unwrap();
+    let exe = Path::new(env!("CARGO_BIN_EXE_kitsune-livewallpaper"));
+    session
+        .apply_wallpaper(exe, "1001", downloads.path(), &[])
+        .unwrap();
+
+    let frame = session
+        .wait_for_frame(Duration::from_secs(15), |f| coverage(f, [0, 0, 0], 8) > 0.9)
+        .unwrap();
+    assert_eq!(
+        (frame.width(), frame.height()),
+        (options.width, options.height)
+    );
+    let [r, g, b] = mean_rgb(&frame);
+    assert!(r > 150.0 && g < 80.0 && b < 80.0, "mean colour {r} {g} {b}");
+}